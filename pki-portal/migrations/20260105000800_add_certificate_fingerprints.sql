@@ -0,0 +1,4 @@
+-- SHA-256 fingerprint of the issued Aletheia certificate (lowercase hex)
+ALTER TABLE certificates ADD COLUMN IF NOT EXISTS fingerprint TEXT NULL;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_certificates_fingerprint ON certificates (fingerprint);
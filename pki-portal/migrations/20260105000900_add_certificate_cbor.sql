@@ -0,0 +1,2 @@
+-- The signed Aletheia certificate as base64 CBOR
+ALTER TABLE certificates ADD COLUMN IF NOT EXISTS certificate_b64 TEXT NULL;
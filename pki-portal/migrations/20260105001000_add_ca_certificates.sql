@@ -0,0 +1,3 @@
+-- Signed CA certificates (base64 CBOR), embedded in published trust bundles
+ALTER TABLE roots ADD COLUMN IF NOT EXISTS certificate_b64 TEXT NULL;
+ALTER TABLE intermediates ADD COLUMN IF NOT EXISTS certificate_b64 TEXT NULL;
@@ -0,0 +1,16 @@
+-- Certificate enrollments awaiting human review
+CREATE TABLE IF NOT EXISTS enrollments (
+    id UUID PRIMARY KEY,
+    issuer_id UUID NOT NULL REFERENCES intermediates(id) ON DELETE CASCADE,
+    subject_id TEXT NOT NULL,
+    subject_name TEXT NOT NULL,
+    public_key BYTEA NOT NULL,
+    request_pem TEXT NOT NULL,
+    status TEXT NOT NULL CHECK (status IN ('pending', 'approved', 'rejected')),
+    reason TEXT NULL,
+    certificate_serial TEXT NULL REFERENCES certificates(serial) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    decided_at TIMESTAMPTZ NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_enrollments_status ON enrollments (status, created_at DESC);
@@ -0,0 +1,5 @@
+-- Email challenge proving control of an enrollment's subject_id
+ALTER TABLE enrollments ADD COLUMN IF NOT EXISTS email_code_hash TEXT NULL;
+ALTER TABLE enrollments ADD COLUMN IF NOT EXISTS email_code_expires_at TIMESTAMPTZ NULL;
+ALTER TABLE enrollments ADD COLUMN IF NOT EXISTS email_attempts INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE enrollments ADD COLUMN IF NOT EXISTS email_verified_at TIMESTAMPTZ NULL;
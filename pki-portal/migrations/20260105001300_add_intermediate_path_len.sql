@@ -0,0 +1,2 @@
+-- Path length constraint of intermediates, as set in their certificates
+ALTER TABLE intermediates ADD COLUMN IF NOT EXISTS path_len INTEGER NULL CHECK (path_len >= 0);
@@ -0,0 +1,6 @@
+-- Passphrase-encrypted CA keys of the Postgres key store
+CREATE TABLE IF NOT EXISTS ca_keys (
+    ca_id UUID PRIMARY KEY,
+    key_pem TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
@@ -0,0 +1,27 @@
+-- Append-only Merkle log of issued certificates
+CREATE TABLE IF NOT EXISTS ct_entries (
+    idx BIGINT PRIMARY KEY CHECK (idx >= 0),
+    serial TEXT NOT NULL UNIQUE,
+    leaf BYTEA NOT NULL,
+    integrated_time BIGINT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- Tree heads signed by the log, one per tree size
+CREATE TABLE IF NOT EXISTS ct_tree_heads (
+    tree_size BIGINT PRIMARY KEY CHECK (tree_size > 0),
+    root_hash BYTEA NOT NULL,
+    signature BYTEA NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE OR REPLACE FUNCTION ct_log_append_only() RETURNS trigger AS $$
+BEGIN
+    RAISE EXCEPTION 'the certificate transparency log is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER ct_entries_append_only BEFORE UPDATE OR DELETE ON ct_entries
+    FOR EACH ROW EXECUTE FUNCTION ct_log_append_only();
+CREATE TRIGGER ct_tree_heads_append_only BEFORE UPDATE OR DELETE ON ct_tree_heads
+    FOR EACH ROW EXECUTE FUNCTION ct_log_append_only();
@@ -0,0 +1,13 @@
+-- Validity and attestation rules of the global policy
+ALTER TABLE policy ADD COLUMN IF NOT EXISTS max_validity BIGINT NULL CHECK (max_validity > 0);
+ALTER TABLE policy ADD COLUMN IF NOT EXISTS allowed_attestation_methods TEXT[] NULL;
+
+-- Policies of single intermediates, applied instead of the global policy
+CREATE TABLE IF NOT EXISTS intermediate_policies (
+    intermediate_id UUID PRIMARY KEY REFERENCES intermediates(id) ON DELETE CASCADE,
+    subject_id_pattern TEXT,
+    allow_ca_issue BOOLEAN NOT NULL DEFAULT false,
+    max_validity BIGINT NULL CHECK (max_validity > 0),
+    allowed_attestation_methods TEXT[] NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
@@ -0,0 +1,7 @@
+-- The signed CRL of each issuer, re-signed only when its revocations change
+CREATE TABLE IF NOT EXISTS crls (
+    issuer_id UUID PRIMARY KEY,
+    number BIGINT NOT NULL CHECK (number > 0),
+    crl BYTEA NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
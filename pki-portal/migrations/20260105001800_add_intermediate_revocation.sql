@@ -0,0 +1,3 @@
+-- Revocation of intermediates, listed on the CRL of their root
+ALTER TABLE intermediates ADD COLUMN IF NOT EXISTS revoked_at TIMESTAMPTZ NULL;
+ALTER TABLE intermediates ADD COLUMN IF NOT EXISTS revocation_reason TEXT NULL;
@@ -0,0 +1,3 @@
+-- Limits on email challenges that outlive each resent code
+ALTER TABLE enrollments ADD COLUMN IF NOT EXISTS email_challenges_sent INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE enrollments ADD COLUMN IF NOT EXISTS email_code_sent_at TIMESTAMPTZ NULL;
@@ -0,0 +1,2 @@
+-- The log's frontier after each entry, so appends need not reload every leaf
+ALTER TABLE ct_entries ADD COLUMN IF NOT EXISTS frontier BYTEA NULL;
@@ -1,20 +1,36 @@
 use actix_web::{get, web, HttpResponse};
 
-use crate::{error::ApiError, models::AuditEvent, AppState};
+use crate::{
+    api::list::{list, ListQuery, ListSpec},
+    error::ApiError,
+    models::AuditEvent,
+    AppState,
+};
 
-async fn list_events_impl(state: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
-    let rows = sqlx::query_as::<_, AuditEvent>(
-        "select id, event_type, actor, scope, payload, occurred_at from audit_logs order by occurred_at desc limit 100",
-    )
-    .fetch_all(&state.db)
-    .await?;
+const AUDIT_LOGS: ListSpec = ListSpec {
+    columns: "id, event_type, actor, scope, payload, occurred_at",
+    from: "audit_logs",
+    time_column: "occurred_at",
+    key_column: "id",
+    status_column: None,
+    subject_id_column: None,
+    issuer_column: None,
+    sortable: &[("occurred_at", "occurred_at"), ("event_type", "event_type")],
+};
 
-    Ok(HttpResponse::Ok().json(rows))
+async fn list_events_impl(
+    state: web::Data<AppState>,
+    query: web::Query<ListQuery>,
+) -> Result<HttpResponse, ApiError> {
+    list::<AuditEvent>(&state.db, &AUDIT_LOGS, &query).await
 }
 
 #[get("/logs")]
-pub async fn list_events_handler(state: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
-    list_events_impl(state).await
+pub async fn list_events_handler(
+    state: web::Data<AppState>,
+    query: web::Query<ListQuery>,
+) -> Result<HttpResponse, ApiError> {
+    list_events_impl(state, query).await
 }
 
 #[cfg(test)]
@@ -22,7 +38,11 @@ mod tests {
     use actix_web::{body::to_bytes, http::StatusCode, web};
     use sqlx::PgPool;
     use uuid::Uuid;
-    use crate::{models::AuditEvent, AppState};
+    use crate::{
+        api::list::{ListQuery, TOTAL_COUNT_HEADER},
+        models::AuditEvent,
+        AppState,
+    };
     use super::list_events_impl;
 
     #[sqlx::test]
@@ -39,11 +59,40 @@ mod tests {
         .await
         .unwrap();
 
-        let state = web::Data::new(AppState { db: pool });
-        let resp = list_events_impl(state).await.unwrap();
+        let state = web::Data::new(AppState { db: pool, keys: None, bundle_signer: None, ct_log: None, proofing: Default::default() });
+        let resp = list_events_impl(state, web::Query(ListQuery::default())).await.unwrap();
         assert_eq!(resp.status(), StatusCode::OK);
         let events: Vec<AuditEvent> = serde_json::from_slice(&to_bytes(resp.into_body()).await.unwrap()).unwrap();
         assert!(!events.is_empty());
         assert_eq!(events[0].event_type, "test_event");
     }
+
+    #[sqlx::test]
+    async fn list_events_paginated(pool: PgPool) {
+        for (i, event_type) in ["a", "b", "c"].into_iter().enumerate() {
+            sqlx::query("insert into audit_logs (id, event_type, occurred_at) values ($1, $2, now() - make_interval(secs => $3))")
+                .bind(Uuid::new_v4())
+                .bind(event_type)
+                .bind(i as f64)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+        let state = web::Data::new(AppState { db: pool, keys: None, bundle_signer: None, ct_log: None, proofing: Default::default() });
+
+        let query = ListQuery { page: Some(2), limit: Some(2), ..Default::default() };
+        let resp = list_events_impl(state.clone(), web::Query(query)).await.unwrap();
+        assert_eq!(resp.headers().get(TOTAL_COUNT_HEADER).unwrap(), "3");
+        let events: Vec<AuditEvent> = serde_json::from_slice(&to_bytes(resp.into_body()).await.unwrap()).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event_type, "c");
+
+        let query = ListQuery { sort: Some("event_type".into()), limit: Some(1), ..Default::default() };
+        let resp = list_events_impl(state.clone(), web::Query(query)).await.unwrap();
+        let events: Vec<AuditEvent> = serde_json::from_slice(&to_bytes(resp.into_body()).await.unwrap()).unwrap();
+        assert_eq!(events[0].event_type, "a");
+
+        let query = ListQuery { status: Some("active".into()), ..Default::default() };
+        assert!(list_events_impl(state, web::Query(query)).await.is_err());
+    }
 }
@@ -1,47 +1,179 @@
-use actix_web::{get, post, web, HttpResponse};
+use aletheia::{
+    ca::{CertificateAuthority, IssueOptions},
+    revocation::CertificateStatus,
+    Certificate as AletheiaCertificate,
+};
+use actix_web::{get, post, web, HttpRequest, HttpResponse};
 use base64::engine::general_purpose::STANDARD as b64;
 use base64::Engine;
+use chrono::Utc;
 use serde::Deserialize;
 use uuid::Uuid;
 
-use crate::{error::ApiError, models::Certificate, AppState};
+use crate::{
+    api::{policy::effective_policy, wants_cbor},
+    ct::embed_entry,
+    error::ApiError,
+    keys::{load_ca, load_ca_signer},
+    models::Certificate,
+    AppState,
+};
+
+/// How long a signed status assertion may be relied on, in seconds
+const STATUS_VALIDITY: i64 = 3600;
 
 #[derive(Deserialize)]
 pub struct CertificateRequest {
+    /// Intermediate that issues the certificate
     pub issuer_id: Option<Uuid>,
     pub subject_id: String,
     pub subject_name: String,
     pub public_key_b64: String,
     pub is_ca: bool,
+    /// Seconds the certificate is valid for; the policy's maximum if absent
+    pub validity_seconds: Option<i64>,
 }
 
-async fn issue_certificate_impl(
-    state: web::Data<AppState>,
-    req: web::Json<CertificateRequest>,
-) -> Result<HttpResponse, ApiError> {
-    let serial = Uuid::new_v4().to_string();
-    let public_key = b64
-        .decode(&req.public_key_b64)
-        .map_err(|e| ApiError::Invalid(format!("invalid public key b64: {e}")))?;
+fn normalize_fingerprint(fingerprint: &str) -> Result<String, ApiError> {
+    let fingerprint = fingerprint.trim().to_ascii_lowercase();
+    if fingerprint.len() != 64 || !fingerprint.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(ApiError::Invalid(
+            "fingerprint must be 64 hex characters".into(),
+        ));
+    }
+    Ok(fingerprint)
+}
+
+/// Serial number bytes of a certificate from its hex serial
+pub(crate) fn serial_bytes(serial: &str) -> Result<Vec<u8>, ApiError> {
+    if !serial.len().is_multiple_of(2) || !serial.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(ApiError::Invalid(format!("serial {serial} is not hex")));
+    }
+    (0..serial.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&serial[i..i + 2], 16)
+                .map_err(|e| ApiError::Invalid(format!("serial {serial}: {e}")))
+        })
+        .collect()
+}
+
+/// Issue a certificate with the key of intermediate `issuer_id` and record it
+///
+/// Checks that the issuer is active and that a certificate for `subject_id`
+/// with `options` meets the issuer's policy (see [`effective_policy`]);
+/// `issue` produces the certificate from the loaded CA. Without a validity in
+/// `options`, the certificate gets the policy's maximum validity. With a transparency log, the
+/// certificate is appended to it and re-signed with its log entry embedded.
+pub(crate) async fn issue_and_store<F>(
+    state: &AppState,
+    issuer_id: Uuid,
+    subject_id: &str,
+    is_ca: bool,
+    mut options: IssueOptions,
+    issue: F,
+) -> Result<Certificate, ApiError>
+where
+    F: FnOnce(&CertificateAuthority, &IssueOptions) -> aletheia::Result<AletheiaCertificate> + Send + 'static,
+{
+    let keys = state
+        .keys
+        .clone()
+        .ok_or_else(|| ApiError::KeyStore("no key storage configured (set CA_KEY_STORE)".into()))?;
+
+    let issuer: Option<(String,)> =
+        sqlx::query_as("select status from intermediates where id = $1")
+            .bind(issuer_id)
+            .fetch_optional(&state.db)
+            .await?;
+    match issuer {
+        Some((status,)) if status == "active" => {}
+        Some(_) => return Err(ApiError::Invalid(format!("issuer {issuer_id} is not active"))),
+        None => return Err(ApiError::Invalid(format!("unknown issuer {issuer_id}"))),
+    }
+
+    let policy = effective_policy(&state.db, issuer_id).await?;
+    if options.validity.is_none() {
+        options.validity = policy.policy.max_validity;
+    }
+    policy.check(subject_id, is_ca, &options)?;
+
+    let (ca, signer) = load_ca_signer(&state.db, keys.as_ref(), issuer_id).await?;
+    let ca = ca.with_issuance_policy(policy.policy);
+    // Signing is CPU-bound, or a network call for KMS-held keys
+    let mut cert = web::block(move || {
+        issue(&ca, &options).map_err(|e| ApiError::Invalid(format!("issuance refused: {e}")))
+    })
+    .await
+    .map_err(|e| ApiError::KeyStore(e.to_string()))??;
+
+    // The certificate is logged before it is stored, so the log never
+    // misses a stored certificate; re-signing it holds no lock
+    if let Some(log) = &state.ct_log {
+        let entry = log.append(&state.db, &cert).await?;
+        cert = web::block(move || embed_entry(cert, &entry, signer.as_ref()))
+            .await
+            .map_err(|e| ApiError::KeyStore(e.to_string()))??;
+    }
+
+    let serial: String = cert.serial.iter().map(|b| format!("{b:02x}")).collect();
+    let fingerprint = cert.fingerprint().to_hex();
+    let mut cbor = Vec::new();
+    ciborium::into_writer(&cert, &mut cbor)
+        .map_err(|e| ApiError::KeyStore(format!("encode certificate: {e}")))?;
 
     sqlx::query(
-        "insert into certificates (serial, issuer_id, subject_id, subject_name, is_ca, public_key, status) values ($1, $2, $3, $4, $5, $6, 'active')",
+        "insert into certificates (serial, issuer_id, subject_id, subject_name, is_ca, public_key, fingerprint, certificate_b64, status) values ($1, $2, $3, $4, $5, $6, $7, $8, 'active')",
     )
     .bind(&serial)
-    .bind(req.issuer_id)
-    .bind(&req.subject_id)
-    .bind(&req.subject_name)
-    .bind(req.is_ca)
-    .bind(&public_key)
+    .bind(issuer_id)
+    .bind(&cert.subject_id)
+    .bind(&cert.subject_name)
+    .bind(cert.is_ca)
+    .bind(&cert.public_key)
+    .bind(&fingerprint)
+    .bind(b64.encode(&cbor))
     .execute(&state.db)
     .await?;
 
     let created = sqlx::query_as::<_, Certificate>(
-        "select serial, issuer_id, subject_id, subject_name, is_ca, public_key, status, created_at from certificates where serial = $1",
+        "select serial, issuer_id, subject_id, subject_name, is_ca, public_key, fingerprint, certificate_b64, status, created_at from certificates where serial = $1",
     )
     .bind(&serial)
     .fetch_one(&state.db)
     .await?;
+    Ok(created)
+}
+
+pub(crate) async fn issue_certificate_impl(
+    state: web::Data<AppState>,
+    req: web::Json<CertificateRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let req = req.into_inner();
+    let public_key = b64
+        .decode(&req.public_key_b64)
+        .map_err(|e| ApiError::Invalid(format!("invalid public key b64: {e}")))?;
+    let issuer_id = req
+        .issuer_id
+        .ok_or_else(|| ApiError::Invalid("issuer_id is required".into()))?;
+
+    let mut options = IssueOptions::new();
+    if let Some(seconds) = req.validity_seconds {
+        options = options.with_validity(seconds);
+    }
+
+    let subject_id = req.subject_id.clone();
+    let created = issue_and_store(&state, issuer_id, &subject_id, req.is_ca, options, move |ca, options| {
+        ca.issue_certificate_with_options(
+            req.subject_id,
+            req.subject_name,
+            &public_key,
+            req.is_ca,
+            Utc::now().timestamp(),
+            options,
+        )
+    })
+    .await?;
 
     Ok(HttpResponse::Created().json(created))
 }
@@ -52,7 +184,7 @@ async fn get_certificate_impl(
 ) -> Result<HttpResponse, ApiError> {
     let serial = path.into_inner();
     let cert = sqlx::query_as::<_, Certificate>(
-        "select serial, issuer_id, subject_id, subject_name, is_ca, public_key, status, created_at from certificates where serial = $1",
+        "select serial, issuer_id, subject_id, subject_name, is_ca, public_key, fingerprint, certificate_b64, status, created_at from certificates where serial = $1",
     )
     .bind(&serial)
     .fetch_optional(&state.db)
@@ -64,6 +196,69 @@ async fn get_certificate_impl(
     }
 }
 
+async fn get_certificate_by_fingerprint_impl(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let fingerprint = normalize_fingerprint(&path.into_inner())?;
+    let cert = sqlx::query_as::<_, Certificate>(
+        "select serial, issuer_id, subject_id, subject_name, is_ca, public_key, fingerprint, certificate_b64, status, created_at from certificates where fingerprint = $1",
+    )
+    .bind(&fingerprint)
+    .fetch_optional(&state.db)
+    .await?;
+
+    match cert {
+        Some(c) => Ok(HttpResponse::Ok().json(c)),
+        None => Err(ApiError::NotFound),
+    }
+}
+
+/// Status of the certificate, signed by its issuer and valid for [`STATUS_VALIDITY`]
+async fn get_certificate_status_impl(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+    cbor: bool,
+) -> Result<HttpResponse, ApiError> {
+    let serial = path.into_inner();
+    let row: Option<(Option<Uuid>, bool)> = sqlx::query_as(
+        "select c.issuer_id, exists(select 1 from revocations r where r.serial = c.serial) from certificates c where c.serial = $1",
+    )
+    .bind(&serial)
+    .fetch_optional(&state.db)
+    .await?;
+    let (issuer_id, revoked) = row.ok_or(ApiError::NotFound)?;
+    let issuer_id =
+        issuer_id.ok_or_else(|| ApiError::Invalid(format!("certificate {serial} has no issuer")))?;
+    let serial_bytes = serial_bytes(&serial)?;
+    let keys = state
+        .keys
+        .clone()
+        .ok_or_else(|| ApiError::KeyStore("no key storage configured (set CA_KEY_STORE)".into()))?;
+
+    let status = if revoked {
+        CertificateStatus::Revoked
+    } else {
+        CertificateStatus::Good
+    };
+    let ca = load_ca(&state.db, keys.as_ref(), issuer_id).await?;
+    let assertion = web::block(move || {
+        ca.sign_status(&serial_bytes, status, STATUS_VALIDITY)
+            .map_err(|e| ApiError::KeyStore(format!("sign status: {e}")))
+    })
+    .await
+    .map_err(|e| ApiError::KeyStore(e.to_string()))??;
+
+    if cbor {
+        let body = assertion
+            .to_bytes()
+            .map_err(|e| ApiError::KeyStore(format!("encode status: {e}")))?;
+        Ok(HttpResponse::Ok().content_type("application/cbor").body(body))
+    } else {
+        Ok(HttpResponse::Ok().json(assertion))
+    }
+}
+
 #[post("")]
 pub async fn issue_certificate_handler(
     state: web::Data<AppState>,
@@ -80,23 +275,75 @@ pub async fn get_certificate_handler(
     get_certificate_impl(state, path).await
 }
 
+#[get("/{serial}/status")]
+pub async fn get_certificate_status_handler(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    get_certificate_status_impl(state, path, wants_cbor(&req)).await
+}
+
+#[get("/by-fingerprint/{fingerprint}")]
+pub async fn get_certificate_by_fingerprint_handler(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    get_certificate_by_fingerprint_impl(state, path).await
+}
+
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
+    use std::sync::Arc;
+
     use actix_web::{body::to_bytes, http::StatusCode, web};
+    use aletheia::{
+        ca::{CertificateAuthority, SigningKeyPair},
+        Certificate as AletheiaCertificate,
+    };
     use base64::Engine;
     use sqlx::PgPool;
-    use crate::{error::ApiError, models::Certificate, AppState};
-    use super::{get_certificate_impl, issue_certificate_impl, CertificateRequest};
+    use uuid::Uuid;
+    use crate::{error::ApiError, keys::{FileKeyStore, KeyStore}, models::Certificate, AppState};
+    use aletheia::revocation::{CertificateStatus, StatusAssertion};
+    use super::{
+        get_certificate_by_fingerprint_impl, get_certificate_impl, get_certificate_status_impl,
+        issue_certificate_impl, CertificateRequest,
+    };
+
+    /// Register an active intermediate whose key lives in a fresh file key
+    /// store; returns its id, the store and its public key
+    pub(crate) async fn intermediate(pool: &PgPool) -> (Uuid, Arc<dyn KeyStore>, Vec<u8>) {
+        let id = Uuid::new_v4();
+        let dir = std::env::temp_dir().join(format!("pki-portal-keys-{id}"));
+        std::fs::create_dir_all(&dir).unwrap();
+        let ca = CertificateAuthority::new_root(id.to_string(), "Test Root");
+        std::fs::write(dir.join(format!("{id}.key")), SigningKeyPair::from_bytes(&ca.private_key_bytes()).unwrap().to_pem().unwrap()).unwrap();
+        let mut cbor = Vec::new();
+        ciborium::into_writer(&ca.certificate, &mut cbor).unwrap();
+
+        sqlx::query("insert into intermediates (id, name, fingerprint, status, certificate_b64) values ($1, $2, $3, 'active', $4)")
+            .bind(id)
+            .bind("Test Root")
+            .bind(ca.certificate.fingerprint().to_hex())
+            .bind(base64::engine::general_purpose::STANDARD.encode(&cbor))
+            .execute(pool)
+            .await
+            .unwrap();
+        (id, Arc::new(FileKeyStore::new(dir, None)), ca.public_key())
+    }
 
     #[sqlx::test]
     async fn issue_and_get_certificate_round_trip(pool: PgPool) {
-        let state = web::Data::new(AppState { db: pool.clone() });
+        let (issuer_id, keys, _) = intermediate(&pool).await;
+        let state = web::Data::new(AppState { db: pool.clone(), keys: Some(keys), bundle_signer: None, ct_log: None, proofing: Default::default() });
         let req = CertificateRequest {
-            issuer_id: None,
+            issuer_id: Some(issuer_id),
             subject_id: "subj-1".into(),
             subject_name: "Test Subject".into(),
-            public_key_b64: base64::engine::general_purpose::STANDARD.encode(b"foo-key"),
+            public_key_b64: base64::engine::general_purpose::STANDARD.encode([7u8; 32]),
             is_ca: false,
+            validity_seconds: None,
         };
 
         let resp = issue_certificate_impl(state.clone(), web::Json(req)).await.unwrap();
@@ -104,7 +351,15 @@ mod tests {
         let body = to_bytes(resp.into_body()).await.unwrap();
         let created: Certificate = serde_json::from_slice(&body).unwrap();
 
-        let resp = get_certificate_impl(state, web::Path::from(created.serial.clone()))
+        let cbor = base64::engine::general_purpose::STANDARD
+            .decode(created.certificate_b64.as_deref().unwrap())
+            .unwrap();
+        let issued: AletheiaCertificate = ciborium::from_reader(cbor.as_slice()).unwrap();
+        let fingerprint = issued.fingerprint().to_hex();
+        assert_eq!(created.fingerprint.as_deref(), Some(fingerprint.as_str()));
+        assert_eq!(issued.public_key, vec![7u8; 32]);
+
+        let resp = get_certificate_impl(state.clone(), web::Path::from(created.serial.clone()))
             .await
             .unwrap();
         assert_eq!(resp.status(), StatusCode::OK);
@@ -113,17 +368,92 @@ mod tests {
         assert_eq!(fetched.subject_id, "subj-1");
         assert_eq!(fetched.subject_name, "Test Subject");
         assert_eq!(fetched.is_ca, false);
+        assert_eq!(fetched.issuer_id, Some(issuer_id));
+
+        let resp = get_certificate_by_fingerprint_impl(state, web::Path::from(fingerprint))
+            .await
+            .unwrap();
+        let by_fingerprint: Certificate = serde_json::from_slice(&to_bytes(resp.into_body()).await.unwrap()).unwrap();
+        assert_eq!(by_fingerprint.serial, created.serial);
+    }
+
+    #[sqlx::test]
+    async fn issue_certificate_without_key_store_unavailable(pool: PgPool) {
+        let (issuer_id, _, _) = intermediate(&pool).await;
+        let state = web::Data::new(AppState { db: pool, keys: None, bundle_signer: None, ct_log: None, proofing: Default::default() });
+        let req = CertificateRequest {
+            issuer_id: Some(issuer_id),
+            subject_id: "subj-1".into(),
+            subject_name: "Test Subject".into(),
+            public_key_b64: base64::engine::general_purpose::STANDARD.encode([7u8; 32]),
+            is_ca: false,
+            validity_seconds: None,
+        };
+
+        let result = issue_certificate_impl(state, web::Json(req)).await;
+        match result {
+            Err(ApiError::KeyStore(_)) => {}
+            other => panic!("expected key store error, got {other:?}"),
+        }
+    }
+
+    #[sqlx::test]
+    async fn certificate_status_signed_by_issuer(pool: PgPool) {
+        let (issuer_id, keys, issuer_key) = intermediate(&pool).await;
+        let state = web::Data::new(AppState { db: pool.clone(), keys: Some(keys), bundle_signer: None, ct_log: None, proofing: Default::default() });
+        let req = CertificateRequest {
+            issuer_id: Some(issuer_id),
+            subject_id: "subj-1".into(),
+            subject_name: "Test Subject".into(),
+            public_key_b64: base64::engine::general_purpose::STANDARD.encode([7u8; 32]),
+            is_ca: false,
+            validity_seconds: None,
+        };
+        let resp = issue_certificate_impl(state.clone(), web::Json(req)).await.unwrap();
+        let created: Certificate = serde_json::from_slice(&to_bytes(resp.into_body()).await.unwrap()).unwrap();
+
+        let resp = get_certificate_status_impl(state.clone(), web::Path::from(created.serial.clone()), false)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let good: StatusAssertion = serde_json::from_slice(&to_bytes(resp.into_body()).await.unwrap()).unwrap();
+        assert_eq!(good.status, CertificateStatus::Good);
+        good.verify_signature(&issuer_key).unwrap();
+
+        sqlx::query("insert into revocations (serial, reason) values ($1, 'compromise')")
+            .bind(&created.serial)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let resp = get_certificate_status_impl(state, web::Path::from(created.serial), true)
+            .await
+            .unwrap();
+        let revoked = StatusAssertion::from_bytes(&to_bytes(resp.into_body()).await.unwrap()).unwrap();
+        assert_eq!(revoked.status, CertificateStatus::Revoked);
+        revoked.verify_signature(&issuer_key).unwrap();
+    }
+
+    #[sqlx::test]
+    async fn certificate_status_unknown_serial_not_found(pool: PgPool) {
+        let (_, keys, _) = intermediate(&pool).await;
+        let state = web::Data::new(AppState { db: pool, keys: Some(keys), bundle_signer: None, ct_log: None, proofing: Default::default() });
+
+        match get_certificate_status_impl(state, web::Path::from("00ff".to_string()), false).await {
+            Err(ApiError::NotFound) => {}
+            other => panic!("expected not found, got {other:?}"),
+        }
     }
 
     #[sqlx::test]
     async fn issue_certificate_invalid_b64_rejected(_pool: PgPool) {
-        let state = web::Data::new(AppState { db: _pool });
+        let state = web::Data::new(AppState { db: _pool, keys: None, bundle_signer: None, ct_log: None, proofing: Default::default() });
         let bad_req = CertificateRequest {
             issuer_id: None,
             subject_id: "subj-bad".into(),
             subject_name: "Bad".into(),
             public_key_b64: "@@notb64".into(),
             is_ca: false,
+            validity_seconds: None,
         };
 
         let result = issue_certificate_impl(state, web::Json(bad_req)).await;
@@ -0,0 +1,155 @@
+use actix_web::{get, web, HttpRequest, HttpResponse};
+use base64::{engine::general_purpose::STANDARD as b64, Engine};
+use serde::Serialize;
+
+use crate::{
+    api::wants_cbor,
+    ct::{leaves, CtLog, TreeHead},
+    error::ApiError,
+    AppState,
+};
+
+/// The latest signed tree head, with the key that signed it
+#[derive(Serialize)]
+pub struct SignedTreeHead {
+    /// SHA-256 of the log's public key (base64)
+    pub log_id: String,
+    pub public_key_b64: String,
+    #[serde(flatten)]
+    pub head: TreeHead,
+}
+
+fn ct_log(state: &AppState) -> Result<&CtLog, ApiError> {
+    state
+        .ct_log
+        .as_ref()
+        .ok_or_else(|| ApiError::KeyStore("no transparency log key configured (set CT_LOG_KEY_ID)".into()))
+}
+
+async fn latest_head(db: &sqlx::PgPool) -> Result<TreeHead, ApiError> {
+    sqlx::query_as::<_, TreeHead>(
+        "select tree_size, root_hash, signature, created_at from ct_tree_heads order by tree_size desc limit 1",
+    )
+    .fetch_optional(db)
+    .await?
+    .ok_or(ApiError::NotFound)
+}
+
+async fn get_sth_impl(state: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
+    let log = ct_log(&state)?;
+    let head = latest_head(&state.db).await?;
+    Ok(HttpResponse::Ok().json(SignedTreeHead {
+        log_id: b64.encode(log.log_id()),
+        public_key_b64: b64.encode(log.public_key()),
+        head,
+    }))
+}
+
+/// The entry proving that certificate `serial` is in the latest tree head
+async fn get_proof_impl(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+    cbor: bool,
+) -> Result<HttpResponse, ApiError> {
+    let log = ct_log(&state)?;
+    let serial = path.into_inner();
+    let (index, integrated_time): (i64, i64) =
+        sqlx::query_as("select idx, integrated_time from ct_entries where serial = $1")
+            .bind(&serial)
+            .fetch_optional(&state.db)
+            .await?
+            .ok_or(ApiError::NotFound)?;
+    let head = latest_head(&state.db).await?;
+    let mut conn = state.db.acquire().await?;
+    let leaves = leaves(&mut conn, Some(head.tree_size)).await?;
+    let entry = log.entry(&leaves, index as u64, integrated_time, &head);
+
+    if cbor {
+        let mut body = Vec::new();
+        ciborium::into_writer(&entry, &mut body)
+            .map_err(|e| ApiError::KeyStore(format!("encode log entry: {e}")))?;
+        Ok(HttpResponse::Ok().content_type("application/cbor").body(body))
+    } else {
+        Ok(HttpResponse::Ok().json(entry))
+    }
+}
+
+#[get("/sth")]
+pub async fn get_sth_handler(state: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
+    get_sth_impl(state).await
+}
+
+#[get("/proof/{serial}")]
+pub async fn get_proof_handler(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    get_proof_impl(state, path, wants_cbor(&req)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{body::to_bytes, http::StatusCode, web};
+    use aletheia::{
+        ca::SigningKeyPair, certificate::verify_certificate_signature, transparency, Certificate as AletheiaCertificate,
+        LogEntry,
+    };
+    use base64::{engine::general_purpose::STANDARD as b64, Engine};
+    use sqlx::PgPool;
+
+    use super::{get_proof_impl, get_sth_impl};
+    use crate::{
+        api::certificates::{issue_certificate_impl, tests::intermediate, CertificateRequest},
+        ct::{logged_data, CtLog, LOG_ENTRY_EXTENSION},
+        models::Certificate,
+        AppState,
+    };
+
+    async fn issue(state: &web::Data<AppState>, issuer_id: uuid::Uuid, subject_id: &str) -> AletheiaCertificate {
+        let req = CertificateRequest {
+            issuer_id: Some(issuer_id),
+            subject_id: subject_id.into(),
+            subject_name: subject_id.into(),
+            public_key_b64: b64.encode(SigningKeyPair::generate().public_key()),
+            is_ca: false,
+            validity_seconds: None,
+        };
+        let resp = issue_certificate_impl(state.clone(), web::Json(req)).await.unwrap();
+        let created: Certificate = serde_json::from_slice(&to_bytes(resp.into_body()).await.unwrap()).unwrap();
+        let cbor = b64.decode(created.certificate_b64.unwrap()).unwrap();
+        ciborium::from_reader(cbor.as_slice()).unwrap()
+    }
+
+    #[sqlx::test]
+    async fn issued_certificates_embed_inclusion_proofs(pool: PgPool) {
+        let (issuer_id, keys, issuer_key) = intermediate(&pool).await;
+        let log = CtLog::new(SigningKeyPair::generate());
+        let state = web::Data::new(AppState { db: pool, keys: Some(keys), bundle_signer: None, ct_log: Some(log.clone()), proofing: Default::default() });
+
+        let first = issue(&state, issuer_id, "first").await;
+        let second = issue(&state, issuer_id, "second").await;
+        for (index, cert) in [&first, &second].into_iter().enumerate() {
+            verify_certificate_signature(cert, &issuer_key).unwrap();
+            let value = serde_json::to_value(cert.extension(LOG_ENTRY_EXTENSION).unwrap()).unwrap();
+            let entry: LogEntry = ciborium::from_reader(b64.decode(value.as_str().unwrap()).unwrap().as_slice()).unwrap();
+            assert_eq!(entry.index, index as u64);
+            assert_eq!(entry.tree_size, index as u64 + 1);
+            transparency::verify_entry(&entry, &logged_data(cert), &[log.public_key()]).unwrap();
+        }
+
+        // Against the latest tree head, the first certificate's proof grows
+        let serial: String = first.serial.iter().map(|b| format!("{b:02x}")).collect();
+        let resp = get_proof_impl(state.clone(), web::Path::from(serial), false).await.unwrap();
+        let entry: LogEntry = serde_json::from_slice(&to_bytes(resp.into_body()).await.unwrap()).unwrap();
+        assert_eq!((entry.index, entry.tree_size), (0, 2));
+        transparency::verify_entry(&entry, &logged_data(&first), &[log.public_key()]).unwrap();
+
+        let resp = get_sth_impl(state).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let sth: serde_json::Value = serde_json::from_slice(&to_bytes(resp.into_body()).await.unwrap()).unwrap();
+        assert_eq!(sth["tree_size"], 2);
+        assert_eq!(sth["root_hash"], b64.encode(&entry.root_hash));
+        assert_eq!(sth["signature"], b64.encode(&entry.checkpoint_signature));
+    }
+}
@@ -0,0 +1,692 @@
+use aletheia::{
+    ca::IssueOptions, request::CertificateRequest, AttestationMethod, IdentityAttestation,
+};
+use actix_web::{get, post, web, HttpResponse};
+use chrono::{DateTime, Utc};
+use rand::{rngs::OsRng, Rng};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use sqlx::{Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::{
+    api::{
+        certificates::issue_and_store,
+        list::{list, ListQuery, ListSpec},
+    },
+    error::ApiError,
+    models::Enrollment,
+    AppState,
+};
+
+const ENROLLMENT_COLUMNS: &str = "id, issuer_id, subject_id, subject_name, public_key, request_pem, status, reason, certificate_serial, email_verified_at, created_at, decided_at";
+
+/// How long an email challenge code stays valid, in seconds
+const CHALLENGE_VALIDITY: i64 = 15 * 60;
+/// Wrong codes accepted over all of an enrollment's challenges; the
+/// enrollment is rejected at the last one
+const MAX_CHALLENGE_ATTEMPTS: i32 = 5;
+/// Challenge emails sent for one enrollment, the first included
+const MAX_CHALLENGES_SENT: i32 = 5;
+/// Seconds to wait before another challenge email
+const RESEND_INTERVAL: i64 = 60;
+
+const ENROLLMENTS: ListSpec = ListSpec {
+    columns: ENROLLMENT_COLUMNS,
+    from: "enrollments",
+    time_column: "created_at",
+    key_column: "id",
+    status_column: Some("status"),
+    subject_id_column: Some("subject_id"),
+    issuer_column: Some("issuer_id"),
+    sortable: &[("created_at", "created_at"), ("subject_id", "subject_id")],
+};
+
+#[derive(Deserialize)]
+pub struct EnrollmentRequest {
+    /// Intermediate asked to issue the certificate
+    pub issuer_id: Uuid,
+    /// Certificate request signed by the key to certify, as written by
+    /// `aletheia cert-request`
+    pub request_pem: String,
+}
+
+#[derive(Deserialize)]
+pub struct VerifyEmailRequest {
+    /// Code from the challenge email
+    pub code: String,
+}
+
+#[derive(Deserialize)]
+pub struct RejectRequest {
+    pub reason: Option<String>,
+}
+
+fn parse_request(pem: &str) -> Result<CertificateRequest, ApiError> {
+    CertificateRequest::from_pem(pem)
+        .map_err(|e| ApiError::Invalid(format!("invalid certificate request: {e}")))
+}
+
+fn ensure_pending(enrollment: &Enrollment) -> Result<(), ApiError> {
+    if enrollment.status != "pending" {
+        return Err(ApiError::Invalid(format!(
+            "enrollment {} is already {}",
+            enrollment.id, enrollment.status
+        )));
+    }
+    Ok(())
+}
+
+/// A random six-digit challenge code
+fn challenge_code() -> String {
+    format!("{:06}", OsRng.gen_range(0..1_000_000u32))
+}
+
+fn code_hash(id: Uuid, code: &str) -> String {
+    Sha256::new()
+        .chain_update(id.as_bytes())
+        .chain_update(code.as_bytes())
+        .finalize()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// Store a fresh challenge code for `enrollment` and email it to the subject
+///
+/// Only the code's hash is kept. Wrong attempts count against the
+/// enrollment, not the code, so resending does not reset them. The mail goes
+/// out before `tx` commits, so a failed delivery leaves no unusable challenge
+/// behind.
+async fn send_challenge(
+    state: &AppState,
+    tx: &mut Transaction<'_, Postgres>,
+    enrollment: &Enrollment,
+) -> Result<(), ApiError> {
+    let code = challenge_code();
+    sqlx::query(
+        "update enrollments set email_code_hash = $2, email_code_expires_at = now() + make_interval(secs => $3), email_code_sent_at = now(), email_challenges_sent = email_challenges_sent + 1 where id = $1",
+    )
+    .bind(enrollment.id)
+    .bind(code_hash(enrollment.id, &code))
+    .bind(CHALLENGE_VALIDITY as f64)
+    .execute(&mut **tx)
+    .await?;
+
+    let mailer = state.proofing.mailer.clone();
+    let to = enrollment.subject_id.clone();
+    let body = format!(
+        "Your code to confirm the certificate request {} for {to} is {code}.\n\nIt expires in {} minutes. If you did not request a certificate, ignore this email.\n",
+        enrollment.id,
+        CHALLENGE_VALIDITY / 60,
+    );
+    web::block(move || mailer.send(&to, "Confirm your email address", &body))
+        .await
+        .map_err(|e| ApiError::Mail(e.to_string()))?
+}
+
+async fn submit_enrollment_impl(
+    state: web::Data<AppState>,
+    req: web::Json<EnrollmentRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let request = parse_request(&req.request_pem)?;
+    request
+        .verify()
+        .map_err(|e| ApiError::Invalid(format!("proof of possession failed: {e}")))?;
+
+    let issuer: Option<(String,)> =
+        sqlx::query_as("select status from intermediates where id = $1")
+            .bind(req.issuer_id)
+            .fetch_optional(&state.db)
+            .await?;
+    match issuer {
+        Some((status,)) if status == "active" => {}
+        Some(_) => return Err(ApiError::Invalid(format!("issuer {} is not active", req.issuer_id))),
+        None => return Err(ApiError::Invalid(format!("unknown issuer {}", req.issuer_id))),
+    }
+
+    if request.subject_id.parse::<lettre::Address>().is_err() {
+        return Err(ApiError::Invalid(format!(
+            "subject_id {} must be an email address",
+            request.subject_id
+        )));
+    }
+
+    let id = Uuid::new_v4();
+    let mut tx = state.db.begin().await?;
+    let created = sqlx::query_as::<_, Enrollment>(&format!(
+        "insert into enrollments (id, issuer_id, subject_id, subject_name, public_key, request_pem, status) values ($1, $2, $3, $4, $5, $6, 'pending') returning {ENROLLMENT_COLUMNS}",
+    ))
+    .bind(id)
+    .bind(req.issuer_id)
+    .bind(&request.subject_id)
+    .bind(&request.subject_name)
+    .bind(&request.public_key)
+    .bind(&req.request_pem)
+    .fetch_one(&mut *tx)
+    .await?;
+    send_challenge(&state, &mut tx, &created).await?;
+    tx.commit().await?;
+
+    Ok(HttpResponse::Created().json(created))
+}
+
+async fn get_enrollment_impl(
+    state: web::Data<AppState>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, ApiError> {
+    let enrollment = sqlx::query_as::<_, Enrollment>(&format!(
+        "select {ENROLLMENT_COLUMNS} from enrollments where id = $1",
+    ))
+    .bind(path.into_inner())
+    .fetch_optional(&state.db)
+    .await?;
+
+    match enrollment {
+        Some(e) => Ok(HttpResponse::Ok().json(e)),
+        None => Err(ApiError::NotFound),
+    }
+}
+
+/// Email a new challenge code, replacing any earlier one
+///
+/// At most [`MAX_CHALLENGES_SENT`] codes go out per enrollment, one per
+/// [`RESEND_INTERVAL`].
+async fn resend_challenge_impl(
+    state: web::Data<AppState>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, ApiError> {
+    let id = path.into_inner();
+    let mut tx = state.db.begin().await?;
+    let enrollment = sqlx::query_as::<_, Enrollment>(&format!(
+        "select {ENROLLMENT_COLUMNS} from enrollments where id = $1 for update",
+    ))
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(ApiError::NotFound)?;
+    ensure_pending(&enrollment)?;
+    if enrollment.email_verified_at.is_some() {
+        return Err(ApiError::Invalid(format!("email address of enrollment {id} is already verified")));
+    }
+
+    let (sent, sent_at): (i32, Option<DateTime<Utc>>) = sqlx::query_as(
+        "select email_challenges_sent, email_code_sent_at from enrollments where id = $1",
+    )
+    .bind(id)
+    .fetch_one(&mut *tx)
+    .await?;
+    if sent >= MAX_CHALLENGES_SENT {
+        return Err(ApiError::RateLimited(format!(
+            "no codes left for enrollment {id}; submit a new request"
+        )));
+    }
+    if let Some(sent_at) = sent_at {
+        let wait = RESEND_INTERVAL - (Utc::now() - sent_at).num_seconds();
+        if wait > 0 {
+            return Err(ApiError::RateLimited(format!("wait {wait} seconds before requesting a new code")));
+        }
+    }
+    send_challenge(&state, &mut tx, &enrollment).await?;
+    tx.commit().await?;
+
+    Ok(HttpResponse::Accepted().json(enrollment))
+}
+
+/// Check the code from the challenge email
+async fn verify_email_impl(
+    state: web::Data<AppState>,
+    path: web::Path<Uuid>,
+    req: web::Json<VerifyEmailRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let id = path.into_inner();
+    let mut tx = state.db.begin().await?;
+    let enrollment = sqlx::query_as::<_, Enrollment>(&format!(
+        "select {ENROLLMENT_COLUMNS} from enrollments where id = $1 for update",
+    ))
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(ApiError::NotFound)?;
+    ensure_pending(&enrollment)?;
+    if enrollment.email_verified_at.is_some() {
+        return Err(ApiError::Invalid(format!("email address of enrollment {id} is already verified")));
+    }
+
+    let (hash, expires_at, attempts): (Option<String>, Option<DateTime<Utc>>, i32) = sqlx::query_as(
+        "select email_code_hash, email_code_expires_at, email_attempts from enrollments where id = $1",
+    )
+    .bind(id)
+    .fetch_one(&mut *tx)
+    .await?;
+    let (Some(hash), Some(expires_at)) = (hash, expires_at) else {
+        return Err(ApiError::Invalid("no challenge pending; request a new code".into()));
+    };
+    if attempts >= MAX_CHALLENGE_ATTEMPTS {
+        return Err(ApiError::Invalid("too many attempts".into()));
+    }
+    if expires_at < Utc::now() {
+        return Err(ApiError::Invalid("code expired; request a new code".into()));
+    }
+
+    if code_hash(id, req.code.trim()) != hash {
+        // The last allowed wrong code locks the enrollment for good
+        let locked = attempts + 1 >= MAX_CHALLENGE_ATTEMPTS;
+        sqlx::query(
+            "update enrollments set email_attempts = email_attempts + 1, status = case when $2 then 'rejected' else status end, reason = case when $2 then 'too many incorrect email codes' else reason end, decided_at = case when $2 then now() else decided_at end, email_code_hash = case when $2 then null else email_code_hash end where id = $1",
+        )
+        .bind(id)
+        .bind(locked)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        if locked {
+            return Err(ApiError::Invalid(format!(
+                "incorrect code; enrollment {id} is rejected after too many attempts"
+            )));
+        }
+        return Err(ApiError::Invalid("incorrect code".into()));
+    }
+
+    let verified = sqlx::query_as::<_, Enrollment>(&format!(
+        "update enrollments set email_verified_at = now(), email_code_hash = null, email_code_expires_at = null where id = $1 returning {ENROLLMENT_COLUMNS}",
+    ))
+    .bind(id)
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(verified))
+}
+
+async fn list_pending_impl(
+    state: web::Data<AppState>,
+    query: web::Query<ListQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let query = ListQuery {
+        status: Some("pending".into()),
+        ..query.into_inner()
+    };
+    list::<Enrollment>(&state.db, &ENROLLMENTS, &query).await
+}
+
+/// Issue the requested certificate and mark the enrollment approved
+///
+/// The enrollment row stays locked until the decision is recorded, so
+/// concurrent reviewers cannot issue twice.
+async fn approve_enrollment_impl(
+    state: web::Data<AppState>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, ApiError> {
+    let id = path.into_inner();
+    let mut tx = state.db.begin().await?;
+    let enrollment = sqlx::query_as::<_, Enrollment>(&format!(
+        "select {ENROLLMENT_COLUMNS} from enrollments where id = $1 for update",
+    ))
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(ApiError::NotFound)?;
+    ensure_pending(&enrollment)?;
+    let verified_at = enrollment.email_verified_at.ok_or_else(|| {
+        ApiError::Invalid(format!("email address of enrollment {id} is not verified"))
+    })?;
+
+    // The enrollment record is the evidence of the proofing
+    let evidence = serde_json::json!({
+        "method": "email",
+        "enrollment_id": id,
+        "subject_id": enrollment.subject_id,
+        "verified_at": verified_at.timestamp(),
+    });
+    let attestation = IdentityAttestation::new(
+        AttestationMethod::Email,
+        state.proofing.verifier.clone(),
+        verified_at.timestamp(),
+    )
+    .with_evidence(evidence.to_string().as_bytes());
+    let options = IssueOptions::new().with_identity_attestation(attestation);
+
+    let request = parse_request(&enrollment.request_pem)?;
+    let cert = issue_and_store(&state, enrollment.issuer_id, &enrollment.subject_id, false, options, move |ca, options| {
+        ca.issue_from_request_with_options(&request, false, Utc::now().timestamp(), options)
+    })
+    .await?;
+
+    let approved = sqlx::query_as::<_, Enrollment>(&format!(
+        "update enrollments set status = 'approved', certificate_serial = $2, decided_at = now() where id = $1 returning {ENROLLMENT_COLUMNS}",
+    ))
+    .bind(id)
+    .bind(&cert.serial)
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(approved))
+}
+
+async fn reject_enrollment_impl(
+    state: web::Data<AppState>,
+    path: web::Path<Uuid>,
+    req: web::Json<RejectRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let id = path.into_inner();
+    let mut tx = state.db.begin().await?;
+    let enrollment = sqlx::query_as::<_, Enrollment>(&format!(
+        "select {ENROLLMENT_COLUMNS} from enrollments where id = $1 for update",
+    ))
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(ApiError::NotFound)?;
+    ensure_pending(&enrollment)?;
+
+    let rejected = sqlx::query_as::<_, Enrollment>(&format!(
+        "update enrollments set status = 'rejected', reason = $2, decided_at = now() where id = $1 returning {ENROLLMENT_COLUMNS}",
+    ))
+    .bind(id)
+    .bind(&req.reason)
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(rejected))
+}
+
+#[post("")]
+pub async fn submit_enrollment_handler(
+    state: web::Data<AppState>,
+    req: web::Json<EnrollmentRequest>,
+) -> Result<HttpResponse, ApiError> {
+    submit_enrollment_impl(state, req).await
+}
+
+#[get("/pending")]
+pub async fn list_pending_handler(
+    state: web::Data<AppState>,
+    query: web::Query<ListQuery>,
+) -> Result<HttpResponse, ApiError> {
+    list_pending_impl(state, query).await
+}
+
+#[get("/{id}")]
+pub async fn get_enrollment_handler(
+    state: web::Data<AppState>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, ApiError> {
+    get_enrollment_impl(state, path).await
+}
+
+#[post("/{id}/challenge")]
+pub async fn resend_challenge_handler(
+    state: web::Data<AppState>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, ApiError> {
+    resend_challenge_impl(state, path).await
+}
+
+#[post("/{id}/verify-email")]
+pub async fn verify_email_handler(
+    state: web::Data<AppState>,
+    path: web::Path<Uuid>,
+    req: web::Json<VerifyEmailRequest>,
+) -> Result<HttpResponse, ApiError> {
+    verify_email_impl(state, path, req).await
+}
+
+#[post("/{id}/approve")]
+pub async fn approve_enrollment_handler(
+    state: web::Data<AppState>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, ApiError> {
+    approve_enrollment_impl(state, path).await
+}
+
+#[post("/{id}/reject")]
+pub async fn reject_enrollment_handler(
+    state: web::Data<AppState>,
+    path: web::Path<Uuid>,
+    req: web::Json<RejectRequest>,
+) -> Result<HttpResponse, ApiError> {
+    reject_enrollment_impl(state, path, req).await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use actix_web::{body::to_bytes, http::StatusCode, web};
+    use aletheia::{
+        ca::SigningKeyPair, request::CertificateRequest, AttestationMethod,
+        Certificate as AletheiaCertificate,
+    };
+    use base64::{engine::general_purpose::STANDARD as b64, Engine};
+    use sqlx::PgPool;
+    use uuid::Uuid;
+    use crate::{
+        api::{
+            certificates::tests::intermediate,
+            list::{ListQuery, TOTAL_COUNT_HEADER},
+        },
+        error::ApiError,
+        mail::{EmailProofing, Mailer},
+        models::{Certificate, Enrollment},
+        AppState,
+    };
+    use super::{
+        approve_enrollment_impl, list_pending_impl, reject_enrollment_impl,
+        resend_challenge_impl, submit_enrollment_impl, verify_email_impl, EnrollmentRequest,
+        RejectRequest, VerifyEmailRequest, MAX_CHALLENGES_SENT, MAX_CHALLENGE_ATTEMPTS,
+    };
+
+    /// Keeps sent email so tests can read the challenge codes
+    #[derive(Default)]
+    struct Outbox(Mutex<Vec<(String, String)>>);
+
+    impl Mailer for Outbox {
+        fn send(&self, to: &str, _subject: &str, body: &str) -> Result<(), ApiError> {
+            self.0.lock().unwrap().push((to.into(), body.into()));
+            Ok(())
+        }
+    }
+
+    impl Outbox {
+        fn last_code(&self) -> String {
+            let sent = self.0.lock().unwrap();
+            let (_, body) = sent.last().unwrap();
+            body.split_whitespace()
+                .map(|word| word.trim_end_matches('.'))
+                .find(|word| word.len() == 6 && word.bytes().all(|b| b.is_ascii_digit()))
+                .unwrap()
+                .to_string()
+        }
+    }
+
+    async fn submit(state: &web::Data<AppState>, issuer_id: Uuid, keys: &SigningKeyPair) -> Enrollment {
+        let req = EnrollmentRequest {
+            issuer_id,
+            request_pem: CertificateRequest::new(keys, "alice@example.com", "Alice").to_pem(),
+        };
+        let resp = submit_enrollment_impl(state.clone(), web::Json(req)).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        serde_json::from_slice(&to_bytes(resp.into_body()).await.unwrap()).unwrap()
+    }
+
+    #[sqlx::test]
+    async fn enrollment_approved_issues_certificate(pool: PgPool) {
+        let (issuer_id, keys, _) = intermediate(&pool).await;
+        let outbox = Arc::new(Outbox::default());
+        let proofing = EmailProofing::new(outbox.clone(), "Test Portal");
+        let state = web::Data::new(AppState { db: pool.clone(), keys: Some(keys), bundle_signer: None, ct_log: None, proofing });
+        let user_keys = SigningKeyPair::generate();
+
+        let enrollment = submit(&state, issuer_id, &user_keys).await;
+        assert_eq!(enrollment.status, "pending");
+        assert_eq!(enrollment.subject_id, "alice@example.com");
+        assert_eq!(outbox.0.lock().unwrap()[0].0, "alice@example.com");
+
+        let resp = list_pending_impl(state.clone(), web::Query(ListQuery::default())).await.unwrap();
+        assert_eq!(resp.headers().get(TOTAL_COUNT_HEADER).unwrap(), "1");
+
+        let req = VerifyEmailRequest { code: outbox.last_code() };
+        let resp = verify_email_impl(state.clone(), web::Path::from(enrollment.id), web::Json(req))
+            .await
+            .unwrap();
+        let verified: Enrollment = serde_json::from_slice(&to_bytes(resp.into_body()).await.unwrap()).unwrap();
+        assert!(verified.email_verified_at.is_some());
+
+        let resp = approve_enrollment_impl(state.clone(), web::Path::from(enrollment.id)).await.unwrap();
+        let approved: Enrollment = serde_json::from_slice(&to_bytes(resp.into_body()).await.unwrap()).unwrap();
+        assert_eq!(approved.status, "approved");
+        let serial = approved.certificate_serial.unwrap();
+
+        let cert: Certificate = sqlx::query_as(
+            "select serial, issuer_id, subject_id, subject_name, is_ca, public_key, fingerprint, certificate_b64, status, created_at from certificates where serial = $1",
+        )
+        .bind(&serial)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(cert.public_key, user_keys.public_key());
+        assert_eq!(cert.issuer_id, Some(issuer_id));
+        let cbor = b64.decode(cert.certificate_b64.unwrap()).unwrap();
+        let issued: AletheiaCertificate = ciborium::from_reader(cbor.as_slice()).unwrap();
+        let attestation = issued.identity_attestation.unwrap();
+        assert_eq!(attestation.method, AttestationMethod::Email);
+        assert_eq!(attestation.verifier, "Test Portal");
+
+        let resp = list_pending_impl(state, web::Query(ListQuery::default())).await.unwrap();
+        assert_eq!(resp.headers().get(TOTAL_COUNT_HEADER).unwrap(), "0");
+    }
+
+    #[sqlx::test]
+    async fn rejected_enrollment_cannot_be_approved(pool: PgPool) {
+        let (issuer_id, keys, _) = intermediate(&pool).await;
+        let state = web::Data::new(AppState { db: pool, keys: Some(keys), bundle_signer: None, ct_log: None, proofing: Default::default() });
+        let enrollment = submit(&state, issuer_id, &SigningKeyPair::generate()).await;
+
+        let req = RejectRequest { reason: Some("identity not verified".into()) };
+        let resp = reject_enrollment_impl(state.clone(), web::Path::from(enrollment.id), web::Json(req))
+            .await
+            .unwrap();
+        let rejected: Enrollment = serde_json::from_slice(&to_bytes(resp.into_body()).await.unwrap()).unwrap();
+        assert_eq!(rejected.status, "rejected");
+        assert_eq!(rejected.reason.as_deref(), Some("identity not verified"));
+
+        match approve_enrollment_impl(state, web::Path::from(enrollment.id)).await {
+            Err(ApiError::Invalid(_)) => {}
+            other => panic!("expected invalid error, got {other:?}"),
+        }
+    }
+
+    #[sqlx::test]
+    async fn unverified_enrollment_cannot_be_approved(pool: PgPool) {
+        let (issuer_id, keys, _) = intermediate(&pool).await;
+        let outbox = Arc::new(Outbox::default());
+        let proofing = EmailProofing::new(outbox.clone(), "Test Portal");
+        let state = web::Data::new(AppState { db: pool, keys: Some(keys), bundle_signer: None, ct_log: None, proofing });
+        let enrollment = submit(&state, issuer_id, &SigningKeyPair::generate()).await;
+
+        let wrong = if outbox.last_code() == "000000" { "111111" } else { "000000" };
+        let req = VerifyEmailRequest { code: wrong.into() };
+        match verify_email_impl(state.clone(), web::Path::from(enrollment.id), web::Json(req)).await {
+            Err(ApiError::Invalid(message)) => assert_eq!(message, "incorrect code"),
+            other => panic!("expected invalid error, got {other:?}"),
+        }
+
+        match approve_enrollment_impl(state, web::Path::from(enrollment.id)).await {
+            Err(ApiError::Invalid(message)) => assert!(message.contains("not verified")),
+            other => panic!("expected invalid error, got {other:?}"),
+        }
+    }
+
+    #[sqlx::test]
+    async fn challenge_attempts_survive_resends(pool: PgPool) {
+        let (issuer_id, keys, _) = intermediate(&pool).await;
+        let outbox = Arc::new(Outbox::default());
+        let proofing = EmailProofing::new(outbox.clone(), "Test Portal");
+        let state = web::Data::new(AppState { db: pool.clone(), keys: Some(keys), bundle_signer: None, ct_log: None, proofing });
+        let enrollment = submit(&state, issuer_id, &SigningKeyPair::generate()).await;
+        let backdate = |id: Uuid| {
+            sqlx::query("update enrollments set email_code_sent_at = now() - interval '2 minutes' where id = $1")
+                .bind(id)
+                .execute(&pool)
+        };
+
+        // Resends are rate limited
+        match resend_challenge_impl(state.clone(), web::Path::from(enrollment.id)).await {
+            Err(ApiError::RateLimited(_)) => {}
+            other => panic!("expected rate limit, got {other:?}"),
+        }
+
+        // Wrong codes count across resends, and the last one locks the enrollment
+        for attempt in 1..=MAX_CHALLENGE_ATTEMPTS {
+            if attempt > 1 {
+                backdate(enrollment.id).await.unwrap();
+                resend_challenge_impl(state.clone(), web::Path::from(enrollment.id)).await.unwrap();
+            }
+            let wrong = if outbox.last_code() == "000000" { "111111" } else { "000000" };
+            let req = VerifyEmailRequest { code: wrong.into() };
+            match verify_email_impl(state.clone(), web::Path::from(enrollment.id), web::Json(req)).await {
+                Err(ApiError::Invalid(message)) if attempt < MAX_CHALLENGE_ATTEMPTS => assert_eq!(message, "incorrect code"),
+                Err(ApiError::Invalid(message)) => assert!(message.contains("rejected")),
+                other => panic!("expected invalid error, got {other:?}"),
+            }
+        }
+
+        let (status,): (String,) = sqlx::query_as("select status from enrollments where id = $1")
+            .bind(enrollment.id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(status, "rejected");
+        let req = VerifyEmailRequest { code: outbox.last_code() };
+        assert!(verify_email_impl(state.clone(), web::Path::from(enrollment.id), web::Json(req)).await.is_err());
+        assert!(resend_challenge_impl(state, web::Path::from(enrollment.id)).await.is_err());
+    }
+
+    #[sqlx::test]
+    async fn challenge_emails_are_limited(pool: PgPool) {
+        let (issuer_id, keys, _) = intermediate(&pool).await;
+        let outbox = Arc::new(Outbox::default());
+        let proofing = EmailProofing::new(outbox.clone(), "Test Portal");
+        let state = web::Data::new(AppState { db: pool.clone(), keys: Some(keys), bundle_signer: None, ct_log: None, proofing });
+        let enrollment = submit(&state, issuer_id, &SigningKeyPair::generate()).await;
+
+        for _ in 1..MAX_CHALLENGES_SENT {
+            sqlx::query("update enrollments set email_code_sent_at = now() - interval '2 minutes' where id = $1")
+                .bind(enrollment.id)
+                .execute(&pool)
+                .await
+                .unwrap();
+            resend_challenge_impl(state.clone(), web::Path::from(enrollment.id)).await.unwrap();
+        }
+        assert_eq!(outbox.0.lock().unwrap().len(), MAX_CHALLENGES_SENT as usize);
+
+        sqlx::query("update enrollments set email_code_sent_at = now() - interval '2 minutes' where id = $1")
+            .bind(enrollment.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        match resend_challenge_impl(state, web::Path::from(enrollment.id)).await {
+            Err(ApiError::RateLimited(message)) => assert!(message.contains("no codes left")),
+            other => panic!("expected rate limit, got {other:?}"),
+        }
+    }
+
+    #[sqlx::test]
+    async fn enrollment_without_proof_of_possession_rejected(pool: PgPool) {
+        let (issuer_id, keys, _) = intermediate(&pool).await;
+        let state = web::Data::new(AppState { db: pool, keys: Some(keys), bundle_signer: None, ct_log: None, proofing: Default::default() });
+
+        // A request for someone else's key, signed with ours
+        let mut request = CertificateRequest::new(&SigningKeyPair::generate(), "mallory", "Mallory");
+        request.public_key = SigningKeyPair::generate().public_key();
+        let req = EnrollmentRequest { issuer_id, request_pem: request.to_pem() };
+
+        match submit_enrollment_impl(state, web::Json(req)).await {
+            Err(ApiError::Invalid(message)) => assert!(message.contains("proof of possession")),
+            other => panic!("expected invalid error, got {other:?}"),
+        }
+    }
+}
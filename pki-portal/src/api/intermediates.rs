@@ -1,47 +1,109 @@
 use actix_web::{get, post, web, HttpResponse};
+use aletheia::ca::IssueOptions;
+use base64::{engine::general_purpose::STANDARD as b64, Engine};
 use serde::Deserialize;
 use uuid::Uuid;
-use crate::{error::ApiError, models::Intermediate, AppState};
+use crate::{
+    api::list::{list, ListQuery, ListSpec},
+    error::ApiError,
+    keys::load_ca,
+    models::Intermediate,
+    AppState,
+};
 
 #[derive(Deserialize)]
 pub struct CreateIntermediateRequest {
+    /// Root that signs the intermediate's certificate
     pub parent_id: Uuid,
     pub name: String,
+    /// Intermediate CAs allowed below this one; unlimited when omitted
     pub path_len: Option<i32>,
 }
 
-#[get("")]
-pub async fn list_intermediates(state: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
-    let rows = sqlx::query_as::<_, Intermediate>(
-        "select id, parent_id, name, fingerprint, path_len, status, created_at from intermediates order by created_at desc",
-    )
-    .fetch_all(&state.db)
-    .await?;
+const INTERMEDIATES: ListSpec = ListSpec {
+    columns: "id, issuer_id as parent_id, name, fingerprint, path_len, status, created_at",
+    from: "intermediates",
+    time_column: "created_at",
+    key_column: "id",
+    status_column: Some("status"),
+    subject_id_column: None,
+    issuer_column: Some("issuer_id"),
+    sortable: &[("created_at", "created_at"), ("name", "name")],
+};
 
-    Ok(HttpResponse::Ok().json(rows))
+#[get("")]
+pub async fn list_intermediates(
+    state: web::Data<AppState>,
+    query: web::Query<ListQuery>,
+) -> Result<HttpResponse, ApiError> {
+    list::<Intermediate>(&state.db, &INTERMEDIATES, &query).await
 }
 
-#[post("")]
-pub async fn create_intermediate(
+/// Generate an intermediate key in the key store and have the parent root
+/// sign its CA certificate
+///
+/// The intermediate's id is its certificate's subject id.
+pub(crate) async fn create_intermediate_impl(
     state: web::Data<AppState>,
     req: web::Json<CreateIntermediateRequest>,
 ) -> Result<HttpResponse, ApiError> {
+    let keys = state
+        .keys
+        .clone()
+        .ok_or_else(|| ApiError::KeyStore("no key storage configured (set CA_KEY_STORE)".into()))?;
+    let mut options = IssueOptions::new();
+    if let Some(path_len) = req.path_len {
+        let path_len = u32::try_from(path_len)
+            .map_err(|_| ApiError::Invalid("path_len must not be negative".into()))?;
+        options = options.with_path_len(path_len);
+    }
+
+    let parent: Option<(String,)> = sqlx::query_as("select status from roots where id = $1")
+        .bind(req.parent_id)
+        .fetch_optional(&state.db)
+        .await?;
+    match parent {
+        Some((status,)) if status == "active" => {}
+        Some(_) => return Err(ApiError::Invalid(format!("root {} is not active", req.parent_id))),
+        None => return Err(ApiError::Invalid(format!("unknown root {}", req.parent_id))),
+    }
+    let root = load_ca(&state.db, keys.as_ref(), req.parent_id).await?;
+
     let id = Uuid::new_v4();
-    let fingerprint = format!("fp-{}", id);
+    let public_key = keys.generate(id).await?.public_key();
+    let name = req.name.clone();
+    // Signing is CPU-bound, or a network call for KMS-held keys
+    let certificate = web::block(move || {
+        root.issue_certificate_with_options(
+            id.to_string(),
+            name,
+            &public_key,
+            true,
+            chrono::Utc::now().timestamp(),
+            &options,
+        )
+    })
+    .await
+    .map_err(|e| ApiError::KeyStore(e.to_string()))?
+    .map_err(|e| ApiError::KeyStore(format!("sign intermediate {id}: {e}")))?;
+    let mut cbor = Vec::new();
+    ciborium::into_writer(&certificate, &mut cbor)
+        .map_err(|e| ApiError::KeyStore(format!("encode certificate: {e}")))?;
 
     sqlx::query(
-        "insert into intermediates (id, parent_id, name, fingerprint, path_len, status) values ($1, $2, $3, $4, $5, 'active')",
+        "insert into intermediates (id, issuer_id, name, fingerprint, path_len, status, certificate_b64) values ($1, $2, $3, $4, $5, 'active', $6)",
     )
     .bind(id)
     .bind(req.parent_id)
     .bind(&req.name)
-    .bind(&fingerprint)
+    .bind(certificate.fingerprint().to_hex())
     .bind(req.path_len)
+    .bind(b64.encode(&cbor))
     .execute(&state.db)
     .await?;
 
     let created = sqlx::query_as::<_, Intermediate>(
-        "select id, parent_id, name, fingerprint, path_len, status, created_at from intermediates where id = $1",
+        "select id, issuer_id as parent_id, name, fingerprint, path_len, status, created_at from intermediates where id = $1",
     )
     .bind(id)
     .fetch_one(&state.db)
@@ -50,6 +112,50 @@ pub async fn create_intermediate(
     Ok(HttpResponse::Created().json(created))
 }
 
+#[post("")]
+pub async fn create_intermediate(
+    state: web::Data<AppState>,
+    req: web::Json<CreateIntermediateRequest>,
+) -> Result<HttpResponse, ApiError> {
+    create_intermediate_impl(state, req).await
+}
+
+#[derive(Deserialize)]
+pub struct RevokeIntermediateRequest {
+    pub reason: Option<String>,
+}
+
+/// Revoke an intermediate: it stops issuing and is listed on the CRL of its
+/// root
+pub(crate) async fn revoke_intermediate_impl(
+    state: web::Data<AppState>,
+    path: web::Path<Uuid>,
+    req: web::Json<RevokeIntermediateRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let id = path.into_inner();
+    let item = sqlx::query_as::<_, Intermediate>(
+        "update intermediates set status = 'revoked', revoked_at = coalesce(revoked_at, now()), revocation_reason = $2 where id = $1 returning id, issuer_id as parent_id, name, fingerprint, path_len, status, created_at",
+    )
+    .bind(id)
+    .bind(&req.reason)
+    .fetch_optional(&state.db)
+    .await?;
+
+    match item {
+        Some(row) => Ok(HttpResponse::Ok().json(row)),
+        None => Err(ApiError::NotFound),
+    }
+}
+
+#[post("/{id}/revoke")]
+pub async fn revoke_intermediate(
+    state: web::Data<AppState>,
+    path: web::Path<Uuid>,
+    req: web::Json<RevokeIntermediateRequest>,
+) -> Result<HttpResponse, ApiError> {
+    revoke_intermediate_impl(state, path, req).await
+}
+
 #[get("/{id}")]
 pub async fn get_intermediate(
     state: web::Data<AppState>,
@@ -57,7 +163,7 @@ pub async fn get_intermediate(
 ) -> Result<HttpResponse, ApiError> {
     let id = path.into_inner();
     let item = sqlx::query_as::<_, Intermediate>(
-        "select id, parent_id, name, fingerprint, path_len, status, created_at from intermediates where id = $1",
+        "select id, issuer_id as parent_id, name, fingerprint, path_len, status, created_at from intermediates where id = $1",
     )
     .bind(id)
     .fetch_optional(&state.db)
@@ -68,3 +174,75 @@ pub async fn get_intermediate(
         None => Err(ApiError::NotFound),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use actix_web::{body::to_bytes, http::StatusCode, web};
+    use aletheia::{certificate::verify_certificate_signature, Certificate as AletheiaCertificate};
+    use base64::{engine::general_purpose::STANDARD as b64, Engine};
+    use sqlx::PgPool;
+    use uuid::Uuid;
+
+    use super::{create_intermediate_impl, CreateIntermediateRequest};
+    use crate::{
+        api::roots::{create_root_impl, CreateRootRequest},
+        error::ApiError,
+        keys::{FileKeyStore, KeyStore},
+        models::{Intermediate, Root},
+        AppState,
+    };
+
+    async fn certificate(pool: &PgPool, table: &str, id: Uuid) -> AletheiaCertificate {
+        let (certificate_b64,): (String,) =
+            sqlx::query_as(&format!("select certificate_b64 from {table} where id = $1"))
+                .bind(id)
+                .fetch_one(pool)
+                .await
+                .unwrap();
+        ciborium::from_reader(b64.decode(certificate_b64).unwrap().as_slice()).unwrap()
+    }
+
+    #[sqlx::test]
+    async fn root_signs_intermediate_with_stored_keys(pool: PgPool) {
+        let dir = std::env::temp_dir().join(format!("pki-portal-keys-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let keys: Arc<dyn KeyStore> = Arc::new(FileKeyStore::new(&dir, None));
+        let state = web::Data::new(AppState { db: pool.clone(), keys: Some(keys.clone()), bundle_signer: None, ct_log: None, proofing: Default::default() });
+
+        let resp = create_root_impl(state.clone(), web::Json(CreateRootRequest { name: "Root".into() })).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        let root: Root = serde_json::from_slice(&to_bytes(resp.into_body()).await.unwrap()).unwrap();
+        let root_cert = certificate(&pool, "roots", root.id).await;
+        assert_eq!(root.fingerprint, root_cert.fingerprint().to_hex());
+        assert_eq!(root_cert.public_key, keys.load(root.id).await.unwrap().public_key());
+        verify_certificate_signature(&root_cert, &root_cert.public_key).unwrap();
+
+        let req = CreateIntermediateRequest { parent_id: root.id, name: "Issuing".into(), path_len: Some(0) };
+        let resp = create_intermediate_impl(state.clone(), web::Json(req)).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        let intermediate: Intermediate = serde_json::from_slice(&to_bytes(resp.into_body()).await.unwrap()).unwrap();
+        assert_eq!(intermediate.parent_id, root.id);
+        assert_eq!(intermediate.path_len, Some(0));
+
+        let cert = certificate(&pool, "intermediates", intermediate.id).await;
+        assert!(cert.is_ca);
+        assert_eq!(cert.issuer_id, root_cert.subject_id);
+        assert_eq!(cert.public_key, keys.load(intermediate.id).await.unwrap().public_key());
+        verify_certificate_signature(&cert, &root_cert.public_key).unwrap();
+    }
+
+    #[sqlx::test]
+    async fn unknown_parent_is_rejected(pool: PgPool) {
+        let dir = std::env::temp_dir().join(format!("pki-portal-keys-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let state = web::Data::new(AppState { db: pool, keys: Some(Arc::new(FileKeyStore::new(dir, None))), bundle_signer: None, ct_log: None, proofing: Default::default() });
+        let req = CreateIntermediateRequest { parent_id: Uuid::new_v4(), name: "Orphan".into(), path_len: None };
+
+        match create_intermediate_impl(state, web::Json(req)).await {
+            Err(ApiError::Invalid(_)) => {}
+            other => panic!("expected invalid error, got {other:?}"),
+        }
+    }
+}
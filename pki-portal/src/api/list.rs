@@ -0,0 +1,131 @@
+use actix_web::HttpResponse;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgRow, FromRow, PgPool, Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use crate::error::ApiError;
+
+const DEFAULT_LIMIT: i64 = 50;
+const MAX_LIMIT: i64 = 500;
+
+/// Response header carrying the number of rows matching the filters
+pub const TOTAL_COUNT_HEADER: &str = "X-Total-Count";
+
+/// `?page=&limit=&sort=` and filters accepted by the list endpoints
+///
+/// `page` starts at 1. `sort` names a column, prefixed with `-` for
+/// descending order. `from`/`to` bound the endpoint's timestamp column
+/// (RFC 3339, inclusive).
+#[derive(Debug, Default, Deserialize)]
+pub struct ListQuery {
+    pub page: Option<i64>,
+    pub limit: Option<i64>,
+    pub sort: Option<String>,
+    pub status: Option<String>,
+    pub subject_id: Option<String>,
+    pub issuer_id: Option<Uuid>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+/// How a list endpoint maps a [`ListQuery`] onto its table
+pub struct ListSpec {
+    pub columns: &'static str,
+    pub from: &'static str,
+    /// Column filtered by `from`/`to`; the default sort is newest first
+    pub time_column: &'static str,
+    /// Tie-breaker keeping pages stable when sort values repeat
+    pub key_column: &'static str,
+    pub status_column: Option<&'static str>,
+    pub subject_id_column: Option<&'static str>,
+    pub issuer_column: Option<&'static str>,
+    /// Names `sort` accepts, with the column each sorts by
+    pub sortable: &'static [(&'static str, &'static str)],
+}
+
+impl ListSpec {
+    fn push_filters(&self, qb: &mut QueryBuilder<'_, Postgres>, query: &ListQuery) -> Result<(), ApiError> {
+        fn column(
+            name: &str,
+            column: Option<&'static str>,
+            given: bool,
+        ) -> Result<Option<&'static str>, ApiError> {
+            match column {
+                None if given => Err(ApiError::Invalid(format!("filtering by {name} is not supported here"))),
+                _ => Ok(column.filter(|_| given)),
+            }
+        }
+
+        qb.push(" where true");
+        if let Some(col) = column("status", self.status_column, query.status.is_some())? {
+            qb.push(format!(" and {col} = ")).push_bind(query.status.clone());
+        }
+        if let Some(col) = column("subject_id", self.subject_id_column, query.subject_id.is_some())? {
+            qb.push(format!(" and {col} = ")).push_bind(query.subject_id.clone());
+        }
+        if let Some(col) = column("issuer_id", self.issuer_column, query.issuer_id.is_some())? {
+            qb.push(format!(" and {col} = ")).push_bind(query.issuer_id);
+        }
+        if let Some(from) = query.from {
+            qb.push(format!(" and {} >= ", self.time_column)).push_bind(from);
+        }
+        if let Some(to) = query.to {
+            qb.push(format!(" and {} <= ", self.time_column)).push_bind(to);
+        }
+        Ok(())
+    }
+
+    fn order_by(&self, sort: Option<&str>) -> Result<String, ApiError> {
+        let Some(sort) = sort else {
+            return Ok(format!("{} desc, {} desc", self.time_column, self.key_column));
+        };
+        let (name, direction) = match sort.strip_prefix('-') {
+            Some(name) => (name, "desc"),
+            None => (sort, "asc"),
+        };
+        let column = self
+            .sortable
+            .iter()
+            .find(|(sortable, _)| *sortable == name)
+            .map(|(_, column)| column)
+            .ok_or_else(|| ApiError::Invalid(format!("cannot sort by {name}")))?;
+        Ok(format!("{column} {direction}, {} {direction}", self.key_column))
+    }
+}
+
+/// One page of the rows matching `query`, with the total in [`TOTAL_COUNT_HEADER`]
+pub async fn list<T>(db: &PgPool, spec: &ListSpec, query: &ListQuery) -> Result<HttpResponse, ApiError>
+where
+    T: for<'r> FromRow<'r, PgRow> + Send + Unpin + Serialize,
+{
+    let page = query.page.unwrap_or(1);
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT);
+    if page < 1 {
+        return Err(ApiError::Invalid("page starts at 1".into()));
+    }
+    if !(1..=MAX_LIMIT).contains(&limit) {
+        return Err(ApiError::Invalid(format!("limit must be between 1 and {MAX_LIMIT}")));
+    }
+    let offset = (page - 1)
+        .checked_mul(limit)
+        .ok_or_else(|| ApiError::Invalid("page out of range".into()))?;
+    let order_by = spec.order_by(query.sort.as_deref())?;
+
+    let mut count = QueryBuilder::new(format!("select count(*) from {}", spec.from));
+    spec.push_filters(&mut count, query)?;
+    let (total,): (i64,) = count.build_query_as().fetch_one(db).await?;
+
+    let mut select = QueryBuilder::new(format!("select {} from {}", spec.columns, spec.from));
+    spec.push_filters(&mut select, query)?;
+    select
+        .push(format!(" order by {order_by} limit "))
+        .push_bind(limit)
+        .push(" offset ")
+        .push_bind(offset);
+    let rows: Vec<T> = select.build_query_as().fetch_all(db).await?;
+
+    Ok(HttpResponse::Ok()
+        .insert_header((TOTAL_COUNT_HEADER, total.to_string()))
+        .json(rows))
+}
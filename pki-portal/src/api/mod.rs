@@ -1,13 +1,24 @@
 pub mod audit;
 pub mod certificates;
+pub mod ct;
+pub mod enrollments;
 pub mod health;
 pub mod intermediates;
+pub mod list;
 pub mod policy;
 pub mod revocations;
 pub mod roots;
 pub mod trust_bundles;
 
-use actix_web::web;
+use actix_web::{http::header, web, HttpRequest};
+
+/// Whether the client asked for CBOR rather than JSON
+pub(crate) fn wants_cbor(req: &HttpRequest) -> bool {
+    req.headers()
+        .get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .is_some_and(|accept| accept.contains("application/cbor"))
+}
 
 pub fn configure(cfg: &mut web::ServiceConfig) {
     cfg.service(health::health)
@@ -22,15 +33,32 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
             web::scope("/intermediates")
                 .service(intermediates::list_intermediates)
                 .service(intermediates::create_intermediate)
-                .service(intermediates::get_intermediate),
+                .service(intermediates::get_intermediate)
+                .service(intermediates::revoke_intermediate)
+                .service(policy::get_intermediate_policy_handler)
+                .service(policy::update_intermediate_policy_handler)
+                .service(policy::delete_intermediate_policy_handler),
         )
         .service(
             web::scope("/certificates")
                 .service(certificates::issue_certificate_handler)
+                .service(certificates::get_certificate_by_fingerprint_handler)
+                .service(certificates::get_certificate_status_handler)
                 .service(certificates::get_certificate_handler),
         )
+        .service(
+            web::scope("/enrollments")
+                .service(enrollments::submit_enrollment_handler)
+                .service(enrollments::list_pending_handler)
+                .service(enrollments::get_enrollment_handler)
+                .service(enrollments::resend_challenge_handler)
+                .service(enrollments::verify_email_handler)
+                .service(enrollments::approve_enrollment_handler)
+                .service(enrollments::reject_enrollment_handler),
+        )
         .service(
             web::scope("/revocations")
+                .service(revocations::get_crl_handler)
                 .service(revocations::get_revocations_handler)
                 .service(revocations::revoke_certificate_handler),
         )
@@ -40,6 +68,15 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
                 .service(trust_bundles::get_bundle_by_version_handler)
                 .service(trust_bundles::publish_bundle_handler),
         )
+        .service(
+            web::scope("/.well-known")
+                .service(trust_bundles::get_bundle_signer_handler),
+        )
+        .service(
+            web::scope("/ct")
+                .service(ct::get_sth_handler)
+                .service(ct::get_proof_handler),
+        )
         .service(
             web::scope("/policy")
                 .service(policy::get_policy_handler)
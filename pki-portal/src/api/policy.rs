@@ -1,14 +1,87 @@
-use actix_web::{get, put, web, HttpResponse};
+use actix_web::{delete, get, put, web, HttpResponse};
+use aletheia::{
+    ca::{IssuancePolicy, IssueOptions},
+    AttestationMethod,
+};
 use serde::Deserialize;
+use sqlx::PgPool;
+use uuid::Uuid;
 
 use crate::{error::ApiError, models::Policy, AppState};
 
-async fn get_policy_impl(state: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
-    let row = sqlx::query_as::<_, Policy>(
-        "select subject_id_pattern, allow_ca_issue, updated_at from policy where id = 1",
-    )
-    .fetch_optional(&state.db)
+const POLICY_COLUMNS: &str = "subject_id_pattern, allow_ca_issue, max_validity, allowed_attestation_methods, updated_at";
+
+/// The policy an intermediate issues under
+pub(crate) struct EffectivePolicy {
+    pub policy: IssuancePolicy,
+    /// `intermediate` for the intermediate's own policy, else `global`
+    pub scope: &'static str,
+}
+
+impl EffectivePolicy {
+    /// Check a certificate about to be issued with `options`
+    pub fn check(&self, subject_id: &str, is_ca: bool, options: &IssueOptions) -> Result<(), ApiError> {
+        match self.policy.evaluate(subject_id, is_ca, options) {
+            Some(violation) => Err(ApiError::PolicyViolation {
+                rule: violation.rule.as_str(),
+                scope: self.scope,
+                message: violation.message,
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The core crate's issuance policy for a portal policy row
+fn issuance_policy(row: &Policy) -> Result<IssuancePolicy, ApiError> {
+    let mut policy = IssuancePolicy::new();
+    if let Some(pattern) = &row.subject_id_pattern {
+        policy = policy
+            .with_subject_id_pattern(pattern)
+            .map_err(|e| ApiError::Invalid(format!("invalid subject_id_pattern in policy: {e}")))?;
+    }
+    if row.allow_ca_issue {
+        policy = policy.allow_ca_issue();
+    }
+    if let Some(max_validity) = row.max_validity {
+        policy = policy.with_max_validity(max_validity);
+    }
+    if let Some(methods) = &row.allowed_attestation_methods {
+        policy = policy.require_attestation(methods.iter().map(|method| AttestationMethod::from(method.as_str())));
+    }
+    Ok(policy)
+}
+
+/// The policy of intermediate `issuer_id`, or the global policy without one
+///
+/// Without either, CA certificates are refused and any subject is accepted.
+pub(crate) async fn effective_policy(db: &PgPool, issuer_id: Uuid) -> Result<EffectivePolicy, ApiError> {
+    let own = sqlx::query_as::<_, Policy>(&format!(
+        "select {POLICY_COLUMNS} from intermediate_policies where intermediate_id = $1"
+    ))
+    .bind(issuer_id)
+    .fetch_optional(db)
     .await?;
+    if let Some(row) = own {
+        return Ok(EffectivePolicy {
+            policy: issuance_policy(&row)?,
+            scope: "intermediate",
+        });
+    }
+
+    let global = sqlx::query_as::<_, Policy>(&format!("select {POLICY_COLUMNS} from policy where id = 1"))
+        .fetch_optional(db)
+        .await?;
+    Ok(EffectivePolicy {
+        policy: global.as_ref().map(issuance_policy).transpose()?.unwrap_or_default(),
+        scope: "global",
+    })
+}
+
+async fn get_policy_impl(state: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
+    let row = sqlx::query_as::<_, Policy>(&format!("select {POLICY_COLUMNS} from policy where id = 1"))
+        .fetch_optional(&state.db)
+        .await?;
 
     match row {
         Some(p) => Ok(HttpResponse::Ok().json(p)),
@@ -20,25 +93,118 @@ async fn get_policy_impl(state: web::Data<AppState>) -> Result<HttpResponse, Api
 pub struct UpdatePolicyRequest {
     pub subject_id_pattern: Option<String>,
     pub allow_ca_issue: bool,
+    /// Longest validity of issued certificates, in seconds
+    pub max_validity: Option<i64>,
+    /// Require an attestation by one of these methods (any method if empty)
+    pub allowed_attestation_methods: Option<Vec<String>>,
+}
+
+impl UpdatePolicyRequest {
+    /// Check that the rules are usable, normalizing attestation method names
+    fn validate(&mut self) -> Result<(), ApiError> {
+        if let Some(pattern) = &self.subject_id_pattern {
+            IssuancePolicy::new()
+                .with_subject_id_pattern(pattern)
+                .map_err(|e| ApiError::Invalid(format!("invalid subject_id_pattern: {e}")))?;
+        }
+        if self.max_validity.is_some_and(|seconds| seconds <= 0) {
+            return Err(ApiError::Invalid("max_validity must be positive".into()));
+        }
+        if let Some(methods) = &mut self.allowed_attestation_methods {
+            for method in methods.iter_mut() {
+                *method = AttestationMethod::from(method.as_str()).to_string();
+            }
+        }
+        Ok(())
+    }
 }
 
 async fn update_policy_impl(
     state: web::Data<AppState>,
     req: web::Json<UpdatePolicyRequest>,
 ) -> Result<HttpResponse, ApiError> {
-    let updated = sqlx::query_as::<_, Policy>(
-        "insert into policy (id, subject_id_pattern, allow_ca_issue) values (1, $1, $2)
-         on conflict (id) do update set subject_id_pattern = excluded.subject_id_pattern, allow_ca_issue = excluded.allow_ca_issue, updated_at = now()
-         returning subject_id_pattern, allow_ca_issue, updated_at",
-    )
+    let mut req = req.into_inner();
+    req.validate()?;
+    let updated = sqlx::query_as::<_, Policy>(&format!(
+        "insert into policy (id, subject_id_pattern, allow_ca_issue, max_validity, allowed_attestation_methods) values (1, $1, $2, $3, $4)
+         on conflict (id) do update set subject_id_pattern = excluded.subject_id_pattern, allow_ca_issue = excluded.allow_ca_issue, max_validity = excluded.max_validity, allowed_attestation_methods = excluded.allowed_attestation_methods, updated_at = now()
+         returning {POLICY_COLUMNS}",
+    ))
     .bind(&req.subject_id_pattern)
     .bind(req.allow_ca_issue)
+    .bind(req.max_validity)
+    .bind(&req.allowed_attestation_methods)
     .fetch_one(&state.db)
     .await?;
 
     Ok(HttpResponse::Ok().json(updated))
 }
 
+async fn get_intermediate_policy_impl(
+    state: web::Data<AppState>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, ApiError> {
+    let row = sqlx::query_as::<_, Policy>(&format!(
+        "select {POLICY_COLUMNS} from intermediate_policies where intermediate_id = $1"
+    ))
+    .bind(path.into_inner())
+    .fetch_optional(&state.db)
+    .await?;
+
+    match row {
+        Some(p) => Ok(HttpResponse::Ok().json(p)),
+        None => Err(ApiError::NotFound),
+    }
+}
+
+/// Give intermediate `id` its own policy, replacing the global one for it
+async fn update_intermediate_policy_impl(
+    state: web::Data<AppState>,
+    path: web::Path<Uuid>,
+    req: web::Json<UpdatePolicyRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let id = path.into_inner();
+    let mut req = req.into_inner();
+    req.validate()?;
+    let exists: Option<(Uuid,)> = sqlx::query_as("select id from intermediates where id = $1")
+        .bind(id)
+        .fetch_optional(&state.db)
+        .await?;
+    if exists.is_none() {
+        return Err(ApiError::NotFound);
+    }
+
+    let updated = sqlx::query_as::<_, Policy>(&format!(
+        "insert into intermediate_policies (intermediate_id, subject_id_pattern, allow_ca_issue, max_validity, allowed_attestation_methods) values ($1, $2, $3, $4, $5)
+         on conflict (intermediate_id) do update set subject_id_pattern = excluded.subject_id_pattern, allow_ca_issue = excluded.allow_ca_issue, max_validity = excluded.max_validity, allowed_attestation_methods = excluded.allowed_attestation_methods, updated_at = now()
+         returning {POLICY_COLUMNS}",
+    ))
+    .bind(id)
+    .bind(&req.subject_id_pattern)
+    .bind(req.allow_ca_issue)
+    .bind(req.max_validity)
+    .bind(&req.allowed_attestation_methods)
+    .fetch_one(&state.db)
+    .await?;
+
+    Ok(HttpResponse::Ok().json(updated))
+}
+
+/// Drop intermediate `id`'s own policy; it falls back to the global one
+async fn delete_intermediate_policy_impl(
+    state: web::Data<AppState>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, ApiError> {
+    let deleted = sqlx::query("delete from intermediate_policies where intermediate_id = $1")
+        .bind(path.into_inner())
+        .execute(&state.db)
+        .await?;
+    if deleted.rows_affected() == 0 {
+        return Err(ApiError::NotFound);
+    }
+    Ok(HttpResponse::NoContent().finish())
+}
+
 #[get("")]
 pub async fn get_policy_handler(state: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
     get_policy_impl(state).await
@@ -52,26 +218,62 @@ pub async fn update_policy_handler(
     update_policy_impl(state, req).await
 }
 
+#[get("/{id}/policy")]
+pub async fn get_intermediate_policy_handler(
+    state: web::Data<AppState>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, ApiError> {
+    get_intermediate_policy_impl(state, path).await
+}
+
+#[put("/{id}/policy")]
+pub async fn update_intermediate_policy_handler(
+    state: web::Data<AppState>,
+    path: web::Path<Uuid>,
+    req: web::Json<UpdatePolicyRequest>,
+) -> Result<HttpResponse, ApiError> {
+    update_intermediate_policy_impl(state, path, req).await
+}
+
+#[delete("/{id}/policy")]
+pub async fn delete_intermediate_policy_handler(
+    state: web::Data<AppState>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, ApiError> {
+    delete_intermediate_policy_impl(state, path).await
+}
+
 #[cfg(test)]
 mod tests {
     use actix_web::{body::to_bytes, http::StatusCode, web};
+    use aletheia::Certificate as AletheiaCertificate;
+    use base64::{engine::general_purpose::STANDARD as b64, Engine};
     use sqlx::PgPool;
-    use crate::{models::Policy, AppState};
-    use super::{get_policy_impl, update_policy_impl, UpdatePolicyRequest};
+    use crate::{
+        api::certificates::{issue_certificate_impl, tests::intermediate, CertificateRequest},
+        error::ApiError,
+        models::{Certificate, Policy},
+        AppState,
+    };
+    use super::{get_policy_impl, update_intermediate_policy_impl, update_policy_impl, UpdatePolicyRequest};
 
     #[sqlx::test]
     async fn policy_round_trip(pool: PgPool) {
-        let state = web::Data::new(AppState { db: pool });
+        let state = web::Data::new(AppState { db: pool, keys: None, bundle_signer: None, ct_log: None, proofing: Default::default() });
 
         // Update policy (upsert) - creates if not exists
         let req = UpdatePolicyRequest {
             subject_id_pattern: Some("^subj-.*$".into()),
             allow_ca_issue: true,
+            max_validity: Some(86400),
+            allowed_attestation_methods: Some(vec!["EMAIL".into()]),
         };
         let resp = update_policy_impl(state.clone(), web::Json(req)).await.unwrap();
         let updated: Policy = serde_json::from_slice(&to_bytes(resp.into_body()).await.unwrap()).unwrap();
         assert_eq!(updated.allow_ca_issue, true);
         assert_eq!(updated.subject_id_pattern.as_deref(), Some("^subj-.*$"));
+        assert_eq!(updated.max_validity, Some(86400));
+        assert_eq!(updated.allowed_attestation_methods, Some(vec!["email".to_string()]));
 
         // Now get should succeed
         let resp = get_policy_impl(state).await.unwrap();
@@ -79,4 +281,66 @@ mod tests {
         let fetched: Policy = serde_json::from_slice(&to_bytes(resp.into_body()).await.unwrap()).unwrap();
         assert_eq!(fetched.allow_ca_issue, true);
     }
+
+    fn request(issuer_id: uuid::Uuid, subject_id: &str) -> CertificateRequest {
+        CertificateRequest {
+            issuer_id: Some(issuer_id),
+            subject_id: subject_id.into(),
+            subject_name: "Test Subject".into(),
+            public_key_b64: b64.encode([7u8; 32]),
+            is_ca: false,
+            validity_seconds: None,
+        }
+    }
+
+    #[sqlx::test]
+    async fn intermediate_policy_overrides_global_policy(pool: PgPool) {
+        let (issuer_id, keys, _) = intermediate(&pool).await;
+        let state = web::Data::new(AppState { db: pool, keys: Some(keys), bundle_signer: None, ct_log: None, proofing: Default::default() });
+        let global = UpdatePolicyRequest {
+            subject_id_pattern: None,
+            allow_ca_issue: false,
+            max_validity: None,
+            allowed_attestation_methods: None,
+        };
+        update_policy_impl(state.clone(), web::Json(global)).await.unwrap();
+        issue_certificate_impl(state.clone(), web::Json(request(issuer_id, "anyone"))).await.unwrap();
+
+        let own = UpdatePolicyRequest {
+            subject_id_pattern: Some("^svc-".into()),
+            allow_ca_issue: false,
+            max_validity: Some(3600),
+            allowed_attestation_methods: None,
+        };
+        update_intermediate_policy_impl(state.clone(), web::Path::from(issuer_id), web::Json(own))
+            .await
+            .unwrap();
+
+        let err = issue_certificate_impl(state.clone(), web::Json(request(issuer_id, "anyone")))
+            .await
+            .unwrap_err();
+        match err {
+            ApiError::PolicyViolation { rule, scope, .. } => {
+                assert_eq!(rule, "subject_id_pattern");
+                assert_eq!(scope, "intermediate");
+            }
+            other => panic!("expected a policy violation, got {other:?}"),
+        }
+        let resp = actix_web::ResponseError::error_response(
+            &issue_certificate_impl(state.clone(), web::Json(request(issuer_id, "anyone"))).await.unwrap_err(),
+        );
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+        // Without a requested validity the certificate gets the maximum
+        let resp = issue_certificate_impl(state.clone(), web::Json(request(issuer_id, "svc-1"))).await.unwrap();
+        let created: Certificate = serde_json::from_slice(&to_bytes(resp.into_body()).await.unwrap()).unwrap();
+        let cbor = b64.decode(created.certificate_b64.as_deref().unwrap()).unwrap();
+        let issued: AletheiaCertificate = ciborium::from_reader(cbor.as_slice()).unwrap();
+        assert_eq!(issued.not_after, Some(issued.issued_at + 3600));
+
+        let mut longer = request(issuer_id, "svc-2");
+        longer.validity_seconds = Some(7200);
+        let err = issue_certificate_impl(state, web::Json(longer)).await.unwrap_err();
+        assert!(matches!(err, ApiError::PolicyViolation { rule: "max_validity", .. }));
+    }
 }
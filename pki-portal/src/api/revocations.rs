@@ -1,7 +1,25 @@
-use actix_web::{get, post, web, HttpResponse};
-use serde::Deserialize;
+use aletheia::{
+    revocation::{RevocationList, RevokedCertificate},
+    Certificate,
+};
+use actix_web::{get, post, web, HttpRequest, HttpResponse};
+use base64::{engine::general_purpose::STANDARD as b64, Engine};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
 
-use crate::{error::ApiError, models::Revocation, AppState};
+use crate::{
+    api::{
+        certificates::serial_bytes,
+        list::{list, ListQuery, ListSpec},
+        wants_cbor,
+    },
+    error::ApiError,
+    keys::load_ca,
+    models::Revocation,
+    AppState,
+};
 
 #[derive(Deserialize)]
 pub struct RevocationRequest {
@@ -9,14 +27,178 @@ pub struct RevocationRequest {
     pub reason: Option<String>,
 }
 
-async fn get_revocations_impl(state: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
-    let rows = sqlx::query_as::<_, Revocation>(
-        "select serial, reason, revoked_at from revocations order by revoked_at desc",
+const REVOCATIONS: ListSpec = ListSpec {
+    columns: "r.serial, r.reason, r.revoked_at",
+    from: "revocations r join certificates c on c.serial = r.serial",
+    time_column: "r.revoked_at",
+    key_column: "r.serial",
+    status_column: None,
+    subject_id_column: Some("c.subject_id"),
+    issuer_column: Some("c.issuer_id"),
+    sortable: &[("revoked_at", "r.revoked_at"), ("serial", "r.serial")],
+};
+
+async fn get_revocations_impl(
+    state: web::Data<AppState>,
+    query: web::Query<ListQuery>,
+) -> Result<HttpResponse, ApiError> {
+    list::<Revocation>(&state.db, &REVOCATIONS, &query).await
+}
+
+#[derive(Deserialize)]
+pub struct CrlQuery {
+    /// Root or intermediate whose certificates the list covers
+    pub issuer_id: Uuid,
+}
+
+/// A signed CRL with its entries in readable form
+#[derive(Serialize, Deserialize)]
+pub struct CrlResponse {
+    /// CRL number, signed into the list; increases with every change to it
+    pub version: i64,
+    pub issuer_id: Uuid,
+    pub issued_at: i64,
+    pub entries: Vec<Revocation>,
+    /// The signed `RevocationList` as base64 CBOR, as the core verifier takes it
+    pub crl_b64: String,
+}
+
+/// Revocations on the list of a CA: the revoked certificates of an
+/// intermediate, or the revoked intermediates of a root
+async fn crl_rows(db: &PgPool, issuer_id: Uuid, is_root: bool) -> Result<Vec<Revocation>, ApiError> {
+    if !is_root {
+        return Ok(sqlx::query_as::<_, Revocation>(
+            "select r.serial, r.reason, r.revoked_at from revocations r join certificates c on c.serial = r.serial where c.issuer_id = $1 order by r.revoked_at, r.serial",
+        )
+        .bind(issuer_id)
+        .fetch_all(db)
+        .await?);
+    }
+
+    let intermediates = sqlx::query_as::<_, RevokedIntermediate>(
+        "select id, certificate_b64, revocation_reason, revoked_at from intermediates where issuer_id = $1 and revoked_at is not null order by revoked_at, id",
     )
-    .fetch_all(&state.db)
+    .bind(issuer_id)
+    .fetch_all(db)
     .await?;
+    intermediates
+        .into_iter()
+        .map(|row| {
+            let id = row.id;
+            let certificate: Certificate = row
+                .certificate_b64
+                .and_then(|certificate_b64| b64.decode(certificate_b64).ok())
+                .and_then(|cbor| ciborium::from_reader(cbor.as_slice()).ok())
+                .ok_or_else(|| ApiError::Invalid(format!("intermediate {id} has no valid certificate")))?;
+            Ok(Revocation {
+                serial: certificate.serial.iter().map(|b| format!("{b:02x}")).collect(),
+                reason: row.revocation_reason,
+                revoked_at: row.revoked_at,
+            })
+        })
+        .collect()
+}
 
-    Ok(HttpResponse::Ok().json(rows))
+#[derive(FromRow)]
+struct RevokedIntermediate {
+    id: Uuid,
+    certificate_b64: Option<String>,
+    revocation_reason: Option<String>,
+    revoked_at: DateTime<Utc>,
+}
+
+/// The revocation list of a root or intermediate, signed with its key
+///
+/// The signed list is cached per issuer and re-signed under the next CRL
+/// number only when the issuer's revocations have changed.
+async fn get_crl_impl(
+    state: web::Data<AppState>,
+    query: web::Query<CrlQuery>,
+    cbor: bool,
+) -> Result<HttpResponse, ApiError> {
+    let issuer_id = query.issuer_id;
+    let issuer: Option<(bool,)> = sqlx::query_as(
+        "select false from intermediates where id = $1 union all select true from roots where id = $1",
+    )
+    .bind(issuer_id)
+    .fetch_optional(&state.db)
+    .await?;
+    let Some((is_root,)) = issuer else {
+        return Err(ApiError::NotFound);
+    };
+
+    let rows = crl_rows(&state.db, issuer_id, is_root).await?;
+
+    let entries = rows
+        .iter()
+        .map(|row| {
+            let entry = RevokedCertificate::new(serial_bytes(&row.serial)?, row.revoked_at.timestamp());
+            Ok(match &row.reason {
+                Some(reason) => entry.with_reason(reason),
+                None => entry,
+            })
+        })
+        .collect::<Result<Vec<_>, ApiError>>()?;
+
+    let cached: Option<(i64, Vec<u8>)> = sqlx::query_as("select number, crl from crls where issuer_id = $1")
+        .bind(issuer_id)
+        .fetch_optional(&state.db)
+        .await?;
+    let cached = cached
+        .map(|(number, crl_bytes)| {
+            RevocationList::from_bytes(&crl_bytes)
+                .map(|crl| (number, crl, crl_bytes))
+                .map_err(|e| ApiError::KeyStore(format!("cached CRL of {issuer_id}: {e}")))
+        })
+        .transpose()?;
+
+    let (crl, crl_bytes) = match cached {
+        Some((_, crl, crl_bytes)) if crl.entries == entries => (crl, crl_bytes),
+        cached => {
+            let number = cached.map_or(1, |(number, ..)| number + 1);
+            let keys = state
+                .keys
+                .clone()
+                .ok_or_else(|| ApiError::KeyStore("no key storage configured (set CA_KEY_STORE)".into()))?;
+            let ca = load_ca(&state.db, keys.as_ref(), issuer_id).await?;
+            // Signing is CPU-bound, or a network call for KMS-held keys
+            let crl = web::block(move || {
+                ca.create_numbered_revocation_list(entries, number as u64, Utc::now().timestamp())
+                    .map_err(|e| ApiError::KeyStore(format!("sign CRL: {e}")))
+            })
+            .await
+            .map_err(|e| ApiError::KeyStore(e.to_string()))??;
+            let crl_bytes = crl
+                .to_bytes()
+                .map_err(|e| ApiError::KeyStore(format!("encode CRL: {e}")))?;
+
+            // A concurrent request may have stored the same number already;
+            // both lists are signed over the same entries.
+            sqlx::query(
+                "insert into crls (issuer_id, number, crl) values ($1, $2, $3) on conflict (issuer_id) do update set number = excluded.number, crl = excluded.crl, updated_at = now() where crls.number < excluded.number",
+            )
+            .bind(issuer_id)
+            .bind(number)
+            .bind(&crl_bytes)
+            .execute(&state.db)
+            .await?;
+            (crl, crl_bytes)
+        }
+    };
+    let version = crl.number.unwrap_or_default() as i64;
+
+    let mut resp = HttpResponse::Ok();
+    resp.insert_header(("ETag", format!("\"{version}\"")));
+    if cbor {
+        return Ok(resp.content_type("application/cbor").body(crl_bytes));
+    }
+    Ok(resp.json(CrlResponse {
+        version,
+        issuer_id,
+        issued_at: crl.issued_at,
+        entries: rows,
+        crl_b64: b64.encode(&crl_bytes),
+    }))
 }
 
 async fn revoke_certificate_impl(
@@ -42,8 +224,20 @@ async fn revoke_certificate_impl(
 }
 
 #[get("")]
-pub async fn get_revocations_handler(state: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
-    get_revocations_impl(state).await
+pub async fn get_revocations_handler(
+    state: web::Data<AppState>,
+    query: web::Query<ListQuery>,
+) -> Result<HttpResponse, ApiError> {
+    get_revocations_impl(state, query).await
+}
+
+#[get("/crl")]
+pub async fn get_crl_handler(
+    state: web::Data<AppState>,
+    query: web::Query<CrlQuery>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    get_crl_impl(state, query, wants_cbor(&req)).await
 }
 
 #[post("")]
@@ -56,10 +250,25 @@ pub async fn revoke_certificate_handler(
 
 #[cfg(test)]
 mod tests {
+    use std::sync::Arc;
+
     use actix_web::{body::to_bytes, http::StatusCode, web};
     use sqlx::PgPool;
-    use crate::{models::Revocation, AppState};
-    use super::{get_revocations_impl, revoke_certificate_impl, RevocationRequest};
+    use aletheia::{revocation::RevocationList, Certificate};
+    use base64::{engine::general_purpose::STANDARD as b64, Engine};
+    use uuid::Uuid;
+    use crate::{
+        api::{
+            certificates::tests::intermediate,
+            intermediates::{create_intermediate_impl, revoke_intermediate_impl, CreateIntermediateRequest, RevokeIntermediateRequest},
+            list::{ListQuery, TOTAL_COUNT_HEADER},
+            roots::{create_root_impl, CreateRootRequest},
+        },
+        keys::{FileKeyStore, KeyStore},
+        models::{Intermediate, Revocation, Root},
+        AppState,
+    };
+    use super::{get_crl_impl, get_revocations_impl, revoke_certificate_impl, CrlQuery, CrlResponse, RevocationRequest};
 
     #[sqlx::test]
     async fn revoke_and_list(pool: PgPool) {
@@ -76,7 +285,7 @@ mod tests {
         .await
         .unwrap();
         
-        let state = web::Data::new(AppState { db: pool });
+        let state = web::Data::new(AppState { db: pool, keys: None, bundle_signer: None, ct_log: None, proofing: Default::default() });
         
         let req = RevocationRequest {
             serial: "serial-1".into(),
@@ -89,10 +298,107 @@ mod tests {
         assert_eq!(created.serial, "serial-1");
         assert_eq!(created.reason.as_deref(), Some("compromise"));
 
-        let resp = get_revocations_impl(state).await.unwrap();
+        let resp = get_revocations_impl(state.clone(), web::Query(ListQuery::default())).await.unwrap();
         assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers().get(TOTAL_COUNT_HEADER).unwrap(), "1");
         let list: Vec<Revocation> = serde_json::from_slice(&to_bytes(resp.into_body()).await.unwrap()).unwrap();
         assert_eq!(list.len(), 1);
         assert_eq!(list[0].serial, "serial-1");
+
+        let query = ListQuery { subject_id: Some("subj-2".into()), ..Default::default() };
+        let resp = get_revocations_impl(state, web::Query(query)).await.unwrap();
+        assert_eq!(resp.headers().get(TOTAL_COUNT_HEADER).unwrap(), "0");
+    }
+
+    #[sqlx::test]
+    async fn crl_signed_by_issuer(pool: PgPool) {
+        let (issuer_id, keys, issuer_key) = intermediate(&pool).await;
+        for serial in ["0a01", "0a02"] {
+            sqlx::query(
+                "insert into certificates (serial, issuer_id, subject_id, subject_name, is_ca, public_key, status) values ($1, $2, 'subj', 'Subject', false, $3, 'active')",
+            )
+            .bind(serial)
+            .bind(issuer_id)
+            .bind(b"test-key")
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+        sqlx::query("insert into revocations (serial, reason) values ('0a01', 'compromise')")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let state = web::Data::new(AppState { db: pool, keys: Some(keys), bundle_signer: None, ct_log: None, proofing: Default::default() });
+
+        let resp = get_crl_impl(state.clone(), web::Query(CrlQuery { issuer_id }), false)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body: CrlResponse = serde_json::from_slice(&to_bytes(resp.into_body()).await.unwrap()).unwrap();
+        assert_eq!(body.version, 1);
+        assert_eq!(body.entries.len(), 1);
+        let crl = RevocationList::from_bytes(&b64.decode(&body.crl_b64).unwrap()).unwrap();
+        crl.verify_signature(&issuer_key).unwrap();
+        assert_eq!(crl.number, Some(1));
+        assert_eq!(crl.find(&[0x0a, 0x01]).unwrap().reason.as_deref(), Some("compromise"));
+        assert!(crl.find(&[0x0a, 0x02]).is_none());
+
+        // Unchanged revocations are served from the cached list, as is
+        let resp = get_crl_impl(state.clone(), web::Query(CrlQuery { issuer_id }), true).await.unwrap();
+        let cached = to_bytes(resp.into_body()).await.unwrap();
+        assert_eq!(cached.as_ref(), b64.decode(&body.crl_b64).unwrap().as_slice());
+
+        // A new revocation is signed under the next number
+        sqlx::query("insert into revocations (serial) values ('0a02')")
+            .execute(&state.db)
+            .await
+            .unwrap();
+        let resp = get_crl_impl(state, web::Query(CrlQuery { issuer_id }), true).await.unwrap();
+        assert_eq!(resp.headers().get("ETag").unwrap(), "\"2\"");
+        let crl = RevocationList::from_bytes(&to_bytes(resp.into_body()).await.unwrap()).unwrap();
+        crl.verify_signature(&issuer_key).unwrap();
+        assert_eq!(crl.number, Some(2));
+        assert!(crl.find(&[0x0a, 0x02]).is_some());
+    }
+
+    #[sqlx::test]
+    async fn root_crl_lists_revoked_intermediates(pool: PgPool) {
+        let dir = std::env::temp_dir().join(format!("pki-portal-keys-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let keys: Arc<dyn KeyStore> = Arc::new(FileKeyStore::new(&dir, None));
+        let state = web::Data::new(AppState { db: pool, keys: Some(keys.clone()), bundle_signer: None, ct_log: None, proofing: Default::default() });
+
+        let resp = create_root_impl(state.clone(), web::Json(CreateRootRequest { name: "Root".into() })).await.unwrap();
+        let root: Root = serde_json::from_slice(&to_bytes(resp.into_body()).await.unwrap()).unwrap();
+        let mut intermediates = Vec::new();
+        for name in ["Issuing 1", "Issuing 2"] {
+            let req = CreateIntermediateRequest { parent_id: root.id, name: name.into(), path_len: None };
+            let resp = create_intermediate_impl(state.clone(), web::Json(req)).await.unwrap();
+            let intermediate: Intermediate = serde_json::from_slice(&to_bytes(resp.into_body()).await.unwrap()).unwrap();
+            intermediates.push(intermediate.id);
+        }
+
+        let req = RevokeIntermediateRequest { reason: Some("compromise".into()) };
+        let resp = revoke_intermediate_impl(state.clone(), web::Path::from(intermediates[0]), web::Json(req))
+            .await
+            .unwrap();
+        let revoked: Intermediate = serde_json::from_slice(&to_bytes(resp.into_body()).await.unwrap()).unwrap();
+        assert_eq!(revoked.status, "revoked");
+
+        let resp = get_crl_impl(state.clone(), web::Query(CrlQuery { issuer_id: root.id }), false)
+            .await
+            .unwrap();
+        let body: CrlResponse = serde_json::from_slice(&to_bytes(resp.into_body()).await.unwrap()).unwrap();
+        assert_eq!(body.entries.len(), 1);
+        let crl = RevocationList::from_bytes(&b64.decode(&body.crl_b64).unwrap()).unwrap();
+        crl.verify_signature(&keys.load(root.id).await.unwrap().public_key()).unwrap();
+
+        let (certificate_b64,): (String,) = sqlx::query_as("select certificate_b64 from intermediates where id = $1")
+            .bind(intermediates[0])
+            .fetch_one(&state.db)
+            .await
+            .unwrap();
+        let certificate: Certificate = ciborium::from_reader(b64.decode(certificate_b64).unwrap().as_slice()).unwrap();
+        assert_eq!(crl.find(&certificate.serial).unwrap().reason.as_deref(), Some("compromise"));
     }
 }
@@ -1,38 +1,69 @@
 use actix_web::{get, post, web, HttpResponse};
+use base64::{engine::general_purpose::STANDARD as b64, Engine};
 use serde::Deserialize;
 use uuid::Uuid;
 
-use crate::{error::ApiError, models::Root, AppState};
+use crate::{
+    api::list::{list, ListQuery, ListSpec},
+    error::ApiError,
+    models::Root,
+    AppState,
+};
 
 #[derive(Deserialize)]
 pub struct CreateRootRequest {
     pub name: String,
 }
 
-#[get("")]
-pub async fn list_roots(state: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
-    let rows = sqlx::query_as::<_, Root>(
-        "select id, name, fingerprint, status, created_at from roots order by created_at desc",
-    )
-    .fetch_all(&state.db)
-    .await?;
+const ROOTS: ListSpec = ListSpec {
+    columns: "id, name, fingerprint, status, created_at",
+    from: "roots",
+    time_column: "created_at",
+    key_column: "id",
+    status_column: Some("status"),
+    subject_id_column: None,
+    issuer_column: None,
+    sortable: &[("created_at", "created_at"), ("name", "name")],
+};
 
-    Ok(HttpResponse::Ok().json(rows))
+#[get("")]
+pub async fn list_roots(
+    state: web::Data<AppState>,
+    query: web::Query<ListQuery>,
+) -> Result<HttpResponse, ApiError> {
+    list::<Root>(&state.db, &ROOTS, &query).await
 }
 
-#[post("")]
-pub async fn create_root(
+/// Generate a root key in the key store and self-sign the root certificate
+///
+/// The root's id is its certificate's subject id, so certificates it signs
+/// name it as their issuer.
+pub(crate) async fn create_root_impl(
     state: web::Data<AppState>,
     req: web::Json<CreateRootRequest>,
 ) -> Result<HttpResponse, ApiError> {
-    // Placeholder: in real implementation, key material lives in KMS/HSM; fingerprint comes from stored public key.
+    let keys = state
+        .keys
+        .clone()
+        .ok_or_else(|| ApiError::KeyStore("no key storage configured (set CA_KEY_STORE)".into()))?;
     let id = Uuid::new_v4();
-    let fingerprint = format!("fp-{}", id);
+    let key = keys.generate(id).await?;
 
-    sqlx::query("insert into roots (id, name, fingerprint, status) values ($1, $2, $3, 'active')")
+    let name = req.name.clone();
+    // Signing is CPU-bound, or a network call for KMS-held keys
+    let root = web::block(move || key.into_root(id.to_string(), name))
+        .await
+        .map_err(|e| ApiError::KeyStore(e.to_string()))?
+        .map_err(|e| ApiError::KeyStore(format!("self-sign root {id}: {e}")))?;
+    let mut cbor = Vec::new();
+    ciborium::into_writer(&root.certificate, &mut cbor)
+        .map_err(|e| ApiError::KeyStore(format!("encode certificate: {e}")))?;
+
+    sqlx::query("insert into roots (id, name, fingerprint, status, certificate_b64) values ($1, $2, $3, 'active', $4)")
     .bind(id)
     .bind(&req.name)
-    .bind(&fingerprint)
+    .bind(root.certificate.fingerprint().to_hex())
+    .bind(b64.encode(&cbor))
     .execute(&state.db)
     .await?;
 
@@ -46,6 +77,14 @@ pub async fn create_root(
     Ok(HttpResponse::Created().json(created))
 }
 
+#[post("")]
+pub async fn create_root(
+    state: web::Data<AppState>,
+    req: web::Json<CreateRootRequest>,
+) -> Result<HttpResponse, ApiError> {
+    create_root_impl(state, req).await
+}
+
 #[get("/{id}")]
 pub async fn get_root(
     state: web::Data<AppState>,
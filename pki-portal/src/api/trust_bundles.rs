@@ -1,20 +1,25 @@
 use actix_web::{get, post, web, HttpResponse};
+use base64::{engine::general_purpose::STANDARD as b64, Engine};
 use chrono::Utc;
-use serde::Deserialize;
-use sha2::{Digest, Sha256};
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use aletheia::Certificate;
+
 use crate::{error::ApiError, models::TrustBundleMeta, AppState};
 
+/// Bundles are served as the signed `aletheia::TrustBundle` JSON, which
+/// clients check with `TrustBundle::verify_with_key` against the key at
+/// `/.well-known/aletheia-bundle-signer`.
 async fn get_latest_bundle_impl(state: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
-    let item = sqlx::query_as::<_, TrustBundleMeta>(
-        "select version, issued_at, url, signer_fingerprint, status, payload, signature from trust_bundles order by issued_at desc limit 1",
+    let item: Option<(serde_json::Value,)> = sqlx::query_as(
+        "select payload from trust_bundles order by issued_at desc limit 1",
     )
     .fetch_optional(&state.db)
     .await?;
 
     match item {
-        Some(bundle) => Ok(HttpResponse::Ok().json(bundle)),
+        Some((bundle,)) => Ok(HttpResponse::Ok().json(bundle)),
         None => Err(ApiError::NotFound),
     }
 }
@@ -24,15 +29,15 @@ async fn get_bundle_by_version_impl(
     path: web::Path<String>,
 ) -> Result<HttpResponse, ApiError> {
     let version = path.into_inner();
-    let item = sqlx::query_as::<_, TrustBundleMeta>(
-        "select version, issued_at, url, signer_fingerprint, status, payload, signature from trust_bundles where version = $1",
+    let item: Option<(serde_json::Value,)> = sqlx::query_as(
+        "select payload from trust_bundles where version = $1",
     )
     .bind(&version)
     .fetch_optional(&state.db)
     .await?;
 
     match item {
-        Some(bundle) => Ok(HttpResponse::Ok().json(bundle)),
+        Some((bundle,)) => Ok(HttpResponse::Ok().json(bundle)),
         None => Err(ApiError::NotFound),
     }
 }
@@ -40,57 +45,68 @@ async fn get_bundle_by_version_impl(
 #[derive(Deserialize)]
 pub struct PublishBundleRequest {
     pub url: String,
-    pub signer_fingerprint: String,
+}
+
+/// Full certificates of CAs, for embedding in a bundle
+fn bundle_certificates(kind: &str, rows: Vec<(Uuid, Option<String>)>) -> Result<Vec<Certificate>, ApiError> {
+    rows.into_iter()
+        .map(|(id, certificate_b64)| {
+            let certificate_b64 = certificate_b64
+                .ok_or_else(|| ApiError::Invalid(format!("{kind} {id} has no certificate")))?;
+            let cbor = b64
+                .decode(&certificate_b64)
+                .map_err(|e| ApiError::Invalid(format!("certificate of {kind} {id}: {e}")))?;
+            ciborium::from_reader(cbor.as_slice())
+                .map_err(|e| ApiError::Invalid(format!("certificate of {kind} {id}: {e}")))
+        })
+        .collect()
 }
 
 async fn publish_bundle_impl(
     state: web::Data<AppState>,
-    _req: web::Json<PublishBundleRequest>,
+    req: web::Json<PublishBundleRequest>,
 ) -> Result<HttpResponse, ApiError> {
-    // Assemble payload from current roots and intermediates.
-    let roots: Vec<(Uuid, String, String)> = sqlx::query_as(
-        "select id, name, fingerprint from roots where status = 'active'",
-    )
-    .fetch_all(&state.db)
-    .await?;
+    let signer = state.bundle_signer.clone().ok_or_else(|| {
+        ApiError::KeyStore("no bundle signing key configured (set BUNDLE_SIGNING_KEY_ID)".into())
+    })?;
 
-    let intermediates: Vec<(Uuid, String, String)> = sqlx::query_as(
-        "select id, name, fingerprint from intermediates where status = 'active'",
-    )
-    .fetch_all(&state.db)
-    .await?;
+    // Assemble the bundle from current roots and intermediates.
+    let roots: Vec<(Uuid, Option<String>)> =
+        sqlx::query_as("select id, certificate_b64 from roots where status = 'active'")
+            .fetch_all(&state.db)
+            .await?;
 
+    let intermediates: Vec<(Uuid, Option<String>)> =
+        sqlx::query_as("select id, certificate_b64 from intermediates where status = 'active'")
+            .fetch_all(&state.db)
+            .await?;
+
+    let roots = bundle_certificates("root", roots)?;
+    let intermediates = bundle_certificates("intermediate", intermediates)?;
     let issued_at = Utc::now();
     let version = issued_at.timestamp_millis().to_string();
 
-    let payload = serde_json::json!({
-        "version": version,
-        "issued_at": issued_at.timestamp(),
-        "roots": roots.iter().map(|(id, name, fp)| serde_json::json!({
-            "id": id,
-            "name": name,
-            "fingerprint": fp,
-        })).collect::<Vec<_>>(),
-        "intermediates": intermediates.iter().map(|(id, name, fp)| serde_json::json!({
-            "id": id,
-            "name": name,
-            "fingerprint": fp,
-        })).collect::<Vec<_>>(),
-    });
-
-    let payload_bytes = serde_json::to_vec(&payload)
-        .map_err(|e| ApiError::Invalid(format!("serialize payload: {e}")))?;
-    let mut hasher = Sha256::new();
-    hasher.update(&payload_bytes);
-    let signature = format!("{:x}", hasher.finalize());
+    // Signed over the bundle's canonical CBOR, however the JSON is
+    // re-encoded on the way; a network call for KMS-held keys.
+    let bundle_version = version.clone();
+    let bundle = web::block(move || {
+        signer.sign_bundle(bundle_version, issued_at.timestamp(), roots, intermediates)
+    })
+    .await
+    .map_err(|e| ApiError::KeyStore(e.to_string()))?
+    .map_err(|e| ApiError::KeyStore(format!("sign trust bundle: {e}")))?;
+    let payload = serde_json::to_value(&bundle)
+        .map_err(|e| ApiError::Invalid(format!("serialize bundle: {e}")))?;
+    let signature = b64.encode(&bundle.signature);
+    let signer_fingerprint = bundle.signer_fingerprint;
 
     sqlx::query(
         "insert into trust_bundles (version, issued_at, url, signer_fingerprint, status, payload, signature) values ($1, $2, $3, $4, 'active', $5, $6)",
     )
     .bind(&version)
     .bind(issued_at)
-    .bind(&_req.url)
-    .bind(&_req.signer_fingerprint)
+    .bind(&req.url)
+    .bind(&signer_fingerprint)
     .bind(&payload)
     .bind(&signature)
     .execute(&state.db)
@@ -106,6 +122,27 @@ async fn publish_bundle_impl(
     Ok(HttpResponse::Created().json(created))
 }
 
+#[derive(Serialize)]
+pub struct BundleSignerInfo {
+    pub algorithm: &'static str,
+    pub public_key_b64: String,
+    pub public_key_pem: String,
+    pub fingerprint: String,
+}
+
+async fn get_bundle_signer_impl(state: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
+    let signer = state.bundle_signer.as_ref().ok_or_else(|| {
+        ApiError::KeyStore("no bundle signing key configured (set BUNDLE_SIGNING_KEY_ID)".into())
+    })?;
+
+    Ok(HttpResponse::Ok().json(BundleSignerInfo {
+        algorithm: "Ed25519",
+        public_key_b64: b64.encode(signer.public_key()),
+        public_key_pem: signer.public_key_pem()?,
+        fingerprint: signer.fingerprint(),
+    }))
+}
+
 #[get("/latest")]
 pub async fn get_latest_bundle_handler(state: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
     get_latest_bundle_impl(state).await
@@ -122,23 +159,36 @@ pub async fn get_bundle_by_version_handler(
 #[post("")]
 pub async fn publish_bundle_handler(
     state: web::Data<AppState>,
-    _req: web::Json<PublishBundleRequest>,
+    req: web::Json<PublishBundleRequest>,
 ) -> Result<HttpResponse, ApiError> {
-    publish_bundle_impl(state, _req).await
+    publish_bundle_impl(state, req).await
+}
+
+#[get("/aletheia-bundle-signer")]
+pub async fn get_bundle_signer_handler(state: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
+    get_bundle_signer_impl(state).await
 }
 
 #[cfg(test)]
 mod tests {
     use actix_web::{body::to_bytes, http::StatusCode, web};
+    use aletheia::{
+        ca::{CertificateAuthority, SigningKeyPair},
+        trust_bundle::TrustBundle,
+    };
+    use base64::{engine::general_purpose::STANDARD as b64, Engine};
     use sqlx::PgPool;
     use uuid::Uuid;
-    use crate::{models::TrustBundleMeta, AppState};
-    use super::{get_bundle_by_version_impl, get_latest_bundle_impl, publish_bundle_impl, PublishBundleRequest};
+    use crate::{error::ApiError, keys::BundleSigner, models::TrustBundleMeta, AppState};
+    use super::{
+        get_bundle_by_version_impl, get_bundle_signer_impl, get_latest_bundle_impl,
+        publish_bundle_impl, PublishBundleRequest,
+    };
 
     #[sqlx::test]
     async fn latest_and_specific_bundle(pool: PgPool) {
         sqlx::query(
-            "insert into trust_bundles (version, url, signer_fingerprint, status, payload, signature) values ($1, $2, $3, 'active', '{}'::jsonb, 'sig')",
+            "insert into trust_bundles (version, url, signer_fingerprint, status, payload, signature) values ($1, $2, $3, 'active', '{\"version\": \"v1\"}'::jsonb, 'sig')",
         )
         .bind("v1")
         .bind("https://example.com/bundles/v1.json")
@@ -147,48 +197,90 @@ mod tests {
         .await
         .unwrap();
 
-        let state = web::Data::new(AppState { db: pool.clone() });
+        let state = web::Data::new(AppState { db: pool.clone(), keys: None, bundle_signer: None, ct_log: None, proofing: Default::default() });
 
         let resp = get_latest_bundle_impl(state.clone()).await.unwrap();
         assert_eq!(resp.status(), StatusCode::OK);
-        let latest: TrustBundleMeta = serde_json::from_slice(&to_bytes(resp.into_body()).await.unwrap()).unwrap();
-        assert_eq!(latest.version, "v1");
+        let latest: serde_json::Value = serde_json::from_slice(&to_bytes(resp.into_body()).await.unwrap()).unwrap();
+        assert_eq!(latest["version"], "v1");
 
-        let resp = get_bundle_by_version_impl(state, web::Path::from("v1".to_string()))
+        let resp = get_bundle_by_version_impl(state.clone(), web::Path::from("v1".to_string()))
             .await
             .unwrap();
         assert_eq!(resp.status(), StatusCode::OK);
-        let fetched: TrustBundleMeta = serde_json::from_slice(&to_bytes(resp.into_body()).await.unwrap()).unwrap();
-        assert_eq!(fetched.version, "v1");
-        assert_eq!(fetched.url, "https://example.com/bundles/v1.json");
+        let fetched: serde_json::Value = serde_json::from_slice(&to_bytes(resp.into_body()).await.unwrap()).unwrap();
+        assert_eq!(fetched["version"], "v1");
+
+        match get_bundle_by_version_impl(state, web::Path::from("v2".to_string())).await {
+            Err(ApiError::NotFound) => {}
+            other => panic!("expected not found, got {other:?}"),
+        }
     }
 
     #[sqlx::test]
     async fn publish_creates_bundle(pool: PgPool) {
         // seed data
-        sqlx::query("insert into roots (id, name, fingerprint, status) values ($1, $2, $3, 'active')")
+        let root = CertificateAuthority::new_root("root1", "Root 1");
+        let mut cbor = Vec::new();
+        ciborium::into_writer(&root.certificate, &mut cbor).unwrap();
+        let root_b64 = b64.encode(&cbor);
+        sqlx::query("insert into roots (id, name, fingerprint, status, certificate_b64) values ($1, $2, $3, 'active', $4)")
             .bind(Uuid::new_v4())
             .bind("root1")
-            .bind("fp-root1")
+            .bind(root.certificate.fingerprint().to_hex())
+            .bind(&root_b64)
             .execute(&pool)
             .await
             .unwrap();
 
-        let state = web::Data::new(AppState { db: pool });
+        let signer = BundleSigner::new(SigningKeyPair::generate());
+        let state = web::Data::new(AppState { db: pool, keys: None, bundle_signer: Some(signer.clone()), ct_log: None, proofing: Default::default() });
         let req = PublishBundleRequest {
             url: "https://example.com/bundles/v2.json".into(),
-            signer_fingerprint: "fp-signer".into(),
         };
 
         let resp = publish_bundle_impl(state.clone(), web::Json(req)).await.unwrap();
         assert_eq!(resp.status(), StatusCode::CREATED);
         let created: TrustBundleMeta = serde_json::from_slice(&to_bytes(resp.into_body()).await.unwrap()).unwrap();
         assert_eq!(created.url, "https://example.com/bundles/v2.json");
-        assert!(!created.signature.is_empty());
-        assert!(created.payload.get("roots").is_some());
+        assert_eq!(created.signer_fingerprint, signer.fingerprint());
 
+        // The served bundle is the library's, signed by the published key
+        // over its CBOR encoding, so the reordered JSON still verifies
         let fetched_resp = get_latest_bundle_impl(state).await.unwrap();
-        let fetched: TrustBundleMeta = serde_json::from_slice(&to_bytes(fetched_resp.into_body()).await.unwrap()).unwrap();
+        let fetched = TrustBundle::parse(&to_bytes(fetched_resp.into_body()).await.unwrap()).unwrap();
         assert_eq!(fetched.version, created.version);
+        fetched.verify_with_key(&signer.public_key()).unwrap();
+        assert_eq!(fetched.public_keys(), vec![root.public_key()]);
+
+        let mut tampered = fetched.clone();
+        tampered.roots.clear();
+        assert!(tampered.verify_with_key(&signer.public_key()).is_err());
+    }
+
+    #[sqlx::test]
+    async fn publish_without_signer_unavailable(pool: PgPool) {
+        let state = web::Data::new(AppState { db: pool, keys: None, bundle_signer: None, ct_log: None, proofing: Default::default() });
+        let req = PublishBundleRequest {
+            url: "https://example.com/bundles/v3.json".into(),
+        };
+
+        match publish_bundle_impl(state, web::Json(req)).await {
+            Err(ApiError::KeyStore(_)) => {}
+            other => panic!("expected key store error, got {other:?}"),
+        }
+    }
+
+    #[sqlx::test]
+    async fn well_known_bundle_signer(pool: PgPool) {
+        let signer = BundleSigner::new(SigningKeyPair::generate());
+        let state = web::Data::new(AppState { db: pool, keys: None, bundle_signer: Some(signer.clone()), ct_log: None, proofing: Default::default() });
+
+        let resp = get_bundle_signer_impl(state).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let info: serde_json::Value = serde_json::from_slice(&to_bytes(resp.into_body()).await.unwrap()).unwrap();
+        assert_eq!(info["algorithm"], "Ed25519");
+        assert_eq!(info["public_key_b64"], b64.encode(signer.public_key()).as_str());
+        assert_eq!(info["fingerprint"], signer.fingerprint().as_str());
     }
 }
@@ -1,7 +1,32 @@
+use uuid::Uuid;
+
 pub struct Config {
     pub bind_addr: String,
     pub database_url: String,
     pub db_max_connections: u32,
+    /// Backend holding CA keys: file, postgres, vault or aws-kms
+    pub ca_key_store: Option<String>,
+    /// Directory holding CA keys for the file store
+    pub ca_key_dir: Option<String>,
+    /// Passphrase encrypting CA keys in the file and postgres stores
+    pub ca_key_passphrase: Option<String>,
+    pub vault_addr: Option<String>,
+    pub vault_token: Option<String>,
+    /// Mount point of the Vault transit engine
+    pub vault_transit_mount: String,
+    pub aws_region: Option<String>,
+    /// KMS endpoint overriding the regional one
+    pub aws_kms_endpoint: Option<String>,
+    /// Id in the key store of the key that signs trust bundles
+    pub bundle_signing_key_id: Option<Uuid>,
+    /// Id in the key store of the key that signs transparency log tree heads
+    pub ct_log_key_id: Option<Uuid>,
+    /// SMTP relay for proofing email; email is logged without it
+    pub smtp_url: Option<String>,
+    /// Sender of proofing email
+    pub mail_from: String,
+    /// Organization named as the verifier in identity attestations
+    pub proofing_verifier: String,
 }
 
 impl Config {
@@ -12,11 +37,44 @@ impl Config {
             .ok()
             .and_then(|s| s.parse().ok())
             .unwrap_or(5);
+        let ca_key_store = std::env::var("CA_KEY_STORE").ok();
+        let ca_key_dir = std::env::var("CA_KEY_DIR").ok();
+        let ca_key_passphrase = std::env::var("CA_KEY_PASSPHRASE").ok();
+        let vault_addr = std::env::var("VAULT_ADDR").ok();
+        let vault_token = std::env::var("VAULT_TOKEN").ok();
+        let vault_transit_mount =
+            std::env::var("VAULT_TRANSIT_MOUNT").unwrap_or_else(|_| "transit".to_string());
+        let aws_region = std::env::var("AWS_REGION").ok();
+        let aws_kms_endpoint = std::env::var("AWS_KMS_ENDPOINT").ok();
+        let key_id = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .map(|id| id.parse().unwrap_or_else(|_| panic!("{name} must be a UUID")))
+        };
+        let bundle_signing_key_id = key_id("BUNDLE_SIGNING_KEY_ID");
+        let ct_log_key_id = key_id("CT_LOG_KEY_ID");
+        let smtp_url = std::env::var("SMTP_URL").ok();
+        let mail_from = std::env::var("MAIL_FROM").unwrap_or_else(|_| "pki@localhost".to_string());
+        let proofing_verifier = std::env::var("PROOFING_VERIFIER")
+            .unwrap_or_else(|_| "Aletheia PKI Portal".to_string());
 
         Self {
             bind_addr,
             database_url,
             db_max_connections,
+            ca_key_store,
+            ca_key_dir,
+            ca_key_passphrase,
+            vault_addr,
+            vault_token,
+            vault_transit_mount,
+            aws_region,
+            aws_kms_endpoint,
+            bundle_signing_key_id,
+            ct_log_key_id,
+            smtp_url,
+            mail_from,
+            proofing_verifier,
         }
     }
 }
@@ -0,0 +1,261 @@
+use std::sync::Arc;
+
+use actix_web::web;
+use aletheia::{
+    hsm::ExternalSigner,
+    merkle::{self, Frontier, Hash},
+    transparency, Certificate, LogEntry,
+};
+use base64::{engine::general_purpose::STANDARD as b64, Engine};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_bytes::ByteBuf;
+use sqlx::{FromRow, PgConnection, PgPool};
+
+use crate::{error::ApiError, keys::CaKey};
+
+/// Certificate extension holding the certificate's [`LogEntry`] as base64 CBOR
+pub const LOG_ENTRY_EXTENSION: &str = "transparency_log_entry";
+
+/// The portal's certificate transparency log
+///
+/// An append-only Merkle log of every issued certificate, in the format of
+/// the core's [`transparency`] module. Each leaf commits to a certificate's
+/// signable data without [`LOG_ENTRY_EXTENSION`] (see [`logged_data`]), and
+/// every append produces a tree head signed with the log's Ed25519 key.
+/// Each entry stores the log's [`Frontier`] after it, so an append reads one
+/// row rather than the whole log.
+#[derive(Clone)]
+pub struct CtLog {
+    signer: Arc<dyn ExternalSigner>,
+}
+
+/// A tree head the log signed when it reached `tree_size` entries
+#[derive(Debug, Serialize, FromRow)]
+pub struct TreeHead {
+    pub tree_size: i64,
+    #[serde(serialize_with = "base64")]
+    pub root_hash: Vec<u8>,
+    /// Ed25519 signature over [`transparency::checkpoint_input`]
+    #[serde(serialize_with = "base64")]
+    pub signature: Vec<u8>,
+    pub created_at: DateTime<Utc>,
+}
+
+fn base64<S: serde::Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&b64.encode(bytes))
+}
+
+impl CtLog {
+    pub fn new(key: impl Into<CaKey>) -> Self {
+        Self { signer: key.into().into_signer() }
+    }
+
+    pub fn public_key(&self) -> Vec<u8> {
+        self.signer.public_key()
+    }
+
+    /// SHA-256 of the log's public key, as entries name the log
+    pub fn log_id(&self) -> Hash {
+        transparency::log_id(&self.signer.public_key())
+    }
+
+    /// Append `precert`, an issued certificate before its log entry is
+    /// embedded, and sign the grown tree
+    ///
+    /// Appends are serialized by locking the log only while the entry is
+    /// written; the tree head is signed after, which for a KMS-held key is a
+    /// network call. Heads of concurrent appends may therefore be stored out
+    /// of order, each under its own tree size. Returns the entry proving
+    /// inclusion in the new tree head.
+    pub async fn append(&self, db: &PgPool, precert: &Certificate) -> Result<LogEntry, ApiError> {
+        let mut tx = db.begin().await?;
+        sqlx::query("lock table ct_entries in exclusive mode")
+            .execute(&mut *tx)
+            .await?;
+        let mut frontier = frontier(&mut tx).await?;
+
+        let index = frontier.size();
+        let integrated_time = Utc::now().timestamp();
+        let leaf = transparency::leaf_data(&logged_data(precert), integrated_time);
+        let proof = frontier.push(&leaf);
+        let serial: String = precert.serial.iter().map(|b| format!("{b:02x}")).collect();
+        sqlx::query(
+            "insert into ct_entries (idx, serial, leaf, integrated_time, frontier) values ($1, $2, $3, $4, $5)",
+        )
+        .bind(index as i64)
+        .bind(&serial)
+        .bind(&leaf)
+        .bind(integrated_time)
+        .bind(frontier.nodes().concat())
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        let root_hash = frontier.root();
+        let tree_size = frontier.size();
+        // A key in a key management service signs over the network
+        let signer = self.signer.clone();
+        let checkpoint = transparency::checkpoint_input(&self.log_id(), tree_size, &root_hash);
+        let signature = web::block(move || signer.sign(&checkpoint))
+            .await
+            .map_err(|e| ApiError::KeyStore(e.to_string()))?
+            .map_err(|e| ApiError::KeyStore(format!("sign tree head: {e}")))?;
+        let head = sqlx::query_as::<_, TreeHead>(
+            "insert into ct_tree_heads (tree_size, root_hash, signature) values ($1, $2, $3) returning tree_size, root_hash, signature, created_at",
+        )
+        .bind(tree_size as i64)
+        .bind(root_hash.as_slice())
+        .bind(&signature)
+        .fetch_one(db)
+        .await?;
+
+        Ok(self.entry_with_proof(index, integrated_time, proof, &head))
+    }
+
+    /// The entry proving that leaf `index` of `leaves` is in `head`
+    ///
+    /// `leaves` are the first `head.tree_size` leaves of the log.
+    pub fn entry(&self, leaves: &[Vec<u8>], index: u64, integrated_time: i64, head: &TreeHead) -> LogEntry {
+        self.entry_with_proof(index, integrated_time, inclusion_proof(leaves, index), head)
+    }
+
+    fn entry_with_proof(&self, index: u64, integrated_time: i64, proof: Vec<Hash>, head: &TreeHead) -> LogEntry {
+        LogEntry {
+            log_id: self.log_id().to_vec(),
+            integrated_time,
+            index,
+            tree_size: head.tree_size as u64,
+            root_hash: head.root_hash.clone(),
+            inclusion_proof: proof
+                .into_iter()
+                .map(|hash| ByteBuf::from(hash.to_vec()))
+                .collect(),
+            checkpoint_signature: head.signature.clone(),
+        }
+    }
+}
+
+/// The log's leaves in order, the first `tree_size` of them when given
+pub async fn leaves(conn: &mut PgConnection, tree_size: Option<i64>) -> Result<Vec<Vec<u8>>, ApiError> {
+    let rows: Vec<(Vec<u8>,)> =
+        sqlx::query_as("select leaf from ct_entries where $1::bigint is null or idx < $1 order by idx")
+            .bind(tree_size)
+            .fetch_all(conn)
+            .await?;
+    Ok(rows.into_iter().map(|(leaf,)| leaf).collect())
+}
+
+/// The frontier of the log as its last entry left it
+///
+/// Entries appended before frontiers were stored have none; the frontier is
+/// then rebuilt from the leaves once, and stored with the next entry.
+async fn frontier(conn: &mut PgConnection) -> Result<Frontier, ApiError> {
+    let last: Option<(i64, Option<Vec<u8>>)> =
+        sqlx::query_as("select idx, frontier from ct_entries order by idx desc limit 1")
+            .fetch_optional(&mut *conn)
+            .await?;
+    match last {
+        None => Ok(Frontier::new()),
+        Some((idx, Some(nodes))) => {
+            let nodes = nodes
+                .chunks(32)
+                .map(|node| Hash::try_from(node).ok())
+                .collect::<Option<Vec<_>>>()
+                .and_then(|nodes| Frontier::from_parts(idx as u64 + 1, nodes));
+            nodes.ok_or_else(|| ApiError::KeyStore(format!("corrupt log frontier at entry {idx}")))
+        }
+        Some((_, None)) => {
+            let mut frontier = Frontier::new();
+            for leaf in leaves(conn, None).await? {
+                frontier.push(&leaf);
+            }
+            Ok(frontier)
+        }
+    }
+}
+
+/// The bytes the log commits to for `certificate`
+///
+/// Its signable data without [`LOG_ENTRY_EXTENSION`], so a certificate's
+/// embedded entry can be checked with [`transparency::verify_entry`]
+/// against `logged_data(&certificate)`.
+pub fn logged_data(certificate: &Certificate) -> Vec<u8> {
+    let mut precert = certificate.clone();
+    precert.extensions.remove(LOG_ENTRY_EXTENSION);
+    precert.signable_data()
+}
+
+/// `precert` with `entry` embedded, re-signed with the issuer's `signer`
+pub fn embed_entry(
+    mut precert: Certificate,
+    entry: &LogEntry,
+    signer: &dyn ExternalSigner,
+) -> Result<Certificate, ApiError> {
+    let mut cbor = Vec::new();
+    ciborium::into_writer(entry, &mut cbor)
+        .map_err(|e| ApiError::KeyStore(format!("encode log entry: {e}")))?;
+    let value = serde_json::from_value(serde_json::Value::String(b64.encode(&cbor)))
+        .map_err(|e| ApiError::KeyStore(format!("encode log entry: {e}")))?;
+    precert.extensions.insert(LOG_ENTRY_EXTENSION.to_string(), value);
+    precert.signature = signer
+        .sign(&precert.signable_data())
+        .map_err(|e| ApiError::KeyStore(format!("sign certificate: {e}")))?;
+    Ok(precert)
+}
+
+/// Leaves have a fixed size, so the chunked Merkle helpers build the tree
+fn leaf_len() -> u32 {
+    transparency::leaf_data(&[], 0).len() as u32
+}
+
+fn inclusion_proof(leaves: &[Vec<u8>], index: u64) -> Vec<Hash> {
+    merkle::merkle_proof(&leaves.concat(), leaf_len(), index).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use aletheia::{ca::SigningKeyPair, merkle::Frontier, transparency};
+    use chrono::Utc;
+
+    use super::{CtLog, TreeHead};
+
+    fn head(log: &CtLog, frontier: &Frontier) -> TreeHead {
+        let root = frontier.root();
+        let size = frontier.size();
+        TreeHead {
+            tree_size: size as i64,
+            root_hash: root.to_vec(),
+            signature: log.signer.sign(&transparency::checkpoint_input(&log.log_id(), size, &root)).unwrap(),
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn entries_prove_inclusion_of_every_leaf() {
+        let log = CtLog::new(SigningKeyPair::generate());
+        let data: Vec<Vec<u8>> = (0..5u8).map(|i| vec![i; 40]).collect();
+        let leaves: Vec<Vec<u8>> = data
+            .iter()
+            .enumerate()
+            .map(|(i, data)| transparency::leaf_data(data, 1_700_000_000 + i as i64))
+            .collect();
+        let mut frontier = Frontier::new();
+        let mut appended = Vec::new();
+        for (i, leaf) in leaves.iter().enumerate() {
+            let proof = frontier.push(leaf);
+            let time = 1_700_000_000 + i as i64;
+            appended.push(log.entry_with_proof(i as u64, time, proof, &head(&log, &frontier)));
+        }
+        let head = head(&log, &frontier);
+
+        for (i, data) in data.iter().enumerate() {
+            let entry = log.entry(&leaves, i as u64, 1_700_000_000 + i as i64, &head);
+            transparency::verify_entry(&entry, data, &[log.public_key()]).unwrap();
+            assert!(transparency::verify_entry(&entry, b"other", &[log.public_key()]).is_err());
+
+            // As append proved it in the tree it grew
+            transparency::verify_entry(&appended[i], data, &[log.public_key()]).unwrap();
+        }
+    }
+}
@@ -12,6 +12,20 @@ pub enum ApiError {
     Invalid(String),
     #[error("not implemented")]
     NotImplemented,
+    #[error("key storage error: {0}")]
+    KeyStore(String),
+    #[error("mail delivery failed: {0}")]
+    Mail(String),
+    #[error("too many requests: {0}")]
+    RateLimited(String),
+    #[error("policy violation: {message}")]
+    PolicyViolation {
+        /// Policy field whose rule was broken
+        rule: &'static str,
+        /// `intermediate` or `global`, whichever policy applied
+        scope: &'static str,
+        message: String,
+    },
 }
 
 #[derive(Serialize)]
@@ -20,6 +34,14 @@ struct ErrorBody {
     message: String,
 }
 
+#[derive(Serialize)]
+struct PolicyViolationBody {
+    error: &'static str,
+    message: String,
+    rule: &'static str,
+    scope: &'static str,
+}
+
 impl ResponseError for ApiError {
     fn error_response(&self) -> HttpResponse {
         match self {
@@ -39,6 +61,24 @@ impl ResponseError for ApiError {
                 error: "not_implemented",
                 message: self.to_string(),
             }),
+            ApiError::KeyStore(_) => HttpResponse::ServiceUnavailable().json(ErrorBody {
+                error: "key_store",
+                message: self.to_string(),
+            }),
+            ApiError::Mail(_) => HttpResponse::ServiceUnavailable().json(ErrorBody {
+                error: "mail",
+                message: self.to_string(),
+            }),
+            ApiError::RateLimited(_) => HttpResponse::TooManyRequests().json(ErrorBody {
+                error: "rate_limited",
+                message: self.to_string(),
+            }),
+            ApiError::PolicyViolation { rule, scope, .. } => HttpResponse::Forbidden().json(PolicyViolationBody {
+                error: "policy_violation",
+                message: self.to_string(),
+                rule,
+                scope,
+            }),
         }
     }
 }
@@ -0,0 +1,106 @@
+use std::sync::Arc;
+
+use actix_web::web;
+use aletheia::kms::{AwsCredentials, AwsKmsClient, AwsKmsSigner};
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use super::{CaKey, KeyStore};
+use crate::error::ApiError;
+
+/// Keys in AWS KMS
+///
+/// Each CA has an `ECC_NIST_EDWARDS25519` signing key behind the alias
+/// `alias/aletheia-ca-<id>`; KMS signs every certificate, CRL and status
+/// assertion and the private key never leaves it.
+///
+/// Requests go through the core's blocking KMS client, on a blocking thread.
+/// Pure Ed25519 hashes the message inside the signature, so KMS only takes
+/// it whole, up to [`aletheia::kms::AWS_MAX_MESSAGE`] bytes: larger data,
+/// such as the CRL of a CA with more than about a hundred revocations,
+/// cannot be signed by a KMS key.
+#[derive(Clone)]
+pub struct AwsKmsKeyStore {
+    client: AwsKmsClient,
+}
+
+impl AwsKmsKeyStore {
+    /// Use KMS in `region`, at `endpoint` when given (e.g. a VPC endpoint)
+    ///
+    /// A blocking client cannot be created on the runtime, so this builds it
+    /// on a blocking thread.
+    pub async fn new(region: String, credentials: AwsCredentials, endpoint: Option<String>) -> Result<Self, String> {
+        let client = web::block(move || match endpoint {
+            Some(endpoint) => AwsKmsClient::new_at(&region, credentials, &endpoint),
+            None => AwsKmsClient::new(&region, credentials),
+        })
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| format!("AWS_KMS_ENDPOINT: {e}"))?;
+        Ok(Self { client })
+    }
+
+    /// Run `f` with the client on a blocking thread
+    async fn blocking<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(AwsKmsClient) -> aletheia::Result<T> + Send + 'static,
+    ) -> Result<T, ApiError> {
+        let client = self.client.clone();
+        web::block(move || f(client))
+            .await
+            .map_err(|e| ApiError::KeyStore(e.to_string()))?
+            .map_err(|e| ApiError::KeyStore(e.to_string()))
+    }
+}
+
+#[async_trait]
+impl KeyStore for AwsKmsKeyStore {
+    async fn generate(&self, id: Uuid) -> Result<CaKey, ApiError> {
+        let signer = self
+            .blocking(move |client| {
+                let key_id = client.create_key(&format!("Aletheia CA {id}"))?;
+                client.create_alias(&alias(id), &key_id)?;
+                AwsKmsSigner::open_with(client, &alias(id))
+            })
+            .await?;
+        Ok(CaKey::External(Arc::new(signer)))
+    }
+
+    async fn load(&self, id: Uuid) -> Result<CaKey, ApiError> {
+        let signer = self.blocking(move |client| AwsKmsSigner::open_with(client, &alias(id))).await?;
+        Ok(CaKey::External(Arc::new(signer)))
+    }
+}
+
+fn alias(id: Uuid) -> String {
+    format!("alias/aletheia-ca-{id}")
+}
+
+#[cfg(test)]
+mod tests {
+    use aletheia::kms::AwsCredentials;
+    use uuid::Uuid;
+
+    use super::AwsKmsKeyStore;
+    use crate::{error::ApiError, keys::KeyStore};
+
+    /// The blocking client runs off the runtime, so an unreachable KMS fails
+    /// the request rather than the worker
+    #[actix_rt::test]
+    async fn unreachable_kms_fails_the_load() {
+        let credentials = AwsCredentials {
+            access_key_id: "AKIDEXAMPLE".into(),
+            secret_access_key: "secret".into(),
+            session_token: None,
+        };
+        let store = AwsKmsKeyStore::new("us-east-1".into(), credentials, Some("http://127.0.0.1:1".into()))
+            .await
+            .unwrap();
+
+        match store.load(Uuid::new_v4()).await {
+            Err(ApiError::KeyStore(err)) => assert!(err.contains("KMS GetPublicKey:"), "{err}"),
+            Err(err) => panic!("unexpected error: {err}"),
+            Ok(_) => panic!("loaded a key from an unreachable KMS"),
+        }
+    }
+}
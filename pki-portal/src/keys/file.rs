@@ -0,0 +1,73 @@
+use std::{io::Write, path::PathBuf};
+
+use actix_web::web;
+use aletheia::ca::SigningKeyPair;
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use super::{encode_key, parse_key, CaKey, KeyStore};
+use crate::error::ApiError;
+
+/// Keys as PKCS#8 PEM files in a directory, one `<id>.key` per CA
+///
+/// The format the `aletheia` CLI writes, so keys made offline can be
+/// dropped in. New keys are encrypted when a passphrase is configured, and
+/// encrypted keys are opened with it.
+#[derive(Clone)]
+pub struct FileKeyStore {
+    dir: PathBuf,
+    passphrase: Option<String>,
+}
+
+impl FileKeyStore {
+    pub fn new(dir: impl Into<PathBuf>, passphrase: Option<String>) -> Self {
+        Self {
+            dir: dir.into(),
+            passphrase,
+        }
+    }
+
+    fn path(&self, id: Uuid) -> PathBuf {
+        self.dir.join(format!("{id}.key"))
+    }
+}
+
+#[async_trait]
+impl KeyStore for FileKeyStore {
+    async fn generate(&self, id: Uuid) -> Result<CaKey, ApiError> {
+        let path = self.path(id);
+        let passphrase = self.passphrase.clone();
+        // Encrypting the key is CPU-bound
+        let key = web::block(move || {
+            let key = SigningKeyPair::generate();
+            let pem = encode_key(&key, passphrase.as_deref())
+                .map_err(|e| ApiError::KeyStore(format!("encode key of CA {id}: {e}")))?;
+            std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+                .and_then(|mut file| file.write_all(pem.as_bytes()))
+                .map_err(|e| ApiError::KeyStore(format!("write {}: {e}", path.display())))?;
+            Ok::<_, ApiError>(key)
+        })
+        .await
+        .map_err(|e| ApiError::KeyStore(e.to_string()))??;
+        Ok(key.into())
+    }
+
+    async fn load(&self, id: Uuid) -> Result<CaKey, ApiError> {
+        let path = self.path(id);
+        let passphrase = self.passphrase.clone();
+        // Decrypting the key is CPU-bound
+        let key = web::block(move || {
+            let pem = std::fs::read_to_string(&path)
+                .map_err(|e| ApiError::KeyStore(format!("read {}: {e}", path.display())))?;
+            parse_key(&pem, passphrase.as_deref())
+                .map_err(|e| ApiError::KeyStore(format!("key of CA {id}: {e}")))
+        })
+        .await
+        .map_err(|e| ApiError::KeyStore(e.to_string()))??;
+        Ok(key.into())
+    }
+}
+
@@ -0,0 +1,145 @@
+use std::sync::Arc;
+
+use aletheia::{hsm::ExternalSigner, AletheiaError};
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD as b64, Engine};
+use serde_json::{json, Value};
+use tokio::runtime::Handle;
+use uuid::Uuid;
+
+use super::{CaKey, KeyStore};
+use crate::error::ApiError;
+
+/// Keys in a Hashicorp Vault transit engine
+///
+/// Each CA has an `ed25519` transit key named `aletheia-ca-<id>`; the
+/// private key never leaves Vault, which signs every certificate, CRL and
+/// status assertion.
+#[derive(Clone)]
+pub struct VaultKeyStore {
+    client: VaultClient,
+}
+
+impl VaultKeyStore {
+    /// Use the transit engine mounted at `mount` on the Vault server `addr`
+    pub fn new(addr: impl Into<String>, token: impl Into<String>, mount: impl Into<String>) -> Self {
+        Self {
+            client: VaultClient {
+                http: reqwest::Client::new(),
+                addr: addr.into().trim_end_matches('/').to_string(),
+                token: token.into(),
+                mount: mount.into(),
+            },
+        }
+    }
+
+    async fn signer(&self, id: Uuid) -> Result<CaKey, ApiError> {
+        let name = key_name(id);
+        let public_key = self.client.public_key(&name).await.map_err(ApiError::KeyStore)?;
+        Ok(CaKey::External(Arc::new(VaultSigner {
+            client: self.client.clone(),
+            name,
+            public_key,
+            runtime: Handle::current(),
+        })))
+    }
+}
+
+#[async_trait]
+impl KeyStore for VaultKeyStore {
+    async fn generate(&self, id: Uuid) -> Result<CaKey, ApiError> {
+        self.client
+            .call(reqwest::Method::POST, &format!("keys/{}", key_name(id)), Some(json!({ "type": "ed25519" })))
+            .await
+            .map_err(ApiError::KeyStore)?;
+        self.signer(id).await
+    }
+
+    async fn load(&self, id: Uuid) -> Result<CaKey, ApiError> {
+        self.signer(id).await
+    }
+}
+
+fn key_name(id: Uuid) -> String {
+    format!("aletheia-ca-{id}")
+}
+
+#[derive(Clone)]
+struct VaultClient {
+    http: reqwest::Client,
+    addr: String,
+    token: String,
+    mount: String,
+}
+
+impl VaultClient {
+    /// Call `path` under the transit mount, returning the response's `data`
+    async fn call(&self, method: reqwest::Method, path: &str, body: Option<Value>) -> Result<Value, String> {
+        let url = format!("{}/v1/{}/{path}", self.addr, self.mount);
+        let mut request = self.http.request(method, &url).header("X-Vault-Token", &self.token);
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+        let response = request.send().await.map_err(|e| format!("vault {path}: {e}"))?;
+        let status = response.status();
+        if status == reqwest::StatusCode::NO_CONTENT {
+            return Ok(Value::Null);
+        }
+        let body: Value = response.json().await.map_err(|e| format!("vault {path}: {e}"))?;
+        if !status.is_success() {
+            return Err(format!("vault {path}: {status}: {}", body["errors"]));
+        }
+        Ok(body["data"].clone())
+    }
+
+    /// Raw Ed25519 public key of the latest version of `name`
+    async fn public_key(&self, name: &str) -> Result<Vec<u8>, String> {
+        let data = self.call(reqwest::Method::GET, &format!("keys/{name}"), None).await?;
+        let version = data["latest_version"].as_u64().ok_or_else(|| format!("vault key {name} has no versions"))?;
+        let public_key = data["keys"][version.to_string()]["public_key"]
+            .as_str()
+            .ok_or_else(|| format!("vault key {name} is not an ed25519 key"))?;
+        b64.decode(public_key).map_err(|e| format!("public key of vault key {name}: {e}"))
+    }
+
+    async fn sign(&self, name: &str, message: &[u8]) -> Result<Vec<u8>, String> {
+        let data = self
+            .call(
+                reqwest::Method::POST,
+                &format!("sign/{name}"),
+                Some(json!({ "input": b64.encode(message) })),
+            )
+            .await?;
+        // Signatures come back as `vault:v<version>:<base64>`
+        let signature = data["signature"]
+            .as_str()
+            .and_then(|signature| signature.rsplit(':').next())
+            .ok_or_else(|| format!("vault sign/{name} returned no signature"))?;
+        b64.decode(signature).map_err(|e| format!("signature from vault key {name}: {e}"))
+    }
+}
+
+struct VaultSigner {
+    client: VaultClient,
+    name: String,
+    public_key: Vec<u8>,
+    runtime: Handle,
+}
+
+impl ExternalSigner for VaultSigner {
+    fn public_key(&self) -> Vec<u8> {
+        self.public_key.clone()
+    }
+
+    fn sign(&self, message: &[u8]) -> aletheia::Result<Vec<u8>> {
+        self.runtime
+            .block_on(self.client.sign(&self.name, message))
+            .map_err(AletheiaError::ExternalSigner)
+    }
+
+    fn sign_prehashed(&self, _message: &[u8], _context: &[u8]) -> aletheia::Result<Vec<u8>> {
+        Err(AletheiaError::ExternalSigner(
+            "Vault transit keys do not sign Ed25519ph".into(),
+        ))
+    }
+}
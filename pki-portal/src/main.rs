@@ -1,19 +1,34 @@
 mod api;
 mod config;
+mod ct;
 mod error;
+mod keys;
+mod mail;
 mod models;
 
 use actix_web::{middleware::Logger, App, HttpServer, web};
 use actix_cors::Cors;
 use config::Config;
+use ct::CtLog;
+use keys::{BundleSigner, KeyStore};
+use mail::{EmailProofing, LogMailer, Mailer, SmtpMailer};
 use sqlx::postgres::PgPoolOptions;
-use std::net::SocketAddr;
+use std::{net::SocketAddr, sync::Arc};
 use tracing_subscriber::EnvFilter;
 
 #[derive(Clone)]
 pub struct AppState {
     /// Shared Postgres connection pool.
     pub db: sqlx::PgPool,
+    /// Keys of roots and intermediates; CA operations are unavailable without it.
+    pub keys: Option<Arc<dyn KeyStore>>,
+    /// Signer of trust bundles; publishing is unavailable without it.
+    pub bundle_signer: Option<BundleSigner>,
+    /// Transparency log of issued certificates; certificates are issued
+    /// without log entries when it is not configured.
+    pub ct_log: Option<CtLog>,
+    /// Email challenges proving control of enrolling subjects' addresses.
+    pub proofing: EmailProofing,
 }
 
 #[actix_web::main]
@@ -29,10 +44,40 @@ async fn main() -> std::io::Result<()> {
         .await
         .expect("failed to connect to database");
 
+    let keys = keys::from_config(&cfg, &db_pool)
+        .await
+        .expect("invalid key store configuration");
+    let service_key = |id, name| {
+        let keys = keys.clone();
+        async move {
+            let keys = keys.unwrap_or_else(|| panic!("{name} requires a key store (CA_KEY_STORE)"));
+            keys::service_key(keys.as_ref(), id)
+                .await
+                .unwrap_or_else(|e| panic!("failed to load {name}: {e}"))
+        }
+    };
+    let bundle_signer = match cfg.bundle_signing_key_id {
+        Some(id) => Some(BundleSigner::new(service_key(id, "BUNDLE_SIGNING_KEY_ID").await)),
+        None => None,
+    };
+    let ct_log = match cfg.ct_log_key_id {
+        Some(id) => Some(CtLog::new(service_key(id, "CT_LOG_KEY_ID").await)),
+        None => None,
+    };
+    let mailer: Arc<dyn Mailer> = match &cfg.smtp_url {
+        Some(url) => Arc::new(SmtpMailer::new(url, &cfg.mail_from).expect("invalid mail configuration")),
+        None => Arc::new(LogMailer),
+    };
+    let proofing = EmailProofing::new(mailer, cfg.proofing_verifier);
+
     HttpServer::new(move || {
         App::new()
             .app_data(web::Data::new(AppState {
                 db: db_pool.clone(),
+                keys: keys.clone(),
+                bundle_signer: bundle_signer.clone(),
+                ct_log: ct_log.clone(),
+                proofing: proofing.clone(),
             }))
             .wrap(Logger::default())
             .wrap(
@@ -40,6 +85,7 @@ async fn main() -> std::io::Result<()> {
                     .allow_any_origin()
                     .allow_any_method()
                     .allow_any_header()
+                    .expose_any_header()
                     .max_age(3600),
             )
             .configure(api::configure)
@@ -31,10 +31,35 @@ pub struct Certificate {
     pub subject_name: String,
     pub is_ca: bool,
     pub public_key: Vec<u8>,
+    pub fingerprint: Option<String>,
+    /// The signed Aletheia certificate as base64 CBOR
+    pub certificate_b64: Option<String>,
     pub status: String,
     pub created_at: DateTime<Utc>,
 }
 
+/// A certificate request awaiting, or decided by, review
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct Enrollment {
+    pub id: Uuid,
+    /// Intermediate that issues the certificate once approved
+    pub issuer_id: Uuid,
+    pub subject_id: String,
+    pub subject_name: String,
+    pub public_key: Vec<u8>,
+    /// The signed request as submitted
+    pub request_pem: String,
+    pub status: String,
+    /// Why the request was rejected
+    pub reason: Option<String>,
+    /// Certificate issued on approval
+    pub certificate_serial: Option<String>,
+    /// When the subject proved control of the `subject_id` address
+    pub email_verified_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub decided_at: Option<DateTime<Utc>>,
+}
+
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 pub struct Revocation {
     pub serial: String,
@@ -57,6 +82,11 @@ pub struct TrustBundleMeta {
 pub struct Policy {
     pub subject_id_pattern: Option<String>,
     pub allow_ca_issue: bool,
+    /// Longest validity of issued certificates, in seconds
+    pub max_validity: Option<i64>,
+    /// Attestation methods issued certificates need one of; any method when
+    /// empty, no attestation when absent
+    pub allowed_attestation_methods: Option<Vec<String>>,
     pub updated_at: DateTime<Utc>,
 }
 
@@ -0,0 +1,164 @@
+//! Certificate bundles
+//!
+//! A bundle stores several certificates in one file: the chain a signer
+//! presents (leaf first, root last) or the set of roots a verifier trusts.
+//! The encoding is
+//!
+//! ```text
+//! BUNDLE_MAGIC (8 bytes) || major (1 byte) || minor (1 byte) || CBOR array of certificates
+//! ```
+//!
+//! The minor version is bumped for backward-compatible additions; readers
+//! reject bundles with an unknown major version.
+
+extern crate alloc;
+
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use crate::{AletheiaError, Certificate, Fingerprint, Result};
+
+/// Magic bytes at the start of a bundle
+pub const BUNDLE_MAGIC: &[u8; 8] = b"ALXCERTS";
+
+/// Bundle format major version
+pub const BUNDLE_VERSION_MAJOR: u8 = 1;
+
+/// Bundle format minor version
+pub const BUNDLE_VERSION_MINOR: u8 = 0;
+
+/// Several certificates stored together
+#[derive(Debug, Clone, Default)]
+pub struct CertificateBundle {
+    /// The certificates, in the order they were added
+    pub certificates: Vec<Certificate>,
+}
+
+impl CertificateBundle {
+    /// Create an empty bundle
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a certificate to the end of the bundle
+    pub fn with_certificate(mut self, certificate: Certificate) -> Self {
+        self.certificates.push(certificate);
+        self
+    }
+
+    /// Public keys of all certificates, for use as trusted root keys
+    pub fn public_keys(&self) -> Vec<Vec<u8>> {
+        self.certificates
+            .iter()
+            .map(|cert| cert.public_key.clone())
+            .collect()
+    }
+
+    /// Find a certificate by fingerprint
+    pub fn find(&self, fingerprint: &Fingerprint) -> Option<&Certificate> {
+        self.certificates
+            .iter()
+            .find(|cert| cert.fingerprint() == *fingerprint)
+    }
+
+    /// Encode the bundle
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut data = Vec::new();
+        data.extend_from_slice(BUNDLE_MAGIC);
+        data.push(BUNDLE_VERSION_MAJOR);
+        data.push(BUNDLE_VERSION_MINOR);
+        ciborium::into_writer(&self.certificates, &mut data)
+            .map_err(|e| AletheiaError::CborEncode(e.to_string()))?;
+        Ok(data)
+    }
+
+    /// Decode a bundle written by [`Self::to_bytes`]
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        if !Self::is_bundle(data) {
+            return Err(AletheiaError::InvalidCertificate(
+                "Not a certificate bundle".into(),
+            ));
+        }
+        let (major, minor) = (data[8], data[9]);
+        if major != BUNDLE_VERSION_MAJOR {
+            return Err(AletheiaError::UnsupportedVersion { major, minor });
+        }
+        let certificates = ciborium::from_reader(&data[10..])
+            .map_err(|e| AletheiaError::CborDecode(e.to_string()))?;
+        Ok(Self { certificates })
+    }
+
+    /// Whether `data` starts like a bundle
+    pub fn is_bundle(data: &[u8]) -> bool {
+        data.len() >= 10 && data.starts_with(BUNDLE_MAGIC)
+    }
+
+    /// Write the bundle to a path
+    #[cfg(feature = "std")]
+    pub fn write_to_file(&self, path: impl AsRef<std::path::Path>) -> Result<()> {
+        std::fs::write(path, self.to_bytes()?)?;
+        Ok(())
+    }
+
+    /// Read a bundle from a path
+    #[cfg(feature = "std")]
+    pub fn read_from_file(path: impl AsRef<std::path::Path>) -> Result<Self> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+}
+
+impl From<Vec<Certificate>> for CertificateBundle {
+    fn from(certificates: Vec<Certificate>) -> Self {
+        Self { certificates }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ca::{CertificateAuthority, SigningKeyPair};
+
+    #[test]
+    fn test_bundle_round_trip() {
+        let timestamp = 1704067200;
+        let ca =
+            CertificateAuthority::new_root_with_timestamp("root@example.com", "Root CA", timestamp);
+        let user_keys = SigningKeyPair::generate();
+        let user_cert = ca
+            .issue_certificate_with_timestamp(
+                "alice@example.com",
+                "Alice",
+                &user_keys.public_key(),
+                false,
+                timestamp,
+            )
+            .unwrap();
+        let bundle = CertificateBundle::new()
+            .with_certificate(user_cert.clone())
+            .with_certificate(ca.certificate.clone());
+
+        let data = bundle.to_bytes().unwrap();
+        assert!(CertificateBundle::is_bundle(&data));
+        let decoded = CertificateBundle::from_bytes(&data).unwrap();
+        assert_eq!(decoded.certificates.len(), 2);
+        assert_eq!(
+            decoded.public_keys(),
+            [user_keys.public_key(), ca.public_key()]
+        );
+        assert_eq!(
+            decoded
+                .find(&ca.certificate.fingerprint())
+                .unwrap()
+                .subject_id,
+            "root@example.com"
+        );
+
+        let mut future = data.clone();
+        future[8] = BUNDLE_VERSION_MAJOR + 1;
+        assert!(matches!(
+            CertificateBundle::from_bytes(&future),
+            Err(AletheiaError::UnsupportedVersion { .. })
+        ));
+        assert!(CertificateBundle::from_bytes(&data[1..]).is_err());
+    }
+}
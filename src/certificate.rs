@@ -1,6 +1,20 @@
+extern crate alloc;
+
 use crate::{AletheiaError, Certificate, Result};
+use alloc::collections::BTreeSet;
+use alloc::format;
+use alloc::vec;
+use alloc::vec::Vec;
 use ed25519_dalek::{Signature, Verifier, VerifyingKey};
 
+/// Longest certificate chain (signer, intermediates and root) accepted by
+/// default
+pub const DEFAULT_MAX_CHAIN_LEN: usize = 16;
+
+/// Partial paths extended while searching a chain for an alternate path,
+/// before giving up
+const MAX_PATH_EXPANSIONS: usize = 256;
+
 /// Verify that a certificate was properly signed by its issuer
 pub fn verify_certificate_signature(cert: &Certificate, issuer_public_key: &[u8]) -> Result<()> {
     let verifying_key = VerifyingKey::try_from(issuer_public_key).map_err(|e| {
@@ -14,10 +28,123 @@ pub fn verify_certificate_signature(cert: &Certificate, issuer_public_key: &[u8]
     let signable = cert.signable_data();
     verifying_key
         .verify(&signable, &signature)
-        .map_err(|_| AletheiaError::InvalidCertificate("Signature verification failed".into()))
+        .map_err(|_| AletheiaError::CertificateSignatureInvalid(cert.subject_id.clone()))
+}
+
+/// Which signatures verification requires on certificates and files
+///
+/// Certificates and files may carry an ML-DSA-65 signature next to their
+/// Ed25519 one (see the `ml-dsa` feature). Requiring ML-DSA fails on
+/// anything that lacks it, so stripping the post-quantum signatures cannot
+/// downgrade a file to Ed25519. Roots are trusted by their Ed25519 public
+/// key in every case.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SignaturePolicy {
+    /// Check the Ed25519 signatures only
+    #[default]
+    Ed25519,
+    /// Check the ML-DSA signatures only
+    MlDsa,
+    /// Check both signatures, failing unless both are present and valid
+    Both,
+}
+
+impl SignaturePolicy {
+    /// Whether Ed25519 signatures are checked
+    pub fn requires_ed25519(self) -> bool {
+        matches!(self, Self::Ed25519 | Self::Both)
+    }
+
+    /// Whether ML-DSA signatures are required and checked
+    pub fn requires_ml_dsa(self) -> bool {
+        matches!(self, Self::MlDsa | Self::Both)
+    }
+}
+
+/// Verify that a certificate was signed by `issuer` as `policy` requires
+pub fn verify_certificate_signatures(
+    cert: &Certificate,
+    issuer: &Certificate,
+    policy: SignaturePolicy,
+) -> Result<()> {
+    if policy.requires_ml_dsa() {
+        let (Some(public_key), Some(signature)) = (&issuer.pq_public_key, &cert.pq_signature)
+        else {
+            return Err(AletheiaError::SignaturePolicy(format!(
+                "Certificate '{}' has no ML-DSA signature by '{}'",
+                cert.subject_id, issuer.subject_id
+            )));
+        };
+        verify_ml_dsa(public_key, &cert.signable_data(), signature).map_err(|e| match e {
+            AletheiaError::InvalidSignature => {
+                AletheiaError::CertificateSignatureInvalid(cert.subject_id.clone())
+            }
+            e => e,
+        })?;
+    }
+    if policy.requires_ed25519() {
+        verify_certificate_signature(cert, &issuer.public_key)?;
+    }
+    Ok(())
+}
+
+/// Verify an ML-DSA-65 signature, if support for it is compiled in
+pub(crate) fn verify_ml_dsa(public_key: &[u8], message: &[u8], signature: &[u8]) -> Result<()> {
+    #[cfg(feature = "ml-dsa")]
+    {
+        crate::pq::verify(public_key, message, signature)
+    }
+    #[cfg(not(feature = "ml-dsa"))]
+    {
+        let _ = (public_key, message, signature);
+        Err(AletheiaError::SignaturePolicy(
+            "ML-DSA signatures need the ml-dsa feature".into(),
+        ))
+    }
 }
 
-/// Verify a complete certificate chain
+/// How certificate validity periods are checked during chain verification
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidityCheck {
+    /// Every certificate must be valid at this Unix timestamp
+    At(i64),
+    /// Ignore validity periods (archival verification)
+    Skip,
+}
+
+impl ValidityCheck {
+    /// Check validity at the current time
+    ///
+    /// Without the `std` feature there is no clock, so this skips the check;
+    /// pass [`ValidityCheck::At`] explicitly in no_std and WASM environments.
+    pub fn now() -> Self {
+        #[cfg(feature = "std")]
+        {
+            Self::At(chrono::Utc::now().timestamp())
+        }
+        #[cfg(not(feature = "std"))]
+        {
+            Self::Skip
+        }
+    }
+}
+
+/// Check a certificate's validity period
+pub(crate) fn check_validity(cert: &Certificate, validity: ValidityCheck) -> Result<()> {
+    if let ValidityCheck::At(at) = validity {
+        if cert.not_before.is_some_and(|nb| at < nb) {
+            return Err(AletheiaError::CertificateNotYetValid(
+                cert.subject_id.clone(),
+            ));
+        }
+        if cert.not_after.is_some_and(|na| at > na) {
+            return Err(AletheiaError::CertificateExpired(cert.subject_id.clone()));
+        }
+    }
+    Ok(())
+}
+
+/// Verify a complete certificate chain, checking validity periods at the current time
 ///
 /// The chain should be ordered: [creator_cert, ..., root_cert]
 /// Each certificate is verified against the next one in the chain.
@@ -26,68 +153,391 @@ pub fn verify_certificate_chain(
     chain: &[Certificate],
     trusted_root_keys: &[Vec<u8>],
 ) -> Result<()> {
+    verify_certificate_chain_at(chain, trusted_root_keys, ValidityCheck::now())
+}
+
+/// Verify a complete certificate chain with an explicit validity check
+///
+/// Chains longer than [`DEFAULT_MAX_CHAIN_LEN`] are rejected.
+pub fn verify_certificate_chain_at(
+    chain: &[Certificate],
+    trusted_root_keys: &[Vec<u8>],
+    validity: ValidityCheck,
+) -> Result<()> {
+    verify_certificate_chain_with_max_len(chain, trusted_root_keys, validity, DEFAULT_MAX_CHAIN_LEN)
+}
+
+/// Verify a complete certificate chain of at most `max_len` certificates
+///
+/// See [`find_trusted_path`].
+pub fn verify_certificate_chain_with_max_len(
+    chain: &[Certificate],
+    trusted_root_keys: &[Vec<u8>],
+    validity: ValidityCheck,
+    max_len: usize,
+) -> Result<()> {
+    find_trusted_path(chain, trusted_root_keys, validity, max_len).map(|_| ())
+}
+
+/// Find a path from the signer (the first certificate) to a trusted root
+///
+/// The chain is normally ordered [signer, ..., root] and verified as it
+/// stands. If that order does not verify, the remaining certificates are
+/// searched for another path, so a chain can carry intermediates
+/// cross-signed by an old and a new root during a CA migration and verify
+/// against either. If no path verifies, the error of the chain as ordered is
+/// returned.
+///
+/// Before any signature is checked, the chain is rejected if it is longer
+/// than `max_len`, holds the same certificate twice, or repeats a serial of
+/// the same issuer.
+pub fn find_trusted_path<'a>(
+    chain: &'a [Certificate],
+    trusted_root_keys: &[Vec<u8>],
+    validity: ValidityCheck,
+    max_len: usize,
+) -> Result<Vec<&'a Certificate>> {
+    find_trusted_path_with_policy(
+        chain,
+        trusted_root_keys,
+        validity,
+        max_len,
+        SignaturePolicy::Ed25519,
+    )
+}
+
+/// Find a path to a trusted root whose certificate signatures meet `policy`
+///
+/// See [`find_trusted_path`], which checks the Ed25519 signatures only.
+pub fn find_trusted_path_with_policy<'a>(
+    chain: &'a [Certificate],
+    trusted_root_keys: &[Vec<u8>],
+    validity: ValidityCheck,
+    max_len: usize,
+    policy: SignaturePolicy,
+) -> Result<Vec<&'a Certificate>> {
     if chain.is_empty() {
-        return Err(AletheiaError::CertificateChainInvalid(
-            "Empty certificate chain".into(),
-        ));
+        return Err(AletheiaError::CertificateChainInvalid {
+            step: 0,
+            reason: "Empty certificate chain".into(),
+        });
+    }
+    if chain.len() > max_len {
+        return Err(AletheiaError::ChainTooLong {
+            len: chain.len(),
+            max: max_len,
+        });
     }
+    check_distinct(chain)?;
+
+    let ordered: Vec<&Certificate> = chain.iter().collect();
+    let Err(error) = verify_path(&ordered, trusted_root_keys, validity, policy) else {
+        return Ok(ordered);
+    };
+
+    // Search for other paths, following issuer IDs from the signer to a
+    // self-signed certificate. Only issuers whose key verifies the
+    // certificate's signature are followed, and every extended path counts
+    // against the budget, so dead ends cannot make the search blow up.
+    let issuers: Vec<Vec<usize>> = chain
+        .iter()
+        .map(|cert| {
+            chain
+                .iter()
+                .enumerate()
+                .filter(|(_, issuer)| {
+                    issuer.is_ca
+                        && issuer.subject_id == cert.issuer_id
+                        && verify_certificate_signatures(cert, issuer, policy).is_ok()
+                })
+                .map(|(j, _)| j)
+                .collect()
+        })
+        .collect();
+    let mut expansions = 0;
+    let mut pending: Vec<Vec<usize>> = vec![vec![0]];
+    while let Some(indices) = pending.pop() {
+        expansions += 1;
+        if expansions > MAX_PATH_EXPANSIONS {
+            break;
+        }
+        let last_index = indices[indices.len() - 1];
+        let last = &chain[last_index];
+        if last.issuer_id == last.subject_id {
+            let is_ordered = indices.iter().enumerate().all(|(i, &j)| i == j);
+            if is_ordered && indices.len() == chain.len() {
+                continue;
+            }
+            let path: Vec<&Certificate> = indices.iter().map(|&j| &chain[j]).collect();
+            if verify_path(&path, trusted_root_keys, validity, policy).is_ok() {
+                return Ok(path);
+            }
+            continue;
+        }
+        for &j in &issuers[last_index] {
+            if !indices.contains(&j) {
+                let mut next = indices.clone();
+                next.push(j);
+                pending.push(next);
+            }
+        }
+    }
+
+    Err(error)
+}
+
+/// Check each certificate of a chain, as ordered, against the next one
+///
+/// Returns one result per certificate, signer first: its validity period,
+/// its issuer link and its signature by the next certificate, or for the last
+/// one that it is a trusted, self-signed CA. Unlike [`find_trusted_path`],
+/// a failing certificate does not stop the checks, so a viewer can point at
+/// each certificate that breaks the chain. Whether the chain is accepted is
+/// still for [`find_trusted_path`] to decide: it may find another path
+/// through a chain that does not verify as ordered.
+pub fn check_certificate_chain(
+    chain: &[Certificate],
+    trusted_root_keys: &[Vec<u8>],
+    validity: ValidityCheck,
+) -> Vec<Result<()>> {
+    let path: Vec<&Certificate> = chain.iter().collect();
+    (0..path.len())
+        .map(|i| {
+            verify_step(
+                &path,
+                i,
+                trusted_root_keys,
+                validity,
+                SignaturePolicy::Ed25519,
+            )
+        })
+        .collect()
+}
+
+/// Verify a path ordered [signer, ..., root]
+fn verify_path(
+    path: &[&Certificate],
+    trusted_root_keys: &[Vec<u8>],
+    validity: ValidityCheck,
+    policy: SignaturePolicy,
+) -> Result<()> {
+    let mut keys = BTreeSet::new();
 
     // Verify each certificate in the chain
-    for i in 0..chain.len() {
-        let cert = &chain[i];
+    for (i, cert) in path.iter().enumerate() {
+        // A subject's key may only appear once on a path
+        if !keys.insert((cert.subject_id.as_str(), cert.public_key.as_slice())) {
+            return Err(AletheiaError::CertificateChainInvalid {
+                step: i,
+                reason: format!("Certificate chain loops back to '{}'", cert.subject_id),
+            });
+        }
+
+        verify_step(path, i, trusted_root_keys, validity, policy).map_err(|e| e.at_step(i))?;
+    }
+
+    Ok(())
+}
+
+/// Verify the certificate at `i` on a path against its issuer, the next one
+fn verify_step(
+    path: &[&Certificate],
+    i: usize,
+    trusted_root_keys: &[Vec<u8>],
+    validity: ValidityCheck,
+    policy: SignaturePolicy,
+) -> Result<()> {
+    let cert = path[i];
+
+    // Check the validity period
+    check_validity(cert, validity)?;
 
-        // Get the issuer's public key
-        let issuer_key = if i + 1 < chain.len() {
-            // Issuer is the next certificate in the chain
-            let issuer = &chain[i + 1];
+    // Get the issuer's certificate
+    let issuer = if i + 1 < path.len() {
+        // Issuer is the next certificate in the chain
+        let issuer = path[i + 1];
 
-            // Verify the issuer is allowed to issue certificates
-            if !issuer.is_ca {
-                return Err(AletheiaError::CertificateChainInvalid(format!(
+        // Verify the issuer is allowed to issue certificates
+        if !issuer.is_ca {
+            return Err(AletheiaError::CertificateChainInvalid {
+                step: i,
+                reason: format!(
                     "Certificate '{}' is not a CA but issued '{}'",
                     issuer.subject_id, cert.subject_id
-                )));
-            }
+                ),
+            });
+        }
 
-            // Verify issuer ID matches
-            if cert.issuer_id != issuer.subject_id {
-                return Err(AletheiaError::CertificateChainInvalid(format!(
+        // A single-use key signs one file, never certificates
+        if issuer.single_use {
+            return Err(AletheiaError::CertificateChainInvalid {
+                step: i,
+                reason: format!(
+                    "Single-use certificate '{}' issued '{}'",
+                    issuer.subject_id, cert.subject_id
+                ),
+            });
+        }
+
+        // Verify the issuer's path length constraint: the `i` certificates
+        // between the leaf and the issuer are all intermediate CAs
+        if issuer
+            .path_len
+            .is_some_and(|path_len| i > path_len as usize)
+        {
+            return Err(AletheiaError::CertificateChainInvalid {
+                step: i,
+                reason: format!("Path length constraint of '{}' exceeded", issuer.subject_id),
+            });
+        }
+
+        // Verify issuer ID matches
+        if cert.issuer_id != issuer.subject_id {
+            return Err(AletheiaError::CertificateChainInvalid {
+                step: i,
+                reason: format!(
                     "Issuer ID mismatch: cert says '{}', chain has '{}'",
                     cert.issuer_id, issuer.subject_id
-                )));
-            }
+                ),
+            });
+        }
 
-            &issuer.public_key
-        } else {
-            // This is the root certificate - must be self-signed
-            if cert.issuer_id != cert.subject_id {
-                return Err(AletheiaError::CertificateChainInvalid(
-                    "Root certificate is not self-signed".into(),
-                ));
-            }
+        issuer
+    } else {
+        // This is the root certificate - must be self-signed
+        if cert.issuer_id != cert.subject_id {
+            return Err(AletheiaError::CertificateChainInvalid {
+                step: i,
+                reason: "Root certificate is not self-signed".into(),
+            });
+        }
 
-            // Root must be a CA
-            if !cert.is_ca {
-                return Err(AletheiaError::CertificateChainInvalid(
-                    "Root certificate is not marked as CA".into(),
-                ));
-            }
+        // Root must be a CA
+        if !cert.is_ca {
+            return Err(AletheiaError::CertificateChainInvalid {
+                step: i,
+                reason: "Root certificate is not marked as CA".into(),
+            });
+        }
 
-            // Verify root is trusted
-            if !trusted_root_keys.contains(&cert.public_key) {
-                return Err(AletheiaError::UntrustedRoot);
-            }
+        // Verify root is trusted
+        if !crate::ct::contains(trusted_root_keys, &cert.public_key) {
+            return Err(AletheiaError::UntrustedRoot);
+        }
 
-            &cert.public_key
-        };
+        cert
+    };
 
-        // Verify this certificate's signature
-        verify_certificate_signature(cert, issuer_key)?;
-    }
+    // Verify this certificate's signatures
+    verify_certificate_signatures(cert, issuer, policy)
+}
 
+/// Reject chains that repeat a certificate or an issuer's serial
+fn check_distinct(chain: &[Certificate]) -> Result<()> {
+    let mut fingerprints = BTreeSet::new();
+    let mut serials = BTreeSet::new();
+    for (i, cert) in chain.iter().enumerate() {
+        if !fingerprints.insert(cert.fingerprint()) {
+            return Err(AletheiaError::CertificateChainInvalid {
+                step: i,
+                reason: format!("Certificate '{}' appears more than once", cert.subject_id),
+            });
+        }
+        if !serials.insert((cert.issuer_id.as_str(), cert.serial.as_slice())) {
+            return Err(AletheiaError::CertificateChainInvalid {
+                step: i,
+                reason: format!("Duplicate serial in chain at '{}'", cert.subject_id),
+            });
+        }
+    }
     Ok(())
 }
 
+/// Assembles a certificate chain from a pool of CA certificates
+///
+/// Starting from the leaf, each step looks in the pool for a CA whose subject
+/// is the current certificate's issuer and whose key verifies its signature,
+/// until a self-signed root is reached. The pool may hold unrelated
+/// certificates and be in any order.
+///
+/// ```rust
+/// use aletheia::{ca::{CertificateAuthority, SigningKeyPair}, certificate::ChainBuilder};
+///
+/// let ca = CertificateAuthority::new_root("root@example.com", "Root CA");
+/// let keys = SigningKeyPair::generate();
+/// let leaf = ca.issue_certificate("alice@example.com", "Alice", &keys.public_key(), false).unwrap();
+///
+/// let chain = ChainBuilder::new()
+///     .with_certificate(ca.certificate.clone())
+///     .build(leaf)
+///     .unwrap();
+/// assert_eq!(chain.len(), 2);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ChainBuilder {
+    pool: Vec<Certificate>,
+}
+
+impl ChainBuilder {
+    /// Create a builder with an empty pool
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a CA certificate to the pool
+    pub fn with_certificate(mut self, certificate: Certificate) -> Self {
+        self.pool.push(certificate);
+        self
+    }
+
+    /// Add several CA certificates to the pool
+    pub fn with_certificates(
+        mut self,
+        certificates: impl IntoIterator<Item = Certificate>,
+    ) -> Self {
+        self.pool.extend(certificates);
+        self
+    }
+
+    /// Build `[leaf, intermediates..., root]`
+    ///
+    /// Fails with [`AletheiaError::CertificateNotFound`] naming the missing
+    /// issuer if the pool has a gap. Validity periods, path lengths and trust
+    /// in the root are not checked here; the verifier does that.
+    pub fn build(&self, leaf: Certificate) -> Result<Vec<Certificate>> {
+        let mut chain = vec![leaf];
+        loop {
+            let current = &chain[chain.len() - 1];
+            if is_self_signed(current) {
+                return Ok(chain);
+            }
+            // Each certificate can appear once, so a cycle ends the search
+            if chain.len() > self.pool.len() {
+                return Err(AletheiaError::CertificateChainInvalid {
+                    step: chain.len() - 1,
+                    reason: "Certificate chain contains a cycle".into(),
+                });
+            }
+            let issuer = self
+                .pool
+                .iter()
+                .find(|candidate| {
+                    candidate.is_ca
+                        && candidate.subject_id == current.issuer_id
+                        && verify_certificate_signature(current, &candidate.public_key).is_ok()
+                })
+                .ok_or_else(|| AletheiaError::CertificateNotFound(current.issuer_id.clone()))?;
+            chain.push(issuer.clone());
+        }
+    }
+}
+
+fn is_self_signed(cert: &Certificate) -> bool {
+    cert.is_ca
+        && cert.issuer_id == cert.subject_id
+        && verify_certificate_signature(cert, &cert.public_key).is_ok()
+}
+
 /// Generate a unique serial number for a certificate
 pub fn generate_serial() -> Vec<u8> {
     extern crate alloc;
@@ -99,6 +549,111 @@ pub fn generate_serial() -> Vec<u8> {
     serial
 }
 
+/// How a CA numbers the certificates it issues
+#[derive(Debug, Clone, Default)]
+pub enum SerialPolicy {
+    /// 16 random bytes ([`generate_serial`])
+    #[default]
+    Random,
+    /// An 8-byte big-endian counter persisted in a state file, so serials are
+    /// consecutive and never reused
+    #[cfg(feature = "std")]
+    Counter(std::sync::Arc<SerialCounter>),
+    /// A UUIDv7 (RFC 9562): millisecond timestamp then random bits, so
+    /// serials sort by issuance time
+    UuidV7,
+}
+
+impl SerialPolicy {
+    /// Count serials in the state file at `path`, creating it if missing
+    #[cfg(feature = "std")]
+    pub fn counter(path: impl Into<std::path::PathBuf>) -> Result<Self> {
+        Ok(Self::Counter(std::sync::Arc::new(SerialCounter::open(
+            path,
+        )?)))
+    }
+
+    /// Produce the serial for a certificate issued at `issued_at`
+    ///
+    /// UUIDv7 serials take the time from the system clock with the `std`
+    /// feature, and from `issued_at` (at second precision) without it.
+    pub fn next_serial(&self, issued_at: i64) -> Result<Vec<u8>> {
+        match self {
+            Self::Random => Ok(generate_serial()),
+            #[cfg(feature = "std")]
+            Self::Counter(counter) => Ok(counter.next()?.to_be_bytes().to_vec()),
+            Self::UuidV7 => {
+                #[cfg(feature = "std")]
+                let millis = chrono::Utc::now().timestamp_millis();
+                #[cfg(not(feature = "std"))]
+                let millis = issued_at.saturating_mul(1000);
+                #[cfg(feature = "std")]
+                let _ = issued_at;
+                Ok(uuid_v7(millis as u64).to_vec())
+            }
+        }
+    }
+}
+
+/// Monotonic serial counter backed by a state file
+///
+/// The file holds the last serial issued, in decimal. Each serial is written
+/// back before it is handed out, so a crash never leads to reuse. The counter
+/// is safe to share between threads, but not between processes.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct SerialCounter {
+    path: std::path::PathBuf,
+    last: std::sync::Mutex<u64>,
+}
+
+#[cfg(feature = "std")]
+impl SerialCounter {
+    /// Open the counter at `path`, starting from 0 if the file does not exist
+    pub fn open(path: impl Into<std::path::PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let last = match std::fs::read_to_string(&path) {
+            Ok(content) => content.trim().parse().map_err(|_| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("Invalid serial counter file: {}", path.display()),
+                )
+            })?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => 0,
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self {
+            path,
+            last: std::sync::Mutex::new(last),
+        })
+    }
+
+    /// Reserve and persist the next serial
+    pub fn next(&self) -> Result<u64> {
+        let mut last = self.last.lock().unwrap_or_else(|e| e.into_inner());
+        let next = *last + 1;
+
+        // Replace the file atomically so a crash leaves the old or new value
+        let tmp = self.path.with_extension("tmp");
+        std::fs::write(&tmp, next.to_string())?;
+        std::fs::rename(&tmp, &self.path)?;
+
+        *last = next;
+        Ok(next)
+    }
+}
+
+/// A version 7 UUID for the given Unix time in milliseconds
+fn uuid_v7(unix_millis: u64) -> [u8; 16] {
+    use rand::{Rng, rngs::OsRng};
+    let mut uuid = [0u8; 16];
+    OsRng.fill(&mut uuid[6..]);
+    uuid[..6].copy_from_slice(&unix_millis.to_be_bytes()[2..]);
+    uuid[6] = 0x70 | (uuid[6] & 0x0F); // version 7
+    uuid[8] = 0x80 | (uuid[8] & 0x3F); // RFC 9562 variant
+    uuid
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -110,4 +665,312 @@ mod tests {
         assert_eq!(s1.len(), 16);
         assert_ne!(s1, s2);
     }
+
+    #[test]
+    fn test_serial_policies() {
+        let uuid = SerialPolicy::UuidV7.next_serial(1704067200).unwrap();
+        assert_eq!(uuid.len(), 16);
+        assert_eq!(uuid[6] >> 4, 7);
+        assert_eq!(uuid[8] >> 6, 0b10);
+        assert!(uuid_v7(1) < uuid_v7(2));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_serial_counter_persists() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ca.serial");
+        let policy = SerialPolicy::counter(&path).unwrap();
+        assert_eq!(policy.next_serial(0).unwrap(), 1u64.to_be_bytes());
+        assert_eq!(policy.next_serial(0).unwrap(), 2u64.to_be_bytes());
+
+        // The count survives a restart
+        let reopened = SerialPolicy::counter(&path).unwrap();
+        assert_eq!(reopened.next_serial(0).unwrap(), 3u64.to_be_bytes());
+
+        std::fs::write(&path, "not a number").unwrap();
+        assert!(SerialPolicy::counter(&path).is_err());
+    }
+
+    #[test]
+    fn test_chain_loops_and_length() {
+        use crate::ca::{CertificateAuthority, SigningKeyPair};
+
+        let timestamp = 1704067200;
+        let root =
+            CertificateAuthority::new_root_with_timestamp("root@example.com", "Root CA", timestamp);
+        let leaf = root
+            .issue_certificate_with_timestamp(
+                "alice@example.com",
+                "Alice",
+                &SigningKeyPair::generate().public_key(),
+                false,
+                timestamp,
+            )
+            .unwrap();
+        let roots = [root.public_key()];
+        let verify = |chain: &[Certificate], max_len| {
+            verify_certificate_chain_with_max_len(chain, &roots, ValidityCheck::Skip, max_len)
+        };
+
+        verify(&[leaf.clone(), root.certificate.clone()], 2).unwrap();
+        assert!(matches!(
+            verify(&[leaf.clone(), root.certificate.clone()], 1),
+            Err(AletheiaError::ChainTooLong { len: 2, max: 1 })
+        ));
+        let long = vec![root.certificate.clone(); DEFAULT_MAX_CHAIN_LEN + 1];
+        assert!(matches!(
+            verify_certificate_chain(&long, &roots),
+            Err(AletheiaError::ChainTooLong { .. })
+        ));
+
+        // A repeated certificate is a loop
+        let looped = [
+            leaf.clone(),
+            root.certificate.clone(),
+            root.certificate.clone(),
+        ];
+        assert!(matches!(
+            verify(&looped, 16),
+            Err(AletheiaError::CertificateChainInvalid { step: 2, .. })
+        ));
+
+        // So is a reused serial under another key
+        let mut copy = leaf.clone();
+        copy.public_key = SigningKeyPair::generate().public_key();
+        let result = verify(&[leaf, copy, root.certificate.clone()], 16);
+        let Err(AletheiaError::CertificateChainInvalid { step: 1, reason }) = result else {
+            panic!("expected a duplicate serial error");
+        };
+        assert!(reason.contains("Duplicate serial"));
+    }
+
+    #[test]
+    fn test_check_certificate_chain() {
+        use crate::ca::{CertificateAuthority, IssueOptions, SigningKeyPair};
+
+        let timestamp = 1704067200;
+        let root =
+            CertificateAuthority::new_root_with_timestamp("root@example.com", "Root CA", timestamp);
+        let leaf = root
+            .issue_certificate_with_options(
+                "alice@example.com",
+                "Alice",
+                &SigningKeyPair::generate().public_key(),
+                false,
+                timestamp,
+                &IssueOptions::new().with_validity(60),
+            )
+            .unwrap();
+        let chain = [leaf, root.certificate.clone()];
+
+        let checks = check_certificate_chain(&chain, &[root.public_key()], ValidityCheck::Skip);
+        assert!(checks.iter().all(Result::is_ok));
+
+        // Each failure is reported on its own certificate
+        let other = CertificateAuthority::new_root_with_timestamp("x@example.com", "X", timestamp);
+        let checks = check_certificate_chain(
+            &chain,
+            &[other.public_key()],
+            ValidityCheck::At(timestamp + 3600),
+        );
+        assert!(matches!(
+            checks[0],
+            Err(AletheiaError::CertificateExpired(_))
+        ));
+        assert!(matches!(checks[1], Err(AletheiaError::UntrustedRoot)));
+    }
+
+    #[test]
+    fn test_cross_signed_paths() {
+        use crate::ca::{CertificateAuthority, SigningKeyPair};
+
+        let timestamp = 1704067200;
+        let old_root =
+            CertificateAuthority::new_root_with_timestamp("old@example.com", "Old Root", timestamp);
+        let new_root =
+            CertificateAuthority::new_root_with_timestamp("new@example.com", "New Root", timestamp);
+        let intermediate_keys = SigningKeyPair::generate();
+        let [old_cross, new_cross] = [&old_root, &new_root].map(|root| {
+            root.issue_certificate_with_timestamp(
+                "intermediate@example.com",
+                "Intermediate CA",
+                &intermediate_keys.public_key(),
+                true,
+                timestamp,
+            )
+            .unwrap()
+        });
+        let intermediate = CertificateAuthority::from_key_and_cert(
+            &intermediate_keys.private_key_bytes(),
+            old_cross.clone(),
+        )
+        .unwrap();
+        let leaf = intermediate
+            .issue_certificate_with_timestamp(
+                "alice@example.com",
+                "Alice",
+                &SigningKeyPair::generate().public_key(),
+                false,
+                timestamp,
+            )
+            .unwrap();
+        let chain = [
+            leaf,
+            old_cross,
+            old_root.certificate.clone(),
+            new_cross.clone(),
+            new_root.certificate.clone(),
+        ];
+
+        // Either root alone is enough
+        for root in [&old_root, &new_root] {
+            let path = find_trusted_path(&chain, &[root.public_key()], ValidityCheck::Skip, 16);
+            let path = path.unwrap();
+            assert_eq!(path.len(), 3);
+            assert_eq!(path[2].fingerprint(), root.certificate.fingerprint());
+        }
+
+        let other = CertificateAuthority::new_root_with_timestamp("x@example.com", "X", timestamp);
+        assert!(verify_certificate_chain(&chain, &[other.public_key()]).is_err());
+    }
+
+    #[test]
+    fn test_alternate_path_search_is_bounded() {
+        use crate::ca::{CertificateAuthority, SigningKeyPair};
+
+        // CA certificates alternating subject A / issuer B and subject B /
+        // issuer A never reach a self-signed root. Without a bound, every
+        // ordering of them would be tried.
+        let timestamp = 1704067200;
+        let root =
+            CertificateAuthority::new_root_with_timestamp("root@example.com", "Root CA", timestamp);
+        let [key_a, key_b] = [SigningKeyPair::generate(), SigningKeyPair::generate()];
+        let mut leaf = root
+            .issue_certificate_with_timestamp(
+                "alice@example.com",
+                "Alice",
+                &SigningKeyPair::generate().public_key(),
+                false,
+                timestamp,
+            )
+            .unwrap();
+        leaf.issuer_id = "a@example.com".into();
+        let looping_chain = |signed: bool| {
+            let mut leaf = leaf.clone();
+            if signed {
+                leaf.signature = key_a.sign(&leaf.signable_data());
+            }
+            let mut chain = vec![leaf];
+            for i in 0..DEFAULT_MAX_CHAIN_LEN - 1 {
+                let (subject, issuer, subject_key, issuer_key) = if i % 2 == 0 {
+                    ("a@example.com", "b@example.com", &key_a, &key_b)
+                } else {
+                    ("b@example.com", "a@example.com", &key_b, &key_a)
+                };
+                let mut cert = root.certificate.clone();
+                cert.serial = vec![i as u8];
+                cert.subject_id = subject.into();
+                cert.issuer_id = issuer.into();
+                cert.public_key = subject_key.public_key();
+                cert.signature = if signed {
+                    issuer_key.sign(&cert.signable_data())
+                } else {
+                    vec![0; 64]
+                };
+                chain.push(cert);
+            }
+            chain
+        };
+
+        // Bogus signatures: no issuer is followed at all
+        let chain = looping_chain(false);
+        let result = find_trusted_path(
+            &chain,
+            &[root.public_key()],
+            ValidityCheck::Skip,
+            DEFAULT_MAX_CHAIN_LEN,
+        );
+        assert!(matches!(
+            result.as_ref().map_err(AletheiaError::cause),
+            Err(AletheiaError::CertificateSignatureInvalid(_))
+        ));
+
+        // Valid signatures: the search stops when its budget runs out
+        let chain = looping_chain(true);
+        let result = find_trusted_path(
+            &chain,
+            &[root.public_key()],
+            ValidityCheck::Skip,
+            DEFAULT_MAX_CHAIN_LEN,
+        );
+        assert!(matches!(
+            result,
+            Err(AletheiaError::CertificateChainInvalid { .. })
+        ));
+    }
+
+    #[test]
+    fn test_chain_builder() {
+        use crate::ca::{CertificateAuthority, SigningKeyPair};
+
+        let timestamp = 1704067200;
+        let root =
+            CertificateAuthority::new_root_with_timestamp("root@example.com", "Root CA", timestamp);
+        let other_root =
+            CertificateAuthority::new_root_with_timestamp("other@example.com", "Other", timestamp);
+        let intermediate_keys = SigningKeyPair::generate();
+        let intermediate_cert = root
+            .issue_certificate_with_timestamp(
+                "intermediate@example.com",
+                "Intermediate CA",
+                &intermediate_keys.public_key(),
+                true,
+                timestamp,
+            )
+            .unwrap();
+        let intermediate = CertificateAuthority::from_key_and_cert(
+            &intermediate_keys.private_key_bytes(),
+            intermediate_cert.clone(),
+        )
+        .unwrap();
+        let leaf = intermediate
+            .issue_certificate_with_timestamp(
+                "alice@example.com",
+                "Alice",
+                &SigningKeyPair::generate().public_key(),
+                false,
+                timestamp,
+            )
+            .unwrap();
+
+        // Any order, with unrelated certificates mixed in
+        let chain = ChainBuilder::new()
+            .with_certificates([
+                root.certificate.clone(),
+                other_root.certificate.clone(),
+                intermediate_cert.clone(),
+            ])
+            .build(leaf.clone())
+            .unwrap();
+        let subjects: Vec<_> = chain.iter().map(|c| c.subject_id.as_str()).collect();
+        assert_eq!(
+            subjects,
+            [
+                "alice@example.com",
+                "intermediate@example.com",
+                "root@example.com"
+            ]
+        );
+        verify_certificate_chain_at(&chain, &[root.public_key()], ValidityCheck::Skip).unwrap();
+
+        // A missing intermediate is a gap
+        let gap = ChainBuilder::new()
+            .with_certificate(root.certificate.clone())
+            .build(leaf);
+        assert!(matches!(
+            gap,
+            Err(AletheiaError::CertificateNotFound(issuer)) if issuer == "intermediate@example.com"
+        ));
+    }
 }
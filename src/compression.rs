@@ -0,0 +1,67 @@
+extern crate alloc;
+
+use crate::{AletheiaError, CompressionAlgorithm, Result};
+use alloc::vec::Vec;
+
+/// Compress a payload with the given algorithm
+pub(crate) fn compress(algorithm: CompressionAlgorithm, data: &[u8]) -> Result<Vec<u8>> {
+    match algorithm {
+        CompressionAlgorithm::Lz4 => Ok(lz4_flex::compress_prepend_size(data)),
+        #[cfg(feature = "zstd")]
+        CompressionAlgorithm::Zstd { level } => zstd::bulk::compress(data, level)
+            .map_err(|e| AletheiaError::Compression(alloc::format!("{}", e))),
+        #[cfg(not(feature = "zstd"))]
+        CompressionAlgorithm::Zstd { .. } => Err(AletheiaError::Compression(
+            "Zstd feature not enabled".into(),
+        )),
+    }
+}
+
+/// Decompress a payload compressed with the given algorithm
+///
+/// Fails without decompressing if the output would exceed `max_len` bytes,
+/// so a small payload cannot expand into an arbitrarily large allocation.
+pub(crate) fn decompress(
+    algorithm: CompressionAlgorithm,
+    data: &[u8],
+    max_len: u64,
+) -> Result<Vec<u8>> {
+    let too_large = |len: u64| {
+        AletheiaError::Decompression(alloc::format!(
+            "Decompressed size of {} bytes exceeds the limit of {} bytes",
+            len,
+            max_len
+        ))
+    };
+    match algorithm {
+        CompressionAlgorithm::Lz4 => {
+            // The uncompressed size is prepended; check it before allocating
+            let (len, rest) = lz4_flex::block::uncompressed_size(data)
+                .map_err(|e| AletheiaError::Decompression(alloc::format!("{}", e)))?;
+            if len as u64 > max_len {
+                return Err(too_large(len as u64));
+            }
+            lz4_flex::block::decompress(rest, len)
+                .map_err(|e| AletheiaError::Decompression(alloc::format!("{}", e)))
+        }
+        #[cfg(feature = "zstd")]
+        CompressionAlgorithm::Zstd { .. } => {
+            use std::io::Read;
+            let decoder = zstd::stream::read::Decoder::new(data)
+                .map_err(|e| AletheiaError::Decompression(alloc::format!("{}", e)))?;
+            let mut out = Vec::new();
+            decoder
+                .take(max_len.saturating_add(1))
+                .read_to_end(&mut out)
+                .map_err(|e| AletheiaError::Decompression(alloc::format!("{}", e)))?;
+            if out.len() as u64 > max_len {
+                return Err(too_large(out.len() as u64));
+            }
+            Ok(out)
+        }
+        #[cfg(not(feature = "zstd"))]
+        CompressionAlgorithm::Zstd { .. } => Err(AletheiaError::Decompression(
+            "Zstd feature not enabled".into(),
+        )),
+    }
+}
@@ -0,0 +1,42 @@
+//! Constant-time comparisons
+//!
+//! Keys, fingerprints and digests are compared without exiting at the first
+//! differing byte, so the time a comparison takes does not reveal how much
+//! of an attacker-supplied value matched. Lengths are not secret and are
+//! compared directly.
+
+use subtle::{Choice, ConstantTimeEq};
+
+/// Whether `a` and `b` are equal, comparing their contents in constant time
+pub(crate) fn eq(a: &[u8], b: &[u8]) -> bool {
+    a.ct_eq(b).into()
+}
+
+/// Whether `keys` contains `key`
+///
+/// Every entry is compared, so the time taken does not depend on which
+/// entry (if any) matched.
+pub(crate) fn contains<K: AsRef<[u8]>>(keys: &[K], key: &[u8]) -> bool {
+    keys.iter()
+        .fold(Choice::from(0), |found, candidate| {
+            found | candidate.as_ref().ct_eq(key)
+        })
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eq_and_contains() {
+        assert!(eq(b"abc", b"abc"));
+        assert!(!eq(b"abc", b"abd"));
+        assert!(!eq(b"abc", b"ab"));
+
+        let keys = [[1u8; 32], [2u8; 32]];
+        assert!(contains(&keys, &[2u8; 32]));
+        assert!(!contains(&keys, &[3u8; 32]));
+        assert!(!contains(&keys, &[2u8; 31]));
+    }
+}
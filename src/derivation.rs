@@ -0,0 +1,148 @@
+//! Hierarchical derivation of sub-identity keys
+//!
+//! One master identity key can stand behind many signing keys: one per
+//! device or project, each derived along a path such as `m/0'/1'` following
+//! SLIP-0010 for Ed25519. Only the master key (or its mnemonic backup) needs
+//! to be kept safe; any device key can be re-derived from it, and a lost
+//! device key reveals neither the master nor its siblings. The CA certifies
+//! derived keys as sub-identities of the master's certificate with
+//! [`CertificateAuthority::issue_sub_identity`](crate::ca::CertificateAuthority::issue_sub_identity).
+//!
+//! Ed25519 only supports hardened derivation, so every index is hardened
+//! (`0'` and `0` name the same child).
+
+extern crate alloc;
+
+use hmac::{Hmac, Mac};
+use sha2::Sha512;
+
+use crate::{AletheiaError, Result, ca::SigningKeyPair};
+
+/// HMAC key of the SLIP-0010 master key derivation for Ed25519
+const ED25519_CURVE: &[u8] = b"ed25519 seed";
+
+/// Offset of hardened child indices
+const HARDENED: u32 = 0x8000_0000;
+
+/// A signing key with the chain code needed to derive its children
+#[derive(Clone)]
+pub struct ExtendedSigningKey {
+    key: [u8; 32],
+    chain_code: [u8; 32],
+}
+
+impl ExtendedSigningKey {
+    /// The SLIP-0010 master key of `seed`
+    pub fn from_seed(seed: &[u8]) -> Self {
+        Self::from_hmac(ED25519_CURVE, &[seed])
+    }
+
+    /// The master key rooted at an identity key
+    ///
+    /// The private key serves as the seed, so a mnemonic backup of the
+    /// identity key also backs up every key derived from it.
+    pub fn from_signing_key(keys: &SigningKeyPair) -> Self {
+        Self::from_seed(&keys.private_key_bytes())
+    }
+
+    /// Derive the hardened child at `index`
+    pub fn derive_child(&self, index: u32) -> Self {
+        let index = (index | HARDENED).to_be_bytes();
+        Self::from_hmac(&self.chain_code, &[&[0], &self.key, &index])
+    }
+
+    /// Derive the key at `path`, such as `m/0'/1'`
+    pub fn derive_path(&self, path: &str) -> Result<Self> {
+        let mut segments = path.split('/');
+        if segments.next() != Some("m") {
+            return Err(path_error(path));
+        }
+        segments.try_fold(self.clone(), |key, segment| {
+            let index: u32 = segment
+                .strip_suffix('\'')
+                .unwrap_or(segment)
+                .parse()
+                .map_err(|_| path_error(path))?;
+            if index >= HARDENED {
+                return Err(path_error(path));
+            }
+            Ok(key.derive_child(index))
+        })
+    }
+
+    /// The key pair to sign with
+    pub fn signing_key_pair(&self) -> SigningKeyPair {
+        SigningKeyPair::from_bytes(&self.key).expect("derived keys are 32 bytes")
+    }
+
+    /// The chain code (secret: it lets anyone holding the key derive children)
+    pub fn chain_code(&self) -> &[u8; 32] {
+        &self.chain_code
+    }
+
+    fn from_hmac(key: &[u8], data: &[&[u8]]) -> Self {
+        let mut mac = Hmac::<Sha512>::new_from_slice(key).expect("HMAC accepts any key length");
+        for part in data {
+            mac.update(part);
+        }
+        let output = mac.finalize().into_bytes();
+        let mut derived = Self {
+            key: [0; 32],
+            chain_code: [0; 32],
+        };
+        derived.key.copy_from_slice(&output[..32]);
+        derived.chain_code.copy_from_slice(&output[32..]);
+        derived
+    }
+}
+
+fn path_error(path: &str) -> AletheiaError {
+    AletheiaError::KeyGeneration(alloc::format!("Invalid derivation path '{path}'"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(bytes: &[u8]) -> alloc::string::String {
+        bytes.iter().map(|b| alloc::format!("{b:02x}")).collect()
+    }
+
+    #[test]
+    fn test_slip10_vectors() {
+        // Test vector 1 for ed25519 from SLIP-0010
+        let seed: alloc::vec::Vec<u8> = (0..16).collect();
+        let master = ExtendedSigningKey::from_seed(&seed);
+        assert_eq!(
+            hex(&master.key),
+            "2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7"
+        );
+        assert_eq!(
+            hex(master.chain_code()),
+            "90046a93de5380a72b5e45010748567d5ea02bbf6522f979e05c0d8d8ca9fffb"
+        );
+
+        let child = master.derive_path("m/0'/1'").unwrap();
+        assert_eq!(
+            hex(&child.key),
+            "b1d0bad404bf35da785a64ca1ac54b2617211d2777696fbffaf208f746ae84f2"
+        );
+        assert_eq!(
+            hex(child.chain_code()),
+            "a320425f77d1b5c2505a6b1b27382b37368ee640e3557c315416801243552f14"
+        );
+        assert_eq!(
+            master.derive_child(0).derive_child(1).key,
+            master.derive_path("m/0/1").unwrap().key
+        );
+    }
+
+    #[test]
+    fn test_invalid_paths() {
+        let master = ExtendedSigningKey::from_signing_key(&SigningKeyPair::generate());
+        assert_eq!(master.derive_path("m").unwrap().key, master.key);
+        for path in ["", "0'/1'", "m/x", "m/1''", "m//1", "m/2147483648"] {
+            assert!(master.derive_path(path).is_err(), "{path}");
+        }
+    }
+}
@@ -0,0 +1,268 @@
+//! Manifests in JPEG APP11 segments
+//!
+//! The manifest is stored in one or more APP11 segments whose data starts
+//! with the identifier `ALETHEIA\0` and a 2-byte big-endian sequence number,
+//! followed by the next piece of the `.alx` manifest. Large manifests are
+//! split across segments because a segment holds at most 64 KiB. The
+//! segments are placed after the leading APPn segments (e.g. JFIF or Exif).
+//! The signed content is the JPEG with these segments removed; other APP11
+//! segments (such as C2PA's) are left alone.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use crate::{
+    AletheiaError, AletheiaFile, Header, Result,
+    file::{from_bytes, to_bytes},
+    signer::Signer,
+    verifier::{VerificationResult, VerifyOptions, verify_detached_with_options},
+};
+
+/// Marker of the segments holding the manifest (APP11)
+pub const MARKER: u8 = 0xEB;
+
+/// Identifier at the start of each manifest segment
+pub const IDENTIFIER: &[u8; 9] = b"ALETHEIA\0";
+
+pub(super) const SOI: u8 = 0xD8;
+const EOI: u8 = 0xD9;
+const SOS: u8 = 0xDA;
+
+/// Largest manifest piece that fits into a single segment
+const MAX_PIECE_LEN: usize = u16::MAX as usize - 2 - IDENTIFIER.len() - 2;
+
+/// A marker segment located in a JPEG byte stream
+struct Segment<'a> {
+    marker: u8,
+    data: &'a [u8],
+    /// The whole segment, including marker and length
+    raw: &'a [u8],
+}
+
+impl Segment<'_> {
+    /// The sequence number and manifest piece, if this is a manifest segment
+    fn manifest_piece(&self) -> Option<(u16, &[u8])> {
+        if self.marker != MARKER {
+            return None;
+        }
+        let rest = self.data.strip_prefix(IDENTIFIER.as_slice())?;
+        let (seq, piece) = rest.split_first_chunk::<2>()?;
+        Some((u16::from_be_bytes(*seq), piece))
+    }
+
+    fn is_app(&self) -> bool {
+        (0xE0..=0xEF).contains(&self.marker)
+    }
+}
+
+/// Split a JPEG into the marker segments before the scan and the remaining bytes
+///
+/// The remainder starts at the first SOS marker (or EOI) and is kept verbatim.
+fn segments(jpeg: &[u8]) -> Result<(Vec<Segment<'_>>, &[u8])> {
+    let mut rest = jpeg
+        .strip_prefix([0xFF, SOI].as_slice())
+        .ok_or_else(|| AletheiaError::Embed("Not a JPEG file".into()))?;
+
+    let mut segments = Vec::new();
+    loop {
+        if rest.len() < 2 || rest[0] != 0xFF {
+            return Err(AletheiaError::Embed("Invalid JPEG marker".into()));
+        }
+        let marker = rest[1];
+        if marker == SOS || marker == EOI {
+            return Ok((segments, rest));
+        }
+
+        // Standalone markers carry no length
+        let total = if marker == 0x01 || (0xD0..=0xD7).contains(&marker) {
+            2
+        } else {
+            if rest.len() < 4 {
+                return Err(AletheiaError::Embed("Truncated JPEG segment".into()));
+            }
+            let len = u16::from_be_bytes([rest[2], rest[3]]) as usize;
+            if len < 2 || len + 2 > rest.len() {
+                return Err(AletheiaError::Embed("Truncated JPEG segment".into()));
+            }
+            len + 2
+        };
+
+        let (raw, tail) = rest.split_at(total);
+        segments.push(Segment {
+            marker,
+            data: raw.get(4..).unwrap_or(&[]),
+            raw,
+        });
+        rest = tail;
+    }
+}
+
+/// Remove any embedded manifest, returning the content a manifest is signed over
+pub fn strip(jpeg: &[u8]) -> Result<Vec<u8>> {
+    let (segments, scan) = segments(jpeg)?;
+    let mut out = Vec::with_capacity(jpeg.len());
+    out.extend_from_slice(&[0xFF, SOI]);
+    for segment in segments.iter().filter(|s| s.manifest_piece().is_none()) {
+        out.extend_from_slice(segment.raw);
+    }
+    out.extend_from_slice(scan);
+    Ok(out)
+}
+
+/// Embed `manifest` into `jpeg`, replacing any manifest already present
+pub fn embed(jpeg: &[u8], manifest: &AletheiaFile) -> Result<Vec<u8>> {
+    let manifest_bytes = to_bytes(manifest)?;
+    let pieces = manifest_bytes.chunks(MAX_PIECE_LEN);
+    if pieces.len() > u16::MAX as usize {
+        return Err(AletheiaError::Embed(
+            "Manifest too large for JPEG segments".into(),
+        ));
+    }
+
+    let (segments, scan) = segments(jpeg)?;
+    let segments: Vec<_> = segments
+        .into_iter()
+        .filter(|s| s.manifest_piece().is_none())
+        .collect();
+    let insert_at = segments
+        .iter()
+        .position(|s| !s.is_app())
+        .unwrap_or(segments.len());
+
+    let mut out = Vec::with_capacity(jpeg.len() + manifest_bytes.len() + 64);
+    out.extend_from_slice(&[0xFF, SOI]);
+    for segment in &segments[..insert_at] {
+        out.extend_from_slice(segment.raw);
+    }
+    for (seq, piece) in pieces.enumerate() {
+        let len = 2 + IDENTIFIER.len() + 2 + piece.len();
+        out.extend_from_slice(&[0xFF, MARKER]);
+        out.extend_from_slice(&(len as u16).to_be_bytes());
+        out.extend_from_slice(IDENTIFIER);
+        out.extend_from_slice(&(seq as u16).to_be_bytes());
+        out.extend_from_slice(piece);
+    }
+    for segment in &segments[insert_at..] {
+        out.extend_from_slice(segment.raw);
+    }
+    out.extend_from_slice(scan);
+    Ok(out)
+}
+
+/// The encoded manifest embedded in `jpeg`, if there is one
+pub fn manifest_bytes(jpeg: &[u8]) -> Result<Option<Vec<u8>>> {
+    let (segments, _) = segments(jpeg)?;
+    let mut manifest_bytes = Vec::new();
+    let mut count = 0u16;
+    for (seq, piece) in segments.iter().filter_map(Segment::manifest_piece) {
+        if seq != count {
+            return Err(AletheiaError::Embed(
+                "Manifest segments out of order".into(),
+            ));
+        }
+        manifest_bytes.extend_from_slice(piece);
+        count = count.wrapping_add(1);
+    }
+    Ok((!manifest_bytes.is_empty()).then_some(manifest_bytes))
+}
+
+/// Extract the embedded manifest and the content it is signed over
+pub fn extract(jpeg: &[u8]) -> Result<(AletheiaFile, Vec<u8>)> {
+    let manifest =
+        manifest_bytes(jpeg)?.ok_or_else(|| AletheiaError::Embed("JPEG has no manifest".into()))?;
+
+    Ok((from_bytes(&manifest)?, strip(jpeg)?))
+}
+
+/// Sign `jpeg` and embed the manifest into it
+///
+/// The header's content type defaults to `image/jpeg`.
+pub fn sign(signer: &Signer, jpeg: &[u8], mut header: Header) -> Result<Vec<u8>> {
+    let content = strip(jpeg)?;
+    header
+        .content_type
+        .get_or_insert_with(|| "image/jpeg".into());
+    let manifest = signer.sign_detached(&content, header)?;
+    embed(&content, &manifest)
+}
+
+/// Verify a JPEG against its embedded manifest
+pub fn verify(jpeg: &[u8], trusted_root_keys: &[Vec<u8>]) -> Result<VerificationResult> {
+    verify_with_options(jpeg, trusted_root_keys, &VerifyOptions::default())
+}
+
+/// Verify a JPEG against its embedded manifest with explicit options
+pub fn verify_with_options(
+    jpeg: &[u8],
+    trusted_root_keys: &[Vec<u8>],
+    options: &VerifyOptions,
+) -> Result<VerificationResult> {
+    let (manifest, content) = extract(jpeg)?;
+    verify_detached_with_options(&manifest, &content, trusted_root_keys, options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::embed::tests::test_signer;
+
+    fn segment(marker: u8, data: &[u8]) -> Vec<u8> {
+        let mut out = vec![0xFF, marker];
+        out.extend_from_slice(&((data.len() + 2) as u16).to_be_bytes());
+        out.extend_from_slice(data);
+        out
+    }
+
+    fn test_jpeg() -> Vec<u8> {
+        let mut jpeg = vec![0xFF, SOI];
+        jpeg.extend(segment(0xE0, b"JFIF\0\x01\x01\0\0\x01\0\x01\0\0"));
+        jpeg.extend(segment(MARKER, b"JP\0\0other APP11 data"));
+        jpeg.extend(segment(0xDB, &[0u8; 65]));
+        jpeg.extend(segment(SOS, &[1, 1, 0, 0, 63, 0]));
+        jpeg.extend_from_slice(&[0x12, 0xFF, 0x00, 0x34, 0xFF, EOI]);
+        jpeg
+    }
+
+    #[test]
+    fn test_sign_and_verify_jpeg() {
+        let (signer, trusted_roots) = test_signer();
+        let jpeg = test_jpeg();
+        let header = Header::new_with_timestamp("alice@example.com", 1704067200);
+
+        let signed = sign(&signer, &jpeg, header).unwrap();
+        let result = verify(&signed, &trusted_roots).unwrap();
+        assert_eq!(result.creator_id, "alice@example.com");
+        assert_eq!(strip(&signed).unwrap(), jpeg);
+
+        // The manifest follows the leading APP segments
+        let (segments, _) = segments(&signed).unwrap();
+        let markers: Vec<u8> = segments.iter().map(|s| s.marker).collect();
+        assert_eq!(markers, [0xE0, MARKER, MARKER, 0xDB]);
+        assert!(segments[1].manifest_piece().is_none());
+        assert!(segments[2].manifest_piece().is_some());
+
+        // Tampering with the scan data is detected
+        let mut tampered = signed.clone();
+        let len = tampered.len();
+        tampered[len - 3] ^= 0xFF;
+        assert!(matches!(
+            verify(&tampered, &trusted_roots),
+            Err(AletheiaError::ContentDigestMismatch)
+        ));
+    }
+
+    #[test]
+    fn test_large_manifest_spans_segments() {
+        let (signer, trusted_roots) = test_signer();
+        let header = Header::new_with_timestamp("alice@example.com", 1704067200)
+            .with_description("x".repeat(150_000));
+
+        let signed = sign(&signer, &test_jpeg(), header).unwrap();
+        let (segments, _) = segments(&signed).unwrap();
+        let pieces = segments.iter().filter_map(Segment::manifest_piece).count();
+        assert_eq!(pieces, 3);
+        verify(&signed, &trusted_roots).unwrap();
+        assert_eq!(strip(&signed).unwrap(), test_jpeg());
+    }
+}
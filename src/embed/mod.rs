@@ -0,0 +1,101 @@
+//! Embedding manifests into media files
+//!
+//! A manifest is a detached `.alx` file (see [`Signer::sign_detached`]): it
+//! carries the header, certificate chain and signature but no payload, and
+//! binds the content through the header's content digest. The digest covers
+//! the media file with the manifest removed, so embedding the manifest does
+//! not invalidate it and the file needs no sidecar.
+//!
+//! [`Signer::sign_detached`]: crate::signer::Signer::sign_detached
+
+pub mod jpeg;
+#[cfg(feature = "pdf")]
+pub mod pdf;
+pub mod png;
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use crate::{
+    Result,
+    verifier::{VerificationResult, VerifyOptions},
+};
+
+/// Media formats a manifest can be embedded in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaFormat {
+    Png,
+    Jpeg,
+}
+
+impl MediaFormat {
+    /// Recognize a media file by its leading bytes
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        if data.starts_with(png::PNG_SIGNATURE) {
+            Some(Self::Png)
+        } else if data.starts_with(&[0xFF, jpeg::SOI]) {
+            Some(Self::Jpeg)
+        } else {
+            None
+        }
+    }
+
+    /// Short lowercase name (`png` or `jpeg`)
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Jpeg => "jpeg",
+        }
+    }
+
+    /// Whether `data` carries an embedded manifest
+    pub fn has_manifest(self, data: &[u8]) -> Result<bool> {
+        match self {
+            Self::Png => png::manifest_bytes(data).map(|m| m.is_some()),
+            Self::Jpeg => jpeg::manifest_bytes(data).map(|m| m.is_some()),
+        }
+    }
+
+    /// Verify `data` against its embedded manifest
+    pub fn verify_with_options(
+        self,
+        data: &[u8],
+        trusted_root_keys: &[Vec<u8>],
+        options: &VerifyOptions,
+    ) -> Result<VerificationResult> {
+        match self {
+            Self::Png => png::verify_with_options(data, trusted_root_keys, options),
+            Self::Jpeg => jpeg::verify_with_options(data, trusted_root_keys, options),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::alloc::{vec, vec::Vec};
+
+    use crate::{
+        ca::{CertificateAuthority, SigningKeyPair},
+        signer::Signer,
+    };
+
+    /// A signer with a certificate issued by a fresh root, and the root's key
+    pub(crate) fn test_signer() -> (Signer, Vec<Vec<u8>>) {
+        let timestamp = 1704067200;
+        let ca =
+            CertificateAuthority::new_root_with_timestamp("root@example.com", "Root CA", timestamp);
+        let user_keys = SigningKeyPair::generate();
+        let user_cert = ca
+            .issue_certificate_with_timestamp(
+                "alice@example.com",
+                "Alice",
+                &user_keys.public_key(),
+                false,
+                timestamp,
+            )
+            .unwrap();
+        let signer = Signer::new(user_keys, vec![user_cert, ca.certificate.clone()]).unwrap();
+        (signer, vec![ca.public_key()])
+    }
+}
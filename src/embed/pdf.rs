@@ -0,0 +1,435 @@
+//! Manifests attached to PDF documents
+//!
+//! The manifest is added as an embedded file named `aletheia.alx` in an
+//! incremental update appended to the document, so any PDF reader lists it
+//! as an attachment. The signed content is the document as it was before
+//! that update; its length is recorded in the attachment's file
+//! specification under `/AletheiaSignedLength`.
+//!
+//! Verification also checks that nothing but the attachment was changed
+//! after signing: every object of the signed document must be unchanged,
+//! apart from the entries that register the attachment.
+
+use lopdf::{Dictionary, Document, IncrementalDocument, Object, ObjectId, Stream, dictionary};
+
+use crate::{
+    AletheiaError, AletheiaFile, Header, Result,
+    file::{from_bytes, to_bytes},
+    signer::Signer,
+    verifier::{VerificationResult, VerifyOptions, verify_detached_with_options},
+};
+
+/// File name of the attached manifest
+pub const ATTACHMENT_NAME: &str = "aletheia.alx";
+
+/// Key in the file specification holding the length of the signed content
+const SIGNED_LENGTH_KEY: &[u8] = b"AletheiaSignedLength";
+
+fn pdf_error(e: lopdf::Error) -> AletheiaError {
+    AletheiaError::Embed(e.to_string())
+}
+
+/// The objects that register an attached manifest
+struct Attachment {
+    root_id: ObjectId,
+    names_id: Option<ObjectId>,
+    files_id: Option<ObjectId>,
+    filespec_id: ObjectId,
+    stream_id: ObjectId,
+    manifest_bytes: Vec<u8>,
+    signed_len: usize,
+}
+
+/// Resolve a direct or indirect dictionary, if present
+fn resolve_dict(doc: &Document, object: Option<&Object>) -> Result<(Option<ObjectId>, Dictionary)> {
+    match object {
+        None => Ok((None, Dictionary::new())),
+        Some(Object::Reference(id)) => Ok((
+            Some(*id),
+            doc.get_dictionary(*id).map_err(pdf_error)?.clone(),
+        )),
+        Some(Object::Dictionary(dict)) => Ok((None, dict.clone())),
+        Some(_) => Err(AletheiaError::Embed("Malformed name dictionary".into())),
+    }
+}
+
+/// The catalog, names dictionary and embedded files name tree of a document
+struct NameTree {
+    catalog: Dictionary,
+    names_id: Option<ObjectId>,
+    names: Dictionary,
+    files_id: Option<ObjectId>,
+    files: Dictionary,
+}
+
+/// Read the name tree of a document, with the id of its catalog
+fn name_tree(doc: &Document) -> Result<(ObjectId, NameTree)> {
+    let root_id = doc
+        .trailer
+        .get(b"Root")
+        .and_then(Object::as_reference)
+        .map_err(pdf_error)?;
+    let catalog = doc.get_dictionary(root_id).map_err(pdf_error)?.clone();
+    let (names_id, names) = resolve_dict(doc, catalog.get(b"Names").ok())?;
+    let (files_id, files) = resolve_dict(doc, names.get(b"EmbeddedFiles").ok())?;
+    if files.has(b"Kids") {
+        return Err(AletheiaError::Embed(
+            "Nested attachment name trees are not supported".into(),
+        ));
+    }
+    Ok((
+        root_id,
+        NameTree {
+            catalog,
+            names_id,
+            names,
+            files_id,
+            files,
+        },
+    ))
+}
+
+impl NameTree {
+    /// The `(name, file specification)` pairs of the embedded files tree
+    fn entries(&self) -> Vec<Object> {
+        self.files
+            .get(b"Names")
+            .and_then(Object::as_array)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// The entries without the manifest
+    fn other_entries(&self) -> Vec<Object> {
+        self.entries()
+            .chunks(2)
+            .filter(|pair| !is_manifest_name(&pair[0]))
+            .flatten()
+            .cloned()
+            .collect()
+    }
+
+    /// The file specification of the manifest, if present
+    fn manifest_filespec(&self) -> Option<ObjectId> {
+        self.entries()
+            .chunks(2)
+            .find(|pair| is_manifest_name(&pair[0]))
+            .and_then(|pair| pair.get(1)?.as_reference().ok())
+    }
+}
+
+fn is_manifest_name(name: &Object) -> bool {
+    name.as_str()
+        .is_ok_and(|name| name == ATTACHMENT_NAME.as_bytes())
+}
+
+/// Locate the attached manifest
+fn find_attachment(doc: &Document) -> Result<Attachment> {
+    let (root_id, tree) = name_tree(doc)?;
+    let filespec_id = tree
+        .manifest_filespec()
+        .ok_or_else(|| AletheiaError::Embed("PDF has no manifest".into()))?;
+    let filespec = doc.get_dictionary(filespec_id).map_err(pdf_error)?;
+    let stream_id = filespec
+        .get(b"EF")
+        .and_then(Object::as_dict)
+        .and_then(|ef| ef.get(b"F"))
+        .and_then(Object::as_reference)
+        .map_err(pdf_error)?;
+    let manifest_bytes = doc
+        .get_object(stream_id)
+        .and_then(Object::as_stream)
+        .and_then(Stream::get_plain_content)
+        .map_err(pdf_error)?;
+    let signed_len = filespec
+        .get(SIGNED_LENGTH_KEY)
+        .and_then(Object::as_i64)
+        .ok()
+        .and_then(|len| usize::try_from(len).ok())
+        .ok_or_else(|| AletheiaError::Embed("Missing signed content length".into()))?;
+
+    Ok(Attachment {
+        root_id,
+        names_id: tree.names_id,
+        files_id: tree.files_id,
+        filespec_id,
+        stream_id,
+        manifest_bytes,
+        signed_len,
+    })
+}
+
+/// Remove an attached manifest, returning the content a manifest is signed over
+///
+/// The incremental update holding the manifest is cut off. A PDF without a
+/// manifest is returned unchanged.
+pub fn strip(pdf: &[u8]) -> Result<Vec<u8>> {
+    let doc = Document::load_mem(pdf).map_err(pdf_error)?;
+    if name_tree(&doc)?.1.manifest_filespec().is_none() {
+        return Ok(pdf.to_vec());
+    }
+
+    let attachment = find_attachment(&doc)?;
+    pdf.get(..attachment.signed_len)
+        .map(<[u8]>::to_vec)
+        .ok_or_else(|| AletheiaError::Embed("Signed content length out of range".into()))
+}
+
+/// Attach `manifest` to `pdf` in a new incremental update
+///
+/// `pdf` is the signed content; it must not carry a manifest already.
+pub fn embed(pdf: &[u8], manifest: &AletheiaFile) -> Result<Vec<u8>> {
+    let manifest_bytes = to_bytes(manifest)?;
+    let mut doc: IncrementalDocument = pdf.try_into().map_err(pdf_error)?;
+    let (root_id, mut tree) = name_tree(doc.get_prev_documents())?;
+    if tree.manifest_filespec().is_some() {
+        return Err(AletheiaError::Embed("PDF already has a manifest".into()));
+    }
+
+    let update = &mut doc.new_document;
+    let stream_id = update.add_object(Stream::new(
+        dictionary! {
+            "Type" => "EmbeddedFile",
+            "Subtype" => Object::Name(b"application/x-aletheia".to_vec()),
+            "Params" => dictionary! { "Size" => manifest_bytes.len() as i64 },
+        },
+        manifest_bytes,
+    ));
+    let filespec_id = update.add_object(dictionary! {
+        "Type" => "Filespec",
+        "F" => Object::string_literal(ATTACHMENT_NAME),
+        "UF" => Object::string_literal(ATTACHMENT_NAME),
+        "Desc" => Object::string_literal("Aletheia provenance manifest"),
+        "EF" => dictionary! { "F" => stream_id },
+        SIGNED_LENGTH_KEY => pdf.len() as i64,
+    });
+
+    // Insert the manifest into the (sorted) embedded files name tree
+    let mut entries = tree.entries();
+    let position = entries
+        .chunks(2)
+        .position(|pair| {
+            pair[0]
+                .as_str()
+                .is_ok_and(|name| name > ATTACHMENT_NAME.as_bytes())
+        })
+        .map_or(entries.len(), |pair| pair * 2);
+    entries.splice(
+        position..position,
+        [Object::string_literal(ATTACHMENT_NAME), filespec_id.into()],
+    );
+    tree.files.set("Names", entries);
+
+    // Store the changed dictionaries where they were, cloning indirect ones
+    let files = store(update, tree.files_id, tree.files);
+    tree.names.set("EmbeddedFiles", files);
+    let names = store(update, tree.names_id, tree.names);
+    tree.catalog.set("Names", names);
+    update.set_object(root_id, tree.catalog);
+
+    let mut out = Vec::new();
+    doc.save_to(&mut out)?;
+    Ok(out)
+}
+
+/// Write `dict` back as the object `id`, or inline if it was direct
+fn store(update: &mut Document, id: Option<ObjectId>, dict: Dictionary) -> Object {
+    match id {
+        Some(id) => {
+            update.set_object(id, dict);
+            Object::Reference(id)
+        }
+        None => Object::Dictionary(dict),
+    }
+}
+
+/// Extract the attached manifest and the content it is signed over
+///
+/// Fails if the document was changed after the manifest was attached in any
+/// way other than registering the attachment.
+pub fn extract(pdf: &[u8]) -> Result<(AletheiaFile, Vec<u8>)> {
+    let doc = Document::load_mem(pdf).map_err(pdf_error)?;
+    let attachment = find_attachment(&doc)?;
+    let content = pdf
+        .get(..attachment.signed_len)
+        .ok_or_else(|| AletheiaError::Embed("Signed content length out of range".into()))?;
+
+    let original = Document::load_mem(content).map_err(pdf_error)?;
+    check_unchanged(&original, &doc, &attachment)?;
+
+    Ok((from_bytes(&attachment.manifest_bytes)?, content.to_vec()))
+}
+
+/// Check that `doc` only differs from `original` by the attachment
+fn check_unchanged(original: &Document, doc: &Document, attachment: &Attachment) -> Result<()> {
+    let modified = || {
+        Err(AletheiaError::Embed(
+            "PDF was modified after signing".into(),
+        ))
+    };
+
+    let (original_root, original_tree) = name_tree(original)?;
+    let (_, tree) = name_tree(doc)?;
+    if original_root != attachment.root_id
+        || without(&tree.catalog, b"Names") != without(&original_tree.catalog, b"Names")
+        || without(&tree.names, b"EmbeddedFiles") != without(&original_tree.names, b"EmbeddedFiles")
+        || tree.other_entries() != original_tree.other_entries()
+    {
+        return modified();
+    }
+
+    let registering = [
+        Some(attachment.root_id),
+        attachment.names_id,
+        attachment.files_id,
+        Some(attachment.filespec_id),
+        Some(attachment.stream_id),
+    ];
+    for (id, object) in &doc.objects {
+        let unchanged = original.objects.get(id) == Some(object)
+            || registering.contains(&Some(*id))
+            || object.type_name().is_ok_and(|name| name == b"XRef");
+        if !unchanged {
+            return modified();
+        }
+    }
+    if original
+        .objects
+        .keys()
+        .any(|id| !doc.objects.contains_key(id))
+    {
+        return modified();
+    }
+    Ok(())
+}
+
+fn without(dict: &Dictionary, key: &[u8]) -> Dictionary {
+    let mut dict = dict.clone();
+    dict.remove(key);
+    dict
+}
+
+/// Sign `pdf` and attach the manifest to it
+///
+/// A manifest already attached is replaced. The header's content type
+/// defaults to `application/pdf`.
+pub fn sign(signer: &Signer, pdf: &[u8], mut header: Header) -> Result<Vec<u8>> {
+    let content = strip(pdf)?;
+    header
+        .content_type
+        .get_or_insert_with(|| "application/pdf".into());
+    let manifest = signer.sign_detached(&content, header)?;
+    embed(&content, &manifest)
+}
+
+/// Verify a PDF against its attached manifest
+pub fn verify(pdf: &[u8], trusted_root_keys: &[Vec<u8>]) -> Result<VerificationResult> {
+    verify_with_options(pdf, trusted_root_keys, &VerifyOptions::default())
+}
+
+/// Verify a PDF against its attached manifest with explicit options
+pub fn verify_with_options(
+    pdf: &[u8],
+    trusted_root_keys: &[Vec<u8>],
+    options: &VerifyOptions,
+) -> Result<VerificationResult> {
+    let (manifest, content) = extract(pdf)?;
+    verify_detached_with_options(&manifest, &content, trusted_root_keys, options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::embed::tests::test_signer;
+
+    fn test_pdf() -> Vec<u8> {
+        let mut doc = Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let content_id = doc.add_object(Stream::new(
+            dictionary! {},
+            b"BT /F1 12 Tf 72 720 Td (Quarterly report) Tj ET".to_vec(),
+        ));
+        let page_id = doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "Contents" => content_id,
+            "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+        });
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Kids" => vec![page_id.into()],
+                "Count" => 1,
+            }),
+        );
+        let catalog_id = doc.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+        });
+        doc.trailer.set("Root", catalog_id);
+
+        let mut pdf = Vec::new();
+        doc.save_to(&mut pdf).unwrap();
+        pdf
+    }
+
+    #[test]
+    fn test_sign_and_verify_pdf() {
+        let (signer, trusted_roots) = test_signer();
+        let pdf = test_pdf();
+        let header = Header::new_with_timestamp("alice@example.com", 1704067200);
+
+        let signed = sign(&signer, &pdf, header.clone()).unwrap();
+        assert!(signed.starts_with(&pdf));
+        let result = verify(&signed, &trusted_roots).unwrap();
+        assert_eq!(result.creator_id, "alice@example.com");
+        assert_eq!(strip(&signed).unwrap(), pdf);
+
+        let (manifest, _) = extract(&signed).unwrap();
+        assert_eq!(
+            manifest.header.content_type.as_deref(),
+            Some("application/pdf")
+        );
+
+        // Re-signing replaces the manifest
+        let resigned = sign(&signer, &signed, header).unwrap();
+        verify(&resigned, &trusted_roots).unwrap();
+    }
+
+    #[test]
+    fn test_verify_modified_pdf() {
+        let (signer, trusted_roots) = test_signer();
+        let header = Header::new_with_timestamp("alice@example.com", 1704067200);
+        let signed = sign(&signer, &test_pdf(), header).unwrap();
+
+        // Change the page content in a later incremental update
+        let mut doc: IncrementalDocument = signed.as_slice().try_into().unwrap();
+        let content_id = doc
+            .get_prev_documents()
+            .objects
+            .iter()
+            .find(|(_, object)| {
+                object
+                    .as_stream()
+                    .is_ok_and(|s| s.content.starts_with(b"BT"))
+            })
+            .map(|(id, _)| *id)
+            .unwrap();
+        doc.new_document.set_object(
+            content_id,
+            Stream::new(dictionary! {}, b"BT (Forged report) Tj ET".to_vec()),
+        );
+        let mut modified = Vec::new();
+        doc.save_to(&mut modified).unwrap();
+
+        assert!(matches!(
+            verify(&modified, &trusted_roots),
+            Err(AletheiaError::Embed(_))
+        ));
+        assert!(matches!(
+            verify(&test_pdf(), &trusted_roots),
+            Err(AletheiaError::Embed(_))
+        ));
+    }
+}
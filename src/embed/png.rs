@@ -0,0 +1,246 @@
+//! Manifests in a private PNG chunk
+//!
+//! The manifest is stored in an `alXa` chunk (ancillary, private,
+//! safe-to-copy) placed just before `IEND`. The signed content is the PNG with
+//! every `alXa` chunk removed.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use crate::{
+    AletheiaError, AletheiaFile, Header, Result,
+    file::{from_bytes, to_bytes},
+    signer::Signer,
+    verifier::{VerificationResult, VerifyOptions, verify_detached_with_options},
+};
+
+/// Type of the chunk holding the manifest
+pub const CHUNK_TYPE: &[u8; 4] = b"alXa";
+
+pub(super) const PNG_SIGNATURE: &[u8; 8] = b"\x89PNG\r\n\x1a\n";
+const IEND: &[u8; 4] = b"IEND";
+
+/// A chunk located in a PNG byte stream
+struct Chunk<'a> {
+    kind: &'a [u8],
+    data: &'a [u8],
+    /// The whole chunk, including length, type and CRC
+    raw: &'a [u8],
+}
+
+/// Split a PNG into its chunks, up to and including `IEND`
+fn chunks(png: &[u8]) -> Result<Vec<Chunk<'_>>> {
+    let mut rest = png
+        .strip_prefix(PNG_SIGNATURE.as_slice())
+        .ok_or_else(|| AletheiaError::Embed("Not a PNG file".into()))?;
+
+    let mut chunks = Vec::new();
+    loop {
+        if rest.len() < 12 {
+            return Err(AletheiaError::Embed("Truncated PNG chunk".into()));
+        }
+        let len = u32::from_be_bytes([rest[0], rest[1], rest[2], rest[3]]) as usize;
+        let total = len
+            .checked_add(12)
+            .filter(|&total| total <= rest.len())
+            .ok_or_else(|| AletheiaError::Embed("Truncated PNG chunk".into()))?;
+
+        let (raw, tail) = rest.split_at(total);
+        let chunk = Chunk {
+            kind: &raw[4..8],
+            data: &raw[8..8 + len],
+            raw,
+        };
+        let is_end = chunk.kind == IEND;
+        chunks.push(chunk);
+        if is_end {
+            return Ok(chunks);
+        }
+        rest = tail;
+    }
+}
+
+/// Remove any embedded manifest, returning the content a manifest is signed over
+pub fn strip(png: &[u8]) -> Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(png.len());
+    out.extend_from_slice(PNG_SIGNATURE);
+    for chunk in chunks(png)?.iter().filter(|c| c.kind != CHUNK_TYPE) {
+        out.extend_from_slice(chunk.raw);
+    }
+    Ok(out)
+}
+
+/// Embed `manifest` into `png`, replacing any manifest already present
+pub fn embed(png: &[u8], manifest: &AletheiaFile) -> Result<Vec<u8>> {
+    let manifest_bytes = to_bytes(manifest)?;
+    let len = u32::try_from(manifest_bytes.len())
+        .map_err(|_| AletheiaError::Embed("Manifest too large for a PNG chunk".into()))?;
+
+    let mut out = Vec::with_capacity(png.len() + manifest_bytes.len() + 12);
+    out.extend_from_slice(PNG_SIGNATURE);
+    for chunk in chunks(png)?.iter().filter(|c| c.kind != CHUNK_TYPE) {
+        if chunk.kind == IEND {
+            out.extend_from_slice(&len.to_be_bytes());
+            out.extend_from_slice(CHUNK_TYPE);
+            out.extend_from_slice(&manifest_bytes);
+            out.extend_from_slice(&crc32(&[CHUNK_TYPE, &manifest_bytes]).to_be_bytes());
+        }
+        out.extend_from_slice(chunk.raw);
+    }
+    Ok(out)
+}
+
+/// The encoded manifest embedded in `png`, if there is one
+pub fn manifest_bytes(png: &[u8]) -> Result<Option<&[u8]>> {
+    let chunks = chunks(png)?;
+    let mut manifests = chunks.iter().filter(|c| c.kind == CHUNK_TYPE);
+    match (manifests.next(), manifests.next()) {
+        (Some(chunk), None) => Ok(Some(chunk.data)),
+        (None, _) => Ok(None),
+        (Some(_), Some(_)) => Err(AletheiaError::Embed("PNG has several manifests".into())),
+    }
+}
+
+/// Extract the embedded manifest and the content it is signed over
+pub fn extract(png: &[u8]) -> Result<(AletheiaFile, Vec<u8>)> {
+    let manifest =
+        manifest_bytes(png)?.ok_or_else(|| AletheiaError::Embed("PNG has no manifest".into()))?;
+
+    Ok((from_bytes(manifest)?, strip(png)?))
+}
+
+/// Sign `png` and embed the manifest into it
+///
+/// The header's content type defaults to `image/png`.
+pub fn sign(signer: &Signer, png: &[u8], mut header: Header) -> Result<Vec<u8>> {
+    let content = strip(png)?;
+    header
+        .content_type
+        .get_or_insert_with(|| "image/png".into());
+    let manifest = signer.sign_detached(&content, header)?;
+    embed(&content, &manifest)
+}
+
+/// Verify a PNG against its embedded manifest
+pub fn verify(png: &[u8], trusted_root_keys: &[Vec<u8>]) -> Result<VerificationResult> {
+    verify_with_options(png, trusted_root_keys, &VerifyOptions::default())
+}
+
+/// Verify a PNG against its embedded manifest with explicit options
+pub fn verify_with_options(
+    png: &[u8],
+    trusted_root_keys: &[Vec<u8>],
+    options: &VerifyOptions,
+) -> Result<VerificationResult> {
+    let (manifest, content) = extract(png)?;
+    verify_detached_with_options(&manifest, &content, trusted_root_keys, options)
+}
+
+/// CRC-32 (ISO-HDLC) as used by PNG, over the concatenation of `parts`
+fn crc32(parts: &[&[u8]]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for byte in parts.iter().flat_map(|part| part.iter()) {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::embed::tests::test_signer;
+
+    fn chunk(kind: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let mut out = (data.len() as u32).to_be_bytes().to_vec();
+        out.extend_from_slice(kind);
+        out.extend_from_slice(data);
+        out.extend_from_slice(&crc32(&[kind, data]).to_be_bytes());
+        out
+    }
+
+    fn test_png() -> Vec<u8> {
+        let mut png = PNG_SIGNATURE.to_vec();
+        png.extend(chunk(b"IHDR", &[0, 0, 0, 1, 0, 0, 0, 1, 8, 0, 0, 0, 0]));
+        png.extend(chunk(
+            b"IDAT",
+            &[0x78, 0x9c, 0x63, 0x60, 0x00, 0x00, 0x00, 0x02],
+        ));
+        png.extend(chunk(b"IEND", &[]));
+        png
+    }
+
+    #[test]
+    fn test_crc32() {
+        // CRC of an empty IEND chunk, as found in every PNG
+        assert_eq!(crc32(&[IEND, &[]]), 0xAE42_6082);
+    }
+
+    #[test]
+    fn test_sign_and_verify_png() {
+        let (signer, trusted_roots) = test_signer();
+        let png = test_png();
+        let header = Header::new_with_timestamp("alice@example.com", 1704067200);
+
+        let signed = sign(&signer, &png, header.clone()).unwrap();
+        let result = verify(&signed, &trusted_roots).unwrap();
+        assert_eq!(result.creator_id, "alice@example.com");
+        assert_eq!(strip(&signed).unwrap(), png);
+
+        let (manifest, _) = extract(&signed).unwrap();
+        assert!(manifest.flags.is_detached());
+        assert_eq!(manifest.header.content_type.as_deref(), Some("image/png"));
+
+        // Re-signing replaces the manifest instead of adding a second one
+        let resigned = sign(&signer, &signed, header).unwrap();
+        verify(&resigned, &trusted_roots).unwrap();
+    }
+
+    #[test]
+    fn test_detect_embedded_manifest() {
+        use crate::embed::MediaFormat;
+
+        let (signer, trusted_roots) = test_signer();
+        let header = Header::new_with_timestamp("alice@example.com", 1704067200);
+        let signed = sign(&signer, &test_png(), header).unwrap();
+
+        let format = MediaFormat::detect(&signed).unwrap();
+        assert_eq!(format, MediaFormat::Png);
+        assert!(format.has_manifest(&signed).unwrap());
+        assert!(!format.has_manifest(&test_png()).unwrap());
+        let options = VerifyOptions::default();
+        let result = format.verify_with_options(&signed, &trusted_roots, &options);
+        assert_eq!(result.unwrap().creator_id, "alice@example.com");
+
+        assert_eq!(
+            MediaFormat::detect(&[0xFF, 0xD8, 0xFF]),
+            Some(MediaFormat::Jpeg)
+        );
+        assert_eq!(MediaFormat::detect(b"ALETHEIA"), None);
+    }
+
+    #[test]
+    fn test_verify_modified_png() {
+        let (signer, trusted_roots) = test_signer();
+        let header = Header::new_with_timestamp("alice@example.com", 1704067200);
+        let mut signed = sign(&signer, &test_png(), header).unwrap();
+
+        // Flip a byte of the image data
+        let idat = signed.windows(4).position(|w| w == b"IDAT").unwrap();
+        signed[idat + 4] ^= 0xFF;
+        let result = verify(&signed, &trusted_roots);
+        assert!(matches!(result, Err(AletheiaError::ContentDigestMismatch)));
+
+        assert!(matches!(
+            verify(&test_png(), &trusted_roots),
+            Err(AletheiaError::Embed(_))
+        ));
+    }
+}
@@ -1,8 +1,51 @@
 extern crate alloc;
 
+use alloc::boxed::Box;
 use alloc::string::String;
 use thiserror::Error;
 
+/// Broad category of an [`AletheiaError`]
+///
+/// Lets applications react to a failure (ask for another file, show a
+/// certificate warning, report tampering) without matching every variant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// The data is malformed or uses an unsupported format
+    Parse,
+    /// A certificate, its chain or its revocation status was rejected
+    Chain,
+    /// A signature or digest does not match the signed data
+    Signature,
+    /// A CA's issuance policy or the verifier's policy refused the operation
+    Policy,
+    /// Generating, encoding or jointly using a key failed
+    Key,
+    /// A timestamp, transparency log entry or OpenTimestamps proof was rejected
+    Evidence,
+    /// Producing output (encoding, compression, embedding) failed
+    Encoding,
+    /// Reading or writing files or an issuance store failed
+    Io,
+}
+
+/// A certificate chain rejected at a known certificate
+///
+/// Wraps an error that does not record where in the chain it happened, such
+/// as an expired certificate or a certificate signature that does not verify.
+#[derive(Debug)]
+pub struct ChainError {
+    /// Position in the chain (0 = signer) of the rejected certificate
+    pub step: usize,
+    /// Why the certificate was rejected
+    pub reason: Box<AletheiaError>,
+}
+
+impl From<ChainError> for AletheiaError {
+    fn from(error: ChainError) -> Self {
+        Self::Chain(error)
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum AletheiaError {
     #[error("Invalid magic bytes: expected 'ALETHEIA'")]
@@ -14,8 +57,10 @@ pub enum AletheiaError {
     #[error("Invalid signature")]
     InvalidSignature,
 
-    #[error("Certificate chain verification failed: {0}")]
-    CertificateChainInvalid(String),
+    /// `step` is the position in the chain (0 = signer) of the certificate
+    /// being checked when the chain was rejected
+    #[error("Certificate chain verification failed at certificate {step}: {reason}")]
+    CertificateChainInvalid { step: usize, reason: String },
 
     #[error("Certificate not found for subject: {0}")]
     CertificateNotFound(String),
@@ -23,12 +68,37 @@ pub enum AletheiaError {
     #[error("Untrusted root certificate")]
     UntrustedRoot,
 
+    #[error("Certificate expired: {0}")]
+    CertificateExpired(String),
+
+    #[error("Certificate not yet valid: {0}")]
+    CertificateNotYetValid(String),
+
     #[error("Certificate revoked: serial {0}")]
     CertificateRevoked(String),
 
+    #[error("Invalid revocation list: {0}")]
+    InvalidRevocationList(String),
+
+    #[error("Invalid trust bundle: {0}")]
+    InvalidTrustBundle(String),
+
+    /// Code, kind and tampering are those of the wrapped error
+    #[error("Certificate chain verification failed at certificate {}: {}", .0.step, .0.reason)]
+    Chain(ChainError),
+
+    #[error("Certificate chain too long: {len} certificates (max {max})")]
+    ChainTooLong { len: usize, max: usize },
+
+    #[error("No current status assertion for certificate: serial {0}")]
+    MissingCertificateStatus(String),
+
     #[error("Invalid certificate: {0}")]
     InvalidCertificate(String),
 
+    #[error("Certificate signature does not verify: {0}")]
+    CertificateSignatureInvalid(String),
+
     #[error("CBOR encoding error: {0}")]
     CborEncode(String),
 
@@ -44,15 +114,261 @@ pub enum AletheiaError {
     #[error("Unexpected end of data")]
     UnexpectedEof,
 
+    #[error("Section too large: {section} is {len} bytes (limit {limit})")]
+    SectionTooLarge {
+        section: &'static str,
+        len: u64,
+        limit: u64,
+    },
+
+    #[error("Too many certificates in chain: {count} (limit {limit})")]
+    TooManyCertificates { count: usize, limit: usize },
+
     #[cfg(feature = "std")]
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
+    #[error("Content digest does not match payload")]
+    ContentDigestMismatch,
+
+    #[error("Chunk verification failed: {0}")]
+    ChunkVerification(String),
+
+    #[error("Payload is detached; verify it against the original content")]
+    DetachedPayload,
+
+    #[error("Embedding error: {0}")]
+    Embed(String),
+
+    #[error("C2PA error: {0}")]
+    C2pa(String),
+
+    #[error("Invalid unsigned draft: {0}")]
+    InvalidDraft(String),
+
+    #[error("JWS error: {0}")]
+    Jws(String),
+
+    #[error("Timestamp error: {0}")]
+    Timestamp(String),
+
+    #[error("Transparency log error: {0}")]
+    TransparencyLog(String),
+
+    #[error("OpenTimestamps error: {0}")]
+    OpenTimestamps(String),
+
     #[error("Invalid header: {0}")]
     InvalidHeader(String),
 
     #[error("Key generation failed: {0}")]
     KeyGeneration(String),
+
+    #[error("Key encoding error: {0}")]
+    KeyEncoding(String),
+
+    #[error("Threshold signing error: {0}")]
+    Threshold(String),
+
+    #[error("External signer error: {0}")]
+    ExternalSigner(String),
+
+    #[error("Issuance store error: {0}")]
+    Storage(String),
+
+    #[error("Online revocation check failed: {0}")]
+    RevocationCheck(String),
+
+    #[error("Issuance policy: {0}")]
+    Policy(String),
+
+    #[error("Verification warning treated as an error: {0}")]
+    EscalatedWarning(String),
+
+    #[error("Signature policy not met: {0}")]
+    SignaturePolicy(String),
+}
+
+impl AletheiaError {
+    /// Stable numeric code of the error
+    ///
+    /// The hundreds digit gives the [`ErrorKind`]: 1xx parse, 2xx chain,
+    /// 3xx signature, 4xx policy, 5xx key, 6xx evidence, 7xx encoding and
+    /// 8xx IO. A code keeps its meaning across releases; new errors get new
+    /// codes. A [`ChainError`] has the code of the error it wraps.
+    pub fn code(&self) -> u16 {
+        match self {
+            Self::Chain(error) => error.reason.code(),
+            Self::InvalidMagic => 101,
+            Self::UnsupportedVersion { .. } => 102,
+            Self::CborDecode(_) => 103,
+            Self::UnexpectedEof => 104,
+            Self::SectionTooLarge { .. } => 105,
+            Self::TooManyCertificates { .. } => 106,
+            Self::InvalidHeader(_) => 107,
+            Self::Decompression(_) => 108,
+            Self::DetachedPayload => 109,
+            Self::Jws(_) => 110,
+            Self::C2pa(_) => 111,
+            Self::InvalidDraft(_) => 112,
+            Self::CertificateChainInvalid { .. } => 201,
+            Self::CertificateNotFound(_) => 202,
+            Self::UntrustedRoot => 203,
+            Self::CertificateExpired(_) => 204,
+            Self::CertificateNotYetValid(_) => 205,
+            Self::CertificateRevoked(_) => 206,
+            Self::InvalidRevocationList(_) => 207,
+            Self::MissingCertificateStatus(_) => 208,
+            Self::InvalidCertificate(_) => 209,
+            Self::CertificateSignatureInvalid(_) => 210,
+            Self::InvalidTrustBundle(_) => 211,
+            Self::ChainTooLong { .. } => 212,
+            Self::InvalidSignature => 301,
+            Self::ContentDigestMismatch => 302,
+            Self::ChunkVerification(_) => 303,
+            Self::Policy(_) => 401,
+            Self::EscalatedWarning(_) => 402,
+            Self::SignaturePolicy(_) => 403,
+            Self::KeyGeneration(_) => 501,
+            Self::KeyEncoding(_) => 502,
+            Self::Threshold(_) => 503,
+            Self::ExternalSigner(_) => 504,
+            Self::Timestamp(_) => 601,
+            Self::TransparencyLog(_) => 602,
+            Self::OpenTimestamps(_) => 603,
+            Self::CborEncode(_) => 701,
+            Self::Compression(_) => 702,
+            Self::Embed(_) => 703,
+            #[cfg(feature = "std")]
+            Self::Io(_) => 801,
+            Self::Storage(_) => 802,
+            Self::RevocationCheck(_) => 803,
+        }
+    }
+
+    /// Category of the error
+    pub fn kind(&self) -> ErrorKind {
+        match self.code() / 100 {
+            1 => ErrorKind::Parse,
+            2 => ErrorKind::Chain,
+            3 => ErrorKind::Signature,
+            4 => ErrorKind::Policy,
+            5 => ErrorKind::Key,
+            6 => ErrorKind::Evidence,
+            7 => ErrorKind::Encoding,
+            _ => ErrorKind::Io,
+        }
+    }
+
+    /// Whether the error shows that signed data was altered
+    ///
+    /// True when a signature over the content or over a certificate does
+    /// not verify, or the content no longer matches its signed digest. A
+    /// file that is merely malformed, untrusted or expired is not tampered.
+    pub fn is_tampering(&self) -> bool {
+        matches!(
+            self.cause(),
+            Self::InvalidSignature
+                | Self::ContentDigestMismatch
+                | Self::ChunkVerification(_)
+                | Self::CertificateSignatureInvalid(_)
+        )
+    }
+
+    /// Position in the chain (0 = signer) of the certificate that was rejected
+    pub fn chain_step(&self) -> Option<usize> {
+        match self {
+            Self::Chain(error) => Some(error.step),
+            Self::CertificateChainInvalid { step, .. } => Some(*step),
+            _ => None,
+        }
+    }
+
+    /// The error without the chain position of a [`ChainError`]
+    ///
+    /// Match on this to handle, say, an expired certificate the same way
+    /// wherever it sits in the chain.
+    pub fn cause(&self) -> &Self {
+        match self {
+            Self::Chain(error) => error.reason.cause(),
+            error => error,
+        }
+    }
+
+    /// Record that the certificate at `step` of a chain failed with this error
+    ///
+    /// Errors that already carry their position are returned as they are.
+    pub(crate) fn at_step(self, step: usize) -> Self {
+        match self {
+            Self::Chain(_) | Self::CertificateChainInvalid { .. } => self,
+            reason => Self::Chain(ChainError {
+                step,
+                reason: Box::new(reason),
+            }),
+        }
+    }
 }
 
 pub type Result<T> = core::result::Result<T, AletheiaError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_codes_and_kinds() {
+        let chain = AletheiaError::CertificateChainInvalid {
+            step: 1,
+            reason: "Root certificate is not self-signed".into(),
+        };
+        assert_eq!(chain.code(), 201);
+        assert_eq!(chain.kind(), ErrorKind::Chain);
+        assert!(!chain.is_tampering());
+        assert_eq!(
+            alloc::string::ToString::to_string(&chain),
+            "Certificate chain verification failed at certificate 1: \
+             Root certificate is not self-signed"
+        );
+
+        assert_eq!(AletheiaError::InvalidMagic.kind(), ErrorKind::Parse);
+        assert_eq!(AletheiaError::Policy("no".into()).kind(), ErrorKind::Policy);
+        assert_eq!(AletheiaError::Storage("full".into()).kind(), ErrorKind::Io);
+        assert!(AletheiaError::ContentDigestMismatch.is_tampering());
+        assert!(AletheiaError::CertificateSignatureInvalid("alice".into()).is_tampering());
+        assert!(!AletheiaError::UntrustedRoot.is_tampering());
+    }
+
+    #[test]
+    fn test_chain_error() {
+        let error = AletheiaError::CertificateSignatureInvalid("alice".into()).at_step(1);
+        assert_eq!(error.chain_step(), Some(1));
+        assert_eq!(error.code(), 210);
+        assert_eq!(error.kind(), ErrorKind::Chain);
+        assert!(error.is_tampering());
+        assert!(matches!(
+            error.cause(),
+            AletheiaError::CertificateSignatureInvalid(_)
+        ));
+        assert_eq!(
+            alloc::string::ToString::to_string(&error),
+            "Certificate chain verification failed at certificate 1: \
+             Certificate signature does not verify: alice"
+        );
+
+        // A position already recorded is kept
+        let error = error.at_step(2);
+        assert_eq!(error.chain_step(), Some(1));
+        assert_eq!(AletheiaError::UntrustedRoot.chain_step(), None);
+
+        let error: AletheiaError = ChainError {
+            step: 0,
+            reason: Box::new(AletheiaError::CertificateExpired("alice".into())),
+        }
+        .into();
+        assert_eq!(error.kind(), ErrorKind::Chain);
+        assert!(matches!(
+            error.cause(),
+            AletheiaError::CertificateExpired(_)
+        ));
+    }
+}
@@ -6,9 +6,296 @@
 
 extern crate alloc;
 
-use crate::{AletheiaError, AletheiaFile, Certificate, Flags, Header, MAGIC_BYTES, Result};
+use crate::{
+    AletheiaError, AletheiaFile, Certificate, CoSignature, Countersignature, Flags, Header,
+    LogEntry, MAGIC_BYTES, OpenTimestampsProof, Result, TimestampToken, VERSION_MAJOR,
+    VERSION_MINOR, revocation::StatusAssertion,
+};
 use alloc::string::ToString;
 use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+/// Upper bounds enforced while parsing an envelope
+///
+/// Length fields are checked against these limits before anything is
+/// allocated, so a crafted file cannot make the parser reserve gigabytes. The
+/// default is meant for untrusted input; raise individual limits for known
+/// large content. Streaming reads never buffer the payload and do not apply
+/// `max_payload_len`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseLimits {
+    /// Maximum length of the CBOR header in bytes
+    pub max_header_len: usize,
+    /// Maximum length of the payload in bytes
+    pub max_payload_len: u64,
+    /// Maximum length of the CBOR certificate chain in bytes
+    pub max_cert_chain_len: usize,
+    /// Maximum number of certificates in any one chain
+    pub max_certificates: usize,
+    /// Maximum length of the CBOR trailer in bytes
+    pub max_trailer_len: usize,
+    /// Maximum ratio of decompressed to compressed payload length
+    pub max_expansion_ratio: u64,
+}
+
+impl Default for ParseLimits {
+    /// 1 MiB header, 4 GiB payload, 64 KiB and 16 certificates per chain,
+    /// 16 MiB trailer, 1024x decompression
+    fn default() -> Self {
+        Self {
+            max_header_len: 1 << 20,
+            max_payload_len: 1 << 32,
+            max_cert_chain_len: 64 << 10,
+            max_certificates: 16,
+            max_trailer_len: 16 << 20,
+            max_expansion_ratio: 1024,
+        }
+    }
+}
+
+impl ParseLimits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum header length
+    pub fn with_max_header_len(mut self, len: usize) -> Self {
+        self.max_header_len = len;
+        self
+    }
+
+    /// Set the maximum payload length
+    pub fn with_max_payload_len(mut self, len: u64) -> Self {
+        self.max_payload_len = len;
+        self
+    }
+
+    /// Set the maximum certificate chain length
+    pub fn with_max_cert_chain_len(mut self, len: usize) -> Self {
+        self.max_cert_chain_len = len;
+        self
+    }
+
+    /// Set the maximum number of certificates per chain
+    pub fn with_max_certificates(mut self, count: usize) -> Self {
+        self.max_certificates = count;
+        self
+    }
+
+    /// Set the maximum trailer length
+    pub fn with_max_trailer_len(mut self, len: usize) -> Self {
+        self.max_trailer_len = len;
+        self
+    }
+
+    /// Set the maximum ratio of decompressed to compressed payload length
+    pub fn with_max_expansion_ratio(mut self, ratio: u64) -> Self {
+        self.max_expansion_ratio = ratio;
+        self
+    }
+
+    /// Largest decompressed payload allowed for `stored_len` stored bytes
+    #[cfg(feature = "compression")]
+    pub(crate) fn max_decompressed_len(&self, stored_len: usize) -> u64 {
+        (stored_len as u64)
+            .saturating_mul(self.max_expansion_ratio)
+            .min(self.max_payload_len)
+    }
+
+    /// Longest run of sections after the payload the limits allow
+    pub(crate) fn max_signature_section_len(&self) -> usize {
+        // Two length prefixes and the signature
+        (4 + 64 + 4usize)
+            .saturating_add(self.max_cert_chain_len)
+            .saturating_add(self.max_trailer_len)
+    }
+
+    /// Longest file the limits allow in total
+    #[cfg(feature = "std")]
+    fn max_file_len(&self) -> u64 {
+        // Fixed-size fields: magic, version, flags, four length prefixes, signature
+        (8 + 2 + 2 + 4 + 8 + 4 + 64 + 4u64)
+            .saturating_add(self.max_header_len as u64)
+            .saturating_add(self.max_payload_len)
+            .saturating_add(self.max_cert_chain_len as u64)
+            .saturating_add(self.max_trailer_len as u64)
+    }
+
+    /// Reject a section of `len` bytes longer than `limit`
+    fn check_len(section: &'static str, len: u64, limit: u64) -> Result<()> {
+        if len > limit {
+            return Err(AletheiaError::SectionTooLarge {
+                section,
+                len,
+                limit,
+            });
+        }
+        Ok(())
+    }
+
+    /// Reject a certificate chain with too many certificates
+    fn check_chain(&self, chain: &[Certificate]) -> Result<()> {
+        if chain.len() > self.max_certificates {
+            return Err(AletheiaError::TooManyCertificates {
+                count: chain.len(),
+                limit: self.max_certificates,
+            });
+        }
+        Ok(())
+    }
+
+    /// Check the certificate chains carried in the trailer
+    fn check_trailer(&self, trailer: &Trailer) -> Result<()> {
+        for co in &trailer.co_signatures {
+            self.check_chain(&co.certificate_chain)?;
+        }
+        for cs in &trailer.countersignatures {
+            self.check_chain(&cs.certificate_chain)?;
+        }
+        Ok(())
+    }
+}
+
+/// Unsigned sections stored after the primary signature (CBOR map)
+///
+/// Readers that predate the trailer stop at the primary signature and ignore
+/// it, and adding to it does not invalidate the primary signature.
+#[derive(Serialize)]
+struct TrailerRef<'a> {
+    #[serde(skip_serializing_if = "Option::is_none", with = "serde_bytes")]
+    pq_signature: Option<&'a [u8]>,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    co_signatures: &'a [CoSignature],
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    countersignatures: &'a [Countersignature],
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    timestamp_tokens: &'a [TimestampToken],
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    log_entries: &'a [LogEntry],
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    opentimestamps: &'a [OpenTimestampsProof],
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    status_assertions: &'a [StatusAssertion],
+}
+
+#[derive(Deserialize, Default)]
+pub(crate) struct Trailer {
+    #[serde(default, with = "serde_bytes")]
+    pub pq_signature: Option<Vec<u8>>,
+    #[serde(default)]
+    pub co_signatures: Vec<CoSignature>,
+    #[serde(default)]
+    pub countersignatures: Vec<Countersignature>,
+    #[serde(default)]
+    pub timestamp_tokens: Vec<TimestampToken>,
+    #[serde(default)]
+    pub log_entries: Vec<LogEntry>,
+    #[serde(default)]
+    pub opentimestamps: Vec<OpenTimestampsProof>,
+    #[serde(default)]
+    pub status_assertions: Vec<StatusAssertion>,
+}
+
+/// Encode the trailer, or nothing if all of its sections are empty
+fn encode_trailer(file: &AletheiaFile) -> Result<Vec<u8>> {
+    TrailerRef {
+        pq_signature: file.pq_signature.as_deref(),
+        co_signatures: &file.co_signatures,
+        countersignatures: &file.countersignatures,
+        timestamp_tokens: &file.timestamp_tokens,
+        log_entries: &file.log_entries,
+        opentimestamps: &file.opentimestamps,
+        status_assertions: &file.status_assertions,
+    }
+    .encode()
+}
+
+impl TrailerRef<'_> {
+    /// Encode the trailer with its length prefix, or nothing if it is empty
+    fn encode(&self) -> Result<Vec<u8>> {
+        if self.pq_signature.is_none()
+            && self.co_signatures.is_empty()
+            && self.countersignatures.is_empty()
+            && self.timestamp_tokens.is_empty()
+            && self.log_entries.is_empty()
+            && self.opentimestamps.is_empty()
+            && self.status_assertions.is_empty()
+        {
+            return Ok(Vec::new());
+        }
+
+        let mut trailer_bytes = Vec::new();
+        ciborium::into_writer(self, &mut trailer_bytes)
+            .map_err(|e| AletheiaError::CborEncode(e.to_string()))?;
+
+        let mut buffer = Vec::with_capacity(4 + trailer_bytes.len());
+        buffer.extend_from_slice(&(trailer_bytes.len() as u32).to_le_bytes());
+        buffer.extend_from_slice(&trailer_bytes);
+        Ok(buffer)
+    }
+}
+
+/// Encode the sections following a freshly signed payload: the certificate
+/// chain, the signatures and any stapled status assertions
+pub(crate) fn encode_signature_section(
+    cert_chain_bytes: &[u8],
+    signature: &[u8],
+    pq_signature: Option<&[u8]>,
+    status_assertions: &[StatusAssertion],
+) -> Result<Vec<u8>> {
+    let trailer = TrailerRef {
+        pq_signature,
+        co_signatures: &[],
+        countersignatures: &[],
+        timestamp_tokens: &[],
+        log_entries: &[],
+        opentimestamps: &[],
+        status_assertions,
+    };
+    let mut buffer = Vec::new();
+    buffer.extend_from_slice(&(cert_chain_bytes.len() as u32).to_le_bytes());
+    buffer.extend_from_slice(cert_chain_bytes);
+    buffer.extend_from_slice(signature);
+    buffer.extend_from_slice(&trailer.encode()?);
+    Ok(buffer)
+}
+
+/// Locate the CBOR trailer in the bytes following the primary signature
+///
+/// Returns an empty slice if there is no trailer.
+fn split_trailer<'a>(data: &'a [u8], limits: &ParseLimits) -> Result<&'a [u8]> {
+    if data.is_empty() {
+        return Ok(data);
+    }
+
+    let len_bytes: [u8; 4] = data
+        .get(..4)
+        .ok_or(AletheiaError::UnexpectedEof)?
+        .try_into()
+        .unwrap();
+    let len = u32::from_le_bytes(len_bytes);
+    ParseLimits::check_len("trailer", len as u64, limits.max_trailer_len as u64)?;
+    data[4..]
+        .get(..len as usize)
+        .ok_or(AletheiaError::UnexpectedEof)
+}
+
+/// Decode a CBOR trailer located by [`split_trailer`]
+fn decode_trailer_cbor(trailer_bytes: &[u8], limits: &ParseLimits) -> Result<Trailer> {
+    if trailer_bytes.is_empty() {
+        return Ok(Trailer::default());
+    }
+
+    let trailer: Trailer = ciborium::from_reader(trailer_bytes)
+        .map_err(|e| AletheiaError::CborDecode(e.to_string()))?;
+    limits.check_trailer(&trailer)?;
+    Ok(trailer)
+}
+
+/// Decode the bytes following the primary signature (empty means no trailer)
+fn decode_trailer(data: &[u8], limits: &ParseLimits) -> Result<Trailer> {
+    decode_trailer_cbor(split_trailer(data, limits)?, limits)
+}
 
 /// Serialize an Aletheia file to bytes
 pub fn to_bytes(file: &AletheiaFile) -> Result<Vec<u8>> {
@@ -47,16 +334,146 @@ pub fn to_bytes(file: &AletheiaFile) -> Result<Vec<u8>> {
     // Signature
     buffer.extend_from_slice(&file.signature);
 
+    // Trailer (unsigned sections)
+    buffer.extend_from_slice(&encode_trailer(file)?);
+
+    Ok(buffer)
+}
+
+/// Write an unsigned draft of an Aletheia file
+///
+/// The draft is the start of a file up to and including the payload, with
+/// the header's content digest set. Signing it with
+/// [`Signer::finalize`](crate::signer::Signer::finalize) appends the
+/// certificate chain and signature without rewriting the payload, so the
+/// draft can be produced by a separate tool and signed later.
+pub fn write_unsigned(payload: &[u8], header: Header) -> Result<Vec<u8>> {
+    let header = header.with_content_digest(payload);
+    let mut header_bytes = Vec::new();
+    ciborium::into_writer(&header, &mut header_bytes)
+        .map_err(|e| AletheiaError::CborEncode(e.to_string()))?;
+
+    let mut buffer = Vec::with_capacity(8 + 2 + 2 + 4 + header_bytes.len() + 8 + payload.len());
+    buffer.extend_from_slice(MAGIC_BYTES);
+    buffer.push(VERSION_MAJOR);
+    buffer.push(VERSION_MINOR);
+    buffer.extend_from_slice(&Flags::new().to_bytes());
+    buffer.extend_from_slice(&(header_bytes.len() as u32).to_le_bytes());
+    buffer.extend_from_slice(&header_bytes);
+    buffer.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+    buffer.extend_from_slice(payload);
     Ok(buffer)
 }
 
 /// Deserialize an Aletheia file from bytes
+///
+/// Untrusted input is bounded by the default [`ParseLimits`]; use
+/// [`from_bytes_with_limits`] to change them.
 pub fn from_bytes(data: &[u8]) -> Result<AletheiaFile> {
-    let mut cursor = 0;
+    from_bytes_with_limits(data, &ParseLimits::default())
+}
+
+/// Deserialize an Aletheia file from bytes, enforcing `limits`
+pub fn from_bytes_with_limits(data: &[u8], limits: &ParseLimits) -> Result<AletheiaFile> {
+    from_bytes_ref_with_limits(data, limits)?.into_owned()
+}
+
+/// An Aletheia file that borrows its sections from the input buffer
+///
+/// Only the header is decoded up front. The payload, certificate chain,
+/// signature and trailer stay slices of the input, so parsing does not copy
+/// the payload, and the chain and trailer are decoded when asked for. Use
+/// [`crate::verifier::verify_ref`] to verify it without converting to an
+/// [`AletheiaFile`].
+#[derive(Debug, Clone)]
+pub struct AletheiaFileRef<'a> {
+    /// Major format version
+    pub version_major: u8,
+    /// Minor format version
+    pub version_minor: u8,
+    /// Feature flags
+    pub flags: Flags,
+    /// Decoded header
+    pub header: Header,
+    /// The header as stored (and signed)
+    pub header_bytes: &'a [u8],
+    /// Payload as stored (possibly compressed)
+    pub payload: &'a [u8],
+    /// The CBOR certificate chain as stored (and signed)
+    pub cert_chain_bytes: &'a [u8],
+    /// Primary signature (64 bytes)
+    pub signature: &'a [u8],
+    /// The CBOR trailer without its length prefix, empty if there is none
+    pub trailer_bytes: &'a [u8],
+    limits: ParseLimits,
+}
+
+impl AletheiaFileRef<'_> {
+    /// Decode the certificate chain
+    pub fn certificate_chain(&self) -> Result<Vec<Certificate>> {
+        let chain: Vec<Certificate> = ciborium::from_reader(self.cert_chain_bytes)
+            .map_err(|e| AletheiaError::CborDecode(e.to_string()))?;
+        self.limits.check_chain(&chain)?;
+        Ok(chain)
+    }
+
+    /// Decode the trailer
+    pub(crate) fn trailer(&self) -> Result<Trailer> {
+        decode_trailer_cbor(self.trailer_bytes, &self.limits)
+    }
+
+    /// Get the original (decompressed) payload
+    pub fn get_payload(&self) -> Result<Vec<u8>> {
+        crate::types::decompress_payload(self.flags, self.payload)
+    }
+
+    /// Decode the remaining sections into an owned [`AletheiaFile`]
+    pub fn into_owned(self) -> Result<AletheiaFile> {
+        let certificate_chain = self.certificate_chain()?;
+        let trailer = self.trailer()?;
+        Ok(AletheiaFile {
+            version_major: self.version_major,
+            version_minor: self.version_minor,
+            flags: self.flags,
+            header: self.header,
+            payload: self.payload.to_vec(),
+            certificate_chain,
+            signature: self.signature.to_vec(),
+            pq_signature: trailer.pq_signature,
+            co_signatures: trailer.co_signatures,
+            countersignatures: trailer.countersignatures,
+            timestamp_tokens: trailer.timestamp_tokens,
+            log_entries: trailer.log_entries,
+            opentimestamps: trailer.opentimestamps,
+            status_assertions: trailer.status_assertions,
+        })
+    }
+}
+
+/// Parse an Aletheia file without copying its payload
+pub fn from_bytes_ref(data: &[u8]) -> Result<AletheiaFileRef<'_>> {
+    from_bytes_ref_with_limits(data, &ParseLimits::default())
+}
+
+/// Parse an Aletheia file without copying its payload, enforcing `limits`
+pub fn from_bytes_ref_with_limits<'a>(
+    data: &'a [u8],
+    limits: &ParseLimits,
+) -> Result<AletheiaFileRef<'a>> {
+    let Prefix {
+        version_major,
+        version_minor,
+        flags,
+        header,
+        header_bytes,
+        payload_len,
+        len: mut cursor,
+    } = parse_prefix(data, limits)?.ok_or(AletheiaError::UnexpectedEof)?;
+    let payload_len = usize::try_from(payload_len).map_err(|_| AletheiaError::UnexpectedEof)?;
 
     // Helper to read bytes
-    let read_bytes = |cursor: &mut usize, len: usize| -> Result<&[u8]> {
-        if *cursor + len > data.len() {
+    let read_bytes = |cursor: &mut usize, len: usize| -> Result<&'a [u8]> {
+        if len > data.len() - *cursor {
             return Err(AletheiaError::UnexpectedEof);
         }
         let result = &data[*cursor..*cursor + len];
@@ -64,55 +481,454 @@ pub fn from_bytes(data: &[u8]) -> Result<AletheiaFile> {
         Ok(result)
     };
 
+    // Payload
+    let payload = read_bytes(&mut cursor, payload_len)?;
+
+    // Certificate chain length
+    let cert_len_bytes: [u8; 4] = read_bytes(&mut cursor, 4)?.try_into().unwrap();
+    let cert_len = u32::from_le_bytes(cert_len_bytes) as usize;
+    ParseLimits::check_len(
+        "certificate chain",
+        cert_len as u64,
+        limits.max_cert_chain_len as u64,
+    )?;
+
+    // Certificate chain
+    let cert_chain_bytes = read_bytes(&mut cursor, cert_len)?;
+
+    // Signature
+    let signature = read_bytes(&mut cursor, 64)?;
+
+    // Trailer (unsigned sections)
+    let trailer_bytes = split_trailer(&data[cursor..], limits)?;
+
+    Ok(AletheiaFileRef {
+        version_major,
+        version_minor,
+        flags,
+        header,
+        header_bytes,
+        payload,
+        cert_chain_bytes,
+        signature,
+        trailer_bytes,
+        limits: *limits,
+    })
+}
+
+/// The sections of an Aletheia file before the payload, see [`parse_prefix`]
+pub(crate) struct Prefix<'a> {
+    pub version_major: u8,
+    pub version_minor: u8,
+    pub flags: Flags,
+    pub header: Header,
+    pub header_bytes: &'a [u8],
+    pub payload_len: u64,
+    /// Length of the encoded sections, i.e. the payload's offset
+    pub len: usize,
+}
+
+/// Parse the sections before the payload from the start of a file
+///
+/// Returns `None` if `data` ends before the payload length.
+pub(crate) fn parse_prefix<'a>(data: &'a [u8], limits: &ParseLimits) -> Result<Option<Prefix<'a>>> {
     // Magic bytes
-    let magic = read_bytes(&mut cursor, 8)?;
+    let Some(magic) = data.get(..8) else {
+        return Ok(None);
+    };
     if magic != MAGIC_BYTES {
         return Err(AletheiaError::InvalidMagic);
     }
 
-    // Version
-    let version = read_bytes(&mut cursor, 2)?;
-    let version_major = version[0];
-    let version_minor = version[1];
-
+    // Version, flags and header length
+    let Some(fixed) = data.get(8..16) else {
+        return Ok(None);
+    };
+    let (version_major, version_minor) = (fixed[0], fixed[1]);
     if version_major != 1 {
         return Err(AletheiaError::UnsupportedVersion {
             major: version_major,
             minor: version_minor,
         });
     }
+    let flags = Flags::from_bytes([fixed[2], fixed[3]]);
+    let header_len = u32::from_le_bytes(fixed[4..8].try_into().unwrap()) as usize;
+    ParseLimits::check_len("header", header_len as u64, limits.max_header_len as u64)?;
 
-    // Flags
-    let flags_bytes: [u8; 2] = read_bytes(&mut cursor, 2)?.try_into().unwrap();
-    let flags = Flags::from_bytes(flags_bytes);
-
-    // Header length
-    let header_len_bytes: [u8; 4] = read_bytes(&mut cursor, 4)?.try_into().unwrap();
-    let header_len = u32::from_le_bytes(header_len_bytes) as usize;
-
-    // Header
-    let header_bytes = read_bytes(&mut cursor, header_len)?;
+    // Header and payload length
+    let Some(header_bytes) = data.get(16..16 + header_len) else {
+        return Ok(None);
+    };
+    let len = 16 + header_len + 8;
+    let Some(payload_len) = data.get(16 + header_len..len) else {
+        return Ok(None);
+    };
     let header: Header = ciborium::from_reader(header_bytes)
         .map_err(|e| AletheiaError::CborDecode(e.to_string()))?;
+    let payload_len = u64::from_le_bytes(payload_len.try_into().unwrap());
+    ParseLimits::check_len("payload", payload_len, limits.max_payload_len)?;
 
-    // Payload length
-    let payload_len_bytes: [u8; 8] = read_bytes(&mut cursor, 8)?.try_into().unwrap();
-    let payload_len = u64::from_le_bytes(payload_len_bytes) as usize;
-
-    // Payload
-    let payload = read_bytes(&mut cursor, payload_len)?.to_vec();
+    Ok(Some(Prefix {
+        version_major,
+        version_minor,
+        flags,
+        header,
+        header_bytes,
+        payload_len,
+        len,
+    }))
+}
 
-    // Certificate chain length
-    let cert_len_bytes: [u8; 4] = read_bytes(&mut cursor, 4)?.try_into().unwrap();
-    let cert_len = u32::from_le_bytes(cert_len_bytes) as usize;
+/// The sections of an Aletheia file after the payload
+pub(crate) struct SignatureSection {
+    pub certificate_chain: Vec<Certificate>,
+    pub cert_chain_bytes: Vec<u8>,
+    pub signature: Vec<u8>,
+    pub trailer: Trailer,
+}
 
+/// Parse the certificate chain, signature and trailer following the payload
+pub(crate) fn parse_signature_section(
+    data: &[u8],
+    limits: &ParseLimits,
+) -> Result<SignatureSection> {
     // Certificate chain
-    let cert_chain_bytes = read_bytes(&mut cursor, cert_len)?;
+    let cert_len_bytes: [u8; 4] = data
+        .get(..4)
+        .ok_or(AletheiaError::UnexpectedEof)?
+        .try_into()
+        .unwrap();
+    let cert_len = u32::from_le_bytes(cert_len_bytes) as usize;
+    ParseLimits::check_len(
+        "certificate chain",
+        cert_len as u64,
+        limits.max_cert_chain_len as u64,
+    )?;
+    let cert_chain_bytes = data
+        .get(4..4 + cert_len)
+        .ok_or(AletheiaError::UnexpectedEof)?;
     let certificate_chain: Vec<Certificate> = ciborium::from_reader(cert_chain_bytes)
         .map_err(|e| AletheiaError::CborDecode(e.to_string()))?;
+    limits.check_chain(&certificate_chain)?;
 
     // Signature
-    let signature = read_bytes(&mut cursor, 64)?.to_vec();
+    let signature = data
+        .get(4 + cert_len..4 + cert_len + 64)
+        .ok_or(AletheiaError::UnexpectedEof)?;
+
+    // Trailer (unsigned sections)
+    let trailer = decode_trailer(&data[4 + cert_len + 64..], limits)?;
+
+    Ok(SignatureSection {
+        certificate_chain,
+        cert_chain_bytes: cert_chain_bytes.to_vec(),
+        signature: signature.to_vec(),
+        trailer,
+    })
+}
+
+/// An Aletheia file whose payload was left in the underlying reader
+#[derive(Debug, Clone)]
+pub struct FileStructure {
+    /// Major format version
+    pub version_major: u8,
+    /// Minor format version
+    pub version_minor: u8,
+    /// Feature flags
+    pub flags: Flags,
+    /// Decoded header
+    pub header: Header,
+    /// The header as stored (and signed)
+    pub header_bytes: Vec<u8>,
+    /// Position of the payload from the start of the reader
+    pub payload_offset: u64,
+    /// Length of the payload as stored (possibly compressed)
+    pub payload_len: u64,
+    /// Certificate chain (signer first)
+    pub certificate_chain: Vec<Certificate>,
+    /// The CBOR certificate chain as stored (and signed)
+    pub cert_chain_bytes: Vec<u8>,
+    /// Primary signature (64 bytes)
+    pub signature: Vec<u8>,
+    /// ML-DSA signature of the primary signer from the trailer
+    pub pq_signature: Option<Vec<u8>>,
+    /// Co-signatures from the trailer
+    pub co_signatures: Vec<CoSignature>,
+    /// Countersignatures from the trailer
+    pub countersignatures: Vec<Countersignature>,
+    /// Timestamp tokens from the trailer
+    pub timestamp_tokens: Vec<TimestampToken>,
+    /// Transparency log entries from the trailer
+    pub log_entries: Vec<LogEntry>,
+    /// OpenTimestamps proofs from the trailer
+    pub opentimestamps: Vec<OpenTimestampsProof>,
+    /// Status assertions stapled in the trailer
+    pub status_assertions: Vec<StatusAssertion>,
+}
+
+/// A section of an encoded file and its byte range, see [`sections`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    /// Name of the section: "magic", "version", "flags", "header",
+    /// "payload", "certificate chain", "signature", "trailer" or
+    /// "trailing data"
+    pub name: &'static str,
+    /// Offset of the first byte, including any length prefix
+    pub start: usize,
+    /// Offset just past the last byte
+    pub end: usize,
+    /// Whether the data holds the whole section (false when a length runs
+    /// past the end of the data)
+    pub complete: bool,
+}
+
+/// Locate the sections of an encoded file from its length prefixes
+///
+/// Nothing is decoded or checked, so this also works on malformed files,
+/// for debugging: a section whose length runs past the end of the data is
+/// cut short and ends the list. Length-prefixed sections include their
+/// prefix, and the trailer is only listed when present.
+pub fn sections(data: &[u8]) -> Vec<Section> {
+    // (name, width of the length prefix, or the fixed length if unprefixed)
+    const LAYOUT: [(&str, usize, bool); 7] = [
+        ("magic", 8, false),
+        ("version", 2, false),
+        ("flags", 2, false),
+        ("header", 4, true),
+        ("payload", 8, true),
+        ("certificate chain", 4, true),
+        ("signature", 64, false),
+    ];
+
+    let mut sections = Vec::new();
+    let mut start = 0;
+    let mut push = |name, start: usize, len: u64| {
+        let end = start.saturating_add(usize::try_from(len).unwrap_or(usize::MAX));
+        let complete = end <= data.len();
+        sections.push(Section {
+            name,
+            start,
+            end: end.min(data.len()),
+            complete,
+        });
+        complete.then_some(end)
+    };
+    let prefixed_len = |start: usize, width: usize| {
+        let prefix = data.get(start..start.checked_add(width)?)?;
+        let mut len = [0u8; 8];
+        len[..width].copy_from_slice(prefix);
+        Some(u64::from_le_bytes(len))
+    };
+
+    for (name, width, prefixed) in LAYOUT {
+        if start >= data.len() {
+            return sections;
+        }
+        let len = match prefixed {
+            true => prefixed_len(start, width).map_or(width as u64, |len| len + width as u64),
+            false => width as u64,
+        };
+        match push(name, start, len) {
+            Some(end) => start = end,
+            None => return sections,
+        }
+    }
+    if start < data.len() {
+        let len = prefixed_len(start, 4).map_or(4, |len| len + 4);
+        if let Some(end) = push("trailer", start, len)
+            && end < data.len()
+        {
+            push("trailing data", end, (data.len() - end) as u64);
+        }
+    }
+    sections
+}
+
+const COSE_SIGN1_TAG: u64 = 18;
+
+/// COSE header label for the content type
+const COSE_CONTENT_TYPE: i64 = 3;
+
+// Private COSE header labels carrying the envelope fields
+const COSE_VERSION: &str = "alx-version";
+const COSE_FLAGS: &str = "alx-flags";
+const COSE_HEADER: &str = "alx-header";
+const COSE_CERT_CHAIN: &str = "alx-cert-chain";
+const COSE_TRAILER: &str = "alx-trailer";
+
+/// Serialize an Aletheia file as a tagged COSE_Sign1 message (RFC 9052)
+///
+/// The version, flags and header go in the protected bucket, the certificate
+/// chain and trailer in the unprotected bucket. The signature is the Aletheia
+/// signature over the usual signature input rather than over the COSE
+/// `Sig_structure`, so it is checked with [`from_cose`] and the Aletheia
+/// verifier. A detached manifest has a `nil` payload.
+pub fn to_cose(file: &AletheiaFile) -> Result<Vec<u8>> {
+    use ciborium::Value;
+
+    let mut header_bytes = Vec::new();
+    ciborium::into_writer(&file.header, &mut header_bytes)
+        .map_err(|e| AletheiaError::CborEncode(e.to_string()))?;
+
+    let mut protected = Vec::new();
+    if let Some(content_type) = &file.header.content_type {
+        protected.push((
+            Value::from(COSE_CONTENT_TYPE),
+            Value::Text(content_type.clone()),
+        ));
+    }
+    protected.push((
+        Value::Text(COSE_VERSION.into()),
+        Value::Array(alloc::vec![
+            file.version_major.into(),
+            file.version_minor.into(),
+        ]),
+    ));
+    protected.push((
+        Value::Text(COSE_FLAGS.into()),
+        u16::from_le_bytes(file.flags.to_bytes()).into(),
+    ));
+    protected.push((Value::Text(COSE_HEADER.into()), Value::Bytes(header_bytes)));
+
+    let mut protected_bytes = Vec::new();
+    ciborium::into_writer(&Value::Map(protected), &mut protected_bytes)
+        .map_err(|e| AletheiaError::CborEncode(e.to_string()))?;
+
+    let mut cert_chain_bytes = Vec::new();
+    ciborium::into_writer(&file.certificate_chain, &mut cert_chain_bytes)
+        .map_err(|e| AletheiaError::CborEncode(e.to_string()))?;
+
+    let mut unprotected = alloc::vec![(
+        Value::Text(COSE_CERT_CHAIN.into()),
+        Value::Bytes(cert_chain_bytes),
+    )];
+    // The trailer is stored without its length prefix
+    let trailer = encode_trailer(file)?;
+    if let Some(trailer_bytes) = trailer.get(4..) {
+        unprotected.push((
+            Value::Text(COSE_TRAILER.into()),
+            Value::Bytes(trailer_bytes.to_vec()),
+        ));
+    }
+
+    let payload = if file.flags.is_detached() {
+        Value::Null
+    } else {
+        Value::Bytes(file.payload.clone())
+    };
+
+    let message = Value::Tag(
+        COSE_SIGN1_TAG,
+        alloc::boxed::Box::new(Value::Array(alloc::vec![
+            Value::Bytes(protected_bytes),
+            Value::Map(unprotected),
+            payload,
+            Value::Bytes(file.signature.clone()),
+        ])),
+    );
+
+    let mut buffer = Vec::new();
+    ciborium::into_writer(&message, &mut buffer)
+        .map_err(|e| AletheiaError::CborEncode(e.to_string()))?;
+    Ok(buffer)
+}
+
+/// Deserialize an Aletheia file from a COSE_Sign1 message written by [`to_cose`]
+///
+/// Untagged messages are accepted as well.
+pub fn from_cose(data: &[u8]) -> Result<AletheiaFile> {
+    use ciborium::Value;
+
+    let invalid = |msg: &str| AletheiaError::CborDecode(alloc::format!("COSE_Sign1: {msg}"));
+
+    let message: Value =
+        ciborium::from_reader(data).map_err(|e| AletheiaError::CborDecode(e.to_string()))?;
+    let message = match message {
+        Value::Tag(COSE_SIGN1_TAG, inner) => *inner,
+        Value::Tag(..) => return Err(invalid("not a COSE_Sign1 message")),
+        other => other,
+    };
+    let [protected, unprotected, payload, signature]: [Value; 4] = message
+        .into_array()
+        .ok()
+        .and_then(|items| items.try_into().ok())
+        .ok_or_else(|| invalid("expected an array of 4 items"))?;
+
+    let protected_bytes = protected
+        .into_bytes()
+        .map_err(|_| invalid("protected header is not a byte string"))?;
+    let protected: Value = ciborium::from_reader(protected_bytes.as_slice())
+        .map_err(|e| AletheiaError::CborDecode(e.to_string()))?;
+    let protected = protected
+        .into_map()
+        .map_err(|_| invalid("protected header is not a map"))?;
+    let unprotected = unprotected
+        .into_map()
+        .map_err(|_| invalid("unprotected header is not a map"))?;
+
+    let field = |map: &[(Value, Value)], label: &str| {
+        map.iter()
+            .find(|(key, _)| key.as_text() == Some(label))
+            .map(|(_, value)| value.clone())
+    };
+
+    let version = field(&protected, COSE_VERSION)
+        .and_then(|value| value.into_array().ok())
+        .ok_or_else(|| invalid("missing version"))?;
+    let (version_major, version_minor) = match version.as_slice() {
+        [major, minor] => (
+            major.as_integer().and_then(|v| u8::try_from(v).ok()),
+            minor.as_integer().and_then(|v| u8::try_from(v).ok()),
+        ),
+        _ => (None, None),
+    };
+    let (Some(version_major), Some(version_minor)) = (version_major, version_minor) else {
+        return Err(invalid("invalid version"));
+    };
+    if version_major != 1 {
+        return Err(AletheiaError::UnsupportedVersion {
+            major: version_major,
+            minor: version_minor,
+        });
+    }
+
+    let flags = field(&protected, COSE_FLAGS)
+        .and_then(|value| value.as_integer())
+        .and_then(|value| u16::try_from(value).ok())
+        .ok_or_else(|| invalid("missing flags"))?;
+    let flags = Flags::from_bytes(flags.to_le_bytes());
+
+    let header_bytes = field(&protected, COSE_HEADER)
+        .and_then(|value| value.into_bytes().ok())
+        .ok_or_else(|| invalid("missing header"))?;
+    let header: Header = ciborium::from_reader(header_bytes.as_slice())
+        .map_err(|e| AletheiaError::CborDecode(e.to_string()))?;
+
+    let cert_chain_bytes = field(&unprotected, COSE_CERT_CHAIN)
+        .and_then(|value| value.into_bytes().ok())
+        .ok_or_else(|| invalid("missing certificate chain"))?;
+    let certificate_chain: Vec<Certificate> = ciborium::from_reader(cert_chain_bytes.as_slice())
+        .map_err(|e| AletheiaError::CborDecode(e.to_string()))?;
+
+    let trailer = match field(&unprotected, COSE_TRAILER) {
+        Some(value) => {
+            let trailer_bytes = value
+                .into_bytes()
+                .map_err(|_| invalid("trailer is not a byte string"))?;
+            ciborium::from_reader(trailer_bytes.as_slice())
+                .map_err(|e| AletheiaError::CborDecode(e.to_string()))?
+        }
+        None => Trailer::default(),
+    };
+
+    let payload = match payload {
+        Value::Null => Vec::new(),
+        Value::Bytes(payload) => payload,
+        _ => return Err(invalid("payload is not a byte string")),
+    };
+    let signature = signature
+        .into_bytes()
+        .map_err(|_| invalid("signature is not a byte string"))?;
 
     Ok(AletheiaFile {
         version_major,
@@ -122,6 +938,13 @@ pub fn from_bytes(data: &[u8]) -> Result<AletheiaFile> {
         payload,
         certificate_chain,
         signature,
+        pq_signature: trailer.pq_signature,
+        co_signatures: trailer.co_signatures,
+        countersignatures: trailer.countersignatures,
+        timestamp_tokens: trailer.timestamp_tokens,
+        log_entries: trailer.log_entries,
+        opentimestamps: trailer.opentimestamps,
+        status_assertions: trailer.status_assertions,
     })
 }
 
@@ -129,7 +952,10 @@ pub fn from_bytes(data: &[u8]) -> Result<AletheiaFile> {
 #[cfg(feature = "std")]
 mod std_io {
     use super::*;
-    use std::io::{Read, Write};
+    use std::io::{Read, Seek, SeekFrom, Write};
+
+    /// Size of the buffer used when streaming payloads
+    const STREAM_CHUNK_SIZE: usize = 64 * 1024;
 
     /// Write an Aletheia file to a writer
     pub fn write<W: Write>(file: &AletheiaFile, mut writer: W) -> Result<()> {
@@ -138,18 +964,90 @@ mod std_io {
         Ok(())
     }
 
+    /// Options for writing files to a path
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+    pub struct WriteOptions {
+        /// Also sync the directory after renaming, so the new file survives
+        /// a crash right after the write returns
+        pub sync_parent_dir: bool,
+    }
+
+    impl WriteOptions {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Sync the parent directory after the rename (Unix only)
+        pub fn with_sync_parent_dir(mut self, sync: bool) -> Self {
+            self.sync_parent_dir = sync;
+            self
+        }
+    }
+
     /// Write an Aletheia file to a path
+    ///
+    /// The file is written to a temporary file in the same directory, synced
+    /// to disk and renamed over `path`, so a crash leaves either the old file
+    /// or the complete new one, never a partial write.
     pub fn write_to_file(file: &AletheiaFile, path: impl AsRef<std::path::Path>) -> Result<()> {
-        let f = std::fs::File::create(path)?;
-        let writer = std::io::BufWriter::new(f);
-        write(file, writer)
+        write_to_file_with_options(file, path, &WriteOptions::default())
+    }
+
+    /// Write an Aletheia file to a path atomically with explicit options
+    pub fn write_to_file_with_options(
+        file: &AletheiaFile,
+        path: impl AsRef<std::path::Path>,
+        options: &WriteOptions,
+    ) -> Result<()> {
+        write_atomic(path.as_ref(), &to_bytes(file)?, options)
+    }
+
+    /// Path of a temporary file next to `path`
+    pub(crate) fn temp_path(path: &std::path::Path) -> std::path::PathBuf {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        path.with_file_name(format!(".{}.{:016x}.tmp", name, rand::random::<u64>()))
+    }
+
+    /// Write `bytes` to a temporary file, sync it and rename it over `path`
+    fn write_atomic(path: &std::path::Path, bytes: &[u8], options: &WriteOptions) -> Result<()> {
+        let temp = temp_path(path);
+        let written = (|| {
+            let mut f = std::fs::File::create_new(&temp)?;
+            f.write_all(bytes)?;
+            f.sync_all()?;
+            std::fs::rename(&temp, path)
+        })();
+        if let Err(e) = written {
+            let _ = std::fs::remove_file(&temp);
+            return Err(e.into());
+        }
+
+        #[cfg(unix)]
+        if options.sync_parent_dir {
+            let parent = path.parent().filter(|p| !p.as_os_str().is_empty());
+            std::fs::File::open(parent.unwrap_or(std::path::Path::new(".")))?.sync_all()?;
+        }
+        #[cfg(not(unix))]
+        let _ = options;
+        Ok(())
     }
 
     /// Read an Aletheia file from a reader
-    pub fn read<R: Read>(mut reader: R) -> Result<AletheiaFile> {
+    pub fn read<R: Read>(reader: R) -> Result<AletheiaFile> {
+        read_with_limits(reader, &ParseLimits::default())
+    }
+
+    /// Read an Aletheia file from a reader, enforcing `limits`
+    ///
+    /// Stops reading once the input is longer than the limits allow in total.
+    pub fn read_with_limits<R: Read>(reader: R, limits: &ParseLimits) -> Result<AletheiaFile> {
+        let max_len = limits.max_file_len();
         let mut buffer = Vec::new();
-        reader.read_to_end(&mut buffer)?;
-        from_bytes(&buffer)
+        let read = reader
+            .take(max_len.saturating_add(1))
+            .read_to_end(&mut buffer)? as u64;
+        ParseLimits::check_len("file", read, max_len)?;
+        from_bytes_with_limits(&buffer, limits)
     }
 
     /// Read an Aletheia file from a path
@@ -169,11 +1067,635 @@ mod std_io {
             Err(e) => Err(e.into()),
         }
     }
+
+    /// Where a signed file found by [`scan_dir`] keeps its manifest
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum ScanFormat {
+        /// An Aletheia file (`.alx` envelope or detached manifest)
+        Alx,
+        /// A PNG with an embedded manifest
+        Png,
+        /// A JPEG with an embedded manifest
+        Jpeg,
+    }
+
+    /// A signed file found by [`scan_dir`]
+    #[derive(Debug)]
+    pub struct ScanEntry {
+        /// Path of the file
+        pub path: std::path::PathBuf,
+        /// Where the file keeps its manifest
+        pub format: ScanFormat,
+        /// Version, flags and header of the manifest, or why they could not
+        /// be read
+        pub metadata: Result<PeekedHeader>,
+    }
+
+    /// Iterator over the signed files below a directory, see [`scan_dir`]
+    pub struct ScanDir {
+        dirs: Vec<std::fs::ReadDir>,
+    }
+
+    impl Iterator for ScanDir {
+        type Item = ScanEntry;
+
+        fn next(&mut self) -> Option<ScanEntry> {
+            loop {
+                let dir = self.dirs.last_mut()?;
+                let Some(entry) = dir.next() else {
+                    self.dirs.pop();
+                    continue;
+                };
+                // Unreadable entries are skipped; symbolic links are not
+                // followed, so the walk cannot loop
+                let Ok(entry) = entry else { continue };
+                let Ok(file_type) = entry.file_type() else {
+                    continue;
+                };
+                if file_type.is_dir() {
+                    if let Ok(subdir) = std::fs::read_dir(entry.path()) {
+                        self.dirs.push(subdir);
+                    }
+                } else if file_type.is_file()
+                    && let Some(found) = scan_file(entry.path())
+                {
+                    return Some(found);
+                }
+            }
+        }
+    }
+
+    /// Walk the tree below `path`, yielding every signed file
+    ///
+    /// Aletheia files are recognized by their magic bytes whatever their
+    /// extension, and PNG and JPEG files are included when they carry an
+    /// embedded manifest. Only the header is decoded and nothing is verified,
+    /// so the entries are suitable for listing and indexing but not for trust
+    /// decisions. Unreadable files and directories are skipped.
+    pub fn scan_dir(path: impl AsRef<std::path::Path>) -> Result<ScanDir> {
+        Ok(ScanDir {
+            dirs: vec![std::fs::read_dir(path)?],
+        })
+    }
+
+    /// Identify a signed file and read its manifest's header
+    fn scan_file(path: std::path::PathBuf) -> Option<ScanEntry> {
+        let mut file = std::fs::File::open(&path).ok()?;
+        let mut magic = [0u8; 8];
+        file.read_exact(&mut magic).ok()?;
+
+        if &magic == MAGIC_BYTES {
+            let reader = std::io::BufReader::new(magic.chain(file));
+            let metadata = peek_header(reader);
+            return Some(ScanEntry {
+                path,
+                format: ScanFormat::Alx,
+                metadata,
+            });
+        }
+
+        let format = if magic.starts_with(b"\x89PNG") {
+            ScanFormat::Png
+        } else if magic.starts_with(&[0xFF, 0xD8]) {
+            ScanFormat::Jpeg
+        } else {
+            return None;
+        };
+        let mut data = magic.to_vec();
+        file.read_to_end(&mut data).ok()?;
+        let manifest = match format {
+            ScanFormat::Png => {
+                crate::embed::png::manifest_bytes(&data).map(|m| m.map(<[u8]>::to_vec))
+            }
+            _ => crate::embed::jpeg::manifest_bytes(&data),
+        };
+        let metadata = match manifest {
+            Ok(None) => return None,
+            Ok(Some(manifest)) => peek_header(manifest.as_slice()),
+            Err(e) => Err(e),
+        };
+        Some(ScanEntry {
+            path,
+            format,
+            metadata,
+        })
+    }
+
+    /// The sections of an Aletheia file before the payload
+    #[derive(Debug, Clone)]
+    pub struct PeekedHeader {
+        /// Major format version
+        pub version_major: u8,
+        /// Minor format version
+        pub version_minor: u8,
+        /// Feature flags
+        pub flags: Flags,
+        /// Decoded header
+        pub header: Header,
+    }
+
+    /// Read the version, flags and header of an Aletheia file
+    ///
+    /// Stops after the header, leaving the payload, certificate chain and
+    /// signature unread, so metadata such as the creator and content type can
+    /// be indexed without loading whole files. Nothing is verified: the
+    /// header is only trustworthy once the file has been verified.
+    pub fn peek_header<R: Read>(reader: R) -> Result<PeekedHeader> {
+        peek_header_with_limits(reader, &ParseLimits::default())
+    }
+
+    /// Read the version, flags and header, enforcing `limits`
+    pub fn peek_header_with_limits<R: Read>(
+        mut reader: R,
+        limits: &ParseLimits,
+    ) -> Result<PeekedHeader> {
+        read_header_section(&mut reader, limits).map(|(peeked, _)| peeked)
+    }
+
+    /// Read magic bytes, version, flags and header, returning the header as
+    /// stored along with the decoded sections
+    fn read_header_section<R: Read>(
+        reader: &mut R,
+        limits: &ParseLimits,
+    ) -> Result<(PeekedHeader, Vec<u8>)> {
+        // Magic bytes
+        let mut magic = [0u8; 8];
+        read_exact(reader, &mut magic)?;
+        if &magic != MAGIC_BYTES {
+            return Err(AletheiaError::InvalidMagic);
+        }
+
+        // Version
+        let mut version = [0u8; 2];
+        read_exact(reader, &mut version)?;
+        let (version_major, version_minor) = (version[0], version[1]);
+
+        if version_major != 1 {
+            return Err(AletheiaError::UnsupportedVersion {
+                major: version_major,
+                minor: version_minor,
+            });
+        }
+
+        // Flags
+        let mut flags_bytes = [0u8; 2];
+        read_exact(reader, &mut flags_bytes)?;
+        let flags = Flags::from_bytes(flags_bytes);
+
+        // Header
+        let mut header_len = [0u8; 4];
+        read_exact(reader, &mut header_len)?;
+        let header_len = u32::from_le_bytes(header_len);
+        ParseLimits::check_len("header", header_len as u64, limits.max_header_len as u64)?;
+        let mut header_bytes = vec![0u8; header_len as usize];
+        read_exact(reader, &mut header_bytes)?;
+        let header: Header = ciborium::from_reader(&header_bytes[..])
+            .map_err(|e| AletheiaError::CborDecode(e.to_string()))?;
+
+        let peeked = PeekedHeader {
+            version_major,
+            version_minor,
+            flags,
+            header,
+        };
+        Ok((peeked, header_bytes))
+    }
+
+    /// An unsigned draft written by [`write_unsigned`]
+    pub(crate) struct Draft {
+        pub peeked: PeekedHeader,
+        pub header_bytes: Vec<u8>,
+        pub payload_len: u64,
+    }
+
+    /// Read a draft up to its payload, leaving the reader at the payload
+    ///
+    /// Fails unless the draft ends right after the payload.
+    pub(crate) fn read_draft<R: Read + Seek>(reader: &mut R) -> Result<Draft> {
+        reader.seek(SeekFrom::Start(0))?;
+        let (peeked, header_bytes) = read_header_section(reader, &ParseLimits::default())?;
+        if peeked.flags.is_chunked() || peeked.flags.is_detached() {
+            return Err(AletheiaError::InvalidDraft(
+                "Chunked and detached drafts are not supported".into(),
+            ));
+        }
+
+        let mut payload_len = [0u8; 8];
+        read_exact(reader, &mut payload_len)?;
+        let payload_len = u64::from_le_bytes(payload_len);
+        let payload_offset = reader.stream_position()?;
+        let end = reader.seek(SeekFrom::End(0))?;
+        match end.checked_sub(payload_offset) {
+            Some(len) if len == payload_len => {}
+            Some(len) if len > payload_len => {
+                return Err(AletheiaError::InvalidDraft(
+                    "Data follows the payload; the file may already be signed".into(),
+                ));
+            }
+            _ => return Err(AletheiaError::UnexpectedEof),
+        }
+        reader.seek(SeekFrom::Start(payload_offset))?;
+
+        Ok(Draft {
+            peeked,
+            header_bytes,
+            payload_len,
+        })
+    }
+
+    /// Append the certificate chain, signatures and stapled status
+    /// assertions to a draft
+    pub(crate) fn append_signature<W: Write + Seek>(
+        writer: &mut W,
+        cert_chain_bytes: &[u8],
+        signature: &[u8],
+        pq_signature: Option<&[u8]>,
+        status_assertions: &[StatusAssertion],
+    ) -> Result<()> {
+        writer.seek(SeekFrom::End(0))?;
+        writer.write_all(&encode_signature_section(
+            cert_chain_bytes,
+            signature,
+            pq_signature,
+            status_assertions,
+        )?)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    impl FileStructure {
+        /// Copy the original payload from `reader` to `writer`
+        ///
+        /// `reader` must be the one the structure was read from. An
+        /// uncompressed payload is copied in chunks; a compressed one is read
+        /// and decompressed in memory. Returns the number of bytes written.
+        pub fn extract_payload_to<R: Read + Seek, W: Write>(
+            &self,
+            reader: &mut R,
+            mut writer: W,
+        ) -> Result<u64> {
+            if self.flags.is_detached() {
+                return Err(AletheiaError::DetachedPayload);
+            }
+            reader.seek(SeekFrom::Start(self.payload_offset))?;
+            if self.flags.is_compressed() {
+                let mut stored = Vec::new();
+                read_chunks(reader, self.payload_len, |chunk| {
+                    stored.extend_from_slice(chunk);
+                    Ok(())
+                })
+                .map_err(eof_error)?;
+                let payload = crate::types::decompress_payload(self.flags, &stored)?;
+                writer.write_all(&payload)?;
+                return Ok(payload.len() as u64);
+            }
+            read_chunks(reader, self.payload_len, |chunk| writer.write_all(chunk))
+                .map_err(eof_error)?;
+            Ok(self.payload_len)
+        }
+    }
+
+    /// Read everything except the payload from a seekable reader
+    ///
+    /// The payload is skipped by seeking; its position and length are
+    /// recorded so it can be extracted on demand with
+    /// [`FileStructure::extract_payload_to`]. Nothing is verified.
+    pub fn read_structure<R: Read + Seek>(reader: &mut R) -> Result<FileStructure> {
+        read_structure_with_limits(reader, &ParseLimits::default())
+    }
+
+    /// Read everything except the payload, enforcing `limits`
+    pub fn read_structure_with_limits<R: Read + Seek>(
+        reader: &mut R,
+        limits: &ParseLimits,
+    ) -> Result<FileStructure> {
+        let (
+            PeekedHeader {
+                version_major,
+                version_minor,
+                flags,
+                header,
+            },
+            header_bytes,
+        ) = read_header_section(reader, limits)?;
+
+        // Payload (skipped)
+        let mut payload_len = [0u8; 8];
+        read_exact(reader, &mut payload_len)?;
+        let payload_len = u64::from_le_bytes(payload_len);
+        let payload_offset = reader.stream_position()?;
+        let end = reader.seek(SeekFrom::End(0))?;
+        if end.saturating_sub(payload_offset) < payload_len {
+            return Err(AletheiaError::UnexpectedEof);
+        }
+        reader.seek(SeekFrom::Start(payload_offset + payload_len))?;
+
+        // Certificate chain, signature and trailer
+        let mut section = Vec::new();
+        reader
+            .take(limits.max_signature_section_len() as u64)
+            .read_to_end(&mut section)?;
+        let SignatureSection {
+            certificate_chain,
+            cert_chain_bytes,
+            signature,
+            trailer,
+        } = parse_signature_section(&section, limits)?;
+
+        Ok(FileStructure {
+            version_major,
+            version_minor,
+            flags,
+            header,
+            header_bytes,
+            payload_offset,
+            payload_len,
+            certificate_chain,
+            cert_chain_bytes,
+            signature,
+            pq_signature: trailer.pq_signature,
+            co_signatures: trailer.co_signatures,
+            countersignatures: trailer.countersignatures,
+            timestamp_tokens: trailer.timestamp_tokens,
+            log_entries: trailer.log_entries,
+            opentimestamps: trailer.opentimestamps,
+            status_assertions: trailer.status_assertions,
+        })
+    }
+
+    /// Read exactly `len` bytes from `reader`, handing them to `f` chunk by chunk
+    pub(crate) fn read_chunks<R: Read>(
+        reader: &mut R,
+        len: u64,
+        mut f: impl FnMut(&[u8]) -> std::io::Result<()>,
+    ) -> std::io::Result<()> {
+        let mut buffer = vec![0u8; STREAM_CHUNK_SIZE];
+        let mut remaining = len;
+        while remaining > 0 {
+            let want = remaining.min(STREAM_CHUNK_SIZE as u64) as usize;
+            reader.read_exact(&mut buffer[..want])?;
+            f(&buffer[..want])?;
+            remaining -= want as u64;
+        }
+        Ok(())
+    }
+
+    /// `read_exact` that reports truncation as [`AletheiaError::UnexpectedEof`]
+    fn read_exact<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<()> {
+        reader.read_exact(buf).map_err(eof_error)
+    }
+
+    /// Report truncation as [`AletheiaError::UnexpectedEof`]
+    fn eof_error(e: std::io::Error) -> AletheiaError {
+        match e.kind() {
+            std::io::ErrorKind::UnexpectedEof => AletheiaError::UnexpectedEof,
+            _ => e.into(),
+        }
+    }
 }
 
 #[cfg(feature = "std")]
 pub use std_io::*;
 
+// Async file I/O for tokio-based services
+#[cfg(feature = "tokio")]
+mod tokio_io {
+    use super::*;
+    use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+    /// Write an Aletheia file to an async writer
+    pub async fn write_async<W: AsyncWrite + Unpin>(
+        file: &AletheiaFile,
+        mut writer: W,
+    ) -> Result<()> {
+        let bytes = to_bytes(file)?;
+        writer.write_all(&bytes).await?;
+        writer.flush().await?;
+        Ok(())
+    }
+
+    /// Write an Aletheia file to a path without blocking the runtime
+    ///
+    /// Like [`write_to_file`], the file is written to a temporary file,
+    /// synced and renamed over `path`.
+    pub async fn write_to_file_async(
+        file: &AletheiaFile,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<()> {
+        let path = path.as_ref();
+        let temp = temp_path(path);
+        let bytes = to_bytes(file)?;
+        let written = async {
+            let mut f = tokio::fs::File::create_new(&temp).await?;
+            f.write_all(&bytes).await?;
+            f.sync_all().await?;
+            tokio::fs::rename(&temp, path).await
+        }
+        .await;
+        if let Err(e) = written {
+            let _ = tokio::fs::remove_file(&temp).await;
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    /// Read an Aletheia file from an async reader
+    pub async fn read_async<R: AsyncRead + Unpin>(reader: R) -> Result<AletheiaFile> {
+        read_with_limits_async(reader, &ParseLimits::default()).await
+    }
+
+    /// Read an Aletheia file from an async reader, enforcing `limits`
+    ///
+    /// Stops reading once the input is longer than the limits allow in total.
+    pub async fn read_with_limits_async<R: AsyncRead + Unpin>(
+        reader: R,
+        limits: &ParseLimits,
+    ) -> Result<AletheiaFile> {
+        let max_len = limits.max_file_len();
+        let mut buffer = Vec::new();
+        let read = reader
+            .take(max_len.saturating_add(1))
+            .read_to_end(&mut buffer)
+            .await? as u64;
+        ParseLimits::check_len("file", read, max_len)?;
+        from_bytes_with_limits(&buffer, limits)
+    }
+
+    /// Read an Aletheia file from a path without blocking the runtime
+    pub async fn read_from_file_async(path: impl AsRef<std::path::Path>) -> Result<AletheiaFile> {
+        let f = tokio::fs::File::open(path).await?;
+        read_async(tokio::io::BufReader::new(f)).await
+    }
+}
+
+#[cfg(feature = "tokio")]
+pub use tokio_io::*;
+
+// JWS serialization for JSON APIs
+#[cfg(feature = "jws")]
+mod jws {
+    use super::*;
+    use alloc::string::String;
+    use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+
+    /// Header parameter carrying the envelope fields (listed in `crit`)
+    const ALX_PARAM: &str = "alx";
+
+    /// JOSE header of an Aletheia JWS
+    #[derive(Serialize, Deserialize)]
+    struct JwsHeader {
+        alg: String,
+        crit: Vec<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        cty: Option<String>,
+        alx: AlxParams,
+    }
+
+    /// Envelope fields, with CBOR sections in base64url
+    #[derive(Serialize, Deserialize)]
+    struct AlxParams {
+        version: [u8; 2],
+        flags: u16,
+        header: String,
+        cert_chain: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        trailer: Option<String>,
+    }
+
+    /// Flattened JWS JSON serialization
+    #[derive(Serialize, Deserialize)]
+    struct JwsJson {
+        protected: String,
+        payload: String,
+        signature: String,
+    }
+
+    fn encode_parts(file: &AletheiaFile) -> Result<JwsJson> {
+        let mut header_bytes = Vec::new();
+        ciborium::into_writer(&file.header, &mut header_bytes)
+            .map_err(|e| AletheiaError::CborEncode(e.to_string()))?;
+        let mut cert_chain_bytes = Vec::new();
+        ciborium::into_writer(&file.certificate_chain, &mut cert_chain_bytes)
+            .map_err(|e| AletheiaError::CborEncode(e.to_string()))?;
+        // The trailer is stored without its length prefix
+        let trailer = encode_trailer(file)?;
+
+        let header = JwsHeader {
+            alg: "EdDSA".into(),
+            crit: alloc::vec![ALX_PARAM.into()],
+            cty: file.header.content_type.clone(),
+            alx: AlxParams {
+                version: [file.version_major, file.version_minor],
+                flags: u16::from_le_bytes(file.flags.to_bytes()),
+                header: URL_SAFE_NO_PAD.encode(header_bytes),
+                cert_chain: URL_SAFE_NO_PAD.encode(cert_chain_bytes),
+                trailer: trailer.get(4..).map(|bytes| URL_SAFE_NO_PAD.encode(bytes)),
+            },
+        };
+        let header_json =
+            serde_json::to_vec(&header).map_err(|e| AletheiaError::Jws(e.to_string()))?;
+
+        Ok(JwsJson {
+            protected: URL_SAFE_NO_PAD.encode(header_json),
+            payload: URL_SAFE_NO_PAD.encode(&file.payload),
+            signature: URL_SAFE_NO_PAD.encode(&file.signature),
+        })
+    }
+
+    /// Serialize an Aletheia file as a compact JWS
+    ///
+    /// The envelope fields travel in the critical `alx` header parameter, and
+    /// the signature is the Aletheia signature over the usual signature input
+    /// rather than over the JWS signing input. Generic JOSE libraries reject
+    /// the token because they do not understand `alx`; it is checked with
+    /// [`from_jws`] and the Aletheia verifier. A detached manifest has an
+    /// empty payload.
+    pub fn to_jws(file: &AletheiaFile) -> Result<String> {
+        let parts = encode_parts(file)?;
+        Ok(alloc::format!(
+            "{}.{}.{}",
+            parts.protected,
+            parts.payload,
+            parts.signature
+        ))
+    }
+
+    /// Serialize an Aletheia file as a flattened JSON JWS
+    ///
+    /// See [`to_jws`] for the layout.
+    pub fn to_jws_json(file: &AletheiaFile) -> Result<String> {
+        serde_json::to_string(&encode_parts(file)?).map_err(|e| AletheiaError::Jws(e.to_string()))
+    }
+
+    /// Deserialize an Aletheia file from a compact or flattened JSON JWS
+    pub fn from_jws(jws: &str) -> Result<AletheiaFile> {
+        let jws = jws.trim();
+        let parts = if jws.starts_with('{') {
+            serde_json::from_str(jws).map_err(|e| AletheiaError::Jws(e.to_string()))?
+        } else {
+            let mut parts = jws.split('.');
+            match (parts.next(), parts.next(), parts.next(), parts.next()) {
+                (Some(protected), Some(payload), Some(signature), None) => JwsJson {
+                    protected: protected.into(),
+                    payload: payload.into(),
+                    signature: signature.into(),
+                },
+                _ => return Err(AletheiaError::Jws("Expected three segments".into())),
+            }
+        };
+
+        let header: JwsHeader = serde_json::from_slice(&decode(&parts.protected)?)
+            .map_err(|e| AletheiaError::Jws(e.to_string()))?;
+        if header.alg != "EdDSA" || !header.crit.iter().any(|param| param == ALX_PARAM) {
+            return Err(AletheiaError::Jws("Not an Aletheia JWS".into()));
+        }
+
+        let [version_major, version_minor] = header.alx.version;
+        if version_major != 1 {
+            return Err(AletheiaError::UnsupportedVersion {
+                major: version_major,
+                minor: version_minor,
+            });
+        }
+
+        let header_bytes = decode(&header.alx.header)?;
+        let cert_chain_bytes = decode(&header.alx.cert_chain)?;
+        let trailer = match &header.alx.trailer {
+            Some(trailer) => ciborium::from_reader(decode(trailer)?.as_slice())
+                .map_err(|e| AletheiaError::CborDecode(e.to_string()))?,
+            None => Trailer::default(),
+        };
+
+        Ok(AletheiaFile {
+            version_major,
+            version_minor,
+            flags: Flags::from_bytes(header.alx.flags.to_le_bytes()),
+            header: ciborium::from_reader(header_bytes.as_slice())
+                .map_err(|e| AletheiaError::CborDecode(e.to_string()))?,
+            payload: decode(&parts.payload)?,
+            certificate_chain: ciborium::from_reader(cert_chain_bytes.as_slice())
+                .map_err(|e| AletheiaError::CborDecode(e.to_string()))?,
+            signature: decode(&parts.signature)?,
+            pq_signature: trailer.pq_signature,
+            co_signatures: trailer.co_signatures,
+            countersignatures: trailer.countersignatures,
+            timestamp_tokens: trailer.timestamp_tokens,
+            log_entries: trailer.log_entries,
+            opentimestamps: trailer.opentimestamps,
+            status_assertions: trailer.status_assertions,
+        })
+    }
+
+    fn decode(segment: &str) -> Result<Vec<u8>> {
+        URL_SAFE_NO_PAD
+            .decode(segment)
+            .map_err(|e| AletheiaError::Jws(e.to_string()))
+    }
+}
+
+#[cfg(feature = "jws")]
+pub use jws::*;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -209,6 +1731,134 @@ mod tests {
         signer.sign(payload, header).unwrap()
     }
 
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_async_file_io() {
+        let original = create_test_file();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("test.alx");
+
+        write_to_file_async(&original, &path).await.unwrap();
+        let loaded = read_from_file_async(&path).await.unwrap();
+        assert_eq!(loaded.payload, original.payload);
+        assert_eq!(loaded.signature, original.signature);
+
+        // Oversized input is refused
+        let bytes = to_bytes(&original).unwrap();
+        let limits = ParseLimits::new().with_max_payload_len(4);
+        let result = read_with_limits_async(bytes.as_slice(), &limits).await;
+        assert!(matches!(result, Err(AletheiaError::SectionTooLarge { .. })));
+    }
+
+    #[test]
+    fn test_sections() {
+        let bytes = to_bytes(&create_test_file()).unwrap();
+        let found = sections(&bytes);
+        let names: Vec<&str> = found.iter().map(|section| section.name).collect();
+        assert_eq!(
+            names,
+            [
+                "magic",
+                "version",
+                "flags",
+                "header",
+                "payload",
+                "certificate chain",
+                "signature"
+            ]
+        );
+        assert_eq!(found[0].start, 0);
+        assert_eq!(found.last().unwrap().end, bytes.len());
+        assert!(found.windows(2).all(|pair| pair[0].end == pair[1].start));
+        assert!(found.iter().all(|section| section.complete));
+
+        // A truncated file is located up to where its data ends
+        let found = sections(&bytes[..found[4].start + 20]);
+        assert_eq!(found.len(), 5);
+        assert!(!found[4].complete);
+        assert_eq!(found[4].end, found[4].start + 20);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_peek_header() {
+        let file = create_test_file();
+        let bytes = to_bytes(&file).unwrap();
+
+        // Only the bytes up to the end of the header are needed
+        let header_end = 8 + 2 + 2 + 4 + from_bytes_ref(&bytes).unwrap().header_bytes.len();
+        let peeked = peek_header(&bytes[..header_end]).unwrap();
+        assert_eq!(peeked.header.creator_id, "alice@example.com");
+        assert_eq!(peeked.header.content_type.as_deref(), Some("text/plain"));
+        assert_eq!(peeked.version_major, file.version_major);
+
+        assert!(matches!(
+            peek_header(&bytes[..header_end - 1]),
+            Err(AletheiaError::UnexpectedEof)
+        ));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_read_structure_and_extract_payload() {
+        let file = create_test_file();
+        let mut reader = std::io::Cursor::new(to_bytes(&file).unwrap());
+
+        let structure = read_structure(&mut reader).unwrap();
+        assert_eq!(structure.header.creator_id, "alice@example.com");
+        assert_eq!(structure.payload_len, file.payload.len() as u64);
+        assert_eq!(structure.certificate_chain.len(), 2);
+        assert_eq!(structure.signature, file.signature);
+
+        let mut payload = Vec::new();
+        let written = structure
+            .extract_payload_to(&mut reader, &mut payload)
+            .unwrap();
+        assert_eq!(written, file.payload.len() as u64);
+        assert_eq!(payload, file.payload);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_write_to_file_replaces_atomically() {
+        let file = create_test_file();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.alx");
+        std::fs::write(&path, "previous contents").unwrap();
+
+        let options = WriteOptions::new().with_sync_parent_dir(true);
+        write_to_file_with_options(&file, &path, &options).unwrap();
+        assert_eq!(read_from_file(&path).unwrap().payload, file.payload);
+
+        // No temporary file is left behind
+        let names: Vec<_> = std::fs::read_dir(dir.path()).unwrap().collect();
+        assert_eq!(names.len(), 1);
+
+        // A failed write leaves nothing behind either
+        let missing = dir.path().join("missing/out.alx");
+        assert!(write_to_file(&file, &missing).is_err());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_scan_dir() {
+        let file = create_test_file();
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("nested")).unwrap();
+        write_to_file(&file, dir.path().join("nested/signed.bin")).unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "not signed").unwrap();
+        std::fs::write(dir.path().join("broken.alx"), b"ALETHEIA\x01").unwrap();
+
+        let mut entries: Vec<ScanEntry> = scan_dir(dir.path()).unwrap().collect();
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+        assert_eq!(entries.len(), 2);
+        assert!(entries.iter().all(|entry| entry.format == ScanFormat::Alx));
+        assert!(entries[0].metadata.is_err());
+        let metadata = entries[1].metadata.as_ref().unwrap();
+        assert_eq!(metadata.header.creator_id, "alice@example.com");
+        assert!(entries[1].path.ends_with("nested/signed.bin"));
+    }
+
     #[test]
     fn test_to_bytes_and_from_bytes() {
         let original = create_test_file();
@@ -249,10 +1899,127 @@ mod tests {
         assert_eq!(loaded.payload, original.payload);
     }
 
+    #[test]
+    fn test_cose_roundtrip() {
+        let original = create_test_file();
+        let cose = to_cose(&original).unwrap();
+
+        // Tagged COSE_Sign1 with a 4-item array
+        assert_eq!(&cose[..2], &[0xD2, 0x84]);
+
+        let loaded = from_cose(&cose).unwrap();
+        assert_eq!(to_bytes(&loaded).unwrap(), to_bytes(&original).unwrap());
+
+        let roots = vec![original.certificate_chain[1].public_key.clone()];
+        assert!(crate::verifier::verify(&loaded, &roots).unwrap().valid);
+    }
+
+    #[test]
+    fn test_from_cose_rejects_other_messages() {
+        // COSE_Mac0 (tag 17) and a plain Aletheia file
+        let mut mac0 = Vec::new();
+        ciborium::into_writer(
+            &ciborium::Value::Tag(
+                17,
+                alloc::boxed::Box::new(ciborium::Value::Array(Vec::new())),
+            ),
+            &mut mac0,
+        )
+        .unwrap();
+        assert!(matches!(
+            from_cose(&mac0),
+            Err(AletheiaError::CborDecode(_))
+        ));
+
+        let alx = to_bytes(&create_test_file()).unwrap();
+        assert!(from_cose(&alx).is_err());
+    }
+
+    #[cfg(feature = "jws")]
+    #[test]
+    fn test_jws_roundtrip() {
+        let original = create_test_file();
+        let roots = vec![original.certificate_chain[1].public_key.clone()];
+
+        let compact = to_jws(&original).unwrap();
+        assert_eq!(compact.split('.').count(), 3);
+        let json = to_jws_json(&original).unwrap();
+
+        for jws in [compact, json] {
+            let loaded = from_jws(&jws).unwrap();
+            assert_eq!(to_bytes(&loaded).unwrap(), to_bytes(&original).unwrap());
+            assert!(crate::verifier::verify(&loaded, &roots).unwrap().valid);
+        }
+
+        // A plain EdDSA token without the `alx` parameter is refused
+        let other = "eyJhbGciOiJFZERTQSJ9.e30.AA";
+        assert!(matches!(from_jws(other), Err(AletheiaError::Jws(_))));
+    }
+
+    #[test]
+    fn test_serde_json_representation() {
+        let original = create_test_file();
+        let json = serde_json::to_value(&original).unwrap();
+
+        // Byte fields are base64, flags an integer
+        assert_eq!(json["payload"], "VGVzdCBjb250ZW50IGZvciBmaWxlIEkvTw==");
+        assert!(json["signature"].is_string());
+        assert!(json["certificate_chain"][0]["public_key"].is_string());
+        assert_eq!(json["flags"], 0);
+        assert!(json.get("co_signatures").is_none());
+
+        // The document converts back to the same envelope
+        let loaded: AletheiaFile = serde_json::from_value(json).unwrap();
+        assert_eq!(to_bytes(&loaded).unwrap(), to_bytes(&original).unwrap());
+    }
+
     #[test]
     fn test_invalid_magic() {
         let data = b"NOTVALID12345678";
         let result = from_bytes(data);
         assert!(matches!(result, Err(AletheiaError::InvalidMagic)));
     }
+
+    #[test]
+    fn test_parse_limits() {
+        let bytes = to_bytes(&create_test_file()).unwrap();
+
+        // A header length near u32::MAX is rejected before allocating
+        let mut crafted = bytes.clone();
+        crafted[12..16].copy_from_slice(&(u32::MAX - 1).to_le_bytes());
+        assert!(matches!(
+            from_bytes(&crafted),
+            Err(AletheiaError::SectionTooLarge {
+                section: "header",
+                ..
+            })
+        ));
+
+        // A payload length that would overflow the cursor is just truncation
+        let header_len = u32::from_le_bytes(bytes[12..16].try_into().unwrap()) as usize;
+        let mut crafted = bytes.clone();
+        crafted[16 + header_len..24 + header_len].copy_from_slice(&u64::MAX.to_le_bytes());
+        let unbounded = ParseLimits::new().with_max_payload_len(u64::MAX);
+        assert!(matches!(
+            from_bytes_with_limits(&crafted, &unbounded),
+            Err(AletheiaError::UnexpectedEof)
+        ));
+
+        // Tighter limits
+        let limits = ParseLimits::new().with_max_payload_len(8);
+        assert!(matches!(
+            from_bytes_with_limits(&bytes, &limits),
+            Err(AletheiaError::SectionTooLarge {
+                section: "payload",
+                ..
+            })
+        ));
+        let limits = ParseLimits::new().with_max_certificates(1);
+        assert!(matches!(
+            from_bytes_with_limits(&bytes, &limits),
+            Err(AletheiaError::TooManyCertificates { count: 2, limit: 1 })
+        ));
+        #[cfg(feature = "std")]
+        assert!(read_with_limits(&bytes[..], &ParseLimits::new().with_max_payload_len(8)).is_err());
+    }
 }
@@ -0,0 +1,576 @@
+//! Signing keys held on hardware tokens
+//!
+//! Keys on a YubiKey, smart card or HSM never leave the device: an
+//! [`ExternalSigner`] asks the device to sign instead.
+//! [`Signer::new_external`](crate::signer::Signer::new_external) signs files
+//! with one, and
+//! [`CertificateAuthority::from_external_signer`](crate::ca::CertificateAuthority::from_external_signer)
+//! runs a CA on one. [`Pkcs11Signer`] (feature `pkcs11`) reaches a device
+//! through its PKCS#11 module, selecting the key with a `pkcs11:` URI
+//! ([RFC 7512](https://www.rfc-editor.org/rfc/rfc7512)).
+//!
+//! Every signature a device returns is checked against its public key before
+//! it is used, so a misbehaving device cannot produce a file or certificate
+//! that fails verification later.
+
+extern crate alloc;
+
+use alloc::string::ToString;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use ed25519_dalek::{Signature, Signer as _, SigningKey, Verifier, VerifyingKey};
+use sha2::{Digest, Sha512};
+
+use crate::{AletheiaError, Result};
+
+/// An Ed25519 key that signs outside this process
+pub trait ExternalSigner: Send + Sync {
+    /// Ed25519 public key (32 bytes)
+    fn public_key(&self) -> Vec<u8>;
+
+    /// Sign `message` with Ed25519
+    fn sign(&self, message: &[u8]) -> Result<Vec<u8>>;
+
+    /// Sign `message` with Ed25519ph under `context`
+    ///
+    /// The device computes the SHA-512 prehash of `message` itself, as
+    /// PKCS#11 tokens do.
+    fn sign_prehashed(&self, message: &[u8], context: &[u8]) -> Result<Vec<u8>>;
+}
+
+/// The key a CA or signer signs with
+#[derive(Clone)]
+pub(crate) enum KeyHandle {
+    /// A key in memory
+    Local(SigningKey),
+    /// A key behind an external signer, with its checked public key
+    External(Arc<dyn ExternalSigner>, VerifyingKey),
+}
+
+impl KeyHandle {
+    /// Wrap an external signer, checking that its public key is valid
+    pub(crate) fn external(signer: Arc<dyn ExternalSigner>) -> Result<Self> {
+        let verifying_key =
+            VerifyingKey::try_from(signer.public_key().as_slice()).map_err(|e| {
+                AletheiaError::ExternalSigner(alloc::format!("Invalid public key: {e}"))
+            })?;
+        Ok(Self::External(signer, verifying_key))
+    }
+
+    pub(crate) fn is_external(&self) -> bool {
+        matches!(self, Self::External(..))
+    }
+
+    pub(crate) fn public_key(&self) -> Vec<u8> {
+        match self {
+            Self::Local(key) => key.verifying_key().to_bytes().to_vec(),
+            Self::External(_, verifying_key) => verifying_key.to_bytes().to_vec(),
+        }
+    }
+
+    /// Private key bytes, empty for an external key
+    pub(crate) fn private_key_bytes(&self) -> Vec<u8> {
+        match self {
+            Self::Local(key) => key.to_bytes().to_vec(),
+            Self::External(..) => Vec::new(),
+        }
+    }
+
+    /// Sign `message` with Ed25519
+    pub(crate) fn sign(&self, message: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Self::Local(key) => Ok(key.sign(message).to_bytes().to_vec()),
+            Self::External(signer, verifying_key) => {
+                let signature = signer.sign(message)?;
+                verifying_key
+                    .verify(message, &returned_signature(&signature)?)
+                    .map_err(|_| invalid_signature())?;
+                Ok(signature)
+            }
+        }
+    }
+
+    /// Sign `message` with Ed25519ph under `context`
+    pub(crate) fn sign_prehashed(&self, message: &[u8], context: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Self::Local(_) => self.sign_digest(Sha512::new_with_prefix(message), context),
+            Self::External(signer, verifying_key) => {
+                let signature = signer.sign_prehashed(message, context)?;
+                verifying_key
+                    .verify_prehashed(
+                        Sha512::new_with_prefix(message),
+                        Some(context),
+                        &returned_signature(&signature)?,
+                    )
+                    .map_err(|_| invalid_signature())?;
+                Ok(signature)
+            }
+        }
+    }
+
+    /// Sign a SHA-512 prehash with Ed25519ph under `context`
+    ///
+    /// External signers hash the message themselves, so only keys in memory
+    /// can sign a prehash computed while streaming.
+    pub(crate) fn sign_digest(&self, digest: Sha512, context: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Self::Local(key) => Ok(key
+                .sign_prehashed(digest, Some(context))
+                .map_err(|_| AletheiaError::InvalidSignature)?
+                .to_bytes()
+                .to_vec()),
+            Self::External(..) => Err(AletheiaError::ExternalSigner(
+                "External signers need the whole message; sign in memory instead of streaming"
+                    .into(),
+            )),
+        }
+    }
+}
+
+fn returned_signature(signature: &[u8]) -> Result<Signature> {
+    Signature::try_from(signature).map_err(|e| AletheiaError::ExternalSigner(e.to_string()))
+}
+
+fn invalid_signature() -> AletheiaError {
+    AletheiaError::ExternalSigner("Signature does not match the public key".into())
+}
+
+#[cfg(feature = "pkcs11")]
+pub use pkcs11::{Pkcs11Signer, Pkcs11Uri};
+
+#[cfg(feature = "pkcs11")]
+mod pkcs11 {
+    use std::path::{Path, PathBuf};
+    use std::sync::Mutex;
+
+    use cryptoki::context::{CInitializeArgs, CInitializeFlags, Pkcs11};
+    use cryptoki::mechanism::Mechanism;
+    use cryptoki::mechanism::eddsa::{EddsaParams, EddsaSignatureScheme};
+    use cryptoki::object::{Attribute, AttributeType, KeyType, ObjectClass, ObjectHandle};
+    use cryptoki::session::{Session, UserType};
+    use cryptoki::slot::Slot;
+    use cryptoki::types::AuthPin;
+
+    use super::ExternalSigner;
+    use crate::{AletheiaError, Result};
+
+    /// The parts of a `pkcs11:` URI (RFC 7512) used to find a key
+    #[derive(Debug, Clone, Default, PartialEq, Eq)]
+    pub struct Pkcs11Uri {
+        /// Label of the token (`token`)
+        pub token: Option<String>,
+        /// Slot ID (`slot-id`, or `slot`)
+        pub slot_id: Option<u64>,
+        /// Label of the key (`object`)
+        pub object: Option<String>,
+        /// ID of the key (`id`), percent-encoded or as hex digits
+        pub id: Option<Vec<u8>>,
+        /// PKCS#11 module to load (`module-path` query attribute)
+        pub module_path: Option<PathBuf>,
+        /// User PIN (`pin-value` query attribute)
+        pub pin: Option<String>,
+    }
+
+    impl Pkcs11Uri {
+        /// Whether `s` is a PKCS#11 URI rather than a file path
+        pub fn is_uri(s: &str) -> bool {
+            s.starts_with("pkcs11:")
+        }
+
+        /// Parse a `pkcs11:` URI
+        ///
+        /// Attributes that do not select a key (such as `manufacturer`) are
+        /// ignored.
+        pub fn parse(uri: &str) -> Result<Self> {
+            let rest = uri
+                .strip_prefix("pkcs11:")
+                .ok_or_else(|| invalid_uri("URI must start with pkcs11:"))?;
+            let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+
+            let mut parsed = Self::default();
+            let attributes = path
+                .split(';')
+                .chain(query.split('&'))
+                .filter(|attribute| !attribute.is_empty());
+            for attribute in attributes {
+                let (name, value) = attribute.split_once('=').ok_or_else(|| {
+                    invalid_uri(&format!("Attribute without a value: {attribute}"))
+                })?;
+                match name {
+                    "token" => parsed.token = Some(decode_text(value)?),
+                    "slot-id" | "slot" => {
+                        parsed.slot_id = Some(
+                            value
+                                .parse()
+                                .map_err(|_| invalid_uri(&format!("Invalid slot ID: {value}")))?,
+                        )
+                    }
+                    "object" => parsed.object = Some(decode_text(value)?),
+                    "id" => parsed.id = Some(decode_id(value)?),
+                    "module-path" => parsed.module_path = Some(PathBuf::from(decode_text(value)?)),
+                    "pin-value" => parsed.pin = Some(decode_text(value)?),
+                    _ => {}
+                }
+            }
+            if parsed.object.is_none() && parsed.id.is_none() {
+                return Err(invalid_uri("URI must name the key with id or object"));
+            }
+            Ok(parsed)
+        }
+    }
+
+    /// An Ed25519 key on a PKCS#11 token
+    pub struct Pkcs11Signer {
+        session: Mutex<Session>,
+        key: ObjectHandle,
+        public_key: Vec<u8>,
+    }
+
+    impl Pkcs11Signer {
+        /// Open the Ed25519 key named by `uri`
+        ///
+        /// `module` is the token's PKCS#11 library (such as
+        /// `libykcs11.so`), used when the URI has no `module-path`. `pin`
+        /// logs in as the user when the URI has no `pin-value`.
+        pub fn open(uri: &str, module: Option<&Path>, pin: Option<&str>) -> Result<Self> {
+            let uri = Pkcs11Uri::parse(uri)?;
+            let module = uri
+                .module_path
+                .as_deref()
+                .or(module)
+                .ok_or_else(|| invalid_uri("No PKCS#11 module given"))?;
+
+            let context = Pkcs11::new(module).map_err(token_error)?;
+            context
+                .initialize(CInitializeArgs::new(CInitializeFlags::OS_LOCKING_OK))
+                .map_err(token_error)?;
+            let slot = find_slot(&context, &uri)?;
+            let session = context.open_ro_session(slot).map_err(token_error)?;
+            if let Some(pin) = uri.pin.as_deref().or(pin) {
+                session
+                    .login(UserType::User, Some(&AuthPin::from(pin)))
+                    .map_err(token_error)?;
+            }
+
+            let key = find_key(&session, &uri, ObjectClass::PRIVATE_KEY)?;
+            let public = find_key(&session, &uri, ObjectClass::PUBLIC_KEY)?;
+            let public_key = session
+                .get_attributes(public, &[AttributeType::EcPoint])
+                .map_err(token_error)?
+                .into_iter()
+                .find_map(|attribute| match attribute {
+                    Attribute::EcPoint(point) => Some(point),
+                    _ => None,
+                })
+                .ok_or_else(|| {
+                    AletheiaError::ExternalSigner("Public key has no EC point".into())
+                })?;
+
+            Ok(Self {
+                session: Mutex::new(session),
+                key,
+                public_key: ec_point_bytes(public_key)?,
+            })
+        }
+
+        fn sign_with(&self, scheme: EddsaSignatureScheme, message: &[u8]) -> Result<Vec<u8>> {
+            let session = self
+                .session
+                .lock()
+                .map_err(|_| AletheiaError::ExternalSigner("Token session poisoned".into()))?;
+            session
+                .sign(
+                    &Mechanism::Eddsa(EddsaParams::new(scheme)),
+                    self.key,
+                    message,
+                )
+                .map_err(token_error)
+        }
+    }
+
+    impl ExternalSigner for Pkcs11Signer {
+        fn public_key(&self) -> Vec<u8> {
+            self.public_key.clone()
+        }
+
+        fn sign(&self, message: &[u8]) -> Result<Vec<u8>> {
+            self.sign_with(EddsaSignatureScheme::Ed25519, message)
+        }
+
+        fn sign_prehashed(&self, message: &[u8], context: &[u8]) -> Result<Vec<u8>> {
+            self.sign_with(EddsaSignatureScheme::Ed25519ph(context), message)
+        }
+    }
+
+    /// The slot named by the URI, or the only slot with a token
+    fn find_slot(context: &Pkcs11, uri: &Pkcs11Uri) -> Result<Slot> {
+        let mut slots = Vec::new();
+        for slot in context.get_slots_with_token().map_err(token_error)? {
+            if uri.slot_id.is_some_and(|id| slot.id() != id) {
+                continue;
+            }
+            if let Some(label) = &uri.token {
+                let info = context.get_token_info(slot).map_err(token_error)?;
+                if info.label().trim_end() != label {
+                    continue;
+                }
+            }
+            slots.push(slot);
+        }
+        match slots.as_slice() {
+            [slot] => Ok(*slot),
+            [] => Err(AletheiaError::ExternalSigner(
+                "No matching token found".into(),
+            )),
+            _ => Err(AletheiaError::ExternalSigner(
+                "Several tokens match; select one with slot-id or token".into(),
+            )),
+        }
+    }
+
+    /// The single Ed25519 key of `class` matching the URI
+    fn find_key(session: &Session, uri: &Pkcs11Uri, class: ObjectClass) -> Result<ObjectHandle> {
+        let mut template = vec![
+            Attribute::Class(class),
+            Attribute::KeyType(KeyType::EC_EDWARDS),
+        ];
+        if let Some(id) = &uri.id {
+            template.push(Attribute::Id(id.clone()));
+        }
+        if let Some(label) = &uri.object {
+            template.push(Attribute::Label(label.as_bytes().to_vec()));
+        }
+        match session
+            .find_objects(&template)
+            .map_err(token_error)?
+            .as_slice()
+        {
+            [key] => Ok(*key),
+            [] => Err(AletheiaError::ExternalSigner(format!(
+                "No Ed25519 {} found on the token",
+                if class == ObjectClass::PRIVATE_KEY {
+                    "private key"
+                } else {
+                    "public key"
+                }
+            ))),
+            _ => Err(AletheiaError::ExternalSigner(
+                "Several keys match; select one with id or object".into(),
+            )),
+        }
+    }
+
+    /// The 32-byte key of a CKA_EC_POINT, which tokens store either raw or
+    /// wrapped in a DER OCTET STRING
+    fn ec_point_bytes(point: Vec<u8>) -> Result<Vec<u8>> {
+        match point.as_slice() {
+            [0x04, 0x20, key @ ..] if key.len() == 32 => Ok(key.to_vec()),
+            key if key.len() == 32 => Ok(point),
+            _ => Err(AletheiaError::ExternalSigner(
+                "Public key is not an Ed25519 key".into(),
+            )),
+        }
+    }
+
+    /// Decode a percent-encoded attribute value
+    fn decode_percent(value: &str) -> Result<Vec<u8>> {
+        let bytes = value.as_bytes();
+        let mut decoded = Vec::with_capacity(bytes.len());
+        let mut i = 0;
+        while i < bytes.len() {
+            if bytes[i] == b'%' {
+                let hex = value
+                    .get(i + 1..i + 3)
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                    .ok_or_else(|| invalid_uri(&format!("Invalid percent-encoding in {value}")))?;
+                decoded.push(hex);
+                i += 3;
+            } else {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+        Ok(decoded)
+    }
+
+    fn decode_text(value: &str) -> Result<String> {
+        String::from_utf8(decode_percent(value)?)
+            .map_err(|_| invalid_uri(&format!("Attribute is not UTF-8: {value}")))
+    }
+
+    /// Decode a key ID, given percent-encoded (`%01`) or as hex digits (`01`)
+    /// as most token tools print it
+    fn decode_id(value: &str) -> Result<Vec<u8>> {
+        if value.contains('%')
+            || value.len() % 2 == 1
+            || !value.bytes().all(|b| b.is_ascii_hexdigit())
+        {
+            return decode_percent(value);
+        }
+        Ok((0..value.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&value[i..i + 2], 16).expect("checked hex digits"))
+            .collect())
+    }
+
+    fn invalid_uri(reason: &str) -> AletheiaError {
+        AletheiaError::ExternalSigner(format!("Invalid PKCS#11 URI: {reason}"))
+    }
+
+    fn token_error(e: cryptoki::error::Error) -> AletheiaError {
+        AletheiaError::ExternalSigner(e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Header, ca::CertificateAuthority, certificate::verify_certificate_signature,
+        signer::Signer, verifier::verify,
+    };
+    use rand::rngs::OsRng;
+
+    /// A software key standing in for a token
+    struct SoftToken(SigningKey);
+
+    impl ExternalSigner for SoftToken {
+        fn public_key(&self) -> Vec<u8> {
+            self.0.verifying_key().to_bytes().to_vec()
+        }
+
+        fn sign(&self, message: &[u8]) -> Result<Vec<u8>> {
+            Ok(self.0.sign(message).to_bytes().to_vec())
+        }
+
+        fn sign_prehashed(&self, message: &[u8], context: &[u8]) -> Result<Vec<u8>> {
+            let signature = self
+                .0
+                .sign_prehashed(Sha512::new_with_prefix(message), Some(context))
+                .unwrap();
+            Ok(signature.to_bytes().to_vec())
+        }
+    }
+
+    /// A token that signs with a different key than it reports
+    struct LyingToken(SigningKey, SigningKey);
+
+    impl ExternalSigner for LyingToken {
+        fn public_key(&self) -> Vec<u8> {
+            self.0.verifying_key().to_bytes().to_vec()
+        }
+
+        fn sign(&self, message: &[u8]) -> Result<Vec<u8>> {
+            SoftToken(self.1.clone()).sign(message)
+        }
+
+        fn sign_prehashed(&self, message: &[u8], context: &[u8]) -> Result<Vec<u8>> {
+            SoftToken(self.1.clone()).sign_prehashed(message, context)
+        }
+    }
+
+    #[test]
+    fn test_external_ca_and_signer() {
+        let timestamp = 1704067200;
+        let root_token = Arc::new(SoftToken(SigningKey::generate(&mut OsRng)));
+        let ca = CertificateAuthority::new_root_external_with_timestamp(
+            root_token.clone(),
+            "root@example.com",
+            "Root CA",
+            timestamp,
+        )
+        .unwrap();
+        assert!(ca.private_key_bytes().is_empty());
+        verify_certificate_signature(&ca.certificate, &ca.public_key()).unwrap();
+
+        let user_token = Arc::new(SoftToken(SigningKey::generate(&mut OsRng)));
+        let user_cert = ca
+            .issue_certificate_with_timestamp(
+                "alice@example.com",
+                "Alice",
+                &user_token.public_key(),
+                false,
+                timestamp,
+            )
+            .unwrap();
+        let signer =
+            Signer::new_external(user_token, vec![user_cert, ca.certificate.clone()]).unwrap();
+        let file = signer
+            .sign(
+                b"Test content",
+                Header::new_with_timestamp("alice@example.com", timestamp),
+            )
+            .unwrap();
+        verify(&file, &[ca.public_key()]).unwrap();
+
+        // The loaded CA signs on the token too
+        let loaded =
+            CertificateAuthority::from_external_signer(root_token, ca.certificate.clone()).unwrap();
+        loaded
+            .create_revocation_list_with_timestamp(Vec::new(), timestamp)
+            .unwrap();
+        assert!(
+            CertificateAuthority::from_external_signer(
+                Arc::new(SoftToken(SigningKey::generate(&mut OsRng))),
+                ca.certificate,
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_external_signature_checked() {
+        let token = LyingToken(
+            SigningKey::generate(&mut OsRng),
+            SigningKey::generate(&mut OsRng),
+        );
+        let ca = CertificateAuthority::new_root_with_timestamp("root@example.com", "Root CA", 0);
+        let cert = ca
+            .issue_certificate_with_timestamp(
+                "alice@example.com",
+                "Alice",
+                &token.public_key(),
+                false,
+                0,
+            )
+            .unwrap();
+        let signer = Signer::new_external(Arc::new(token), vec![cert, ca.certificate]).unwrap();
+        assert!(matches!(
+            signer.sign(
+                b"Test content",
+                Header::new_with_timestamp("alice@example.com", 0)
+            ),
+            Err(AletheiaError::ExternalSigner(_))
+        ));
+    }
+    #[cfg(feature = "pkcs11")]
+    #[test]
+    fn test_parse_pkcs11_uri() {
+        let uri = Pkcs11Uri::parse(
+            "pkcs11:token=My%20Key;slot=0;id=01?module-path=/usr/lib/libykcs11.so&pin-value=123456",
+        )
+        .unwrap();
+        assert_eq!(uri.token.as_deref(), Some("My Key"));
+        assert_eq!(uri.slot_id, Some(0));
+        assert_eq!(uri.id, Some(vec![0x01]));
+        assert_eq!(
+            uri.module_path,
+            Some(std::path::PathBuf::from("/usr/lib/libykcs11.so"))
+        );
+        assert_eq!(uri.pin.as_deref(), Some("123456"));
+
+        assert_eq!(
+            Pkcs11Uri::parse("pkcs11:id=%01%ff").unwrap().id,
+            Some(vec![0x01, 0xff])
+        );
+        assert_eq!(
+            Pkcs11Uri::parse("pkcs11:object=signing;type=private")
+                .unwrap()
+                .object
+                .as_deref(),
+            Some("signing")
+        );
+        assert!(Pkcs11Uri::parse("pkcs11:token=My%20Key").is_err());
+        assert!(Pkcs11Uri::parse("file:key.pem").is_err());
+    }
+}
@@ -0,0 +1,540 @@
+//! Signing keys held in cloud key management services
+//!
+//! [`AwsKmsSigner`] and [`GcpKmsSigner`] are [`ExternalSigner`]s whose
+//! Ed25519 keys never leave AWS KMS or Google Cloud KMS, so a CA running on
+//! them (see
+//! [`CertificateAuthority::from_external_signer`](crate::ca::CertificateAuthority::from_external_signer))
+//! holds no private key bytes. [`AwsKmsClient`] also creates the AWS keys
+//! and aliases such a CA opens.
+//!
+//! Both services sign pure Ed25519 only. Certificates, revocation lists and
+//! status assertions are signed that way, but the Ed25519ph signatures of
+//! format 1.1 files are not available: sign files through a
+//! [`PendingFile`](crate::signer::PendingFile), which writes format 1.0.
+//!
+//! The signers make blocking HTTP requests. Inside an async runtime, call
+//! them from a blocking thread (such as `tokio::task::spawn_blocking`).
+
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use hmac::{Hmac, Mac};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+
+use crate::{AletheiaError, Result, hsm::ExternalSigner};
+
+/// Largest message AWS KMS signs without a prehash, in bytes
+pub const AWS_MAX_MESSAGE: usize = 4096;
+
+/// Largest message Google Cloud KMS signs, in bytes
+pub const GCP_MAX_MESSAGE: usize = 64 * 1024;
+
+/// DER prefix of an Ed25519 SubjectPublicKeyInfo, followed by the 32-byte key
+const ED25519_SPKI_PREFIX: [u8; 12] = [
+    0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00,
+];
+
+/// Credentials that sign requests to AWS
+#[derive(Clone)]
+pub struct AwsCredentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub session_token: Option<String>,
+}
+
+impl AwsCredentials {
+    /// Read `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY` and `AWS_SESSION_TOKEN`
+    pub fn from_env() -> Result<Self> {
+        let var = |name: &str| {
+            std::env::var(name)
+                .map_err(|_| AletheiaError::ExternalSigner(format!("{name} is not set")))
+        };
+        Ok(Self {
+            access_key_id: var("AWS_ACCESS_KEY_ID")?,
+            secret_access_key: var("AWS_SECRET_ACCESS_KEY")?,
+            session_token: std::env::var("AWS_SESSION_TOKEN").ok(),
+        })
+    }
+}
+
+/// A client of the AWS KMS JSON API in one region
+///
+/// Requests are signed with AWS Signature Version 4. [`AwsKmsSigner`] signs
+/// through it; it also creates the keys a CA later opens.
+#[derive(Clone)]
+pub struct AwsKmsClient {
+    client: reqwest::blocking::Client,
+    url: String,
+    host: String,
+    region: String,
+    credentials: AwsCredentials,
+}
+
+impl AwsKmsClient {
+    /// A client of KMS in `region`
+    pub fn new(region: &str, credentials: AwsCredentials) -> Result<Self> {
+        Self::new_at(
+            region,
+            credentials,
+            &format!("https://kms.{region}.amazonaws.com"),
+        )
+    }
+
+    /// Like [`new`](Self::new), at `endpoint` (e.g. a VPC endpoint)
+    pub fn new_at(region: &str, credentials: AwsCredentials, endpoint: &str) -> Result<Self> {
+        let url = reqwest::Url::parse(endpoint)
+            .map_err(|e| AletheiaError::ExternalSigner(format!("Invalid KMS endpoint: {e}")))?;
+        let host = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{host}:{port}"),
+            (Some(host), None) => host.to_string(),
+            (None, _) => {
+                return Err(AletheiaError::ExternalSigner(format!(
+                    "KMS endpoint {endpoint} has no host"
+                )));
+            }
+        };
+        Ok(Self {
+            client: reqwest::blocking::Client::new(),
+            url: url.to_string(),
+            host,
+            region: region.to_string(),
+            credentials,
+        })
+    }
+
+    /// Create an `ECC_NIST_EDWARDS25519` signing key and return its id
+    pub fn create_key(&self, description: &str) -> Result<String> {
+        let response = self.call(
+            "CreateKey",
+            json!({
+                "KeySpec": "ECC_NIST_EDWARDS25519",
+                "KeyUsage": "SIGN_VERIFY",
+                "Description": description,
+            }),
+        )?;
+        response["KeyMetadata"]["KeyId"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| kms_error("CreateKey", "no key id returned"))
+    }
+
+    /// Name the key `key_id` `alias` (which starts with `alias/`)
+    pub fn create_alias(&self, alias: &str, key_id: &str) -> Result<()> {
+        self.call(
+            "CreateAlias",
+            json!({ "AliasName": alias, "TargetKeyId": key_id }),
+        )
+        .map(drop)
+    }
+
+    /// Call the KMS JSON API `action`
+    fn call(&self, action: &str, body: Value) -> Result<Value> {
+        let payload = body.to_string();
+        let amz_date = chrono::Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+        let mut headers = vec![
+            ("content-type", "application/x-amz-json-1.1".to_string()),
+            ("host", self.host.clone()),
+            ("x-amz-date", amz_date.clone()),
+            ("x-amz-target", format!("TrentService.{action}")),
+        ];
+        if let Some(token) = &self.credentials.session_token {
+            headers.push(("x-amz-security-token", token.clone()));
+        }
+        headers.sort();
+        let authorization = sign_v4(
+            &self.credentials,
+            &self.region,
+            "kms",
+            &AwsRequest {
+                method: "POST",
+                path: "/",
+                query: "",
+                headers: &headers,
+                payload: payload.as_bytes(),
+            },
+            &amz_date,
+        );
+
+        let mut request = self
+            .client
+            .post(&self.url)
+            .header("authorization", authorization);
+        for (name, value) in headers.iter().filter(|(name, _)| *name != "host") {
+            request = request.header(*name, value);
+        }
+        let response = request
+            .body(payload)
+            .send()
+            .map_err(|e| kms_error(action, e))?;
+        let status = response.status();
+        let body = response.bytes().map_err(|e| kms_error(action, e))?;
+        let body: Value = serde_json::from_slice(&body).map_err(|e| kms_error(action, e))?;
+        if !status.is_success() {
+            return Err(kms_error(
+                action,
+                format!("{status}: {} {}", body["__type"], body["message"]),
+            ));
+        }
+        Ok(body)
+    }
+}
+
+/// An `ECC_NIST_EDWARDS25519` key in AWS KMS
+///
+/// Messages are signed with `ED25519_SHA_512`, which KMS accepts only up to
+/// [`AWS_MAX_MESSAGE`] bytes; longer messages are refused before any request
+/// is made.
+pub struct AwsKmsSigner {
+    client: AwsKmsClient,
+    key_id: String,
+    public_key: Vec<u8>,
+}
+
+impl AwsKmsSigner {
+    /// Open the key `key_id` (an id, ARN or `alias/` name) in `region`
+    pub fn open(key_id: &str, region: &str, credentials: AwsCredentials) -> Result<Self> {
+        Self::open_with(AwsKmsClient::new(region, credentials)?, key_id)
+    }
+
+    /// Like [`open`](Self::open), at `endpoint` (e.g. a VPC endpoint)
+    pub fn open_at(
+        key_id: &str,
+        region: &str,
+        credentials: AwsCredentials,
+        endpoint: &str,
+    ) -> Result<Self> {
+        Self::open_with(AwsKmsClient::new_at(region, credentials, endpoint)?, key_id)
+    }
+
+    /// Open the key `key_id` through `client`
+    pub fn open_with(client: AwsKmsClient, key_id: &str) -> Result<Self> {
+        let response = client.call("GetPublicKey", json!({ "KeyId": key_id }))?;
+        let spki = response["PublicKey"]
+            .as_str()
+            .and_then(|key| BASE64.decode(key).ok())
+            .ok_or_else(|| kms_error("GetPublicKey", "no public key returned"))?;
+        Ok(Self {
+            client,
+            key_id: key_id.to_string(),
+            public_key: spki_public_key(&spki)?,
+        })
+    }
+}
+
+impl ExternalSigner for AwsKmsSigner {
+    fn public_key(&self) -> Vec<u8> {
+        self.public_key.clone()
+    }
+
+    fn sign(&self, message: &[u8]) -> Result<Vec<u8>> {
+        check_size(message, AWS_MAX_MESSAGE, "AWS KMS")?;
+        let response = self.client.call(
+            "Sign",
+            json!({
+                "KeyId": self.key_id,
+                "Message": BASE64.encode(message),
+                "MessageType": "RAW",
+                "SigningAlgorithm": "ED25519_SHA_512",
+            }),
+        )?;
+        response["Signature"]
+            .as_str()
+            .and_then(|signature| BASE64.decode(signature).ok())
+            .ok_or_else(|| kms_error("Sign", "no signature returned"))
+    }
+
+    fn sign_prehashed(&self, _message: &[u8], _context: &[u8]) -> Result<Vec<u8>> {
+        Err(AletheiaError::ExternalSigner(
+            "AWS KMS does not sign Ed25519ph with a context; sign a format 1.0 PendingFile".into(),
+        ))
+    }
+}
+
+/// An `EC_SIGN_ED25519` key version in Google Cloud KMS
+///
+/// Requests carry an OAuth 2.0 access token, such as the output of
+/// `gcloud auth print-access-token` or one from the metadata server. Tokens
+/// expire, so renew them with [`set_access_token`](Self::set_access_token).
+pub struct GcpKmsSigner {
+    client: reqwest::blocking::Client,
+    endpoint: String,
+    name: String,
+    access_token: std::sync::RwLock<String>,
+    public_key: Vec<u8>,
+}
+
+impl GcpKmsSigner {
+    /// Open the key version `name`
+    /// (`projects/<p>/locations/<l>/keyRings/<r>/cryptoKeys/<k>/cryptoKeyVersions/<v>`)
+    pub fn open(name: &str, access_token: impl Into<String>) -> Result<Self> {
+        Self::open_at(name, access_token, "https://cloudkms.googleapis.com")
+    }
+
+    /// Like [`open`](Self::open), at `endpoint` (e.g. a private endpoint)
+    pub fn open_at(name: &str, access_token: impl Into<String>, endpoint: &str) -> Result<Self> {
+        let mut signer = Self {
+            client: reqwest::blocking::Client::new(),
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            name: name.to_string(),
+            access_token: std::sync::RwLock::new(access_token.into()),
+            public_key: Vec::new(),
+        };
+        let response = signer.call(reqwest::Method::GET, "publicKey", None)?;
+        if response["algorithm"] != "EC_SIGN_ED25519" {
+            return Err(kms_error(
+                "publicKey",
+                format!("{name} is not an EC_SIGN_ED25519 key"),
+            ));
+        }
+        let pem = response["pem"]
+            .as_str()
+            .ok_or_else(|| kms_error("publicKey", "no public key returned"))?;
+        signer.public_key = spki_public_key(&pem_body(pem)?)?;
+        Ok(signer)
+    }
+
+    /// Use `access_token` for further requests
+    pub fn set_access_token(&self, access_token: impl Into<String>) {
+        if let Ok(mut token) = self.access_token.write() {
+            *token = access_token.into();
+        }
+    }
+
+    /// Call `method` of the key version: a resource such as `/publicKey`
+    /// or a custom method such as `:asymmetricSign`
+    fn call(
+        &self,
+        http_method: reqwest::Method,
+        method: &str,
+        body: Option<Value>,
+    ) -> Result<Value> {
+        let url = format!("{}/v1/{}{method}", self.endpoint, self.name);
+        let token = self
+            .access_token
+            .read()
+            .map_err(|_| kms_error(method, "access token lock poisoned"))?
+            .clone();
+        let mut request = self.client.request(http_method, &url).bearer_auth(token);
+        if let Some(body) = body {
+            request = request
+                .header("content-type", "application/json")
+                .body(body.to_string());
+        }
+        let response = request.send().map_err(|e| kms_error(method, e))?;
+        let status = response.status();
+        let body = response.bytes().map_err(|e| kms_error(method, e))?;
+        let body: Value = serde_json::from_slice(&body).map_err(|e| kms_error(method, e))?;
+        if !status.is_success() {
+            return Err(kms_error(
+                method,
+                format!("{status}: {}", body["error"]["message"]),
+            ));
+        }
+        Ok(body)
+    }
+}
+
+impl ExternalSigner for GcpKmsSigner {
+    fn public_key(&self) -> Vec<u8> {
+        self.public_key.clone()
+    }
+
+    fn sign(&self, message: &[u8]) -> Result<Vec<u8>> {
+        check_size(message, GCP_MAX_MESSAGE, "Cloud KMS")?;
+        let response = self.call(
+            reqwest::Method::POST,
+            ":asymmetricSign",
+            Some(json!({ "data": BASE64.encode(message) })),
+        )?;
+        response["signature"]
+            .as_str()
+            .and_then(|signature| BASE64.decode(signature).ok())
+            .ok_or_else(|| kms_error("asymmetricSign", "no signature returned"))
+    }
+
+    fn sign_prehashed(&self, _message: &[u8], _context: &[u8]) -> Result<Vec<u8>> {
+        Err(AletheiaError::ExternalSigner(
+            "Cloud KMS does not sign Ed25519ph; sign a format 1.0 PendingFile".into(),
+        ))
+    }
+}
+
+fn check_size(message: &[u8], max: usize, service: &str) -> Result<()> {
+    if message.len() > max {
+        return Err(AletheiaError::ExternalSigner(format!(
+            "{service} signs messages of at most {max} bytes, got {}",
+            message.len()
+        )));
+    }
+    Ok(())
+}
+
+/// The raw key of an Ed25519 SubjectPublicKeyInfo
+fn spki_public_key(spki: &[u8]) -> Result<Vec<u8>> {
+    match spki.strip_prefix(&ED25519_SPKI_PREFIX) {
+        Some(key) if key.len() == 32 => Ok(key.to_vec()),
+        _ => Err(AletheiaError::ExternalSigner(
+            "KMS key is not an Ed25519 key".into(),
+        )),
+    }
+}
+
+/// The DER body of a PEM block
+fn pem_body(pem: &str) -> Result<Vec<u8>> {
+    let body: String = pem
+        .lines()
+        .filter(|line| !line.starts_with("-----"))
+        .collect();
+    BASE64
+        .decode(body.trim())
+        .map_err(|e| AletheiaError::ExternalSigner(format!("Invalid public key PEM: {e}")))
+}
+
+fn kms_error(action: &str, reason: impl std::fmt::Display) -> AletheiaError {
+    AletheiaError::ExternalSigner(format!("KMS {action}: {reason}"))
+}
+
+/// An HTTP request as AWS Signature Version 4 sees it
+struct AwsRequest<'a> {
+    method: &'a str,
+    path: &'a str,
+    /// Canonical (sorted, encoded) query string
+    query: &'a str,
+    /// Lowercase names, sorted; all of them are signed
+    headers: &'a [(&'a str, String)],
+    payload: &'a [u8],
+}
+
+/// The `Authorization` header of an AWS Signature Version 4 request
+fn sign_v4(
+    credentials: &AwsCredentials,
+    region: &str,
+    service: &str,
+    request: &AwsRequest<'_>,
+    amz_date: &str,
+) -> String {
+    fn hmac(key: &[u8], data: &str) -> Vec<u8> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes any key length");
+        mac.update(data.as_bytes());
+        mac.finalize().into_bytes().to_vec()
+    }
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    let date = &amz_date[..8];
+    let scope = format!("{date}/{region}/{service}/aws4_request");
+    let canonical_headers: String = request
+        .headers
+        .iter()
+        .map(|(name, value)| format!("{name}:{}\n", value.trim()))
+        .collect();
+    let signed_headers = request
+        .headers
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(";");
+    let canonical_request = format!(
+        "{}\n{}\n{}\n{canonical_headers}\n{signed_headers}\n{}",
+        request.method,
+        request.path,
+        request.query,
+        hex(&Sha256::digest(request.payload)),
+    );
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+        hex(&Sha256::digest(canonical_request.as_bytes())),
+    );
+
+    let key = hmac(
+        format!("AWS4{}", credentials.secret_access_key).as_bytes(),
+        date,
+    );
+    let key = hmac(&key, region);
+    let key = hmac(&key, service);
+    let key = hmac(&key, "aws4_request");
+    let signature = hex(&hmac(&key, &string_to_sign));
+
+    format!(
+        "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={signed_headers}, Signature={signature}",
+        credentials.access_key_id,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The worked example of the AWS Signature Version 4 documentation
+    #[test]
+    fn test_sign_v4_matches_aws_example() {
+        let credentials = AwsCredentials {
+            access_key_id: "AKIDEXAMPLE".into(),
+            secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".into(),
+            session_token: None,
+        };
+        let headers = [
+            (
+                "content-type",
+                "application/x-www-form-urlencoded; charset=utf-8".to_string(),
+            ),
+            ("host", "iam.amazonaws.com".to_string()),
+            ("x-amz-date", "20150830T123600Z".to_string()),
+        ];
+        let request = AwsRequest {
+            method: "GET",
+            path: "/",
+            query: "Action=ListUsers&Version=2010-05-08",
+            headers: &headers,
+            payload: b"",
+        };
+
+        assert_eq!(
+            sign_v4(
+                &credentials,
+                "us-east-1",
+                "iam",
+                &request,
+                "20150830T123600Z"
+            ),
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/iam/aws4_request, \
+             SignedHeaders=content-type;host;x-amz-date, \
+             Signature=5d672d79c15b13162d9279b0855cfba6789a8edb4c82c400e06b5924a6f2b5d7",
+        );
+    }
+
+    #[test]
+    fn test_public_key_from_pem_and_oversized_messages() {
+        let key = crate::ca::SigningKeyPair::generate();
+        let mut spki = ED25519_SPKI_PREFIX.to_vec();
+        spki.extend_from_slice(&key.public_key());
+        let pem = format!(
+            "-----BEGIN PUBLIC KEY-----\n{}\n-----END PUBLIC KEY-----\n",
+            BASE64.encode(&spki)
+        );
+        assert_eq!(
+            spki_public_key(&pem_body(&pem).unwrap()).unwrap(),
+            key.public_key()
+        );
+        assert!(spki_public_key(&spki[1..]).is_err());
+
+        // Refused before anything is sent to the (unreachable) service
+        let client = AwsKmsClient::new_at(
+            "us-east-1",
+            AwsCredentials {
+                access_key_id: "AKIDEXAMPLE".into(),
+                secret_access_key: "secret".into(),
+                session_token: None,
+            },
+            "http://127.0.0.1:9/",
+        )
+        .unwrap();
+        let signer = AwsKmsSigner {
+            client,
+            key_id: "alias/ca".into(),
+            public_key: key.public_key(),
+        };
+        let err = signer.sign(&[0u8; AWS_MAX_MESSAGE + 1]).unwrap_err();
+        assert!(err.to_string().contains("at most 4096 bytes"));
+    }
+}
@@ -43,7 +43,9 @@
 //! ### Signing Content
 //!
 //! ```rust
-//! use aletheia::{ca::{CertificateAuthority, SigningKeyPair}, signer::Signer, Header};
+//! use aletheia::{
+//!     ca::{CertificateAuthority, SigningKeyPair}, certificate::ChainBuilder, signer::Signer, Header,
+//! };
 //!
 //! let ca = CertificateAuthority::new_root("root@example.com", "Root CA");
 //! let user_keys = SigningKeyPair::generate();
@@ -52,7 +54,10 @@
 //! ).unwrap();
 //!
 //! // Build certificate chain: [user_cert, root_cert]
-//! let chain = vec![user_cert, ca.certificate.clone()];
+//! let chain = ChainBuilder::new()
+//!     .with_certificate(ca.certificate.clone())
+//!     .build(user_cert)
+//!     .unwrap();
 //!
 //! // Create signer
 //! let signer = Signer::new(user_keys, chain).unwrap();
@@ -85,19 +90,59 @@
 //! println!("Signed at: {}", result.signed_at);
 //! ```
 
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+// Hosted targets (including the cdylib and WASM builds) link the standard
+// library for its allocator and panic handler; bare-metal targets bring their own
+#[cfg(all(not(feature = "std"), not(target_os = "none")))]
+extern crate std;
+
+#[cfg(feature = "compression")]
+mod compression;
+mod ct;
 mod error;
+mod mnemonic;
 mod types;
 
+pub mod bundle;
+#[cfg(feature = "c2pa")]
+pub mod c2pa;
 pub mod ca;
 pub mod certificate;
+pub mod derivation;
+pub mod embed;
 pub mod file;
+pub mod hsm;
+#[cfg(feature = "kms")]
+pub mod kms;
+pub mod merkle;
+#[cfg(feature = "online-verify")]
+pub mod online;
+pub mod opentimestamps;
+#[cfg(feature = "ml-dsa")]
+pub mod pq;
+pub mod provenance;
+pub mod registry;
+pub mod request;
+pub mod revocation;
 pub mod signer;
+#[cfg(feature = "threshold")]
+pub mod threshold;
+#[cfg(feature = "timestamp")]
+pub mod timestamp;
+pub mod transparency;
+pub mod trust_bundle;
 pub mod verifier;
 
 #[cfg(target_arch = "wasm32")]
 pub mod wasm;
 
-pub use error::{AletheiaError, Result};
+pub use error::{AletheiaError, ChainError, ErrorKind, Result};
+#[cfg(feature = "pkcs8")]
+pub use types::CERTIFICATE_PEM_LABEL;
 pub use types::{
-    AletheiaFile, Certificate, Flags, Header, MAGIC_BYTES, VERSION_MAJOR, VERSION_MINOR,
+    AletheiaFile, Assertion, AttestationMethod, Certificate, ChunkInfo, CoSignature,
+    CompressionAlgorithm, ContentRef, Countersignature, Fingerprint, Flags, Header,
+    IdentityAttestation, LogEntry, MAGIC_BYTES, MAX_CUSTOM_VALUE_SIZE, OpenTimestampsProof,
+    TimestampToken, VERSION_MAJOR, VERSION_MINOR,
 };
@@ -0,0 +1,315 @@
+//! Merkle trees over fixed-size payload chunks
+//!
+//! In chunked mode the payload is split into `chunk_size` pieces and the
+//! signature covers the Merkle root of those pieces instead of the payload
+//! itself, so a single chunk can be verified with a short inclusion proof.
+//!
+//! Leaves are `SHA-256(0x00 || chunk)` and inner nodes are
+//! `SHA-256(0x01 || left || right)`. When a level has an odd number of nodes,
+//! the last node is promoted to the next level unchanged. An empty payload
+//! consists of a single empty chunk.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use sha2::{Digest, Sha256};
+
+/// Default chunk size for chunked payloads (4 MiB)
+pub const DEFAULT_CHUNK_SIZE: u32 = 4 * 1024 * 1024;
+
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+/// A node hash in the tree
+pub type Hash = [u8; 32];
+
+/// Number of chunks a payload of `total_len` bytes is split into
+///
+/// # Panics
+/// If `chunk_size` is 0.
+pub fn chunk_count(total_len: u64, chunk_size: u32) -> u64 {
+    total_len.div_ceil(chunk_size as u64).max(1)
+}
+
+/// Incrementally hashes a payload into Merkle leaves
+pub(crate) struct MerkleBuilder {
+    chunk_size: u64,
+    current: Sha256,
+    current_len: u64,
+    leaves: Vec<Hash>,
+}
+
+impl MerkleBuilder {
+    pub(crate) fn new(chunk_size: u32) -> Self {
+        assert!(chunk_size > 0, "chunk size must be positive");
+        Self {
+            chunk_size: chunk_size as u64,
+            current: leaf_hasher(),
+            current_len: 0,
+            leaves: Vec::new(),
+        }
+    }
+
+    /// Feed the next bytes of the payload
+    pub(crate) fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            let take = ((self.chunk_size - self.current_len) as usize).min(data.len());
+            self.current.update(&data[..take]);
+            self.current_len += take as u64;
+            data = &data[take..];
+
+            if self.current_len == self.chunk_size {
+                let hasher = core::mem::replace(&mut self.current, leaf_hasher());
+                self.leaves.push(hasher.finalize().into());
+                self.current_len = 0;
+            }
+        }
+    }
+
+    /// Finish the last chunk and compute the root
+    pub(crate) fn finalize(mut self) -> Hash {
+        if self.current_len > 0 || self.leaves.is_empty() {
+            self.leaves.push(self.current.finalize().into());
+        }
+        root_of(self.leaves)
+    }
+}
+
+/// Compute the Merkle root of `payload` split into `chunk_size` chunks
+///
+/// # Panics
+/// If `chunk_size` is 0.
+pub fn merkle_root(payload: &[u8], chunk_size: u32) -> Hash {
+    let mut builder = MerkleBuilder::new(chunk_size);
+    builder.update(payload);
+    builder.finalize()
+}
+
+/// Build the inclusion proof for chunk `index`
+///
+/// The proof lists the sibling hashes from the leaf up to the root; levels
+/// where the node is promoted without a sibling contribute nothing.
+/// Returns `None` if `index` is out of range.
+///
+/// # Panics
+/// If `chunk_size` is 0.
+pub fn merkle_proof(payload: &[u8], chunk_size: u32, index: u64) -> Option<Vec<Hash>> {
+    let mut level: Vec<Hash> = if payload.is_empty() {
+        alloc::vec![leaf_hash(&[])]
+    } else {
+        payload.chunks(chunk_size as usize).map(leaf_hash).collect()
+    };
+    let mut index = usize::try_from(index).ok()?;
+    if index >= level.len() {
+        return None;
+    }
+
+    let mut proof = Vec::new();
+    while level.len() > 1 {
+        let sibling = index ^ 1;
+        if sibling < level.len() {
+            proof.push(level[sibling]);
+        }
+        level = next_level(&level);
+        index /= 2;
+    }
+    Some(proof)
+}
+
+/// Check that `chunk` is leaf `index` of a tree with `leaf_count` leaves and root `root`
+pub fn verify_proof(
+    chunk: &[u8],
+    index: u64,
+    leaf_count: u64,
+    proof: &[Hash],
+    root: &[u8],
+) -> bool {
+    if index >= leaf_count {
+        return false;
+    }
+
+    let mut hash = leaf_hash(chunk);
+    let mut index = index;
+    let mut width = leaf_count;
+    let mut siblings = proof.iter();
+    while width > 1 {
+        let sibling = index ^ 1;
+        if sibling < width {
+            let Some(sibling_hash) = siblings.next() else {
+                return false;
+            };
+            hash = if index.is_multiple_of(2) {
+                node_hash(&hash, sibling_hash)
+            } else {
+                node_hash(sibling_hash, &hash)
+            };
+        }
+        index /= 2;
+        width = width.div_ceil(2);
+    }
+
+    siblings.next().is_none() && crate::ct::eq(&hash, root)
+}
+
+/// The right edge of a tree that grows one chunk at a time
+///
+/// Holds the roots of the perfect subtrees the tree splits into, largest
+/// first: one per set bit of its size. That is enough to append a chunk,
+/// prove the appended chunk and compute the root without the earlier chunks,
+/// so an append-only log can persist it instead of rebuilding the tree.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Frontier {
+    size: u64,
+    nodes: Vec<Hash>,
+}
+
+impl Frontier {
+    /// The frontier of an empty tree
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rebuild a frontier from its [`size`](Self::size) and
+    /// [`nodes`](Self::nodes)
+    ///
+    /// Returns `None` if there is not one node per set bit of `size`.
+    pub fn from_parts(size: u64, nodes: Vec<Hash>) -> Option<Self> {
+        (nodes.len() == size.count_ones() as usize).then_some(Self { size, nodes })
+    }
+
+    /// Number of chunks in the tree
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Roots of the perfect subtrees, largest (leftmost) first
+    pub fn nodes(&self) -> &[Hash] {
+        &self.nodes
+    }
+
+    /// Append `chunk` and return its inclusion proof in the grown tree, as
+    /// [`merkle_proof`] would build it
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<Hash> {
+        // The new chunk is the last leaf, so its siblings are exactly the
+        // subtrees to its left, from the smallest up
+        let proof = self.nodes.iter().rev().copied().collect();
+        let mut hash = leaf_hash(chunk);
+        let mut size = self.size;
+        while size & 1 == 1 {
+            let left = self.nodes.pop().expect("one node per set bit of the size");
+            hash = node_hash(&left, &hash);
+            size >>= 1;
+        }
+        self.nodes.push(hash);
+        self.size += 1;
+        proof
+    }
+
+    /// The Merkle root, as [`merkle_root`] computes it over the same chunks
+    ///
+    /// An empty tree has the root of a single empty chunk.
+    pub fn root(&self) -> Hash {
+        let mut nodes = self.nodes.iter().rev();
+        let Some(last) = nodes.next() else {
+            return leaf_hash(&[]);
+        };
+        nodes.fold(*last, |hash, left| node_hash(left, &hash))
+    }
+}
+
+fn leaf_hasher() -> Sha256 {
+    let mut hasher = Sha256::new();
+    hasher.update([LEAF_PREFIX]);
+    hasher
+}
+
+fn leaf_hash(chunk: &[u8]) -> Hash {
+    let mut hasher = leaf_hasher();
+    hasher.update(chunk);
+    hasher.finalize().into()
+}
+
+fn node_hash(left: &Hash, right: &Hash) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update([NODE_PREFIX]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+fn next_level(level: &[Hash]) -> Vec<Hash> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => node_hash(left, right),
+            [single] => *single,
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+fn root_of(mut level: Vec<Hash>) -> Hash {
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level[0]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_proofs_for_every_chunk() {
+        let payload: Vec<u8> = (0..1000u32).map(|i| i as u8).collect();
+        let chunk_size = 64;
+        let root = merkle_root(&payload, chunk_size);
+        let leaf_count = chunk_count(payload.len() as u64, chunk_size);
+        assert_eq!(leaf_count, 16);
+
+        for (index, chunk) in payload.chunks(chunk_size as usize).enumerate() {
+            let proof = merkle_proof(&payload, chunk_size, index as u64).unwrap();
+            assert!(verify_proof(chunk, index as u64, leaf_count, &proof, &root));
+            assert!(!verify_proof(
+                b"forged",
+                index as u64,
+                leaf_count,
+                &proof,
+                &root
+            ));
+        }
+        assert!(merkle_proof(&payload, chunk_size, leaf_count).is_none());
+    }
+
+    #[test]
+    fn test_odd_leaf_count_and_empty_payload() {
+        let payload = [7u8; 300];
+        let root = merkle_root(&payload, 100);
+        let proof = merkle_proof(&payload, 100, 2).unwrap();
+        assert!(verify_proof(&payload[200..], 2, 3, &proof, &root));
+        assert!(!verify_proof(&payload[200..], 1, 3, &proof, &root));
+
+        let root = merkle_root(&[], 100);
+        let proof = merkle_proof(&[], 100, 0).unwrap();
+        assert!(verify_proof(&[], 0, 1, &proof, &root));
+    }
+
+    #[test]
+    fn test_frontier_matches_full_tree() {
+        let payload: Vec<u8> = (0..40u8).flat_map(|i| [i; 8]).collect();
+        let mut frontier = Frontier::new();
+        assert_eq!(frontier.root(), merkle_root(&[], 8));
+
+        for (index, chunk) in payload.chunks(8).enumerate() {
+            let proof = frontier.push(chunk);
+            let grown = &payload[..(index + 1) * 8];
+            assert_eq!(frontier.size(), index as u64 + 1);
+            assert_eq!(frontier.root(), merkle_root(grown, 8));
+            assert_eq!(Some(proof), merkle_proof(grown, 8, index as u64));
+
+            let restored = Frontier::from_parts(frontier.size(), frontier.nodes().to_vec());
+            assert_eq!(restored.as_ref(), Some(&frontier));
+        }
+        assert!(Frontier::from_parts(3, Vec::new()).is_none());
+    }
+}
@@ -0,0 +1,117 @@
+//! BIP39 mnemonic encoding of 32-byte keys
+//!
+//! 256 bits of key material plus an 8-bit checksum (the first byte of its
+//! SHA-256) are split into 24 groups of 11 bits, each naming a word of the
+//! BIP39 English wordlist. Phrases are compatible with BIP39 tools for 24-word
+//! mnemonics, but encode the key itself rather than a seed for PBKDF2.
+
+extern crate alloc;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use sha2::{Digest, Sha256};
+
+use crate::{AletheiaError, Result};
+
+/// The BIP39 English wordlist, sorted
+const WORDLIST: &str = include_str!("bip39_english.txt");
+
+/// Number of words encoding 32 bytes
+const WORD_COUNT: usize = 24;
+
+fn words() -> impl Iterator<Item = &'static str> {
+    WORDLIST.lines()
+}
+
+/// Encode `entropy` as 24 words separated by spaces
+pub(crate) fn encode(entropy: &[u8; 32]) -> String {
+    let checksum = Sha256::digest(entropy)[0];
+    let bits = |i: usize| {
+        let byte = entropy.get(i / 8).copied().unwrap_or(checksum);
+        (byte >> (7 - i % 8)) & 1
+    };
+    let wordlist: Vec<&str> = words().collect();
+
+    let mut phrase = String::new();
+    for word in 0..WORD_COUNT {
+        let index = (0..11).fold(0usize, |acc, bit| {
+            (acc << 1) | bits(word * 11 + bit) as usize
+        });
+        if word > 0 {
+            phrase.push(' ');
+        }
+        phrase.push_str(wordlist[index]);
+    }
+    phrase
+}
+
+/// Decode a phrase written by [`encode`], checking its checksum
+///
+/// Words are separated by any whitespace and matched case-insensitively.
+pub(crate) fn decode(phrase: &str) -> Result<[u8; 32]> {
+    let wordlist: Vec<&str> = words().collect();
+    let mut bytes = [0u8; 33];
+    let mut count = 0;
+    for word in phrase.split_whitespace() {
+        if count == WORD_COUNT {
+            return Err(mnemonic_error("Mnemonic must have 24 words"));
+        }
+        let word = word.to_ascii_lowercase();
+        let index = wordlist.binary_search(&word.as_str()).map_err(|_| {
+            AletheiaError::KeyEncoding(alloc::format!("Unknown mnemonic word '{word}'"))
+        })?;
+        for bit in 0..11 {
+            if index >> (10 - bit) & 1 == 1 {
+                let i = count * 11 + bit;
+                bytes[i / 8] |= 1 << (7 - i % 8);
+            }
+        }
+        count += 1;
+    }
+    if count != WORD_COUNT {
+        return Err(mnemonic_error("Mnemonic must have 24 words"));
+    }
+
+    let mut entropy = [0u8; 32];
+    entropy.copy_from_slice(&bytes[..32]);
+    if Sha256::digest(entropy)[0] != bytes[32] {
+        return Err(mnemonic_error("Invalid mnemonic checksum"));
+    }
+    Ok(entropy)
+}
+
+fn mnemonic_error(message: &str) -> AletheiaError {
+    AletheiaError::KeyEncoding(message.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bip39_vectors() {
+        // Test vectors from the BIP39 reference implementation
+        assert_eq!(
+            encode(&[0; 32]),
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon \
+             abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon \
+             abandon abandon abandon art"
+        );
+        assert_eq!(
+            encode(&[0x7f; 32]),
+            "legal winner thank year wave sausage worth useful legal winner thank year wave \
+             sausage worth useful legal winner thank year wave sausage worth title"
+        );
+
+        let entropy: [u8; 32] = core::array::from_fn(|i| (i * 37 + 11) as u8);
+        let phrase = encode(&entropy);
+        assert_eq!(decode(&phrase.to_uppercase()).unwrap(), entropy);
+
+        // A swapped word breaks the checksum; a dropped word the length
+        let mut words: Vec<&str> = phrase.split(' ').collect();
+        words.swap(0, 1);
+        assert!(decode(&words.join(" ")).is_err());
+        assert!(decode(&words[1..].join(" ")).is_err());
+        assert!(decode("abandon notaword").is_err());
+    }
+}
@@ -0,0 +1,187 @@
+//! Online revocation checks over HTTP
+//!
+//! [`HttpRevocationChecker`] asks a CA's revocation service, such as the PKI
+//! portal's `/revocations` endpoint, about each certificate during
+//! [`verify_with_checker`](crate::verifier::verify_with_checker). Answers are
+//! cached for a configurable time so repeated verifications do not hit the
+//! service for every file.
+//!
+//! The service's answers are not signed, so they are only as trustworthy as
+//! the HTTPS connection. Signed revocation lists and status assertions remain
+//! the way to check revocation offline.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+
+use crate::{
+    AletheiaError, Result,
+    revocation::{RevocationChecker, RevokedCertificate, serial_hex},
+};
+
+/// How long answers are cached by default
+const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(300);
+
+/// Cached answers by issuer and serial
+type Cache = HashMap<(String, Vec<u8>), (Instant, Option<RevokedCertificate>)>;
+
+/// A [`RevocationChecker`] querying a revocation service over HTTP
+///
+/// For each certificate it requests `GET <endpoint>?serial=<hex>&issuer=<id>`
+/// and expects a JSON array of revocations, each with a hex `serial`, a
+/// `revoked_at` time (Unix timestamp or RFC 3339) and an optional `reason`.
+/// The certificate is revoked if the array holds an entry for its serial.
+pub struct HttpRevocationChecker {
+    client: reqwest::Client,
+    endpoint: String,
+    cache_ttl: Duration,
+    cache: Mutex<Cache>,
+}
+
+/// One revocation as returned by the service
+#[derive(Deserialize)]
+struct RevocationEntry {
+    serial: String,
+    #[serde(default)]
+    reason: Option<String>,
+    revoked_at: RevocationTime,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RevocationTime {
+    Unix(i64),
+    Rfc3339(chrono::DateTime<chrono::Utc>),
+}
+
+impl HttpRevocationChecker {
+    /// Query the revocation service at `endpoint`
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            endpoint: endpoint.into(),
+            cache_ttl: DEFAULT_CACHE_TTL,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Cache answers for `ttl` (five minutes by default, zero to disable)
+    pub fn with_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.cache_ttl = ttl;
+        self
+    }
+
+    /// Send requests with `client`, for example to set timeouts or a proxy
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    async fn fetch(&self, issuer_id: &str, serial: &[u8]) -> Result<Option<RevokedCertificate>> {
+        let serial_hex = serial_hex(serial);
+        let response = self
+            .client
+            .get(&self.endpoint)
+            .query(&[("serial", serial_hex.as_str()), ("issuer", issuer_id)])
+            .send()
+            .await
+            .map_err(|e| AletheiaError::RevocationCheck(e.to_string()))?;
+        if !response.status().is_success() {
+            return Err(AletheiaError::RevocationCheck(format!(
+                "Revocation service returned {}",
+                response.status()
+            )));
+        }
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| AletheiaError::RevocationCheck(e.to_string()))?;
+        let entries: Vec<RevocationEntry> = serde_json::from_slice(&body)
+            .map_err(|e| AletheiaError::RevocationCheck(e.to_string()))?;
+
+        Ok(entries
+            .into_iter()
+            .find(|entry| entry.serial.eq_ignore_ascii_case(&serial_hex))
+            .map(|entry| RevokedCertificate {
+                serial: serial.to_vec(),
+                revoked_at: match entry.revoked_at {
+                    RevocationTime::Unix(timestamp) => timestamp,
+                    RevocationTime::Rfc3339(time) => time.timestamp(),
+                },
+                reason: entry.reason,
+            }))
+    }
+}
+
+impl RevocationChecker for HttpRevocationChecker {
+    async fn check(&self, issuer_id: &str, serial: &[u8]) -> Result<Option<RevokedCertificate>> {
+        let key = (issuer_id.to_string(), serial.to_vec());
+        if let Some((fetched, answer)) = self.cache.lock().unwrap().get(&key)
+            && fetched.elapsed() < self.cache_ttl
+        {
+            return Ok(answer.clone());
+        }
+
+        let answer = self.fetch(issuer_id, serial).await?;
+        self.cache
+            .lock()
+            .unwrap()
+            .insert(key, (Instant::now(), answer.clone()));
+        Ok(answer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    /// Answer `count` HTTP requests with `body`, returning the endpoint
+    fn serve(body: &'static str, count: usize) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}/revocations", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for stream in listener.incoming().take(count) {
+                let mut stream = stream.unwrap();
+                let mut request = [0; 4096];
+                let _ = stream.read(&mut request);
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\
+                     Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        endpoint
+    }
+
+    #[tokio::test]
+    async fn test_http_checker_and_cache() {
+        let endpoint = serve(
+            r#"[{"serial":"0A0B","reason":"key compromise","revoked_at":"2024-01-02T00:00:00Z"}]"#,
+            2,
+        );
+        let checker = HttpRevocationChecker::new(endpoint);
+
+        let revoked = checker.check("root@example.com", &[0x0a, 0x0b]).await;
+        let revoked = revoked.unwrap().unwrap();
+        assert_eq!(revoked.revoked_at, 1704153600);
+        assert_eq!(revoked.reason.as_deref(), Some("key compromise"));
+        assert!(
+            checker
+                .check("root@example.com", &[0x01])
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        // The server has stopped answering; cached answers are still served
+        let cached = checker.check("root@example.com", &[0x0a, 0x0b]).await;
+        assert!(cached.unwrap().is_some());
+    }
+}
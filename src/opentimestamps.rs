@@ -0,0 +1,393 @@
+//! OpenTimestamps proofs of the content digest
+//!
+//! An OpenTimestamps proof commits a SHA-256 digest into a Bitcoin block
+//! through a chain of hash operations, proving the digest existed before
+//! that block was mined. No certificate authority is involved: anyone holding
+//! the block headers can check the proof.
+//!
+//! The stamped digest is the header's `content_digest`, so content can be
+//! stamped before or after it is signed. Proofs are stored as complete `.ots`
+//! files in the trailer and can be extracted and checked with the reference
+//! `ots` tool as well.
+//!
+//! Verifiers supply the Merkle roots of the blocks they know through
+//! [`crate::verifier::VerifyOptions::with_bitcoin_block`], in the byte order
+//! of the serialized block header (reversed relative to block explorers).
+
+extern crate alloc;
+
+use alloc::collections::BTreeMap;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use sha2::{Digest, Sha256};
+
+use crate::{AletheiaError, AletheiaFile, OpenTimestampsProof, Result};
+
+/// Magic bytes at the start of an `.ots` file
+const HEADER_MAGIC: &[u8] = b"\x00OpenTimestamps\x00\x00Proof\x00\xbf\x89\xe2\xe8\x84\xe8\x92\x94";
+
+/// Supported `.ots` major version
+const MAJOR_VERSION: u64 = 1;
+
+const TAG_ATTESTATION: u8 = 0x00;
+const TAG_SHA256: u8 = 0x08;
+const TAG_APPEND: u8 = 0xf0;
+const TAG_PREPEND: u8 = 0xf1;
+const TAG_REVERSE: u8 = 0xf2;
+const TAG_HEXLIFY: u8 = 0xf3;
+const TAG_FORK: u8 = 0xff;
+
+const BITCOIN_ATTESTATION: [u8; 8] = [0x05, 0x88, 0x96, 0x0d, 0x73, 0xd7, 0x19, 0x01];
+const PENDING_ATTESTATION: [u8; 8] = [0x83, 0xdf, 0xe3, 0x0d, 0x2e, 0xf9, 0x0c, 0x8e];
+
+/// Longest message an operation may produce or take as argument
+const MAX_MSG_LEN: usize = 4096;
+
+/// Deepest nesting of operations accepted
+const MAX_DEPTH: usize = 256;
+
+/// A leaf of the proof: what the message was committed to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Attestation {
+    /// The message is the Merkle root of the Bitcoin block at this height
+    Bitcoin { height: u64 },
+    /// Submitted to a calendar server; not yet anchored in a block
+    Pending { uri: alloc::string::String },
+    /// An attestation type this implementation does not know
+    Unknown { tag: [u8; 8] },
+}
+
+/// A parsed `.ots` proof
+#[derive(Debug, Clone)]
+pub struct Proof {
+    /// The stamped SHA-256 digest
+    pub digest: Vec<u8>,
+    /// Each attestation with the message it commits to
+    pub attestations: Vec<(Attestation, Vec<u8>)>,
+}
+
+/// Attach the `.ots` proof for the file's content digest to `file`
+///
+/// Checks that the proof stamps exactly the content digest recorded in the
+/// header; the attestations are checked when the file is verified.
+pub fn attach(file: &mut AletheiaFile, ots: &[u8]) -> Result<()> {
+    let digest =
+        file.header.content_digest.as_deref().ok_or_else(|| {
+            AletheiaError::OpenTimestamps("File records no content digest".into())
+        })?;
+    if parse(ots)?.digest != digest {
+        return Err(AletheiaError::OpenTimestamps(
+            "Proof does not cover the content digest".into(),
+        ));
+    }
+
+    file.opentimestamps
+        .push(OpenTimestampsProof { ots: ots.to_vec() });
+    Ok(())
+}
+
+/// Verify that `ots` proves `digest` existed before a known Bitcoin block
+///
+/// `blocks` maps heights to block Merkle roots. Returns the lowest height
+/// among the attestations that check out.
+pub fn verify_proof(ots: &[u8], digest: &[u8], blocks: &BTreeMap<u64, [u8; 32]>) -> Result<u64> {
+    let proof = parse(ots)?;
+    if !crate::ct::eq(&proof.digest, digest) {
+        return Err(AletheiaError::OpenTimestamps(
+            "Proof does not cover the content digest".into(),
+        ));
+    }
+
+    let mut pending = false;
+    let mut unknown_block = false;
+    let mut verified: Option<u64> = None;
+    for (attestation, msg) in proof.attestations {
+        match attestation {
+            Attestation::Bitcoin { height } => match blocks.get(&height) {
+                Some(root) if crate::ct::eq(root, &msg) => {
+                    verified = Some(verified.map_or(height, |h| h.min(height)));
+                }
+                Some(_) => {
+                    return Err(AletheiaError::OpenTimestamps(alloc::format!(
+                        "Proof does not match block {height}"
+                    )));
+                }
+                None => unknown_block = true,
+            },
+            Attestation::Pending { .. } => pending = true,
+            Attestation::Unknown { .. } => {}
+        }
+    }
+
+    match verified {
+        Some(height) => Ok(height),
+        None if unknown_block => Err(AletheiaError::OpenTimestamps(
+            "Proof is anchored in a block the verifier does not know".into(),
+        )),
+        None if pending => Err(AletheiaError::OpenTimestamps(
+            "Proof is still pending".into(),
+        )),
+        None => Err(AletheiaError::OpenTimestamps(
+            "Proof has no Bitcoin attestation".into(),
+        )),
+    }
+}
+
+/// Parse an `.ots` file into its digest and attestations
+pub fn parse(ots: &[u8]) -> Result<Proof> {
+    let mut reader = Reader { data: ots };
+    if reader.bytes(HEADER_MAGIC.len())? != HEADER_MAGIC {
+        return Err(invalid("not an OpenTimestamps proof"));
+    }
+    let version = reader.varuint()?;
+    if version != MAJOR_VERSION {
+        return Err(AletheiaError::OpenTimestamps(alloc::format!(
+            "Unsupported proof version {version}"
+        )));
+    }
+    if reader.byte()? != TAG_SHA256 {
+        return Err(AletheiaError::OpenTimestamps(
+            "Only SHA-256 file digests are supported".into(),
+        ));
+    }
+    let digest = reader.bytes(32)?.to_vec();
+
+    let mut attestations = Vec::new();
+    parse_timestamp(&mut reader, digest.clone(), 0, &mut attestations)?;
+    if !reader.data.is_empty() {
+        return Err(invalid("trailing data"));
+    }
+    Ok(Proof {
+        digest,
+        attestations,
+    })
+}
+
+/// Parse the timestamp for `msg`: a run of forks followed by one last branch
+fn parse_timestamp(
+    reader: &mut Reader,
+    msg: Vec<u8>,
+    depth: usize,
+    attestations: &mut Vec<(Attestation, Vec<u8>)>,
+) -> Result<()> {
+    if depth > MAX_DEPTH {
+        return Err(invalid("proof nested too deeply"));
+    }
+
+    let mut tag = reader.byte()?;
+    while tag == TAG_FORK {
+        let branch = reader.byte()?;
+        parse_branch(reader, branch, &msg, depth, attestations)?;
+        tag = reader.byte()?;
+    }
+    parse_branch(reader, tag, &msg, depth, attestations)
+}
+
+fn parse_branch(
+    reader: &mut Reader,
+    tag: u8,
+    msg: &[u8],
+    depth: usize,
+    attestations: &mut Vec<(Attestation, Vec<u8>)>,
+) -> Result<()> {
+    if tag == TAG_ATTESTATION {
+        let kind: [u8; 8] = reader.bytes(8)?.try_into().unwrap();
+        let payload = reader.varbytes()?;
+        let attestation = match kind {
+            BITCOIN_ATTESTATION => Attestation::Bitcoin {
+                height: Reader { data: payload }.varuint()?,
+            },
+            PENDING_ATTESTATION => Attestation::Pending {
+                uri: core::str::from_utf8(Reader { data: payload }.varbytes()?)
+                    .map_err(|_| invalid("calendar URI is not UTF-8"))?
+                    .to_string(),
+            },
+            tag => Attestation::Unknown { tag },
+        };
+        attestations.push((attestation, msg.to_vec()));
+        return Ok(());
+    }
+
+    let next = match tag {
+        TAG_SHA256 => Sha256::digest(msg).to_vec(),
+        TAG_APPEND | TAG_PREPEND => {
+            let arg = reader.varbytes()?;
+            if arg.len() > MAX_MSG_LEN {
+                return Err(invalid("operation argument too long"));
+            }
+            if tag == TAG_APPEND {
+                [msg, arg].concat()
+            } else {
+                [arg, msg].concat()
+            }
+        }
+        TAG_REVERSE => msg.iter().rev().copied().collect(),
+        TAG_HEXLIFY => msg
+            .iter()
+            .flat_map(|b| {
+                let hex = b"0123456789abcdef";
+                [hex[(b >> 4) as usize], hex[(b & 0xf) as usize]]
+            })
+            .collect(),
+        other => {
+            return Err(AletheiaError::OpenTimestamps(alloc::format!(
+                "Unsupported operation 0x{other:02x}"
+            )));
+        }
+    };
+    if next.len() > MAX_MSG_LEN {
+        return Err(invalid("message too long"));
+    }
+    parse_timestamp(reader, next, depth + 1, attestations)
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.data.len() < len {
+            return Err(AletheiaError::UnexpectedEof);
+        }
+        let (head, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(head)
+    }
+
+    fn byte(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    /// Unsigned LEB128
+    fn varuint(&mut self) -> Result<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(invalid("integer too large"))
+    }
+
+    fn varbytes(&mut self) -> Result<&'a [u8]> {
+        let len = self.varuint()?;
+        self.bytes(usize::try_from(len).map_err(|_| invalid("length too large"))?)
+    }
+}
+
+fn invalid(msg: &str) -> AletheiaError {
+    AletheiaError::OpenTimestamps(alloc::format!("Malformed proof: {msg}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn varbytes(out: &mut Vec<u8>, bytes: &[u8]) {
+        out.push(bytes.len() as u8);
+        out.extend_from_slice(bytes);
+    }
+
+    /// A proof for `digest` anchored at block 800000, with a pending branch
+    ///
+    /// Returns the proof and the Merkle root of the block.
+    fn build_proof(digest: &[u8; 32]) -> (Vec<u8>, [u8; 32]) {
+        let mut ots = HEADER_MAGIC.to_vec();
+        ots.push(0x01);
+        ots.push(TAG_SHA256);
+        ots.extend_from_slice(digest);
+
+        // Fork: one branch ends at a calendar, the other in a block
+        ots.push(TAG_FORK);
+        ots.push(TAG_ATTESTATION);
+        ots.extend_from_slice(&PENDING_ATTESTATION);
+        let uri = b"https://calendar.example.com";
+        let mut payload = Vec::new();
+        varbytes(&mut payload, uri);
+        varbytes(&mut ots, &payload);
+
+        ots.push(TAG_APPEND);
+        varbytes(&mut ots, &[0xAA; 16]);
+        ots.push(TAG_SHA256);
+        ots.push(TAG_PREPEND);
+        varbytes(&mut ots, &[0xBB; 32]);
+        ots.push(TAG_SHA256);
+        ots.push(TAG_SHA256);
+        ots.push(TAG_ATTESTATION);
+        ots.extend_from_slice(&BITCOIN_ATTESTATION);
+        // 800000 as LEB128
+        varbytes(&mut ots, &[0x80, 0xEA, 0x30]);
+
+        let msg = Sha256::digest([digest.as_slice(), &[0xAA; 16]].concat());
+        let msg = Sha256::digest([[0xBB; 32].as_slice(), &msg].concat());
+        let root: [u8; 32] = Sha256::digest(msg).into();
+        (ots, root)
+    }
+
+    #[test]
+    fn test_verify_bitcoin_attestation() {
+        let digest: [u8; 32] = Sha256::digest(b"content").into();
+        let (ots, root) = build_proof(&digest);
+
+        let proof = parse(&ots).unwrap();
+        assert_eq!(proof.attestations.len(), 2);
+        assert!(matches!(
+            proof.attestations[0].0,
+            Attestation::Pending { .. }
+        ));
+
+        let blocks = BTreeMap::from([(800_000, root)]);
+        assert_eq!(verify_proof(&ots, &digest, &blocks).unwrap(), 800_000);
+
+        // Unknown block, wrong block, other digest
+        assert!(verify_proof(&ots, &digest, &BTreeMap::new()).is_err());
+        let blocks = BTreeMap::from([(800_000, [0u8; 32])]);
+        assert!(verify_proof(&ots, &digest, &blocks).is_err());
+        let other: [u8; 32] = Sha256::digest(b"other").into();
+        assert!(verify_proof(&ots, &other, &BTreeMap::from([(800_000, root)])).is_err());
+    }
+
+    #[test]
+    fn test_verify_file_with_proof() {
+        use crate::{
+            Header,
+            ca::{CertificateAuthority, SigningKeyPair},
+            signer::Signer,
+            verifier::{VerifyOptions, verify_with_options},
+        };
+
+        let timestamp = 1704067200;
+        let ca =
+            CertificateAuthority::new_root_with_timestamp("root@example.com", "Root CA", timestamp);
+        let user_keys = SigningKeyPair::generate();
+        let user_cert = ca
+            .issue_certificate_with_timestamp(
+                "alice@example.com",
+                "Alice",
+                &user_keys.public_key(),
+                false,
+                timestamp,
+            )
+            .unwrap();
+        let signer = Signer::new(user_keys, vec![user_cert, ca.certificate.clone()]).unwrap();
+        let mut file = signer
+            .sign(
+                b"content",
+                Header::new_with_timestamp("alice@example.com", timestamp),
+            )
+            .unwrap();
+
+        let digest: [u8; 32] = Sha256::digest(b"content").into();
+        let (ots, root) = build_proof(&digest);
+        attach(&mut file, &ots).unwrap();
+        assert!(attach(&mut file, &build_proof(&[0; 32]).0).is_err());
+
+        let options = VerifyOptions::new().with_bitcoin_block(800_000, root);
+        let result = verify_with_options(&file, &[ca.public_key()], &options).unwrap();
+        assert!(result.opentimestamps[0].valid);
+        assert_eq!(result.opentimestamps[0].block_height, Some(800_000));
+    }
+}
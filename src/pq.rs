@@ -0,0 +1,199 @@
+//! Hybrid post-quantum signatures (ML-DSA)
+//!
+//! Certificates and files can carry an ML-DSA-65 (FIPS 204) signature next
+//! to their Ed25519 one, so they stay verifiable once Ed25519 can no longer
+//! be relied on:
+//!
+//! - A certificate binds an ML-DSA public key to its subject in
+//!   [`Certificate::pq_public_key`], covered by the issuer's signatures, and
+//!   carries the issuer's ML-DSA signature over the same signed bytes in
+//!   [`Certificate::pq_signature`].
+//! - A file signed with [`Signer::with_ml_dsa_key`] carries an ML-DSA
+//!   signature over the same signature input as its Ed25519 signature (its
+//!   SHA-512 prehash, format 1.1+) in [`AletheiaFile::pq_signature`].
+//!
+//! Which signatures verification requires is set with
+//! [`VerifyOptions::with_signature_policy`]. Co-signatures and
+//! countersignatures are Ed25519 only.
+//!
+//! ```rust
+//! use aletheia::{
+//!     Header,
+//!     ca::{CertificateAuthority, IssueOptions, SigningKeyPair},
+//!     certificate::SignaturePolicy,
+//!     pq::MlDsaKeyPair,
+//!     signer::Signer,
+//!     verifier::{VerifyOptions, verify_with_options},
+//! };
+//!
+//! let ca = CertificateAuthority::new_root_hybrid("root@example.com", "Root CA");
+//! let keys = SigningKeyPair::generate();
+//! let pq_keys = MlDsaKeyPair::generate();
+//! let cert = ca
+//!     .issue_certificate_with_options(
+//!         "alice@example.com",
+//!         "Alice",
+//!         &keys.public_key(),
+//!         false,
+//!         1704067200,
+//!         &IssueOptions::new().with_pq_public_key(pq_keys.public_key()),
+//!     )
+//!     .unwrap();
+//!
+//! let signer = Signer::new(keys, vec![cert, ca.certificate.clone()])
+//!     .unwrap()
+//!     .with_ml_dsa_key(pq_keys)
+//!     .unwrap();
+//! let file = signer.sign(b"content", Header::new("alice@example.com")).unwrap();
+//!
+//! let options = VerifyOptions::new().with_signature_policy(SignaturePolicy::Both);
+//! verify_with_options(&file, &[ca.public_key()], &options).unwrap();
+//! ```
+//!
+//! [`Certificate::pq_public_key`]: crate::Certificate::pq_public_key
+//! [`Certificate::pq_signature`]: crate::Certificate::pq_signature
+//! [`AletheiaFile::pq_signature`]: crate::AletheiaFile::pq_signature
+//! [`Signer::with_ml_dsa_key`]: crate::signer::Signer::with_ml_dsa_key
+//! [`VerifyOptions::with_signature_policy`]: crate::verifier::VerifyOptions::with_signature_policy
+
+extern crate alloc;
+
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use fips204::ml_dsa_65;
+use fips204::traits::{KeyGen, SerDes, Signer, Verifier};
+use rand::{RngCore, rngs::OsRng};
+use zeroize::Zeroize;
+
+use crate::{AletheiaError, Result};
+
+/// Length of an ML-DSA-65 public key
+pub const PUBLIC_KEY_LEN: usize = ml_dsa_65::PK_LEN;
+
+/// Length of an ML-DSA-65 signature
+pub const SIGNATURE_LEN: usize = ml_dsa_65::SIG_LEN;
+
+/// Context string of every ML-DSA signature made by Aletheia
+const CONTEXT: &[u8] = b"ALETHEIA";
+
+/// An ML-DSA-65 key pair, kept as the 32-byte seed it is expanded from
+#[derive(Clone)]
+pub struct MlDsaKeyPair {
+    seed: [u8; 32],
+}
+
+impl core::fmt::Debug for MlDsaKeyPair {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("MlDsaKeyPair").finish_non_exhaustive()
+    }
+}
+
+impl Drop for MlDsaKeyPair {
+    fn drop(&mut self) {
+        self.seed.zeroize();
+    }
+}
+
+impl MlDsaKeyPair {
+    /// Generate a new random key pair
+    pub fn generate() -> Self {
+        let mut seed = [0u8; 32];
+        OsRng.fill_bytes(&mut seed);
+        Self { seed }
+    }
+
+    /// Load a key pair from the seed returned by [`Self::private_key_bytes`]
+    pub fn from_bytes(private_key: &[u8]) -> Result<Self> {
+        let seed: [u8; 32] = private_key
+            .try_into()
+            .map_err(|_| AletheiaError::KeyGeneration("Invalid ML-DSA seed length".into()))?;
+        Ok(Self { seed })
+    }
+
+    /// Get the private key seed (for secure storage)
+    pub fn private_key_bytes(&self) -> Vec<u8> {
+        self.seed.to_vec()
+    }
+
+    /// Get the public key bytes
+    pub fn public_key(&self) -> Vec<u8> {
+        let (public_key, _) = ml_dsa_65::KG::keygen_from_seed(&self.seed);
+        public_key.into_bytes().to_vec()
+    }
+
+    /// Sign `message` and return the signature bytes
+    ///
+    /// Signing is hedged: fresh randomness is mixed into every signature.
+    pub fn sign(&self, message: &[u8]) -> Result<Vec<u8>> {
+        let (_, private_key) = ml_dsa_65::KG::keygen_from_seed(&self.seed);
+        let signature = private_key
+            .try_sign_with_rng(&mut OsRng, message, CONTEXT)
+            .map_err(|e| AletheiaError::KeyGeneration(e.to_string()))?;
+        Ok(signature.to_vec())
+    }
+}
+
+/// Verify an ML-DSA-65 `signature` over `message` by `public_key`
+pub fn verify(public_key: &[u8], message: &[u8], signature: &[u8]) -> Result<()> {
+    let public_key = parse_public_key(public_key)?;
+    let signature: [u8; SIGNATURE_LEN] = signature
+        .try_into()
+        .map_err(|_| AletheiaError::InvalidSignature)?;
+    if public_key.verify(message, &signature, CONTEXT) {
+        Ok(())
+    } else {
+        Err(AletheiaError::InvalidSignature)
+    }
+}
+
+/// Fail unless `public_key` is an ML-DSA-65 public key
+pub(crate) fn check_public_key(public_key: &[u8]) -> Result<()> {
+    parse_public_key(public_key).map(drop)
+}
+
+fn parse_public_key(public_key: &[u8]) -> Result<ml_dsa_65::PublicKey> {
+    let public_key: [u8; PUBLIC_KEY_LEN] = public_key.try_into().map_err(|_| {
+        AletheiaError::InvalidCertificate("Invalid ML-DSA public key length".into())
+    })?;
+    ml_dsa_65::PublicKey::try_from_bytes(public_key)
+        .map_err(|e| AletheiaError::InvalidCertificate(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_and_verify() {
+        let keys = MlDsaKeyPair::generate();
+        let public_key = keys.public_key();
+        assert_eq!(public_key.len(), PUBLIC_KEY_LEN);
+
+        let signature = keys.sign(b"message").unwrap();
+        assert_eq!(signature.len(), SIGNATURE_LEN);
+        verify(&public_key, b"message", &signature).unwrap();
+
+        // The key pair round-trips through its seed
+        let restored = MlDsaKeyPair::from_bytes(&keys.private_key_bytes()).unwrap();
+        assert_eq!(restored.public_key(), public_key);
+        assert!(MlDsaKeyPair::from_bytes(&[0u8; 31]).is_err());
+
+        // Another message, a flipped bit or a truncated signature fail
+        assert!(matches!(
+            verify(&public_key, b"massage", &signature),
+            Err(AletheiaError::InvalidSignature)
+        ));
+        let mut tampered = signature.clone();
+        tampered[100] ^= 1;
+        assert!(verify(&public_key, b"message", &tampered).is_err());
+        assert!(verify(&public_key, b"message", &signature[1..]).is_err());
+        assert!(
+            verify(
+                &MlDsaKeyPair::generate().public_key(),
+                b"message",
+                &signature
+            )
+            .is_err()
+        );
+    }
+}
@@ -0,0 +1,236 @@
+//! Provenance of derived works
+//!
+//! A header can list the works its content was derived from (see
+//! [`Header::with_derived_from`](crate::Header::with_derived_from)). Each
+//! [`ContentRef`] names a source by its content digest and, optionally, by
+//! the primary signature of the `.alx` file that signed it. Given a set of
+//! files, [`verify_lineage`] verifies each of them and links every reference
+//! to the files it points to, reconstructing the edit history as a graph.
+
+extern crate alloc;
+
+use alloc::collections::BTreeSet;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::{
+    AletheiaFile, ContentRef,
+    verifier::{VerifyOptions, verify_with_options},
+};
+
+/// Source of a derived work
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// A file in the set, by index
+    File(usize),
+    /// A reference to a work that is not in the set
+    Missing(ContentRef),
+}
+
+/// One file of a lineage graph
+#[derive(Debug, Clone)]
+pub struct LineageNode {
+    /// Whether the file verified
+    pub valid: bool,
+    /// Why verification failed, if it did
+    pub error: Option<String>,
+    /// Signer's ID from the header
+    pub creator_id: String,
+    /// When the file was signed (Unix timestamp)
+    pub signed_at: i64,
+    /// SHA-256 of the file's content, if it could be determined
+    pub content_digest: Option<Vec<u8>>,
+    /// The works this file was derived from, in header order
+    ///
+    /// A reference without a signature links to every file with that
+    /// content.
+    pub sources: Vec<Source>,
+}
+
+/// Edit lineage of a set of files
+#[derive(Debug, Clone)]
+pub struct Lineage {
+    /// One node per file, in the order the files were given
+    pub nodes: Vec<LineageNode>,
+}
+
+impl Lineage {
+    /// Files that `index` was directly derived from
+    pub fn parents(&self, index: usize) -> Vec<usize> {
+        self.nodes[index]
+            .sources
+            .iter()
+            .filter_map(|source| match source {
+                Source::File(parent) => Some(*parent),
+                Source::Missing(_) => None,
+            })
+            .collect()
+    }
+
+    /// Files directly derived from `index`
+    pub fn children(&self, index: usize) -> Vec<usize> {
+        (0..self.nodes.len())
+            .filter(|&child| self.parents(child).contains(&index))
+            .collect()
+    }
+
+    /// Every file `index` descends from, nearest first
+    pub fn ancestors(&self, index: usize) -> Vec<usize> {
+        let mut seen = BTreeSet::from([index]);
+        let mut ancestors = Vec::new();
+        let mut next = 0;
+        let mut queue = self.parents(index);
+        while next < queue.len() {
+            let file = queue[next];
+            next += 1;
+            if seen.insert(file) {
+                ancestors.push(file);
+                queue.extend(self.parents(file));
+            }
+        }
+        ancestors
+    }
+
+    /// Files that were not derived from any other file in the set
+    pub fn originals(&self) -> Vec<usize> {
+        (0..self.nodes.len())
+            .filter(|&index| self.parents(index).is_empty())
+            .collect()
+    }
+}
+
+/// Verify a set of files and link each one to the sources it was derived from
+///
+/// Files that fail verification are kept in the graph and marked invalid, so
+/// that a broken link in the history remains visible.
+pub fn verify_lineage(
+    files: &[AletheiaFile],
+    trusted_root_keys: &[Vec<u8>],
+    options: &VerifyOptions,
+) -> Lineage {
+    let nodes = files
+        .iter()
+        .map(|file| {
+            let outcome = verify_with_options(file, trusted_root_keys, options);
+            let sources = file
+                .header
+                .derived_from
+                .iter()
+                .flat_map(|reference| {
+                    let found: Vec<_> = files
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, source)| reference.matches(source))
+                        .map(|(index, _)| Source::File(index))
+                        .collect();
+                    if found.is_empty() {
+                        alloc::vec![Source::Missing(reference.clone())]
+                    } else {
+                        found
+                    }
+                })
+                .collect();
+
+            LineageNode {
+                valid: outcome.is_ok(),
+                error: outcome.err().map(|e| e.to_string()),
+                creator_id: file.header.creator_id.clone(),
+                signed_at: file.header.signed_at,
+                content_digest: file.content_digest().ok(),
+                sources,
+            }
+        })
+        .collect();
+
+    Lineage { nodes }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Header,
+        ca::{CertificateAuthority, SigningKeyPair},
+        signer::Signer,
+    };
+
+    fn create_signer() -> (Signer, Vec<Vec<u8>>) {
+        let timestamp = 1704067200;
+        let ca =
+            CertificateAuthority::new_root_with_timestamp("root@example.com", "Root CA", timestamp);
+        let user_keys = SigningKeyPair::generate();
+        let user_cert = ca
+            .issue_certificate_with_timestamp(
+                "alice@example.com",
+                "Alice",
+                &user_keys.public_key(),
+                false,
+                timestamp,
+            )
+            .unwrap();
+        let signer = Signer::new(user_keys, vec![user_cert, ca.certificate.clone()]).unwrap();
+        (signer, vec![ca.public_key()])
+    }
+
+    fn header(signed_at: i64) -> Header {
+        Header::new_with_timestamp("alice@example.com", signed_at)
+    }
+
+    #[test]
+    fn test_reconstruct_lineage() {
+        let (signer, roots) = create_signer();
+        let photo = signer.sign(b"raw photo", header(1704067200)).unwrap();
+        let audio = signer.sign(b"field recording", header(1704067300)).unwrap();
+        let edit = signer
+            .sign(
+                b"cropped photo",
+                header(1704070800).with_derived_from(ContentRef::from_file(&photo).unwrap()),
+            )
+            .unwrap();
+        let remix = signer
+            .sign(
+                b"slideshow",
+                header(1704074400)
+                    .with_derived_from(ContentRef::from_file(&edit).unwrap())
+                    .with_derived_from(ContentRef::new(b"field recording"))
+                    .with_derived_from(ContentRef::new(b"stock music")),
+            )
+            .unwrap();
+
+        let files = [remix, photo, audio, edit];
+        let lineage = verify_lineage(&files, &roots, &VerifyOptions::new().archival());
+        assert!(lineage.nodes.iter().all(|node| node.valid));
+        assert_eq!(lineage.parents(0), [3, 2]);
+        assert_eq!(
+            lineage.nodes[0].sources[2],
+            Source::Missing(ContentRef::new(b"stock music"))
+        );
+        assert_eq!(lineage.ancestors(0), [3, 2, 1]);
+        assert_eq!(lineage.children(1), [3]);
+        assert_eq!(lineage.originals(), [1, 2]);
+    }
+
+    #[test]
+    fn test_signature_pins_source() {
+        let (signer, roots) = create_signer();
+        let first = signer.sign(b"draft", header(1704067200)).unwrap();
+        let resigned = signer.sign(b"draft", header(1704070800)).unwrap();
+        let mut pinned = signer
+            .sign(
+                b"final",
+                header(1704074400)
+                    .with_derived_from(ContentRef::from_file(&first).unwrap())
+                    .with_derived_from(ContentRef::new(b"draft")),
+            )
+            .unwrap();
+        pinned.signature[0] ^= 0xFF;
+
+        let files = [first, resigned, pinned];
+        let lineage = verify_lineage(&files, &roots, &VerifyOptions::new().archival());
+        // The pinned reference matches one version, the unpinned one both
+        assert_eq!(lineage.parents(2), [0, 0, 1]);
+        // A tampered file stays in the graph, marked invalid
+        assert!(!lineage.nodes[2].valid);
+        assert!(lineage.nodes[2].error.is_some());
+    }
+}
@@ -0,0 +1,259 @@
+//! Issuance registry
+//!
+//! A CA given an [`IssuanceStore`] through
+//! [`CertificateAuthority::with_issuance_store`](crate::ca::CertificateAuthority::with_issuance_store)
+//! records every certificate it issues, so the operator can audit what was
+//! issued to whom and find the serials to revoke without an external service.
+//! [`SqliteIssuanceStore`] (feature `sqlite`) keeps the records in a database
+//! file; [`MemoryIssuanceStore`] keeps them for the life of the process.
+
+extern crate alloc;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::{Certificate, Result};
+
+/// What the registry keeps about an issued certificate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IssuanceRecord {
+    /// Certificate serial number
+    pub serial: Vec<u8>,
+    /// Identity of the subject
+    pub subject_id: String,
+    /// Human-readable name of the subject
+    pub subject_name: String,
+    /// Unix timestamp of issuance
+    pub issued_at: i64,
+    /// Whether the certificate can issue others
+    pub is_ca: bool,
+}
+
+impl From<&Certificate> for IssuanceRecord {
+    fn from(certificate: &Certificate) -> Self {
+        Self {
+            serial: certificate.serial.clone(),
+            subject_id: certificate.subject_id.clone(),
+            subject_name: certificate.subject_name.clone(),
+            issued_at: certificate.issued_at,
+            is_ca: certificate.is_ca,
+        }
+    }
+}
+
+/// Storage for the records of a CA's issued certificates
+pub trait IssuanceStore: Send + Sync {
+    /// Record a newly issued certificate
+    fn record(&self, record: &IssuanceRecord) -> Result<()>;
+
+    /// All records, in issuance order
+    fn records(&self) -> Result<Vec<IssuanceRecord>>;
+
+    /// Records of the certificates issued to `subject_id`
+    fn find_by_subject(&self, subject_id: &str) -> Result<Vec<IssuanceRecord>> {
+        let mut records = self.records()?;
+        records.retain(|record| record.subject_id == subject_id);
+        Ok(records)
+    }
+}
+
+/// An in-memory issuance store
+#[cfg(feature = "std")]
+#[derive(Debug, Default)]
+pub struct MemoryIssuanceStore {
+    records: std::sync::Mutex<Vec<IssuanceRecord>>,
+}
+
+#[cfg(feature = "std")]
+impl MemoryIssuanceStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[cfg(feature = "std")]
+impl IssuanceStore for MemoryIssuanceStore {
+    fn record(&self, record: &IssuanceRecord) -> Result<()> {
+        self.records
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(record.clone());
+        Ok(())
+    }
+
+    fn records(&self) -> Result<Vec<IssuanceRecord>> {
+        Ok(self
+            .records
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone())
+    }
+}
+
+/// An issuance store in a SQLite database
+///
+/// Records live in the `issued_certificates` table, which is created if
+/// missing. A serial can be recorded only once.
+#[cfg(feature = "sqlite")]
+#[derive(Debug)]
+pub struct SqliteIssuanceStore {
+    connection: std::sync::Mutex<rusqlite::Connection>,
+}
+
+#[cfg(feature = "sqlite")]
+impl SqliteIssuanceStore {
+    /// Open (or create) the database at `path`
+    pub fn open(path: impl AsRef<std::path::Path>) -> Result<Self> {
+        Self::with_connection(rusqlite::Connection::open(path).map_err(storage_error)?)
+    }
+
+    /// Open a database that lives only in memory
+    pub fn open_in_memory() -> Result<Self> {
+        Self::with_connection(rusqlite::Connection::open_in_memory().map_err(storage_error)?)
+    }
+
+    fn with_connection(connection: rusqlite::Connection) -> Result<Self> {
+        connection
+            .execute_batch(
+                "CREATE TABLE IF NOT EXISTS issued_certificates (
+                    serial BLOB PRIMARY KEY,
+                    subject_id TEXT NOT NULL,
+                    subject_name TEXT NOT NULL,
+                    issued_at INTEGER NOT NULL,
+                    is_ca INTEGER NOT NULL
+                );
+                CREATE INDEX IF NOT EXISTS issued_certificates_subject
+                    ON issued_certificates (subject_id);",
+            )
+            .map_err(storage_error)?;
+        Ok(Self {
+            connection: std::sync::Mutex::new(connection),
+        })
+    }
+
+    fn query(&self, sql: &str, params: impl rusqlite::Params) -> Result<Vec<IssuanceRecord>> {
+        let connection = self.connection.lock().unwrap_or_else(|e| e.into_inner());
+        let mut statement = connection.prepare(sql).map_err(storage_error)?;
+        let rows = statement
+            .query_map(params, |row| {
+                Ok(IssuanceRecord {
+                    serial: row.get(0)?,
+                    subject_id: row.get(1)?,
+                    subject_name: row.get(2)?,
+                    issued_at: row.get(3)?,
+                    is_ca: row.get(4)?,
+                })
+            })
+            .map_err(storage_error)?;
+        rows.collect::<core::result::Result<_, _>>()
+            .map_err(storage_error)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl IssuanceStore for SqliteIssuanceStore {
+    fn record(&self, record: &IssuanceRecord) -> Result<()> {
+        self.connection
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .execute(
+                "INSERT INTO issued_certificates
+                    (serial, subject_id, subject_name, issued_at, is_ca)
+                    VALUES (?1, ?2, ?3, ?4, ?5)",
+                rusqlite::params![
+                    record.serial,
+                    record.subject_id,
+                    record.subject_name,
+                    record.issued_at,
+                    record.is_ca
+                ],
+            )
+            .map_err(storage_error)?;
+        Ok(())
+    }
+
+    fn records(&self) -> Result<Vec<IssuanceRecord>> {
+        self.query(
+            "SELECT serial, subject_id, subject_name, issued_at, is_ca
+                FROM issued_certificates ORDER BY rowid",
+            [],
+        )
+    }
+
+    fn find_by_subject(&self, subject_id: &str) -> Result<Vec<IssuanceRecord>> {
+        self.query(
+            "SELECT serial, subject_id, subject_name, issued_at, is_ca
+                FROM issued_certificates WHERE subject_id = ?1 ORDER BY rowid",
+            [subject_id],
+        )
+    }
+}
+
+#[cfg(feature = "sqlite")]
+fn storage_error(e: rusqlite::Error) -> crate::AletheiaError {
+    use alloc::string::ToString;
+    crate::AletheiaError::Storage(e.to_string())
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::ca::{CertificateAuthority, SigningKeyPair};
+    use alloc::sync::Arc;
+
+    fn issue_two(store: Arc<dyn IssuanceStore>) -> Vec<Certificate> {
+        let timestamp = 1704067200;
+        let ca =
+            CertificateAuthority::new_root_with_timestamp("root@example.com", "Root CA", timestamp)
+                .with_issuance_store(store);
+        ["alice@example.com", "bob@example.com"]
+            .iter()
+            .map(|id| {
+                ca.issue_certificate_with_timestamp(
+                    *id,
+                    "User",
+                    &SigningKeyPair::generate().public_key(),
+                    false,
+                    timestamp,
+                )
+                .unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_ca_records_issued_certificates() {
+        let store = Arc::new(MemoryIssuanceStore::new());
+        let certs = issue_two(store.clone());
+
+        let records = store.records().unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0], IssuanceRecord::from(&certs[0]));
+        let bob = store.find_by_subject("bob@example.com").unwrap();
+        assert_eq!(bob.len(), 1);
+        assert_eq!(bob[0].serial, certs[1].serial);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_sqlite_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("issued.db");
+        let certs = issue_two(Arc::new(SqliteIssuanceStore::open(&path).unwrap()));
+
+        // The records outlive the CA and the connection
+        let store = SqliteIssuanceStore::open(&path).unwrap();
+        let records = store.records().unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1], IssuanceRecord::from(&certs[1]));
+        assert!(records.iter().all(|record| !record.is_ca));
+        assert_eq!(store.find_by_subject("alice@example.com").unwrap().len(), 1);
+
+        // Serials are unique
+        assert!(matches!(
+            store.record(&records[0]),
+            Err(crate::AletheiaError::Storage(_))
+        ));
+    }
+}
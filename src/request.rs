@@ -0,0 +1,204 @@
+//! Certificate signing requests
+//!
+//! A subject asks a CA for a certificate by sending a [`CertificateRequest`]:
+//! the identity to certify and the public key, signed with the matching
+//! private key. The signature proves possession of the key, so a CA cannot be
+//! tricked into certifying a key the requester does not control. See
+//! [`CertificateAuthority::issue_from_request`](crate::ca::CertificateAuthority::issue_from_request).
+
+extern crate alloc;
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+
+use crate::{AletheiaError, Result, ca::SigningKeyPair};
+
+/// Domain separator for request signatures
+const REQUEST_CONTEXT: &[u8] = b"ALETHEIA-CERTIFICATE-REQUEST";
+
+/// A request for a certificate, signed by the key to be certified
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CertificateRequest {
+    /// Identity to certify (e.g., email)
+    pub subject_id: String,
+
+    /// Human-readable name of the subject
+    pub subject_name: String,
+
+    /// Ed25519 public key to certify (32 bytes)
+    #[serde(with = "serde_bytes")]
+    pub public_key: Vec<u8>,
+
+    /// Unix timestamp when the request was made
+    pub requested_at: i64,
+
+    /// Ed25519 signature by `public_key` (64 bytes)
+    #[serde(with = "serde_bytes")]
+    pub signature: Vec<u8>,
+}
+
+/// Request data without signature (used for signing)
+#[derive(Serialize)]
+struct UnsignedCertificateRequest<'a> {
+    subject_id: &'a str,
+    subject_name: &'a str,
+    #[serde(with = "serde_bytes")]
+    public_key: &'a [u8],
+    requested_at: i64,
+}
+
+impl CertificateRequest {
+    /// Create a request for `keys`, signed with them
+    #[cfg(feature = "std")]
+    pub fn new(
+        keys: &SigningKeyPair,
+        subject_id: impl Into<String>,
+        subject_name: impl Into<String>,
+    ) -> Self {
+        Self::new_with_timestamp(
+            keys,
+            subject_id,
+            subject_name,
+            chrono::Utc::now().timestamp(),
+        )
+    }
+
+    /// Create a signed request with a specific timestamp
+    pub fn new_with_timestamp(
+        keys: &SigningKeyPair,
+        subject_id: impl Into<String>,
+        subject_name: impl Into<String>,
+        requested_at: i64,
+    ) -> Self {
+        let mut request = Self {
+            subject_id: subject_id.into(),
+            subject_name: subject_name.into(),
+            public_key: keys.public_key(),
+            requested_at,
+            signature: Vec::new(),
+        };
+        request.signature = keys.sign(&request.signable_data());
+        request
+    }
+
+    /// Get the data signed by the requester (context and everything except
+    /// the signature)
+    pub fn signable_data(&self) -> Vec<u8> {
+        let unsigned = UnsignedCertificateRequest {
+            subject_id: &self.subject_id,
+            subject_name: &self.subject_name,
+            public_key: &self.public_key,
+            requested_at: self.requested_at,
+        };
+        let mut data = REQUEST_CONTEXT.to_vec();
+        ciborium::into_writer(&unsigned, &mut data).expect("CBOR encoding failed");
+        data
+    }
+
+    /// Check proof of possession: the request is signed by its own key
+    pub fn verify(&self) -> Result<()> {
+        let verifying_key = VerifyingKey::try_from(self.public_key.as_slice()).map_err(|e| {
+            AletheiaError::InvalidCertificate(alloc::format!("Invalid public key: {}", e))
+        })?;
+        let signature = Signature::try_from(self.signature.as_slice()).map_err(|e| {
+            AletheiaError::InvalidCertificate(alloc::format!("Invalid signature format: {}", e))
+        })?;
+        verifying_key
+            .verify(&self.signable_data(), &signature)
+            .map_err(|_| {
+                AletheiaError::InvalidCertificate(
+                    "Certificate request is not signed by its key".into(),
+                )
+            })
+    }
+
+    /// Encode the request as CBOR
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut data = Vec::new();
+        ciborium::into_writer(self, &mut data)
+            .map_err(|e| AletheiaError::CborEncode(e.to_string()))?;
+        Ok(data)
+    }
+
+    /// Decode a request from CBOR
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        ciborium::from_reader(data).map_err(|e| AletheiaError::CborDecode(e.to_string()))
+    }
+
+    /// Encode the request as PEM (label [`REQUEST_PEM_LABEL`])
+    #[cfg(feature = "pkcs8")]
+    pub fn to_pem(&self) -> String {
+        let mut data = Vec::new();
+        ciborium::into_writer(self, &mut data).expect("CBOR encoding failed");
+        pkcs8::der::pem::encode_string(REQUEST_PEM_LABEL, pkcs8::LineEnding::LF, &data)
+            .expect("PEM encoding failed")
+    }
+
+    /// Decode a request written by [`Self::to_pem`]
+    #[cfg(feature = "pkcs8")]
+    pub fn from_pem(pem: &str) -> Result<Self> {
+        let (label, data) = pkcs8::der::pem::decode_vec(pem.as_bytes())
+            .map_err(|e| AletheiaError::InvalidCertificate(e.to_string()))?;
+        if label != REQUEST_PEM_LABEL {
+            return Err(AletheiaError::InvalidCertificate(alloc::format!(
+                "Unexpected PEM label '{label}'"
+            )));
+        }
+        Self::from_bytes(&data)
+    }
+}
+
+/// PEM label of an encoded [`CertificateRequest`]
+#[cfg(feature = "pkcs8")]
+pub const REQUEST_PEM_LABEL: &str = "ALETHEIA CERTIFICATE REQUEST";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ca::CertificateAuthority, certificate::verify_certificate_signature};
+
+    #[test]
+    fn test_issue_from_request() {
+        let timestamp = 1704067200;
+        let ca =
+            CertificateAuthority::new_root_with_timestamp("root@example.com", "Root CA", timestamp);
+        let keys = SigningKeyPair::generate();
+        let request =
+            CertificateRequest::new_with_timestamp(&keys, "alice@example.com", "Alice", timestamp);
+        let request = CertificateRequest::from_bytes(&request.to_bytes().unwrap()).unwrap();
+
+        let cert = ca
+            .issue_from_request_with_options(&request, false, timestamp, &Default::default())
+            .unwrap();
+        assert_eq!(cert.subject_id, "alice@example.com");
+        assert_eq!(cert.public_key, keys.public_key());
+        verify_certificate_signature(&cert, &ca.public_key()).unwrap();
+    }
+
+    #[test]
+    fn test_request_without_possession_is_rejected() {
+        let timestamp = 1704067200;
+        let ca =
+            CertificateAuthority::new_root_with_timestamp("root@example.com", "Root CA", timestamp);
+        let mallory = SigningKeyPair::generate();
+        let victim = SigningKeyPair::generate();
+
+        // Mallory signs a request naming someone else's key
+        let mut request =
+            CertificateRequest::new_with_timestamp(&mallory, "mallory@example.com", "M", timestamp);
+        request.public_key = victim.public_key();
+        assert!(request.verify().is_err());
+        assert!(matches!(
+            ca.issue_from_request_with_options(&request, false, timestamp, &Default::default()),
+            Err(AletheiaError::InvalidCertificate(_))
+        ));
+
+        // Or alters the identity after signing
+        let mut request =
+            CertificateRequest::new_with_timestamp(&mallory, "mallory@example.com", "M", timestamp);
+        request.subject_id = "alice@example.com".into();
+        assert!(request.verify().is_err());
+    }
+}
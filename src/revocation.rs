@@ -0,0 +1,558 @@
+extern crate alloc;
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+
+use crate::{AletheiaError, Certificate, Result, certificate::ValidityCheck, types::serde_base64};
+
+/// Domain separator for status assertion signatures
+const STATUS_CONTEXT: &[u8] = b"ALETHEIA-CERTIFICATE-STATUS";
+
+/// A single revoked certificate
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RevokedCertificate {
+    /// Serial number of the revoked certificate
+    #[serde(with = "serde_bytes")]
+    pub serial: Vec<u8>,
+
+    /// Unix timestamp of revocation
+    pub revoked_at: i64,
+
+    /// Why the certificate was revoked (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl RevokedCertificate {
+    pub fn new(serial: impl Into<Vec<u8>>, revoked_at: i64) -> Self {
+        Self {
+            serial: serial.into(),
+            revoked_at,
+            reason: None,
+        }
+    }
+
+    pub fn with_reason(mut self, reason: impl Into<String>) -> Self {
+        self.reason = Some(reason.into());
+        self
+    }
+
+    /// Whether the revocation is in effect under `validity`
+    ///
+    /// With [`ValidityCheck::At`], revocations after that time are not.
+    pub fn applies_at(&self, validity: ValidityCheck) -> bool {
+        match validity {
+            ValidityCheck::At(at) => self.revoked_at <= at,
+            ValidityCheck::Skip => true,
+        }
+    }
+}
+
+/// A certificate revocation list (CRL) signed by a CA
+///
+/// The list only applies to certificates issued by `issuer_id`. It is
+/// distributed out-of-band and passed to the verifier explicitly.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RevocationList {
+    /// Identity of the CA that published the list
+    pub issuer_id: String,
+
+    /// Unix timestamp when the list was published
+    pub issued_at: i64,
+
+    /// CRL number, increased by the issuer with every change to the list
+    ///
+    /// Signed with the list, so a relying party holding a newer list can
+    /// reject an older one replayed to it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub number: Option<u64>,
+
+    /// Revoked certificates
+    pub entries: Vec<RevokedCertificate>,
+
+    /// Ed25519 signature by the issuing CA (64 bytes)
+    #[serde(with = "serde_bytes")]
+    pub signature: Vec<u8>,
+}
+
+/// Revocation list data without signature (used for signing)
+#[derive(Serialize)]
+struct UnsignedRevocationList<'a> {
+    issuer_id: &'a str,
+    issued_at: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    number: Option<u64>,
+    entries: &'a [RevokedCertificate],
+}
+
+impl RevocationList {
+    /// Get the data that is signed by the issuer (everything except the signature)
+    pub fn signable_data(&self) -> Vec<u8> {
+        let unsigned = UnsignedRevocationList {
+            issuer_id: &self.issuer_id,
+            issued_at: self.issued_at,
+            number: self.number,
+            entries: &self.entries,
+        };
+        let mut data = Vec::new();
+        ciborium::into_writer(&unsigned, &mut data).expect("CBOR encoding failed");
+        data
+    }
+
+    /// Verify that the list was signed by the given issuer key
+    pub fn verify_signature(&self, issuer_public_key: &[u8]) -> Result<()> {
+        let verifying_key = VerifyingKey::try_from(issuer_public_key).map_err(|e| {
+            AletheiaError::InvalidRevocationList(alloc::format!("Invalid issuer public key: {}", e))
+        })?;
+
+        let signature = Signature::try_from(self.signature.as_slice()).map_err(|e| {
+            AletheiaError::InvalidRevocationList(alloc::format!("Invalid signature format: {}", e))
+        })?;
+
+        verifying_key
+            .verify(&self.signable_data(), &signature)
+            .map_err(|_| {
+                AletheiaError::InvalidRevocationList("Signature verification failed".into())
+            })
+    }
+
+    /// Find the entry for a serial number, if it has been revoked
+    pub fn find(&self, serial: &[u8]) -> Option<&RevokedCertificate> {
+        self.entries.iter().find(|entry| entry.serial == serial)
+    }
+
+    /// Find the entry for a serial number, if it was revoked under `validity`
+    ///
+    /// With [`ValidityCheck::At`], revocations after that time are ignored.
+    pub fn find_at(&self, serial: &[u8], validity: ValidityCheck) -> Option<&RevokedCertificate> {
+        self.find(serial).filter(|entry| entry.applies_at(validity))
+    }
+
+    /// Check a (verified) certificate chain against this list
+    ///
+    /// The list is authenticated with the key of a chain certificate whose
+    /// subject matches `issuer_id`; after a key rotation the old and new keys
+    /// share that subject, so any of them may have signed it. Lists from CAs
+    /// outside the chain do not apply and are ignored.
+    pub fn check_chain(&self, chain: &[Certificate]) -> Result<()> {
+        self.check_chain_at(chain, ValidityCheck::Skip)
+    }
+
+    /// Check a (verified) certificate chain against the revocations in effect
+    /// under `validity`
+    pub fn check_chain_at(&self, chain: &[Certificate], validity: ValidityCheck) -> Result<()> {
+        let mut issuers = chain
+            .iter()
+            .filter(|c| c.subject_id == self.issuer_id)
+            .peekable();
+        if issuers.peek().is_none() {
+            return Ok(());
+        }
+        if !issuers.any(|issuer| self.verify_signature(&issuer.public_key).is_ok()) {
+            return Err(AletheiaError::InvalidRevocationList(
+                "Signature verification failed".into(),
+            ));
+        }
+
+        for cert in chain.iter().filter(|c| c.issuer_id == self.issuer_id) {
+            if self.find_at(&cert.serial, validity).is_some() {
+                return Err(AletheiaError::CertificateRevoked(serial_hex(&cert.serial)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Encode the list as CBOR
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut data = Vec::new();
+        ciborium::into_writer(self, &mut data)
+            .map_err(|e| AletheiaError::CborEncode(e.to_string()))?;
+        Ok(data)
+    }
+
+    /// Decode a list from CBOR
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        ciborium::from_reader(data).map_err(|e| AletheiaError::CborDecode(e.to_string()))
+    }
+}
+
+/// Status of a certificate as asserted by its issuer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CertificateStatus {
+    /// Not revoked
+    Good,
+    /// Revoked by the issuer
+    Revoked,
+    /// Not known to the issuer
+    Unknown,
+}
+
+/// A short-lived statement by a CA of one certificate's status
+///
+/// Like an OCSP response, but produced ahead of time: the signer staples a
+/// fresh "good" assertion into the file, and verifiers check revocation
+/// without contacting the CA. See
+/// [`CertificateAuthority::sign_status`](crate::ca::CertificateAuthority::sign_status).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusAssertion {
+    /// Identity of the CA that issued the certificate
+    pub issuer_id: String,
+
+    /// Serial number of the certificate
+    #[serde(with = "serde_base64")]
+    pub serial: Vec<u8>,
+
+    /// The asserted status
+    pub status: CertificateStatus,
+
+    /// Unix timestamp when the assertion was made
+    pub produced_at: i64,
+
+    /// Unix timestamp after which the assertion must not be relied on
+    pub next_update: i64,
+
+    /// Ed25519 signature by the issuing CA (64 bytes)
+    #[serde(with = "serde_base64")]
+    pub signature: Vec<u8>,
+}
+
+/// Status assertion data without signature (used for signing)
+#[derive(Serialize)]
+struct UnsignedStatusAssertion<'a> {
+    issuer_id: &'a str,
+    #[serde(with = "serde_bytes")]
+    serial: &'a [u8],
+    status: CertificateStatus,
+    produced_at: i64,
+    next_update: i64,
+}
+
+impl StatusAssertion {
+    /// Get the data signed by the issuer (context and everything except the
+    /// signature)
+    pub fn signable_data(&self) -> Vec<u8> {
+        let unsigned = UnsignedStatusAssertion {
+            issuer_id: &self.issuer_id,
+            serial: &self.serial,
+            status: self.status,
+            produced_at: self.produced_at,
+            next_update: self.next_update,
+        };
+        let mut data = STATUS_CONTEXT.to_vec();
+        ciborium::into_writer(&unsigned, &mut data).expect("CBOR encoding failed");
+        data
+    }
+
+    /// Verify that the assertion was signed by the given issuer key
+    pub fn verify_signature(&self, issuer_public_key: &[u8]) -> Result<()> {
+        let verifying_key = VerifyingKey::try_from(issuer_public_key).map_err(|e| {
+            AletheiaError::InvalidRevocationList(alloc::format!("Invalid issuer public key: {}", e))
+        })?;
+
+        let signature = Signature::try_from(self.signature.as_slice()).map_err(|e| {
+            AletheiaError::InvalidRevocationList(alloc::format!("Invalid signature format: {}", e))
+        })?;
+
+        verifying_key
+            .verify(&self.signable_data(), &signature)
+            .map_err(|_| {
+                AletheiaError::InvalidRevocationList("Signature verification failed".into())
+            })
+    }
+
+    /// Whether the assertion may be relied on under `validity`
+    ///
+    /// Archival verification accepts assertions of any age.
+    pub fn is_current(&self, validity: ValidityCheck) -> bool {
+        match validity {
+            ValidityCheck::At(at) => self.produced_at <= at && at <= self.next_update,
+            ValidityCheck::Skip => true,
+        }
+    }
+
+    /// Encode the assertion as CBOR
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut data = Vec::new();
+        ciborium::into_writer(self, &mut data)
+            .map_err(|e| AletheiaError::CborEncode(e.to_string()))?;
+        Ok(data)
+    }
+
+    /// Decode an assertion from CBOR
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        ciborium::from_reader(data).map_err(|e| AletheiaError::CborDecode(e.to_string()))
+    }
+}
+
+/// Check a (verified) certificate chain against status assertions
+///
+/// Each certificate is matched with the assertions about its serial signed
+/// by the next certificate in the chain; assertions that do not verify or
+/// are not current under `validity` are ignored. Fails if a certificate is
+/// asserted revoked or, with `require`, if one other than the root has no
+/// current "good" assertion.
+pub(crate) fn check_chain_status(
+    chain: &[Certificate],
+    assertions: &[StatusAssertion],
+    validity: ValidityCheck,
+    require: bool,
+) -> Result<()> {
+    for (cert, issuer) in chain.iter().zip(chain.iter().skip(1)) {
+        let mut good = false;
+        for assertion in assertions.iter().filter(|a| {
+            a.issuer_id == issuer.subject_id
+                && a.serial == cert.serial
+                && a.is_current(validity)
+                && a.verify_signature(&issuer.public_key).is_ok()
+        }) {
+            match assertion.status {
+                CertificateStatus::Revoked => {
+                    return Err(AletheiaError::CertificateRevoked(serial_hex(&cert.serial)));
+                }
+                CertificateStatus::Good => good = true,
+                CertificateStatus::Unknown => {}
+            }
+        }
+        if require && !good {
+            return Err(AletheiaError::MissingCertificateStatus(serial_hex(
+                &cert.serial,
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// A source of certificate revocations queried during verification
+///
+/// Unlike revocation lists and status assertions, which the verifier is
+/// given up front, a checker looks certificates up one at a time, typically
+/// from a CA's online service; see
+/// [`verify_with_checker`](crate::verifier::verify_with_checker).
+pub trait RevocationChecker {
+    /// The revocation of the certificate with `serial` issued by
+    /// `issuer_id`, or `None` if it is not revoked
+    ///
+    /// Fails if the status cannot be determined, for example because the
+    /// service is unreachable.
+    fn check(
+        &self,
+        issuer_id: &str,
+        serial: &[u8],
+    ) -> impl Future<Output = Result<Option<RevokedCertificate>>> + Send;
+}
+
+/// What verification does when a [`RevocationChecker`] fails
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FailurePolicy {
+    /// Treat the certificate as not revoked
+    #[default]
+    SoftFail,
+    /// Fail verification
+    HardFail,
+}
+
+/// Format a serial number as lowercase hex
+pub(crate) fn serial_hex(serial: &[u8]) -> String {
+    use core::fmt::Write;
+
+    let mut hex = String::with_capacity(serial.len() * 2);
+    for byte in serial {
+        let _ = write!(hex, "{:02x}", byte);
+    }
+    hex
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ca::{CertificateAuthority, SigningKeyPair};
+
+    #[test]
+    fn test_revocation_list_roundtrip() {
+        let ca = CertificateAuthority::new_root_with_timestamp(
+            "root@example.com",
+            "Root CA",
+            1704067200,
+        );
+        let crl = ca
+            .create_revocation_list_with_timestamp(
+                vec![
+                    RevokedCertificate::new(vec![1u8; 16], 1704067300)
+                        .with_reason("key compromise"),
+                ],
+                1704067400,
+            )
+            .unwrap();
+
+        let decoded = RevocationList::from_bytes(&crl.to_bytes().unwrap()).unwrap();
+        decoded.verify_signature(&ca.public_key()).unwrap();
+        assert_eq!(
+            decoded.find(&[1u8; 16]).unwrap().reason.as_deref(),
+            Some("key compromise")
+        );
+        assert!(decoded.find(&[2u8; 16]).is_none());
+        assert_eq!(decoded.number, None);
+    }
+
+    #[test]
+    fn test_numbered_revocation_list() {
+        let ca = CertificateAuthority::new_root_with_timestamp(
+            "root@example.com",
+            "Root CA",
+            1704067200,
+        );
+        let crl = ca
+            .create_numbered_revocation_list(
+                vec![RevokedCertificate::new(vec![1u8; 16], 1704067300)],
+                7,
+                1704067400,
+            )
+            .unwrap();
+
+        let decoded = RevocationList::from_bytes(&crl.to_bytes().unwrap()).unwrap();
+        decoded.verify_signature(&ca.public_key()).unwrap();
+        assert_eq!(decoded.number, Some(7));
+
+        // The number is signed
+        let mut replayed = decoded;
+        replayed.number = Some(8);
+        assert!(replayed.verify_signature(&ca.public_key()).is_err());
+    }
+
+    #[test]
+    fn test_check_chain() {
+        let ca = CertificateAuthority::new_root_with_timestamp(
+            "root@example.com",
+            "Root CA",
+            1704067200,
+        );
+        let user_keys = SigningKeyPair::generate();
+        let user_cert = ca
+            .issue_certificate_with_timestamp(
+                "alice@example.com",
+                "Alice",
+                &user_keys.public_key(),
+                false,
+                1704067200,
+            )
+            .unwrap();
+        let chain = vec![user_cert.clone(), ca.certificate.clone()];
+
+        let empty = ca
+            .create_revocation_list_with_timestamp(Vec::new(), 1704067400)
+            .unwrap();
+        empty.check_chain(&chain).unwrap();
+
+        let mut crl = ca
+            .create_revocation_list_with_timestamp(
+                vec![RevokedCertificate::new(
+                    user_cert.serial.clone(),
+                    1704067300,
+                )],
+                1704067400,
+            )
+            .unwrap();
+        assert!(matches!(
+            crl.check_chain(&chain),
+            Err(AletheiaError::CertificateRevoked(_))
+        ));
+
+        // A forged list must not be accepted
+        crl.entries.clear();
+        assert!(matches!(
+            crl.check_chain(&chain),
+            Err(AletheiaError::InvalidRevocationList(_))
+        ));
+    }
+
+    #[test]
+    fn test_check_chain_after_rotation() {
+        let timestamp = 1704067200;
+        let old =
+            CertificateAuthority::new_root_with_timestamp("root@example.com", "Root CA", timestamp);
+        let rotation = old.rotate_with_timestamp(timestamp + 100, 86400).unwrap();
+        let user_keys = SigningKeyPair::generate();
+        let user_cert = rotation
+            .successor
+            .issue_certificate_with_timestamp(
+                "alice@example.com",
+                "Alice",
+                &user_keys.public_key(),
+                false,
+                timestamp + 200,
+            )
+            .unwrap();
+        // The new key, cross-signed by the old root, precedes the old root
+        let chain = vec![
+            user_cert.clone(),
+            rotation.new_signed_by_old.clone(),
+            old.certificate.clone(),
+        ];
+
+        let crl = old
+            .create_revocation_list_with_timestamp(
+                vec![RevokedCertificate::new(
+                    user_cert.serial.clone(),
+                    timestamp + 300,
+                )],
+                timestamp + 400,
+            )
+            .unwrap();
+        assert!(matches!(
+            crl.check_chain(&chain),
+            Err(AletheiaError::CertificateRevoked(_))
+        ));
+    }
+
+    #[test]
+    fn test_check_chain_status() {
+        let timestamp = 1704067200;
+        let ca =
+            CertificateAuthority::new_root_with_timestamp("root@example.com", "Root CA", timestamp);
+        let user_cert = ca
+            .issue_certificate_with_timestamp(
+                "alice@example.com",
+                "Alice",
+                &SigningKeyPair::generate().public_key(),
+                false,
+                timestamp,
+            )
+            .unwrap();
+        let chain = vec![user_cert.clone(), ca.certificate.clone()];
+        let now = ValidityCheck::At(timestamp + 60);
+
+        let good = ca
+            .sign_status_with_timestamp(&user_cert.serial, CertificateStatus::Good, 3600, timestamp)
+            .unwrap();
+        let good = StatusAssertion::from_bytes(&good.to_bytes().unwrap()).unwrap();
+        check_chain_status(&chain, core::slice::from_ref(&good), now, true).unwrap();
+        check_chain_status(&chain, &[], now, false).unwrap();
+        assert!(matches!(
+            check_chain_status(&chain, &[], now, true),
+            Err(AletheiaError::MissingCertificateStatus(_))
+        ));
+
+        // Expired or forged assertions do not count
+        let later = ValidityCheck::At(timestamp + 7200);
+        assert!(check_chain_status(&chain, core::slice::from_ref(&good), later, true).is_err());
+        let mut forged = good.clone();
+        forged.next_update += 86400;
+        assert!(check_chain_status(&chain, &[forged], later, true).is_err());
+
+        let revoked = ca
+            .sign_status_with_timestamp(
+                &user_cert.serial,
+                CertificateStatus::Revoked,
+                3600,
+                timestamp,
+            )
+            .unwrap();
+        assert!(matches!(
+            check_chain_status(&chain, &[good, revoked], now, false),
+            Err(AletheiaError::CertificateRevoked(_))
+        ));
+    }
+}
@@ -1,18 +1,34 @@
 extern crate alloc;
 
+#[cfg(feature = "compression")]
+use crate::CompressionAlgorithm;
+#[cfg(feature = "ml-dsa")]
+use crate::pq::MlDsaKeyPair;
 use crate::{
-    AletheiaError, AletheiaFile, Certificate, Flags, Header, MAGIC_BYTES, Result, VERSION_MAJOR,
-    VERSION_MINOR, ca::SigningKeyPair,
+    AletheiaError, AletheiaFile, Certificate, ChunkInfo, CoSignature, Countersignature, Flags,
+    Header, MAGIC_BYTES, Result, VERSION_MAJOR, VERSION_MINOR,
+    ca::SigningKeyPair,
+    hsm::{ExternalSigner, KeyHandle},
+    merkle,
+    revocation::StatusAssertion,
 };
-use alloc::string::ToString;
+use alloc::borrow::Cow;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
 use alloc::vec::Vec;
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use sha2::{Digest, Sha512};
 
 /// Builder for creating signed Aletheia files
 pub struct Signer {
-    signing_key: SigningKeyPair,
+    signing_key: KeyHandle,
     certificate_chain: Vec<Certificate>,
     #[cfg(feature = "compression")]
-    compress: bool,
+    compression: Option<CompressionAlgorithm>,
+    chunk_size: Option<u32>,
+    status_assertions: Vec<StatusAssertion>,
+    #[cfg(feature = "ml-dsa")]
+    pq_key: Option<MlDsaKeyPair>,
 }
 
 impl Signer {
@@ -21,10 +37,27 @@ impl Signer {
     /// The certificate chain should be ordered: [creator_cert, ..., root_cert]
     /// The first certificate must contain the public key matching the signing key.
     pub fn new(signing_key: SigningKeyPair, certificate_chain: Vec<Certificate>) -> Result<Self> {
+        Self::from_handle(signing_key.into(), certificate_chain)
+    }
+
+    /// Create a signer whose key is held by an external signer, such as a
+    /// hardware token
+    ///
+    /// Files are signed in memory: [`Signer::finalize`] and
+    /// [`Signer::sign_stream`] need a key in memory.
+    pub fn new_external(
+        signer: Arc<dyn ExternalSigner>,
+        certificate_chain: Vec<Certificate>,
+    ) -> Result<Self> {
+        Self::from_handle(KeyHandle::external(signer)?, certificate_chain)
+    }
+
+    fn from_handle(signing_key: KeyHandle, certificate_chain: Vec<Certificate>) -> Result<Self> {
         if certificate_chain.is_empty() {
-            return Err(AletheiaError::CertificateChainInvalid(
-                "Certificate chain cannot be empty".into(),
-            ));
+            return Err(AletheiaError::CertificateChainInvalid {
+                step: 0,
+                reason: "Certificate chain cannot be empty".into(),
+            });
         }
 
         // Verify the signing key matches the first certificate
@@ -39,29 +72,111 @@ impl Signer {
             signing_key,
             certificate_chain,
             #[cfg(feature = "compression")]
-            compress: false,
+            compression: None,
+            chunk_size: None,
+            status_assertions: Vec::new(),
+            #[cfg(feature = "ml-dsa")]
+            pq_key: None,
         })
     }
 
-    /// Enable compression for payloads
+    /// Enable LZ4 compression for payloads
     #[cfg(feature = "compression")]
-    pub fn with_compression(mut self) -> Self {
-        self.compress = true;
+    pub fn with_compression(self) -> Self {
+        self.with_compression_algorithm(CompressionAlgorithm::Lz4)
+    }
+
+    /// Enable compression for payloads with a specific algorithm
+    #[cfg(feature = "compression")]
+    pub fn with_compression_algorithm(mut self, algorithm: CompressionAlgorithm) -> Self {
+        self.compression = Some(algorithm);
+        self
+    }
+
+    /// Sign a Merkle root over `chunk_size` chunks instead of the whole payload
+    ///
+    /// Chunked files allow verifying a single chunk with
+    /// [`crate::verifier::verify_chunk`]. Chunking cannot be combined with
+    /// compression.
+    pub fn with_chunking(mut self, chunk_size: u32) -> Self {
+        self.chunk_size = Some(chunk_size);
+        self
+    }
+
+    /// Staple `assertion` to every file this signer produces
+    ///
+    /// Use fresh "good" assertions from the issuers of the certificate chain
+    /// (see [`crate::ca::CertificateAuthority::sign_status`]) so verifiers can
+    /// check revocation offline. The trailer is not signed, so assertions can
+    /// also be added to `status_assertions` of a signed file later.
+    pub fn with_status_assertion(mut self, assertion: StatusAssertion) -> Self {
+        self.status_assertions.push(assertion);
         self
     }
 
+    /// Also sign files with the ML-DSA `key`, for hybrid signatures
+    ///
+    /// `key` must match the ML-DSA public key in the creator's certificate.
+    /// Co-signatures and countersignatures by this signer stay Ed25519 only.
+    #[cfg(feature = "ml-dsa")]
+    pub fn with_ml_dsa_key(mut self, key: MlDsaKeyPair) -> Result<Self> {
+        if self.certificate_chain[0].pq_public_key.as_deref() != Some(key.public_key().as_slice()) {
+            return Err(AletheiaError::InvalidCertificate(
+                "ML-DSA key does not match creator certificate".into(),
+            ));
+        }
+        self.pq_key = Some(key);
+        Ok(self)
+    }
+
     /// Sign data and create an Aletheia file structure
+    ///
+    /// The header's content digest is set to the SHA-256 of `payload`.
     pub fn sign(&self, payload: &[u8], header: Header) -> Result<AletheiaFile> {
+        self.sign_payload(Cow::Borrowed(payload), header)
+    }
+
+    /// Sign an owned payload, moving it into the file instead of copying it
+    ///
+    /// Produces the same file as [`Signer::sign`], but an uncompressed
+    /// payload is stored without a second copy, halving peak memory for
+    /// large content.
+    pub fn sign_owned(&self, payload: Vec<u8>, header: Header) -> Result<AletheiaFile> {
+        self.sign_payload(Cow::Owned(payload), header)
+    }
+
+    fn sign_payload(&self, payload: Cow<'_, [u8]>, header: Header) -> Result<AletheiaFile> {
+        let mut header = header.with_content_digest(&payload);
+
         #[cfg(feature = "compression")]
-        let (flags, processed_payload) = if self.compress {
-            let compressed = lz4_flex::compress_prepend_size(payload);
-            (Flags::new().with_compression(), compressed)
-        } else {
-            (Flags::new(), payload.to_vec())
+        let (flags, processed_payload) = match self.compression {
+            Some(algorithm) => (
+                Flags::new().with_compression_algorithm(algorithm),
+                Cow::Owned(crate::compression::compress(algorithm, &payload)?),
+            ),
+            None => (Flags::new(), payload),
         };
 
         #[cfg(not(feature = "compression"))]
-        let (flags, processed_payload) = (Flags::new(), payload.to_vec());
+        let (flags, processed_payload) = (Flags::new(), payload);
+
+        let flags = match self.chunk_size {
+            Some(chunk_size) => {
+                let chunk_size = check_chunk_size(chunk_size)?;
+                if flags.is_compressed() {
+                    return Err(AletheiaError::Compression(
+                        "Compression is not supported for chunked payloads".into(),
+                    ));
+                }
+                header.chunks = Some(ChunkInfo {
+                    chunk_size,
+                    total_len: processed_payload.len() as u64,
+                    merkle_root: merkle::merkle_root(&processed_payload, chunk_size).to_vec(),
+                });
+                flags.with_chunking()
+            }
+            None => flags,
+        };
 
         // Encode header as CBOR
         let mut header_bytes = Vec::new();
@@ -73,36 +188,847 @@ impl Signer {
         ciborium::into_writer(&self.certificate_chain, &mut cert_chain_bytes)
             .map_err(|e| AletheiaError::CborEncode(e.to_string()))?;
 
-        // Build the data to sign
-        let signature_input =
-            build_signature_input(&flags, &header_bytes, &processed_payload, &cert_chain_bytes);
+        // Sign the file contents
+        let (signature, pq_signature) = self.sign_input(&SignatureInput {
+            version_major: VERSION_MAJOR,
+            version_minor: VERSION_MINOR,
+            flags: &flags,
+            header_bytes: &header_bytes,
+            payload: &processed_payload,
+            cert_chain_bytes: &cert_chain_bytes,
+        })?;
 
-        // Sign it
-        let signature = self.signing_key.sign(&signature_input);
+        Ok(AletheiaFile {
+            version_major: VERSION_MAJOR,
+            version_minor: VERSION_MINOR,
+            flags,
+            header,
+            payload: processed_payload.into_owned(),
+            certificate_chain: self.certificate_chain.clone(),
+            signature,
+            pq_signature,
+            co_signatures: Vec::new(),
+            countersignatures: Vec::new(),
+            timestamp_tokens: Vec::new(),
+            log_entries: Vec::new(),
+            opentimestamps: Vec::new(),
+            status_assertions: self.status_assertions.clone(),
+        })
+    }
+
+    /// Sign a detached manifest for `payload`
+    ///
+    /// The resulting file carries no payload. The signature covers the header,
+    /// whose content digest binds the payload, so the content can be stored and
+    /// distributed separately. Compression and chunking do not apply.
+    pub fn sign_detached(&self, payload: &[u8], header: Header) -> Result<AletheiaFile> {
+        let header = header.with_content_digest(payload);
+        let flags = Flags::new().with_detached();
+
+        // Encode header as CBOR
+        let mut header_bytes = Vec::new();
+        ciborium::into_writer(&header, &mut header_bytes)
+            .map_err(|e| AletheiaError::CborEncode(e.to_string()))?;
+
+        // Encode certificate chain as CBOR
+        let mut cert_chain_bytes = Vec::new();
+        ciborium::into_writer(&self.certificate_chain, &mut cert_chain_bytes)
+            .map_err(|e| AletheiaError::CborEncode(e.to_string()))?;
+
+        let (signature, pq_signature) = self.sign_input(&SignatureInput {
+            version_major: VERSION_MAJOR,
+            version_minor: VERSION_MINOR,
+            flags: &flags,
+            header_bytes: &header_bytes,
+            payload: &[],
+            cert_chain_bytes: &cert_chain_bytes,
+        })?;
 
         Ok(AletheiaFile {
             version_major: VERSION_MAJOR,
             version_minor: VERSION_MINOR,
             flags,
             header,
-            payload: processed_payload,
+            payload: Vec::new(),
             certificate_chain: self.certificate_chain.clone(),
             signature,
+            pq_signature,
+            co_signatures: Vec::new(),
+            countersignatures: Vec::new(),
+            timestamp_tokens: Vec::new(),
+            log_entries: Vec::new(),
+            opentimestamps: Vec::new(),
+            status_assertions: self.status_assertions.clone(),
         })
     }
 
+    /// Re-sign a signed file as a detached manifest, dropping its payload
+    ///
+    /// The manifest keeps the file's header (without chunk information) and
+    /// records the payload's digest, so it proves the same claims for content
+    /// distributed separately. Stripping the payload invalidates the file's
+    /// signature, so only its creator can do this: the signer's key must be
+    /// the one the file was signed with. Co-signatures and other evidence over
+    /// the old signature are not carried over.
+    pub fn detach(&self, file: &AletheiaFile) -> Result<AletheiaFile> {
+        if file.flags.is_detached() {
+            return Err(AletheiaError::DetachedPayload);
+        }
+        let creator = file.certificate_chain.first().ok_or_else(|| {
+            AletheiaError::CertificateChainInvalid {
+                step: 0,
+                reason: "Certificate chain cannot be empty".into(),
+            }
+        })?;
+        if creator.public_key != self.certificate_chain[0].public_key {
+            return Err(AletheiaError::InvalidCertificate(
+                "Signing key does not match the file's creator".into(),
+            ));
+        }
+
+        let payload = file.get_payload()?;
+        let mut header = file.header.clone();
+        header.chunks = None;
+        self.sign_detached(&payload, header)
+    }
+
+    /// Sign an unsigned draft in place
+    ///
+    /// `draft` holds a draft written by [`crate::file::write_unsigned`]. The
+    /// payload is streamed into the signature and the certificate chain and
+    /// signature are appended, so the payload bytes are never rewritten. The
+    /// draft's header is signed as it is, with its content digest unchecked;
+    /// verification checks it. Drafts must use a prehashed format version
+    /// (1.1 or later).
+    #[cfg(feature = "std")]
+    pub fn finalize<F: std::io::Read + std::io::Write + std::io::Seek>(
+        &self,
+        mut draft: F,
+    ) -> Result<()> {
+        use crate::file::{append_signature, read_chunks, read_draft};
+
+        let file = read_draft(&mut draft)?;
+        let peeked = &file.peeked;
+        if !is_prehashed(peeked.version_major, peeked.version_minor) {
+            return Err(AletheiaError::InvalidDraft(alloc::format!(
+                "Format version {}.{} cannot be signed as a stream",
+                peeked.version_major,
+                peeked.version_minor
+            )));
+        }
+
+        let mut cert_chain_bytes = Vec::new();
+        ciborium::into_writer(&self.certificate_chain, &mut cert_chain_bytes)
+            .map_err(|e| AletheiaError::CborEncode(e.to_string()))?;
+
+        let mut digest = Sha512::new();
+        digest.update(signature_input_prefix(
+            peeked.version_major,
+            peeked.version_minor,
+            &peeked.flags,
+            &file.header_bytes,
+            file.payload_len,
+        ));
+        read_chunks(&mut draft, file.payload_len, |chunk| {
+            digest.update(chunk);
+            Ok(())
+        })?;
+        digest.update(signature_input_suffix(&cert_chain_bytes));
+        let pq_signature = self.pq_sign(|| digest.clone())?;
+        let signature = self.signing_key.sign_digest(digest, PREHASH_CONTEXT)?;
+
+        append_signature(
+            &mut draft,
+            &cert_chain_bytes,
+            &signature,
+            pq_signature.as_deref(),
+            &self.status_assertions,
+        )
+    }
+
+    /// Add this signer's signature to an already signed file
+    ///
+    /// The co-signature covers the same header and stored payload as the
+    /// primary signature, with this signer's certificate chain in place of the
+    /// primary chain, using the signature scheme of the file's format version.
+    /// Existing signatures are left untouched.
+    pub fn co_sign(&self, existing_file: &AletheiaFile) -> Result<AletheiaFile> {
+        self.co_sign_inner(existing_file, None)
+    }
+
+    /// Add this signer's signature to an already signed file, recording the
+    /// role in which they sign (e.g. "reviewed", "published")
+    ///
+    /// The role is covered by the co-signature, so it cannot be changed
+    /// without invalidating it.
+    pub fn co_sign_with_role(
+        &self,
+        existing_file: &AletheiaFile,
+        role: impl Into<String>,
+    ) -> Result<AletheiaFile> {
+        self.co_sign_inner(existing_file, Some(role.into()))
+    }
+
+    fn co_sign_inner(
+        &self,
+        existing_file: &AletheiaFile,
+        role: Option<String>,
+    ) -> Result<AletheiaFile> {
+        // Encode header as CBOR
+        let mut header_bytes = Vec::new();
+        ciborium::into_writer(&existing_file.header, &mut header_bytes)
+            .map_err(|e| AletheiaError::CborEncode(e.to_string()))?;
+
+        // Encode our certificate chain (and role) as CBOR
+        let cert_chain_bytes = co_signer_chain_bytes(&self.certificate_chain, role.as_deref())?;
+
+        let signature = SignatureInput {
+            version_major: existing_file.version_major,
+            version_minor: existing_file.version_minor,
+            flags: &existing_file.flags,
+            header_bytes: &header_bytes,
+            payload: &existing_file.payload,
+            cert_chain_bytes: &cert_chain_bytes,
+        }
+        .sign(&self.signing_key)?;
+
+        let mut file = existing_file.clone();
+        file.co_signatures.push(CoSignature {
+            certificate_chain: self.certificate_chain.clone(),
+            role,
+            signature,
+        });
+        Ok(file)
+    }
+
+    /// Countersign an already signed file (e.g. as a notary)
+    ///
+    /// The countersignature covers the primary signature and the time of
+    /// countersigning, not the content itself.
+    #[cfg(feature = "std")]
+    pub fn countersign(&self, existing_file: &AletheiaFile) -> Result<AletheiaFile> {
+        self.countersign_with_timestamp(existing_file, chrono::Utc::now().timestamp())
+    }
+
+    /// Countersign an already signed file with a specific timestamp
+    ///
+    /// Use this in no_std environments or when you need to control the timestamp.
+    pub fn countersign_with_timestamp(
+        &self,
+        existing_file: &AletheiaFile,
+        signed_at: i64,
+    ) -> Result<AletheiaFile> {
+        // Encode our certificate chain as CBOR
+        let mut cert_chain_bytes = Vec::new();
+        ciborium::into_writer(&self.certificate_chain, &mut cert_chain_bytes)
+            .map_err(|e| AletheiaError::CborEncode(e.to_string()))?;
+
+        let signature_input =
+            build_countersignature_input(&existing_file.signature, signed_at, &cert_chain_bytes);
+
+        let mut file = existing_file.clone();
+        file.countersignatures.push(Countersignature {
+            certificate_chain: self.certificate_chain.clone(),
+            signed_at,
+            signature: self.signing_key.sign(&signature_input)?,
+        });
+        Ok(file)
+    }
+
     /// Get the creator ID from the certificate
     pub fn creator_id(&self) -> &str {
         &self.certificate_chain[0].subject_id
     }
+
+    /// Sign `input` with the signing key, and with the ML-DSA key over the
+    /// input's prehash if there is one
+    fn sign_input(&self, input: &SignatureInput) -> Result<(Vec<u8>, Option<Vec<u8>>)> {
+        let signature = input.sign(&self.signing_key)?;
+        Ok((signature, self.pq_sign(|| input.digest())?))
+    }
+
+    /// The ML-DSA signature over a prehashed signature input, if the signer
+    /// has an ML-DSA key
+    fn pq_sign(&self, digest: impl FnOnce() -> Sha512) -> Result<Option<Vec<u8>>> {
+        #[cfg(feature = "ml-dsa")]
+        if let Some(key) = &self.pq_key {
+            return key.sign(&digest().finalize()).map(Some);
+        }
+        let _ = digest;
+        Ok(None)
+    }
 }
 
-/// Build the input data for signature computation
-pub(crate) fn build_signature_input(
+// std-only streaming signing
+#[cfg(feature = "std")]
+mod std_stream {
+    use super::*;
+    use crate::file::read_chunks;
+    use std::io::{Read, Seek, SeekFrom, Write};
+
+    impl Signer {
+        /// Sign a payload read from `reader` and write the `.alx` file to `writer`
+        ///
+        /// The payload is read from the reader's current position to its end in
+        /// fixed-size chunks and is never held in memory as a whole. It is read
+        /// twice (once for the content digest, once to prehash and write it), so
+        /// the reader must be seekable and must not change while signing.
+        ///
+        /// Zstd compression is applied chunk by chunk as the payload is read,
+        /// once on each pass; the first pass only measures the compressed size,
+        /// which the file stores ahead of the payload. LZ4 compresses the
+        /// payload as a single block, so a signer using LZ4 is rejected here.
+        pub fn sign_stream<R: Read + Seek, W: Write>(
+            &self,
+            mut reader: R,
+            mut header: Header,
+            mut writer: W,
+        ) -> Result<()> {
+            // Payload extends from the current position to the end of the reader
+            let payload_start = reader.stream_position()?;
+            let payload_len = reader.seek(SeekFrom::End(0))? - payload_start;
+
+            // Digest the payload (and build the Merkle tree) for the header,
+            // measuring its stored size
+            let chunk_size = self.chunk_size.map(check_chunk_size).transpose()?;
+            let mut hasher = sha2::Sha256::new();
+            let mut merkle = chunk_size.map(merkle::MerkleBuilder::new);
+            let mut stored_len = 0u64;
+            let mut stored = self.stored_payload(Sink(|c: &[u8]| {
+                stored_len += c.len() as u64;
+                Ok(())
+            }))?;
+            reader.seek(SeekFrom::Start(payload_start))?;
+            read_chunks(&mut reader, payload_len, |c| {
+                hasher.update(c);
+                if let Some(merkle) = &mut merkle {
+                    merkle.update(c);
+                }
+                stored.write_all(c)
+            })?;
+            stored.finish()?;
+            header.content_digest = Some(hasher.finalize().to_vec());
+            let flags = self.stored_flags();
+            let flags = match (chunk_size, merkle) {
+                (Some(_), Some(_)) if flags.is_compressed() => {
+                    return Err(AletheiaError::Compression(
+                        "Compression is not supported for chunked payloads".into(),
+                    ));
+                }
+                (Some(chunk_size), Some(merkle)) => {
+                    header.chunks = Some(ChunkInfo {
+                        chunk_size,
+                        total_len: payload_len,
+                        merkle_root: merkle.finalize().to_vec(),
+                    });
+                    flags.with_chunking()
+                }
+                _ => flags,
+            };
+
+            // Encode header as CBOR
+            let mut header_bytes = Vec::new();
+            ciborium::into_writer(&header, &mut header_bytes)
+                .map_err(|e| AletheiaError::CborEncode(e.to_string()))?;
+
+            // Encode certificate chain as CBOR
+            let mut cert_chain_bytes = Vec::new();
+            ciborium::into_writer(&self.certificate_chain, &mut cert_chain_bytes)
+                .map_err(|e| AletheiaError::CborEncode(e.to_string()))?;
+
+            let prefix = signature_input_prefix(
+                VERSION_MAJOR,
+                VERSION_MINOR,
+                &flags,
+                &header_bytes,
+                stored_len,
+            );
+
+            // Write the file, prehashing the signature input on the way: the
+            // prefix matches the on-disk layout up to the payload. In chunked
+            // mode the signature input does not include the payload.
+            let mut digest = Sha512::new();
+            let hash_payload = !flags.is_chunked();
+            if hash_payload {
+                digest.update(&prefix);
+            } else {
+                digest.update(signature_input_prefix(
+                    VERSION_MAJOR,
+                    VERSION_MINOR,
+                    &flags,
+                    &header_bytes,
+                    0,
+                ));
+            }
+            writer.write_all(&prefix)?;
+            let mut stored = self.stored_payload(Sink(|c: &[u8]| {
+                if hash_payload {
+                    digest.update(c);
+                }
+                writer.write_all(c)
+            }))?;
+            reader.seek(SeekFrom::Start(payload_start))?;
+            read_chunks(&mut reader, payload_len, |c| stored.write_all(c))?;
+            stored.finish()?;
+            digest.update(signature_input_suffix(&cert_chain_bytes));
+
+            let pq_signature = self.pq_sign(|| digest.clone())?;
+            let signature = self.signing_key.sign_digest(digest, PREHASH_CONTEXT)?;
+            writer.write_all(&crate::file::encode_signature_section(
+                &cert_chain_bytes,
+                &signature,
+                pq_signature.as_deref(),
+                &self.status_assertions,
+            )?)?;
+            writer.flush()?;
+
+            Ok(())
+        }
+
+        /// Flags of a streamed payload, as stored
+        fn stored_flags(&self) -> Flags {
+            #[cfg(feature = "compression")]
+            if let Some(algorithm) = self.compression {
+                return Flags::new().with_compression_algorithm(algorithm);
+            }
+            Flags::new()
+        }
+
+        /// Turn payload written to the result into the payload as stored,
+        /// written to `inner`
+        fn stored_payload<W: Write>(&self, inner: W) -> Result<StoredPayload<W>> {
+            #[cfg(feature = "compression")]
+            match self.compression {
+                None => {}
+                Some(CompressionAlgorithm::Lz4) => {
+                    return Err(AletheiaError::Compression(
+                        "LZ4 compression is not supported when streaming; use zstd".into(),
+                    ));
+                }
+                #[cfg(feature = "zstd")]
+                Some(CompressionAlgorithm::Zstd { level }) => {
+                    return zstd::stream::write::Encoder::new(inner, level)
+                        .map(StoredPayload::Zstd)
+                        .map_err(|e| AletheiaError::Compression(e.to_string()));
+                }
+                #[cfg(not(feature = "zstd"))]
+                Some(CompressionAlgorithm::Zstd { .. }) => {
+                    return Err(AletheiaError::Compression(
+                        "Zstd feature not enabled".into(),
+                    ));
+                }
+            }
+            Ok(StoredPayload::Plain(inner))
+        }
+    }
+
+    /// A streamed payload on its way to storage
+    enum StoredPayload<W: Write> {
+        Plain(W),
+        #[cfg(feature = "zstd")]
+        Zstd(zstd::stream::write::Encoder<'static, W>),
+    }
+
+    impl<W: Write> StoredPayload<W> {
+        fn write_all(&mut self, data: &[u8]) -> std::io::Result<()> {
+            match self {
+                Self::Plain(inner) => inner.write_all(data),
+                #[cfg(feature = "zstd")]
+                Self::Zstd(encoder) => encoder.write_all(data),
+            }
+        }
+
+        /// Write out what is still buffered
+        fn finish(self) -> std::io::Result<()> {
+            match self {
+                Self::Plain(_) => Ok(()),
+                #[cfg(feature = "zstd")]
+                Self::Zstd(encoder) => encoder.finish().map(drop),
+            }
+        }
+    }
+
+    /// Passes everything written to it on to a closure
+    struct Sink<F>(F);
+
+    impl<F: FnMut(&[u8]) -> std::io::Result<()>> Write for Sink<F> {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            (self.0)(buf)?;
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+}
+
+impl Signer {
+    /// Start signing a payload that is supplied piece by piece
+    ///
+    /// The payload is hashed once as it is passed to
+    /// [`IncrementalSigner::update`] and is never held in memory, so it can
+    /// come from a source that cannot be read twice, such as a network or
+    /// browser stream. The signed file is the payload framed by the
+    /// [`SignedParts`] returned at the end. This needs chunked mode, where
+    /// the signature covers the Merkle root rather than the payload;
+    /// compression is not supported.
+    pub fn incremental(&self, header: Header) -> Result<IncrementalSigner<'_>> {
+        #[cfg(feature = "compression")]
+        if self.compression.is_some() {
+            return Err(AletheiaError::Compression(
+                "Compression is not supported when signing incrementally".into(),
+            ));
+        }
+        let chunk_size = self.chunk_size.ok_or_else(|| {
+            AletheiaError::InvalidHeader("Incremental signing requires chunking".into())
+        })?;
+        let chunk_size = check_chunk_size(chunk_size)?;
+
+        Ok(IncrementalSigner {
+            signer: self,
+            header,
+            chunk_size,
+            hasher: sha2::Sha256::new(),
+            merkle: merkle::MerkleBuilder::new(chunk_size),
+            payload_len: 0,
+        })
+    }
+}
+
+/// A payload being signed in one pass, see [`Signer::incremental`]
+pub struct IncrementalSigner<'a> {
+    signer: &'a Signer,
+    header: Header,
+    chunk_size: u32,
+    hasher: sha2::Sha256,
+    merkle: merkle::MerkleBuilder,
+    payload_len: u64,
+}
+
+impl IncrementalSigner<'_> {
+    /// Feed the next piece of the payload
+    pub fn update(&mut self, data: &[u8]) {
+        self.hasher.update(data);
+        self.merkle.update(data);
+        self.payload_len += data.len() as u64;
+    }
+
+    /// Sign the payload fed so far
+    pub fn finish(self) -> Result<SignedParts> {
+        let mut header = self.header;
+        header.content_digest = Some(self.hasher.finalize().to_vec());
+        header.chunks = Some(ChunkInfo {
+            chunk_size: self.chunk_size,
+            total_len: self.payload_len,
+            merkle_root: self.merkle.finalize().to_vec(),
+        });
+        let flags = Flags::new().with_chunking();
+
+        // Encode header as CBOR
+        let mut header_bytes = Vec::new();
+        ciborium::into_writer(&header, &mut header_bytes)
+            .map_err(|e| AletheiaError::CborEncode(e.to_string()))?;
+
+        // Encode certificate chain as CBOR
+        let mut cert_chain_bytes = Vec::new();
+        ciborium::into_writer(&self.signer.certificate_chain, &mut cert_chain_bytes)
+            .map_err(|e| AletheiaError::CborEncode(e.to_string()))?;
+
+        // In chunked mode the signature does not cover the payload
+        let (signature, pq_signature) = self.signer.sign_input(&SignatureInput {
+            version_major: VERSION_MAJOR,
+            version_minor: VERSION_MINOR,
+            flags: &flags,
+            header_bytes: &header_bytes,
+            payload: &[],
+            cert_chain_bytes: &cert_chain_bytes,
+        })?;
+
+        Ok(SignedParts {
+            head: signature_input_prefix(
+                VERSION_MAJOR,
+                VERSION_MINOR,
+                &flags,
+                &header_bytes,
+                self.payload_len,
+            ),
+            tail: crate::file::encode_signature_section(
+                &cert_chain_bytes,
+                &signature,
+                pq_signature.as_deref(),
+                &self.signer.status_assertions,
+            )?,
+        })
+    }
+}
+
+/// The encoded file around a payload signed by an [`IncrementalSigner`]
+///
+/// The `.alx` file is `head`, the payload exactly as it was fed, then `tail`.
+#[derive(Debug, Clone)]
+pub struct SignedParts {
+    /// Magic bytes, version, flags, header and payload length
+    pub head: Vec<u8>,
+    /// Certificate chain, signature and trailer
+    pub tail: Vec<u8>,
+}
+
+/// A file waiting for a pure Ed25519 signature by a key held elsewhere
+///
+/// For keys that cannot sign synchronously or cannot produce Ed25519ph
+/// signatures, such as non-extractable WebCrypto keys in a browser. The file
+/// is written in format 1.0, whose signature is pure Ed25519 over the whole
+/// signature input, so every reader verifies it. Sign
+/// [`PendingFile::message`] with the creator's key and pass the signature to
+/// [`PendingFile::complete`].
+pub struct PendingFile {
+    file: AletheiaFile,
+    message: Vec<u8>,
+}
+
+impl PendingFile {
+    /// Prepare `payload` for signing by the key of the first certificate
+    /// in `certificate_chain`
+    ///
+    /// The header's content digest is set to the SHA-256 of `payload`.
+    pub fn new(
+        payload: &[u8],
+        header: Header,
+        certificate_chain: Vec<Certificate>,
+    ) -> Result<Self> {
+        if certificate_chain.is_empty() {
+            return Err(AletheiaError::CertificateChainInvalid {
+                step: 0,
+                reason: "Certificate chain cannot be empty".into(),
+            });
+        }
+        let header = header.with_content_digest(payload);
+        let flags = Flags::new();
+
+        // Encode header as CBOR
+        let mut header_bytes = Vec::new();
+        ciborium::into_writer(&header, &mut header_bytes)
+            .map_err(|e| AletheiaError::CborEncode(e.to_string()))?;
+
+        // Encode certificate chain as CBOR
+        let mut cert_chain_bytes = Vec::new();
+        ciborium::into_writer(&certificate_chain, &mut cert_chain_bytes)
+            .map_err(|e| AletheiaError::CborEncode(e.to_string()))?;
+
+        let message = SignatureInput {
+            version_major: VERSION_MAJOR,
+            version_minor: 0,
+            flags: &flags,
+            header_bytes: &header_bytes,
+            payload,
+            cert_chain_bytes: &cert_chain_bytes,
+        }
+        .to_vec();
+
+        Ok(Self {
+            file: AletheiaFile {
+                version_major: VERSION_MAJOR,
+                version_minor: 0,
+                flags,
+                header,
+                payload: payload.to_vec(),
+                certificate_chain,
+                signature: Vec::new(),
+                pq_signature: None,
+                co_signatures: Vec::new(),
+                countersignatures: Vec::new(),
+                timestamp_tokens: Vec::new(),
+                log_entries: Vec::new(),
+                opentimestamps: Vec::new(),
+                status_assertions: Vec::new(),
+            },
+            message,
+        })
+    }
+
+    /// The message to sign with pure Ed25519
+    pub fn message(&self) -> &[u8] {
+        &self.message
+    }
+
+    /// Attach the creator's signature over [`PendingFile::message`]
+    ///
+    /// Fails if the signature does not verify with the creator's
+    /// certificate, e.g. because it was made with another key.
+    pub fn complete(mut self, signature: &[u8]) -> Result<AletheiaFile> {
+        let verifying_key =
+            VerifyingKey::try_from(self.file.certificate_chain[0].public_key.as_slice()).map_err(
+                |e| AletheiaError::InvalidCertificate(alloc::format!("Invalid public key: {}", e)),
+            )?;
+        let parsed = Signature::try_from(signature).map_err(|_| AletheiaError::InvalidSignature)?;
+        verifying_key
+            .verify(&self.message, &parsed)
+            .map_err(|_| AletheiaError::InvalidSignature)?;
+
+        self.file.signature = signature.to_vec();
+        Ok(self.file)
+    }
+}
+
+/// Reject chunk sizes the Merkle tree cannot be built with
+fn check_chunk_size(chunk_size: u32) -> Result<u32> {
+    if chunk_size == 0 {
+        return Err(AletheiaError::InvalidHeader(
+            "Chunk size must be positive".into(),
+        ));
+    }
+    Ok(chunk_size)
+}
+
+/// Domain separation context for Ed25519ph file signatures
+pub(crate) const PREHASH_CONTEXT: &[u8] = b"ALETHEIA";
+
+/// The data covered by a file signature (primary or co-signature)
+///
+/// Format 1.0 files are signed with pure Ed25519 over the concatenated
+/// input. From format 1.1 on, the input is hashed with SHA-512 and signed
+/// with Ed25519ph, so the payload never has to be copied.
+///
+/// In chunked and detached mode the payload is left out (and its length
+/// recorded as 0); the Merkle root or content digest in the header binds the
+/// content instead.
+pub(crate) struct SignatureInput<'a> {
+    pub version_major: u8,
+    pub version_minor: u8,
+    pub flags: &'a Flags,
+    pub header_bytes: &'a [u8],
+    pub payload: &'a [u8],
+    pub cert_chain_bytes: &'a [u8],
+}
+
+impl SignatureInput<'_> {
+    /// Whether the signature is made over a SHA-512 prehash (format 1.1+)
+    pub(crate) fn is_prehashed(&self) -> bool {
+        is_prehashed(self.version_major, self.version_minor)
+    }
+
+    /// The payload bytes covered by the signature
+    fn signed_payload(&self) -> &[u8] {
+        if self.flags.is_chunked() || self.flags.is_detached() {
+            &[]
+        } else {
+            self.payload
+        }
+    }
+
+    fn prefix(&self) -> Vec<u8> {
+        signature_input_prefix(
+            self.version_major,
+            self.version_minor,
+            self.flags,
+            self.header_bytes,
+            self.signed_payload().len() as u64,
+        )
+    }
+
+    /// The concatenated signature input
+    pub(crate) fn to_vec(&self) -> Vec<u8> {
+        let mut input = self.prefix();
+
+        // Payload
+        input.extend_from_slice(self.signed_payload());
+
+        // Certificate chain length + chain
+        input.extend_from_slice(&signature_input_suffix(self.cert_chain_bytes));
+
+        input
+    }
+
+    /// SHA-512 of the signature input, computed without concatenating it
+    pub(crate) fn digest(&self) -> Sha512 {
+        let mut digest = Sha512::new();
+        digest.update(self.prefix());
+        digest.update(self.signed_payload());
+        digest.update(signature_input_suffix(self.cert_chain_bytes));
+        digest
+    }
+
+    /// Sign the input with the scheme of its format version
+    pub(crate) fn sign(&self, signing_key: &KeyHandle) -> Result<Vec<u8>> {
+        if !self.is_prehashed() {
+            signing_key.sign(&self.to_vec())
+        } else if signing_key.is_external() {
+            signing_key.sign_prehashed(&self.to_vec(), PREHASH_CONTEXT)
+        } else {
+            signing_key.sign_digest(self.digest(), PREHASH_CONTEXT)
+        }
+    }
+
+    /// Verify a signature over the input with the scheme of its format version
+    pub(crate) fn verify(&self, verifying_key: &VerifyingKey, signature: &Signature) -> Result<()> {
+        let verified = if self.is_prehashed() {
+            verifying_key.verify_prehashed(self.digest(), Some(PREHASH_CONTEXT), signature)
+        } else {
+            verifying_key.verify(&self.to_vec(), signature)
+        };
+        verified.map_err(|_| AletheiaError::InvalidSignature)
+    }
+}
+
+/// Whether file signatures in this format version are made over a SHA-512 prehash
+pub(crate) fn is_prehashed(version_major: u8, version_minor: u8) -> bool {
+    (version_major, version_minor) >= (1, 1)
+}
+
+/// Encode a co-signer's certificate chain as it is signed
+///
+/// A role follows the chain as a CBOR text string, so the co-signature
+/// covers it.
+pub(crate) fn co_signer_chain_bytes(
+    certificate_chain: &[Certificate],
+    role: Option<&str>,
+) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    ciborium::into_writer(certificate_chain, &mut bytes)
+        .map_err(|e| AletheiaError::CborEncode(e.to_string()))?;
+    if let Some(role) = role {
+        ciborium::into_writer(role, &mut bytes)
+            .map_err(|e| AletheiaError::CborEncode(e.to_string()))?;
+    }
+    Ok(bytes)
+}
+
+/// Domain separator for countersignatures, so they cannot be mistaken for file signatures
+const COUNTERSIGNATURE_CONTEXT: &[u8] = b"ALETHEIA-COUNTERSIGNATURE";
+
+/// Build the input data for countersignature computation
+pub(crate) fn build_countersignature_input(
+    primary_signature: &[u8],
+    signed_at: i64,
+    cert_chain_bytes: &[u8],
+) -> Vec<u8> {
+    let mut input = Vec::new();
+
+    // Context
+    input.extend_from_slice(COUNTERSIGNATURE_CONTEXT);
+
+    // Primary signature
+    input.extend_from_slice(primary_signature);
+
+    // Countersigning time
+    input.extend_from_slice(&signed_at.to_le_bytes());
+
+    // Certificate chain length + chain
+    input.extend_from_slice(&signature_input_suffix(cert_chain_bytes));
+
+    input
+}
+
+/// Build the part of the signature input that precedes the payload bytes
+///
+/// This is byte-for-byte identical to the start of the serialized file.
+pub(crate) fn signature_input_prefix(
+    version_major: u8,
+    version_minor: u8,
     flags: &Flags,
     header_bytes: &[u8],
-    payload: &[u8],
-    cert_chain_bytes: &[u8],
+    payload_len: u64,
 ) -> Vec<u8> {
     let mut input = Vec::new();
 
@@ -110,8 +1036,8 @@ pub(crate) fn build_signature_input(
     input.extend_from_slice(MAGIC_BYTES);
 
     // Version
-    input.push(VERSION_MAJOR);
-    input.push(VERSION_MINOR);
+    input.push(version_major);
+    input.push(version_minor);
 
     // Flags
     input.extend_from_slice(&flags.to_bytes());
@@ -120,9 +1046,15 @@ pub(crate) fn build_signature_input(
     input.extend_from_slice(&(header_bytes.len() as u32).to_le_bytes());
     input.extend_from_slice(header_bytes);
 
-    // Payload length + payload
-    input.extend_from_slice(&(payload.len() as u64).to_le_bytes());
-    input.extend_from_slice(payload);
+    // Payload length
+    input.extend_from_slice(&payload_len.to_le_bytes());
+
+    input
+}
+
+/// Build the part of the signature input that follows the payload bytes
+pub(crate) fn signature_input_suffix(cert_chain_bytes: &[u8]) -> Vec<u8> {
+    let mut input = Vec::new();
 
     // Certificate chain length + chain
     input.extend_from_slice(&(cert_chain_bytes.len() as u32).to_le_bytes());
@@ -136,6 +1068,81 @@ mod tests {
     use super::*;
     use crate::ca::CertificateAuthority;
 
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_finalize_draft() {
+        let timestamp = 1704067200;
+        let ca =
+            CertificateAuthority::new_root_with_timestamp("root@example.com", "Root CA", timestamp);
+        let user_keys = SigningKeyPair::generate();
+        let user_cert = ca
+            .issue_certificate_with_timestamp(
+                "alice@example.com",
+                "Alice",
+                &user_keys.public_key(),
+                false,
+                timestamp,
+            )
+            .unwrap();
+        let signer =
+            Signer::new(user_keys, alloc::vec![user_cert, ca.certificate.clone()]).unwrap();
+
+        let header = Header::new_with_timestamp("alice@example.com", timestamp);
+        let draft = crate::file::write_unsigned(b"Produced elsewhere", header).unwrap();
+        let payload_end = draft.len();
+        let mut cursor = std::io::Cursor::new(draft.clone());
+        signer.finalize(&mut cursor).unwrap();
+
+        // The draft is a prefix of the signed file
+        let signed = cursor.into_inner();
+        assert_eq!(&signed[..payload_end], draft.as_slice());
+        let file = crate::file::from_bytes(&signed).unwrap();
+        assert_eq!(file.payload, b"Produced elsewhere");
+        crate::verifier::verify(&file, &[ca.public_key()]).unwrap();
+
+        // A signed file is not a draft
+        let result = signer.finalize(std::io::Cursor::new(signed));
+        assert!(matches!(result, Err(AletheiaError::InvalidDraft(_))));
+    }
+
+    #[test]
+    fn test_detach() {
+        let timestamp = 1704067200;
+        let ca =
+            CertificateAuthority::new_root_with_timestamp("root@example.com", "Root CA", timestamp);
+        let issue = |id: &str, keys: &SigningKeyPair| {
+            ca.issue_certificate_with_timestamp(id, id, &keys.public_key(), false, timestamp)
+                .unwrap()
+        };
+        let alice_keys = SigningKeyPair::generate();
+        let bob_keys = SigningKeyPair::generate();
+        let alice_cert = issue("alice@example.com", &alice_keys);
+        let bob_cert = issue("bob@example.com", &bob_keys);
+        let alice = Signer::new(alice_keys, vec![alice_cert, ca.certificate.clone()])
+            .unwrap()
+            .with_chunking(4);
+        let bob = Signer::new(bob_keys, vec![bob_cert, ca.certificate.clone()]).unwrap();
+
+        let header = Header::new_with_timestamp("alice@example.com", timestamp);
+        let file = alice.sign(b"Distributed separately", header).unwrap();
+        let manifest = alice.detach(&file).unwrap();
+        assert!(manifest.flags.is_detached());
+        assert!(manifest.payload.is_empty() && manifest.header.chunks.is_none());
+        assert_eq!(manifest.header.signed_at, timestamp);
+        crate::verifier::verify_detached(&manifest, b"Distributed separately", &[ca.public_key()])
+            .unwrap();
+
+        // Only the creator can re-sign the content, and only once
+        assert!(matches!(
+            bob.detach(&file),
+            Err(AletheiaError::InvalidCertificate(_))
+        ));
+        assert!(matches!(
+            alice.detach(&manifest),
+            Err(AletheiaError::DetachedPayload)
+        ));
+    }
+
     #[test]
     fn test_sign_data() {
         // Create CA and user
@@ -168,10 +1175,207 @@ mod tests {
         let file = signer.sign(payload, header).unwrap();
 
         assert_eq!(file.version_major, 1);
-        assert_eq!(file.version_minor, 0);
+        assert_eq!(file.version_minor, 1);
         assert!(!file.flags.is_compressed());
         assert_eq!(file.payload, payload);
         assert_eq!(file.signature.len(), 64);
+        assert_eq!(file.header.matches_content(payload), Some(true));
+        assert_eq!(file.header.matches_content(b"Other"), Some(false));
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_integrity_check() {
+        let timestamp = 1704067200;
+        let ca =
+            CertificateAuthority::new_root_with_timestamp("root@example.com", "Root CA", timestamp);
+        let user_keys = SigningKeyPair::generate();
+        let user_cert = ca
+            .issue_certificate_with_timestamp(
+                "alice@example.com",
+                "Alice",
+                &user_keys.public_key(),
+                false,
+                timestamp,
+            )
+            .unwrap();
+        let signer = Signer::new(user_keys, vec![user_cert, ca.certificate.clone()])
+            .unwrap()
+            .with_compression();
+
+        let payload = vec![0u8; 64 << 10];
+        let header = Header::new_with_timestamp("alice@example.com", timestamp);
+        let mut file = signer.sign(&payload, header).unwrap();
+        file.integrity_check().unwrap();
+        assert_eq!(
+            file.payload_digest().unwrap(),
+            <[u8; 32]>::from(sha2::Sha256::digest(&payload))
+        );
+
+        // Zeros compress far beyond a 4x expansion ratio
+        let strict = crate::file::ParseLimits::default().with_max_expansion_ratio(4);
+        assert!(matches!(
+            file.integrity_check_with_limits(&strict),
+            Err(AletheiaError::Decompression(_))
+        ));
+
+        // A forged size prefix is refused before anything is allocated
+        let stored = file.payload.clone();
+        file.payload[..4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(
+            file.integrity_check(),
+            Err(AletheiaError::Decompression(_))
+        ));
+        assert!(file.get_payload().is_err());
+
+        file.payload = stored;
+        file.payload[8] ^= 0xFF;
+        assert!(file.integrity_check().is_err());
+    }
+
+    #[test]
+    fn test_sign_owned_matches_sign() {
+        let timestamp = 1704067200;
+        let ca =
+            CertificateAuthority::new_root_with_timestamp("root@example.com", "Root CA", timestamp);
+        let user_keys = SigningKeyPair::generate();
+        let user_cert = ca
+            .issue_certificate_with_timestamp(
+                "alice@example.com",
+                "Alice",
+                &user_keys.public_key(),
+                false,
+                timestamp,
+            )
+            .unwrap();
+        let signer = Signer::new(user_keys, vec![user_cert, ca.certificate.clone()]).unwrap();
+
+        let payload = vec![0x5a; 64 * 1024];
+        let header = Header::new_with_timestamp("alice@example.com", timestamp);
+        let borrowed = signer.sign(&payload, header.clone()).unwrap();
+
+        // The owned buffer moves into the file without being copied
+        let buffer = payload.as_ptr();
+        let owned = signer.sign_owned(payload, header).unwrap();
+        assert_eq!(owned.payload.as_ptr(), buffer);
+        assert_eq!(
+            crate::file::to_bytes(&owned).unwrap(),
+            crate::file::to_bytes(&borrowed).unwrap()
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_sign_stream_matches_sign() {
+        let timestamp = 1704067200;
+        let ca =
+            CertificateAuthority::new_root_with_timestamp("root@example.com", "Root CA", timestamp);
+        let user_keys = SigningKeyPair::generate();
+
+        let user_cert = ca
+            .issue_certificate_with_timestamp(
+                "alice@example.com",
+                "Alice",
+                &user_keys.public_key(),
+                false,
+                timestamp,
+            )
+            .unwrap();
+
+        let chain = vec![user_cert, ca.certificate.clone()];
+        let signer = Signer::new(user_keys, chain).unwrap();
+
+        // Larger than one stream chunk
+        let payload = vec![0x5a; 200 * 1024];
+        let header = Header::new_with_timestamp("alice@example.com", timestamp);
+
+        let mut streamed = Vec::new();
+        signer
+            .sign_stream(
+                std::io::Cursor::new(&payload),
+                header.clone(),
+                &mut streamed,
+            )
+            .unwrap();
+
+        // Ed25519 is deterministic, so both paths produce identical files
+        let in_memory = signer.sign(&payload, header).unwrap();
+        assert_eq!(streamed, crate::file::to_bytes(&in_memory).unwrap());
+
+        let trusted_roots = vec![ca.public_key()];
+        crate::verifier::verify_stream(std::io::Cursor::new(&streamed), &trusted_roots).unwrap();
+    }
+
+    #[test]
+    fn test_pending_file() {
+        let timestamp = 1704067200;
+        let ca =
+            CertificateAuthority::new_root_with_timestamp("root@example.com", "Root CA", timestamp);
+        let user_keys = SigningKeyPair::generate();
+        let user_cert = ca
+            .issue_certificate_with_timestamp(
+                "alice@example.com",
+                "Alice",
+                &user_keys.public_key(),
+                false,
+                timestamp,
+            )
+            .unwrap();
+        let chain = vec![user_cert, ca.certificate.clone()];
+        let header = Header::new_with_timestamp("alice@example.com", timestamp);
+
+        // Another key's signature is refused
+        let pending = PendingFile::new(b"Browser content", header.clone(), chain.clone()).unwrap();
+        let other = SigningKeyPair::generate();
+        assert!(matches!(
+            pending.complete(&other.sign(b"Browser content")),
+            Err(AletheiaError::InvalidSignature)
+        ));
+
+        let pending = PendingFile::new(b"Browser content", header, chain).unwrap();
+        let signature = user_keys.sign(pending.message());
+        let file = pending.complete(&signature).unwrap();
+        assert_eq!((file.version_major, file.version_minor), (1, 0));
+
+        let bytes = crate::file::to_bytes(&file).unwrap();
+        let file = crate::file::from_bytes(&bytes).unwrap();
+        let result = crate::verifier::verify(&file, &[ca.public_key()]).unwrap();
+        assert_eq!(result.format_version, (1, 0));
+    }
+
+    #[test]
+    fn test_incremental_matches_sign() {
+        let timestamp = 1704067200;
+        let ca =
+            CertificateAuthority::new_root_with_timestamp("root@example.com", "Root CA", timestamp);
+        let user_keys = SigningKeyPair::generate();
+
+        let user_cert = ca
+            .issue_certificate_with_timestamp(
+                "alice@example.com",
+                "Alice",
+                &user_keys.public_key(),
+                false,
+                timestamp,
+            )
+            .unwrap();
+
+        let chain = vec![user_cert, ca.certificate.clone()];
+        let signer = Signer::new(user_keys, chain).unwrap();
+        let header = Header::new_with_timestamp("alice@example.com", timestamp);
+        assert!(signer.incremental(header.clone()).is_err());
+
+        let signer = signer.with_chunking(1000);
+        let payload: Vec<u8> = (0..5000u32).map(|i| i as u8).collect();
+        let mut incremental = signer.incremental(header.clone()).unwrap();
+        for piece in payload.chunks(777) {
+            incremental.update(piece);
+        }
+        let parts = incremental.finish().unwrap();
+        let streamed = [parts.head, payload.clone(), parts.tail].concat();
+
+        let in_memory = signer.sign(&payload, header).unwrap();
+        assert_eq!(streamed, crate::file::to_bytes(&in_memory).unwrap());
     }
 
     #[cfg(feature = "compression")]
@@ -207,5 +1411,135 @@ mod tests {
         // Verify we can decompress
         let decompressed = file.get_payload().unwrap();
         assert_eq!(decompressed, payload.as_bytes());
+
+        // The content digest covers the original payload
+        assert_eq!(file.header.matches_content(payload.as_bytes()), Some(true));
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_sign_with_zstd() {
+        let timestamp = 1704067200;
+        let ca =
+            CertificateAuthority::new_root_with_timestamp("root@example.com", "Root CA", timestamp);
+        let user_keys = SigningKeyPair::generate();
+
+        let user_cert = ca
+            .issue_certificate_with_timestamp(
+                "alice@example.com",
+                "Alice",
+                &user_keys.public_key(),
+                false,
+                timestamp,
+            )
+            .unwrap();
+
+        let chain = vec![user_cert, ca.certificate.clone()];
+        let signer = Signer::new(user_keys, chain)
+            .unwrap()
+            .with_compression_algorithm(CompressionAlgorithm::Zstd { level: 19 });
+
+        let payload = "Hello, World! ".repeat(1000);
+        let header = Header::new_with_timestamp("alice@example.com", timestamp);
+
+        let file = signer.sign(payload.as_bytes(), header).unwrap();
+
+        assert_eq!(
+            file.flags.compression_algorithm(),
+            Some(CompressionAlgorithm::Zstd { level: 0 })
+        );
+        assert!(file.payload.len() < payload.len());
+
+        // Decompression survives a round trip through the file format
+        let loaded = crate::file::from_bytes(&crate::file::to_bytes(&file).unwrap()).unwrap();
+        assert_eq!(loaded.get_payload().unwrap(), payload.as_bytes());
+    }
+
+    #[cfg(all(feature = "std", feature = "zstd"))]
+    #[test]
+    fn test_sign_stream_with_zstd() {
+        let timestamp = 1704067200;
+        let ca =
+            CertificateAuthority::new_root_with_timestamp("root@example.com", "Root CA", timestamp);
+        let user_keys = SigningKeyPair::generate();
+
+        let user_cert = ca
+            .issue_certificate_with_timestamp(
+                "alice@example.com",
+                "Alice",
+                &user_keys.public_key(),
+                false,
+                timestamp,
+            )
+            .unwrap();
+
+        let chain = vec![user_cert, ca.certificate.clone()];
+        let signer = Signer::new(user_keys, chain)
+            .unwrap()
+            .with_compression_algorithm(CompressionAlgorithm::Zstd { level: 3 });
+
+        // Larger than one stream chunk
+        let payload: String = (0..20_000).map(|i| alloc::format!("line {i}\n")).collect();
+        let header = Header::new_with_timestamp("alice@example.com", timestamp);
+
+        let mut streamed = Vec::new();
+        signer
+            .sign_stream(
+                std::io::Cursor::new(payload.as_bytes()),
+                header,
+                &mut streamed,
+            )
+            .unwrap();
+
+        let file = crate::file::from_bytes(&streamed).unwrap();
+        assert_eq!(
+            file.flags.compression_algorithm(),
+            Some(CompressionAlgorithm::Zstd { level: 0 })
+        );
+        assert!(file.payload.len() < payload.len());
+        assert_eq!(file.get_payload().unwrap(), payload.as_bytes());
+        let result = crate::verifier::verify(&file, &[ca.public_key()]).unwrap();
+        assert_eq!(
+            result.content_digest,
+            Some(sha2::Sha256::digest(payload.as_bytes()).into())
+        );
+    }
+
+    #[cfg(all(feature = "std", feature = "compression"))]
+    #[test]
+    fn test_sign_stream_rejects_lz4() {
+        let ca = CertificateAuthority::new_root("root@example.com", "Root CA");
+        let user_keys = SigningKeyPair::generate();
+        let user_cert = ca
+            .issue_certificate("alice@example.com", "Alice", &user_keys.public_key(), false)
+            .unwrap();
+        let signer = Signer::new(user_keys, vec![user_cert, ca.certificate.clone()])
+            .unwrap()
+            .with_compression();
+
+        // LZ4 needs the whole payload at once; nothing is written
+        let mut streamed = Vec::new();
+        let result = signer.sign_stream(
+            std::io::Cursor::new(b"content"),
+            Header::new("alice@example.com"),
+            &mut streamed,
+        );
+        assert!(matches!(result, Err(AletheiaError::Compression(_))));
+        assert!(streamed.is_empty());
+
+        // Neither can zstd be combined with chunking
+        #[cfg(feature = "zstd")]
+        {
+            let signer = signer
+                .with_compression_algorithm(CompressionAlgorithm::Zstd { level: 3 })
+                .with_chunking(1024);
+            let result = signer.sign_stream(
+                std::io::Cursor::new(b"content"),
+                Header::new("alice@example.com"),
+                &mut streamed,
+            );
+            assert!(matches!(result, Err(AletheiaError::Compression(_))));
+            assert!(streamed.is_empty());
+        }
     }
 }
@@ -0,0 +1,796 @@
+//! Threshold root CAs (FROST)
+//!
+//! Splits a root CA key into `n` shares so that any `t` shareholders
+//! together, and no fewer, can sign certificates. Signing follows FROST
+//! (RFC 9591) with the FROST(Ed25519, SHA-512) ciphersuite, whose signatures
+//! are ordinary Ed25519 signatures: certificates issued this way verify like
+//! those of any other [`CertificateAuthority`](crate::ca::CertificateAuthority).
+//!
+//! Shares are created by a trusted dealer in
+//! [`CertificateAuthority::new_root_threshold`](crate::ca::CertificateAuthority::new_root_threshold),
+//! which holds the full key only while splitting it. Issuing a certificate
+//! then takes two rounds:
+//!
+//! 1. Each participating shareholder calls [`KeyShare::commit`], keeps the
+//!    [`SigningNonces`] and sends the [`SigningCommitments`] to a coordinator.
+//! 2. The coordinator prepares the certificate and a [`SigningPackage`] with
+//!    [`ThresholdCertificateAuthority::signing_package`]; each shareholder
+//!    checks the certificate and answers with [`KeyShare::sign`].
+//!
+//! The coordinator combines the answers with
+//! [`ThresholdCertificateAuthority::finish_certificate`].
+//!
+//! ```rust
+//! use aletheia::ca::{CertificateAuthority, IssueOptions, SigningKeyPair};
+//!
+//! let (ca, shares) = CertificateAuthority::new_root_threshold("root@example.com", "Root CA", 3, 2).unwrap();
+//! let keys = SigningKeyPair::generate();
+//! let cert = ca
+//!     .prepare_certificate("alice@example.com", "Alice", &keys.public_key(), false, 1704067200, &IssueOptions::new())
+//!     .unwrap();
+//!
+//! // Round 1: two of the three shareholders commit
+//! let (nonces_a, commitments_a) = shares[0].commit();
+//! let (nonces_c, commitments_c) = shares[2].commit();
+//! let package = ca.signing_package(&cert, vec![commitments_a, commitments_c]).unwrap();
+//!
+//! // Round 2: both sign, and the coordinator combines
+//! let sig_a = shares[0].sign(&package, nonces_a).unwrap();
+//! let sig_c = shares[2].sign(&package, nonces_c).unwrap();
+//! let cert = ca.finish_certificate(cert, &package, &[sig_a, sig_c]).unwrap();
+//! ```
+
+extern crate alloc;
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use curve25519_dalek::{
+    edwards::{CompressedEdwardsY, EdwardsPoint},
+    scalar::Scalar,
+    traits::IsIdentity,
+};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use rand::{RngCore, rngs::OsRng};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha512};
+use zeroize::Zeroize;
+
+use crate::{
+    AletheiaError, Certificate, Result,
+    ca::{IssueOptions, unsigned_certificate},
+    certificate::generate_serial,
+};
+
+/// Ciphersuite context string of FROST(Ed25519, SHA-512)
+const CONTEXT: &[u8] = b"FROST-ED25519-SHA512-v1";
+
+/// One shareholder's part of a threshold CA key
+///
+/// Keep it as secret as a CA private key: `min_signers` shares together
+/// can issue certificates.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct KeyShare {
+    /// Shareholder number (1 to the number of shares)
+    pub identifier: u16,
+
+    /// The secret share
+    #[serde(with = "serde_bytes")]
+    signing_share: [u8; 32],
+
+    /// The CA's Ed25519 public key
+    #[serde(with = "serde_bytes")]
+    pub group_public_key: Vec<u8>,
+
+    /// Number of shareholders needed to sign
+    pub min_signers: u16,
+}
+
+impl core::fmt::Debug for KeyShare {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("KeyShare")
+            .field("identifier", &self.identifier)
+            .field("min_signers", &self.min_signers)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for KeyShare {
+    fn drop(&mut self) {
+        self.signing_share.zeroize();
+    }
+}
+
+/// A shareholder's secret nonces for one signing session
+///
+/// Used by exactly one call to [`KeyShare::sign`]; reusing nonces would
+/// reveal the share, so this type is neither `Clone` nor serializable.
+pub struct SigningNonces {
+    hiding: Scalar,
+    binding: Scalar,
+    commitments: SigningCommitments,
+}
+
+impl Drop for SigningNonces {
+    fn drop(&mut self) {
+        self.hiding.zeroize();
+        self.binding.zeroize();
+    }
+}
+
+/// A shareholder's public commitments to its [`SigningNonces`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SigningCommitments {
+    /// Shareholder number
+    pub identifier: u16,
+
+    /// Commitment to the hiding nonce
+    #[serde(with = "serde_bytes")]
+    pub hiding: [u8; 32],
+
+    /// Commitment to the binding nonce
+    #[serde(with = "serde_bytes")]
+    pub binding: [u8; 32],
+}
+
+/// The message to sign and the commitments of the participating shareholders
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SigningPackage {
+    /// Commitments, ordered by identifier
+    pub commitments: Vec<SigningCommitments>,
+
+    /// Data to sign (for a certificate, its signable data)
+    #[serde(with = "serde_bytes")]
+    pub message: Vec<u8>,
+}
+
+/// A shareholder's answer to a [`SigningPackage`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignatureShare {
+    /// Shareholder number
+    pub identifier: u16,
+
+    /// The signature share
+    #[serde(with = "serde_bytes")]
+    pub share: [u8; 32],
+}
+
+impl KeyShare {
+    /// Start a signing session (round 1)
+    ///
+    /// Keep the nonces and send the commitments to the coordinator.
+    pub fn commit(&self) -> (SigningNonces, SigningCommitments) {
+        let mut hiding_randomness = [0u8; 32];
+        let mut binding_randomness = [0u8; 32];
+        OsRng.fill_bytes(&mut hiding_randomness);
+        OsRng.fill_bytes(&mut binding_randomness);
+        let committed = self.commit_with_randomness(&hiding_randomness, &binding_randomness);
+        hiding_randomness.zeroize();
+        binding_randomness.zeroize();
+        committed
+    }
+
+    /// Round 1 with the random bytes of both nonces given
+    fn commit_with_randomness(
+        &self,
+        hiding_randomness: &[u8; 32],
+        binding_randomness: &[u8; 32],
+    ) -> (SigningNonces, SigningCommitments) {
+        let mut secret = Scalar::from_bytes_mod_order(self.signing_share);
+        let hiding = generate_nonce(hiding_randomness, &secret);
+        let binding = generate_nonce(binding_randomness, &secret);
+        secret.zeroize();
+        let commitments = SigningCommitments {
+            identifier: self.identifier,
+            hiding: EdwardsPoint::mul_base(&hiding).compress().to_bytes(),
+            binding: EdwardsPoint::mul_base(&binding).compress().to_bytes(),
+        };
+        let nonces = SigningNonces {
+            hiding,
+            binding,
+            commitments: commitments.clone(),
+        };
+        (nonces, commitments)
+    }
+
+    /// Sign the package with this share (round 2)
+    ///
+    /// Check [`SigningPackage::message`] before calling this: the share
+    /// signs whatever the coordinator put there.
+    pub fn sign(&self, package: &SigningPackage, nonces: SigningNonces) -> Result<SignatureShare> {
+        if nonces.commitments.identifier != self.identifier {
+            return Err(AletheiaError::Threshold(
+                "Nonces belong to another shareholder".into(),
+            ));
+        }
+        if !package.commitments.contains(&nonces.commitments) {
+            return Err(AletheiaError::Threshold(
+                "Signing package does not include this shareholder's commitments".into(),
+            ));
+        }
+        if package.commitments.len() < usize::from(self.min_signers) {
+            return Err(AletheiaError::Threshold(alloc::format!(
+                "Signing needs {} shareholders, package has {}",
+                self.min_signers,
+                package.commitments.len()
+            )));
+        }
+        package.validate()?;
+
+        let binding_factors = binding_factors(&self.group_public_key, package);
+        let group_commitment = group_commitment(package, &binding_factors)?;
+        let challenge = challenge(&group_commitment, &self.group_public_key, &package.message);
+        let identifiers: Vec<u16> = package.commitments.iter().map(|c| c.identifier).collect();
+        let lambda = lagrange_coefficient(&identifiers, self.identifier);
+        let binding_factor = binding_factors[identifiers
+            .iter()
+            .position(|&id| id == self.identifier)
+            .expect("commitments checked above")];
+
+        let mut secret = Scalar::from_bytes_mod_order(self.signing_share);
+        let share = nonces.hiding + nonces.binding * binding_factor + lambda * secret * challenge;
+        secret.zeroize();
+        Ok(SignatureShare {
+            identifier: self.identifier,
+            share: share.to_bytes(),
+        })
+    }
+
+    /// Encode the share as CBOR
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut data = Vec::new();
+        ciborium::into_writer(self, &mut data)
+            .map_err(|e| AletheiaError::CborEncode(e.to_string()))?;
+        Ok(data)
+    }
+
+    /// Decode a share from CBOR
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        ciborium::from_reader(data).map_err(|e| AletheiaError::CborDecode(e.to_string()))
+    }
+}
+
+impl SigningPackage {
+    /// Create a package, ordering the commitments by identifier
+    pub fn new(mut commitments: Vec<SigningCommitments>, message: Vec<u8>) -> Result<Self> {
+        commitments.sort_by_key(|c| c.identifier);
+        let package = Self {
+            commitments,
+            message,
+        };
+        package.validate()?;
+        Ok(package)
+    }
+
+    /// Check identifiers are non-zero, distinct and in order
+    fn validate(&self) -> Result<()> {
+        let mut previous = 0;
+        for commitments in &self.commitments {
+            if commitments.identifier <= previous {
+                return Err(AletheiaError::Threshold(
+                    "Commitments must have distinct, ordered, non-zero identifiers".into(),
+                ));
+            }
+            previous = commitments.identifier;
+        }
+        Ok(())
+    }
+}
+
+/// A root CA whose key exists only as shares
+///
+/// Holds no secret; the shareholders sign through the two-round flow
+/// described in the [module documentation](self).
+#[derive(Debug, Clone)]
+pub struct ThresholdCertificateAuthority {
+    /// The CA's self-signed root certificate
+    pub certificate: Certificate,
+    /// Number of shareholders needed to sign
+    pub min_signers: u16,
+}
+
+impl ThresholdCertificateAuthority {
+    /// Create a threshold CA from its root certificate
+    pub fn new(certificate: Certificate, min_signers: u16) -> Self {
+        Self {
+            certificate,
+            min_signers,
+        }
+    }
+
+    /// Get the CA's public key
+    pub fn public_key(&self) -> Vec<u8> {
+        self.certificate.public_key.clone()
+    }
+
+    /// Build a certificate to be signed by the shareholders
+    ///
+    /// The returned certificate has no signature yet; pass it to
+    /// [`Self::signing_package`] and [`Self::finish_certificate`].
+    pub fn prepare_certificate(
+        &self,
+        subject_id: impl Into<String>,
+        subject_name: impl Into<String>,
+        subject_public_key: &[u8],
+        is_ca: bool,
+        issued_at: i64,
+        options: &IssueOptions,
+    ) -> Result<Certificate> {
+        unsigned_certificate(
+            &self.certificate,
+            generate_serial(),
+            subject_id.into(),
+            subject_name.into(),
+            subject_public_key,
+            is_ca,
+            issued_at,
+            options,
+        )
+    }
+
+    /// Collect the shareholders' commitments into a package for `certificate`
+    pub fn signing_package(
+        &self,
+        certificate: &Certificate,
+        commitments: Vec<SigningCommitments>,
+    ) -> Result<SigningPackage> {
+        if commitments.len() < usize::from(self.min_signers) {
+            return Err(AletheiaError::Threshold(alloc::format!(
+                "Signing needs {} shareholders, got {}",
+                self.min_signers,
+                commitments.len()
+            )));
+        }
+        SigningPackage::new(commitments, certificate.signable_data())
+    }
+
+    /// Combine the signature shares into the certificate's signature
+    pub fn finish_certificate(
+        &self,
+        mut certificate: Certificate,
+        package: &SigningPackage,
+        shares: &[SignatureShare],
+    ) -> Result<Certificate> {
+        if package.message != certificate.signable_data() {
+            return Err(AletheiaError::Threshold(
+                "Signing package is for a different certificate".into(),
+            ));
+        }
+        certificate.signature = aggregate(package, shares, &self.certificate.public_key)?;
+        Ok(certificate)
+    }
+}
+
+/// Combine signature shares into an Ed25519 signature over the package message
+///
+/// Fails unless every committed shareholder answered and the result verifies
+/// under `group_public_key`.
+pub fn aggregate(
+    package: &SigningPackage,
+    shares: &[SignatureShare],
+    group_public_key: &[u8],
+) -> Result<Vec<u8>> {
+    package.validate()?;
+    if shares.len() != package.commitments.len()
+        || !package
+            .commitments
+            .iter()
+            .all(|c| shares.iter().any(|s| s.identifier == c.identifier))
+    {
+        return Err(AletheiaError::Threshold(
+            "Need exactly one signature share per committed shareholder".into(),
+        ));
+    }
+
+    let binding_factors = binding_factors(group_public_key, package);
+    let group_commitment = group_commitment(package, &binding_factors)?;
+    let mut z = Scalar::ZERO;
+    for share in shares {
+        z +=
+            Option::<Scalar>::from(Scalar::from_canonical_bytes(share.share)).ok_or_else(|| {
+                AletheiaError::Threshold("Signature share is not a canonical scalar".into())
+            })?;
+    }
+
+    let mut signature = group_commitment.compress().to_bytes().to_vec();
+    signature.extend_from_slice(&z.to_bytes());
+
+    // A bad share yields an invalid signature; catch it here, not in verifiers
+    let verifying_key = VerifyingKey::try_from(group_public_key)
+        .map_err(|e| AletheiaError::Threshold(alloc::format!("Invalid group public key: {}", e)))?;
+    let parsed = Signature::try_from(signature.as_slice())
+        .map_err(|_| AletheiaError::Threshold("Invalid combined signature".into()))?;
+    verifying_key
+        .verify(&package.message, &parsed)
+        .map_err(|_| {
+            AletheiaError::Threshold(
+                "Combined signature does not verify; a signature share is invalid".into(),
+            )
+        })?;
+    Ok(signature)
+}
+
+/// Split a new key into `max_signers` shares, any `min_signers` of which can
+/// sign (trusted dealer key generation, RFC 9591 appendix C)
+///
+/// The key itself is erased before returning.
+pub(crate) fn generate_with_dealer(max_signers: u16, min_signers: u16) -> Result<Vec<KeyShare>> {
+    if min_signers < 2 || min_signers > max_signers {
+        return Err(AletheiaError::KeyGeneration(alloc::format!(
+            "Threshold must be between 2 and {} shares, got {}",
+            max_signers,
+            min_signers
+        )));
+    }
+
+    // f(x) = secret + a_1 x + ... + a_{t-1} x^{t-1}; share i is f(i)
+    let mut coefficients: Vec<Scalar> = (0..min_signers).map(|_| random_scalar()).collect();
+    let group_public_key = EdwardsPoint::mul_base(&coefficients[0])
+        .compress()
+        .to_bytes()
+        .to_vec();
+    let shares = (1..=max_signers)
+        .map(|identifier| {
+            let mut value = evaluate_polynomial(&coefficients, identifier);
+            let share = KeyShare {
+                identifier,
+                signing_share: value.to_bytes(),
+                group_public_key: group_public_key.clone(),
+                min_signers,
+            };
+            value.zeroize();
+            share
+        })
+        .collect();
+    coefficients.zeroize();
+    Ok(shares)
+}
+
+/// Run both signing rounds with shares held in one place
+pub(crate) fn sign_with_shares(shares: &[KeyShare], message: &[u8]) -> Result<Vec<u8>> {
+    let (nonces, commitments): (Vec<_>, Vec<_>) = shares.iter().map(KeyShare::commit).unzip();
+    let package = SigningPackage::new(commitments, message.to_vec())?;
+    let signature_shares = shares
+        .iter()
+        .zip(nonces)
+        .map(|(share, nonces)| share.sign(&package, nonces))
+        .collect::<Result<Vec<_>>>()?;
+    aggregate(&package, &signature_shares, &shares[0].group_public_key)
+}
+
+fn random_scalar() -> Scalar {
+    let mut bytes = [0u8; 64];
+    OsRng.fill_bytes(&mut bytes);
+    let scalar = Scalar::from_bytes_mod_order_wide(&bytes);
+    bytes.zeroize();
+    scalar
+}
+
+fn hash(parts: &[&[u8]]) -> [u8; 64] {
+    let mut hasher = Sha512::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    hasher.finalize().into()
+}
+
+fn hash_to_scalar(parts: &[&[u8]]) -> Scalar {
+    Scalar::from_bytes_mod_order_wide(&hash(parts))
+}
+
+/// polynomial_evaluate: the share of `identifier`, with the secret as the
+/// constant coefficient
+fn evaluate_polynomial(coefficients: &[Scalar], identifier: u16) -> Scalar {
+    let x = Scalar::from(u64::from(identifier));
+    coefficients
+        .iter()
+        .rev()
+        .fold(Scalar::ZERO, |acc, coefficient| acc * x + coefficient)
+}
+
+/// nonce_generate: H3(random_bytes || secret)
+fn generate_nonce(random: &[u8; 32], secret: &Scalar) -> Scalar {
+    hash_to_scalar(&[CONTEXT, b"nonce", random, secret.as_bytes()])
+}
+
+fn identifier_bytes(identifier: u16) -> [u8; 32] {
+    Scalar::from(u64::from(identifier)).to_bytes()
+}
+
+/// compute_binding_factors: one factor per commitment, in package order
+fn binding_factors(group_public_key: &[u8], package: &SigningPackage) -> Vec<Scalar> {
+    let mut encoded_commitments = Vec::with_capacity(package.commitments.len() * 96);
+    for c in &package.commitments {
+        encoded_commitments.extend_from_slice(&identifier_bytes(c.identifier));
+        encoded_commitments.extend_from_slice(&c.hiding);
+        encoded_commitments.extend_from_slice(&c.binding);
+    }
+    let message_hash = hash(&[CONTEXT, b"msg", &package.message]);
+    let commitment_hash = hash(&[CONTEXT, b"com", &encoded_commitments]);
+
+    package
+        .commitments
+        .iter()
+        .map(|c| {
+            hash_to_scalar(&[
+                CONTEXT,
+                b"rho",
+                group_public_key,
+                &message_hash,
+                &commitment_hash,
+                &identifier_bytes(c.identifier),
+            ])
+        })
+        .collect()
+}
+
+/// compute_group_commitment: sum of hiding + binding_factor * binding
+fn group_commitment(package: &SigningPackage, binding_factors: &[Scalar]) -> Result<EdwardsPoint> {
+    package
+        .commitments
+        .iter()
+        .zip(binding_factors)
+        .try_fold(EdwardsPoint::default(), |acc, (c, factor)| {
+            Ok(acc + decode_point(&c.hiding)? + decode_point(&c.binding)? * factor)
+        })
+}
+
+/// compute_challenge: the Ed25519 challenge H(R || A || M)
+fn challenge(group_commitment: &EdwardsPoint, group_public_key: &[u8], message: &[u8]) -> Scalar {
+    hash_to_scalar(&[
+        group_commitment.compress().as_bytes(),
+        group_public_key,
+        message,
+    ])
+}
+
+/// derive_interpolating_value: the Lagrange coefficient of `identifier` at 0
+fn lagrange_coefficient(identifiers: &[u16], identifier: u16) -> Scalar {
+    let x_i = Scalar::from(u64::from(identifier));
+    let mut numerator = Scalar::ONE;
+    let mut denominator = Scalar::ONE;
+    for &other in identifiers.iter().filter(|&&other| other != identifier) {
+        let x_j = Scalar::from(u64::from(other));
+        numerator *= x_j;
+        denominator *= x_j - x_i;
+    }
+    numerator * denominator.invert()
+}
+
+/// Decode a commitment, rejecting the identity and points outside the
+/// prime-order subgroup
+fn decode_point(bytes: &[u8; 32]) -> Result<EdwardsPoint> {
+    CompressedEdwardsY(*bytes)
+        .decompress()
+        .filter(|point| !point.is_identity() && point.is_torsion_free())
+        .ok_or_else(|| AletheiaError::Threshold("Invalid commitment".into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ca::{CertificateAuthority, SigningKeyPair},
+        certificate::ValidityCheck,
+        certificate::verify_certificate_chain_at,
+    };
+
+    fn issue(
+        ca: &ThresholdCertificateAuthority,
+        signers: &[&KeyShare],
+        subject_public_key: &[u8],
+    ) -> Result<Certificate> {
+        let cert = ca.prepare_certificate(
+            "alice@example.com",
+            "Alice",
+            subject_public_key,
+            false,
+            1704067200,
+            &IssueOptions::new(),
+        )?;
+        let (nonces, commitments): (Vec<_>, Vec<_>) =
+            signers.iter().map(|share| share.commit()).unzip();
+        let package = ca.signing_package(&cert, commitments)?;
+        let shares = signers
+            .iter()
+            .zip(nonces)
+            .map(|(share, nonces)| share.sign(&package, nonces))
+            .collect::<Result<Vec<_>>>()?;
+        ca.finish_certificate(cert, &package, &shares)
+    }
+
+    #[test]
+    fn test_threshold_issuance() {
+        let (ca, shares) = CertificateAuthority::new_root_threshold_with_timestamp(
+            "root@example.com",
+            "Root CA",
+            5,
+            3,
+            1704067200,
+        )
+        .unwrap();
+        assert_eq!(shares.len(), 5);
+        let keys = SigningKeyPair::generate();
+
+        // Any three shareholders can issue an ordinary, verifiable certificate
+        for signers in [[0, 1, 2], [0, 2, 4], [4, 3, 1]] {
+            let signers: Vec<_> = signers.iter().map(|&i| &shares[i]).collect();
+            let cert = issue(&ca, &signers, &keys.public_key()).unwrap();
+            verify_certificate_chain_at(
+                &[cert, ca.certificate.clone()],
+                &[ca.public_key()],
+                ValidityCheck::Skip,
+            )
+            .unwrap();
+        }
+
+        // Two cannot
+        assert!(matches!(
+            issue(&ca, &[&shares[0], &shares[1]], &keys.public_key()),
+            Err(AletheiaError::Threshold(_))
+        ));
+
+        // Shares survive storage
+        let restored = KeyShare::from_bytes(&shares[3].to_bytes().unwrap()).unwrap();
+        let signers = [&shares[0], &shares[1], &restored];
+        issue(&ca, &signers, &keys.public_key()).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_share_is_detected() {
+        let (ca, shares) = CertificateAuthority::new_root_threshold_with_timestamp(
+            "root@example.com",
+            "Root CA",
+            3,
+            2,
+            1704067200,
+        )
+        .unwrap();
+        let keys = SigningKeyPair::generate();
+        let cert = ca
+            .prepare_certificate(
+                "alice@example.com",
+                "Alice",
+                &keys.public_key(),
+                false,
+                1704067200,
+                &IssueOptions::new(),
+            )
+            .unwrap();
+        let (nonces_a, commitments_a) = shares[0].commit();
+        let (nonces_b, commitments_b) = shares[1].commit();
+        let package = ca
+            .signing_package(&cert, vec![commitments_b, commitments_a])
+            .unwrap();
+        let sig_a = shares[0].sign(&package, nonces_a).unwrap();
+        let mut sig_b = shares[1].sign(&package, nonces_b).unwrap();
+        sig_b.share = (Scalar::from_bytes_mod_order(sig_b.share) + Scalar::ONE).to_bytes();
+
+        assert!(matches!(
+            ca.finish_certificate(cert, &package, &[sig_a, sig_b]),
+            Err(AletheiaError::Threshold(_))
+        ));
+    }
+
+    fn scalar(hex: &str) -> Scalar {
+        Scalar::from_bytes_mod_order(hex::decode(hex).unwrap().try_into().unwrap())
+    }
+
+    fn bytes32(hex: &str) -> [u8; 32] {
+        hex::decode(hex).unwrap().try_into().unwrap()
+    }
+
+    /// RFC 9591 appendix E.1: FROST(Ed25519, SHA-512), 2 of 3, participants 1 and 3
+    #[test]
+    fn test_rfc9591_vectors() {
+        let group_secret_key =
+            scalar("7b1c33d3f5291d85de664833beb1ad469f7fb6025a0ec78b3a790c6e13a98304");
+        let group_public_key =
+            hex::decode("15d21ccd7ee42959562fc8aa63224c8851fb3ec85a3faf66040d380fb9738673")
+                .unwrap();
+        let message = hex::decode("74657374").unwrap();
+        assert_eq!(
+            EdwardsPoint::mul_base(&group_secret_key)
+                .compress()
+                .to_bytes()[..],
+            group_public_key[..]
+        );
+
+        // Trusted dealer shares
+        let coefficients = [
+            group_secret_key,
+            scalar("178199860edd8c62f5212ee91eff1295d0d670ab4ed4506866bae57e7030b204"),
+        ];
+        let participant_shares = [
+            "929dcc590407aae7d388761cddb0c0db6f5627aea8e217f4a033f2ec83d93509",
+            "a91e66e012e4364ac9aaa405fcafd370402d9859f7b6685c07eed76bf409e80d",
+            "d3cb090a075eb154e82fdb4b3cb507f110040905468bb9c46da8bdea643a9a02",
+        ];
+        let shares: Vec<KeyShare> = participant_shares
+            .iter()
+            .zip(1..)
+            .map(|(share, identifier)| {
+                assert_eq!(
+                    evaluate_polynomial(&coefficients, identifier),
+                    scalar(share)
+                );
+                KeyShare {
+                    identifier,
+                    signing_share: bytes32(share),
+                    group_public_key: group_public_key.clone(),
+                    min_signers: 2,
+                }
+            })
+            .collect();
+
+        // Round one: (share, hiding/binding randomness, nonces, commitments, binding factor)
+        let round_one = [
+            (
+                &shares[0],
+                "06894e04ee4aceec8619d5f6a0a180e2f47194d2ac306cba586b555e7c48d765",
+                "40d6f879ff22e22409f7d808fed81f37118e7d3e4af71c0f44c60207553bcbce",
+                "ef6599dea4010581a72b3018c37c29a4341d7cab0773e8687ca74dcf14009701",
+                "2baadfa0c69aa60d517ad4751de372a73f9d89cfc39026601f18458cdec12605",
+                "9b116f12589591a7e23fe8048059ab10ab48e67739e7a2fb3890f61a7999478c",
+                "c39b66b7dfccb122da24f13587f9a08c4347cae70046ca15169adf90ba65854d",
+                "ff960a65374e216a0918729b153466016664fa980d409bc3f308daa7acb30d0d",
+            ),
+            (
+                &shares[2],
+                "fa5d950626782aade9e33fa781376d4888c2d1de7c37518bc248eb818ed0cdde",
+                "7459a4d14ded0e365b085271be8dc6600d5b88f3978a2174297ffa32001a5afc",
+                "80f8d9a4b8f9366e1a0b618107c907cd3ee29fa9bb40b4691cc1bde696240005",
+                "2610b664a5a187b4855e87d2ff485bebdf043dc2f161fcd4854cd01dc0276404",
+                "e679a2a971748ccfaabead4dbe8ac1def61275c186c79d471e1e45091ad1e687",
+                "b2a942478453fabb6bd3181c56ba657413447b4136e1daea2484d396d1a516b3",
+                "279d48ec56f16d234c09ea62f3d02ab776ee38e03f66b20f939f1316e13df10f",
+            ),
+        ];
+        let mut all_nonces = Vec::new();
+        let mut all_commitments = Vec::new();
+        for (
+            share,
+            hiding_randomness,
+            binding_randomness,
+            hiding,
+            binding,
+            hiding_commitment,
+            binding_commitment,
+            _,
+        ) in round_one
+        {
+            let (nonces, commitments) = share
+                .commit_with_randomness(&bytes32(hiding_randomness), &bytes32(binding_randomness));
+            assert_eq!(nonces.hiding, scalar(hiding));
+            assert_eq!(nonces.binding, scalar(binding));
+            assert_eq!(commitments.hiding, bytes32(hiding_commitment));
+            assert_eq!(commitments.binding, bytes32(binding_commitment));
+            all_nonces.push(nonces);
+            all_commitments.push(commitments);
+        }
+        let package = SigningPackage::new(all_commitments, message.clone()).unwrap();
+        let factors = binding_factors(&group_public_key, &package);
+        assert_eq!(factors, [scalar(round_one[0].7), scalar(round_one[1].7)]);
+
+        // Round two
+        let signature_shares: Vec<SignatureShare> = [&shares[0], &shares[2]]
+            .into_iter()
+            .zip(all_nonces)
+            .map(|(share, nonces)| share.sign(&package, nonces).unwrap())
+            .collect();
+        assert_eq!(
+            signature_shares[0].share,
+            bytes32("60997f0142e43e8005027fe5ab7447dac00d22c2d7ddd9571a02613ba7d81c08")
+        );
+        assert_eq!(
+            signature_shares[1].share,
+            bytes32("79390e78bc59699c7af831f8f5fb478ec871a85f561a8641b5670ac4443f720f")
+        );
+
+        let signature = aggregate(&package, &signature_shares, &group_public_key).unwrap();
+        assert_eq!(
+            hex::encode(signature),
+            "154fb694ee7fcb37bf2381d94488c2a84b03b3352ad085feca81ad26d45852b7\
+             ecfe971ce4da95c4a95db93ac376b053897fca212ef85f99cf696bffeb178f07"
+        );
+    }
+}
@@ -0,0 +1,550 @@
+//! RFC 3161 trusted timestamps
+//!
+//! `signed_at` in the header is asserted by the signer. A timestamp authority
+//! (TSA) can vouch for the time instead: the signer sends it a request over the
+//! SHA-256 of the primary signature and stores the returned token in the file's
+//! trailer. Verifiers check the token's CMS signature and its X.509 chain up to
+//! a trusted TSA root given in [`crate::verifier::VerifyOptions::with_tsa_root`].
+//!
+//! Talking to the TSA (usually an HTTP POST of `application/timestamp-query`)
+//! is left to the caller:
+//!
+//! ```ignore
+//! let request = aletheia::timestamp::request(&file)?;
+//! let response = post("https://tsa.example.com", &request)?;
+//! aletheia::timestamp::attach(&mut file, &response)?;
+//! ```
+//!
+//! TSA signatures may use RSA PKCS#1 v1.5, ECDSA over P-256 or P-384, or
+//! Ed25519, with SHA-256, SHA-384 or SHA-512.
+
+extern crate alloc;
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use cms::{
+    cert::{
+        CertificateChoices,
+        x509::{
+            Certificate,
+            der::{
+                Decode, Encode,
+                asn1::{Int, ObjectIdentifier, OctetString},
+            },
+            ext::pkix::{ExtendedKeyUsage, SubjectKeyIdentifier},
+            spki::{AlgorithmIdentifierOwned, SubjectPublicKeyInfoOwned},
+        },
+    },
+    signed_data::{SignedData, SignerIdentifier, SignerInfo},
+};
+use rand::{RngCore, rngs::OsRng};
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use x509_tsp::{MessageImprint, TimeStampReq, TimeStampResp, TspVersion, TstInfo};
+
+use crate::{AletheiaError, AletheiaFile, Result, TimestampToken};
+
+const ID_SIGNED_DATA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.7.2");
+const ID_CT_TST_INFO: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.16.1.4");
+const ID_CONTENT_TYPE: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.3");
+const ID_MESSAGE_DIGEST: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.4");
+const ID_KP_TIME_STAMPING: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.5.5.7.3.8");
+const ID_CE_SUBJECT_KEY_IDENTIFIER: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.5.29.14");
+const ID_CE_EXT_KEY_USAGE: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.5.29.37");
+
+const ID_SHA256: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.2.1");
+const ID_SHA384: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.2.2");
+const ID_SHA512: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.2.3");
+
+const RSA_ENCRYPTION: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.1");
+const SHA256_WITH_RSA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.11");
+const SHA384_WITH_RSA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.12");
+const SHA512_WITH_RSA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.13");
+const ID_EC_PUBLIC_KEY: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.2.1");
+const ECDSA_WITH_SHA256: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.4.3.2");
+const ECDSA_WITH_SHA384: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.4.3.3");
+const ECDSA_WITH_SHA512: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.4.3.4");
+const SECP256R1: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.3.1.7");
+const SECP384R1: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.132.0.34");
+const ID_ED25519: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.101.112");
+
+/// Longest certificate path accepted from a token, excluding the root
+const MAX_CHAIN_DEPTH: usize = 8;
+
+/// What a verified timestamp token asserts
+#[derive(Debug, Clone)]
+pub struct TimestampInfo {
+    /// Subject of the TSA certificate
+    pub tsa_name: String,
+    /// Time asserted by the TSA (Unix timestamp)
+    pub time: i64,
+}
+
+/// Build a DER-encoded TimeStampReq over the primary signature of `file`
+///
+/// The request asks the TSA to include its certificate, so the token can be
+/// verified without fetching it separately.
+pub fn request(file: &AletheiaFile) -> Result<Vec<u8>> {
+    // Positive and minimally encoded
+    let mut nonce = [0u8; 8];
+    OsRng.fill_bytes(&mut nonce);
+    nonce[0] = (nonce[0] & 0x7F) | 0x40;
+
+    let request = TimeStampReq {
+        version: TspVersion::V1,
+        message_imprint: MessageImprint {
+            hash_algorithm: AlgorithmIdentifierOwned {
+                oid: ID_SHA256,
+                parameters: None,
+            },
+            hashed_message: OctetString::new(Sha256::digest(&file.signature).to_vec())
+                .map_err(der_error)?,
+        },
+        req_policy: None,
+        nonce: Some(Int::new(&nonce).map_err(der_error)?),
+        cert_req: true,
+        extensions: None,
+    };
+    request.to_der().map_err(der_error)
+}
+
+/// Store the token from a DER-encoded TimeStampResp in the trailer of `file`
+///
+/// Checks that the TSA granted the request and that the token covers this
+/// file's primary signature. The TSA's signature and chain are checked when
+/// the file is verified.
+pub fn attach(file: &mut AletheiaFile, response: &[u8]) -> Result<()> {
+    let response = TimeStampResp::from_der(response).map_err(der_error)?;
+
+    // 0 = granted, 1 = granted with modifications
+    let status = response.status.status as u8;
+    if status > 1 {
+        return Err(AletheiaError::Timestamp(alloc::format!(
+            "TSA rejected the request (status {status})"
+        )));
+    }
+    let token = response
+        .time_stamp_token
+        .ok_or_else(|| AletheiaError::Timestamp("Response carries no token".into()))?
+        .to_der()
+        .map_err(der_error)?;
+
+    let (_, tst_info) = parse_token(&token)?;
+    check_imprint(&tst_info.message_imprint, &file.signature)?;
+
+    file.timestamp_tokens.push(TimestampToken { token });
+    Ok(())
+}
+
+/// Verify a timestamp token over `primary_signature`
+///
+/// `trusted_roots` are DER-encoded X.509 certificates. The TSA certificate
+/// must carry the time-stamping extended key usage, and every certificate on
+/// the path must be valid at the asserted time.
+pub fn verify_token(
+    token: &TimestampToken,
+    primary_signature: &[u8],
+    trusted_roots: &[Vec<u8>],
+) -> Result<TimestampInfo> {
+    let (signed_data, tst_info) = parse_token(&token.token)?;
+    check_imprint(&tst_info.message_imprint, primary_signature)?;
+    let time = tst_info.gen_time.to_unix_duration().as_secs() as i64;
+
+    let signer_info = match signed_data.signer_infos.0.as_slice() {
+        [signer_info] => signer_info,
+        _ => {
+            return Err(AletheiaError::Timestamp(
+                "Token must have exactly one signer".into(),
+            ));
+        }
+    };
+    let certificates: Vec<&Certificate> = signed_data
+        .certificates
+        .iter()
+        .flat_map(|set| set.0.iter())
+        .filter_map(|choice| match choice {
+            CertificateChoices::Certificate(cert) => Some(cert),
+            _ => None,
+        })
+        .collect();
+
+    let tsa_cert = certificates
+        .iter()
+        .copied()
+        .find(|cert| is_signer(cert, &signer_info.sid))
+        .ok_or_else(|| AletheiaError::Timestamp("TSA certificate not included".into()))?;
+
+    let econtent = signed_data
+        .encap_content_info
+        .econtent
+        .as_ref()
+        .ok_or_else(|| AletheiaError::Timestamp("Token has no content".into()))?
+        .decode_as::<OctetString>()
+        .map_err(der_error)?;
+    verify_signer_info(signer_info, tsa_cert, econtent.as_bytes())?;
+
+    let has_time_stamping = extension::<ExtendedKeyUsage>(tsa_cert, ID_CE_EXT_KEY_USAGE)?
+        .is_some_and(|eku| eku.0.contains(&ID_KP_TIME_STAMPING));
+    if !has_time_stamping {
+        return Err(AletheiaError::Timestamp(
+            "TSA certificate is not valid for time stamping".into(),
+        ));
+    }
+
+    verify_chain(tsa_cert, &certificates, trusted_roots, time)?;
+
+    Ok(TimestampInfo {
+        tsa_name: tsa_cert.tbs_certificate.subject.to_string(),
+        time,
+    })
+}
+
+/// Split a token into its SignedData and the TSTInfo it carries
+fn parse_token(token: &[u8]) -> Result<(SignedData, TstInfo)> {
+    let content_info = x509_tsp::TimeStampToken::from_der(token).map_err(der_error)?;
+    if content_info.content_type != ID_SIGNED_DATA {
+        return Err(AletheiaError::Timestamp(
+            "Token is not CMS SignedData".into(),
+        ));
+    }
+    let signed_data = content_info
+        .content
+        .decode_as::<SignedData>()
+        .map_err(der_error)?;
+
+    let encap = &signed_data.encap_content_info;
+    if encap.econtent_type != ID_CT_TST_INFO {
+        return Err(AletheiaError::Timestamp(
+            "Token does not hold a TSTInfo".into(),
+        ));
+    }
+    let econtent = encap
+        .econtent
+        .as_ref()
+        .ok_or_else(|| AletheiaError::Timestamp("Token has no content".into()))?
+        .decode_as::<OctetString>()
+        .map_err(der_error)?;
+    let tst_info = TstInfo::from_der(econtent.as_bytes()).map_err(der_error)?;
+
+    Ok((signed_data, tst_info))
+}
+
+/// Check that `imprint` is the hash of `primary_signature`
+fn check_imprint(imprint: &MessageImprint, primary_signature: &[u8]) -> Result<()> {
+    let expected = digest(&imprint.hash_algorithm.oid, primary_signature)?;
+    if imprint.hashed_message.as_bytes() != expected.as_slice() {
+        return Err(AletheiaError::Timestamp(
+            "Token does not cover this signature".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Check the signed attributes and signature of the TSA's SignerInfo
+fn verify_signer_info(
+    signer_info: &SignerInfo,
+    tsa_cert: &Certificate,
+    tst_info: &[u8],
+) -> Result<()> {
+    let attrs = signer_info
+        .signed_attrs
+        .as_ref()
+        .ok_or_else(|| AletheiaError::Timestamp("Token has no signed attributes".into()))?;
+    let attr = |oid: ObjectIdentifier| {
+        attrs
+            .iter()
+            .find(|attr| attr.oid == oid)
+            .and_then(|attr| attr.values.iter().next())
+            .ok_or_else(|| AletheiaError::Timestamp(alloc::format!("Missing attribute {oid}")))
+    };
+
+    let content_type = attr(ID_CONTENT_TYPE)?
+        .decode_as::<ObjectIdentifier>()
+        .map_err(der_error)?;
+    if content_type != ID_CT_TST_INFO {
+        return Err(AletheiaError::Timestamp("Content type mismatch".into()));
+    }
+    let message_digest = attr(ID_MESSAGE_DIGEST)?
+        .decode_as::<OctetString>()
+        .map_err(der_error)?;
+    if message_digest.as_bytes() != digest(&signer_info.digest_alg.oid, tst_info)?.as_slice() {
+        return Err(AletheiaError::Timestamp("Message digest mismatch".into()));
+    }
+
+    // The signature covers the attributes encoded as a SET
+    let signed = attrs.to_der().map_err(der_error)?;
+    verify_signature(
+        &tsa_cert.tbs_certificate.subject_public_key_info,
+        &signer_info.signature_algorithm.oid,
+        Some(&signer_info.digest_alg.oid),
+        &signed,
+        signer_info.signature.as_bytes(),
+    )
+}
+
+/// Walk from the TSA certificate to a trusted root, checking validity at `time`
+fn verify_chain(
+    tsa_cert: &Certificate,
+    certificates: &[&Certificate],
+    trusted_roots: &[Vec<u8>],
+    time: i64,
+) -> Result<()> {
+    let roots = trusted_roots
+        .iter()
+        .map(|der| Certificate::from_der(der).map_err(der_error))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut current = tsa_cert;
+    for _ in 0..MAX_CHAIN_DEPTH {
+        check_validity(current, time)?;
+        if roots.iter().any(|root| root == current) {
+            return Ok(());
+        }
+        if roots
+            .iter()
+            .any(|root| issued_by(current, root).is_ok() && check_validity(root, time).is_ok())
+        {
+            return Ok(());
+        }
+        current = certificates
+            .iter()
+            .copied()
+            .find(|cert| *cert != current && issued_by(current, cert).is_ok())
+            .ok_or(AletheiaError::UntrustedRoot)?;
+    }
+    Err(AletheiaError::UntrustedRoot)
+}
+
+/// Check that `cert` names `issuer` as its issuer and carries its signature
+fn issued_by(cert: &Certificate, issuer: &Certificate) -> Result<()> {
+    if cert.tbs_certificate.issuer != issuer.tbs_certificate.subject {
+        return Err(AletheiaError::Timestamp("Issuer mismatch".into()));
+    }
+    let tbs = cert.tbs_certificate.to_der().map_err(der_error)?;
+    let signature = cert
+        .signature
+        .as_bytes()
+        .ok_or_else(|| AletheiaError::Timestamp("Malformed certificate signature".into()))?;
+    verify_signature(
+        &issuer.tbs_certificate.subject_public_key_info,
+        &cert.signature_algorithm.oid,
+        None,
+        &tbs,
+        signature,
+    )
+}
+
+fn check_validity(cert: &Certificate, time: i64) -> Result<()> {
+    let validity = &cert.tbs_certificate.validity;
+    let not_before = validity.not_before.to_unix_duration().as_secs() as i64;
+    let not_after = validity.not_after.to_unix_duration().as_secs() as i64;
+    if time < not_before || time > not_after {
+        return Err(AletheiaError::Timestamp(alloc::format!(
+            "Certificate {} not valid at the asserted time",
+            cert.tbs_certificate.subject
+        )));
+    }
+    Ok(())
+}
+
+/// Whether `cert` is the one identified by a SignerInfo's `sid`
+fn is_signer(cert: &Certificate, sid: &SignerIdentifier) -> bool {
+    match sid {
+        SignerIdentifier::IssuerAndSerialNumber(id) => {
+            cert.tbs_certificate.issuer == id.issuer
+                && cert.tbs_certificate.serial_number == id.serial_number
+        }
+        SignerIdentifier::SubjectKeyIdentifier(ski) => {
+            matches!(
+                extension::<SubjectKeyIdentifier>(cert, ID_CE_SUBJECT_KEY_IDENTIFIER),
+                Ok(Some(own)) if own == *ski
+            )
+        }
+    }
+}
+
+/// Decode extension `oid` of `cert`, if present
+fn extension<'a, T: Decode<'a>>(cert: &'a Certificate, oid: ObjectIdentifier) -> Result<Option<T>> {
+    cert.tbs_certificate
+        .extensions
+        .iter()
+        .flatten()
+        .find(|ext| ext.extn_id == oid)
+        .map(|ext| T::from_der(ext.extn_value.as_bytes()).map_err(der_error))
+        .transpose()
+}
+
+/// Verify `signature` over `message` with the key in `spki`
+///
+/// The hash comes from the signature algorithm, or from `digest_alg` when the
+/// algorithm names only the key type (as CMS allows for RSA).
+fn verify_signature(
+    spki: &SubjectPublicKeyInfoOwned,
+    signature_alg: &ObjectIdentifier,
+    digest_alg: Option<&ObjectIdentifier>,
+    message: &[u8],
+    signature: &[u8],
+) -> Result<()> {
+    let key = spki
+        .subject_public_key
+        .as_bytes()
+        .ok_or_else(|| AletheiaError::Timestamp("Malformed public key".into()))?;
+
+    if *signature_alg == ID_ED25519 {
+        let key: &[u8; 32] = key
+            .try_into()
+            .map_err(|_| AletheiaError::Timestamp("Malformed Ed25519 key".into()))?;
+        let key = ed25519_dalek::VerifyingKey::from_bytes(key)
+            .map_err(|_| AletheiaError::Timestamp("Malformed Ed25519 key".into()))?;
+        let signature =
+            ed25519_dalek::Signature::from_slice(signature).map_err(invalid_signature)?;
+        return key
+            .verify_strict(message, &signature)
+            .map_err(invalid_signature);
+    }
+
+    let hash_alg = match *signature_alg {
+        SHA256_WITH_RSA | ECDSA_WITH_SHA256 => ID_SHA256,
+        SHA384_WITH_RSA | ECDSA_WITH_SHA384 => ID_SHA384,
+        SHA512_WITH_RSA | ECDSA_WITH_SHA512 => ID_SHA512,
+        _ => *digest_alg.ok_or_else(|| unsupported(signature_alg))?,
+    };
+    let hashed = digest(&hash_alg, message)?;
+
+    match spki.algorithm.oid {
+        RSA_ENCRYPTION => {
+            use rsa::{Pkcs1v15Sign, RsaPublicKey, pkcs1::DecodeRsaPublicKey};
+
+            let key = RsaPublicKey::from_pkcs1_der(key)
+                .map_err(|_| AletheiaError::Timestamp("Malformed RSA key".into()))?;
+            let scheme = match hash_alg {
+                ID_SHA256 => Pkcs1v15Sign::new::<Sha256>(),
+                ID_SHA384 => Pkcs1v15Sign::new::<Sha384>(),
+                _ => Pkcs1v15Sign::new::<Sha512>(),
+            };
+            key.verify(scheme, &hashed, signature)
+                .map_err(invalid_signature)
+        }
+        ID_EC_PUBLIC_KEY => {
+            use p256::ecdsa::signature::hazmat::PrehashVerifier;
+
+            let curve = spki
+                .algorithm
+                .parameters
+                .as_ref()
+                .and_then(|params| params.decode_as::<ObjectIdentifier>().ok());
+            match curve {
+                Some(SECP256R1) => {
+                    let key = p256::ecdsa::VerifyingKey::from_sec1_bytes(key)
+                        .map_err(|_| AletheiaError::Timestamp("Malformed P-256 key".into()))?;
+                    let signature =
+                        p256::ecdsa::Signature::from_der(signature).map_err(invalid_signature)?;
+                    key.verify_prehash(&hashed, &signature)
+                        .map_err(invalid_signature)
+                }
+                Some(SECP384R1) => {
+                    let key = p384::ecdsa::VerifyingKey::from_sec1_bytes(key)
+                        .map_err(|_| AletheiaError::Timestamp("Malformed P-384 key".into()))?;
+                    let signature =
+                        p384::ecdsa::Signature::from_der(signature).map_err(invalid_signature)?;
+                    key.verify_prehash(&hashed, &signature)
+                        .map_err(invalid_signature)
+                }
+                _ => Err(AletheiaError::Timestamp(
+                    "Unsupported elliptic curve".into(),
+                )),
+            }
+        }
+        other => Err(unsupported(&other)),
+    }
+}
+
+fn digest(alg: &ObjectIdentifier, data: &[u8]) -> Result<Vec<u8>> {
+    match *alg {
+        ID_SHA256 => Ok(Sha256::digest(data).to_vec()),
+        ID_SHA384 => Ok(Sha384::digest(data).to_vec()),
+        ID_SHA512 => Ok(Sha512::digest(data).to_vec()),
+        _ => Err(unsupported(alg)),
+    }
+}
+
+fn invalid_signature<E>(_: E) -> AletheiaError {
+    AletheiaError::Timestamp("Invalid TSA signature".into())
+}
+
+fn unsupported(oid: &ObjectIdentifier) -> AletheiaError {
+    AletheiaError::Timestamp(alloc::format!("Unsupported algorithm {oid}"))
+}
+
+fn der_error(err: cms::cert::x509::der::Error) -> AletheiaError {
+    AletheiaError::Timestamp(err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        file::{from_bytes, to_bytes},
+        verifier::{VerifyOptions, verify, verify_with_options},
+    };
+
+    // A signed file, a TSA response over its signature (P-256 TSA under an
+    // RSA root) and the TSA root
+    const SIGNED: &[u8] = include_bytes!("../testdata/timestamp/signed.alx");
+    const RESPONSE: &[u8] = include_bytes!("../testdata/timestamp/response.tsr");
+    const TSA_ROOT: &[u8] = include_bytes!("../testdata/timestamp/tsa-root.der");
+
+    #[test]
+    fn test_attach_and_verify_token() {
+        let mut file = from_bytes(SIGNED).unwrap();
+        attach(&mut file, RESPONSE).unwrap();
+
+        // Tokens survive serialization
+        let file = from_bytes(&to_bytes(&file).unwrap()).unwrap();
+        assert_eq!(file.timestamp_tokens.len(), 1);
+
+        let roots = vec![file.certificate_chain[1].public_key.clone()];
+        let options = VerifyOptions::new().with_tsa_root(TSA_ROOT.to_vec());
+        let result = verify_with_options(&file, &roots, &options).unwrap();
+        assert_eq!(result.timestamps.len(), 1);
+        assert!(result.timestamps[0].valid);
+        assert_eq!(
+            result.timestamps[0].tsa_name,
+            "CN=Aletheia Test TSA,O=Aletheia Test"
+        );
+        assert_eq!(result.timestamps[0].time, 1792207463);
+
+        // Without the TSA root the token is reported untrusted, the file still verifies
+        let result = verify(&file, &roots).unwrap();
+        assert!(!result.timestamps[0].valid);
+    }
+
+    #[test]
+    fn test_token_bound_to_signature() {
+        let mut file = from_bytes(SIGNED).unwrap();
+        attach(&mut file, RESPONSE).unwrap();
+        let token = &file.timestamp_tokens[0];
+
+        let mut other_signature = file.signature.clone();
+        other_signature[0] ^= 1;
+        assert!(matches!(
+            verify_token(token, &other_signature, &[TSA_ROOT.to_vec()]),
+            Err(AletheiaError::Timestamp(_))
+        ));
+
+        file.signature = other_signature;
+        assert!(attach(&mut file, RESPONSE).is_err());
+    }
+
+    #[test]
+    fn test_request_covers_signature() {
+        let file = from_bytes(SIGNED).unwrap();
+        let request = TimeStampReq::from_der(&request(&file).unwrap()).unwrap();
+        assert_eq!(request.message_imprint.hash_algorithm.oid, ID_SHA256);
+        assert_eq!(
+            request.message_imprint.hashed_message.as_bytes(),
+            Sha256::digest(&file.signature).as_slice()
+        );
+        assert!(request.cert_req);
+    }
+}
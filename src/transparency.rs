@@ -0,0 +1,221 @@
+//! Transparency log anchoring
+//!
+//! A signer can submit the primary signature to an append-only transparency
+//! log (in the style of Rekor or Certificate Transparency). The log adds a
+//! leaf binding the signature to the time it was integrated, and returns a
+//! [`LogEntry`]: an inclusion proof against a tree head the log has signed.
+//! Storing the entry in the file's trailer lets anyone check, with only the
+//! log's public key, that the signature was public at `integrated_time`.
+//!
+//! The tree uses the same construction as chunked payloads (see
+//! [`crate::merkle`]), with each leaf being [`leaf_data`] of one signature.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use ed25519_dalek::{Signature, VerifyingKey};
+use sha2::{Digest, Sha256};
+
+use crate::{AletheiaError, LogEntry, Result, merkle};
+
+/// Domain separator for log leaves
+const LEAF_CONTEXT: &[u8] = b"ALETHEIA-LOG-ENTRY";
+
+/// Domain separator for signed tree heads
+const CHECKPOINT_CONTEXT: &[u8] = b"ALETHEIA-LOG-CHECKPOINT";
+
+/// Identifier of the log with Ed25519 `public_key`
+pub fn log_id(public_key: &[u8]) -> merkle::Hash {
+    Sha256::digest(public_key).into()
+}
+
+/// Leaf recorded by the log for `primary_signature`
+///
+/// `LEAF_CONTEXT || integrated_time (8 bytes, signed) || SHA-256(primary_signature)`
+pub fn leaf_data(primary_signature: &[u8], integrated_time: i64) -> Vec<u8> {
+    let mut data = Vec::with_capacity(LEAF_CONTEXT.len() + 8 + 32);
+    data.extend_from_slice(LEAF_CONTEXT);
+    data.extend_from_slice(&integrated_time.to_le_bytes());
+    data.extend_from_slice(&Sha256::digest(primary_signature));
+    data
+}
+
+/// Bytes the log signs to commit to a tree head
+///
+/// `CHECKPOINT_CONTEXT || log_id || tree_size (8 bytes) || root_hash`
+pub fn checkpoint_input(log_id: &[u8], tree_size: u64, root_hash: &[u8]) -> Vec<u8> {
+    let mut input = Vec::new();
+    input.extend_from_slice(CHECKPOINT_CONTEXT);
+    input.extend_from_slice(log_id);
+    input.extend_from_slice(&tree_size.to_le_bytes());
+    input.extend_from_slice(root_hash);
+    input
+}
+
+/// Verify that `entry` proves inclusion of `primary_signature` in a trusted log
+///
+/// `log_keys` are the Ed25519 public keys of the logs the verifier trusts.
+pub fn verify_entry(
+    entry: &LogEntry,
+    primary_signature: &[u8],
+    log_keys: &[Vec<u8>],
+) -> Result<()> {
+    let log_key = log_keys
+        .iter()
+        .find(|key| log_id(key).as_slice() == entry.log_id.as_slice())
+        .ok_or_else(|| AletheiaError::TransparencyLog("Entry is from an unknown log".into()))?;
+
+    // The tree head must be signed by the log
+    let verifying_key = VerifyingKey::try_from(log_key.as_slice())
+        .map_err(|_| AletheiaError::TransparencyLog("Invalid log public key".into()))?;
+    let signature = Signature::from_slice(&entry.checkpoint_signature)
+        .map_err(|_| AletheiaError::TransparencyLog("Invalid checkpoint signature".into()))?;
+    verifying_key
+        .verify_strict(
+            &checkpoint_input(&entry.log_id, entry.tree_size, &entry.root_hash),
+            &signature,
+        )
+        .map_err(|_| AletheiaError::TransparencyLog("Invalid checkpoint signature".into()))?;
+
+    // And the signature must be a leaf of that tree
+    let proof = entry
+        .inclusion_proof
+        .iter()
+        .map(|hash| merkle::Hash::try_from(hash.as_slice()))
+        .collect::<core::result::Result<Vec<_>, _>>()
+        .map_err(|_| AletheiaError::TransparencyLog("Malformed inclusion proof".into()))?;
+    if !merkle::verify_proof(
+        &leaf_data(primary_signature, entry.integrated_time),
+        entry.index,
+        entry.tree_size,
+        &proof,
+        &entry.root_hash,
+    ) {
+        return Err(AletheiaError::TransparencyLog(
+            "Invalid inclusion proof".into(),
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        AletheiaFile, Header,
+        ca::{CertificateAuthority, SigningKeyPair},
+        signer::Signer,
+        verifier::{VerifyOptions, verify_with_options},
+    };
+
+    fn create_test_file() -> (AletheiaFile, Vec<Vec<u8>>) {
+        let timestamp = 1704067200;
+        let ca =
+            CertificateAuthority::new_root_with_timestamp("root@example.com", "Root CA", timestamp);
+        let user_keys = SigningKeyPair::generate();
+        let user_cert = ca
+            .issue_certificate_with_timestamp(
+                "alice@example.com",
+                "Alice",
+                &user_keys.public_key(),
+                false,
+                timestamp,
+            )
+            .unwrap();
+        let signer = Signer::new(user_keys, vec![user_cert, ca.certificate.clone()]).unwrap();
+        let file = signer
+            .sign(
+                b"Logged content",
+                Header::new_with_timestamp("alice@example.com", timestamp),
+            )
+            .unwrap();
+        (file, vec![ca.public_key()])
+    }
+
+    /// Log `signature` among other entries, as a log server would
+    fn log_signature(
+        log_keys: &SigningKeyPair,
+        signature: &[u8],
+        integrated_time: i64,
+    ) -> LogEntry {
+        // Leaves have a fixed size, so the chunked Merkle helpers build the tree
+        let index = 3;
+        let mut leaves = Vec::new();
+        for i in 0..5u8 {
+            if i as u64 == index {
+                leaves.extend(leaf_data(signature, integrated_time));
+            } else {
+                leaves.extend(leaf_data(&[i; 64], integrated_time - 10));
+            }
+        }
+        let leaf_len = leaf_data(signature, integrated_time).len() as u32;
+        let root_hash = merkle::merkle_root(&leaves, leaf_len);
+        let proof = merkle::merkle_proof(&leaves, leaf_len, index).unwrap();
+
+        let log_id = log_id(&log_keys.public_key());
+        LogEntry {
+            log_id: log_id.to_vec(),
+            integrated_time,
+            index,
+            tree_size: 5,
+            root_hash: root_hash.to_vec(),
+            inclusion_proof: proof
+                .iter()
+                .map(|hash| serde_bytes::ByteBuf::from(hash.to_vec()))
+                .collect(),
+            checkpoint_signature: log_keys.sign(&checkpoint_input(&log_id, 5, &root_hash)),
+        }
+    }
+
+    #[test]
+    fn test_verify_log_entry() {
+        let (mut file, roots) = create_test_file();
+        let log_keys = SigningKeyPair::generate();
+        file.log_entries
+            .push(log_signature(&log_keys, &file.signature, 1704070800));
+
+        let options = VerifyOptions::new()
+            .with_log_key(log_keys.public_key())
+            .require_log_inclusion();
+        let result = verify_with_options(&file, &roots, &options).unwrap();
+        assert!(result.log_entries[0].valid);
+        assert_eq!(result.log_entries[0].index, 3);
+        assert_eq!(result.log_entries[0].integrated_time, 1704070800);
+
+        // Moving the integration time breaks the proof
+        let mut backdated = file.clone();
+        backdated.log_entries[0].integrated_time = 1704067200;
+        assert!(matches!(
+            verify_with_options(&backdated, &roots, &options),
+            Err(AletheiaError::TransparencyLog(_))
+        ));
+    }
+
+    #[test]
+    fn test_require_log_inclusion() {
+        let (mut file, roots) = create_test_file();
+        let log_keys = SigningKeyPair::generate();
+        let options = VerifyOptions::new()
+            .with_log_key(log_keys.public_key())
+            .require_log_inclusion();
+
+        // No entry at all
+        assert!(verify_with_options(&file, &roots, &options).is_err());
+
+        // An entry from a log the verifier does not trust
+        let other_log = SigningKeyPair::generate();
+        file.log_entries
+            .push(log_signature(&other_log, &file.signature, 1704070800));
+        assert!(verify_with_options(&file, &roots, &options).is_err());
+
+        // Without the requirement, the entry is only reported
+        let result = verify_with_options(
+            &file,
+            &roots,
+            &VerifyOptions::new().with_log_key(log_keys.public_key()),
+        )
+        .unwrap();
+        assert!(!result.log_entries[0].valid);
+    }
+}
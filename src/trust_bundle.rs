@@ -0,0 +1,394 @@
+//! Signed trust bundles
+//!
+//! A publisher such as the PKI portal distributes the set of trusted root
+//! certificates as a signed bundle, so clients can refresh their roots
+//! without trusting the transport. Clients pin the bundle signer and check
+//! every bundle they fetch against it before using its roots. Bundles are
+//! exchanged as JSON (byte fields in base64) or CBOR.
+//!
+//! A CA signs bundles with
+//! [`CertificateAuthority::sign_trust_bundle`](crate::ca::CertificateAuthority::sign_trust_bundle)
+//! and clients pin its certificate ([`TrustBundle::verify_signature`]). A
+//! publisher with a bare key, such as one held in a KMS, signs with
+//! [`TrustBundle::sign_with_key`] and clients pin that key
+//! ([`TrustBundle::verify_with_key`]).
+
+extern crate alloc;
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    AletheiaError, Certificate, Fingerprint, Result, certificate::verify_certificate_signature,
+    hsm::ExternalSigner, types::serde_base64,
+};
+
+/// Domain separator for trust bundle signatures
+const TRUST_BUNDLE_CONTEXT: &[u8] = b"ALETHEIA-TRUST-BUNDLE";
+
+/// A signed set of trusted root certificates
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrustBundle {
+    /// Version label assigned by the publisher
+    pub version: String,
+
+    /// Unix timestamp when the bundle was published
+    ///
+    /// Clients refreshing their roots should reject a bundle older than the
+    /// one they already have.
+    pub issued_at: i64,
+
+    /// Trusted root CA certificates
+    pub roots: Vec<Certificate>,
+
+    /// Intermediate CA certificates under the roots, for building chains
+    ///
+    /// They are not trust anchors.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub intermediates: Vec<Certificate>,
+
+    /// Hex fingerprint of the signer: of its certificate, or of its raw
+    /// public key ([`key_fingerprint`]) for a bundle signed with a bare key
+    pub signer_fingerprint: String,
+
+    /// Ed25519 signature by the signer (64 bytes)
+    #[serde(with = "serde_base64")]
+    pub signature: Vec<u8>,
+}
+
+/// Trust bundle data without signature (used for signing)
+#[derive(Serialize)]
+struct UnsignedTrustBundle<'a> {
+    version: &'a str,
+    issued_at: i64,
+    roots: &'a [Certificate],
+    #[serde(skip_serializing_if = "<[Certificate]>::is_empty")]
+    intermediates: &'a [Certificate],
+    signer_fingerprint: &'a str,
+}
+
+impl TrustBundle {
+    /// Get the data signed by the signer (context and everything except the
+    /// signature)
+    pub fn signable_data(&self) -> Vec<u8> {
+        let unsigned = UnsignedTrustBundle {
+            version: &self.version,
+            issued_at: self.issued_at,
+            roots: &self.roots,
+            intermediates: &self.intermediates,
+            signer_fingerprint: &self.signer_fingerprint,
+        };
+        let mut data = TRUST_BUNDLE_CONTEXT.to_vec();
+        ciborium::into_writer(&unsigned, &mut data).expect("CBOR encoding failed");
+        data
+    }
+
+    /// Sign a bundle of `roots` and `intermediates` with a bare key
+    ///
+    /// The bundle names its signer by [`key_fingerprint`]; clients pin the
+    /// key and check bundles with [`TrustBundle::verify_with_key`]. The
+    /// signature `signer` returns is checked before the bundle is handed out.
+    pub fn sign_with_key(
+        version: impl Into<String>,
+        issued_at: i64,
+        roots: Vec<Certificate>,
+        intermediates: Vec<Certificate>,
+        signer: &dyn ExternalSigner,
+    ) -> Result<Self> {
+        check_roots(&roots)?;
+        check_intermediates(&intermediates)?;
+        let public_key = signer.public_key();
+        let mut bundle = Self {
+            version: version.into(),
+            issued_at,
+            roots,
+            intermediates,
+            signer_fingerprint: key_fingerprint(&public_key),
+            signature: Vec::new(),
+        };
+        bundle.signature = signer.sign(&bundle.signable_data())?;
+        bundle.check_signature(&public_key).map_err(|_| {
+            AletheiaError::ExternalSigner("Signer returned an invalid signature".into())
+        })?;
+        Ok(bundle)
+    }
+
+    /// Verify that the bundle was signed by the pinned `signer` certificate
+    pub fn verify_signature(&self, signer: &Certificate) -> Result<()> {
+        self.check_signer(&signer.fingerprint().to_hex())?;
+        self.check_signature(&signer.public_key)
+    }
+
+    /// Verify that the bundle was signed by the pinned Ed25519 `public_key`
+    pub fn verify_with_key(&self, public_key: &[u8]) -> Result<()> {
+        self.check_signer(&key_fingerprint(public_key))?;
+        self.check_signature(public_key)
+    }
+
+    fn check_signer(&self, fingerprint: &str) -> Result<()> {
+        if crate::ct::eq(fingerprint.as_bytes(), self.signer_fingerprint.as_bytes()) {
+            Ok(())
+        } else {
+            Err(AletheiaError::InvalidTrustBundle(
+                "Bundle was signed by a different signer".into(),
+            ))
+        }
+    }
+
+    fn check_signature(&self, public_key: &[u8]) -> Result<()> {
+        let verifying_key = VerifyingKey::try_from(public_key).map_err(|e| {
+            AletheiaError::InvalidTrustBundle(alloc::format!("Invalid signer public key: {}", e))
+        })?;
+
+        let signature = Signature::try_from(self.signature.as_slice()).map_err(|e| {
+            AletheiaError::InvalidTrustBundle(alloc::format!("Invalid signature format: {}", e))
+        })?;
+
+        verifying_key
+            .verify(&self.signable_data(), &signature)
+            .map_err(|_| AletheiaError::InvalidTrustBundle("Signature verification failed".into()))
+    }
+
+    /// Public keys of the roots, for use as trusted root keys
+    pub fn public_keys(&self) -> Vec<Vec<u8>> {
+        self.roots
+            .iter()
+            .map(|root| root.public_key.clone())
+            .collect()
+    }
+
+    /// Decode a bundle from JSON or CBOR
+    ///
+    /// Every root must be a self-signed CA certificate, and every
+    /// intermediate a CA certificate. The signature is not checked; call
+    /// [`TrustBundle::verify_signature`] or [`TrustBundle::verify_with_key`]
+    /// before trusting the roots.
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let is_json = data
+            .iter()
+            .find(|byte| !byte.is_ascii_whitespace())
+            .is_some_and(|&byte| byte == b'{');
+        let bundle: Self = if is_json {
+            serde_json::from_slice(data)
+                .map_err(|e| AletheiaError::InvalidTrustBundle(e.to_string()))?
+        } else {
+            ciborium::from_reader(data).map_err(|e| AletheiaError::CborDecode(e.to_string()))?
+        };
+        check_roots(&bundle.roots)?;
+        check_intermediates(&bundle.intermediates)?;
+        Ok(bundle)
+    }
+
+    /// Encode the bundle as JSON
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string(self).map_err(|e| AletheiaError::InvalidTrustBundle(e.to_string()))
+    }
+
+    /// Encode the bundle as CBOR
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut data = Vec::new();
+        ciborium::into_writer(self, &mut data)
+            .map_err(|e| AletheiaError::CborEncode(e.to_string()))?;
+        Ok(data)
+    }
+}
+
+/// Check that every root is a self-signed CA certificate
+pub(crate) fn check_roots(roots: &[Certificate]) -> Result<()> {
+    for root in roots {
+        if !root.is_ca || verify_certificate_signature(root, &root.public_key).is_err() {
+            return Err(AletheiaError::InvalidTrustBundle(alloc::format!(
+                "Not a self-signed root CA certificate: {}",
+                root.subject_id
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Check that every intermediate is a CA certificate
+fn check_intermediates(intermediates: &[Certificate]) -> Result<()> {
+    match intermediates
+        .iter()
+        .find(|intermediate| !intermediate.is_ca)
+    {
+        Some(intermediate) => Err(AletheiaError::InvalidTrustBundle(alloc::format!(
+            "Not a CA certificate: {}",
+            intermediate.subject_id
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Hex SHA-256 of a raw Ed25519 public key, naming a bundle signer that has
+/// no certificate
+pub fn key_fingerprint(public_key: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+    Fingerprint(Sha256::digest(public_key).into()).to_hex()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Header,
+        ca::{CertificateAuthority, SigningKeyPair},
+        signer::Signer,
+        verifier::verify_with_bundle,
+    };
+
+    #[test]
+    fn test_trust_bundle_round_trip() {
+        let timestamp = 1704067200;
+        let ca =
+            CertificateAuthority::new_root_with_timestamp("root@example.com", "Root CA", timestamp);
+        let publisher = CertificateAuthority::new_root_with_timestamp(
+            "bundles@example.com",
+            "Bundle signer",
+            timestamp,
+        );
+        let bundle = publisher
+            .sign_trust_bundle_with_timestamp(
+                "2024-01",
+                alloc::vec![ca.certificate.clone()],
+                timestamp,
+            )
+            .unwrap();
+
+        for encoded in [
+            bundle.to_json().unwrap().into_bytes(),
+            bundle.to_bytes().unwrap(),
+        ] {
+            let parsed = TrustBundle::parse(&encoded).unwrap();
+            parsed.verify_signature(&publisher.certificate).unwrap();
+            assert_eq!(parsed.version, "2024-01");
+            assert_eq!(parsed.public_keys(), alloc::vec![ca.public_key()]);
+        }
+
+        // Files under the bundled roots verify
+        let user_keys = SigningKeyPair::generate();
+        let user_cert = ca
+            .issue_certificate_with_timestamp(
+                "alice@example.com",
+                "Alice",
+                &user_keys.public_key(),
+                false,
+                timestamp,
+            )
+            .unwrap();
+        let file = Signer::new(user_keys, alloc::vec![user_cert, ca.certificate.clone()])
+            .unwrap()
+            .sign(
+                b"Test content",
+                Header::new_with_timestamp("alice@example.com", timestamp),
+            )
+            .unwrap();
+        verify_with_bundle(&file, &bundle).unwrap();
+
+        // Another signer, or an added root, is rejected
+        assert!(bundle.verify_signature(&ca.certificate).is_err());
+        let mut tampered = bundle.clone();
+        tampered.roots.push(publisher.certificate.clone());
+        let result = tampered.verify_signature(&publisher.certificate);
+        assert!(matches!(result, Err(AletheiaError::InvalidTrustBundle(_))));
+    }
+
+    #[test]
+    fn test_trust_bundle_rejects_non_roots() {
+        let timestamp = 1704067200;
+        let ca =
+            CertificateAuthority::new_root_with_timestamp("root@example.com", "Root CA", timestamp);
+        let user_keys = SigningKeyPair::generate();
+        let user_cert = ca
+            .issue_certificate_with_timestamp(
+                "alice@example.com",
+                "Alice",
+                &user_keys.public_key(),
+                false,
+                timestamp,
+            )
+            .unwrap();
+        let result = ca.sign_trust_bundle_with_timestamp("1", alloc::vec![user_cert], timestamp);
+        assert!(matches!(result, Err(AletheiaError::InvalidTrustBundle(_))));
+    }
+
+    /// A bare key signing as an external signer would
+    struct KeySigner(SigningKeyPair);
+
+    impl ExternalSigner for KeySigner {
+        fn public_key(&self) -> Vec<u8> {
+            self.0.public_key()
+        }
+
+        fn sign(&self, message: &[u8]) -> Result<Vec<u8>> {
+            Ok(self.0.sign(message))
+        }
+
+        fn sign_prehashed(&self, _message: &[u8], _context: &[u8]) -> Result<Vec<u8>> {
+            unimplemented!()
+        }
+    }
+
+    #[test]
+    fn test_trust_bundle_signed_with_key() {
+        let timestamp = 1704067200;
+        let root =
+            CertificateAuthority::new_root_with_timestamp("root@example.com", "Root CA", timestamp);
+        let intermediate_keys = SigningKeyPair::generate();
+        let intermediate = root
+            .issue_certificate_with_timestamp(
+                "issuing@example.com",
+                "Issuing CA",
+                &intermediate_keys.public_key(),
+                true,
+                timestamp,
+            )
+            .unwrap();
+        let signer = KeySigner(SigningKeyPair::generate());
+        let bundle = TrustBundle::sign_with_key(
+            "2024-01",
+            timestamp,
+            alloc::vec![root.certificate.clone()],
+            alloc::vec![intermediate],
+            &signer,
+        )
+        .unwrap();
+        assert_eq!(
+            bundle.signer_fingerprint,
+            key_fingerprint(&signer.public_key())
+        );
+
+        let parsed = TrustBundle::parse(bundle.to_json().unwrap().as_bytes()).unwrap();
+        parsed.verify_with_key(&signer.public_key()).unwrap();
+        assert_eq!(parsed.intermediates.len(), 1);
+
+        // Another key, or a dropped intermediate, is rejected
+        let other = SigningKeyPair::generate();
+        assert!(parsed.verify_with_key(&other.public_key()).is_err());
+        let mut tampered = parsed.clone();
+        tampered.intermediates.clear();
+        let result = tampered.verify_with_key(&signer.public_key());
+        assert!(matches!(result, Err(AletheiaError::InvalidTrustBundle(_))));
+
+        // An end-entity certificate is not an intermediate
+        let user_cert = root
+            .issue_certificate_with_timestamp(
+                "alice@example.com",
+                "Alice",
+                &other.public_key(),
+                false,
+                timestamp,
+            )
+            .unwrap();
+        let result = TrustBundle::sign_with_key(
+            "2024-02",
+            timestamp,
+            alloc::vec![root.certificate.clone()],
+            alloc::vec![user_cert],
+            &signer,
+        );
+        assert!(matches!(result, Err(AletheiaError::InvalidTrustBundle(_))));
+    }
+}
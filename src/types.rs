@@ -1,20 +1,43 @@
 extern crate alloc;
 
 use alloc::collections::BTreeMap;
-use alloc::string::String;
+use alloc::string::{String, ToString};
 use alloc::vec::Vec;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+use crate::{AletheiaError, Result};
 
 pub const MAGIC_BYTES: &[u8; 8] = b"ALETHEIA";
 pub const VERSION_MAJOR: u8 = 1;
-pub const VERSION_MINOR: u8 = 0;
+pub const VERSION_MINOR: u8 = 1;
+
+/// Algorithm used to compress the payload
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressionAlgorithm {
+    /// LZ4 (pure Rust, fast, WASM compatible)
+    Lz4,
+    /// Zstandard at the given level (better ratios, requires the `zstd` feature)
+    ///
+    /// The level is not stored in the file; algorithms read back from
+    /// [`Flags`] report level 0 (the zstd default).
+    Zstd { level: i32 },
+}
 
 /// Flags for the Aletheia file format
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+///
+/// Serializes as its integer value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(transparent)]
 pub struct Flags(u16);
 
 impl Flags {
     pub const COMPRESSED: u16 = 0b0000_0000_0000_0001;
+    /// Compressed payload uses zstd instead of LZ4 (only meaningful with `COMPRESSED`)
+    pub const ZSTD: u16 = 0b0000_0000_0000_0010;
+    /// The signature covers the Merkle root in the header instead of the payload
+    pub const CHUNKED: u16 = 0b0000_0000_0000_0100;
+    /// The payload is stored elsewhere and bound by the content digest in the header
+    pub const DETACHED: u16 = 0b0000_0000_0000_1000;
 
     pub fn new() -> Self {
         Self(0)
@@ -26,10 +49,48 @@ impl Flags {
         self
     }
 
+    #[cfg(feature = "compression")]
+    pub fn with_compression_algorithm(mut self, algorithm: CompressionAlgorithm) -> Self {
+        self.0 |= Self::COMPRESSED;
+        if let CompressionAlgorithm::Zstd { .. } = algorithm {
+            self.0 |= Self::ZSTD;
+        }
+        self
+    }
+
     pub fn is_compressed(&self) -> bool {
         self.0 & Self::COMPRESSED != 0
     }
 
+    pub fn with_chunking(mut self) -> Self {
+        self.0 |= Self::CHUNKED;
+        self
+    }
+
+    pub fn is_chunked(&self) -> bool {
+        self.0 & Self::CHUNKED != 0
+    }
+
+    pub fn with_detached(mut self) -> Self {
+        self.0 |= Self::DETACHED;
+        self
+    }
+
+    pub fn is_detached(&self) -> bool {
+        self.0 & Self::DETACHED != 0
+    }
+
+    /// The algorithm the payload is compressed with, if any
+    pub fn compression_algorithm(&self) -> Option<CompressionAlgorithm> {
+        if !self.is_compressed() {
+            None
+        } else if self.0 & Self::ZSTD != 0 {
+            Some(CompressionAlgorithm::Zstd { level: 0 })
+        } else {
+            Some(CompressionAlgorithm::Lz4)
+        }
+    }
+
     pub fn to_bytes(&self) -> [u8; 2] {
         self.0.to_le_bytes()
     }
@@ -39,6 +100,9 @@ impl Flags {
     }
 }
 
+/// Maximum CBOR-encoded size of one typed custom metadata value
+pub const MAX_CUSTOM_VALUE_SIZE: usize = 16 * 1024;
+
 /// Header metadata for an Aletheia file
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Header {
@@ -60,11 +124,222 @@ pub struct Header {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
 
+    /// SHA-256 of the original (uncompressed) payload (optional)
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "serde_base64::option"
+    )]
+    pub content_digest: Option<Vec<u8>>,
+
+    /// Chunk layout and Merkle root of a chunked payload (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chunks: Option<ChunkInfo>,
+
+    /// Source works this content was derived from (optional)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub derived_from: Vec<ContentRef>,
+
+    /// Signed claims about how the content was created (optional)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub assertions: Vec<Assertion>,
+
     /// Application-specific custom metadata (optional)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub custom: Option<BTreeMap<String, serde_cbor_value::Value>>,
 }
 
+/// Layout of a chunked payload
+///
+/// See [`crate::merkle`] for how the root is computed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkInfo {
+    /// Size of every chunk except possibly the last
+    pub chunk_size: u32,
+
+    /// Total payload length in bytes
+    pub total_len: u64,
+
+    /// Merkle root over the chunks (32 bytes)
+    #[serde(with = "serde_base64")]
+    pub merkle_root: Vec<u8>,
+}
+
+impl ChunkInfo {
+    /// Number of chunks in the payload
+    pub fn chunk_count(&self) -> u64 {
+        crate::merkle::chunk_count(self.total_len, self.chunk_size)
+    }
+}
+
+/// Reference to a source work, identified by its content
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContentRef {
+    /// SHA-256 of the source content (32 bytes)
+    #[serde(with = "serde_base64")]
+    pub content_digest: Vec<u8>,
+
+    /// Primary signature of the source's `.alx` file (optional)
+    ///
+    /// Pins one signed version of the source when the same content was
+    /// signed more than once.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "serde_base64::option"
+    )]
+    pub signature: Option<Vec<u8>>,
+}
+
+impl ContentRef {
+    /// Reference `content` by its SHA-256 digest
+    pub fn new(content: &[u8]) -> Self {
+        use sha2::{Digest, Sha256};
+        Self {
+            content_digest: Sha256::digest(content).to_vec(),
+            signature: None,
+        }
+    }
+
+    /// Reference the content of a signed file, pinned to its signature
+    pub fn from_file(file: &AletheiaFile) -> Result<Self> {
+        Ok(Self {
+            content_digest: file.content_digest()?,
+            signature: Some(file.signature.clone()),
+        })
+    }
+
+    /// Whether `file` is the source this reference points to
+    pub fn matches(&self, file: &AletheiaFile) -> bool {
+        self.signature
+            .as_ref()
+            .is_none_or(|signature| *signature == file.signature)
+            && file
+                .content_digest()
+                .is_ok_and(|digest| crate::ct::eq(&digest, &self.content_digest))
+    }
+}
+
+/// A signed claim about how the content was created
+///
+/// Assertions live in the header, so they are covered by the primary
+/// signature and only reported once it verifies. They record what the signer
+/// claims; device attestations are carried as evidence but not checked.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Assertion {
+    /// Kind of tool or device the content was made with (e.g. `camera`)
+    CreatedWith(String),
+    /// Whether AI tools were used in creating the content
+    AiAssisted(bool),
+    /// Attestation produced by the capture device
+    DeviceAttestation {
+        /// Format of `data` (e.g. `android-key-attestation`)
+        format: String,
+        /// The attestation blob, in `format`
+        #[serde(with = "serde_base64")]
+        data: Vec<u8>,
+    },
+    /// Application-defined claim, with a namespaced label (e.g. `com.example:rig`)
+    Other {
+        label: String,
+        value: serde_cbor_value::Value,
+    },
+}
+
+/// Byte fields as CBOR byte strings, or as base64 strings in human-readable
+/// formats such as JSON
+pub(crate) mod serde_base64 {
+    extern crate alloc;
+
+    use alloc::string::String;
+    use alloc::vec::Vec;
+    use base64::{Engine, engine::general_purpose::STANDARD};
+    use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&STANDARD.encode(bytes))
+        } else {
+            serializer.serialize_bytes(bytes)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        if deserializer.is_human_readable() {
+            let encoded = String::deserialize(deserializer)?;
+            STANDARD.decode(encoded).map_err(D::Error::custom)
+        } else {
+            serde_bytes::deserialize(deserializer)
+        }
+    }
+
+    /// Same for optional byte fields
+    pub mod option {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(
+            bytes: &Option<Vec<u8>>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            match bytes {
+                Some(bytes) if serializer.is_human_readable() => {
+                    serializer.serialize_some(&STANDARD.encode(bytes))
+                }
+                _ => serde_bytes::serialize(bytes, serializer),
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<Vec<u8>>, D::Error> {
+            if deserializer.is_human_readable() {
+                Option::<String>::deserialize(deserializer)?
+                    .map(|encoded| STANDARD.decode(encoded).map_err(D::Error::custom))
+                    .transpose()
+            } else {
+                serde_bytes::deserialize(deserializer)
+            }
+        }
+    }
+
+    /// Same for lists of byte strings
+    pub mod list {
+        use super::*;
+        use serde::Serialize;
+        use serde_bytes::ByteBuf;
+
+        pub fn serialize<S: Serializer>(
+            list: &[ByteBuf],
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            if serializer.is_human_readable() {
+                serializer.collect_seq(list.iter().map(|bytes| STANDARD.encode(bytes)))
+            } else {
+                list.serialize(serializer)
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Vec<ByteBuf>, D::Error> {
+            if deserializer.is_human_readable() {
+                Vec::<String>::deserialize(deserializer)?
+                    .into_iter()
+                    .map(|encoded| {
+                        STANDARD
+                            .decode(encoded)
+                            .map(ByteBuf::from)
+                            .map_err(D::Error::custom)
+                    })
+                    .collect()
+            } else {
+                Vec::deserialize(deserializer)
+            }
+        }
+    }
+}
+
 /// Workaround for custom CBOR values in the header
 pub mod serde_cbor_value {
     extern crate alloc;
@@ -96,6 +371,10 @@ impl Header {
             creator_id: creator_id.into(),
             original_name: None,
             description: None,
+            content_digest: None,
+            chunks: None,
+            derived_from: Vec::new(),
+            assertions: Vec::new(),
             custom: None,
         }
     }
@@ -108,6 +387,10 @@ impl Header {
             creator_id: creator_id.into(),
             original_name: None,
             description: None,
+            content_digest: None,
+            chunks: None,
+            derived_from: Vec::new(),
+            assertions: Vec::new(),
             custom: None,
         }
     }
@@ -126,6 +409,103 @@ impl Header {
         self.description = Some(description.into());
         self
     }
+
+    /// Record the SHA-256 digest of the original (uncompressed) payload
+    pub fn with_content_digest(mut self, payload: &[u8]) -> Self {
+        use sha2::{Digest, Sha256};
+        self.content_digest = Some(Sha256::digest(payload).to_vec());
+        self
+    }
+
+    /// Record that the content was derived from the work `source`
+    pub fn with_derived_from(mut self, source: ContentRef) -> Self {
+        self.derived_from.push(source);
+        self
+    }
+
+    /// Add a signed claim about how the content was created
+    pub fn with_assertion(mut self, assertion: Assertion) -> Self {
+        self.assertions.push(assertion);
+        self
+    }
+
+    /// Compare `payload` against the recorded content digest
+    ///
+    /// Returns `None` if the header carries no digest.
+    pub fn matches_content(&self, payload: &[u8]) -> Option<bool> {
+        use sha2::{Digest, Sha256};
+        let digest = self.content_digest.as_ref()?;
+        Some(crate::ct::eq(&Sha256::digest(payload), digest))
+    }
+
+    /// Store a structured value in the custom metadata
+    ///
+    /// Keys are namespaced as `namespace:name` (e.g. `com.example:camera`),
+    /// so that metadata from different applications cannot collide. The value
+    /// is stored as its CBOR encoding, which may be at most
+    /// [`MAX_CUSTOM_VALUE_SIZE`] bytes.
+    pub fn with_custom_typed<T: Serialize>(
+        mut self,
+        key: impl Into<String>,
+        value: &T,
+    ) -> Result<Self> {
+        let key = key.into();
+        validate_custom_key(&key)?;
+
+        let mut encoded = Vec::new();
+        ciborium::into_writer(value, &mut encoded)
+            .map_err(|e| AletheiaError::CborEncode(e.to_string()))?;
+        if encoded.len() > MAX_CUSTOM_VALUE_SIZE {
+            return Err(AletheiaError::InvalidHeader(alloc::format!(
+                "Custom metadata '{key}' is {} bytes (limit {MAX_CUSTOM_VALUE_SIZE})",
+                encoded.len()
+            )));
+        }
+
+        self.custom
+            .get_or_insert_with(BTreeMap::new)
+            .insert(key, serde_cbor_value::Value::Bytes(encoded));
+        Ok(self)
+    }
+
+    /// Read a value stored with [`Header::with_custom_typed`]
+    ///
+    /// Returns `None` if the key is absent, and an error if the entry was not
+    /// stored as a typed value or does not decode as `T`.
+    pub fn get_custom_typed<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
+        let Some(value) = self.custom.as_ref().and_then(|custom| custom.get(key)) else {
+            return Ok(None);
+        };
+        let serde_cbor_value::Value::Bytes(encoded) = value else {
+            return Err(AletheiaError::InvalidHeader(alloc::format!(
+                "Custom metadata '{key}' is not a typed value"
+            )));
+        };
+        if encoded.len() > MAX_CUSTOM_VALUE_SIZE {
+            return Err(AletheiaError::InvalidHeader(alloc::format!(
+                "Custom metadata '{key}' exceeds {MAX_CUSTOM_VALUE_SIZE} bytes"
+            )));
+        }
+        ciborium::from_reader(encoded.as_slice())
+            .map(Some)
+            .map_err(|e| AletheiaError::CborDecode(e.to_string()))
+    }
+}
+
+/// Check that a typed custom metadata key has the form `namespace:name`
+fn validate_custom_key(key: &str) -> Result<()> {
+    let valid_part = |part: &str| {
+        !part.is_empty()
+            && part
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'))
+    };
+    match key.split_once(':') {
+        Some((namespace, name)) if valid_part(namespace) && valid_part(name) => Ok(()),
+        _ => Err(AletheiaError::InvalidHeader(alloc::format!(
+            "Custom metadata key '{key}' is not of the form 'namespace:name'"
+        ))),
+    }
 }
 
 /// A certificate that attests to a subject's identity
@@ -135,7 +515,7 @@ pub struct Certificate {
     pub version: u8,
 
     /// Unique serial number
-    #[serde(with = "serde_bytes")]
+    #[serde(with = "serde_base64")]
     pub serial: Vec<u8>,
 
     /// Identity of the certificate holder (e.g., email)
@@ -145,7 +525,7 @@ pub struct Certificate {
     pub subject_name: String,
 
     /// Ed25519 public key (32 bytes)
-    #[serde(with = "serde_bytes")]
+    #[serde(with = "serde_base64")]
     pub public_key: Vec<u8>,
 
     /// Identity of the issuing CA
@@ -157,13 +537,77 @@ pub struct Certificate {
     /// Whether this certificate can issue other certificates
     pub is_ca: bool,
 
+    /// Maximum number of intermediate CAs that may follow this CA in a chain
+    /// (optional, `None` = unlimited)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path_len: Option<u32>,
+
+    /// Unix timestamp before which the certificate is not valid (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub not_before: Option<i64>,
+
+    /// Unix timestamp after which the certificate is no longer valid (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub not_after: Option<i64>,
+
+    /// The key signs a single file and is then discarded
+    ///
+    /// Distinguishes per-file ephemeral keys from long-lived identity keys.
+    #[serde(default, skip_serializing_if = "core::ops::Not::not")]
+    pub single_use: bool,
+
+    /// Deployment-specific extensions (key usage, jurisdiction, etc.)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extensions: BTreeMap<String, serde_cbor_value::Value>,
+
+    /// How the issuer verified the subject's identity (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity_attestation: Option<IdentityAttestation>,
+
+    /// Identity this certificate's subject acts on behalf of (optional)
+    ///
+    /// Set on sub-identities: per-device or per-project keys derived from the
+    /// parent identity's key and certified on its behalf.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<String>,
+
+    /// Serial of the certificate this one renews (optional)
+    ///
+    /// Links a renewed certificate to its predecessor for the same subject,
+    /// so content signed before the renewal stays attributable.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "serde_base64::option"
+    )]
+    pub predecessor_serial: Option<Vec<u8>>,
+
+    /// ML-DSA-65 public key of the holder, for hybrid post-quantum
+    /// signatures (optional, see [`crate::certificate::SignaturePolicy`])
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "serde_base64::option"
+    )]
+    pub pq_public_key: Option<Vec<u8>>,
+
     /// Ed25519 signature by the issuer (64 bytes)
-    #[serde(with = "serde_bytes")]
+    #[serde(with = "serde_base64")]
     pub signature: Vec<u8>,
+
+    /// ML-DSA-65 signature by the issuer over the same data as `signature`
+    /// (optional)
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "serde_base64::option"
+    )]
+    pub pq_signature: Option<Vec<u8>>,
 }
 
 impl Certificate {
-    /// Get the data that is signed by the issuer (everything except the signature)
+    /// Get the data that is signed by the issuer (everything except the
+    /// signatures)
     pub fn signable_data(&self) -> Vec<u8> {
         let unsigned = UnsignedCertificate {
             version: self.version,
@@ -174,57 +618,584 @@ impl Certificate {
             issuer_id: self.issuer_id.clone(),
             issued_at: self.issued_at,
             is_ca: self.is_ca,
+            path_len: self.path_len,
+            not_before: self.not_before,
+            not_after: self.not_after,
+            single_use: self.single_use,
+            extensions: self.extensions.clone(),
+            identity_attestation: self.identity_attestation.clone(),
+            parent_id: self.parent_id.clone(),
+            predecessor_serial: self.predecessor_serial.clone(),
+            pq_public_key: self.pq_public_key.clone(),
         };
         let mut data = Vec::new();
         ciborium::into_writer(&unsigned, &mut data).expect("CBOR encoding failed");
         data
     }
+
+    /// Whether `timestamp` falls inside the certificate's validity period
+    ///
+    /// Certificates without `not_before`/`not_after` are valid at any time.
+    pub fn is_valid_at(&self, timestamp: i64) -> bool {
+        self.not_before.is_none_or(|nb| timestamp >= nb)
+            && self.not_after.is_none_or(|na| timestamp <= na)
+    }
+
+    /// Look up an extension by name
+    pub fn extension(&self, name: &str) -> Option<&serde_cbor_value::Value> {
+        self.extensions.get(name)
+    }
+
+    /// SHA-256 of the certificate's CBOR encoding, signature included
+    ///
+    /// This is the encoding the certificate has in a chain, so two parties
+    /// holding the same certificate always compute the same fingerprint.
+    pub fn fingerprint(&self) -> Fingerprint {
+        use sha2::{Digest, Sha256};
+        let mut data = Vec::new();
+        ciborium::into_writer(self, &mut data).expect("CBOR encoding failed");
+        Fingerprint(Sha256::digest(&data).into())
+    }
+
+    /// Encode the certificate as PEM (`ALETHEIA CERTIFICATE` around its CBOR)
+    #[cfg(feature = "pkcs8")]
+    pub fn to_pem(&self) -> String {
+        let mut data = Vec::new();
+        ciborium::into_writer(self, &mut data).expect("CBOR encoding failed");
+        pkcs8::der::pem::encode_string(CERTIFICATE_PEM_LABEL, pkcs8::LineEnding::LF, &data)
+            .expect("PEM encoding failed")
+    }
+
+    /// Decode a certificate written by [`Self::to_pem`]
+    #[cfg(feature = "pkcs8")]
+    pub fn from_pem(pem: &str) -> Result<Self> {
+        use alloc::string::ToString;
+        let (label, data) = pkcs8::der::pem::decode_vec(pem.as_bytes())
+            .map_err(|e| AletheiaError::InvalidCertificate(e.to_string()))?;
+        if label != CERTIFICATE_PEM_LABEL {
+            return Err(AletheiaError::InvalidCertificate(alloc::format!(
+                "Unexpected PEM label '{label}'"
+            )));
+        }
+        ciborium::from_reader(data.as_slice()).map_err(|e| AletheiaError::CborDecode(e.to_string()))
+    }
+}
+
+/// PEM label of an encoded [`Certificate`]
+#[cfg(feature = "pkcs8")]
+pub const CERTIFICATE_PEM_LABEL: &str = "ALETHEIA CERTIFICATE";
+
+/// How a certificate subject's identity was verified before issuance
+///
+/// Carried in the certificate and covered by the issuer's signature. The
+/// evidence itself stays with the verifier; the certificate only commits to
+/// it by hash.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IdentityAttestation {
+    /// Verification method
+    pub method: AttestationMethod,
+
+    /// Organization that performed the verification
+    pub verifier: String,
+
+    /// Unix timestamp of the verification
+    pub attested_at: i64,
+
+    /// SHA-256 of the evidence retained by the verifier (optional)
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "serde_base64::option"
+    )]
+    pub evidence_hash: Option<Vec<u8>>,
+}
+
+impl IdentityAttestation {
+    pub fn new(method: AttestationMethod, verifier: impl Into<String>, attested_at: i64) -> Self {
+        Self {
+            method,
+            verifier: verifier.into(),
+            attested_at,
+            evidence_hash: None,
+        }
+    }
+
+    /// Commit to the evidence (e.g. a verification record) by its SHA-256
+    pub fn with_evidence(mut self, evidence: &[u8]) -> Self {
+        use sha2::{Digest, Sha256};
+        self.evidence_hash = Some(Sha256::digest(evidence).to_vec());
+        self
+    }
+}
+
+/// Method by which a subject's identity was verified
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AttestationMethod {
+    /// Control of the email address was confirmed
+    Email,
+    /// A government-issued identity document was checked
+    GovId,
+    /// The subject authenticated with a registered WebAuthn credential
+    #[serde(rename = "webauthn")]
+    WebAuthn,
+    /// Another method, by name
+    Other(String),
+}
+
+impl From<&str> for AttestationMethod {
+    /// Parse `email`, `gov-id` or `webauthn`; anything else is [`Self::Other`]
+    fn from(name: &str) -> Self {
+        match name {
+            "email" => Self::Email,
+            "gov-id" | "gov_id" => Self::GovId,
+            "webauthn" => Self::WebAuthn,
+            other => Self::Other(other.into()),
+        }
+    }
+}
+
+impl core::fmt::Display for AttestationMethod {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Email => f.write_str("email"),
+            Self::GovId => f.write_str("gov-id"),
+            Self::WebAuthn => f.write_str("webauthn"),
+            Self::Other(name) => f.write_str(name),
+        }
+    }
+}
+
+/// Stable identifier of a certificate (see [`Certificate::fingerprint`])
+///
+/// Displays as lowercase hex.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Fingerprint(pub [u8; 32]);
+
+impl Fingerprint {
+    /// Lowercase hex rendering (64 characters)
+    pub fn to_hex(&self) -> String {
+        use core::fmt::Write;
+        let mut out = String::with_capacity(64);
+        for byte in self.0 {
+            let _ = write!(out, "{byte:02x}");
+        }
+        out
+    }
+
+    /// Lowercase RFC 4648 base32 rendering without padding (52 characters)
+    ///
+    /// Shorter than hex and case-insensitive, for reading out or typing.
+    pub fn to_base32(&self) -> String {
+        const ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+        let mut out = String::with_capacity(52);
+        let mut buffer = 0u16;
+        let mut bits = 0;
+        for byte in self.0 {
+            buffer = (buffer << 8) | u16::from(byte);
+            bits += 8;
+            while bits >= 5 {
+                bits -= 5;
+                out.push(ALPHABET[usize::from((buffer >> bits) & 0x1F)] as char);
+            }
+        }
+        if bits > 0 {
+            out.push(ALPHABET[usize::from((buffer << (5 - bits)) & 0x1F)] as char);
+        }
+        out
+    }
+}
+
+impl core::fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(&self.to_hex())
+    }
 }
 
 /// Certificate data without signature (used for signing)
 #[derive(Serialize)]
 struct UnsignedCertificate {
     version: u8,
-    #[serde(with = "serde_bytes")]
+    #[serde(with = "serde_base64")]
     serial: Vec<u8>,
     subject_id: String,
     subject_name: String,
-    #[serde(with = "serde_bytes")]
+    #[serde(with = "serde_base64")]
     public_key: Vec<u8>,
     issuer_id: String,
     issued_at: i64,
     is_ca: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path_len: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    not_before: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    not_after: Option<i64>,
+    #[serde(skip_serializing_if = "core::ops::Not::not")]
+    single_use: bool,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    extensions: BTreeMap<String, serde_cbor_value::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    identity_attestation: Option<IdentityAttestation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parent_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", with = "serde_base64::option")]
+    predecessor_serial: Option<Vec<u8>>,
+    #[serde(skip_serializing_if = "Option::is_none", with = "serde_base64::option")]
+    pq_public_key: Option<Vec<u8>>,
+}
+
+/// An additional, independent signature over the same content
+///
+/// Co-signers (e.g. an editor or publisher) sign exactly what the primary
+/// signer signed, but with their own certificate chain in place of the
+/// primary chain.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoSignature {
+    /// Certificate chain of the co-signer: [co_signer_cert, ..., root_cert]
+    pub certificate_chain: Vec<Certificate>,
+
+    /// What the co-signer vouches for (e.g. "reviewed", "published"),
+    /// covered by the signature
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+
+    /// Ed25519 signature by the co-signer (64 bytes)
+    #[serde(with = "serde_base64")]
+    pub signature: Vec<u8>,
+}
+
+/// A signature by a third party over the primary signature
+///
+/// Notaries and timestamp services countersign to attest that the primary
+/// signature existed at `signed_at`. Because only the primary signature is
+/// covered, countersignatures can be added without touching the content.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Countersignature {
+    /// Certificate chain of the countersigner: [countersigner_cert, ..., root_cert]
+    pub certificate_chain: Vec<Certificate>,
+
+    /// Unix timestamp when the countersignature was made
+    pub signed_at: i64,
+
+    /// Ed25519 signature by the countersigner (64 bytes)
+    #[serde(with = "serde_base64")]
+    pub signature: Vec<u8>,
+}
+
+/// An RFC 3161 timestamp token over the primary signature
+///
+/// A timestamp authority (TSA) signs the SHA-256 of the primary signature
+/// together with its own clock reading, proving the signature existed at that
+/// time. Like countersignatures, tokens are stored in the unsigned trailer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimestampToken {
+    /// DER-encoded TimeStampToken (a CMS SignedData) as issued by the TSA
+    #[serde(with = "serde_base64")]
+    pub token: Vec<u8>,
+}
+
+/// Proof that the primary signature was recorded in a transparency log
+///
+/// The log is an append-only Merkle tree (same construction as chunked
+/// payloads). The entry proves inclusion of the signature, together with the
+/// time the log integrated it, in a tree head signed by the log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogEntry {
+    /// SHA-256 of the log's Ed25519 public key
+    #[serde(with = "serde_base64")]
+    pub log_id: Vec<u8>,
+
+    /// Unix timestamp when the log integrated the entry
+    pub integrated_time: i64,
+
+    /// Position of the entry in the log
+    pub index: u64,
+
+    /// Number of entries in the tree the proof is against
+    pub tree_size: u64,
+
+    /// Root hash of the tree at `tree_size` (32 bytes)
+    #[serde(with = "serde_base64")]
+    pub root_hash: Vec<u8>,
+
+    /// Sibling hashes from the entry's leaf up to the root
+    #[serde(with = "serde_base64::list")]
+    pub inclusion_proof: Vec<serde_bytes::ByteBuf>,
+
+    /// Ed25519 signature by the log over the tree head (64 bytes)
+    #[serde(with = "serde_base64")]
+    pub checkpoint_signature: Vec<u8>,
+}
+
+/// An OpenTimestamps proof of the header's content digest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenTimestampsProof {
+    /// Complete `.ots` file, as produced by the OpenTimestamps client
+    #[serde(with = "serde_base64")]
+    pub ots: Vec<u8>,
 }
 
 /// A complete Aletheia file structure
-#[derive(Debug, Clone)]
+///
+/// With serde, the file maps to a document with one field per section; in
+/// human-readable formats such as JSON, byte fields are base64 strings. This
+/// representation is for storage and APIs: the signature is checked over the
+/// binary encoding, which [`crate::file::to_bytes`] reproduces from it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AletheiaFile {
     pub version_major: u8,
     pub version_minor: u8,
     pub flags: Flags,
     pub header: Header,
+    #[serde(with = "serde_base64")]
     pub payload: Vec<u8>,
     pub certificate_chain: Vec<Certificate>,
+    #[serde(with = "serde_base64")]
     pub signature: Vec<u8>,
+    /// ML-DSA-65 signature by the signer over the prehash of the signed data
+    /// (optional, see [`crate::certificate::SignaturePolicy`])
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "serde_base64::option"
+    )]
+    pub pq_signature: Option<Vec<u8>>,
+    /// Signatures by additional signers, stored after the primary signature
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub co_signatures: Vec<CoSignature>,
+    /// Third-party signatures over the primary signature
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub countersignatures: Vec<Countersignature>,
+    /// RFC 3161 timestamp tokens over the primary signature
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub timestamp_tokens: Vec<TimestampToken>,
+    /// Transparency log inclusion proofs for the primary signature
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub log_entries: Vec<LogEntry>,
+    /// OpenTimestamps proofs of the content digest
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub opentimestamps: Vec<OpenTimestampsProof>,
+    /// Status assertions for the certificates of the file, stapled by the signer
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub status_assertions: Vec<crate::revocation::StatusAssertion>,
+}
+
+/// Undo the compression recorded in `flags`
+pub(crate) fn decompress_payload(flags: Flags, payload: &[u8]) -> crate::Result<Vec<u8>> {
+    decompress_payload_with_limits(flags, payload, &crate::file::ParseLimits::default())
+}
+
+/// Decompress a payload, refusing output beyond what `limits` allow
+pub(crate) fn decompress_payload_with_limits(
+    flags: Flags,
+    payload: &[u8],
+    limits: &crate::file::ParseLimits,
+) -> crate::Result<Vec<u8>> {
+    match flags.compression_algorithm() {
+        #[cfg(feature = "compression")]
+        Some(algorithm) => crate::compression::decompress(
+            algorithm,
+            payload,
+            limits.max_decompressed_len(payload.len()),
+        ),
+        #[cfg(not(feature = "compression"))]
+        Some(_) => {
+            let _ = limits;
+            Err(crate::AletheiaError::Decompression(
+                "Compression feature not enabled".into(),
+            ))
+        }
+        None => Ok(payload.to_vec()),
+    }
 }
 
 impl AletheiaFile {
     /// Get the original (decompressed) payload
+    ///
+    /// Decompression is bounded by the default [`ParseLimits`](crate::file::ParseLimits).
     pub fn get_payload(&self) -> crate::Result<Vec<u8>> {
-        if self.flags.is_compressed() {
-            #[cfg(feature = "compression")]
-            {
-                lz4_flex::decompress_size_prepended(&self.payload)
-                    .map_err(|e| crate::AletheiaError::Decompression(alloc::format!("{}", e)))
+        decompress_payload(self.flags, &self.payload)
+    }
+
+    /// SHA-256 of the original payload, computed from the payload itself
+    ///
+    /// Unlike [`AletheiaFile::content_digest`], the header is not consulted.
+    pub fn payload_digest(&self) -> crate::Result<[u8; 32]> {
+        use sha2::{Digest, Sha256};
+        if self.flags.is_detached() {
+            return Err(crate::AletheiaError::DetachedPayload);
+        }
+        Ok(Sha256::digest(self.get_payload()?).into())
+    }
+
+    /// Check the payload before handing it to callers, with the default
+    /// [`ParseLimits`](crate::file::ParseLimits)
+    ///
+    /// See [`AletheiaFile::integrity_check_with_limits`].
+    pub fn integrity_check(&self) -> crate::Result<()> {
+        self.integrity_check_with_limits(&crate::file::ParseLimits::default())
+    }
+
+    /// Check the payload's sizes and content digest against `limits`
+    ///
+    /// The stored payload must fit the payload limit, and a compressed
+    /// payload must decompress within both the payload limit and the maximum
+    /// expansion ratio, so decompression bombs are refused before their
+    /// output is allocated. The decompressed payload must match the header's
+    /// content digest if it records one. Signatures are not checked.
+    pub fn integrity_check_with_limits(
+        &self,
+        limits: &crate::file::ParseLimits,
+    ) -> crate::Result<()> {
+        use sha2::{Digest, Sha256};
+        let stored_len = self.payload.len() as u64;
+        if stored_len > limits.max_payload_len {
+            return Err(crate::AletheiaError::SectionTooLarge {
+                section: "payload",
+                len: stored_len,
+                limit: limits.max_payload_len,
+            });
+        }
+        if self.flags.is_detached() {
+            return Ok(());
+        }
+
+        let payload = decompress_payload_with_limits(self.flags, &self.payload, limits)?;
+        if self
+            .header
+            .content_digest
+            .as_ref()
+            .is_some_and(|digest| !crate::ct::eq(&Sha256::digest(&payload), digest))
+        {
+            return Err(crate::AletheiaError::ContentDigestMismatch);
+        }
+        Ok(())
+    }
+
+    /// SHA-256 of the original content
+    ///
+    /// Taken from the header when recorded there, otherwise computed from the
+    /// payload. Detached manifests without a digest have no content to hash.
+    pub fn content_digest(&self) -> crate::Result<Vec<u8>> {
+        use sha2::{Digest, Sha256};
+        match &self.header.content_digest {
+            Some(digest) => Ok(digest.clone()),
+            None if self.flags.is_detached() => Err(crate::AletheiaError::DetachedPayload),
+            None => Ok(Sha256::digest(self.get_payload()?).to_vec()),
+        }
+    }
+
+    /// Get chunk `index` of a chunked payload with its Merkle inclusion proof
+    ///
+    /// The chunk and proof can be checked with [`crate::verifier::verify_chunk`]
+    /// by a party that does not have the rest of the payload.
+    pub fn chunk_with_proof(&self, index: u64) -> crate::Result<(&[u8], Vec<crate::merkle::Hash>)> {
+        let chunks = match &self.header.chunks {
+            Some(chunks) if self.flags.is_chunked() && chunks.chunk_size > 0 => chunks,
+            _ => {
+                return Err(crate::AletheiaError::ChunkVerification(
+                    "File is not chunked".into(),
+                ));
             }
-            #[cfg(not(feature = "compression"))]
-            {
-                Err(crate::AletheiaError::Decompression(
-                    "Compression feature not enabled".into(),
+        };
+        let proof = crate::merkle::merkle_proof(&self.payload, chunks.chunk_size, index)
+            .ok_or_else(|| {
+                crate::AletheiaError::ChunkVerification(alloc::format!(
+                    "Chunk index {} out of range",
+                    index
                 ))
-            }
-        } else {
-            Ok(self.payload.clone())
+            })?;
+
+        let start = (index * chunks.chunk_size as u64) as usize;
+        let end = (start + chunks.chunk_size as usize).min(self.payload.len());
+        Ok((&self.payload[start..end], proof))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Camera {
+        make: String,
+        exposure: (u32, u32),
+        iso: Option<u32>,
+    }
+
+    #[test]
+    fn test_custom_typed_round_trip() {
+        let camera = Camera {
+            make: "Fujifilm".into(),
+            exposure: (1, 250),
+            iso: Some(400),
+        };
+        let header = Header::new_with_timestamp("alice@example.com", 1704067200)
+            .with_custom_typed("com.example:camera", &camera)
+            .unwrap();
+
+        // Survives the header's own CBOR encoding
+        let mut encoded = Vec::new();
+        ciborium::into_writer(&header, &mut encoded).unwrap();
+        let decoded: Header = ciborium::from_reader(encoded.as_slice()).unwrap();
+        assert_eq!(
+            decoded
+                .get_custom_typed::<Camera>("com.example:camera")
+                .unwrap(),
+            Some(camera)
+        );
+        assert!(
+            decoded
+                .get_custom_typed::<Camera>("com.example:lens")
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            decoded
+                .get_custom_typed::<u32>("com.example:camera")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_custom_typed_limits() {
+        let header = Header::new_with_timestamp("alice@example.com", 1704067200);
+        for key in ["camera", ":camera", "com.example:", "com example:camera"] {
+            assert!(header.clone().with_custom_typed(key, &1u32).is_err());
         }
+
+        let oversized = vec![0u8; MAX_CUSTOM_VALUE_SIZE];
+        assert!(matches!(
+            header.with_custom_typed("com.example:blob", &oversized),
+            Err(AletheiaError::InvalidHeader(_))
+        ));
+    }
+
+    #[test]
+    fn test_certificate_fingerprint() {
+        let ca = crate::ca::CertificateAuthority::new_root_with_timestamp(
+            "root@example.com",
+            "Root CA",
+            1704067200,
+        );
+        let fingerprint = ca.certificate.fingerprint();
+
+        // Stable across encoding, but covers the signature
+        let mut encoded = Vec::new();
+        ciborium::into_writer(&ca.certificate, &mut encoded).unwrap();
+        let decoded: Certificate = ciborium::from_reader(encoded.as_slice()).unwrap();
+        assert_eq!(decoded.fingerprint(), fingerprint);
+        let mut tampered = decoded;
+        tampered.signature[0] ^= 0xFF;
+        assert_ne!(tampered.fingerprint(), fingerprint);
+
+        let mut bytes = [0u8; 32];
+        bytes[..6].copy_from_slice(b"foobar");
+        let fingerprint = Fingerprint(bytes);
+        assert_eq!(fingerprint.to_hex().len(), 64);
+        assert!(fingerprint.to_string().starts_with("666f6f626172"));
+        assert_eq!(fingerprint.to_base32().len(), 52);
+        assert!(fingerprint.to_base32().starts_with("mzxw6ytboi"));
     }
 }
@@ -1,9 +1,263 @@
+extern crate alloc;
+
 use crate::{
-    AletheiaError, AletheiaFile, Result, certificate::verify_certificate_chain,
-    signer::build_signature_input,
+    AletheiaError, AletheiaFile, Assertion, Certificate, ChunkInfo, CoSignature, Countersignature,
+    Fingerprint, Flags, Header, IdentityAttestation, LogEntry, OpenTimestampsProof, Result,
+    TimestampToken, VERSION_MAJOR, VERSION_MINOR,
+    certificate::{
+        DEFAULT_MAX_CHAIN_LEN, SignaturePolicy, ValidityCheck, check_validity,
+        find_trusted_path_with_policy, verify_certificate_signature, verify_ml_dsa,
+    },
+    ct,
+    file::AletheiaFileRef,
+    merkle,
+    revocation::{
+        FailurePolicy, RevocationChecker, RevocationList, StatusAssertion, check_chain_status,
+        serial_hex,
+    },
+    signer::{SignatureInput, build_countersignature_input, co_signer_chain_bytes},
+    trust_bundle::TrustBundle,
+    types::decompress_payload,
 };
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
 use ed25519_dalek::{Signature, Verifier, VerifyingKey};
 
+/// How long before a certificate expires verification warns about it by
+/// default (30 days)
+const DEFAULT_EXPIRY_WARNING: i64 = 30 * 86400;
+
+/// Options controlling how a file is verified
+#[derive(Debug, Clone)]
+pub struct VerifyOptions {
+    /// How certificate validity periods are checked
+    pub validity: ValidityCheck,
+
+    /// Revocation lists every certificate chain is checked against
+    pub revocation_lists: Vec<RevocationList>,
+
+    /// DER-encoded X.509 root certificates of trusted timestamp authorities
+    pub tsa_roots: Vec<Vec<u8>>,
+
+    /// Ed25519 public keys of trusted transparency logs
+    pub log_keys: Vec<Vec<u8>>,
+
+    /// Fail unless the primary signature is proven included in a trusted log
+    pub require_log_inclusion: bool,
+
+    /// Merkle roots of known Bitcoin blocks by height, for OpenTimestamps proofs
+    pub bitcoin_blocks: BTreeMap<u64, [u8; 32]>,
+
+    /// Cross-certificates from trusted roots that vouch for other root keys
+    pub cross_certificates: Vec<Certificate>,
+
+    /// Status assertions checked along with those stapled to the file
+    pub status_assertions: Vec<StatusAssertion>,
+
+    /// Fail unless every certificate below a root has a current "good"
+    /// status assertion
+    pub require_status: bool,
+
+    /// Renewals of signers' certificates, to recognize earlier certificates
+    /// of the same identity
+    pub renewed_certificates: Vec<Certificate>,
+
+    /// Custom header keys the application understands; others are reported
+    /// as warnings
+    pub known_custom_keys: Vec<String>,
+
+    /// Warn about certificates expiring within this many seconds of the
+    /// verification time
+    pub expiry_warning: i64,
+
+    /// Warnings that fail verification instead of being reported
+    pub escalated_warnings: Vec<WarningKind>,
+
+    /// Longest certificate chain accepted
+    pub max_chain_len: usize,
+
+    /// Which signatures certificates and the primary signature must carry
+    pub signature_policy: SignaturePolicy,
+}
+
+impl Default for VerifyOptions {
+    /// Check certificate validity periods at the current time
+    fn default() -> Self {
+        Self {
+            validity: ValidityCheck::now(),
+            revocation_lists: Vec::new(),
+            tsa_roots: Vec::new(),
+            log_keys: Vec::new(),
+            require_log_inclusion: false,
+            bitcoin_blocks: BTreeMap::new(),
+            cross_certificates: Vec::new(),
+            status_assertions: Vec::new(),
+            require_status: false,
+            renewed_certificates: Vec::new(),
+            known_custom_keys: Vec::new(),
+            expiry_warning: DEFAULT_EXPIRY_WARNING,
+            escalated_warnings: Vec::new(),
+            max_chain_len: DEFAULT_MAX_CHAIN_LEN,
+            signature_policy: SignaturePolicy::Ed25519,
+        }
+    }
+}
+
+impl VerifyOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ignore certificate validity periods
+    ///
+    /// Useful for archival verification of content signed with certificates
+    /// that have since expired.
+    pub fn archival(mut self) -> Self {
+        self.validity = ValidityCheck::Skip;
+        self
+    }
+
+    /// Check certificates as of `timestamp` instead of now
+    ///
+    /// Validity periods and status assertions are evaluated at `timestamp`,
+    /// and revocation list entries dated after it are ignored.
+    pub fn at(self, timestamp: i64) -> Self {
+        self.with_validity_check(ValidityCheck::At(timestamp))
+    }
+
+    /// Set how certificate validity periods are checked
+    pub fn with_validity_check(mut self, validity: ValidityCheck) -> Self {
+        self.validity = validity;
+        self
+    }
+
+    /// Reject certificates revoked by `crl`
+    pub fn with_revocation_list(mut self, crl: RevocationList) -> Self {
+        self.revocation_lists.push(crl);
+        self
+    }
+
+    /// Trust RFC 3161 timestamp tokens issued under `root_der`
+    pub fn with_tsa_root(mut self, root_der: Vec<u8>) -> Self {
+        self.tsa_roots.push(root_der);
+        self
+    }
+
+    /// Trust inclusion proofs signed by the transparency log with `public_key`
+    pub fn with_log_key(mut self, public_key: Vec<u8>) -> Self {
+        self.log_keys.push(public_key);
+        self
+    }
+
+    /// Reject files without a valid inclusion proof from a trusted log
+    ///
+    /// A log entry pins the time the signature became public, so a signature
+    /// made later with a compromised key cannot be passed off as older.
+    pub fn require_log_inclusion(mut self) -> Self {
+        self.require_log_inclusion = true;
+        self
+    }
+
+    /// Accept OpenTimestamps proofs anchored in the Bitcoin block at `height`
+    ///
+    /// `merkle_root` is in the byte order of the serialized block header.
+    pub fn with_bitcoin_block(mut self, height: u64, merkle_root: [u8; 32]) -> Self {
+        self.bitcoin_blocks.insert(height, merkle_root);
+        self
+    }
+
+    /// Also trust the root key certified by `cross`, while `cross` is valid
+    ///
+    /// `cross` must be a CA certificate signed by a trusted root, such as the
+    /// cross-certificates of a [`CaRotation`](crate::ca::CaRotation). This
+    /// lets chains ending in the old or the new root verify during a key
+    /// rotation, whichever of the two the verifier trusts.
+    pub fn with_cross_certificate(mut self, cross: Certificate) -> Self {
+        self.cross_certificates.push(cross);
+        self
+    }
+
+    /// Check chains against `assertion` as if it were stapled to the file
+    pub fn with_status_assertion(mut self, assertion: StatusAssertion) -> Self {
+        self.status_assertions.push(assertion);
+        self
+    }
+
+    /// Reject chains without a current "good" status assertion for every
+    /// certificate below the root
+    ///
+    /// This applies to co-signer and countersigner chains as well. Without
+    /// it, status assertions only ever cause a revoked certificate to be
+    /// rejected.
+    pub fn require_status(mut self) -> Self {
+        self.require_status = true;
+        self
+    }
+
+    /// Recognize files signed with a certificate that `renewal` replaces
+    ///
+    /// `renewal` is the current certificate of an identity, issued by
+    /// [`CertificateAuthority::renew`](crate::ca::CertificateAuthority::renew).
+    /// When the file's signing certificate is its predecessor (directly or
+    /// through other renewals given here), the result reports it in
+    /// [`VerificationResult::renewed_by`].
+    pub fn with_renewed_certificate(mut self, renewal: Certificate) -> Self {
+        self.renewed_certificates.push(renewal);
+        self
+    }
+
+    /// Accept `key` in the header's custom metadata without a warning
+    pub fn with_known_custom_key(mut self, key: impl Into<String>) -> Self {
+        self.known_custom_keys.push(key.into());
+        self
+    }
+
+    /// Warn about certificates expiring within `seconds` of the verification
+    /// time (30 days by default)
+    pub fn with_expiry_warning(mut self, seconds: i64) -> Self {
+        self.expiry_warning = seconds;
+        self
+    }
+
+    /// Fail verification on warnings of `kind` instead of reporting them
+    pub fn escalate_warning(mut self, kind: WarningKind) -> Self {
+        self.escalated_warnings.push(kind);
+        self
+    }
+
+    /// Reject certificate chains longer than `len` (16 by default)
+    pub fn with_max_chain_len(mut self, len: usize) -> Self {
+        self.max_chain_len = len;
+        self
+    }
+
+    /// Check the signatures `policy` asks for (Ed25519 only by default)
+    ///
+    /// The policy covers every certificate of every chain and the file's
+    /// primary signature. Requiring ML-DSA rejects files and chains without
+    /// ML-DSA signatures, so they cannot be downgraded by stripping them.
+    /// Co-signatures and countersignatures are Ed25519 only and are checked
+    /// as such under any policy.
+    pub fn with_signature_policy(mut self, policy: SignaturePolicy) -> Self {
+        self.signature_policy = policy;
+        self
+    }
+
+    /// These options plus the status assertions stapled to a file
+    fn with_stapled(&self, stapled: &[StatusAssertion]) -> Cow<'_, Self> {
+        if stapled.is_empty() {
+            return Cow::Borrowed(self);
+        }
+        let mut options = self.clone();
+        options.status_assertions.extend_from_slice(stapled);
+        Cow::Owned(options)
+    }
+}
+
 /// Result of verifying an Aletheia file
 #[derive(Debug, Clone)]
 pub struct VerificationResult {
@@ -13,10 +267,493 @@ pub struct VerificationResult {
     pub creator_id: String,
     /// The creator's name from the certificate
     pub creator_name: String,
+    /// Fingerprint of the creator's certificate (`None` for imported C2PA
+    /// manifests, which are not signed with Aletheia certificates)
+    pub creator_fingerprint: Option<Fingerprint>,
+    /// How the creator's identity was verified, as recorded by their CA
+    pub identity_attestation: Option<IdentityAttestation>,
+    /// Identity the creator signed on behalf of, when their certificate is a
+    /// sub-identity (such as a per-device key)
+    pub parent_id: Option<String>,
+    /// Fingerprint of the latest known renewal of the creator's certificate,
+    /// when the file was signed with an earlier certificate of the same
+    /// identity (see [`VerifyOptions::with_renewed_certificate`])
+    pub renewed_by: Option<Fingerprint>,
     /// When the file was signed (Unix timestamp)
     pub signed_at: i64,
+    /// Whether the signing key was a single-use (per-file) key rather than a
+    /// long-lived identity key
+    pub ephemeral_key: bool,
     /// Description from the header (if any)
     pub description: Option<String>,
+    /// Creation-process claims from the header, covered by the signature
+    pub assertions: Vec<Assertion>,
+    /// Outcome for each co-signature, in file order
+    pub co_signers: Vec<CoSignerResult>,
+    /// Outcome for each countersignature, in file order
+    pub countersigners: Vec<CountersignatureResult>,
+    /// Outcome for each RFC 3161 timestamp token, in file order
+    pub timestamps: Vec<TimestampResult>,
+    /// Outcome for each transparency log entry, in file order
+    pub log_entries: Vec<LogEntryResult>,
+    /// Outcome for each OpenTimestamps proof, in file order
+    pub opentimestamps: Vec<OpenTimestampsResult>,
+    /// Conditions worth reporting that do not make the file invalid
+    pub warnings: Vec<VerificationWarning>,
+    /// Format version of the file (major, minor)
+    pub format_version: (u8, u8),
+    /// SHA-256 of the verified original (uncompressed) payload (`None` when
+    /// the whole payload was not hashed: single chunks and compressed
+    /// payloads verified as a stream)
+    pub content_digest: Option<[u8; 32]>,
+    /// Length of the original payload in bytes (`None` when not known, as
+    /// for `content_digest`)
+    pub payload_size: Option<u64>,
+    /// Length of the payload as stored in the file, after compression
+    /// (0 for detached manifests)
+    pub stored_size: u64,
+    /// Public key of the trusted root the signer's chain was verified
+    /// against; for a chain ending at a cross-signed root, the trusted root
+    /// that issued the cross-certificate
+    pub trust_anchor: Option<Vec<u8>>,
+    /// Fingerprints of the signer's certificate chain, signer first
+    pub chain_fingerprints: Vec<Fingerprint>,
+    /// Serial numbers of the signer's certificate chain, signer first
+    pub chain_serials: Vec<Vec<u8>>,
+}
+
+/// A condition found during verification that does not make a file invalid
+///
+/// Warnings are reported in [`VerificationResult::warnings`] unless their
+/// kind is escalated with [`VerifyOptions::escalate_warning`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerificationWarning {
+    /// The header's custom metadata has a key the application does not know
+    UnknownCustomKey(String),
+    /// The file uses an older minor version of the format
+    DeprecatedVersion { major: u8, minor: u8 },
+    /// A certificate of the signer's chain expires soon after the
+    /// verification time
+    CertificateNearExpiry { subject_id: String, not_after: i64 },
+}
+
+/// The kind of a [`VerificationWarning`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WarningKind {
+    UnknownCustomKey,
+    DeprecatedVersion,
+    CertificateNearExpiry,
+}
+
+impl VerificationWarning {
+    /// The kind of the warning
+    pub fn kind(&self) -> WarningKind {
+        match self {
+            Self::UnknownCustomKey(_) => WarningKind::UnknownCustomKey,
+            Self::DeprecatedVersion { .. } => WarningKind::DeprecatedVersion,
+            Self::CertificateNearExpiry { .. } => WarningKind::CertificateNearExpiry,
+        }
+    }
+}
+
+impl core::fmt::Display for VerificationWarning {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::UnknownCustomKey(key) => write!(f, "Unknown custom header key: {key}"),
+            Self::DeprecatedVersion { major, minor } => {
+                write!(f, "Deprecated format version {major}.{minor}")
+            }
+            Self::CertificateNearExpiry {
+                subject_id,
+                not_after,
+            } => write!(
+                f,
+                "Certificate of {subject_id} expires soon (at {not_after})"
+            ),
+        }
+    }
+}
+
+/// Result of verifying a single co-signature
+///
+/// Co-signatures are checked independently of each other and of the primary
+/// signature, since a verifier may trust some signers' roots but not others.
+#[derive(Debug, Clone)]
+pub struct CoSignerResult {
+    /// Whether this co-signature verified
+    pub valid: bool,
+    /// The co-signer's ID from their certificate
+    pub signer_id: String,
+    /// The co-signer's name from their certificate
+    pub signer_name: String,
+    /// Fingerprint of the co-signer's certificate (`None` if the chain is empty)
+    pub signer_fingerprint: Option<Fingerprint>,
+    /// The role the co-signer signed in, if they recorded one
+    pub role: Option<String>,
+    /// Why verification failed (if it did)
+    pub error: Option<String>,
+}
+
+impl CoSignerResult {
+    fn new(co_signature: &CoSignature, outcome: Result<()>) -> Self {
+        let signer = co_signature.certificate_chain.first();
+        Self {
+            valid: outcome.is_ok(),
+            signer_id: signer.map(|c| c.subject_id.clone()).unwrap_or_default(),
+            signer_name: signer.map(|c| c.subject_name.clone()).unwrap_or_default(),
+            signer_fingerprint: signer.map(Certificate::fingerprint),
+            role: co_signature.role.clone(),
+            error: outcome.err().map(|e| e.to_string()),
+        }
+    }
+}
+
+/// Result of verifying a single countersignature
+#[derive(Debug, Clone)]
+pub struct CountersignatureResult {
+    /// Whether this countersignature verified
+    pub valid: bool,
+    /// The countersigner's ID from their certificate
+    pub signer_id: String,
+    /// The countersigner's name from their certificate
+    pub signer_name: String,
+    /// Fingerprint of the countersigner's certificate (`None` if the chain is empty)
+    pub signer_fingerprint: Option<Fingerprint>,
+    /// When the countersignature was made (Unix timestamp)
+    pub signed_at: i64,
+    /// Why verification failed (if it did)
+    pub error: Option<String>,
+}
+
+impl CountersignatureResult {
+    fn new(countersignature: &Countersignature, outcome: Result<()>) -> Self {
+        let signer = countersignature.certificate_chain.first();
+        Self {
+            valid: outcome.is_ok(),
+            signer_id: signer.map(|c| c.subject_id.clone()).unwrap_or_default(),
+            signer_name: signer.map(|c| c.subject_name.clone()).unwrap_or_default(),
+            signer_fingerprint: signer.map(Certificate::fingerprint),
+            signed_at: countersignature.signed_at,
+            error: outcome.err().map(|e| e.to_string()),
+        }
+    }
+}
+
+/// Result of verifying a single RFC 3161 timestamp token
+#[derive(Debug, Clone)]
+pub struct TimestampResult {
+    /// Whether this token verified against a trusted TSA root
+    pub valid: bool,
+    /// Subject of the TSA certificate (empty if the token could not be parsed)
+    pub tsa_name: String,
+    /// Time asserted by the TSA (Unix timestamp, 0 if unknown)
+    pub time: i64,
+    /// Why verification failed (if it did)
+    pub error: Option<String>,
+}
+
+/// Verify every timestamp token over `primary_signature`
+///
+/// Like countersignatures, tokens are reported separately and do not affect
+/// the primary signature.
+fn verify_timestamps(
+    primary_signature: &[u8],
+    tokens: &[TimestampToken],
+    options: &VerifyOptions,
+) -> Vec<TimestampResult> {
+    tokens
+        .iter()
+        .map(|token| {
+            #[cfg(feature = "timestamp")]
+            let outcome =
+                crate::timestamp::verify_token(token, primary_signature, &options.tsa_roots)
+                    .map(|info| (info.tsa_name, info.time));
+            #[cfg(not(feature = "timestamp"))]
+            let outcome: Result<(String, i64)> = {
+                let _ = (token, primary_signature, &options.tsa_roots);
+                Err(AletheiaError::Timestamp(
+                    "Timestamp feature not enabled".into(),
+                ))
+            };
+            match outcome {
+                Ok((tsa_name, time)) => TimestampResult {
+                    valid: true,
+                    tsa_name,
+                    time,
+                    error: None,
+                },
+                Err(e) => TimestampResult {
+                    valid: false,
+                    tsa_name: String::new(),
+                    time: 0,
+                    error: Some(e.to_string()),
+                },
+            }
+        })
+        .collect()
+}
+
+/// Result of verifying a single transparency log entry
+#[derive(Debug, Clone)]
+pub struct LogEntryResult {
+    /// Whether the inclusion proof verified against a trusted log
+    pub valid: bool,
+    /// Position of the entry in the log
+    pub index: u64,
+    /// When the log integrated the entry (Unix timestamp)
+    pub integrated_time: i64,
+    /// Why verification failed (if it did)
+    pub error: Option<String>,
+}
+
+/// Verify every log entry over `primary_signature`
+///
+/// Entries are reported separately, unless the options require inclusion in
+/// a trusted log and no entry proves it.
+fn verify_log_entries(
+    primary_signature: &[u8],
+    entries: &[LogEntry],
+    options: &VerifyOptions,
+) -> Result<Vec<LogEntryResult>> {
+    let results: Vec<LogEntryResult> = entries
+        .iter()
+        .map(|entry| {
+            let outcome =
+                crate::transparency::verify_entry(entry, primary_signature, &options.log_keys);
+            LogEntryResult {
+                valid: outcome.is_ok(),
+                index: entry.index,
+                integrated_time: entry.integrated_time,
+                error: outcome.err().map(|e| e.to_string()),
+            }
+        })
+        .collect();
+
+    if options.require_log_inclusion && !results.iter().any(|r| r.valid) {
+        return Err(AletheiaError::TransparencyLog(
+            "No valid inclusion proof from a trusted log".into(),
+        ));
+    }
+    Ok(results)
+}
+
+/// Result of verifying a single OpenTimestamps proof
+#[derive(Debug, Clone)]
+pub struct OpenTimestampsResult {
+    /// Whether the proof is anchored in a block the verifier knows
+    pub valid: bool,
+    /// Lowest block height the content digest is proven to predate
+    pub block_height: Option<u64>,
+    /// Why verification failed (if it did)
+    pub error: Option<String>,
+}
+
+/// Verify every OpenTimestamps proof against the content digest in `header`
+fn verify_opentimestamps(
+    header: &Header,
+    proofs: &[OpenTimestampsProof],
+    options: &VerifyOptions,
+) -> Vec<OpenTimestampsResult> {
+    proofs
+        .iter()
+        .map(|proof| {
+            let outcome = match &header.content_digest {
+                Some(digest) => {
+                    crate::opentimestamps::verify_proof(&proof.ots, digest, &options.bitcoin_blocks)
+                }
+                None => Err(AletheiaError::OpenTimestamps(
+                    "File records no content digest".into(),
+                )),
+            };
+            OpenTimestampsResult {
+                valid: outcome.is_ok(),
+                error: outcome.as_ref().err().map(|e| e.to_string()),
+                block_height: outcome.ok(),
+            }
+        })
+        .collect()
+}
+
+/// Verify every countersignature over `primary_signature`
+///
+/// Each countersignature is checked against its own chain and reported
+/// separately; failures do not affect the primary signature.
+fn verify_countersignatures(
+    primary_signature: &[u8],
+    countersignatures: &[Countersignature],
+    trusted_root_keys: &[Vec<u8>],
+    options: &VerifyOptions,
+) -> Vec<CountersignatureResult> {
+    countersignatures
+        .iter()
+        .map(|cs| {
+            let outcome =
+                verify_countersignature(primary_signature, cs, trusted_root_keys, options);
+            CountersignatureResult::new(cs, outcome)
+        })
+        .collect()
+}
+
+/// Verify a certificate chain against the trusted roots and revocation lists
+///
+/// Revocation and status are checked along the path that reached a trusted
+/// root, which differs from the chain as ordered when it carries
+/// cross-signed intermediates.
+fn verify_chain(
+    chain: &[Certificate],
+    trusted_root_keys: &[Vec<u8>],
+    options: &VerifyOptions,
+) -> Result<TrustedPath> {
+    let root_keys = cross_signed_root_keys(trusted_root_keys, options);
+    let path = find_trusted_path_with_policy(
+        chain,
+        &root_keys,
+        options.validity,
+        options.max_chain_len,
+        options.signature_policy,
+    )?;
+    let anchor = trust_anchor(path[path.len() - 1], trusted_root_keys, options);
+    let indices = path
+        .iter()
+        .map(|cert| {
+            chain
+                .iter()
+                .position(|c| core::ptr::eq(c, *cert))
+                .expect("the path is made of chain certificates")
+        })
+        .collect();
+    let path: Cow<'_, [Certificate]> =
+        if path.len() == chain.len() && path.iter().zip(chain).all(|(a, b)| core::ptr::eq(*a, b)) {
+            Cow::Borrowed(chain)
+        } else {
+            Cow::Owned(path.into_iter().cloned().collect())
+        };
+    for crl in &options.revocation_lists {
+        crl.check_chain_at(&path, options.validity)?;
+    }
+    check_chain_status(
+        &path,
+        &options.status_assertions,
+        options.validity,
+        options.require_status,
+    )?;
+    Ok(TrustedPath { anchor, indices })
+}
+
+/// How a verified certificate chain reached a trusted root
+struct TrustedPath {
+    /// The trusted root key the path stands for (see [`trust_anchor`])
+    anchor: Vec<u8>,
+    /// Positions in the chain of the certificates on the path, signer first
+    indices: Vec<usize>,
+}
+
+/// The trusted root key that `root`, the end of a verified path, stands for
+///
+/// A root only trusted through a cross-certificate is attributed to the
+/// trusted root that issued the cross-certificate.
+fn trust_anchor(
+    root: &Certificate,
+    trusted_root_keys: &[Vec<u8>],
+    options: &VerifyOptions,
+) -> Vec<u8> {
+    if ct::contains(trusted_root_keys, &root.public_key) {
+        return root.public_key.clone();
+    }
+    options
+        .cross_certificates
+        .iter()
+        .filter(|cross| ct::eq(&cross.public_key, &root.public_key))
+        .find_map(|cross| {
+            trusted_root_keys
+                .iter()
+                .find(|key| verify_certificate_signature(cross, key).is_ok())
+        })
+        .unwrap_or(&root.public_key)
+        .clone()
+}
+
+/// Fingerprint of the last certificate in the known renewals of `chain[0]`
+///
+/// Each renewal must name the previous certificate's serial as its
+/// predecessor, be for the same subject and be signed by the same issuer.
+fn latest_renewal(chain: &[Certificate], options: &VerifyOptions) -> Option<Fingerprint> {
+    let (signer, issuer) = (chain.first()?, chain.get(1)?);
+    let mut latest: Option<&Certificate> = None;
+    // Each step consumes a renewal, which bounds the walk on cyclic input
+    for _ in 0..options.renewed_certificates.len() {
+        let serial = &latest.unwrap_or(signer).serial;
+        let Some(renewal) = options.renewed_certificates.iter().find(|renewal| {
+            renewal.predecessor_serial.as_ref() == Some(serial)
+                && renewal.subject_id == signer.subject_id
+                && renewal.issuer_id == signer.issuer_id
+                && verify_certificate_signature(renewal, &issuer.public_key).is_ok()
+        }) else {
+            break;
+        };
+        latest = Some(renewal);
+    }
+    latest.map(Certificate::fingerprint)
+}
+
+/// The trusted root keys plus the keys vouched for by valid cross-certificates
+fn cross_signed_root_keys(trusted_root_keys: &[Vec<u8>], options: &VerifyOptions) -> Vec<Vec<u8>> {
+    let mut keys = trusted_root_keys.to_vec();
+    for cross in &options.cross_certificates {
+        let Some(issuer_key) = trusted_root_keys
+            .iter()
+            .find(|key| verify_certificate_signature(cross, key).is_ok())
+        else {
+            continue;
+        };
+        let revoked = options.revocation_lists.iter().any(|crl| {
+            crl.issuer_id == cross.issuer_id
+                && crl.verify_signature(issuer_key).is_ok()
+                && crl.find_at(&cross.serial, options.validity).is_some()
+        });
+        if cross.is_ca
+            && !cross.single_use
+            && !revoked
+            && check_validity(cross, options.validity).is_ok()
+        {
+            keys.push(cross.public_key.clone());
+        }
+    }
+    keys
+}
+
+/// Verify a single countersignature over `primary_signature`
+fn verify_countersignature(
+    primary_signature: &[u8],
+    countersignature: &Countersignature,
+    trusted_root_keys: &[Vec<u8>],
+    options: &VerifyOptions,
+) -> Result<()> {
+    // Verify the countersigner's certificate chain
+    verify_chain(
+        &countersignature.certificate_chain,
+        trusted_root_keys,
+        options,
+    )?;
+
+    let mut cert_chain_bytes = Vec::new();
+    ciborium::into_writer(&countersignature.certificate_chain, &mut cert_chain_bytes)
+        .map_err(|e| AletheiaError::CborEncode(e.to_string()))?;
+
+    let signature_input = build_countersignature_input(
+        primary_signature,
+        countersignature.signed_at,
+        &cert_chain_bytes,
+    );
+
+    let (verifying_key, signature) = signer_key_and_signature(
+        &countersignature.certificate_chain[0],
+        &countersignature.signature,
+    )?;
+
+    verifying_key
+        .verify(&signature_input, &signature)
+        .map_err(|_| AletheiaError::InvalidSignature)
 }
 
 /// Verify an Aletheia file's authenticity
@@ -24,174 +761,2785 @@ pub struct VerificationResult {
 /// This function:
 /// 1. Verifies the certificate chain against trusted roots
 /// 2. Verifies the signature over the entire file contents
+/// 3. Checks the header's content digest, if present, against the payload
+/// 4. Verifies each co-signature, reporting the outcome per co-signer
+/// 5. Verifies each countersignature against its own chain
 ///
 /// # Arguments
 /// * `file` - The Aletheia file to verify
 /// * `trusted_root_keys` - List of trusted root CA public keys
 ///
 /// # Returns
-/// * `Ok(VerificationResult)` - If verification succeeds
-/// * `Err(AletheiaError)` - If verification fails
+/// * `Ok(VerificationResult)` - If the primary signature verifies
+/// * `Err(AletheiaError)` - If verification of the primary signature fails
+///
+/// Certificate validity periods are checked at the current time; use
+/// [`verify_with_options`] to change that.
 pub fn verify(file: &AletheiaFile, trusted_root_keys: &[Vec<u8>]) -> Result<VerificationResult> {
-    // Verify the certificate chain
-    verify_certificate_chain(&file.certificate_chain, trusted_root_keys)?;
+    verify_with_options(file, trusted_root_keys, &VerifyOptions::default())
+}
 
-    // Get the creator's certificate (first in chain)
-    let creator_cert = &file.certificate_chain[0];
+/// Verify an Aletheia file as of `timestamp`
+///
+/// Certificate validity periods, revocation list entries and status
+/// assertions are evaluated at `timestamp` instead of now, so archived
+/// content can be checked against the state of its certificates when it was
+/// signed. Revocations dated after `timestamp` are ignored, so the time
+/// should come from a trusted source such as a timestamp token rather than
+/// the file's own header.
+pub fn verify_at(
+    file: &AletheiaFile,
+    trusted_root_keys: &[Vec<u8>],
+    timestamp: i64,
+) -> Result<VerificationResult> {
+    verify_with_options(file, trusted_root_keys, &VerifyOptions::new().at(timestamp))
+}
+
+/// Verify an Aletheia file, also asking `checker` whether the signer's
+/// certificates have been revoked
+///
+/// After the checks of [`verify_with_options`], every certificate on the
+/// path from the signer to a trusted root, except the root, is looked up
+/// with `checker`. Revocations
+/// dated after the verification time of `options` are ignored. `policy`
+/// decides whether a failed lookup fails verification.
+pub async fn verify_with_checker<C: RevocationChecker>(
+    file: &AletheiaFile,
+    trusted_root_keys: &[Vec<u8>],
+    options: &VerifyOptions,
+    checker: &C,
+    policy: FailurePolicy,
+) -> Result<VerificationResult> {
+    let (result, path) = verify_file(file, trusted_root_keys, options)?;
+    // The path that verified, which differs from the chain as stored when
+    // its certificates are out of order or cross-signed
+    for (step, &index) in path[..path.len() - 1].iter().enumerate() {
+        let cert = &file.certificate_chain[index];
+        match checker.check(&cert.issuer_id, &cert.serial).await {
+            Ok(Some(revoked)) if revoked.applies_at(options.validity) => {
+                return Err(
+                    AletheiaError::CertificateRevoked(serial_hex(&cert.serial)).at_step(step)
+                );
+            }
+            Ok(_) => {}
+            Err(e) if policy == FailurePolicy::HardFail => return Err(e),
+            Err(_) => {}
+        }
+    }
+    Ok(result)
+}
+
+/// Verify an Aletheia file against the roots of a trust bundle
+///
+/// The bundle's own signature is not checked here: check it with
+/// [`TrustBundle::verify_signature`] against the pinned bundle signer before
+/// relying on its roots.
+pub fn verify_with_bundle(file: &AletheiaFile, bundle: &TrustBundle) -> Result<VerificationResult> {
+    verify(file, &bundle.public_keys())
+}
+
+/// Verify an Aletheia file, rejecting certificates revoked by `crl`
+///
+/// Co-signers and countersigners with revoked certificates are reported as
+/// invalid in the result rather than failing the whole verification.
+pub fn verify_with_revocations(
+    file: &AletheiaFile,
+    trusted_root_keys: &[Vec<u8>],
+    crl: &RevocationList,
+) -> Result<VerificationResult> {
+    let options = VerifyOptions::new().with_revocation_list(crl.clone());
+    verify_with_options(file, trusted_root_keys, &options)
+}
+
+/// Verify an Aletheia file's authenticity with explicit options
+pub fn verify_with_options(
+    file: &AletheiaFile,
+    trusted_root_keys: &[Vec<u8>],
+    options: &VerifyOptions,
+) -> Result<VerificationResult> {
+    verify_file(file, trusted_root_keys, options).map(|(result, _)| result)
+}
 
-    // Encode header and cert chain as they would have been signed
+/// [`verify_with_options`], also returning the positions in the signer's
+/// chain of the path that reached a trusted root
+fn verify_file(
+    file: &AletheiaFile,
+    trusted_root_keys: &[Vec<u8>],
+    options: &VerifyOptions,
+) -> Result<(VerificationResult, Vec<usize>)> {
+    // Encode the header as it would have been signed
     let mut header_bytes = Vec::new();
     ciborium::into_writer(&file.header, &mut header_bytes)
         .map_err(|e| AletheiaError::CborEncode(e.to_string()))?;
 
-    let mut cert_chain_bytes = Vec::new();
-    ciborium::into_writer(&file.certificate_chain, &mut cert_chain_bytes)
-        .map_err(|e| AletheiaError::CborEncode(e.to_string()))?;
+    // Verify the primary signer
+    let view = FileView::from(file);
+    let options = &*options.with_stapled(view.status_assertions);
+    let trusted = verify_signer(&view, &header_bytes, None, trusted_root_keys, options)?;
 
-    // Build the signature input
-    let signature_input =
-        build_signature_input(&file.flags, &header_bytes, &file.payload, &cert_chain_bytes);
+    // Check the payload against what the header records about it
+    let payload = verify_payload(&view)?;
 
-    // Verify the signature
-    let verifying_key = VerifyingKey::try_from(creator_cert.public_key.as_slice())
-        .map_err(|e| AletheiaError::InvalidCertificate(format!("Invalid public key: {}", e)))?;
+    let result = verification_result(
+        &view,
+        &header_bytes,
+        trusted_root_keys,
+        options,
+        trusted.anchor,
+        Some(payload),
+    )?;
+    Ok((result, trusted.indices))
+}
 
-    let signature = Signature::try_from(file.signature.as_slice())
-        .map_err(|_| AletheiaError::InvalidSignature)?;
+/// Parse, validate and verify an encoded Aletheia file
+///
+/// Returns the verification result together with the original
+/// (decompressed) payload.
+pub fn verify_bytes(
+    data: &[u8],
+    trusted_root_keys: &[Vec<u8>],
+) -> Result<(VerificationResult, Vec<u8>)> {
+    let file = crate::file::from_bytes(data)?;
+    validate_structure(&file)?;
+    let result = verify(&file, trusted_root_keys)?;
+    let payload = file.get_payload()?;
+    Ok((result, payload))
+}
 
-    verifying_key
-        .verify(&signature_input, &signature)
-        .map_err(|_| AletheiaError::InvalidSignature)?;
+/// Read, validate and verify the Aletheia file at `path`
+///
+/// See [`verify_bytes`].
+#[cfg(feature = "std")]
+pub fn verify_path(
+    path: impl AsRef<std::path::Path>,
+    trusted_root_keys: &[Vec<u8>],
+) -> Result<(VerificationResult, Vec<u8>)> {
+    let file = crate::file::read_from_file(path)?;
+    validate_structure(&file)?;
+    let result = verify(&file, trusted_root_keys)?;
+    let payload = file.get_payload()?;
+    Ok((result, payload))
+}
 
-    Ok(VerificationResult {
-        valid: true,
-        creator_id: creator_cert.subject_id.clone(),
-        creator_name: creator_cert.subject_name.clone(),
-        signed_at: file.header.signed_at,
-        description: file.header.description.clone(),
-    })
+/// Verify a file parsed with [`crate::file::from_bytes_ref`]
+///
+/// Performs the same checks as [`verify`] without copying the payload, and
+/// checks the signature over the header and certificate chain as stored.
+pub fn verify_ref(
+    file: &AletheiaFileRef,
+    trusted_root_keys: &[Vec<u8>],
+) -> Result<VerificationResult> {
+    verify_ref_with_options(file, trusted_root_keys, &VerifyOptions::default())
 }
 
-/// Quick check if an Aletheia file has valid structure (without full verification)
-pub fn validate_structure(file: &AletheiaFile) -> Result<()> {
-    // Check version
-    if file.version_major != 1 {
-        return Err(AletheiaError::UnsupportedVersion {
-            major: file.version_major,
-            minor: file.version_minor,
-        });
-    }
+/// Verify a borrowed file with explicit options
+pub fn verify_ref_with_options(
+    file: &AletheiaFileRef,
+    trusted_root_keys: &[Vec<u8>],
+    options: &VerifyOptions,
+) -> Result<VerificationResult> {
+    let certificate_chain = file.certificate_chain()?;
+    let trailer = file.trailer()?;
+    let view = FileView {
+        version_major: file.version_major,
+        version_minor: file.version_minor,
+        flags: file.flags,
+        header: &file.header,
+        payload: file.payload,
+        certificate_chain: &certificate_chain,
+        signature: file.signature,
+        pq_signature: trailer.pq_signature.as_deref(),
+        co_signatures: &trailer.co_signatures,
+        countersignatures: &trailer.countersignatures,
+        timestamp_tokens: &trailer.timestamp_tokens,
+        log_entries: &trailer.log_entries,
+        opentimestamps: &trailer.opentimestamps,
+        status_assertions: &trailer.status_assertions,
+    };
+    let options = &*options.with_stapled(view.status_assertions);
 
-    // Check certificate chain is not empty
-    if file.certificate_chain.is_empty() {
-        return Err(AletheiaError::CertificateChainInvalid(
-            "Empty certificate chain".into(),
+    // Verify the primary signer over the sections as stored
+    let trust_anchor = verify_chain(&certificate_chain, trusted_root_keys, options)?.anchor;
+    verify_primary_signature(
+        &SignatureInput {
+            version_major: file.version_major,
+            version_minor: file.version_minor,
+            flags: &file.flags,
+            header_bytes: file.header_bytes,
+            payload: file.payload,
+            cert_chain_bytes: file.cert_chain_bytes,
+        },
+        &certificate_chain[0],
+        file.signature,
+        view.pq_signature,
+        options.signature_policy,
+    )?;
+
+    // Check the payload against what the header records about it
+    let payload = verify_payload(&view)?;
+
+    verification_result(
+        &view,
+        file.header_bytes,
+        trusted_root_keys,
+        options,
+        trust_anchor,
+        Some(payload),
+    )
+}
+
+/// Verify a detached manifest against the content it was signed for
+///
+/// The signature covers the header, so the content is checked against the
+/// content digest recorded there.
+pub fn verify_detached(
+    file: &AletheiaFile,
+    content: &[u8],
+    trusted_root_keys: &[Vec<u8>],
+) -> Result<VerificationResult> {
+    verify_detached_with_options(file, content, trusted_root_keys, &VerifyOptions::default())
+}
+
+/// Verify a detached manifest with explicit options
+pub fn verify_detached_with_options(
+    file: &AletheiaFile,
+    content: &[u8],
+    trusted_root_keys: &[Vec<u8>],
+    options: &VerifyOptions,
+) -> Result<VerificationResult> {
+    if !file.flags.is_detached() {
+        return Err(AletheiaError::InvalidHeader(
+            "File is not a detached manifest".into(),
         ));
     }
 
-    // Check signature length
-    if file.signature.len() != 64 {
-        return Err(AletheiaError::InvalidSignature);
-    }
+    // Encode the header as it would have been signed
+    let mut header_bytes = Vec::new();
+    ciborium::into_writer(&file.header, &mut header_bytes)
+        .map_err(|e| AletheiaError::CborEncode(e.to_string()))?;
 
-    // Verify creator ID in header matches certificate
-    let creator_cert = &file.certificate_chain[0];
-    if file.header.creator_id != creator_cert.subject_id {
-        return Err(AletheiaError::InvalidHeader(format!(
-            "Creator ID mismatch: header says '{}', certificate says '{}'",
-            file.header.creator_id, creator_cert.subject_id
-        )));
+    let view = FileView::from(file);
+    let options = &*options.with_stapled(view.status_assertions);
+    let trust_anchor =
+        verify_signer(&view, &header_bytes, None, trusted_root_keys, options)?.anchor;
+
+    let payload = PayloadSummary::of(content);
+    if !file
+        .header
+        .content_digest
+        .as_deref()
+        .is_some_and(|digest| ct::eq(digest, &payload.digest))
+    {
+        return Err(AletheiaError::ContentDigestMismatch);
     }
 
-    Ok(())
+    verification_result(
+        &view,
+        &header_bytes,
+        trusted_root_keys,
+        options,
+        trust_anchor,
+        Some(payload),
+    )
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::{
-        Header,
-        ca::{CertificateAuthority, SigningKeyPair},
-        signer::Signer,
-    };
-
-    fn create_test_file() -> (AletheiaFile, Vec<Vec<u8>>) {
-        let timestamp = 1704067200;
-        let ca =
-            CertificateAuthority::new_root_with_timestamp("root@example.com", "Root CA", timestamp);
-        let user_keys = SigningKeyPair::generate();
+/// Verify a single chunk of a chunked file without its full payload
+///
+/// Checks the primary signature (which covers the Merkle root in the header
+/// instead of the payload) and then the inclusion `proof` of `chunk` as chunk
+/// number `index`. The file's `payload` is ignored, so it may be empty. Use
+/// [`AletheiaFile::chunk_with_proof`] to produce the chunk and proof.
+pub fn verify_chunk(
+    file: &AletheiaFile,
+    trusted_root_keys: &[Vec<u8>],
+    index: u64,
+    chunk: &[u8],
+    proof: &[merkle::Hash],
+) -> Result<VerificationResult> {
+    let chunks = chunk_info(&file.flags, &file.header)?;
 
-        let user_cert = ca
-            .issue_certificate_with_timestamp(
-                "alice@example.com",
-                "Alice",
-                &user_keys.public_key(),
-                false,
-                timestamp,
-            )
-            .unwrap();
+    // Encode the header as it would have been signed
+    let mut header_bytes = Vec::new();
+    ciborium::into_writer(&file.header, &mut header_bytes)
+        .map_err(|e| AletheiaError::CborEncode(e.to_string()))?;
 
-        let chain = vec![user_cert, ca.certificate.clone()];
-        let signer = Signer::new(user_keys, chain).unwrap();
+    let options = VerifyOptions::default()
+        .with_stapled(&file.status_assertions)
+        .into_owned();
+    let view = FileView::from(file);
+    let trust_anchor =
+        verify_signer(&view, &header_bytes, None, trusted_root_keys, &options)?.anchor;
 
-        let payload = b"Test content";
-        let header = Header::new_with_timestamp("alice@example.com", timestamp)
-            .with_description("Test file");
+    // The chunk must have the expected length and be included under the root
+    let offset = index.saturating_mul(chunks.chunk_size as u64);
+    let expected_len = chunks
+        .total_len
+        .saturating_sub(offset)
+        .min(chunks.chunk_size as u64);
+    if index >= chunks.chunk_count() || chunk.len() as u64 != expected_len {
+        return Err(AletheiaError::ChunkVerification(format!(
+            "Chunk {} has unexpected length {}",
+            index,
+            chunk.len()
+        )));
+    }
+    if !merkle::verify_proof(
+        chunk,
+        index,
+        chunks.chunk_count(),
+        proof,
+        &chunks.merkle_root,
+    ) {
+        return Err(AletheiaError::ChunkVerification(format!(
+            "Invalid inclusion proof for chunk {}",
+            index
+        )));
+    }
 
-        let file = signer.sign(payload, header).unwrap();
-        let trusted_roots = vec![ca.public_key()];
+    verification_result(
+        &view,
+        &header_bytes,
+        trusted_root_keys,
+        &options,
+        trust_anchor,
+        None,
+    )
+}
 
-        (file, trusted_roots)
+/// The chunk layout of a chunked file
+fn chunk_info<'a>(flags: &Flags, header: &'a Header) -> Result<&'a ChunkInfo> {
+    match &header.chunks {
+        Some(chunks) if flags.is_chunked() && chunks.chunk_size > 0 => Ok(chunks),
+        Some(_) if flags.is_chunked() => Err(AletheiaError::ChunkVerification(
+            "Invalid chunk size".into(),
+        )),
+        _ => Err(AletheiaError::ChunkVerification(
+            "File is not chunked".into(),
+        )),
+    }
+}
+
+/// The parts of a file that verification reads
+///
+/// Lets owned and borrowed files share the verification code.
+struct FileView<'a> {
+    version_major: u8,
+    version_minor: u8,
+    flags: Flags,
+    header: &'a Header,
+    payload: &'a [u8],
+    certificate_chain: &'a [Certificate],
+    signature: &'a [u8],
+    pq_signature: Option<&'a [u8]>,
+    co_signatures: &'a [CoSignature],
+    countersignatures: &'a [Countersignature],
+    timestamp_tokens: &'a [TimestampToken],
+    log_entries: &'a [LogEntry],
+    opentimestamps: &'a [OpenTimestampsProof],
+    status_assertions: &'a [StatusAssertion],
+}
+
+impl<'a> From<&'a AletheiaFile> for FileView<'a> {
+    fn from(file: &'a AletheiaFile) -> Self {
+        Self {
+            version_major: file.version_major,
+            version_minor: file.version_minor,
+            flags: file.flags,
+            header: &file.header,
+            payload: &file.payload,
+            certificate_chain: &file.certificate_chain,
+            signature: &file.signature,
+            pq_signature: file.pq_signature.as_deref(),
+            co_signatures: &file.co_signatures,
+            countersignatures: &file.countersignatures,
+            timestamp_tokens: &file.timestamp_tokens,
+            log_entries: &file.log_entries,
+            opentimestamps: &file.opentimestamps,
+            status_assertions: &file.status_assertions,
+        }
+    }
+}
+
+/// Digest and length of a verified original payload
+#[derive(Clone, Copy)]
+struct PayloadSummary {
+    digest: [u8; 32],
+    len: u64,
+}
+
+impl PayloadSummary {
+    fn of(payload: &[u8]) -> Self {
+        use sha2::Digest;
+        Self {
+            digest: sha2::Sha256::digest(payload).into(),
+            len: payload.len() as u64,
+        }
+    }
+}
+
+/// Check the payload against the content digest and chunk layout in the header
+fn verify_payload(file: &FileView) -> Result<PayloadSummary> {
+    // A detached manifest can only be checked against the content itself
+    if file.flags.is_detached() {
+        return Err(AletheiaError::DetachedPayload);
+    }
+
+    // In chunked mode the signature only covers the Merkle root
+    if file.flags.is_chunked() {
+        let chunks = chunk_info(&file.flags, file.header)?;
+        if chunks.total_len != file.payload.len() as u64
+            || merkle::merkle_root(file.payload, chunks.chunk_size).as_slice()
+                != chunks.merkle_root.as_slice()
+        {
+            return Err(AletheiaError::ChunkVerification(
+                "Payload does not match the Merkle root".into(),
+            ));
+        }
+    }
+
+    // Check the recorded content digest against the original payload
+    let summary = if file.flags.is_compressed() {
+        PayloadSummary::of(&decompress_payload(file.flags, file.payload)?)
+    } else {
+        PayloadSummary::of(file.payload)
+    };
+    if file
+        .header
+        .content_digest
+        .as_ref()
+        .is_some_and(|digest| !ct::eq(digest, &summary.digest))
+    {
+        return Err(AletheiaError::ContentDigestMismatch);
+    }
+
+    Ok(summary)
+}
+
+/// Conditions worth warning about, failing on those escalated by `options`
+fn collect_warnings(
+    version: (u8, u8),
+    header: &Header,
+    chain: &[Certificate],
+    options: &VerifyOptions,
+) -> Result<Vec<VerificationWarning>> {
+    let mut warnings = Vec::new();
+    for key in header.custom.iter().flat_map(|custom| custom.keys()) {
+        if !options.known_custom_keys.contains(key) {
+            warnings.push(VerificationWarning::UnknownCustomKey(key.clone()));
+        }
+    }
+    let (major, minor) = version;
+    if major == VERSION_MAJOR && minor < VERSION_MINOR {
+        warnings.push(VerificationWarning::DeprecatedVersion { major, minor });
+    }
+    if let ValidityCheck::At(at) = options.validity {
+        for cert in chain {
+            if let Some(not_after) = cert.not_after
+                && not_after - at <= options.expiry_warning
+            {
+                warnings.push(VerificationWarning::CertificateNearExpiry {
+                    subject_id: cert.subject_id.clone(),
+                    not_after,
+                });
+            }
+        }
+    }
+
+    if let Some(warning) = warnings
+        .iter()
+        .find(|warning| options.escalated_warnings.contains(&warning.kind()))
+    {
+        return Err(AletheiaError::EscalatedWarning(warning.to_string()));
+    }
+    Ok(warnings)
+}
+
+/// Build the result for a file whose primary signature verified
+///
+/// Co-signatures, countersignatures, timestamps and log entries are checked
+/// here and reported individually.
+fn verification_result(
+    file: &FileView,
+    header_bytes: &[u8],
+    trusted_root_keys: &[Vec<u8>],
+    options: &VerifyOptions,
+    trust_anchor: Vec<u8>,
+    payload: Option<PayloadSummary>,
+) -> Result<VerificationResult> {
+    // Get the creator's certificate (first in chain)
+    let creator_cert = &file.certificate_chain[0];
+    let warnings = collect_warnings(
+        (file.version_major, file.version_minor),
+        file.header,
+        file.certificate_chain,
+        options,
+    )?;
+
+    // Verify each co-signer independently
+    let co_signers = file
+        .co_signatures
+        .iter()
+        .map(|co| {
+            let outcome = verify_signer(file, header_bytes, Some(co), trusted_root_keys, options);
+            CoSignerResult::new(co, outcome.map(drop))
+        })
+        .collect();
+
+    Ok(VerificationResult {
+        valid: true,
+        creator_id: creator_cert.subject_id.clone(),
+        creator_name: creator_cert.subject_name.clone(),
+        creator_fingerprint: Some(creator_cert.fingerprint()),
+        identity_attestation: creator_cert.identity_attestation.clone(),
+        parent_id: creator_cert.parent_id.clone(),
+        renewed_by: latest_renewal(file.certificate_chain, options),
+        signed_at: file.header.signed_at,
+        ephemeral_key: creator_cert.single_use,
+        description: file.header.description.clone(),
+        assertions: file.header.assertions.clone(),
+        co_signers,
+        countersigners: verify_countersignatures(
+            file.signature,
+            file.countersignatures,
+            trusted_root_keys,
+            options,
+        ),
+        timestamps: verify_timestamps(file.signature, file.timestamp_tokens, options),
+        log_entries: verify_log_entries(file.signature, file.log_entries, options)?,
+        opentimestamps: verify_opentimestamps(file.header, file.opentimestamps, options),
+        warnings,
+        format_version: (file.version_major, file.version_minor),
+        content_digest: payload.map(|payload| payload.digest),
+        payload_size: payload.map(|payload| payload.len),
+        stored_size: file.payload.len() as u64,
+        trust_anchor: Some(trust_anchor),
+        chain_fingerprints: file
+            .certificate_chain
+            .iter()
+            .map(Certificate::fingerprint)
+            .collect(),
+        chain_serials: file
+            .certificate_chain
+            .iter()
+            .map(|cert| cert.serial.clone())
+            .collect(),
+    })
+}
+
+/// Verify the primary signature over the file contents, or the co-signature
+/// `co_signer`
+fn verify_signer(
+    file: &FileView,
+    header_bytes: &[u8],
+    co_signer: Option<&CoSignature>,
+    trusted_root_keys: &[Vec<u8>],
+    options: &VerifyOptions,
+) -> Result<TrustedPath> {
+    let (chain, role, signature) = match co_signer {
+        Some(co) => (
+            &co.certificate_chain[..],
+            co.role.as_deref(),
+            &co.signature[..],
+        ),
+        None => (file.certificate_chain, None, file.signature),
+    };
+
+    // Verify the certificate chain
+    let trusted = verify_chain(chain, trusted_root_keys, options)?;
+
+    // Encode the cert chain as it would have been signed
+    let cert_chain_bytes = co_signer_chain_bytes(chain, role)?;
+
+    // Verify the signature
+    let input = SignatureInput {
+        version_major: file.version_major,
+        version_minor: file.version_minor,
+        flags: &file.flags,
+        header_bytes,
+        payload: file.payload,
+        cert_chain_bytes: &cert_chain_bytes,
+    };
+    match co_signer {
+        Some(_) => {
+            let (verifying_key, signature) = signer_key_and_signature(&chain[0], signature)?;
+            input.verify(&verifying_key, &signature)?;
+        }
+        None => verify_primary_signature(
+            &input,
+            &chain[0],
+            signature,
+            file.pq_signature,
+            options.signature_policy,
+        )?,
+    }
+    Ok(trusted)
+}
+
+/// Verify the primary signature over `input` as `policy` requires
+fn verify_primary_signature(
+    input: &SignatureInput,
+    signer_cert: &Certificate,
+    signature: &[u8],
+    pq_signature: Option<&[u8]>,
+    policy: SignaturePolicy,
+) -> Result<()> {
+    use sha2::Digest;
+
+    if policy.requires_ml_dsa() {
+        if !input.is_prehashed() {
+            return Err(unprehashed_format(input.version_major, input.version_minor));
+        }
+        verify_pq_signature(signer_cert, &input.digest().finalize(), pq_signature)?;
+    }
+    if policy.requires_ed25519() {
+        let (verifying_key, signature) = signer_key_and_signature(signer_cert, signature)?;
+        input.verify(&verifying_key, &signature)?;
+    }
+    Ok(())
+}
+
+/// Verify the creator's ML-DSA signature over the SHA-512 `prehash` of the
+/// signature input
+fn verify_pq_signature(
+    signer_cert: &Certificate,
+    prehash: &[u8],
+    pq_signature: Option<&[u8]>,
+) -> Result<()> {
+    match (&signer_cert.pq_public_key, pq_signature) {
+        (Some(public_key), Some(signature)) => verify_ml_dsa(public_key, prehash, signature),
+        _ => Err(AletheiaError::SignaturePolicy(format!(
+            "No ML-DSA signature by '{}'",
+            signer_cert.subject_id
+        ))),
+    }
+}
+
+/// Files of formats without a prehash (1.0) carry no ML-DSA signature
+fn unprehashed_format(version_major: u8, version_minor: u8) -> AletheiaError {
+    AletheiaError::SignaturePolicy(format!(
+        "Format {}.{} files carry no ML-DSA signature",
+        version_major, version_minor
+    ))
+}
+
+/// Parse the signer's public key and the signature bytes
+fn signer_key_and_signature(
+    signer_cert: &Certificate,
+    signature: &[u8],
+) -> Result<(VerifyingKey, Signature)> {
+    let verifying_key = VerifyingKey::try_from(signer_cert.public_key.as_slice())
+        .map_err(|e| AletheiaError::InvalidCertificate(format!("Invalid public key: {}", e)))?;
+
+    let signature = Signature::try_from(signature).map_err(|_| AletheiaError::InvalidSignature)?;
+
+    Ok((verifying_key, signature))
+}
+
+/// Verify an Aletheia file read from a seekable reader without loading its payload
+///
+/// Performs the same checks as [`verify`], but the payload is streamed through
+/// the signature checks in fixed-size chunks, so memory use does not grow with
+/// the payload size. All signatures are checked in a single pass over the
+/// payload. The reader must be positioned at the start of the file.
+///
+/// The content digest is only checked for uncompressed payloads.
+#[cfg(feature = "std")]
+pub fn verify_stream<R: std::io::Read + std::io::Seek>(
+    reader: R,
+    trusted_root_keys: &[Vec<u8>],
+) -> Result<VerificationResult> {
+    verify_stream_with_options(reader, trusted_root_keys, &VerifyOptions::default())
+}
+
+/// Streaming verification with explicit options
+#[cfg(feature = "std")]
+pub fn verify_stream_with_options<R: std::io::Read + std::io::Seek>(
+    mut reader: R,
+    trusted_root_keys: &[Vec<u8>],
+    options: &VerifyOptions,
+) -> Result<VerificationResult> {
+    use crate::file::{read_chunks, read_structure};
+    use crate::signer::signature_input_prefix;
+    use sha2::Digest;
+    use std::io::SeekFrom;
+
+    let file = read_structure(&mut reader)?;
+    if file.flags.is_detached() {
+        return Err(AletheiaError::DetachedPayload);
+    }
+    let options = &*options.with_stapled(&file.status_assertions);
+    let prehashed = crate::signer::is_prehashed(file.version_major, file.version_minor);
+
+    // Start a stream verifier for a signer whose chain checks out
+    let start = |chain: &[Certificate], role: Option<&str>, signature: &[u8]| {
+        start_signature_stream(
+            chain,
+            role,
+            signature,
+            trusted_root_keys,
+            options,
+            |verifying_key, signature| SignatureStream::new(verifying_key, signature, prehashed),
+        )
+    };
+
+    let (mut primary, _, trust_anchor) = start(&file.certificate_chain, None, &file.signature)?;
+    let mut co_streams: Vec<_> = file
+        .co_signatures
+        .iter()
+        .map(|co| start(&co.certificate_chain, co.role.as_deref(), &co.signature))
+        .collect();
+
+    // In chunked mode the signature covers the Merkle root instead of the payload
+    let mut merkle = if file.flags.is_chunked() {
+        let chunks = chunk_info(&file.flags, &file.header)?;
+        Some(merkle::MerkleBuilder::new(chunks.chunk_size))
+    } else {
+        None
+    };
+    let signed_payload_len = if merkle.is_some() {
+        0
+    } else {
+        file.payload_len
+    };
+
+    // Feed the signature input through every verifier piece by piece
+    let prefix = signature_input_prefix(
+        file.version_major,
+        file.version_minor,
+        &file.flags,
+        &file.header_bytes,
+        signed_payload_len,
+    );
+    primary.update(&prefix);
+    for (stream, _, _) in co_streams.iter_mut().filter_map(|s| s.as_mut().ok()) {
+        stream.update(&prefix);
+    }
+
+    let mut content_hasher = sha2::Sha256::new();
+    reader.seek(SeekFrom::Start(file.payload_offset))?;
+    read_chunks(&mut reader, file.payload_len, |chunk| {
+        content_hasher.update(chunk);
+        if let Some(merkle) = &mut merkle {
+            merkle.update(chunk);
+            return Ok(());
+        }
+        primary.update(chunk);
+        for (stream, _, _) in co_streams.iter_mut().filter_map(|s| s.as_mut().ok()) {
+            stream.update(chunk);
+        }
+        Ok(())
+    })?;
+
+    finish_streamed(
+        file,
+        StreamedSignatures {
+            primary,
+            co_streams,
+            trust_anchor,
+        },
+        merkle,
+        content_hasher,
+        trusted_root_keys,
+        options,
+    )
+}
+
+/// Verification of a file supplied piece by piece
+///
+/// For sources that can be neither held in memory nor read twice, such as
+/// network or browser streams. Pass the file's bytes in order to
+/// [`IncrementalVerifier::update`], then call
+/// [`IncrementalVerifier::finish`]. The payload is hashed as it arrives and
+/// is not kept, except in format 1.0 files, whose signature cannot be
+/// checked before the signer is known; such payloads are buffered. Performs
+/// the same checks as [`verify`].
+pub struct IncrementalVerifier {
+    trusted_root_keys: Vec<Vec<u8>>,
+    options: VerifyOptions,
+    limits: crate::file::ParseLimits,
+    /// The sections before the payload until they are parsed, then the ones
+    /// after it
+    buffer: Vec<u8>,
+    payload: Option<StreamedPayload>,
+}
+
+impl IncrementalVerifier {
+    /// Start verifying a file against `trusted_root_keys`
+    pub fn new(trusted_root_keys: &[Vec<u8>], options: &VerifyOptions) -> Self {
+        Self {
+            trusted_root_keys: trusted_root_keys.to_vec(),
+            options: options.clone(),
+            limits: crate::file::ParseLimits::default(),
+            buffer: Vec::new(),
+            payload: None,
+        }
+    }
+
+    /// Parse with `limits` instead of the defaults
+    pub fn with_limits(mut self, limits: crate::file::ParseLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Feed the next piece of the file
+    ///
+    /// Fails early if the sections before the payload are malformed or the
+    /// file is a detached manifest.
+    pub fn update(&mut self, data: &[u8]) -> Result<()> {
+        let Some(payload) = &mut self.payload else {
+            self.buffer.extend_from_slice(data);
+            let Some(prefix) = crate::file::parse_prefix(&self.buffer, &self.limits)? else {
+                return Ok(());
+            };
+            let len = prefix.len;
+            self.payload = Some(StreamedPayload::new(prefix)?);
+            let rest = self.buffer.split_off(len);
+            self.buffer.clear();
+            return self.update(&rest);
+        };
+
+        let take = payload.remaining.min(data.len() as u64) as usize;
+        payload.update(&data[..take]);
+
+        // Anything past the longest signature section allowed is ignored
+        let rest = &data[take..];
+        let room = self
+            .limits
+            .max_signature_section_len()
+            .saturating_sub(self.buffer.len());
+        self.buffer.extend_from_slice(&rest[..rest.len().min(room)]);
+        Ok(())
+    }
+
+    /// Verify the file once all of it has been fed
+    pub fn finish(self) -> Result<VerificationResult> {
+        let StreamedPayload {
+            mut file,
+            remaining,
+            content_hasher,
+            merkle,
+            signed,
+        } = self.payload.ok_or(AletheiaError::UnexpectedEof)?;
+        if remaining > 0 {
+            return Err(AletheiaError::UnexpectedEof);
+        }
+        let section = crate::file::parse_signature_section(&self.buffer, &self.limits)?;
+        file.certificate_chain = section.certificate_chain;
+        file.cert_chain_bytes = section.cert_chain_bytes;
+        file.signature = section.signature;
+        file.pq_signature = section.trailer.pq_signature;
+        file.co_signatures = section.trailer.co_signatures;
+        file.countersignatures = section.trailer.countersignatures;
+        file.timestamp_tokens = section.trailer.timestamp_tokens;
+        file.log_entries = section.trailer.log_entries;
+        file.opentimestamps = section.trailer.opentimestamps;
+        file.status_assertions = section.trailer.status_assertions;
+
+        let options = &*self.options.with_stapled(&file.status_assertions);
+        let start = |chain: &[Certificate], role: Option<&str>, signature: &[u8]| {
+            start_signature_stream(
+                chain,
+                role,
+                signature,
+                &self.trusted_root_keys,
+                options,
+                |verifying_key, signature| signed.stream(verifying_key, signature),
+            )
+        };
+        let (primary, _, trust_anchor) = start(&file.certificate_chain, None, &file.signature)?;
+        let co_streams = file
+            .co_signatures
+            .iter()
+            .map(|co| start(&co.certificate_chain, co.role.as_deref(), &co.signature))
+            .collect();
+
+        finish_streamed(
+            file,
+            StreamedSignatures {
+                primary,
+                co_streams,
+                trust_anchor,
+            },
+            merkle,
+            content_hasher,
+            &self.trusted_root_keys,
+            options,
+        )
+    }
+}
+
+/// The payload of a file being verified incrementally
+struct StreamedPayload {
+    /// The file, its sections after the payload filled in at the end
+    file: crate::file::FileStructure,
+    /// Payload bytes not fed yet
+    remaining: u64,
+    content_hasher: sha2::Sha256,
+    merkle: Option<merkle::MerkleBuilder>,
+    signed: SignedPayload,
+}
+
+impl StreamedPayload {
+    fn new(prefix: crate::file::Prefix<'_>) -> Result<Self> {
+        use crate::signer::signature_input_prefix;
+        use sha2::Digest;
+
+        if prefix.flags.is_detached() {
+            return Err(AletheiaError::DetachedPayload);
+        }
+
+        // In chunked mode the signature covers the Merkle root instead of the payload
+        let merkle = if prefix.flags.is_chunked() {
+            let chunks = chunk_info(&prefix.flags, &prefix.header)?;
+            Some(merkle::MerkleBuilder::new(chunks.chunk_size))
+        } else {
+            None
+        };
+        let signed_payload_len = if merkle.is_some() {
+            0
+        } else {
+            prefix.payload_len
+        };
+        let signed_prefix = signature_input_prefix(
+            prefix.version_major,
+            prefix.version_minor,
+            &prefix.flags,
+            prefix.header_bytes,
+            signed_payload_len,
+        );
+        let signed = if crate::signer::is_prehashed(prefix.version_major, prefix.version_minor) {
+            SignedPayload::Prehashed(Box::new(sha2::Sha512::new_with_prefix(signed_prefix)))
+        } else {
+            SignedPayload::Buffered(signed_prefix)
+        };
+
+        Ok(Self {
+            file: crate::file::FileStructure {
+                version_major: prefix.version_major,
+                version_minor: prefix.version_minor,
+                flags: prefix.flags,
+                header: prefix.header,
+                header_bytes: prefix.header_bytes.to_vec(),
+                payload_offset: prefix.len as u64,
+                payload_len: prefix.payload_len,
+                certificate_chain: Vec::new(),
+                cert_chain_bytes: Vec::new(),
+                signature: Vec::new(),
+                pq_signature: None,
+                co_signatures: Vec::new(),
+                countersignatures: Vec::new(),
+                timestamp_tokens: Vec::new(),
+                log_entries: Vec::new(),
+                opentimestamps: Vec::new(),
+                status_assertions: Vec::new(),
+            },
+            remaining: prefix.payload_len,
+            content_hasher: sha2::Sha256::new(),
+            merkle,
+            signed,
+        })
+    }
+
+    fn update(&mut self, chunk: &[u8]) {
+        use sha2::Digest;
+
+        self.remaining -= chunk.len() as u64;
+        self.content_hasher.update(chunk);
+        match (&mut self.merkle, &mut self.signed) {
+            (Some(merkle), _) => merkle.update(chunk),
+            (None, SignedPayload::Prehashed(digest)) => digest.update(chunk),
+            (None, SignedPayload::Buffered(input)) => input.extend_from_slice(chunk),
+        }
+    }
+}
+
+/// The signature input up to the end of the payload, shared by all signers
+enum SignedPayload {
+    /// SHA-512 state (format 1.1+)
+    Prehashed(Box<sha2::Sha512>),
+    /// The input itself, as pure Ed25519 needs the signer first (format 1.0)
+    Buffered(Vec<u8>),
+}
+
+impl SignedPayload {
+    /// A signature check for one signer, fed with the input so far
+    fn stream(&self, verifying_key: VerifyingKey, signature: Signature) -> Result<SignatureStream> {
+        match self {
+            Self::Prehashed(digest) => Ok(SignatureStream::Prehashed(
+                verifying_key,
+                signature,
+                (**digest).clone(),
+            )),
+            Self::Buffered(input) => {
+                let mut stream = SignatureStream::new(verifying_key, signature, false)?;
+                stream.update(input);
+                Ok(stream)
+            }
+        }
+    }
+}
+
+/// A signer's signature check, the input suffix that completes it and the
+/// signer's trust anchor
+type SignerStream = (SignatureStream, Vec<u8>, Vec<u8>);
+
+/// Check a signer's chain and start the check of its signature
+///
+/// `begin` creates the signature check for the signer's key.
+fn start_signature_stream(
+    chain: &[Certificate],
+    role: Option<&str>,
+    signature: &[u8],
+    trusted_root_keys: &[Vec<u8>],
+    options: &VerifyOptions,
+    begin: impl FnOnce(VerifyingKey, Signature) -> Result<SignatureStream>,
+) -> Result<SignerStream> {
+    use crate::signer::signature_input_suffix;
+
+    let anchor = verify_chain(chain, trusted_root_keys, options)?.anchor;
+
+    let cert_chain_bytes = co_signer_chain_bytes(chain, role)?;
+
+    let (verifying_key, signature) = signer_key_and_signature(&chain[0], signature)?;
+    Ok((
+        begin(verifying_key, signature)?,
+        signature_input_suffix(&cert_chain_bytes),
+        anchor,
+    ))
+}
+
+/// Signature checks fed with a file's signature input up to the end of its
+/// payload
+struct StreamedSignatures {
+    primary: SignatureStream,
+    co_streams: Vec<Result<SignerStream>>,
+    trust_anchor: Vec<u8>,
+}
+
+/// Complete a streamed verification once the payload has been read
+fn finish_streamed(
+    file: crate::file::FileStructure,
+    signatures: StreamedSignatures,
+    merkle: Option<merkle::MerkleBuilder>,
+    content_hasher: sha2::Sha256,
+    trusted_root_keys: &[Vec<u8>],
+    options: &VerifyOptions,
+) -> Result<VerificationResult> {
+    use crate::signer::signature_input_suffix;
+    use sha2::Digest;
+
+    // The primary chain is hashed as stored; co-signer chains are re-encoded
+    let StreamedSignatures {
+        mut primary,
+        co_streams,
+        trust_anchor,
+    } = signatures;
+    primary.update(&signature_input_suffix(&file.cert_chain_bytes));
+    if options.signature_policy.requires_ml_dsa() {
+        let SignatureStream::Prehashed(_, _, digest) = &primary else {
+            return Err(unprehashed_format(file.version_major, file.version_minor));
+        };
+        verify_pq_signature(
+            &file.certificate_chain[0],
+            &digest.clone().finalize(),
+            file.pq_signature.as_deref(),
+        )?;
+    }
+    if options.signature_policy.requires_ed25519() {
+        primary.finalize_and_verify()?;
+    }
+
+    if let Some(merkle) = merkle {
+        let chunks = chunk_info(&file.flags, &file.header)?;
+        if chunks.total_len != file.payload_len || !ct::eq(&merkle.finalize(), &chunks.merkle_root)
+        {
+            return Err(AletheiaError::ChunkVerification(
+                "Payload does not match the Merkle root".into(),
+            ));
+        }
+    }
+
+    // A compressed payload is hashed as stored, so its digest is unknown
+    let content_digest: Option<[u8; 32]> =
+        (!file.flags.is_compressed()).then(|| content_hasher.finalize().into());
+    if let (Some(expected), Some(digest)) = (&file.header.content_digest, &content_digest)
+        && !ct::eq(expected, digest)
+    {
+        return Err(AletheiaError::ContentDigestMismatch);
+    }
+
+    let co_signers = file
+        .co_signatures
+        .iter()
+        .zip(co_streams)
+        .map(|(co, stream)| {
+            let outcome = stream.and_then(|(mut stream, suffix, _)| {
+                stream.update(&suffix);
+                stream.finalize_and_verify()
+            });
+            CoSignerResult::new(co, outcome)
+        })
+        .collect();
+
+    // Get the creator's certificate (first in chain)
+    let creator_cert = &file.certificate_chain[0];
+    let warnings = collect_warnings(
+        (file.version_major, file.version_minor),
+        &file.header,
+        &file.certificate_chain,
+        options,
+    )?;
+
+    Ok(VerificationResult {
+        valid: true,
+        creator_id: creator_cert.subject_id.clone(),
+        creator_name: creator_cert.subject_name.clone(),
+        creator_fingerprint: Some(creator_cert.fingerprint()),
+        identity_attestation: creator_cert.identity_attestation.clone(),
+        parent_id: creator_cert.parent_id.clone(),
+        renewed_by: latest_renewal(&file.certificate_chain, options),
+        signed_at: file.header.signed_at,
+        ephemeral_key: creator_cert.single_use,
+        opentimestamps: verify_opentimestamps(&file.header, &file.opentimestamps, options),
+        description: file.header.description,
+        assertions: file.header.assertions,
+        co_signers,
+        countersigners: verify_countersignatures(
+            &file.signature,
+            &file.countersignatures,
+            trusted_root_keys,
+            options,
+        ),
+        timestamps: verify_timestamps(&file.signature, &file.timestamp_tokens, options),
+        log_entries: verify_log_entries(&file.signature, &file.log_entries, options)?,
+        warnings,
+        format_version: (file.version_major, file.version_minor),
+        content_digest,
+        payload_size: content_digest.map(|_| file.payload_len),
+        stored_size: file.payload_len,
+        trust_anchor: Some(trust_anchor),
+        chain_fingerprints: file
+            .certificate_chain
+            .iter()
+            .map(Certificate::fingerprint)
+            .collect(),
+        chain_serials: file
+            .certificate_chain
+            .iter()
+            .map(|cert| cert.serial.clone())
+            .collect(),
+    })
+}
+
+/// A signature check that is fed the signature input piece by piece
+enum SignatureStream {
+    /// Pure Ed25519 (format 1.0)
+    Pure(ed25519_dalek::StreamVerifier),
+    /// Ed25519ph over a SHA-512 prehash (format 1.1+)
+    Prehashed(VerifyingKey, Signature, sha2::Sha512),
+}
+
+impl SignatureStream {
+    fn new(verifying_key: VerifyingKey, signature: Signature, prehashed: bool) -> Result<Self> {
+        use sha2::Digest;
+
+        if prehashed {
+            return Ok(Self::Prehashed(
+                verifying_key,
+                signature,
+                sha2::Sha512::new(),
+            ));
+        }
+        let stream = verifying_key
+            .verify_stream(&signature)
+            .map_err(|_| AletheiaError::InvalidSignature)?;
+        Ok(Self::Pure(stream))
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        use sha2::Digest;
+
+        match self {
+            Self::Pure(stream) => stream.update(data),
+            Self::Prehashed(_, _, digest) => digest.update(data),
+        }
+    }
+
+    fn finalize_and_verify(self) -> Result<()> {
+        let verified =
+            match self {
+                Self::Pure(stream) => stream.finalize_and_verify(),
+                Self::Prehashed(verifying_key, signature, digest) => verifying_key
+                    .verify_prehashed(digest, Some(crate::signer::PREHASH_CONTEXT), &signature),
+            };
+        verified.map_err(|_| AletheiaError::InvalidSignature)
+    }
+}
+
+/// Quick check if an Aletheia file has valid structure (without full verification)
+pub fn validate_structure(file: &AletheiaFile) -> Result<()> {
+    // Check version
+    if file.version_major != 1 {
+        return Err(AletheiaError::UnsupportedVersion {
+            major: file.version_major,
+            minor: file.version_minor,
+        });
+    }
+
+    // Check certificate chain is not empty
+    if file.certificate_chain.is_empty() {
+        return Err(AletheiaError::CertificateChainInvalid {
+            step: 0,
+            reason: "Empty certificate chain".into(),
+        });
+    }
+
+    // Check signature length
+    if file.signature.len() != 64 {
+        return Err(AletheiaError::InvalidSignature);
+    }
+
+    // Verify creator ID in header matches certificate
+    let creator_cert = &file.certificate_chain[0];
+    if file.header.creator_id != creator_cert.subject_id {
+        return Err(AletheiaError::InvalidHeader(format!(
+            "Creator ID mismatch: header says '{}', certificate says '{}'",
+            file.header.creator_id, creator_cert.subject_id
+        )));
+    }
+
+    // Check each co-signature has a signer and a well-formed signature
+    for co in &file.co_signatures {
+        if co.certificate_chain.is_empty() {
+            return Err(AletheiaError::CertificateChainInvalid {
+                step: 0,
+                reason: "Empty co-signer certificate chain".into(),
+            });
+        }
+        if co.signature.len() != 64 {
+            return Err(AletheiaError::InvalidSignature);
+        }
+    }
+
+    // Same for countersignatures
+    for cs in &file.countersignatures {
+        if cs.certificate_chain.is_empty() {
+            return Err(AletheiaError::CertificateChainInvalid {
+                step: 0,
+                reason: "Empty countersigner certificate chain".into(),
+            });
+        }
+        if cs.signature.len() != 64 {
+            return Err(AletheiaError::InvalidSignature);
+        }
+    }
+
+    // Chunked files must describe their chunk layout
+    if file.flags.is_chunked() {
+        chunk_info(&file.flags, &file.header)?;
+    }
+
+    // Detached manifests bind their content by digest and carry no payload
+    if file.flags.is_detached()
+        && (file.header.content_digest.is_none() || !file.payload.is_empty())
+    {
+        return Err(AletheiaError::InvalidHeader(
+            "Detached manifest must have a content digest and no payload".into(),
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Header,
+        ca::{CertificateAuthority, SigningKeyPair},
+        signer::Signer,
+    };
+
+    fn create_test_file() -> (AletheiaFile, Vec<Vec<u8>>) {
+        let timestamp = 1704067200;
+        let ca =
+            CertificateAuthority::new_root_with_timestamp("root@example.com", "Root CA", timestamp);
+        let user_keys = SigningKeyPair::generate();
+
+        let user_cert = ca
+            .issue_certificate_with_timestamp(
+                "alice@example.com",
+                "Alice",
+                &user_keys.public_key(),
+                false,
+                timestamp,
+            )
+            .unwrap();
+
+        let chain = vec![user_cert, ca.certificate.clone()];
+        let signer = Signer::new(user_keys, chain).unwrap();
+
+        let payload = b"Test content";
+        let header = Header::new_with_timestamp("alice@example.com", timestamp)
+            .with_description("Test file");
+
+        let file = signer.sign(payload, header).unwrap();
+        let trusted_roots = vec![ca.public_key()];
+
+        (file, trusted_roots)
+    }
+
+    #[test]
+    fn test_verify_valid_file() {
+        let (file, trusted_roots) = create_test_file();
+
+        let result = verify(&file, &trusted_roots).unwrap();
+
+        assert!(result.valid);
+        assert_eq!(result.creator_id, "alice@example.com");
+        assert_eq!(result.creator_name, "Alice");
+        assert_eq!(result.description, Some("Test file".to_string()));
+    }
+
+    #[test]
+    fn test_verify_ref() {
+        let (file, trusted_roots) = create_test_file();
+        let mut bytes = crate::file::to_bytes(&file).unwrap();
+
+        let borrowed = crate::file::from_bytes_ref(&bytes).unwrap();
+        // The payload is a slice of the input, not a copy
+        let range = bytes.as_ptr_range();
+        assert!(range.contains(&borrowed.payload.as_ptr()));
+        let result = verify_ref(&borrowed, &trusted_roots).unwrap();
+        assert_eq!(result.creator_id, "alice@example.com");
+        assert_eq!(result.description, Some("Test file".to_string()));
+        let owned = borrowed.into_owned().unwrap();
+        assert_eq!(owned.payload, file.payload);
+        assert_eq!(owned.certificate_chain.len(), 2);
+
+        // Tampering with the buffer is caught
+        let payload_end = bytes.len() - 64 - 4 - {
+            let borrowed = crate::file::from_bytes_ref(&bytes).unwrap();
+            borrowed.cert_chain_bytes.len()
+        };
+        bytes[payload_end - 1] ^= 0xFF;
+        let borrowed = crate::file::from_bytes_ref(&bytes).unwrap();
+        assert!(verify_ref(&borrowed, &trusted_roots).is_err());
+    }
+
+    #[test]
+    fn test_verify_reports_payload_metadata() {
+        use sha2::Digest;
+        let (file, trusted_roots) = create_test_file();
+
+        let result = verify(&file, &trusted_roots).unwrap();
+        let digest: [u8; 32] = sha2::Sha256::digest(b"Test content").into();
+        assert_eq!(
+            result.format_version,
+            (file.version_major, file.version_minor)
+        );
+        assert_eq!(result.content_digest, Some(digest));
+        assert_eq!(result.payload_size, Some(12));
+        assert_eq!(result.stored_size, 12);
+        let fingerprints: Vec<_> = file
+            .certificate_chain
+            .iter()
+            .map(|c| c.fingerprint())
+            .collect();
+        assert_eq!(result.chain_fingerprints, fingerprints);
+        assert_eq!(result.chain_serials[0], file.certificate_chain[0].serial);
+
+        #[cfg(feature = "std")]
+        {
+            let bytes = crate::file::to_bytes(&file).unwrap();
+            let streamed = verify_stream(std::io::Cursor::new(&bytes), &trusted_roots).unwrap();
+            assert_eq!(streamed.content_digest, Some(digest));
+            assert_eq!(streamed.chain_fingerprints, fingerprints);
+        }
+    }
+
+    #[test]
+    fn test_verify_bytes() {
+        let (file, trusted_roots) = create_test_file();
+        let mut bytes = crate::file::to_bytes(&file).unwrap();
+
+        let (result, payload) = verify_bytes(&bytes, &trusted_roots).unwrap();
+        assert_eq!(result.creator_id, "alice@example.com");
+        assert_eq!(payload, b"Test content");
+
+        #[cfg(feature = "std")]
+        {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("test.alx");
+            std::fs::write(&path, &bytes).unwrap();
+            let (result, payload) = verify_path(&path, &trusted_roots).unwrap();
+            assert_eq!(result.creator_id, "alice@example.com");
+            assert_eq!(payload, b"Test content");
+        }
+
+        let len = bytes.len();
+        bytes[len - 1] ^= 0xFF;
+        assert!(verify_bytes(&bytes, &trusted_roots).is_err());
+    }
+
+    #[test]
+    fn test_verify_untrusted_root() {
+        let (file, _) = create_test_file();
+
+        // Use a different trusted root
+        let other_ca = CertificateAuthority::new_root_with_timestamp(
+            "other@example.com",
+            "Other CA",
+            1704067200,
+        );
+        let wrong_roots = vec![other_ca.public_key()];
+
+        let error = verify(&file, &wrong_roots).unwrap_err();
+        assert!(matches!(error.cause(), AletheiaError::UntrustedRoot));
+        assert_eq!(error.chain_step(), Some(1));
+    }
+
+    #[test]
+    fn test_verify_tampered_payload() {
+        let (mut file, trusted_roots) = create_test_file();
+
+        // Tamper with the payload
+        file.payload = b"Tampered content".to_vec();
+
+        let result = verify(&file, &trusted_roots);
+        assert!(matches!(result, Err(AletheiaError::InvalidSignature)));
+    }
+
+    #[test]
+    fn test_verify_tampered_header() {
+        let (mut file, trusted_roots) = create_test_file();
+
+        // Tamper with the header
+        file.header.description = Some("Tampered description".to_string());
+
+        let result = verify(&file, &trusted_roots);
+        assert!(matches!(result, Err(AletheiaError::InvalidSignature)));
+    }
+
+    #[test]
+    fn test_verify_tampered_certificate() {
+        let (mut file, trusted_roots) = create_test_file();
+
+        // Rename the creator; their CA's signature no longer covers the certificate
+        file.certificate_chain[0].subject_name = "Mallory".into();
+
+        let error = verify(&file, &trusted_roots).unwrap_err();
+        assert!(matches!(
+            error.cause(),
+            AletheiaError::CertificateSignatureInvalid(_)
+        ));
+        assert_eq!(error.chain_step(), Some(0));
+        assert_eq!(error.kind(), crate::ErrorKind::Chain);
+        assert!(error.is_tampering());
+
+        // An untrusted root is a trust failure, not tampering
+        let (file, _) = create_test_file();
+        assert!(!verify(&file, &[]).unwrap_err().is_tampering());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_verify_stream() {
+        let (file, trusted_roots) = create_test_file();
+        let bytes = crate::file::to_bytes(&file).unwrap();
+
+        let result = verify_stream(std::io::Cursor::new(&bytes), &trusted_roots).unwrap();
+        assert!(result.valid);
+        assert_eq!(result.creator_id, "alice@example.com");
+
+        // Tamper with the payload bytes in place
+        let mut tampered = bytes.clone();
+        let offset = bytes
+            .windows(file.payload.len())
+            .position(|w| w == file.payload.as_slice())
+            .unwrap();
+        tampered[offset] ^= 0xff;
+        let result = verify_stream(std::io::Cursor::new(&tampered), &trusted_roots);
+        assert!(matches!(result, Err(AletheiaError::InvalidSignature)));
+    }
+
+    /// Create a co-signer under a separate root, returning it with that root's key
+    fn create_co_signer(id: &str) -> (Signer, Vec<u8>) {
+        let timestamp = 1704067200;
+        let ca = CertificateAuthority::new_root_with_timestamp(
+            "publisher-root@example.com",
+            "Publisher Root",
+            timestamp,
+        );
+        let keys = SigningKeyPair::generate();
+        let cert = ca
+            .issue_certificate_with_timestamp(id, "Publisher", &keys.public_key(), false, timestamp)
+            .unwrap();
+        let signer = Signer::new(keys, vec![cert, ca.certificate.clone()]).unwrap();
+        (signer, ca.public_key())
+    }
+
+    #[test]
+    fn test_verify_co_signed_file() {
+        let (file, mut trusted_roots) = create_test_file();
+        let (co_signer, co_root) = create_co_signer("publisher@example.com");
+        let file = co_signer.co_sign(&file).unwrap();
+
+        // Co-signer's root not trusted: primary still verifies, co-signer does not
+        let result = verify(&file, &trusted_roots).unwrap();
+        assert_eq!(result.creator_id, "alice@example.com");
+        assert_eq!(result.co_signers.len(), 1);
+        assert!(!result.co_signers[0].valid);
+
+        trusted_roots.push(co_root);
+        let result = verify(&file, &trusted_roots).unwrap();
+        assert!(result.co_signers[0].valid);
+        assert_eq!(result.co_signers[0].signer_id, "publisher@example.com");
+
+        // Co-signatures survive serialization
+        let bytes = crate::file::to_bytes(&file).unwrap();
+        let loaded = crate::file::from_bytes(&bytes).unwrap();
+        assert_eq!(loaded.co_signatures.len(), 1);
+        assert!(verify(&loaded, &trusted_roots).unwrap().co_signers[0].valid);
+    }
+
+    #[test]
+    fn test_verify_tampered_co_signature() {
+        let (file, mut trusted_roots) = create_test_file();
+        let (co_signer, co_root) = create_co_signer("publisher@example.com");
+        let mut file = co_signer.co_sign(&file).unwrap();
+        trusted_roots.push(co_root);
+
+        file.co_signatures[0].signature[0] ^= 0xff;
+
+        let result = verify(&file, &trusted_roots).unwrap();
+        assert!(!result.co_signers[0].valid);
+        assert!(result.co_signers[0].error.is_some());
+    }
+
+    #[test]
+    fn test_verify_reports_trust_anchor() {
+        let (file, trusted_roots) = create_test_file();
+        let (co_signer, co_root) = create_co_signer("publisher@example.com");
+        let file = co_signer.co_sign(&file).unwrap();
+
+        // With several roots trusted, the result names the one that anchored the chain
+        let roots = vec![co_root, trusted_roots[0].clone()];
+        let result = verify(&file, &roots).unwrap();
+        assert_eq!(result.trust_anchor.as_ref(), Some(&trusted_roots[0]));
+        let bytes = crate::file::to_bytes(&file).unwrap();
+        let (result, _) = verify_bytes(&bytes, &roots).unwrap();
+        assert_eq!(result.trust_anchor.as_ref(), Some(&trusted_roots[0]));
+    }
+
+    #[test]
+    fn test_verify_co_signature_role() {
+        let (file, mut trusted_roots) = create_test_file();
+        let (co_signer, co_root) = create_co_signer("editor@example.com");
+        let mut file = co_signer.co_sign_with_role(&file, "reviewed").unwrap();
+        trusted_roots.push(co_root);
+
+        let bytes = crate::file::to_bytes(&file).unwrap();
+        let loaded = crate::file::from_bytes(&bytes).unwrap();
+        let result = verify(&loaded, &trusted_roots).unwrap();
+        assert!(result.co_signers[0].valid);
+        assert_eq!(result.co_signers[0].role.as_deref(), Some("reviewed"));
+
+        // The role is signed: promoting a review to publication breaks it
+        file.co_signatures[0].role = Some("published".into());
+        assert!(!verify(&file, &trusted_roots).unwrap().co_signers[0].valid);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_verify_stream_co_signed() {
+        let (file, mut trusted_roots) = create_test_file();
+        let (co_signer, co_root) = create_co_signer("publisher@example.com");
+        let file = co_signer.co_sign(&file).unwrap();
+        trusted_roots.push(co_root);
+
+        let bytes = crate::file::to_bytes(&file).unwrap();
+        let result = verify_stream(std::io::Cursor::new(&bytes), &trusted_roots).unwrap();
+        assert_eq!(result.co_signers.len(), 1);
+        assert!(result.co_signers[0].valid);
+    }
+
+    /// Verify `bytes` with an [`IncrementalVerifier`], fed `piece` bytes at a time
+    fn verify_incremental(
+        bytes: &[u8],
+        trusted_roots: &[Vec<u8>],
+        piece: usize,
+    ) -> Result<VerificationResult> {
+        let mut verifier = IncrementalVerifier::new(trusted_roots, &VerifyOptions::default());
+        for chunk in bytes.chunks(piece) {
+            verifier.update(chunk)?;
+        }
+        verifier.finish()
+    }
+
+    #[test]
+    fn test_incremental_verifier() {
+        let (file, mut trusted_roots) = create_test_file();
+        let (co_signer, co_root) = create_co_signer("publisher@example.com");
+        let file = co_signer.co_sign(&file).unwrap();
+        trusted_roots.push(co_root);
+        let bytes = crate::file::to_bytes(&file).unwrap();
+
+        let result = verify_incremental(&bytes, &trusted_roots, 3).unwrap();
+        assert_eq!(result.creator_id, "alice@example.com");
+        assert!(result.co_signers[0].valid);
+        assert_eq!(result.stored_size, file.payload.len() as u64);
+
+        // Tampering with the payload is caught at the end
+        let mut tampered = bytes.clone();
+        let offset = bytes
+            .windows(file.payload.len())
+            .position(|w| w == file.payload.as_slice())
+            .unwrap();
+        tampered[offset] ^= 0xff;
+        assert!(matches!(
+            verify_incremental(&tampered, &trusted_roots, 4096),
+            Err(AletheiaError::InvalidSignature)
+        ));
+
+        // So is a file cut short
+        assert!(matches!(
+            verify_incremental(&bytes[..offset + 1], &trusted_roots, 4096),
+            Err(AletheiaError::UnexpectedEof)
+        ));
+    }
+
+    #[test]
+    fn test_verify_countersigned_file() {
+        let (file, mut trusted_roots) = create_test_file();
+        let (notary, notary_root) = create_co_signer("notary@example.com");
+        let file = notary
+            .countersign_with_timestamp(&file, 1704153600)
+            .unwrap();
+        trusted_roots.push(notary_root);
+
+        let bytes = crate::file::to_bytes(&file).unwrap();
+        let loaded = crate::file::from_bytes(&bytes).unwrap();
+
+        let result = verify(&loaded, &trusted_roots).unwrap();
+        assert_eq!(result.countersigners.len(), 1);
+        assert!(result.countersigners[0].valid);
+        assert_eq!(result.countersigners[0].signer_id, "notary@example.com");
+        assert_eq!(result.countersigners[0].signed_at, 1704153600);
+
+        // Moving the countersigning time breaks the countersignature only
+        let mut backdated = loaded.clone();
+        backdated.countersignatures[0].signed_at = 1704067200;
+        let result = verify(&backdated, &trusted_roots).unwrap();
+        assert!(!result.countersigners[0].valid);
+    }
+
+    #[test]
+    fn test_verify_expired_certificate() {
+        let timestamp = 1704067200;
+        let ca =
+            CertificateAuthority::new_root_with_timestamp("root@example.com", "Root CA", timestamp);
+        let user_keys = SigningKeyPair::generate();
+        let user_cert = ca
+            .issue_certificate_with_options(
+                "alice@example.com",
+                "Alice",
+                &user_keys.public_key(),
+                false,
+                timestamp,
+                &crate::ca::IssueOptions::new().with_validity(86400),
+            )
+            .unwrap();
+        let signer = Signer::new(user_keys, vec![user_cert, ca.certificate.clone()]).unwrap();
+        let file = signer
+            .sign(
+                b"Test content",
+                Header::new_with_timestamp("alice@example.com", timestamp),
+            )
+            .unwrap();
+        let trusted_roots = vec![ca.public_key()];
+
+        let options = VerifyOptions::new().with_validity_check(ValidityCheck::At(2000000000));
+        let error = verify_with_options(&file, &trusted_roots, &options).unwrap_err();
+        assert!(matches!(
+            error.cause(),
+            AletheiaError::CertificateExpired(_)
+        ));
+
+        let options = VerifyOptions::new().archival();
+        verify_with_options(&file, &trusted_roots, &options).unwrap();
+    }
+
+    #[test]
+    fn test_verify_revoked_certificate() {
+        use crate::revocation::RevokedCertificate;
+
+        let timestamp = 1704067200;
+        let ca =
+            CertificateAuthority::new_root_with_timestamp("root@example.com", "Root CA", timestamp);
+        let user_keys = SigningKeyPair::generate();
+        let user_cert = ca
+            .issue_certificate_with_timestamp(
+                "alice@example.com",
+                "Alice",
+                &user_keys.public_key(),
+                false,
+                timestamp,
+            )
+            .unwrap();
+        let serial = user_cert.serial.clone();
+        let signer = Signer::new(user_keys, vec![user_cert, ca.certificate.clone()]).unwrap();
+        let file = signer
+            .sign(
+                b"Test content",
+                Header::new_with_timestamp("alice@example.com", timestamp),
+            )
+            .unwrap();
+        let trusted_roots = vec![ca.public_key()];
+
+        let crl = ca
+            .create_revocation_list_with_timestamp(Vec::new(), timestamp)
+            .unwrap();
+        verify_with_revocations(&file, &trusted_roots, &crl).unwrap();
+
+        let crl = ca
+            .create_revocation_list_with_timestamp(
+                vec![RevokedCertificate::new(serial, timestamp).with_reason("key compromise")],
+                timestamp,
+            )
+            .unwrap();
+        let result = verify_with_revocations(&file, &trusted_roots, &crl);
+        assert!(matches!(result, Err(AletheiaError::CertificateRevoked(_))));
+    }
+
+    #[test]
+    fn test_verify_at_signing_time() {
+        use crate::revocation::RevokedCertificate;
+
+        let timestamp = 1704067200;
+        let year = 365 * 86400;
+        let ca =
+            CertificateAuthority::new_root_with_timestamp("root@example.com", "Root CA", timestamp);
+        let user_keys = SigningKeyPair::generate();
+        let user_cert = ca
+            .issue_certificate_with_options(
+                "alice@example.com",
+                "Alice",
+                &user_keys.public_key(),
+                false,
+                timestamp,
+                &crate::ca::IssueOptions::new().with_validity(year),
+            )
+            .unwrap();
+        let serial = user_cert.serial.clone();
+        let signer = Signer::new(user_keys, vec![user_cert, ca.certificate.clone()]).unwrap();
+        let file = signer
+            .sign(
+                b"Test content",
+                Header::new_with_timestamp("alice@example.com", timestamp),
+            )
+            .unwrap();
+        let trusted_roots = vec![ca.public_key()];
+
+        // Valid when signed, long expired now
+        verify_at(&file, &trusted_roots, timestamp + 86400).unwrap();
+        let result = verify_at(&file, &trusted_roots, timestamp + 2 * year);
+        assert!(matches!(
+            result.as_ref().map_err(AletheiaError::cause),
+            Err(AletheiaError::CertificateExpired(_))
+        ));
+        let result = verify_at(&file, &trusted_roots, timestamp - 1);
+        assert!(matches!(
+            result.as_ref().map_err(AletheiaError::cause),
+            Err(AletheiaError::CertificateNotYetValid(_))
+        ));
+
+        // Revoked after signing: only verification past the revocation fails
+        let crl = ca
+            .create_revocation_list_with_timestamp(
+                vec![RevokedCertificate::new(serial, timestamp + 30 * 86400)],
+                timestamp + 30 * 86400,
+            )
+            .unwrap();
+        let options = VerifyOptions::new()
+            .at(timestamp + 86400)
+            .with_revocation_list(crl.clone());
+        verify_with_options(&file, &trusted_roots, &options).unwrap();
+        let options = VerifyOptions::new()
+            .at(timestamp + 60 * 86400)
+            .with_revocation_list(crl);
+        let result = verify_with_options(&file, &trusted_roots, &options);
+        assert!(matches!(result, Err(AletheiaError::CertificateRevoked(_))));
+    }
+
+    #[tokio::test]
+    async fn test_verify_with_checker() {
+        use crate::revocation::{FailurePolicy, RevocationChecker, RevokedCertificate};
+
+        /// Revokes `serial` at `revoked_at`, or fails for any other serial
+        struct Checker {
+            serial: Vec<u8>,
+            revoked_at: i64,
+        }
+
+        impl RevocationChecker for Checker {
+            async fn check(
+                &self,
+                _issuer_id: &str,
+                serial: &[u8],
+            ) -> Result<Option<RevokedCertificate>> {
+                if serial == self.serial {
+                    Ok(Some(RevokedCertificate::new(serial, self.revoked_at)))
+                } else {
+                    Err(AletheiaError::RevocationCheck("unreachable".into()))
+                }
+            }
+        }
+
+        let timestamp = 1704067200;
+        let ca =
+            CertificateAuthority::new_root_with_timestamp("root@example.com", "Root CA", timestamp);
+        let user_keys = SigningKeyPair::generate();
+        let user_cert = ca
+            .issue_certificate_with_timestamp(
+                "alice@example.com",
+                "Alice",
+                &user_keys.public_key(),
+                false,
+                timestamp,
+            )
+            .unwrap();
+        let serial = user_cert.serial.clone();
+        let signer = Signer::new(user_keys, vec![user_cert, ca.certificate.clone()]).unwrap();
+        let file = signer
+            .sign(
+                b"Test content",
+                Header::new_with_timestamp("alice@example.com", timestamp),
+            )
+            .unwrap();
+        let trusted_roots = vec![ca.public_key()];
+        let options = VerifyOptions::new().at(timestamp + 60 * 86400);
+
+        let revoked = Checker {
+            serial,
+            revoked_at: timestamp + 30 * 86400,
+        };
+        let result = verify_with_checker(
+            &file,
+            &trusted_roots,
+            &options,
+            &revoked,
+            FailurePolicy::SoftFail,
+        )
+        .await;
+        assert!(matches!(
+            result.as_ref().map_err(AletheiaError::cause),
+            Err(AletheiaError::CertificateRevoked(_))
+        ));
+
+        // Revoked after the verification time
+        let earlier = options.clone().at(timestamp + 86400);
+        verify_with_checker(
+            &file,
+            &trusted_roots,
+            &earlier,
+            &revoked,
+            FailurePolicy::HardFail,
+        )
+        .await
+        .unwrap();
+
+        // An unreachable service only fails verification under hard-fail
+        let unreachable = Checker {
+            serial: Vec::new(),
+            revoked_at: timestamp,
+        };
+        verify_with_checker(
+            &file,
+            &trusted_roots,
+            &options,
+            &unreachable,
+            FailurePolicy::SoftFail,
+        )
+        .await
+        .unwrap();
+        let result = verify_with_checker(
+            &file,
+            &trusted_roots,
+            &options,
+            &unreachable,
+            FailurePolicy::HardFail,
+        )
+        .await;
+        assert!(matches!(result, Err(AletheiaError::RevocationCheck(_))));
+    }
+
+    #[tokio::test]
+    async fn test_verify_with_checker_follows_verified_path() {
+        use crate::revocation::{FailurePolicy, RevocationChecker, RevokedCertificate};
+
+        /// Revokes `serial` and reports every other certificate good
+        struct Checker {
+            serial: Vec<u8>,
+        }
+
+        impl RevocationChecker for Checker {
+            async fn check(
+                &self,
+                _issuer_id: &str,
+                serial: &[u8],
+            ) -> Result<Option<RevokedCertificate>> {
+                Ok((serial == self.serial).then(|| RevokedCertificate::new(serial, 0)))
+            }
+        }
+
+        let timestamp = 1704067200;
+        let root =
+            CertificateAuthority::new_root_with_timestamp("root@example.com", "Root CA", timestamp);
+        let intermediate_keys = SigningKeyPair::generate();
+        let intermediate_cert = root
+            .issue_certificate_with_timestamp(
+                "intermediate@example.com",
+                "Intermediate CA",
+                &intermediate_keys.public_key(),
+                true,
+                timestamp,
+            )
+            .unwrap();
+        let intermediate = CertificateAuthority::from_key_and_cert(
+            &intermediate_keys.private_key_bytes(),
+            intermediate_cert.clone(),
+        )
+        .unwrap();
+        let user_keys = SigningKeyPair::generate();
+        let user_cert = intermediate
+            .issue_certificate_with_timestamp(
+                "alice@example.com",
+                "Alice",
+                &user_keys.public_key(),
+                false,
+                timestamp,
+            )
+            .unwrap();
+        // Stored out of order, so the intermediate is last in the chain
+        let signer = Signer::new(
+            user_keys,
+            vec![
+                user_cert,
+                root.certificate.clone(),
+                intermediate_cert.clone(),
+            ],
+        )
+        .unwrap();
+        let file = signer
+            .sign(
+                b"Test content",
+                Header::new_with_timestamp("alice@example.com", timestamp),
+            )
+            .unwrap();
+        let trusted_roots = vec![root.public_key()];
+        let options = VerifyOptions::new().at(timestamp + 86400);
+        verify_with_options(&file, &trusted_roots, &options).unwrap();
+
+        let result = verify_with_checker(
+            &file,
+            &trusted_roots,
+            &options,
+            &Checker {
+                serial: intermediate_cert.serial,
+            },
+            FailurePolicy::HardFail,
+        )
+        .await;
+        let error = result.unwrap_err();
+        assert!(matches!(
+            error.cause(),
+            AletheiaError::CertificateRevoked(_)
+        ));
+        assert_eq!(error.chain_step(), Some(1));
+    }
+
+    #[test]
+    fn test_verify_across_ca_rotation() {
+        let timestamp = 1704067200;
+        let transition = 30 * 86400;
+        let old_ca =
+            CertificateAuthority::new_root_with_timestamp("root@example.com", "Root CA", timestamp);
+        let rotation = old_ca.rotate_with_timestamp(timestamp, transition).unwrap();
+
+        // Signed before the rotation, checked by a verifier trusting only the new root
+        let user_keys = SigningKeyPair::generate();
+        let user_cert = old_ca
+            .issue_certificate_with_timestamp(
+                "alice@example.com",
+                "Alice",
+                &user_keys.public_key(),
+                false,
+                timestamp,
+            )
+            .unwrap();
+        let signer = Signer::new(user_keys, vec![user_cert, old_ca.certificate.clone()]).unwrap();
+        let file = signer
+            .sign(
+                b"Test content",
+                Header::new_with_timestamp("alice@example.com", timestamp),
+            )
+            .unwrap();
+        let trusted_roots = vec![rotation.successor.public_key()];
+
+        let during = VerifyOptions::new()
+            .with_validity_check(ValidityCheck::At(timestamp + 86400))
+            .with_cross_certificate(rotation.old_signed_by_new.clone());
+        let result = verify_with_options(&file, &trusted_roots, &during).unwrap();
+        // Attributed to the trusted root that vouched for the old one
+        assert_eq!(result.trust_anchor, Some(rotation.successor.public_key()));
+
+        // Once the transition window closes, the old root is no longer accepted
+        let after = VerifyOptions::new()
+            .with_validity_check(ValidityCheck::At(timestamp + transition + 1))
+            .with_cross_certificate(rotation.old_signed_by_new.clone());
+        assert!(matches!(
+            verify_with_options(&file, &trusted_roots, &after)
+                .as_ref()
+                .map_err(AletheiaError::cause),
+            Err(AletheiaError::UntrustedRoot)
+        ));
+
+        // A cross-certificate only counts if a trusted root signed it
+        let unrelated = VerifyOptions::new()
+            .with_validity_check(ValidityCheck::At(timestamp + 86400))
+            .with_cross_certificate(rotation.new_signed_by_old);
+        assert!(verify_with_options(&file, &trusted_roots, &unrelated).is_err());
+    }
+
+    #[test]
+    fn test_verify_content_digest_mismatch() {
+        let timestamp = 1704067200;
+        let ca =
+            CertificateAuthority::new_root_with_timestamp("root@example.com", "Root CA", timestamp);
+        let user_keys = SigningKeyPair::generate();
+        let user_cert = ca
+            .issue_certificate_with_timestamp(
+                "alice@example.com",
+                "Alice",
+                &user_keys.public_key(),
+                false,
+                timestamp,
+            )
+            .unwrap();
+        let chain = vec![user_cert, ca.certificate.clone()];
+
+        // A signer that records the digest of different content
+        let mut file = Signer::new(
+            SigningKeyPair::from_bytes(&user_keys.private_key_bytes()).unwrap(),
+            chain,
+        )
+        .unwrap()
+        .sign(
+            b"Test content",
+            Header::new_with_timestamp("alice@example.com", timestamp),
+        )
+        .unwrap();
+        file.header = file.header.with_content_digest(b"Other content");
+
+        let mut header_bytes = Vec::new();
+        ciborium::into_writer(&file.header, &mut header_bytes).unwrap();
+        let mut cert_chain_bytes = Vec::new();
+        ciborium::into_writer(&file.certificate_chain, &mut cert_chain_bytes).unwrap();
+        file.signature = SignatureInput {
+            version_major: file.version_major,
+            version_minor: file.version_minor,
+            flags: &file.flags,
+            header_bytes: &header_bytes,
+            payload: &file.payload,
+            cert_chain_bytes: &cert_chain_bytes,
+        }
+        .sign(&user_keys.into())
+        .unwrap();
+
+        let trusted_roots = vec![ca.public_key()];
+        let result = verify(&file, &trusted_roots);
+        assert!(matches!(result, Err(AletheiaError::ContentDigestMismatch)));
+    }
+
+    #[test]
+    fn test_verify_version_1_0_file() {
+        let timestamp = 1704067200;
+        let ca =
+            CertificateAuthority::new_root_with_timestamp("root@example.com", "Root CA", timestamp);
+        let user_keys = SigningKeyPair::generate();
+        let user_cert = ca
+            .issue_certificate_with_timestamp(
+                "alice@example.com",
+                "Alice",
+                &user_keys.public_key(),
+                false,
+                timestamp,
+            )
+            .unwrap();
+        let chain = vec![user_cert, ca.certificate.clone()];
+        let mut file = Signer::new(
+            SigningKeyPair::from_bytes(&user_keys.private_key_bytes()).unwrap(),
+            chain,
+        )
+        .unwrap()
+        .sign(
+            b"Test content",
+            Header::new_with_timestamp("alice@example.com", timestamp),
+        )
+        .unwrap();
+
+        // Re-sign as a format 1.0 file: pure Ed25519 over the whole input
+        let mut header_bytes = Vec::new();
+        ciborium::into_writer(&file.header, &mut header_bytes).unwrap();
+        let mut cert_chain_bytes = Vec::new();
+        ciborium::into_writer(&file.certificate_chain, &mut cert_chain_bytes).unwrap();
+        file.version_minor = 0;
+        let input = SignatureInput {
+            version_major: 1,
+            version_minor: 0,
+            flags: &file.flags,
+            header_bytes: &header_bytes,
+            payload: &file.payload,
+            cert_chain_bytes: &cert_chain_bytes,
+        };
+        assert!(!input.is_prehashed());
+        file.signature = user_keys.sign(&input.to_vec());
+
+        let trusted_roots = vec![ca.public_key()];
+        verify(&file, &trusted_roots).unwrap();
+        #[cfg(feature = "std")]
+        {
+            let bytes = crate::file::to_bytes(&file).unwrap();
+            verify_stream(std::io::Cursor::new(&bytes), &trusted_roots).unwrap();
+            verify_incremental(&bytes, &trusted_roots, 5).unwrap();
+        }
+
+        // A prehashed signature is not accepted under the 1.0 scheme
+        file.signature = crate::hsm::KeyHandle::from(user_keys)
+            .sign_digest(input.digest(), crate::signer::PREHASH_CONTEXT)
+            .unwrap();
+        assert!(matches!(
+            verify(&file, &trusted_roots),
+            Err(AletheiaError::InvalidSignature)
+        ));
     }
 
     #[test]
-    fn test_verify_valid_file() {
+    fn test_verify_detached_manifest() {
+        let timestamp = 1704067200;
+        let ca =
+            CertificateAuthority::new_root_with_timestamp("root@example.com", "Root CA", timestamp);
+        let user_keys = SigningKeyPair::generate();
+        let user_cert = ca
+            .issue_certificate_with_timestamp(
+                "alice@example.com",
+                "Alice",
+                &user_keys.public_key(),
+                false,
+                timestamp,
+            )
+            .unwrap();
+        let signer = Signer::new(user_keys, vec![user_cert, ca.certificate.clone()]).unwrap();
+        let manifest = signer
+            .sign_detached(
+                b"Test content",
+                Header::new_with_timestamp("alice@example.com", timestamp),
+            )
+            .unwrap();
+        let trusted_roots = vec![ca.public_key()];
+
+        assert!(manifest.payload.is_empty());
+        validate_structure(&manifest).unwrap();
+        verify_detached(&manifest, b"Test content", &trusted_roots).unwrap();
+        assert!(matches!(
+            verify_detached(&manifest, b"Other content", &trusted_roots),
+            Err(AletheiaError::ContentDigestMismatch)
+        ));
+        assert!(matches!(
+            verify(&manifest, &trusted_roots),
+            Err(AletheiaError::DetachedPayload)
+        ));
+    }
+
+    #[test]
+    fn test_verify_reports_ephemeral_key() {
+        let (file, trusted_roots) = create_test_file();
+        assert!(!verify(&file, &trusted_roots).unwrap().ephemeral_key);
+
+        let timestamp = 1704067200;
+        let ca =
+            CertificateAuthority::new_root_with_timestamp("root@example.com", "Root CA", timestamp);
+        let ephemeral_keys = SigningKeyPair::generate();
+        let options = crate::ca::IssueOptions::new().single_use();
+        let ephemeral_cert = ca
+            .issue_certificate_with_options(
+                "alice@example.com",
+                "Alice",
+                &ephemeral_keys.public_key(),
+                false,
+                timestamp,
+                &options,
+            )
+            .unwrap();
+        assert!(
+            ca.issue_certificate_with_options(
+                "sub@example.com",
+                "Sub CA",
+                &SigningKeyPair::generate().public_key(),
+                true,
+                timestamp,
+                &options,
+            )
+            .is_err()
+        );
+
+        let signer =
+            Signer::new(ephemeral_keys, vec![ephemeral_cert, ca.certificate.clone()]).unwrap();
+        let file = signer
+            .sign(
+                b"Test content",
+                Header::new_with_timestamp("alice@example.com", timestamp),
+            )
+            .unwrap();
+        let result = verify(&file, &[ca.public_key()]).unwrap();
+        assert!(result.ephemeral_key);
+
+        // The marking is signed by the CA
+        let mut relabeled = file.clone();
+        relabeled.certificate_chain[0].single_use = false;
+        assert!(verify(&relabeled, &[ca.public_key()]).is_err());
+    }
+
+    #[test]
+    fn test_verify_reports_identity_attestation() {
+        use crate::{AttestationMethod, IdentityAttestation};
+
         let (file, trusted_roots) = create_test_file();
+        assert!(
+            verify(&file, &trusted_roots)
+                .unwrap()
+                .identity_attestation
+                .is_none()
+        );
+
+        let timestamp = 1704067200;
+        let ca =
+            CertificateAuthority::new_root_with_timestamp("root@example.com", "Root CA", timestamp);
+        let user_keys = SigningKeyPair::generate();
+        let attestation = IdentityAttestation::new(
+            AttestationMethod::GovId,
+            "Example Verification Ltd",
+            timestamp,
+        )
+        .with_evidence(b"passport check #1234");
+        let user_cert = ca
+            .issue_certificate_with_options(
+                "alice@example.com",
+                "Alice",
+                &user_keys.public_key(),
+                false,
+                timestamp,
+                &crate::ca::IssueOptions::new().with_identity_attestation(attestation.clone()),
+            )
+            .unwrap();
+        let signer = Signer::new(user_keys, vec![user_cert, ca.certificate.clone()]).unwrap();
+        let file = signer
+            .sign(
+                b"Test content",
+                Header::new_with_timestamp("alice@example.com", timestamp),
+            )
+            .unwrap();
+        let result = verify(&file, &[ca.public_key()]).unwrap();
+        assert_eq!(result.identity_attestation, Some(attestation));
+
+        // The attestation is signed by the CA
+        let mut upgraded = file.clone();
+        upgraded.certificate_chain[0]
+            .identity_attestation
+            .as_mut()
+            .unwrap()
+            .method = AttestationMethod::WebAuthn;
+        assert!(verify(&upgraded, &[ca.public_key()]).is_err());
+    }
+
+    #[test]
+    fn test_verify_warnings() {
+        let timestamp = 1704067200;
+        let ca =
+            CertificateAuthority::new_root_with_timestamp("root@example.com", "Root CA", timestamp);
+        let user_keys = SigningKeyPair::generate();
+        let user_cert = ca
+            .issue_certificate_with_options(
+                "alice@example.com",
+                "Alice",
+                &user_keys.public_key(),
+                false,
+                timestamp,
+                &crate::ca::IssueOptions::new().with_validity(60 * 86400),
+            )
+            .unwrap();
+        let signer = Signer::new(user_keys, vec![user_cert, ca.certificate.clone()]).unwrap();
+        let header = Header::new_with_timestamp("alice@example.com", timestamp)
+            .with_custom_typed("com.example:camera", &"X100")
+            .unwrap();
+        let file = signer.sign(b"Test content", header).unwrap();
+        let trusted_roots = vec![ca.public_key()];
+
+        // Early in the certificate's life only the custom key is unknown
+        let options = VerifyOptions::new().at(timestamp + 86400);
+        let result = verify_with_options(&file, &trusted_roots, &options).unwrap();
+        assert_eq!(
+            result.warnings,
+            vec![VerificationWarning::UnknownCustomKey(
+                "com.example:camera".into()
+            )]
+        );
+        let known = options.clone().with_known_custom_key("com.example:camera");
+        assert!(
+            verify_with_options(&file, &trusted_roots, &known)
+                .unwrap()
+                .warnings
+                .is_empty()
+        );
+
+        // Close to expiry
+        let late = known.clone().at(timestamp + 50 * 86400);
+        let result = verify_with_options(&file, &trusted_roots, &late).unwrap();
+        assert_eq!(result.warnings.len(), 1);
+        assert_eq!(
+            result.warnings[0].kind(),
+            WarningKind::CertificateNearExpiry
+        );
+
+        // Escalated warnings fail verification
+        let strict = late.escalate_warning(WarningKind::CertificateNearExpiry);
+        let result = verify_with_options(&file, &trusted_roots, &strict);
+        assert!(matches!(result, Err(AletheiaError::EscalatedWarning(_))));
+        let strict = known.escalate_warning(WarningKind::CertificateNearExpiry);
+        verify_with_options(&file, &trusted_roots, &strict).unwrap();
+    }
+
+    #[test]
+    fn test_verify_reports_renewal() {
+        let timestamp = 1704067200;
+        let ca =
+            CertificateAuthority::new_root_with_timestamp("root@example.com", "Root CA", timestamp);
+        let old_keys = SigningKeyPair::generate();
+        let old_cert = ca
+            .issue_certificate_with_timestamp(
+                "alice@example.com",
+                "Alice",
+                &old_keys.public_key(),
+                false,
+                timestamp,
+            )
+            .unwrap();
+        let signer = Signer::new(old_keys, vec![old_cert.clone(), ca.certificate.clone()]).unwrap();
+        let file = signer
+            .sign(
+                b"Test content",
+                Header::new_with_timestamp("alice@example.com", timestamp),
+            )
+            .unwrap();
+
+        // Renewed twice, the second time with a new key
+        let renewed = ca
+            .renew_with_timestamp(&old_cert, None, timestamp + 100)
+            .unwrap();
+        let current = ca
+            .renew_with_timestamp(
+                &renewed,
+                Some(&SigningKeyPair::generate().public_key()),
+                timestamp + 200,
+            )
+            .unwrap();
+        let options = VerifyOptions::new()
+            .with_renewed_certificate(current.clone())
+            .with_renewed_certificate(renewed.clone());
+        let result = verify_with_options(&file, &[ca.public_key()], &options).unwrap();
+        assert_eq!(result.renewed_by, Some(current.fingerprint()));
+
+        // Without the intermediate renewal the link is missing
+        let options = VerifyOptions::new().with_renewed_certificate(current.clone());
+        let result = verify_with_options(&file, &[ca.public_key()], &options).unwrap();
+        assert_eq!(result.renewed_by, None);
+
+        // A renewal claimed by another issuer is ignored
+        let other =
+            CertificateAuthority::new_root_with_timestamp("root@example.com", "Root CA", timestamp);
+        let mut forged = other
+            .issue_certificate_with_options(
+                "alice@example.com",
+                "Alice",
+                &SigningKeyPair::generate().public_key(),
+                false,
+                timestamp,
+                &crate::ca::IssueOptions::new().with_predecessor_serial(old_cert.serial.clone()),
+            )
+            .unwrap();
+        forged.issuer_id = ca.certificate.subject_id.clone();
+        let options = VerifyOptions::new().with_renewed_certificate(forged);
+        let result = verify_with_options(&file, &[ca.public_key()], &options).unwrap();
+        assert_eq!(result.renewed_by, None);
+    }
+
+    #[test]
+    fn test_verify_stapled_status() {
+        use crate::revocation::CertificateStatus;
+
+        let timestamp = 1704067200;
+        let ca =
+            CertificateAuthority::new_root_with_timestamp("root@example.com", "Root CA", timestamp);
+        let user_keys = SigningKeyPair::generate();
+        let user_cert = ca
+            .issue_certificate_with_timestamp(
+                "alice@example.com",
+                "Alice",
+                &user_keys.public_key(),
+                false,
+                timestamp,
+            )
+            .unwrap();
+        let good = ca
+            .sign_status_with_timestamp(&user_cert.serial, CertificateStatus::Good, 3600, timestamp)
+            .unwrap();
+        let revoked = ca
+            .sign_status_with_timestamp(
+                &user_cert.serial,
+                CertificateStatus::Revoked,
+                3600,
+                timestamp,
+            )
+            .unwrap();
+        let header = Header::new_with_timestamp("alice@example.com", timestamp);
+        let signer = Signer::new(user_keys, vec![user_cert, ca.certificate.clone()]).unwrap();
+        let unstapled = signer.sign(b"Test content", header.clone()).unwrap();
+        let file = signer
+            .with_status_assertion(good)
+            .sign(b"Test content", header)
+            .unwrap();
+        let file = crate::file::from_bytes(&crate::file::to_bytes(&file).unwrap()).unwrap();
+
+        let roots = [ca.public_key()];
+        let within = VerifyOptions::new()
+            .with_validity_check(ValidityCheck::At(timestamp + 60))
+            .require_status();
+        verify_with_options(&file, &roots, &within).unwrap();
+        assert!(matches!(
+            verify_with_options(&unstapled, &roots, &within),
+            Err(AletheiaError::MissingCertificateStatus(_))
+        ));
+
+        // A stale assertion no longer counts
+        let later = VerifyOptions::new()
+            .with_validity_check(ValidityCheck::At(timestamp + 7200))
+            .require_status();
+        assert!(verify_with_options(&file, &roots, &later).is_err());
+
+        // A revocation from the CA overrides the stapled good status
+        let with_revoked = VerifyOptions::new()
+            .with_validity_check(ValidityCheck::At(timestamp + 60))
+            .with_status_assertion(revoked);
+        assert!(matches!(
+            verify_with_options(&file, &roots, &with_revoked),
+            Err(AletheiaError::CertificateRevoked(_))
+        ));
+    }
+
+    #[test]
+    fn test_verify_reports_assertions() {
+        let timestamp = 1704067200;
+        let ca =
+            CertificateAuthority::new_root_with_timestamp("root@example.com", "Root CA", timestamp);
+        let user_keys = SigningKeyPair::generate();
+        let user_cert = ca
+            .issue_certificate_with_timestamp(
+                "alice@example.com",
+                "Alice",
+                &user_keys.public_key(),
+                false,
+                timestamp,
+            )
+            .unwrap();
+        let signer = Signer::new(user_keys, vec![user_cert, ca.certificate.clone()]).unwrap();
+        let header = Header::new_with_timestamp("alice@example.com", timestamp)
+            .with_assertion(Assertion::CreatedWith("camera".into()))
+            .with_assertion(Assertion::AiAssisted(false))
+            .with_assertion(Assertion::DeviceAttestation {
+                format: "test-attestation".into(),
+                data: vec![0xA7; 48],
+            });
+        let file = signer.sign(b"Test content", header).unwrap();
+        let trusted_roots = vec![ca.public_key()];
 
+        // Assertions survive serialization and are reported once verified
+        let file = crate::file::from_bytes(&crate::file::to_bytes(&file).unwrap()).unwrap();
         let result = verify(&file, &trusted_roots).unwrap();
+        assert_eq!(result.assertions.len(), 3);
+        assert!(matches!(&result.assertions[0], Assertion::CreatedWith(tool) if tool == "camera"));
+        assert!(matches!(result.assertions[1], Assertion::AiAssisted(false)));
+        assert!(matches!(
+            &result.assertions[2],
+            Assertion::DeviceAttestation { data, .. } if data.len() == 48
+        ));
 
-        assert!(result.valid);
-        assert_eq!(result.creator_id, "alice@example.com");
-        assert_eq!(result.creator_name, "Alice");
-        assert_eq!(result.description, Some("Test file".to_string()));
+        // They are covered by the signature
+        let mut tampered = file.clone();
+        tampered.header.assertions[1] = Assertion::AiAssisted(true);
+        assert!(verify(&tampered, &trusted_roots).is_err());
     }
 
     #[test]
-    fn test_verify_untrusted_root() {
-        let (file, _) = create_test_file();
+    fn test_verify_chunked_file() {
+        let timestamp = 1704067200;
+        let ca =
+            CertificateAuthority::new_root_with_timestamp("root@example.com", "Root CA", timestamp);
+        let user_keys = SigningKeyPair::generate();
+        let user_cert = ca
+            .issue_certificate_with_timestamp(
+                "alice@example.com",
+                "Alice",
+                &user_keys.public_key(),
+                false,
+                timestamp,
+            )
+            .unwrap();
+        let signer = Signer::new(user_keys, vec![user_cert, ca.certificate.clone()])
+            .unwrap()
+            .with_chunking(100);
+        let payload: Vec<u8> = (0..1000u32).map(|i| (i % 251) as u8).collect();
+        let file = signer
+            .sign(
+                &payload,
+                Header::new_with_timestamp("alice@example.com", timestamp),
+            )
+            .unwrap();
+        let trusted_roots = vec![ca.public_key()];
 
-        // Use a different trusted root
-        let other_ca = CertificateAuthority::new_root_with_timestamp(
-            "other@example.com",
-            "Other CA",
-            1704067200,
+        assert!(file.flags.is_chunked());
+        verify(&file, &trusted_roots).unwrap();
+
+        // A single chunk verifies without the rest of the payload
+        let (chunk, proof) = file.chunk_with_proof(3).unwrap();
+        let chunk = chunk.to_vec();
+        let mut partial = file.clone();
+        partial.payload.clear();
+        verify_chunk(&partial, &trusted_roots, 3, &chunk, &proof).unwrap();
+
+        let mut tampered_chunk = chunk.clone();
+        tampered_chunk[0] ^= 0xFF;
+        assert!(matches!(
+            verify_chunk(&partial, &trusted_roots, 3, &tampered_chunk, &proof),
+            Err(AletheiaError::ChunkVerification(_))
+        ));
+
+        // Tampering with the stored payload is caught by full verification
+        let mut tampered = file.clone();
+        tampered.payload[500] ^= 0xFF;
+        assert!(matches!(
+            verify(&tampered, &trusted_roots),
+            Err(AletheiaError::ChunkVerification(_))
+        ));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_verify_stream_chunked() {
+        let timestamp = 1704067200;
+        let ca =
+            CertificateAuthority::new_root_with_timestamp("root@example.com", "Root CA", timestamp);
+        let user_keys = SigningKeyPair::generate();
+        let user_cert = ca
+            .issue_certificate_with_timestamp(
+                "alice@example.com",
+                "Alice",
+                &user_keys.public_key(),
+                false,
+                timestamp,
+            )
+            .unwrap();
+        let signer = Signer::new(user_keys, vec![user_cert, ca.certificate.clone()])
+            .unwrap()
+            .with_chunking(7);
+        let payload = b"Streamed chunked content".repeat(10);
+        let header = Header::new_with_timestamp("alice@example.com", timestamp);
+
+        let mut bytes = Vec::new();
+        signer
+            .sign_stream(std::io::Cursor::new(&payload), header.clone(), &mut bytes)
+            .unwrap();
+        let expected = crate::file::to_bytes(&signer.sign(&payload, header).unwrap()).unwrap();
+        assert_eq!(bytes, expected);
+
+        let trusted_roots = vec![ca.public_key()];
+        verify_stream(std::io::Cursor::new(&bytes), &trusted_roots).unwrap();
+        verify_incremental(&bytes, &trusted_roots, 11).unwrap();
+    }
+
+    /// A file signed with an Ed25519 and an ML-DSA key under a hybrid root
+    #[cfg(feature = "ml-dsa")]
+    fn create_hybrid_file() -> (AletheiaFile, Vec<Vec<u8>>) {
+        use crate::{ca::IssueOptions, pq::MlDsaKeyPair};
+
+        let timestamp = 1704067200;
+        let ca = CertificateAuthority::new_root_hybrid_with_timestamp(
+            "root@example.com",
+            "Root CA",
+            timestamp,
         );
-        let wrong_roots = vec![other_ca.public_key()];
+        let user_keys = SigningKeyPair::generate();
+        let pq_keys = MlDsaKeyPair::generate();
+        let user_cert = ca
+            .issue_certificate_with_options(
+                "alice@example.com",
+                "Alice",
+                &user_keys.public_key(),
+                false,
+                timestamp,
+                &IssueOptions::new().with_pq_public_key(pq_keys.public_key()),
+            )
+            .unwrap();
+        let signer = Signer::new(user_keys, vec![user_cert, ca.certificate.clone()])
+            .unwrap()
+            .with_ml_dsa_key(pq_keys)
+            .unwrap();
+        let header = Header::new_with_timestamp("alice@example.com", timestamp);
+        let file = signer.sign(b"Test content", header).unwrap();
+        (file, vec![ca.public_key()])
+    }
+
+    #[cfg(feature = "ml-dsa")]
+    #[test]
+    fn test_verify_hybrid_file() {
+        let (file, trusted_roots) = create_hybrid_file();
+        assert!(file.pq_signature.is_some());
+        for policy in [
+            SignaturePolicy::Ed25519,
+            SignaturePolicy::MlDsa,
+            SignaturePolicy::Both,
+        ] {
+            let options = VerifyOptions::new().with_signature_policy(policy);
+            verify_with_options(&file, &trusted_roots, &options).unwrap();
+        }
 
-        let result = verify(&file, &wrong_roots);
-        assert!(matches!(result, Err(AletheiaError::UntrustedRoot)));
+        // The ML-DSA signature survives encoding, and every path checks it
+        let both = VerifyOptions::new().with_signature_policy(SignaturePolicy::Both);
+        let bytes = crate::file::to_bytes(&file).unwrap();
+        let parsed = crate::file::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed.pq_signature, file.pq_signature);
+        verify_with_options(&parsed, &trusted_roots, &both).unwrap();
+        let borrowed = crate::file::from_bytes_ref(&bytes).unwrap();
+        verify_ref_with_options(&borrowed, &trusted_roots, &both).unwrap();
+        #[cfg(feature = "std")]
+        verify_stream_with_options(std::io::Cursor::new(&bytes), &trusted_roots, &both).unwrap();
+        let mut verifier = IncrementalVerifier::new(&trusted_roots, &both);
+        verifier.update(&bytes).unwrap();
+        verifier.finish().unwrap();
     }
 
+    #[cfg(feature = "ml-dsa")]
     #[test]
-    fn test_verify_tampered_payload() {
-        let (mut file, trusted_roots) = create_test_file();
+    fn test_verify_hybrid_downgrade() {
+        let (file, trusted_roots) = create_hybrid_file();
+        let both = VerifyOptions::new().with_signature_policy(SignaturePolicy::Both);
 
-        // Tamper with the payload
-        file.payload = b"Tampered content".to_vec();
+        // Stripping the file's ML-DSA signature leaves a valid Ed25519 file
+        // that a hybrid policy rejects
+        let mut stripped = file.clone();
+        stripped.pq_signature = None;
+        verify(&stripped, &trusted_roots).unwrap();
+        assert!(matches!(
+            verify_with_options(&stripped, &trusted_roots, &both),
+            Err(AletheiaError::SignaturePolicy(_))
+        ));
+        #[cfg(feature = "std")]
+        {
+            let bytes = crate::file::to_bytes(&stripped).unwrap();
+            assert!(matches!(
+                verify_stream_with_options(std::io::Cursor::new(&bytes), &trusted_roots, &both),
+                Err(AletheiaError::SignaturePolicy(_))
+            ));
+        }
 
-        let result = verify(&file, &trusted_roots);
-        assert!(matches!(result, Err(AletheiaError::InvalidSignature)));
+        // So is stripping a certificate's ML-DSA signature
+        let mut stripped = file.clone();
+        stripped.certificate_chain[0].pq_signature = None;
+        assert!(matches!(
+            verify_with_options(&stripped, &trusted_roots, &both)
+                .as_ref()
+                .map_err(AletheiaError::cause),
+            Err(AletheiaError::SignaturePolicy(_))
+        ));
+
+        // The certified ML-DSA key cannot be removed without breaking the
+        // Ed25519 signatures
+        let mut stripped = file.clone();
+        stripped.certificate_chain[0].pq_public_key = None;
+        assert!(matches!(
+            verify(&stripped, &trusted_roots)
+                .as_ref()
+                .map_err(AletheiaError::cause),
+            Err(AletheiaError::CertificateSignatureInvalid(_))
+        ));
     }
 
+    #[cfg(feature = "ml-dsa")]
     #[test]
-    fn test_verify_tampered_header() {
-        let (mut file, trusted_roots) = create_test_file();
+    fn test_verify_hybrid_tampering() {
+        let (file, trusted_roots) = create_hybrid_file();
+        let ml_dsa = VerifyOptions::new().with_signature_policy(SignaturePolicy::MlDsa);
+        let both = VerifyOptions::new().with_signature_policy(SignaturePolicy::Both);
 
-        // Tamper with the header
-        file.header.description = Some("Tampered description".to_string());
+        // An altered ML-DSA signature only matters when ML-DSA is checked
+        let mut tampered = file.clone();
+        tampered.pq_signature.as_mut().unwrap()[0] ^= 1;
+        verify(&tampered, &trusted_roots).unwrap();
+        for options in [&ml_dsa, &both] {
+            assert!(matches!(
+                verify_with_options(&tampered, &trusted_roots, options),
+                Err(AletheiaError::InvalidSignature)
+            ));
+        }
 
-        let result = verify(&file, &trusted_roots);
-        assert!(matches!(result, Err(AletheiaError::InvalidSignature)));
+        // Likewise for an altered Ed25519 signature
+        let mut tampered = file.clone();
+        tampered.signature[0] ^= 1;
+        verify_with_options(&tampered, &trusted_roots, &ml_dsa).unwrap();
+        assert!(matches!(
+            verify_with_options(&tampered, &trusted_roots, &both),
+            Err(AletheiaError::InvalidSignature)
+        ));
+
+        // Tampered content and certificates fail under ML-DSA alone
+        let mut tampered = file.clone();
+        tampered.payload[0] ^= 1;
+        assert!(matches!(
+            verify_with_options(&tampered, &trusted_roots, &ml_dsa),
+            Err(AletheiaError::InvalidSignature)
+        ));
+        let mut tampered = file.clone();
+        tampered.certificate_chain[0].pq_signature.as_mut().unwrap()[0] ^= 1;
+        assert!(matches!(
+            verify_with_options(&tampered, &trusted_roots, &ml_dsa)
+                .as_ref()
+                .map_err(AletheiaError::cause),
+            Err(AletheiaError::CertificateSignatureInvalid(_))
+        ));
+    }
+
+    #[test]
+    fn test_verify_signature_policy_without_ml_dsa() {
+        let (file, trusted_roots) = create_test_file();
+        for policy in [SignaturePolicy::MlDsa, SignaturePolicy::Both] {
+            let options = VerifyOptions::new().with_signature_policy(policy);
+            assert!(matches!(
+                verify_with_options(&file, &trusted_roots, &options)
+                    .as_ref()
+                    .map_err(AletheiaError::cause),
+                Err(AletheiaError::SignaturePolicy(_))
+            ));
+        }
     }
 
     #[test]
@@ -1,24 +1,72 @@
 //! WASM bindings for browser use
 
+use js_sys::{Array, Reflect, Uint8Array};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::format;
+use std::string::String;
+use std::vec::Vec;
+use tsify::{Ts, Tsify};
 use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    Blob, Crypto, CryptoKey, IdbDatabase, IdbFactory, IdbObjectStore, IdbRequest,
+    IdbTransactionMode, ReadableStream, ReadableStreamDefaultReader, SubtleCrypto,
+};
 
 use crate::{
-    Certificate, Header,
-    ca::{CertificateAuthority, SigningKeyPair},
+    AletheiaError, Certificate, CompressionAlgorithm, Header, IdentityAttestation,
+    ca::{CertificateAuthority, IssueOptions, SigningKeyPair},
+    certificate::{
+        ChainBuilder, ValidityCheck, check_certificate_chain, verify_certificate_chain_at,
+    },
+    embed::MediaFormat,
     file::{from_bytes, to_bytes},
-    signer::Signer,
-    verifier::verify,
+    merkle::DEFAULT_CHUNK_SIZE,
+    request::CertificateRequest,
+    signer::{PendingFile, Signer},
+    trust_bundle::TrustBundle,
+    types::serde_cbor_value::Value,
+    verifier::{IncrementalVerifier, VerificationResult, VerifyOptions, verify_with_options},
 };
 
+#[cfg(feature = "wasm-parallel")]
+pub use wasm_bindgen_rayon::init_thread_pool;
+
 #[wasm_bindgen]
 extern "C" {
     #[wasm_bindgen(js_namespace = console)]
     fn log(s: &str);
 }
 
+// TypeScript shapes of library types the result objects embed
+#[wasm_bindgen(typescript_custom_section)]
+const TS_LIBRARY_TYPES: &'static str = r#"
+/** A CBOR value carried in a certificate extension; maps are entry lists */
+export type CborValue =
+    | undefined
+    | boolean
+    | number
+    | string
+    | number[]
+    | CborValue[]
+    | [string, CborValue][];
+
+/** How a certificate subject's identity was verified before issuance */
+export interface IdentityAttestation {
+    method: "email" | "gov_id" | "webauthn" | { other: string };
+    verifier: string;
+    attested_at: number;
+    /** SHA-256 of the verifier's evidence, base64 */
+    evidence_hash?: string;
+}
+"#;
+
 // Re-export types with serde
-#[derive(Serialize, Deserialize)]
+//
+// Returned to JS as plain objects, with `.d.ts` declarations generated by tsify
+#[derive(Serialize, Deserialize, Tsify)]
+#[tsify(hashmap_as_object)]
 #[serde(rename_all = "camelCase")]
 pub struct WasmHeader {
     pub creator_id: String,
@@ -26,36 +74,61 @@ pub struct WasmHeader {
     pub content_type: Option<String>,
     pub original_name: Option<String>,
     pub description: Option<String>,
+    #[serde(with = "serde_bytes")]
+    #[tsify(type = "Uint8Array | undefined")]
+    pub content_digest: Option<Vec<u8>>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Tsify)]
+#[tsify(hashmap_as_object)]
 #[serde(rename_all = "camelCase")]
 pub struct WasmCertificate {
     pub version: u8,
     #[serde(with = "serde_bytes")]
+    #[tsify(type = "Uint8Array")]
     pub serial: Vec<u8>,
     pub subject_id: String,
     pub subject_name: String,
     #[serde(with = "serde_bytes")]
+    #[tsify(type = "Uint8Array")]
     pub public_key: Vec<u8>,
     pub issuer_id: String,
     pub issued_at: i64,
     pub is_ca: bool,
+    pub path_len: Option<u32>,
+    pub not_before: Option<i64>,
+    pub not_after: Option<i64>,
+    pub single_use: bool,
+    #[tsify(type = "Record<string, CborValue>")]
+    pub extensions: BTreeMap<String, Value>,
+    pub identity_attestation: Option<IdentityAttestation>,
+    pub parent_id: Option<String>,
+    #[serde(with = "serde_bytes")]
+    #[tsify(type = "Uint8Array | undefined")]
+    pub predecessor_serial: Option<Vec<u8>>,
     #[serde(with = "serde_bytes")]
+    #[tsify(type = "Uint8Array")]
     pub signature: Vec<u8>,
+    /// SHA-256 fingerprint as lowercase hex
+    pub fingerprint: String,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Tsify)]
+#[tsify(hashmap_as_object)]
 #[serde(rename_all = "camelCase")]
 pub struct WasmParsedFile {
     pub version_major: u8,
     pub version_minor: u8,
     pub is_compressed: bool,
+    /// "lz4" or "zstd" when compressed
+    pub compression: Option<String>,
     pub header: WasmHeader,
     #[serde(with = "serde_bytes")]
+    #[tsify(type = "Uint8Array")]
     pub payload: Vec<u8>,
     pub certificate_chain: Vec<WasmCertificate>,
     #[serde(with = "serde_bytes")]
+    #[tsify(type = "Uint8Array")]
     pub signature: Vec<u8>,
 
     // Byte ranges for hex highlighting
@@ -68,76 +141,71 @@ pub struct WasmParsedFile {
     pub signature_range: (usize, usize),
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Tsify)]
+#[tsify(hashmap_as_object)]
 #[serde(rename_all = "camelCase")]
 pub struct WasmVerificationResult {
     pub valid: bool,
     pub creator_id: String,
     pub creator_name: String,
+    pub creator_fingerprint: Option<String>,
+    pub identity_attestation: Option<IdentityAttestation>,
+    pub parent_id: Option<String>,
     pub signed_at: i64,
     pub description: Option<String>,
+    pub ephemeral_key: bool,
+    pub warnings: Vec<String>,
+    pub version_major: u8,
+    pub version_minor: u8,
+    /// SHA-256 of the original payload
+    #[serde(with = "serde_bytes")]
+    #[tsify(type = "Uint8Array | undefined")]
+    pub content_digest: Option<Vec<u8>>,
+    pub payload_size: Option<u64>,
+    pub stored_size: u64,
+    /// Chain fingerprints as lowercase hex, signer first
+    pub chain_fingerprints: Vec<String>,
 }
 
 /// Parse an Aletheia file from bytes
 #[wasm_bindgen]
-pub fn parse_aletheia_file(data: &[u8]) -> Result<JsValue, JsValue> {
+pub fn parse_aletheia_file(data: &[u8]) -> Result<Ts<WasmParsedFile>, JsValue> {
     let file = from_bytes(data).map_err(|e| JsValue::from_str(&format!("Parse error: {}", e)))?;
 
-    // Calculate byte ranges
-    let mut offset = 0;
-
-    // Magic (8 bytes)
-    let magic_range = (offset, offset + 8);
-    offset += 8;
-
-    // Version (2 bytes)
-    let version_range = (offset, offset + 2);
-    offset += 2;
-
-    // Flags (2 bytes)
-    let flags_range = (offset, offset + 2);
-    offset += 2;
-
-    // Header (4-byte length + content)
-    let header_start = offset;
-    let mut header_bytes = Vec::new();
-    ciborium::into_writer(&file.header, &mut header_bytes).unwrap();
-    let header_len = header_bytes.len();
-    offset += 4 + header_len;
-    let header_range = (header_start, offset);
-
-    // Payload (8-byte length + content)
-    let payload_start = offset;
-    offset += 8 + file.payload.len();
-    let payload_range = (payload_start, offset);
-
-    // Cert chain (4-byte length + content)
-    let cert_start = offset;
-    let mut cert_bytes = Vec::new();
-    ciborium::into_writer(&file.certificate_chain, &mut cert_bytes).unwrap();
-    let cert_len = cert_bytes.len();
-    offset += 4 + cert_len;
-    let cert_chain_range = (cert_start, offset);
-
-    // Signature (64 bytes)
-    let signature_range = (offset, offset + 64);
+    // Byte ranges for hex highlighting
+    let sections = crate::file::sections(data);
+    let range = |name: &str| {
+        sections
+            .iter()
+            .find(|section| section.name == name)
+            .map_or((0, 0), |section| (section.start, section.end))
+    };
 
     let parsed = WasmParsedFile {
         version_major: file.version_major,
         version_minor: file.version_minor,
         is_compressed: file.flags.is_compressed(),
+        compression: file
+            .flags
+            .compression_algorithm()
+            .map(|algorithm| match algorithm {
+                CompressionAlgorithm::Lz4 => "lz4".into(),
+                CompressionAlgorithm::Zstd { .. } => "zstd".into(),
+            }),
         header: WasmHeader {
             creator_id: file.header.creator_id,
             signed_at: file.header.signed_at,
             content_type: file.header.content_type,
             original_name: file.header.original_name,
             description: file.header.description,
+            content_digest: file.header.content_digest,
         },
         payload: file.payload,
         certificate_chain: file
             .certificate_chain
             .into_iter()
             .map(|c| WasmCertificate {
+                fingerprint: c.fingerprint().to_hex(),
                 version: c.version,
                 serial: c.serial,
                 subject_id: c.subject_id,
@@ -146,48 +214,328 @@ pub fn parse_aletheia_file(data: &[u8]) -> Result<JsValue, JsValue> {
                 issuer_id: c.issuer_id,
                 issued_at: c.issued_at,
                 is_ca: c.is_ca,
+                path_len: c.path_len,
+                not_before: c.not_before,
+                not_after: c.not_after,
+                single_use: c.single_use,
+                extensions: c.extensions,
+                identity_attestation: c.identity_attestation,
+                parent_id: c.parent_id,
+                predecessor_serial: c.predecessor_serial,
                 signature: c.signature,
             })
             .collect(),
         signature: file.signature,
-        magic_range,
-        version_range,
-        flags_range,
-        header_range,
-        payload_range,
-        cert_chain_range,
-        signature_range,
+        magic_range: range("magic"),
+        version_range: range("version"),
+        flags_range: range("flags"),
+        header_range: range("header"),
+        payload_range: range("payload"),
+        cert_chain_range: range("certificate chain"),
+        signature_range: range("signature"),
     };
 
-    serde_wasm_bindgen::to_value(&parsed)
-        .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    to_js(&parsed)
 }
 
 /// Verify an Aletheia file
 /// trusted_root_keys should be a JS Array of Uint8Array
 #[wasm_bindgen]
-pub fn verify_aletheia_file(data: &[u8], trusted_root_keys: JsValue) -> Result<JsValue, JsValue> {
-    let file = from_bytes(data).map_err(|e| JsValue::from_str(&format!("Parse error: {}", e)))?;
+pub async fn verify_aletheia_file(
+    data: Vec<u8>,
+    #[wasm_bindgen(unchecked_param_type = "Uint8Array[]")] trusted_root_keys: JsValue,
+) -> Result<Ts<WasmVerificationResult>, JsValue> {
+    let file = from_bytes(&data).map_err(|e| JsValue::from_str(&format!("Parse error: {}", e)))?;
+    let trusted_roots = trusted_roots(trusted_root_keys)?;
+    let options = verify_options();
+
+    let result = offload(move || verify_with_options(&file, &trusted_roots, &options))
+        .await
+        .map_err(verification_error)?;
 
-    // Convert JsValue to Vec<Vec<u8>>
-    let trusted_roots: Vec<Vec<u8>> = serde_wasm_bindgen::from_value(trusted_root_keys)
-        .map_err(|e| JsValue::from_str(&format!("Invalid trusted roots format: {}", e)))?;
+    to_js(&WasmVerificationResult::from(result))
+}
+
+/// Verify an Aletheia file against the roots of a signed trust bundle
+///
+/// `bundle_json` is a trust bundle as published by the PKI portal, and
+/// `bundle_signer_key` the raw Ed25519 public key (32 bytes) of the bundle
+/// signer the application pins, as the portal publishes it at
+/// `/.well-known/aletheia-bundle-signer`. The bundle's signature is checked
+/// against the pinned key before its roots are trusted, so the bundle can be
+/// fetched over any transport.
+#[wasm_bindgen]
+pub async fn verify_with_trust_bundle(
+    data: Vec<u8>,
+    bundle_json: String,
+    bundle_signer_key: Vec<u8>,
+) -> Result<Ts<WasmVerificationResult>, JsValue> {
+    let file = from_bytes(&data).map_err(|e| JsValue::from_str(&format!("Parse error: {}", e)))?;
 
-    let result = verify(&file, &trusted_roots)
-        .map_err(|e| JsValue::from_str(&format!("Verification error: {}", e)))?;
+    let bundle = TrustBundle::parse(bundle_json.as_bytes())
+        .and_then(|bundle| bundle.verify_with_key(&bundle_signer_key).map(|_| bundle))
+        .map_err(|e| JsValue::from_str(&format!("Invalid trust bundle: {}", e)))?;
 
-    let wasm_result = WasmVerificationResult {
-        valid: result.valid,
-        creator_id: result.creator_id,
-        creator_name: result.creator_name,
-        signed_at: result.signed_at,
-        description: result.description,
+    let trusted_roots = bundle.public_keys();
+    let options = verify_options();
+
+    let result = offload(move || verify_with_options(&file, &trusted_roots, &options))
+        .await
+        .map_err(verification_error)?;
+
+    to_js(&WasmVerificationResult::from(result))
+}
+
+/// Verify an Aletheia file given as a `Blob` (such as a `File`) or a
+/// `ReadableStream` of `Uint8Array` chunks
+///
+/// Like `verify_aletheia_file`, but the file is read chunk by chunk and its
+/// payload is hashed as it arrives instead of being copied into WASM memory,
+/// so files of any size can be verified.
+#[wasm_bindgen]
+pub async fn verify_stream(
+    #[wasm_bindgen(unchecked_param_type = "Blob | ReadableStream<Uint8Array>")] input: JsValue,
+    #[wasm_bindgen(unchecked_param_type = "Uint8Array[]")] trusted_root_keys: JsValue,
+) -> Result<Ts<WasmVerificationResult>, JsValue> {
+    let trusted_roots = trusted_roots(trusted_root_keys)?;
+
+    let mut verifier = IncrementalVerifier::new(&trusted_roots, &verify_options());
+    for_each_chunk(&input, |chunk| {
+        verifier.update(&chunk.to_vec()).map_err(verification_error)
+    })
+    .await?;
+    let result = verifier.finish().map_err(verification_error)?;
+
+    to_js(&WasmVerificationResult::from(result))
+}
+
+/// Result of `detect_and_verify`
+#[derive(Serialize, Deserialize, Tsify)]
+#[tsify(hashmap_as_object)]
+#[serde(rename_all = "camelCase")]
+pub struct WasmEmbeddedVerification {
+    /// "png" or "jpeg", when the asset is in a format manifests are embedded in
+    pub format: Option<String>,
+    /// Whether the asset carries an embedded manifest
+    pub has_manifest: bool,
+    /// The verification result, when the manifest verified
+    pub result: Option<WasmVerificationResult>,
+    /// Why the asset could not be read or its manifest failed to verify
+    pub error: Option<String>,
+    pub error_code: Option<u16>,
+}
+
+/// Find the manifest embedded in a PNG or JPEG and verify the asset against it
+///
+/// Meant for badging images as they are encountered: an asset in another
+/// format or without a manifest resolves with `hasManifest: false` rather
+/// than rejecting, and a manifest that fails to verify is reported in
+/// `error`. Only a malformed list of trusted roots rejects.
+#[wasm_bindgen]
+pub async fn detect_and_verify(
+    asset_bytes: Vec<u8>,
+    #[wasm_bindgen(unchecked_param_type = "Uint8Array[]")] trusted_root_keys: JsValue,
+) -> Result<Ts<WasmEmbeddedVerification>, JsValue> {
+    let trusted_roots = trusted_roots(trusted_root_keys)?;
+    let options = verify_options();
+    let format = MediaFormat::detect(&asset_bytes);
+
+    let outcome = match format {
+        Some(format) => {
+            offload(move || {
+                format.has_manifest(&asset_bytes).map(|found| {
+                    found
+                        .then(|| format.verify_with_options(&asset_bytes, &trusted_roots, &options))
+                })
+            })
+            .await
+        }
+        None => Ok(None),
     };
 
-    serde_wasm_bindgen::to_value(&wasm_result)
+    let (has_manifest, result) = match outcome {
+        Ok(None) => (false, Ok(None)),
+        Ok(Some(result)) => (true, result.map(Some)),
+        Err(e) => (false, Err(e)),
+    };
+    to_js(&WasmEmbeddedVerification {
+        format: format.map(|format| format.name().into()),
+        has_manifest,
+        error: result.as_ref().err().map(|e| format!("{}", e)),
+        error_code: result.as_ref().err().map(AletheiaError::code),
+        result: result.ok().flatten().map(Into::into),
+    })
+}
+
+/// Outcome of one file in `verify_many`
+#[derive(Serialize, Deserialize, Tsify)]
+#[tsify(hashmap_as_object)]
+#[serde(rename_all = "camelCase")]
+pub struct WasmBatchVerification {
+    /// The verification result, when the file verified
+    pub result: Option<WasmVerificationResult>,
+    /// Why the file failed to parse or verify
+    pub error: Option<String>,
+    pub error_code: Option<u16>,
+}
+
+/// Verify several Aletheia files against the same trusted roots
+///
+/// Resolves to one entry per file, in order, holding either the result or
+/// the error the file failed with, so one bad file does not hide the others.
+/// With the `wasm-parallel` feature the files are verified in parallel on the
+/// thread pool.
+#[wasm_bindgen]
+pub async fn verify_many(
+    files: Vec<Uint8Array>,
+    #[wasm_bindgen(unchecked_param_type = "Uint8Array[]")] trusted_root_keys: JsValue,
+) -> Result<Vec<Ts<WasmBatchVerification>>, JsValue> {
+    let files: Vec<Vec<u8>> = files.iter().map(Uint8Array::to_vec).collect();
+    let trusted_roots = trusted_roots(trusted_root_keys)?;
+    let options = verify_options();
+
+    let results = offload(move || {
+        let verify = |data: &Vec<u8>| {
+            from_bytes(data).and_then(|file| verify_with_options(&file, &trusted_roots, &options))
+        };
+
+        #[cfg(feature = "wasm-parallel")]
+        {
+            use rayon::prelude::*;
+            files.par_iter().map(verify).collect::<Vec<_>>()
+        }
+        #[cfg(not(feature = "wasm-parallel"))]
+        {
+            files.iter().map(verify).collect::<Vec<_>>()
+        }
+    })
+    .await;
+
+    results
+        .into_iter()
+        .map(|result| match result {
+            Ok(result) => to_js(&WasmBatchVerification {
+                result: Some(result.into()),
+                error: None,
+                error_code: None,
+            }),
+            Err(e) => to_js(&WasmBatchVerification {
+                result: None,
+                error: Some(format!("{}", e)),
+                error_code: Some(e.code()),
+            }),
+        })
+        .collect()
+}
+
+/// Run CPU-heavy work without blocking the calling thread
+///
+/// With the `wasm-parallel` feature the work runs on the rayon thread pool
+/// (started from JS with `initThreadPool`) while the caller awaits, so the
+/// page stays responsive. Otherwise it runs in place: the async API is the
+/// same either way, and apps can also call it from a Web Worker.
+async fn offload<T: Send + 'static>(work: impl FnOnce() -> T + Send + 'static) -> T {
+    #[cfg(feature = "wasm-parallel")]
+    {
+        let (sender, receiver) = futures_channel::oneshot::channel();
+        rayon::spawn(move || {
+            let _ = sender.send(work());
+        });
+        receiver.await.expect_throw("thread pool job was dropped")
+    }
+    #[cfg(not(feature = "wasm-parallel"))]
+    {
+        work()
+    }
+}
+
+/// Convert a result to the plain JS object its generated type declares
+fn to_js<T: Tsify + Serialize>(value: &T) -> Result<Ts<T>, JsValue> {
+    value
+        .into_ts()
         .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
 }
 
+/// Convert trusted root keys from a JS Array of Uint8Array
+fn trusted_roots(trusted_root_keys: JsValue) -> Result<Vec<Vec<u8>>, JsValue> {
+    serde_wasm_bindgen::from_value(trusted_root_keys)
+        .map_err(|e| JsValue::from_str(&format!("Invalid trusted roots format: {}", e)))
+}
+
+/// Verification options checking validity against the browser's clock
+fn verify_options() -> VerifyOptions {
+    VerifyOptions::new().with_validity_check(validity_now())
+}
+
+/// Check validity periods at the current time
+fn validity_now() -> ValidityCheck {
+    // std::time is unavailable in the browser, so take the clock from JS
+    let now = (js_sys::Date::now() / 1000.0) as i64;
+    ValidityCheck::At(now)
+}
+
+fn verification_error(e: AletheiaError) -> JsValue {
+    JsValue::from_str(&format!("Verification error {}: {}", e.code(), e))
+}
+
+/// Read a `Blob` or a `ReadableStream` of `Uint8Array`, passing each chunk to `f`
+///
+/// The stream is cancelled if `f` fails.
+async fn for_each_chunk(
+    input: &JsValue,
+    mut f: impl FnMut(&Uint8Array) -> Result<(), JsValue>,
+) -> Result<(), JsValue> {
+    let stream = match input.dyn_ref::<Blob>() {
+        Some(blob) => blob.stream(),
+        None => input
+            .clone()
+            .dyn_into::<ReadableStream>()
+            .map_err(|_| JsValue::from_str("Expected a Blob or ReadableStream"))?,
+    };
+    let reader: ReadableStreamDefaultReader = stream.get_reader().unchecked_into();
+    loop {
+        let read = JsFuture::from(reader.read()).await?;
+        if Reflect::get(&read, &"done".into())?.is_truthy() {
+            return Ok(());
+        }
+        let handled = Reflect::get(&read, &"value".into())?
+            .dyn_into::<Uint8Array>()
+            .map_err(|_| JsValue::from_str("Stream chunks must be Uint8Array"))
+            .and_then(|chunk| f(&chunk));
+        if let Err(e) = handled {
+            let _ = reader.cancel();
+            return Err(e);
+        }
+    }
+}
+
+impl From<VerificationResult> for WasmVerificationResult {
+    fn from(result: VerificationResult) -> Self {
+        Self {
+            valid: result.valid,
+            creator_id: result.creator_id,
+            creator_name: result.creator_name,
+            creator_fingerprint: result.creator_fingerprint.map(|f| f.to_hex()),
+            identity_attestation: result.identity_attestation,
+            parent_id: result.parent_id,
+            signed_at: result.signed_at,
+            description: result.description,
+            ephemeral_key: result.ephemeral_key,
+            warnings: result.warnings.iter().map(|w| format!("{w}")).collect(),
+            version_major: result.format_version.0,
+            version_minor: result.format_version.1,
+            content_digest: result.content_digest.map(|digest| digest.to_vec()),
+            payload_size: result.payload_size,
+            stored_size: result.stored_size,
+            chain_fingerprints: result
+                .chain_fingerprints
+                .iter()
+                .map(|f| f.to_hex())
+                .collect(),
+        }
+    }
+}
+
 /// Decompress payload if compressed
 #[wasm_bindgen]
 pub fn decompress_payload(payload: &[u8], is_compressed: bool) -> Result<Vec<u8>, JsValue> {
@@ -195,20 +543,14 @@ pub fn decompress_payload(payload: &[u8], is_compressed: bool) -> Result<Vec<u8>
         return Ok(payload.to_vec());
     }
 
-    #[cfg(feature = "compression")]
-    {
-        lz4_flex::decompress_size_prepended(payload)
-            .map_err(|e| JsValue::from_str(&format!("Decompression error: {}", e)))
-    }
-
-    #[cfg(not(feature = "compression"))]
-    {
-        Err(JsValue::from_str("Compression support not enabled"))
-    }
+    let flags = crate::Flags::new().with_compression_algorithm(CompressionAlgorithm::Lz4);
+    crate::types::decompress_payload(flags, payload)
+        .map_err(|e| JsValue::from_str(&format!("Decompression error: {}", e)))
 }
 
 /// Result structure for CA generation
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Tsify)]
+#[tsify(hashmap_as_object)]
 #[serde(rename_all = "camelCase")]
 pub struct WasmGeneratedCA {
     /// Private key as hex string (64 chars)
@@ -233,7 +575,10 @@ pub struct WasmGeneratedCA {
 /// - certificateBase64: Base64-encoded CBOR certificate
 /// - subjectId, subjectName: CA identity info
 #[wasm_bindgen]
-pub fn generate_root_ca(subject_id: &str, subject_name: &str) -> Result<JsValue, JsValue> {
+pub fn generate_root_ca(
+    subject_id: &str,
+    subject_name: &str,
+) -> Result<Ts<WasmGeneratedCA>, JsValue> {
     // Get current timestamp from JavaScript
     let timestamp_ms = js_sys::Date::now();
     let timestamp = (timestamp_ms / 1000.0) as i64;
@@ -263,8 +608,7 @@ pub fn generate_root_ca(subject_id: &str, subject_name: &str) -> Result<JsValue,
         subject_name: ca.certificate.subject_name.clone(),
     };
 
-    serde_wasm_bindgen::to_value(&result)
-        .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    to_js(&result)
 }
 
 /// Simple base64 encoding (no external dependency needed)
@@ -301,11 +645,12 @@ fn base64_encode(data: &[u8]) -> String {
 /// Parse a CBOR-encoded certificate and return its details
 /// Used for validating and displaying CA certificate information
 #[wasm_bindgen]
-pub fn parse_certificate(cbor_bytes: &[u8]) -> Result<JsValue, JsValue> {
+pub fn parse_certificate(cbor_bytes: &[u8]) -> Result<Ts<WasmCertificate>, JsValue> {
     let cert: Certificate = ciborium::from_reader(cbor_bytes)
         .map_err(|e| JsValue::from_str(&format!("Certificate parse error: {}", e)))?;
 
     let wasm_cert = WasmCertificate {
+        fingerprint: cert.fingerprint().to_hex(),
         version: cert.version,
         serial: cert.serial,
         subject_id: cert.subject_id,
@@ -314,21 +659,107 @@ pub fn parse_certificate(cbor_bytes: &[u8]) -> Result<JsValue, JsValue> {
         issuer_id: cert.issuer_id,
         issued_at: cert.issued_at,
         is_ca: cert.is_ca,
+        path_len: cert.path_len,
+        not_before: cert.not_before,
+        not_after: cert.not_after,
+        single_use: cert.single_use,
+        extensions: cert.extensions,
+        identity_attestation: cert.identity_attestation,
+        parent_id: cert.parent_id,
+        predecessor_serial: cert.predecessor_serial,
         signature: cert.signature,
     };
 
-    serde_wasm_bindgen::to_value(&wasm_cert)
-        .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    to_js(&wasm_cert)
+}
+
+/// Status of one certificate of a chain, see `validate_certificate_chain`
+#[derive(Serialize, Deserialize, Tsify)]
+#[tsify(hashmap_as_object)]
+#[serde(rename_all = "camelCase")]
+pub struct WasmCertificateStatus {
+    pub subject_id: String,
+    pub subject_name: String,
+    /// SHA-256 fingerprint as lowercase hex
+    pub fingerprint: String,
+    /// Why the certificate fails against the next one, or as the root
+    pub error: Option<String>,
+    pub error_code: Option<u16>,
+}
+
+/// Result of `validate_certificate_chain`
+#[derive(Serialize, Deserialize, Tsify)]
+#[tsify(hashmap_as_object)]
+#[serde(rename_all = "camelCase")]
+pub struct WasmChainValidation {
+    /// Whether a path from the signer to a trusted root verifies
+    pub valid: bool,
+    /// Why the chain was rejected
+    pub error: Option<String>,
+    pub error_code: Option<u16>,
+    /// One status per certificate, signer first
+    pub certificates: Vec<WasmCertificateStatus>,
+}
+
+/// Validate a CBOR-encoded certificate chain (creator first) against trusted
+/// root keys
+///
+/// Besides the verdict, each certificate is checked on its own against the
+/// next one in the chain, so a viewer can highlight exactly which certificate
+/// breaks it: an expired certificate, a wrong issuer, a bad signature or an
+/// untrusted root. The chain may still be valid when certificates fail as
+/// ordered, if it carries another path to a trusted root.
+#[wasm_bindgen]
+pub fn validate_certificate_chain(
+    chain_cbor: &[u8],
+    #[wasm_bindgen(unchecked_param_type = "Uint8Array[]")] trusted_root_keys: JsValue,
+) -> Result<Ts<WasmChainValidation>, JsValue> {
+    let chain: Vec<Certificate> = ciborium::from_reader(chain_cbor)
+        .map_err(|e| JsValue::from_str(&format!("Failed to parse certificate chain: {}", e)))?;
+    let trusted_roots = trusted_roots(trusted_root_keys)?;
+    let validity = validity_now();
+
+    let verdict = verify_certificate_chain_at(&chain, &trusted_roots, validity);
+    let checks = check_certificate_chain(&chain, &trusted_roots, validity);
+    let certificates = chain
+        .iter()
+        .zip(checks)
+        .map(|(cert, check)| WasmCertificateStatus {
+            subject_id: cert.subject_id.clone(),
+            subject_name: cert.subject_name.clone(),
+            fingerprint: cert.fingerprint().to_hex(),
+            error: check.as_ref().err().map(|e| format!("{}", e)),
+            error_code: check.err().map(|e| e.code()),
+        })
+        .collect();
+
+    to_js(&WasmChainValidation {
+        valid: verdict.is_ok(),
+        error: verdict.as_ref().err().map(|e| format!("{}", e)),
+        error_code: verdict.err().map(|e| e.code()),
+        certificates,
+    })
+}
+
+/// SHA-256 fingerprint of a CBOR-encoded certificate, as lowercase hex
+#[wasm_bindgen]
+pub fn certificate_fingerprint(cbor_bytes: &[u8]) -> Result<String, JsValue> {
+    let cert: Certificate = ciborium::from_reader(cbor_bytes)
+        .map_err(|e| JsValue::from_str(&format!("Certificate parse error: {}", e)))?;
+    Ok(cert.fingerprint().to_hex())
 }
 
 /// Sign a file using CA credentials (all-in-one function)
 ///
 /// This function:
 /// 1. Generates an ephemeral keypair for this specific file
-/// 2. Issues a certificate for the ephemeral key using the CA
+/// 2. Issues a single-use certificate for the ephemeral key using the CA
 /// 3. Signs the file with the ephemeral key
 /// 4. Returns the complete .alx file bytes
 ///
+/// This puts the CA key in the browser; end users should sign with their own
+/// certificate through `sign_file_with_key`.
+///
 /// # Arguments
 /// * `payload` - The file content to sign
 /// * `ca_private_key` - CA private key (32 bytes)
@@ -339,11 +770,11 @@ pub fn parse_certificate(cbor_bytes: &[u8]) -> Result<JsValue, JsValue> {
 /// * `description` - Optional description
 /// * `compress` - Whether to enable compression
 #[wasm_bindgen]
-pub fn sign_file_with_ca(
-    payload: &[u8],
-    ca_private_key: &[u8],
-    ca_cert_cbor: &[u8],
-    creator_id: &str,
+pub async fn sign_file_with_ca(
+    payload: Vec<u8>,
+    ca_private_key: Vec<u8>,
+    ca_cert_cbor: Vec<u8>,
+    creator_id: String,
     content_type: Option<String>,
     original_name: Option<String>,
     description: Option<String>,
@@ -353,6 +784,170 @@ pub fn sign_file_with_ca(
     let timestamp_ms = js_sys::Date::now();
     let timestamp = (timestamp_ms / 1000.0) as i64;
 
+    let signer = ephemeral_signer(&ca_private_key, &ca_cert_cbor, &creator_id, timestamp)?;
+
+    // Optionally enable compression
+    #[cfg(feature = "compression")]
+    let signer = if compress {
+        signer.with_compression()
+    } else {
+        signer
+    };
+
+    #[cfg(not(feature = "compression"))]
+    let _ = compress; // Suppress unused warning
+
+    let header = file_header(
+        &creator_id,
+        timestamp,
+        content_type,
+        original_name,
+        description,
+    );
+
+    sign_offloaded(signer, payload, header).await
+}
+
+/// Sign a file with the creator's own key and certificate chain
+///
+/// Unlike `sign_file_with_ca`, no CA key is needed in the browser: the
+/// creator signs with the key their certificate was issued for, and the
+/// header's creator is taken from that certificate.
+///
+/// # Arguments
+/// * `payload` - The file content to sign
+/// * `private_key` - The creator's private key (32 bytes)
+/// * `cert_chain_cbor` - CBOR array of the certificate chain, creator first
+/// * `content_type` - Optional MIME type
+/// * `original_name` - Optional original filename
+/// * `description` - Optional description
+/// * `compress` - Whether to enable compression
+#[wasm_bindgen]
+pub async fn sign_file_with_key(
+    payload: Vec<u8>,
+    private_key: Vec<u8>,
+    cert_chain_cbor: Vec<u8>,
+    content_type: Option<String>,
+    original_name: Option<String>,
+    description: Option<String>,
+    compress: bool,
+) -> Result<Vec<u8>, JsValue> {
+    let timestamp = (js_sys::Date::now() / 1000.0) as i64;
+
+    let key = SigningKeyPair::from_bytes(&private_key)
+        .map_err(|e| JsValue::from_str(&format!("Invalid private key: {}", e)))?;
+    let cert_chain: Vec<Certificate> = ciborium::from_reader(cert_chain_cbor.as_slice())
+        .map_err(|e| JsValue::from_str(&format!("Failed to parse certificate chain: {}", e)))?;
+
+    // Fails unless the key matches the first certificate
+    let signer = Signer::new(key, cert_chain)
+        .map_err(|e| JsValue::from_str(&format!("Failed to create signer: {}", e)))?;
+
+    #[cfg(feature = "compression")]
+    let signer = if compress {
+        signer.with_compression()
+    } else {
+        signer
+    };
+
+    #[cfg(not(feature = "compression"))]
+    let _ = compress; // Suppress unused warning
+
+    let header = file_header(
+        signer.creator_id(),
+        timestamp,
+        content_type,
+        original_name,
+        description,
+    );
+
+    sign_offloaded(signer, payload, header).await
+}
+
+/// Sign and serialize a file off the calling thread, see `offload`
+async fn sign_offloaded(
+    signer: Signer,
+    payload: Vec<u8>,
+    header: Header,
+) -> Result<Vec<u8>, JsValue> {
+    offload(move || {
+        signer
+            .sign(&payload, header)
+            .and_then(|file| to_bytes(&file))
+    })
+    .await
+    .map_err(|e| JsValue::from_str(&format!("Failed to sign file: {}", e)))
+}
+
+/// Sign a `Blob` (such as a `File`) or a `ReadableStream` of `Uint8Array`
+/// chunks with CA credentials
+///
+/// Like `sign_file_with_ca`, but the content is read chunk by chunk and
+/// hashed as it arrives instead of being copied into WASM memory, so files of
+/// any size can be signed. The file is signed in chunked mode (4 MiB chunks),
+/// which needs only one pass, and is not compressed. Returns the `.alx` file
+/// as a `Blob` built around the content: a `Blob` input is referenced, not
+/// copied. A `ReadableStream` can only be read once, so its chunks are kept
+/// (by the browser, outside WASM memory) to build the result.
+#[wasm_bindgen]
+pub async fn sign_stream(
+    #[wasm_bindgen(unchecked_param_type = "Blob | ReadableStream<Uint8Array>")] input: JsValue,
+    ca_private_key: Vec<u8>,
+    ca_cert_cbor: Vec<u8>,
+    creator_id: String,
+    content_type: Option<String>,
+    original_name: Option<String>,
+    description: Option<String>,
+) -> Result<Blob, JsValue> {
+    let timestamp = (js_sys::Date::now() / 1000.0) as i64;
+    let signer = ephemeral_signer(&ca_private_key, &ca_cert_cbor, &creator_id, timestamp)?
+        .with_chunking(DEFAULT_CHUNK_SIZE);
+    let header = file_header(
+        &creator_id,
+        timestamp,
+        content_type,
+        original_name,
+        description,
+    );
+
+    let mut incremental = signer
+        .incremental(header)
+        .map_err(|e| JsValue::from_str(&format!("Failed to sign file: {}", e)))?;
+    let blob = input.dyn_ref::<Blob>();
+    let kept = Array::new();
+    for_each_chunk(&input, |chunk| {
+        incremental.update(&chunk.to_vec());
+        if blob.is_none() {
+            kept.push(chunk);
+        }
+        Ok(())
+    })
+    .await?;
+    let parts = incremental
+        .finish()
+        .map_err(|e| JsValue::from_str(&format!("Failed to sign file: {}", e)))?;
+
+    let blob_parts = Array::new();
+    blob_parts.push(&Uint8Array::from(&parts.head[..]));
+    match blob {
+        Some(blob) => {
+            blob_parts.push(blob);
+        }
+        None => kept.for_each(&mut |chunk, _, _| {
+            blob_parts.push(&chunk);
+        }),
+    }
+    blob_parts.push(&Uint8Array::from(&parts.tail[..]));
+    Blob::new_with_u8_array_sequence(&blob_parts)
+}
+
+/// A signer for a freshly generated key, certified by the CA for one use
+fn ephemeral_signer(
+    ca_private_key: &[u8],
+    ca_cert_cbor: &[u8],
+    creator_id: &str,
+    timestamp: i64,
+) -> Result<Signer, JsValue> {
     // Parse CA certificate from CBOR
     let ca_cert: Certificate = ciborium::from_reader(ca_cert_cbor)
         .map_err(|e| JsValue::from_str(&format!("Failed to parse CA certificate: {}", e)))?;
@@ -364,36 +959,36 @@ pub fn sign_file_with_ca(
     // Generate ephemeral keypair for this file
     let ephemeral_key = SigningKeyPair::generate();
 
-    // Issue certificate for ephemeral key
+    // Issue certificate for ephemeral key, marked so verifiers can tell
     let ephemeral_cert = ca
-        .issue_certificate_with_timestamp(
+        .issue_certificate_with_options(
             creator_id,
             creator_id, // Use creator_id as name too for simplicity
             &ephemeral_key.public_key(),
             false, // Not a CA
             timestamp,
+            &IssueOptions::new().single_use(),
         )
         .map_err(|e| JsValue::from_str(&format!("Failed to issue certificate: {}", e)))?;
 
     // Build certificate chain: [ephemeral_cert, ca_cert]
-    let cert_chain = vec![ephemeral_cert, ca_cert];
-
-    // Create signer
-    let signer = Signer::new(ephemeral_key, cert_chain)
-        .map_err(|e| JsValue::from_str(&format!("Failed to create signer: {}", e)))?;
+    let cert_chain = ChainBuilder::new()
+        .with_certificate(ca_cert)
+        .build(ephemeral_cert)
+        .map_err(|e| JsValue::from_str(&format!("Failed to build certificate chain: {}", e)))?;
 
-    // Optionally enable compression
-    #[cfg(feature = "compression")]
-    let signer = if compress {
-        signer.with_compression()
-    } else {
-        signer
-    };
-
-    #[cfg(not(feature = "compression"))]
-    let _ = compress; // Suppress unused warning
+    Signer::new(ephemeral_key, cert_chain)
+        .map_err(|e| JsValue::from_str(&format!("Failed to create signer: {}", e)))
+}
 
-    // Build header
+/// Build the header of a file signed in the browser
+fn file_header(
+    creator_id: &str,
+    timestamp: i64,
+    content_type: Option<String>,
+    original_name: Option<String>,
+    description: Option<String>,
+) -> Header {
     let mut header = Header::new_with_timestamp(creator_id, timestamp);
     if let Some(ct) = content_type {
         header = header.with_content_type(ct);
@@ -404,15 +999,481 @@ pub fn sign_file_with_ca(
     if let Some(desc) = description {
         header = header.with_description(desc);
     }
+    header
+}
+
+/// IndexedDB database and object store holding browser keys
+const KEY_DATABASE: &str = "aletheia-keys";
+const KEY_STORE: &str = "keys";
+
+/// Generate an Ed25519 signing key with WebCrypto and store it under `key_id`
+///
+/// The private key is created non-extractable and kept in IndexedDB: scripts
+/// on the page can have it sign, but cannot read it, so an XSS attack cannot
+/// carry the identity away. An existing key under `key_id` is replaced.
+/// Fails in browsers without Ed25519 support in WebCrypto.
+///
+/// Returns the raw public key (32 bytes).
+#[wasm_bindgen]
+pub async fn generate_browser_key(key_id: String) -> Result<Uint8Array, JsValue> {
+    let subtle = subtle_crypto()?;
+    let usages = Array::of2(&"sign".into(), &"verify".into());
+    let pair = JsFuture::from(subtle.generate_key_with_str("Ed25519", false, &usages)?)
+        .await
+        .map_err(|_| JsValue::from_str("This browser cannot generate Ed25519 keys in WebCrypto"))?;
+    let public_key: CryptoKey = Reflect::get(&pair, &"publicKey".into())?.unchecked_into();
+    let raw = JsFuture::from(subtle.export_key("raw", &public_key)?).await?;
+
+    key_store(IdbTransactionMode::Readwrite, |store| {
+        store.put_with_key(&pair, &key_id.as_str().into())
+    })
+    .await?;
+    Ok(Uint8Array::new(&raw))
+}
+
+/// Delete the browser key stored under `key_id`
+#[wasm_bindgen]
+pub async fn delete_browser_key(key_id: String) -> Result<(), JsValue> {
+    key_store(IdbTransactionMode::Readwrite, |store| {
+        store.delete(&key_id.as_str().into())
+    })
+    .await?;
+    Ok(())
+}
+
+/// Create a certificate request for the browser key `key_id`
+///
+/// The request is signed by the key as proof of possession. Returns the
+/// request as CBOR, for the CA to approve.
+#[wasm_bindgen]
+pub async fn create_browser_key_request(
+    key_id: String,
+    subject_id: String,
+    subject_name: String,
+) -> Result<Vec<u8>, JsValue> {
+    let pair = browser_key(&key_id).await?;
+    let public_key: CryptoKey = Reflect::get(&pair, &"publicKey".into())?.unchecked_into();
+    let raw = JsFuture::from(subtle_crypto()?.export_key("raw", &public_key)?).await?;
+
+    let mut request = CertificateRequest {
+        subject_id,
+        subject_name,
+        public_key: Uint8Array::new(&raw).to_vec(),
+        requested_at: (js_sys::Date::now() / 1000.0) as i64,
+        signature: Vec::new(),
+    };
+    request.signature = browser_key_sign(&pair, &request.signable_data()).await?;
+    request
+        .verify()
+        .and_then(|_| request.to_bytes())
+        .map_err(|e| JsValue::from_str(&format!("Failed to create request: {}", e)))
+}
 
-    // Sign the file
-    let file = signer
-        .sign(payload, header)
+/// Sign a file with the browser key `key_id` and its certificate chain
+///
+/// Like `sign_file_with_key`, but the private key never enters WASM memory:
+/// WebCrypto signs. WebCrypto only produces pure Ed25519 signatures, so the
+/// file is written in format 1.0, which every reader verifies, and is not
+/// compressed.
+///
+/// # Arguments
+/// * `payload` - The file content to sign
+/// * `key_id` - Name the key was stored under by `generate_browser_key`
+/// * `cert_chain_cbor` - CBOR array of the certificate chain, creator first
+/// * `content_type` - Optional MIME type
+/// * `original_name` - Optional original filename
+/// * `description` - Optional description
+#[wasm_bindgen]
+pub async fn sign_file_with_browser_key(
+    payload: Vec<u8>,
+    key_id: String,
+    cert_chain_cbor: Vec<u8>,
+    content_type: Option<String>,
+    original_name: Option<String>,
+    description: Option<String>,
+) -> Result<Vec<u8>, JsValue> {
+    let timestamp = (js_sys::Date::now() / 1000.0) as i64;
+
+    let cert_chain: Vec<Certificate> = ciborium::from_reader(cert_chain_cbor.as_slice())
+        .map_err(|e| JsValue::from_str(&format!("Failed to parse certificate chain: {}", e)))?;
+    let creator_id = cert_chain
+        .first()
+        .map(|cert| cert.subject_id.clone())
+        .ok_or_else(|| JsValue::from_str("Certificate chain is empty"))?;
+    let header = file_header(
+        &creator_id,
+        timestamp,
+        content_type,
+        original_name,
+        description,
+    );
+
+    let pending = PendingFile::new(&payload, header, cert_chain)
+        .map_err(|e| JsValue::from_str(&format!("Failed to sign file: {}", e)))?;
+    let pair = browser_key(&key_id).await?;
+    let signature = browser_key_sign(&pair, pending.message()).await?;
+    let file = pending
+        .complete(&signature)
         .map_err(|e| JsValue::from_str(&format!("Failed to sign file: {}", e)))?;
 
-    // Serialize to bytes
-    let bytes = to_bytes(&file)
-        .map_err(|e| JsValue::from_str(&format!("Failed to serialize file: {}", e)))?;
+    to_bytes(&file).map_err(|e| JsValue::from_str(&format!("Failed to serialize file: {}", e)))
+}
+
+/// The WebCrypto interface of the page or worker
+fn subtle_crypto() -> Result<SubtleCrypto, JsValue> {
+    let crypto: Crypto = Reflect::get(&js_sys::global(), &"crypto".into())?
+        .dyn_into()
+        .map_err(|_| JsValue::from_str("WebCrypto is not available"))?;
+    Ok(crypto.subtle())
+}
+
+/// Load the key pair stored under `key_id`
+async fn browser_key(key_id: &str) -> Result<JsValue, JsValue> {
+    let pair = key_store(IdbTransactionMode::Readonly, |store| {
+        store.get(&key_id.into())
+    })
+    .await?;
+    if pair.is_undefined() {
+        return Err(JsValue::from_str(&format!(
+            "No browser key named '{}'",
+            key_id
+        )));
+    }
+    Ok(pair)
+}
+
+/// Sign `message` with pure Ed25519 using a stored key pair
+async fn browser_key_sign(pair: &JsValue, message: &[u8]) -> Result<Vec<u8>, JsValue> {
+    let private_key: CryptoKey = Reflect::get(pair, &"privateKey".into())?.unchecked_into();
+    let signature = JsFuture::from(subtle_crypto()?.sign_with_str_and_u8_array(
+        "Ed25519",
+        &private_key,
+        message,
+    )?)
+    .await?;
+    Ok(Uint8Array::new(&signature).to_vec())
+}
+
+/// Run `op` on the browser key store, returning the result of its request
+async fn key_store(
+    mode: IdbTransactionMode,
+    op: impl FnOnce(&IdbObjectStore) -> Result<IdbRequest, JsValue>,
+) -> Result<JsValue, JsValue> {
+    let factory: IdbFactory = Reflect::get(&js_sys::global(), &"indexedDB".into())?
+        .dyn_into()
+        .map_err(|_| JsValue::from_str("IndexedDB is not available"))?;
+    let open = factory.open_with_u32(KEY_DATABASE, 1)?;
+
+    // Create the store the first time the database is opened
+    let upgrading = open.clone();
+    let on_upgrade = Closure::<dyn FnMut()>::new(move || {
+        if let Ok(db) = upgrading.result() {
+            let _ = db
+                .unchecked_into::<IdbDatabase>()
+                .create_object_store(KEY_STORE);
+        }
+    });
+    open.set_onupgradeneeded(Some(on_upgrade.as_ref().unchecked_ref()));
+    let db: IdbDatabase = request_result(&open).await?.unchecked_into();
+
+    let result = async {
+        let store = db
+            .transaction_with_str_and_mode(KEY_STORE, mode)?
+            .object_store(KEY_STORE)?;
+        request_result(&op(&store)?).await
+    }
+    .await;
+    db.close();
+    result
+}
+
+/// Wait for an IndexedDB request to complete
+async fn request_result(request: &IdbRequest) -> Result<JsValue, JsValue> {
+    let promise = js_sys::Promise::new(&mut |resolve, reject| {
+        let succeeded = request.clone();
+        request.set_onsuccess(Some(
+            Closure::once_into_js(move || {
+                let _ = resolve.call1(&JsValue::UNDEFINED, &succeeded.result().unwrap_or_default());
+            })
+            .unchecked_ref(),
+        ));
+        let failed = request.clone();
+        request.set_onerror(Some(
+            Closure::once_into_js(move || {
+                let error = failed.error().ok().flatten().map_or_else(
+                    || JsValue::from_str("IndexedDB request failed"),
+                    JsValue::from,
+                );
+                let _ = reject.call1(&JsValue::UNDEFINED, &error);
+            })
+            .unchecked_ref(),
+        ));
+    });
+    JsFuture::from(promise).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hsm::ExternalSigner;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    /// A bare key signing trust bundles, as the portal's signer does
+    struct BundleKey(SigningKeyPair);
+
+    impl ExternalSigner for BundleKey {
+        fn public_key(&self) -> Vec<u8> {
+            self.0.public_key()
+        }
+
+        fn sign(&self, message: &[u8]) -> crate::Result<Vec<u8>> {
+            Ok(self.0.sign(message))
+        }
+
+        fn sign_prehashed(&self, _message: &[u8], _context: &[u8]) -> crate::Result<Vec<u8>> {
+            unimplemented!()
+        }
+    }
+
+    /// The current time from the JS clock, as the bindings take it
+    fn now() -> i64 {
+        (js_sys::Date::now() / 1000.0) as i64
+    }
+
+    fn root(id: &str, name: &str) -> CertificateAuthority {
+        CertificateAuthority::new_root_with_timestamp(id, name, now())
+    }
+
+    /// A key certified by `ca`, and its chain
+    fn user(ca: &CertificateAuthority) -> (SigningKeyPair, Vec<Certificate>) {
+        let keys = SigningKeyPair::generate();
+        let cert = ca
+            .issue_certificate_with_timestamp(
+                "alice@example.com",
+                "Alice",
+                &keys.public_key(),
+                false,
+                now(),
+            )
+            .unwrap();
+        (keys, vec![cert, ca.certificate.clone()])
+    }
+
+    /// A file signed by a user of `ca`
+    fn signed_file(ca: &CertificateAuthority, content: &[u8]) -> Vec<u8> {
+        let (keys, chain) = user(ca);
+        let file = Signer::new(keys, chain)
+            .unwrap()
+            .sign(
+                content,
+                Header::new_with_timestamp("alice@example.com", now()),
+            )
+            .unwrap();
+        to_bytes(&file).unwrap()
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_verify_with_trust_bundle() {
+        let ca = root("root@example.com", "Root CA");
+        let other = root("other@example.com", "Other CA");
+        let bundle_key = BundleKey(SigningKeyPair::generate());
+        let bundle = |root: &CertificateAuthority| {
+            TrustBundle::sign_with_key("1", 0, vec![root.certificate.clone()], vec![], &bundle_key)
+                .unwrap()
+                .to_json()
+                .unwrap()
+        };
+        let data = signed_file(&ca, b"content");
+
+        let result = verify_with_trust_bundle(data.clone(), bundle(&ca), bundle_key.public_key())
+            .await
+            .unwrap()
+            .to_rust()
+            .unwrap();
+        assert!(result.valid);
+        assert_eq!(result.creator_id, "alice@example.com");
+        assert_eq!(result.chain_fingerprints.len(), 2);
+
+        // The bundle must come from the pinned signer, and its roots must anchor the file
+        let error = verify_with_trust_bundle(
+            data.clone(),
+            bundle(&ca),
+            SigningKeyPair::generate().public_key(),
+        )
+        .await
+        .unwrap_err();
+        assert!(
+            error
+                .as_string()
+                .unwrap()
+                .starts_with("Invalid trust bundle")
+        );
+        let error = verify_with_trust_bundle(data, bundle(&other), bundle_key.public_key())
+            .await
+            .unwrap_err();
+        assert!(
+            error
+                .as_string()
+                .unwrap()
+                .starts_with("Verification error 2")
+        );
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_sign_file_with_key() {
+        let ca = root("root@example.com", "Root CA");
+        let (keys, chain) = user(&ca);
+        let mut chain_cbor = Vec::new();
+        ciborium::into_writer(&chain, &mut chain_cbor).unwrap();
+
+        let data = sign_file_with_key(
+            b"content".to_vec(),
+            keys.private_key_bytes(),
+            chain_cbor.clone(),
+            Some("text/plain".into()),
+            Some("note.txt".into()),
+            None,
+            true,
+        )
+        .await
+        .unwrap();
+
+        // The creator signs as their certificate's subject, with their own key
+        let file = from_bytes(&data).unwrap();
+        assert_eq!(file.header.creator_id, "alice@example.com");
+        assert_eq!(file.header.original_name.as_deref(), Some("note.txt"));
+        assert!(file.flags.is_compressed());
+        assert_eq!(file.get_payload().unwrap(), b"content");
+        let result = verify_with_options(&file, &[ca.public_key()], &verify_options()).unwrap();
+        assert_eq!(result.creator_name, "Alice");
+        assert!(!result.ephemeral_key);
+        assert_eq!(result.creator_fingerprint, Some(chain[0].fingerprint()));
+
+        // The key must be the one the certificate was issued for
+        let error = sign_file_with_key(
+            b"content".to_vec(),
+            SigningKeyPair::generate().private_key_bytes(),
+            chain_cbor,
+            None,
+            None,
+            None,
+            false,
+        )
+        .await
+        .unwrap_err();
+        assert!(
+            error
+                .as_string()
+                .unwrap()
+                .starts_with("Failed to create signer")
+        );
+        let error = sign_file_with_key(
+            b"content".to_vec(),
+            keys.private_key_bytes(),
+            b"not cbor".to_vec(),
+            None,
+            None,
+            None,
+            false,
+        )
+        .await
+        .unwrap_err();
+        assert!(
+            error
+                .as_string()
+                .unwrap()
+                .starts_with("Failed to parse certificate chain")
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn test_results_are_plain_objects() {
+        let ca = root("root@example.com", "Root CA");
+        let data = signed_file(&ca, b"content");
+        let get = |value: &JsValue, key: &str| Reflect::get(value, &key.into()).unwrap();
+        let is_plain_object =
+            |value: &JsValue| value.is_object() && !value.is_instance_of::<js_sys::Map>();
+
+        // Objects with camelCase fields as the .d.ts declares, not Maps
+        let parsed = JsValue::from(parse_aletheia_file(&data).unwrap());
+        assert!(is_plain_object(&parsed));
+        let header = get(&parsed, "header");
+        assert!(is_plain_object(&header));
+        assert_eq!(
+            get(&header, "creatorId").as_string().as_deref(),
+            Some("alice@example.com")
+        );
+        assert!(get(&header, "description").is_undefined());
+        assert!(get(&parsed, "payload").is_instance_of::<Uint8Array>());
+        let range = get(&parsed, "payloadRange");
+        assert!(Array::is_array(&range));
+        assert_eq!(Array::from(&range).length(), 2);
+
+        let chain = get(&parsed, "certificateChain");
+        assert!(Array::is_array(&chain));
+        let cert = Array::from(&chain).get(0);
+        assert_eq!(
+            get(&cert, "subjectName").as_string().as_deref(),
+            Some("Alice")
+        );
+        assert!(get(&cert, "publicKey").is_instance_of::<Uint8Array>());
+        assert!(is_plain_object(&get(&cert, "extensions")));
 
-    Ok(bytes)
+        // And they read back as the declared type
+        let parsed = parse_aletheia_file(&data).unwrap().to_rust().unwrap();
+        assert_eq!(parsed.payload, b"content");
+        assert_eq!(
+            parsed.certificate_chain[1].fingerprint,
+            ca.certificate.fingerprint().to_hex()
+        );
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_verify_many() {
+        let ca = root("root@example.com", "Root CA");
+        let other = root("other@example.com", "Other CA");
+        let roots = Array::of1(&Uint8Array::from(&ca.public_key()[..]));
+        let files = [
+            signed_file(&ca, b"first"),
+            b"not an aletheia file".to_vec(),
+            signed_file(&other, b"untrusted"),
+            signed_file(&ca, b"second"),
+        ];
+
+        // One entry per file, in order, so one bad file does not hide the others
+        let results: Vec<WasmBatchVerification> = verify_many(
+            files
+                .iter()
+                .map(|file| Uint8Array::from(&file[..]))
+                .collect(),
+            roots.clone().into(),
+        )
+        .await
+        .unwrap()
+        .iter()
+        .map(|result| result.to_rust().unwrap())
+        .collect();
+        assert_eq!(results.len(), 4);
+        for i in [0, 3] {
+            let result = results[i].result.as_ref().unwrap();
+            assert_eq!(result.creator_id, "alice@example.com");
+            assert!(results[i].error.is_none());
+        }
+        assert_eq!(results[1].error_code.map(|code| code / 100), Some(1));
+        assert_eq!(results[2].error_code.map(|code| code / 100), Some(2));
+        assert!(results[2].result.is_none());
+
+        // The single-file entry point awaits the same way
+        let result = verify_aletheia_file(files[0].clone(), roots.into())
+            .await
+            .unwrap()
+            .to_rust()
+            .unwrap();
+        assert!(result.valid);
+        assert!(
+            verify_many(vec![], JsValue::from_str("not a list"))
+                .await
+                .is_err()
+        );
+    }
 }
@@ -0,0 +1,969 @@
+//! The `aletheia` command line: each command run against files in a
+//! temporary directory, checking its output and exit code
+
+use std::ffi::{OsStr, OsString};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::sync::{Arc, Mutex};
+
+use serde_json::Value;
+use tempfile::TempDir;
+
+/// Run the CLI with JSON output
+fn aletheia<I, S>(args: I) -> Output
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    Command::new(env!("CARGO_BIN_EXE_aletheia"))
+        .args(["--format", "json"])
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+/// Run the CLI with its default text output
+fn aletheia_text<I, S>(args: I) -> Output
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    Command::new(env!("CARGO_BIN_EXE_aletheia"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+/// The JSON a successful command printed
+fn success(output: Output) -> Value {
+    assert!(output.status.success(), "{output:?}");
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+/// The JSON error object a failed command printed
+fn failure(output: Output) -> Value {
+    assert!(!output.status.success(), "{output:?}");
+    let report: Value = serde_json::from_slice(&output.stdout).unwrap();
+    report["error"].clone()
+}
+
+/// Flip the case of the first byte of `content` where it is stored in `file`
+fn tamper(file: &Path, content: &[u8]) {
+    let mut data = std::fs::read(file).unwrap();
+    let at = data
+        .windows(content.len())
+        .position(|window| window == content)
+        .unwrap();
+    data[at] ^= 0x20;
+    std::fs::write(file, data).unwrap();
+}
+
+/// A CA and a user it certified, in a temporary directory
+struct Fixture {
+    dir: TempDir,
+}
+
+impl Fixture {
+    fn new() -> Self {
+        let dir = tempfile::tempdir().unwrap();
+        let fixture = Self { dir };
+        success(aletheia([
+            OsStr::new("ca-init"),
+            OsStr::new("--id"),
+            OsStr::new("root@example.com"),
+            OsStr::new("--name"),
+            OsStr::new("Root CA"),
+            OsStr::new("-o"),
+            fixture.path("ca").as_os_str(),
+        ]));
+        fixture.issue("alice", "Alice");
+        fixture
+    }
+
+    /// Issue a user a key and certificate, in the directory named by their ID
+    fn issue(&self, id: &str, name: &str) {
+        success(aletheia([
+            OsStr::new("cert-issue"),
+            OsStr::new("--ca-key"),
+            self.path("ca/ca.key").as_os_str(),
+            OsStr::new("--ca-cert"),
+            self.path("ca/ca.cert").as_os_str(),
+            OsStr::new("-i"),
+            OsStr::new(id),
+            OsStr::new("-n"),
+            OsStr::new(name),
+            OsStr::new("-o"),
+            self.path(id).as_os_str(),
+        ]));
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        self.dir.path().join(name)
+    }
+
+    /// The key and certificates to sign as the user
+    fn signer_args(&self) -> Vec<OsString> {
+        self.signer_args_of("alice")
+    }
+
+    /// The key and certificates to sign as the user with `id`
+    fn signer_args_of(&self, id: &str) -> Vec<OsString> {
+        vec![
+            "--key".into(),
+            self.path(&format!("{id}/{id}.key")).into(),
+            "--cert".into(),
+            self.path(&format!("{id}/{id}.cert")).into(),
+            "--ca-cert".into(),
+            self.path("ca/ca.cert").into(),
+        ]
+    }
+
+    /// Write `content` to `name` and sign it, returning the .alx file
+    fn sign(&self, name: &str, content: &[u8]) -> PathBuf {
+        let report = self.sign_with(name, content, &[]);
+        PathBuf::from(report["output"].as_str().unwrap())
+    }
+
+    /// Write `content` to `name` and sign it with `extra` options, returning
+    /// the report
+    fn sign_with(&self, name: &str, content: &[u8], extra: &[&str]) -> Value {
+        let input = self.path(name);
+        std::fs::write(&input, content).unwrap();
+        let mut args: Vec<OsString> = vec!["sign".into(), "-i".into(), input.into()];
+        args.extend(self.signer_args());
+        args.extend(extra.iter().map(OsString::from));
+        success(aletheia(args))
+    }
+
+    /// Verify `file` against the fixture's CA
+    fn verify(&self, file: &Path, extra: &[&str]) -> Output {
+        let mut command = vec![
+            OsStr::new("verify"),
+            file.as_os_str(),
+            OsStr::new("--trust"),
+        ];
+        let ca_cert = self.path("ca/ca.cert");
+        command.push(ca_cert.as_os_str());
+        command.extend(extra.iter().map(OsStr::new));
+        aletheia(command)
+    }
+}
+
+#[test]
+fn test_json_output() {
+    let fixture = Fixture::new();
+    let cert = success(aletheia([
+        OsStr::new("cert-info"),
+        fixture.path("alice/alice.cert").as_os_str(),
+    ]));
+    let fingerprint = cert["certificates"][0]["fingerprint"].clone();
+    assert_eq!(cert["certificates"][0]["subject_id"], "alice");
+    assert_eq!(cert["certificates"][0]["issuer_id"], "root@example.com");
+
+    // Signing reports the file and who signed it
+    let signed = fixture.sign_with("note.txt", b"hello aletheia", &[]);
+    let file = PathBuf::from(signed["output"].as_str().unwrap());
+    assert_eq!(file, fixture.path("note.txt.alx"));
+    assert_eq!(signed["creator_id"], "alice");
+    assert_eq!(signed["fingerprint"], fingerprint);
+    assert_eq!(signed["payload_size"], 14);
+
+    let info = success(aletheia([OsStr::new("info"), file.as_os_str()]));
+    assert_eq!(info["header"]["creator_id"], "alice");
+    assert_eq!(info["header"]["original_name"], "note.txt");
+    assert_eq!(info["certificate_chain"][0]["fingerprint"], fingerprint);
+
+    let verified = success(fixture.verify(&file, &[]));
+    assert_eq!(verified["verified"], true);
+    assert_eq!(verified["creator_name"], "Alice");
+    assert_eq!(verified["creator_fingerprint"], fingerprint);
+    assert_eq!(verified["trusted_root"]["subject_id"], "root@example.com");
+
+    // Failures print an error object with the library error's code
+    tamper(&file, b"hello aletheia");
+    let error = failure(fixture.verify(&file, &[]));
+    assert!(
+        error["message"]
+            .as_str()
+            .unwrap()
+            .contains("Verification failed")
+    );
+    assert_eq!(error["kind"], "signature");
+    assert_eq!(error["tampering"], true);
+    assert!(error["code"].as_u64().unwrap() / 100 == 3);
+
+    // Errors without a library error behind them have no code
+    let error = failure(fixture.verify(&fixture.path("missing.alx"), &[]));
+    assert_eq!(error["code"], Value::Null);
+    assert_eq!(error["tampering"], false);
+}
+
+#[test]
+fn test_sign_batch() {
+    let fixture = Fixture::new();
+    for name in ["photos/a.png", "photos/2024/b.png", "photos/2024/c.txt"] {
+        let path = fixture.path(name);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, name).unwrap();
+    }
+    let pattern = fixture.path("photos/**/*.png");
+    let sign_batch = |extra: &[&str]| {
+        let mut args: Vec<OsString> = vec![
+            "sign-batch".into(),
+            "-i".into(),
+            pattern.clone().into(),
+            "-o".into(),
+            fixture.path("signed").into(),
+        ];
+        args.extend(fixture.signer_args());
+        args.extend(extra.iter().map(OsString::from));
+        success(aletheia(args))
+    };
+
+    // Outputs mirror the directories below the pattern's base
+    let report = sign_batch(&[]);
+    assert_eq!(report["signed"], 2);
+    assert_eq!(report["skipped"], 0);
+    assert_eq!(report["failed"], 0);
+    for name in ["signed/a.png.alx", "signed/2024/b.png.alx"] {
+        assert_eq!(
+            success(fixture.verify(&fixture.path(name), &[]))["verified"],
+            true
+        );
+    }
+    assert!(!fixture.path("signed/2024/c.txt.alx").exists());
+
+    // Signed files are skipped when run again, unless overwriting
+    let report = sign_batch(&[]);
+    assert_eq!(report["signed"], 0);
+    assert_eq!(report["skipped"], 2);
+    assert!(
+        report["files"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .all(|file| file["status"] == "skipped")
+    );
+    let report = sign_batch(&["--overwrite"]);
+    assert_eq!(report["signed"], 2);
+
+    // A bad key fails the whole batch before signing anything
+    let mut args: Vec<OsString> = vec!["sign-batch".into(), "-i".into(), pattern.into()];
+    args.extend(fixture.signer_args());
+    args[4] = fixture.path("ca/ca.cert").into();
+    assert!(!failure(aletheia(args))["message"].is_null());
+    assert!(!fixture.path("photos/a.png.alx").exists());
+}
+
+#[test]
+fn test_verify_batch() {
+    let fixture = Fixture::new();
+    std::fs::create_dir_all(fixture.path("files/nested")).unwrap();
+    fixture.sign("files/a.txt", b"first");
+    fixture.sign("files/nested/b.txt", b"second");
+    let ca_cert = fixture.path("ca/ca.cert");
+    let verify_batch = |input: &Path, json: bool| {
+        let args = [
+            OsStr::new("verify-batch"),
+            input.as_os_str(),
+            OsStr::new("--trust"),
+            ca_cert.as_os_str(),
+        ];
+        if json {
+            aletheia(args)
+        } else {
+            aletheia_text(args)
+        }
+    };
+
+    // A directory stands for every .alx file below it
+    let report = success(verify_batch(&fixture.path("files"), true));
+    assert_eq!(report["verified"], 2);
+    assert_eq!(report["failed"], 0);
+    for file in report["files"].as_array().unwrap() {
+        assert_eq!(file["verified"], true);
+        assert_eq!(file["creator_id"], "alice");
+        assert_eq!(file["trusted_root"]["subject_id"], "root@example.com");
+    }
+
+    // One damaged file fails the batch, with its reason in the report
+    let damaged = fixture.path("files/nested/damaged.alx");
+    std::fs::write(&damaged, b"not an alx file").unwrap();
+    let output = verify_batch(&fixture.path("files"), true);
+    assert!(!output.status.success());
+    let report: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["verified"], 2);
+    assert_eq!(report["failed"], 1);
+    let failed: Vec<&Value> = report["files"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|file| file["verified"] == false)
+        .collect();
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0]["file"], damaged.display().to_string());
+    assert_eq!(failed[0]["error"]["kind"], "parse");
+
+    let output = verify_batch(&fixture.path("files"), false);
+    assert!(!output.status.success());
+    let text = String::from_utf8(output.stdout).unwrap();
+    assert!(text.contains("FAILED"));
+    assert!(text.contains("Verified: 2, failed: 1"));
+
+    // A pattern selects the files to verify
+    let report = success(verify_batch(&fixture.path("files/*.alx"), true));
+    assert_eq!(report["verified"], 1);
+}
+
+#[test]
+fn test_cert_info() {
+    let fixture = Fixture::new();
+    let alice_cert = fixture.path("alice/alice.cert");
+    let ca_cert = fixture.path("ca/ca.cert");
+    let cert_info = |file: &Path, issuers: &[&Path]| {
+        let mut args = vec![OsStr::new("cert-info"), file.as_os_str()];
+        for issuer in issuers {
+            args.extend([OsStr::new("--issuer"), issuer.as_os_str()]);
+        }
+        aletheia(args)
+    };
+
+    let report = success(cert_info(&alice_cert, &[]));
+    let cert = &report["certificates"][0];
+    assert_eq!(cert["subject_name"], "Alice");
+    assert_eq!(cert["is_ca"], false);
+    assert_eq!(cert["valid_now"], true);
+    assert_eq!(cert["signature"], Value::Null);
+    assert!(!cert["serial"].as_str().unwrap().is_empty());
+
+    // Every certificate of a bundle is shown, checked against its issuer
+    let bundle = fixture.path("chain.bundle");
+    success(aletheia([
+        OsStr::new("bundle"),
+        alice_cert.as_os_str(),
+        ca_cert.as_os_str(),
+        OsStr::new("-o"),
+        bundle.as_os_str(),
+    ]));
+    let report = success(cert_info(&bundle, &[&ca_cert]));
+    let certs = report["certificates"].as_array().unwrap();
+    assert_eq!(certs.len(), 2);
+    assert_eq!(certs[1]["is_ca"], true);
+    let ca_fingerprint = certs[1]["fingerprint"].clone();
+    for cert in certs {
+        assert_eq!(cert["signature"]["valid"], true);
+        assert_eq!(cert["signature"]["issuer_fingerprint"], ca_fingerprint);
+    }
+
+    // Another CA of the same name did not sign it; a missing issuer is reported too
+    success(aletheia([
+        OsStr::new("ca-init"),
+        OsStr::new("--id"),
+        OsStr::new("root@example.com"),
+        OsStr::new("--name"),
+        OsStr::new("Impostor CA"),
+        OsStr::new("-o"),
+        fixture.path("impostor").as_os_str(),
+    ]));
+    let output = cert_info(&alice_cert, &[&fixture.path("impostor/ca.cert")]);
+    assert!(!output.status.success());
+    let report: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["certificates"][0]["signature"]["valid"], false);
+    assert_eq!(
+        report["certificates"][0]["signature"]["error"],
+        "signature does not verify"
+    );
+    let output = cert_info(&ca_cert, &[&alice_cert]);
+    assert!(!output.status.success());
+    let report: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        report["certificates"][0]["signature"]["error"],
+        "no issuer certificate for this issuer ID"
+    );
+
+    let output = aletheia_text([OsStr::new("cert-info"), alice_cert.as_os_str()]);
+    assert!(output.status.success());
+    let text = String::from_utf8(output.stdout).unwrap();
+    assert!(text.contains("Alice (alice)"));
+    assert!(text.contains("Issuer:       root@example.com"));
+    assert!(text.contains("Is CA:        no"));
+}
+
+#[test]
+fn test_revoke() {
+    let fixture = Fixture::new();
+    let file = fixture.sign("note.txt", b"revoked later");
+    let crl = fixture.path("ca/revoked.crl");
+    let revoke = |ca: &str, target: &[&OsStr]| {
+        let mut args = vec![OsStr::new("revoke"), OsStr::new("--ca-key")];
+        let ca_key = fixture.path(&format!("{ca}/ca.key"));
+        let ca_cert = fixture.path(&format!("{ca}/ca.cert"));
+        args.extend([
+            ca_key.as_os_str(),
+            OsStr::new("--ca-cert"),
+            ca_cert.as_os_str(),
+            OsStr::new("--crl"),
+            crl.as_os_str(),
+        ]);
+        args.extend(target);
+        aletheia(args)
+    };
+    let alice_cert = fixture.path("alice/alice.cert");
+    let serial = success(aletheia([OsStr::new("cert-info"), alice_cert.as_os_str()]))
+        ["certificates"][0]["serial"]
+        .clone();
+    let crl_arg = ["--crl", crl.to_str().unwrap()];
+
+    // The list is created with the first revocation
+    let report = success(revoke(
+        "ca",
+        &[OsStr::new("--serial"), OsStr::new("0badc0de")],
+    ));
+    assert_eq!(report["entries"], 1);
+    assert_eq!(report["serial"], "0badc0de");
+    assert_eq!(success(fixture.verify(&file, &crl_arg))["verified"], true);
+
+    let report = success(revoke(
+        "ca",
+        &[
+            OsStr::new("--cert"),
+            alice_cert.as_os_str(),
+            OsStr::new("--reason"),
+            OsStr::new("key compromise"),
+        ],
+    ));
+    assert_eq!(report["entries"], 2);
+    assert_eq!(report["serial"], serial);
+    assert_eq!(report["reason"], "key compromise");
+    let error = failure(fixture.verify(&file, &crl_arg));
+    assert_eq!(error["code"], 206);
+    assert_eq!(error["kind"], "chain");
+
+    // A certificate is revoked once, and only its CA may add to the list
+    let error = failure(revoke(
+        "ca",
+        &[OsStr::new("--cert"), alice_cert.as_os_str()],
+    ));
+    assert!(
+        error["message"]
+            .as_str()
+            .unwrap()
+            .contains("already revoked")
+    );
+    success(aletheia([
+        OsStr::new("ca-init"),
+        OsStr::new("--id"),
+        OsStr::new("other@example.com"),
+        OsStr::new("--name"),
+        OsStr::new("Other CA"),
+        OsStr::new("-o"),
+        fixture.path("other").as_os_str(),
+    ]));
+    let error = failure(revoke("other", &[OsStr::new("--serial"), OsStr::new("01")]));
+    assert!(
+        error["message"]
+            .as_str()
+            .unwrap()
+            .contains("not by this CA")
+    );
+    let error = failure(revoke(
+        "other",
+        &[OsStr::new("--cert"), alice_cert.as_os_str()],
+    ));
+    assert!(
+        error["message"]
+            .as_str()
+            .unwrap()
+            .contains("not by this CA")
+    );
+}
+
+#[test]
+fn test_extract() {
+    let fixture = Fixture::new();
+    let ca_cert = fixture.path("ca/ca.cert");
+    let out = fixture.path("out");
+    let extract = |file: &Path, extra: &[&str]| {
+        let mut args = vec![
+            OsStr::new("extract"),
+            file.as_os_str(),
+            OsStr::new("-o"),
+            out.as_os_str(),
+            OsStr::new("--trust"),
+            ca_cert.as_os_str(),
+        ];
+        args.extend(extra.iter().map(OsStr::new));
+        aletheia(args)
+    };
+
+    // The payload gets its original name back
+    let file = fixture.sign("note.txt", b"some notes");
+    let report = success(extract(&file, &[]));
+    assert_eq!(report["output"], out.join("note.txt").display().to_string());
+    assert_eq!(report["payload_size"], 10);
+    assert_eq!(report["verification"]["creator_id"], "alice");
+    assert_eq!(std::fs::read(out.join("note.txt")).unwrap(), b"some notes");
+
+    // An existing file is only replaced when asked to
+    let error = failure(extract(&file, &[]));
+    assert!(error["message"].as_str().unwrap().contains("--overwrite"));
+    success(extract(&file, &["--overwrite"]));
+
+    // A name without an extension gets one for its content type
+    let report = fixture.sign_with("photo", b"\x89PNG", &["--content-type", "image/png"]);
+    let file = PathBuf::from(report["output"].as_str().unwrap());
+    let report = success(extract(&file, &[]));
+    assert_eq!(
+        report["output"],
+        out.join("photo.png").display().to_string()
+    );
+    assert_eq!(report["content_type"], "image/png");
+
+    // A file that fails verification is not extracted, unless told not to verify
+    let file = fixture.sign("tampered.txt", b"original text");
+    tamper(&file, b"original text");
+    let error = failure(extract(&file, &[]));
+    assert_eq!(error["tampering"], true);
+    assert!(!out.join("tampered.txt").exists());
+    let report = success(extract(&file, &["--no-verify"]));
+    assert_eq!(report["verification"], Value::Null);
+    assert_eq!(
+        std::fs::read(out.join("tampered.txt")).unwrap(),
+        b"Original text"
+    );
+}
+
+#[test]
+fn test_diff() {
+    let fixture = Fixture::new();
+    let diff =
+        |a: &Path, b: &Path| success(aletheia([OsStr::new("diff"), a.as_os_str(), b.as_os_str()]));
+    let original = fixture.sign("note.txt", b"the same words");
+
+    let report = diff(&original, &original);
+    assert_eq!(report["identical"], true);
+    assert_eq!(report["summary"], "identical");
+
+    // Re-signed with another description: the same content from the same signer
+    std::fs::create_dir_all(fixture.path("copy")).unwrap();
+    let report = fixture.sign_with(
+        "copy/note.txt",
+        b"the same words",
+        &["--description", "a copy", "--compress"],
+    );
+    let copy = PathBuf::from(report["output"].as_str().unwrap());
+    let report = diff(&original, &copy);
+    assert_eq!(report["identical"], false);
+    assert_eq!(
+        report["summary"],
+        "same payload, same signer, different header, different signature"
+    );
+    assert_eq!(report["payload"]["same"], true);
+    assert_eq!(report["payload"]["a"], report["payload"]["b"]);
+    assert_eq!(report["signer"]["same"], true);
+    assert_eq!(report["chain_same"], true);
+    assert_eq!(report["format_same"], false);
+    assert!(
+        report["header_fields"]
+            .as_array()
+            .unwrap()
+            .contains(&"description".into())
+    );
+
+    // The same content from another signer
+    fixture.issue("bob", "Bob");
+    std::fs::create_dir_all(fixture.path("other")).unwrap();
+    let input = fixture.path("other/note.txt");
+    std::fs::write(&input, b"the same words").unwrap();
+    let mut args: Vec<OsString> = vec!["sign".into(), "-i".into(), input.into()];
+    args.extend(fixture.signer_args_of("bob"));
+    let bob = success(aletheia(args));
+    let bob = PathBuf::from(bob["output"].as_str().unwrap());
+    let report = diff(&original, &bob);
+    assert!(
+        report["summary"]
+            .as_str()
+            .unwrap()
+            .starts_with("same payload, different signer")
+    );
+    assert_ne!(report["signer"]["a"], report["signer"]["b"]);
+
+    // Tampered content no longer matches
+    tamper(&bob, b"the same words");
+    let report = diff(&original, &bob);
+    assert_eq!(report["payload"]["same"], false);
+    assert!(
+        report["summary"]
+            .as_str()
+            .unwrap()
+            .starts_with("different payload")
+    );
+
+    let output = aletheia_text([OsStr::new("diff"), original.as_os_str(), copy.as_os_str()]);
+    assert!(output.status.success());
+    let text = String::from_utf8(output.stdout).unwrap();
+    assert!(text.contains("Payload:    same"));
+    assert!(text.contains("Header:     different: "));
+}
+
+#[test]
+fn test_qr() {
+    let fixture = Fixture::new();
+    let cert = fixture.path("alice/alice.cert");
+    let qr = |extra: &[&str]| {
+        let mut args = vec![OsStr::new("qr"), OsStr::new("--cert"), cert.as_os_str()];
+        args.extend(extra.iter().map(OsStr::new));
+        success(aletheia(args))
+    };
+    let fingerprint = success(aletheia([OsStr::new("cert-info"), cert.as_os_str()]))
+        ["certificates"][0]["fingerprint"]
+        .clone();
+
+    // The fingerprint is rendered for the terminal by default
+    let report = qr(&[]);
+    assert_eq!(report["subject_id"], "alice");
+    assert_eq!(report["data"], fingerprint);
+    assert_eq!(report["output"], Value::Null);
+    assert!(report["qr"].as_str().unwrap().lines().count() > 10);
+
+    let report = qr(&["--full"]);
+    assert_eq!(
+        report["data"],
+        std::fs::read_to_string(&cert).unwrap().as_str()
+    );
+
+    // PNG images are square, with `--scale` pixels per module
+    let png_size = |scale: &str| {
+        let path = fixture.path(&format!("qr-{scale}.png"));
+        let report = qr(&["-o", path.to_str().unwrap(), "--scale", scale]);
+        assert_eq!(report["output"], path.display().to_string());
+        assert_eq!(report["qr"], Value::Null);
+        let png = std::fs::read(&path).unwrap();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        let width = u32::from_be_bytes(png[16..20].try_into().unwrap());
+        let height = u32::from_be_bytes(png[20..24].try_into().unwrap());
+        assert_eq!(width, height);
+        width
+    };
+    assert_eq!(png_size("8"), 4 * png_size("2"));
+
+    let output = aletheia_text([OsStr::new("qr"), OsStr::new("--cert"), cert.as_os_str()]);
+    assert!(output.status.success());
+    let text = String::from_utf8(output.stdout).unwrap();
+    assert!(text.contains(&format!("Fingerprint: {}", fingerprint.as_str().unwrap())));
+}
+
+/// A timestamp request received by [`serve_tsa`]: its content type and body
+type TsaRequest = (String, Vec<u8>);
+
+/// Answer every POST with `status` and `body` until the test exits,
+/// recording the requests
+fn serve_tsa(status: &'static str, body: &'static [u8]) -> (String, Arc<Mutex<Vec<TsaRequest>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let received = requests.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let (mut content_type, mut length) = (String::new(), 0);
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 2 {
+                if let Some((name, value)) = line.split_once(':') {
+                    match name.to_ascii_lowercase().as_str() {
+                        "content-type" => content_type = value.trim().to_string(),
+                        "content-length" => length = value.trim().parse().unwrap(),
+                        _ => {}
+                    }
+                }
+                line.clear();
+            }
+            let mut request = vec![0; length];
+            reader.read_exact(&mut request).unwrap();
+            received.lock().unwrap().push((content_type, request));
+
+            let mut response = format!(
+                "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            )
+            .into_bytes();
+            response.extend_from_slice(body);
+            let _ = stream.write_all(&response);
+        }
+    });
+    (format!("http://{address}/tsa"), requests)
+}
+
+#[test]
+fn test_sign_with_timestamp_url() {
+    let fixture = Fixture::new();
+    let sign = |url: &str| {
+        let input = fixture.path("note.txt");
+        std::fs::write(&input, b"timestamped").unwrap();
+        let mut args: Vec<OsString> = vec!["sign".into(), "-i".into(), input.into()];
+        args.extend(fixture.signer_args());
+        args.extend(["--timestamp-url".into(), url.into()]);
+        aletheia(args)
+    };
+
+    // The TSA is asked over the fresh signature; its refusal fails signing
+    // (a TimeStampResp with status 2, rejection, and no token)
+    let (url, requests) = serve_tsa("200 OK", b"\x30\x05\x30\x03\x02\x01\x02");
+    let error = failure(sign(&url));
+    assert!(
+        error["message"]
+            .as_str()
+            .unwrap()
+            .contains("TSA rejected the request")
+    );
+    assert_eq!(error["code"], 601);
+    assert!(!fixture.path("note.txt.alx").exists());
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].0, "application/timestamp-query");
+    assert_eq!(requests[0].1[0], 0x30);
+
+    // So does a token over another signature, or an HTTP error
+    let (url, _) = serve_tsa(
+        "200 OK",
+        include_bytes!("../testdata/timestamp/response.tsr"),
+    );
+    let error = failure(sign(&url));
+    assert!(
+        error["message"]
+            .as_str()
+            .unwrap()
+            .contains("Invalid timestamp response")
+    );
+    let (url, _) = serve_tsa("500 Internal Server Error", b"");
+    let error = failure(sign(&url));
+    assert!(
+        error["message"]
+            .as_str()
+            .unwrap()
+            .contains("Failed to get a timestamp")
+    );
+    assert!(!fixture.path("note.txt.alx").exists());
+}
+
+#[test]
+fn test_verify_shows_timestamp() {
+    // A file with a token from the test TSA attached, and its roots
+    let dir = tempfile::tempdir().unwrap();
+    let mut file =
+        aletheia::file::from_bytes(include_bytes!("../testdata/timestamp/signed.alx")).unwrap();
+    aletheia::timestamp::attach(
+        &mut file,
+        include_bytes!("../testdata/timestamp/response.tsr"),
+    )
+    .unwrap();
+    let path = dir.path().join("signed.alx");
+    std::fs::write(&path, aletheia::file::to_bytes(&file).unwrap()).unwrap();
+    let root = dir.path().join("root.cert");
+    std::fs::write(&root, file.certificate_chain[1].to_pem()).unwrap();
+    let tsa_root = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/timestamp/tsa-root.der");
+    let verify = |json: bool, extra: &[&OsStr]| {
+        let mut args = vec![
+            OsStr::new("verify"),
+            path.as_os_str(),
+            OsStr::new("--trust"),
+            root.as_os_str(),
+        ];
+        args.extend(extra);
+        if json {
+            aletheia(args)
+        } else {
+            aletheia_text(args)
+        }
+    };
+
+    let report = success(verify(
+        true,
+        &[OsStr::new("--tsa-root"), tsa_root.as_os_str()],
+    ));
+    let timestamp = &report["timestamps"][0];
+    assert_eq!(timestamp["valid"], true);
+    assert_eq!(timestamp["time"], 1792207463);
+    assert_eq!(
+        timestamp["tsa_name"],
+        "CN=Aletheia Test TSA,O=Aletheia Test"
+    );
+
+    let output = verify(false, &[OsStr::new("--tsa-root"), tsa_root.as_os_str()]);
+    assert!(output.status.success());
+    let text = String::from_utf8(output.stdout).unwrap();
+    assert!(text.contains(
+        "Timestamped by: CN=Aletheia Test TSA,O=Aletheia Test at 2026-10-17 03:24:23 UTC"
+    ));
+
+    // Without the TSA's root the attested time is not relied on
+    let report = success(verify(true, &[]));
+    assert_eq!(report["timestamps"][0]["valid"], false);
+    let output = verify(false, &[]);
+    assert!(
+        String::from_utf8(output.stdout)
+            .unwrap()
+            .contains("Timestamp NOT VERIFIED")
+    );
+}
+
+#[test]
+fn test_cert_request_and_approve() {
+    let fixture = Fixture::new();
+    let request = fixture.path("carol/carol.acr");
+    std::fs::create_dir_all(fixture.path("carol")).unwrap();
+    let cert_request = |extra: &[&OsStr]| {
+        let mut args = vec![
+            OsStr::new("cert-request"),
+            OsStr::new("--id"),
+            OsStr::new("carol@example.com"),
+            OsStr::new("--name"),
+            OsStr::new("Carol"),
+            OsStr::new("-o"),
+            request.as_os_str(),
+        ];
+        args.extend(extra);
+        aletheia(args)
+    };
+
+    // The key is generated next to the request, and never goes to the CA
+    let report = success(cert_request(&[]));
+    let key = fixture.path("carol/carol.key");
+    assert_eq!(report["request"], request.display().to_string());
+    assert_eq!(report["key"], key.display().to_string());
+    assert_eq!(report["subject_id"], "carol@example.com");
+    assert!(key.exists());
+    let error = failure(cert_request(&[]));
+    assert!(
+        error["message"]
+            .as_str()
+            .unwrap()
+            .contains("already exists")
+    );
+
+    let issued = fixture.path("issued");
+    let report = success(aletheia([
+        OsStr::new("cert-approve"),
+        request.as_os_str(),
+        OsStr::new("--ca-key"),
+        fixture.path("ca/ca.key").as_os_str(),
+        OsStr::new("--ca-cert"),
+        fixture.path("ca/ca.cert").as_os_str(),
+        OsStr::new("-o"),
+        issued.as_os_str(),
+    ]));
+    let cert = issued.join("carol_example_com.cert");
+    assert_eq!(report["subject_id"], "carol@example.com");
+    assert_eq!(report["subject_name"], "Carol");
+    assert_eq!(report["key"], Value::Null);
+    assert_eq!(report["certificate"], cert.display().to_string());
+    assert_eq!(std::fs::read_dir(&issued).unwrap().count(), 1);
+
+    // The approved certificate signs with the requester's own key
+    let input = fixture.path("carol/note.txt");
+    std::fs::write(&input, b"from carol").unwrap();
+    let signed = success(aletheia([
+        OsStr::new("sign"),
+        OsStr::new("-i"),
+        input.as_os_str(),
+        OsStr::new("--key"),
+        key.as_os_str(),
+        OsStr::new("--cert"),
+        cert.as_os_str(),
+        OsStr::new("--ca-cert"),
+        fixture.path("ca/ca.cert").as_os_str(),
+    ]));
+    let file = PathBuf::from(signed["output"].as_str().unwrap());
+    assert_eq!(
+        success(fixture.verify(&file, &[]))["creator_id"],
+        "carol@example.com"
+    );
+
+    // A request for an existing key writes no key
+    std::fs::remove_file(&request).unwrap();
+    let report = success(cert_request(&[
+        OsStr::new("--key"),
+        fixture.path("alice/alice.key").as_os_str(),
+    ]));
+    assert_eq!(report["key"], Value::Null);
+
+    let output = aletheia_text([
+        OsStr::new("cert-request"),
+        OsStr::new("--id"),
+        OsStr::new("dave"),
+        OsStr::new("--name"),
+        OsStr::new("Dave"),
+        OsStr::new("-o"),
+        fixture.path("dave.acr").as_os_str(),
+    ]);
+    assert!(output.status.success());
+    let text = String::from_utf8(output.stdout).unwrap();
+    assert!(text.contains("your private key stays with you"));
+}
+
+#[test]
+fn test_batch_jobs() {
+    let fixture = Fixture::new();
+    std::fs::create_dir_all(fixture.path("files")).unwrap();
+    for i in 0..6 {
+        std::fs::write(fixture.path(&format!("files/{i}.txt")), [i]).unwrap();
+    }
+    let pattern = fixture.path("files/*.txt");
+    let sign_batch = |jobs: &str, json: bool| {
+        let mut args: Vec<OsString> = vec![
+            "sign-batch".into(),
+            "-i".into(),
+            pattern.clone().into(),
+            "--overwrite".into(),
+            "--jobs".into(),
+            jobs.into(),
+        ];
+        args.extend(fixture.signer_args());
+        if json {
+            aletheia(args)
+        } else {
+            aletheia_text(args)
+        }
+    };
+    let ca_cert = fixture.path("ca/ca.cert");
+    let verify_batch = |jobs: &str| {
+        aletheia([
+            OsStr::new("verify-batch"),
+            fixture.path("files").as_os_str(),
+            OsStr::new("-j"),
+            OsStr::new(jobs),
+            OsStr::new("--trust"),
+            ca_cert.as_os_str(),
+        ])
+    };
+
+    // Any number of workers signs and verifies every file
+    for jobs in ["1", "3"] {
+        assert_eq!(success(sign_batch(jobs, true))["signed"], 6);
+        assert_eq!(success(verify_batch(jobs))["verified"], 6);
+    }
+    let error = failure(sign_batch("0", true));
+    assert!(
+        error["message"]
+            .as_str()
+            .unwrap()
+            .contains("--jobs must be at least 1")
+    );
+    let error = failure(verify_batch("0"));
+    assert!(
+        error["message"]
+            .as_str()
+            .unwrap()
+            .contains("--jobs must be at least 1")
+    );
+
+    // Progress goes to stderr, and only to a terminal
+    let output = sign_batch("2", false);
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty());
+    let text = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(text.matches("  signed   ").count(), 6);
+}
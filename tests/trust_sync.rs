@@ -0,0 +1,171 @@
+//! `aletheia trust sync` against a publisher serving signed trust bundles
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::path::Path;
+use std::process::{Command, Output};
+use std::sync::{Arc, Mutex};
+
+use aletheia::{
+    ca::{CertificateAuthority, SigningKeyPair},
+    hsm::ExternalSigner,
+    trust_bundle::{TrustBundle, key_fingerprint},
+};
+
+/// The portal's bundle signing key
+struct BundleKey(SigningKeyPair);
+
+impl ExternalSigner for BundleKey {
+    fn public_key(&self) -> Vec<u8> {
+        self.0.public_key()
+    }
+
+    fn sign(&self, message: &[u8]) -> aletheia::Result<Vec<u8>> {
+        Ok(self.0.sign(message))
+    }
+
+    fn sign_prehashed(&self, _message: &[u8], _context: &[u8]) -> aletheia::Result<Vec<u8>> {
+        unimplemented!()
+    }
+}
+
+/// Serve the bodies in `routes` over HTTP until the test exits
+fn serve(routes: Arc<Mutex<HashMap<String, Vec<u8>>>>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let path = request_line.split(' ').nth(1).unwrap_or("/").to_string();
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+            let response = match routes.lock().unwrap().get(&path) {
+                Some(body) => {
+                    let mut response = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                        body.len()
+                    )
+                    .into_bytes();
+                    response.extend_from_slice(body);
+                    response
+                }
+                None => b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                    .to_vec(),
+            };
+            let _ = stream.write_all(&response);
+        }
+    });
+    format!("http://{address}")
+}
+
+fn sync(url: &str, pin: Option<&str>, store: &Path) -> Output {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_aletheia"));
+    command.args(["--format", "json", "trust", "sync", "--url", url, "--store"]);
+    command.arg(store);
+    if let Some(pin) = pin {
+        command.args(["--pin", pin]);
+    }
+    command.output().unwrap()
+}
+
+fn installed_version(store: &Path) -> String {
+    let data = std::fs::read(store.join("bundle.json")).unwrap();
+    TrustBundle::parse(&data).unwrap().version
+}
+
+#[test]
+fn test_trust_sync_pins_bundle_signer_key() {
+    let timestamp = 1704067200;
+    let root =
+        CertificateAuthority::new_root_with_timestamp("root@example.com", "Root CA", timestamp);
+    let other_root =
+        CertificateAuthority::new_root_with_timestamp("other@example.com", "Other CA", timestamp);
+    let key = BundleKey(SigningKeyPair::generate());
+    let bundle = |version: &str, issued_at: i64| {
+        TrustBundle::sign_with_key(
+            version,
+            issued_at,
+            vec![root.certificate.clone()],
+            Vec::new(),
+            &key,
+        )
+        .unwrap()
+    };
+    let older = bundle("1", timestamp);
+    let current = bundle("2", timestamp + 60);
+    let mut tampered = bundle("3", timestamp + 120);
+    tampered.roots.push(other_root.certificate.clone());
+
+    let routes = Arc::new(Mutex::new(HashMap::new()));
+    let base = serve(routes.clone());
+    let url = format!("{base}/trust-bundles/latest");
+    let publish = |bundle: &TrustBundle| {
+        routes.lock().unwrap().insert(
+            "/trust-bundles/latest".to_string(),
+            bundle.to_json().unwrap().into_bytes(),
+        );
+    };
+
+    let dir = tempfile::tempdir().unwrap();
+    let store = dir.path().join("store");
+    let key_path = dir.path().join("signer.pem");
+    std::fs::write(&key_path, key.0.public_key_pem().unwrap()).unwrap();
+
+    // Without a pin there is nothing to check the bundle against
+    publish(&current);
+    assert!(!sync(&url, None, &store).status.success());
+
+    // A valid bundle installs, and the key stays pinned for later syncs
+    let output = sync(&url, Some(key_path.to_str().unwrap()), &store);
+    assert!(output.status.success(), "{output:?}");
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["signer"], key_fingerprint(&key.public_key()));
+    assert_eq!(installed_version(&store), "2");
+    assert!(sync(&url, None, &store).status.success());
+
+    // A tampered bundle is rejected and the installed one kept
+    publish(&tampered);
+    let output = sync(&url, None, &store);
+    assert!(!output.status.success());
+    assert_eq!(installed_version(&store), "2");
+
+    // So is a validly signed bundle older than the installed one
+    publish(&older);
+    let output = sync(&url, None, &store);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("older"));
+    assert_eq!(installed_version(&store), "2");
+
+    // A fingerprint pin fetches the key from the well-known endpoint
+    routes.lock().unwrap().insert(
+        "/.well-known/aletheia-bundle-signer".to_string(),
+        serde_json::json!({
+            "algorithm": "Ed25519",
+            "public_key_b64": base64::Engine::encode(
+                &base64::engine::general_purpose::STANDARD,
+                key.public_key(),
+            ),
+            "fingerprint": key_fingerprint(&key.public_key()),
+        })
+        .to_string()
+        .into_bytes(),
+    );
+    publish(&current);
+    let fresh = dir.path().join("fresh");
+    let wrong = key_fingerprint(&other_root.public_key());
+    assert!(!sync(&url, Some(&wrong), &fresh).status.success());
+    assert!(!fresh.join("bundle.json").exists());
+    let output = sync(&url, Some(&key_fingerprint(&key.public_key())), &fresh);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(installed_version(&fresh), "2");
+    assert_eq!(
+        std::fs::read_to_string(fresh.join("signer.pub")).unwrap(),
+        key.0.public_key_pem().unwrap()
+    );
+}